@@ -3,15 +3,23 @@
 //! This module provides an implementation of the AgentClient trait that delegates
 //! file system, terminal, and permission requests to the appropriate handlers.
 
+use super::elicitation::AgentQuestion;
+use super::middleware::MiddlewareStack;
+use super::permission::{PermissionOutcome, RequestPermissionParams, ToolPermissionRequest};
 use super::traits::{AgentClient, SessionNotification};
-use crate::error::Result;
-use crate::sandbox::{FileOperation, FileSystemHandler, PermissionManager, TerminalHandler};
+use crate::error::{Error, Result, SandboxError};
+use crate::sandbox::{
+    format_command_line, AutoMode, ChangeSetCollector, CommandConfirmation, ContainerConfig, DryRunRecorder,
+    ExternalEditTracker, FileOperation, FileSystemHandler, LiveTerminal, LiveTerminals, PermissionManager,
+    PolicyDecision, ProposedActionKind, TerminalHandler, TerminalOutput, ToolPolicy,
+};
 use crate::storage::Storage;
-use crate::types::{FileMetadata, TerminalExecuteResult, TerminalPolicy};
+use crate::types::{AuditEntry, AuditOutcome, FileMetadata, TerminalBackend, TerminalExecuteResult, TerminalPolicy};
 use async_trait::async_trait;
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::{broadcast, RwLock};
+use tokio::sync::{broadcast, mpsc, oneshot, RwLock};
 use tracing::{debug, warn};
 
 /// Default implementation of AgentClient that uses the sandbox and storage systems
@@ -22,6 +30,28 @@ pub struct AgentClientDelegate {
     storage: Arc<Storage>,
     /// Notification sender for UI updates
     notification_tx: Option<broadcast::Sender<SessionNotification>>,
+    /// Sender for commands that need interactive confirmation
+    confirmation_tx: Option<mpsc::UnboundedSender<CommandConfirmation>>,
+    /// Sender for questions agents ask the user
+    question_tx: Option<mpsc::UnboundedSender<AgentQuestion>>,
+    /// Sender for tool calls the agent asks permission for
+    permission_tx: Option<mpsc::UnboundedSender<ToolPermissionRequest>>,
+    /// Recorder for sessions running in dry-run mode
+    dry_run: Option<Arc<DryRunRecorder>>,
+    /// Long-lived terminals agents have open
+    live_terminals: Option<Arc<LiveTerminals>>,
+    /// Collector for writes that are reviewed as a batch
+    change_sets: Option<Arc<ChangeSetCollector>>,
+    /// Sessions that approve requests without asking
+    auto_mode: Option<Arc<AutoMode>>,
+    /// Tracker told about the files the agent reads and writes
+    external_edits: Option<Arc<ExternalEditTracker>>,
+    /// Agent whose tool policy applies
+    agent_id: Option<String>,
+    /// Workspace whose tool policy applies
+    workspace: Option<PathBuf>,
+    /// Hooks on the messages of connections made with this delegate
+    middleware: MiddlewareStack,
 }
 
 impl AgentClientDelegate {
@@ -34,6 +64,17 @@ impl AgentClientDelegate {
             permission_manager,
             storage,
             notification_tx: None,
+            confirmation_tx: None,
+            question_tx: None,
+            permission_tx: None,
+            dry_run: None,
+            live_terminals: None,
+            change_sets: None,
+            auto_mode: None,
+            external_edits: None,
+            agent_id: None,
+            workspace: None,
+            middleware: MiddlewareStack::default(),
         }
     }
 
@@ -47,6 +88,278 @@ impl AgentClientDelegate {
             permission_manager,
             storage,
             notification_tx: Some(notification_tx),
+            confirmation_tx: None,
+            question_tx: None,
+            permission_tx: None,
+            dry_run: None,
+            live_terminals: None,
+            change_sets: None,
+            auto_mode: None,
+            external_edits: None,
+            agent_id: None,
+            workspace: None,
+            middleware: MiddlewareStack::default(),
+        }
+    }
+
+    /// Route commands the terminal policy does not allow outright to the
+    /// given channel, so the user can approve or reject them
+    pub fn with_command_confirmation(
+        mut self,
+        confirmation_tx: mpsc::UnboundedSender<CommandConfirmation>,
+    ) -> Self {
+        self.confirmation_tx = Some(confirmation_tx);
+        self
+    }
+
+    /// Send questions agents ask to the given channel, so the user can reply;
+    /// without one they are declined
+    pub fn with_questions(mut self, question_tx: mpsc::UnboundedSender<AgentQuestion>) -> Self {
+        self.question_tx = Some(question_tx);
+        self
+    }
+
+    /// Send tool calls that need the user's permission to the given channel,
+    /// so they can pick one of the agent's options; without one they are rejected
+    pub fn with_permission_requests(mut self, permission_tx: mpsc::UnboundedSender<ToolPermissionRequest>) -> Self {
+        self.permission_tx = Some(permission_tx);
+        self
+    }
+
+    /// Record side effects instead of applying them for sessions that have
+    /// dry run enabled in the given recorder
+    pub fn with_dry_run(mut self, recorder: Arc<DryRunRecorder>) -> Self {
+        self.dry_run = Some(recorder);
+        self
+    }
+
+    /// Keep the terminals agents open in `terminals`; without it agents can
+    /// only run commands to completion
+    pub fn with_live_terminals(mut self, terminals: Arc<LiveTerminals>) -> Self {
+        self.live_terminals = Some(terminals);
+        self
+    }
+
+    /// Queue writes that need confirmation into a reviewable change set
+    /// instead of rejecting them
+    pub fn with_change_batching(mut self, collector: Arc<ChangeSetCollector>) -> Self {
+        self.change_sets = Some(collector);
+        self
+    }
+
+    /// Approve requests without asking for sessions that have auto mode
+    /// enabled, logging each approval to the audit log
+    pub fn with_auto_mode(mut self, auto_mode: Arc<AutoMode>) -> Self {
+        self.auto_mode = Some(auto_mode);
+        self
+    }
+
+    /// Tell `tracker` about the files the agent reads and writes, so changes
+    /// made to them by others can be noticed
+    pub fn with_external_edits(mut self, tracker: Arc<ExternalEditTracker>) -> Self {
+        self.external_edits = Some(tracker);
+        self
+    }
+
+    /// Apply the tool policy stored for this agent and workspace
+    pub fn with_policy_scope(mut self, agent_id: impl Into<String>, workspace: impl Into<PathBuf>) -> Self {
+        self.agent_id = Some(agent_id.into());
+        self.workspace = Some(workspace.into());
+        self
+    }
+
+    /// Run the messages of connections made with this delegate through `middleware`
+    pub fn with_middleware(mut self, middleware: MiddlewareStack) -> Self {
+        self.middleware = middleware;
+        self
+    }
+
+    /// The recorder to use if dry run is enabled for this session
+    fn dry_run_for(&self, session_id: &str) -> Option<&DryRunRecorder> {
+        self.dry_run
+            .as_deref()
+            .filter(|recorder| recorder.is_enabled(session_id))
+    }
+
+    /// Whether auto mode is on for a session
+    fn is_auto_mode(&self, session_id: &str) -> bool {
+        self.auto_mode
+            .as_ref()
+            .is_some_and(|auto_mode| auto_mode.is_enabled(session_id))
+    }
+
+    /// Ask the user to confirm a command; resolves to false when nobody is listening
+    async fn confirm_command(
+        &self,
+        session_id: &str,
+        operation: FileOperation,
+        command_line: String,
+        cwd: Option<&str>,
+        reason: String,
+    ) -> bool {
+        let Some(ref tx) = self.confirmation_tx else {
+            return false;
+        };
+
+        let (responder, response_rx) = oneshot::channel();
+        let request = CommandConfirmation {
+            session_id: session_id.to_string(),
+            operation,
+            command_line,
+            cwd: cwd.map(str::to_string),
+            reason,
+            responder,
+        };
+
+        if tx.send(request).is_err() {
+            warn!("Command confirmation channel closed");
+            return false;
+        }
+
+        response_rx.await.unwrap_or(false)
+    }
+
+    /// Get approval for an operation: from auto mode if it's on for the
+    /// session, otherwise from the user
+    async fn approve(&self, session_id: &str, operation: FileOperation, target: &str) -> Result<AuditOutcome> {
+        if self.is_auto_mode(session_id) {
+            debug!("Auto-approved {} for session {}: {}", operation.as_str(), session_id, target);
+            return Ok(AuditOutcome::AutoApproved);
+        }
+
+        let reason = "Needs your approval under the current tool policy".to_string();
+        if self
+            .confirm_command(session_id, operation, target.to_string(), None, reason)
+            .await
+        {
+            Ok(AuditOutcome::Approved)
+        } else {
+            Err(Error::Sandbox(SandboxError::RejectedByUser(target.to_string())))
+        }
+    }
+
+    /// How a permission request is decided without asking: allowed if the
+    /// policies need no confirmation, auto-approved in auto mode, and denied
+    /// otherwise
+    ///
+    /// Commands go through the terminal policy, and one it blocks fails here
+    /// so nobody is asked about it. File operations are only let through once
+    /// every path they touch is inside the sandbox.
+    async fn permission_outcome(
+        &self,
+        session_id: &str,
+        operation: FileOperation,
+        resource: &str,
+        paths: &[String],
+        command: Option<&str>,
+    ) -> Result<AuditOutcome> {
+        if operation == FileOperation::Execute {
+            // Without a command there is nothing to check against the block list
+            let Some(command) = command else {
+                return Ok(AuditOutcome::Denied);
+            };
+            let decision = self
+                .get_tool_policy()
+                .terminal_decision(TerminalHandler::evaluate(&self.get_terminal_policy(), command, &[]));
+            return match decision {
+                PolicyDecision::Allow => Ok(AuditOutcome::Allowed),
+                PolicyDecision::Deny { reason } => Err(Error::Sandbox(SandboxError::AccessDenied(reason))),
+                PolicyDecision::Confirm { .. } if self.is_auto_mode(session_id) => Ok(AuditOutcome::AutoApproved),
+                PolicyDecision::Confirm { .. } => Ok(AuditOutcome::Denied),
+            };
+        }
+
+        let pm = self.permission_manager.read().await;
+        if paths.iter().any(|path| pm.validate_access(path).is_err()) {
+            return Ok(AuditOutcome::Denied);
+        }
+        let by_default = pm.requires_confirmation(resource, operation)
+            || paths.iter().any(|path| pm.requires_confirmation(path, operation));
+        Ok(if !self.get_tool_policy().needs_confirmation(operation, by_default) {
+            AuditOutcome::Allowed
+        } else if !paths.is_empty() && self.is_auto_mode(session_id) {
+            AuditOutcome::AutoApproved
+        } else {
+            AuditOutcome::Denied
+        })
+    }
+
+    /// Whether an operation on `paths` needs approval under the tool policy
+    /// and the paths' security levels
+    ///
+    /// Paths outside the sandbox fail here, so nobody is asked about them.
+    async fn needs_approval(&self, operation: FileOperation, paths: &[&str]) -> Result<bool> {
+        let pm = self.permission_manager.read().await;
+        let by_default = paths.iter().any(|path| pm.requires_confirmation(path, operation));
+        if !self.get_tool_policy().needs_confirmation(operation, by_default) {
+            return Ok(false);
+        }
+        for path in paths {
+            pm.validate_access(path)?;
+        }
+        Ok(true)
+    }
+
+    /// Decide whether an operation may go ahead, asking for approval if needed
+    async fn gate(
+        &self,
+        session_id: &str,
+        operation: FileOperation,
+        paths: &[&str],
+        target: &str,
+    ) -> Result<AuditOutcome> {
+        if self.needs_approval(operation, paths).await? {
+            self.approve(session_id, operation, target).await
+        } else {
+            Ok(AuditOutcome::Allowed)
+        }
+    }
+
+    /// Append an entry to the audit log
+    fn log_audit(&self, entry: AuditEntry) {
+        let logged = self
+            .storage
+            .connection()
+            .and_then(|conn| crate::storage::insert_audit_entry(&conn, &entry));
+        if let Err(e) = logged {
+            warn!("Failed to write audit entry for {} {}: {}", entry.operation, entry.target, e);
+        }
+    }
+
+    /// Record an operation and how it was decided, passing its result through
+    fn audited<T>(
+        &self,
+        session_id: &str,
+        operation: FileOperation,
+        target: &str,
+        result: Result<(T, AuditOutcome)>,
+    ) -> Result<T> {
+        match result {
+            Ok((value, outcome)) => {
+                self.log_audit(AuditEntry::new(session_id, operation.as_str(), target, outcome));
+                Ok(value)
+            }
+            Err(e) => {
+                self.log_audit(
+                    AuditEntry::new(session_id, operation.as_str(), target, outcome_for_error(&e))
+                        .with_detail(e.to_string()),
+                );
+                Err(e)
+            }
+        }
+    }
+
+    /// Get the tool policy for this agent and workspace from storage
+    fn get_tool_policy(&self) -> ToolPolicy {
+        let resolved = self.storage.connection().and_then(|conn| {
+            ToolPolicy::resolve(&conn, self.agent_id.as_deref(), self.workspace.as_deref())
+        });
+        match resolved {
+            Ok(policy) => policy,
+            Err(e) => {
+                warn!("Failed to get tool policy: {}", e);
+                ToolPolicy::default()
+            }
         }
     }
 
@@ -71,53 +384,288 @@ impl AgentClientDelegate {
         raw.and_then(|v| serde_json::from_str::<TerminalPolicy>(&v).ok())
             .unwrap_or_default()
     }
+
+    /// Run an approved command on the terminal policy's backend
+    async fn run_command(
+        &self,
+        policy: &TerminalPolicy,
+        command: &str,
+        args: &[String],
+        cwd: Option<&str>,
+        env: Option<&HashMap<String, String>>,
+    ) -> Result<TerminalExecuteResult> {
+        match policy.backend {
+            TerminalBackend::Host => TerminalHandler::run_limited(command, args, cwd, env, &policy.limits).await,
+            TerminalBackend::Container => {
+                let workspace = self.workspace.as_deref().ok_or_else(|| {
+                    Error::Sandbox(SandboxError::AccessDenied("Container execution needs a workspace".to_string()))
+                })?;
+                let config = ContainerConfig::resolve(&*self.storage.connection()?, workspace)?;
+                TerminalHandler::run_in_container(&config, workspace, command, args, cwd, env, &policy.limits).await
+            }
+        }
+    }
+    async fn read(&self, session_id: &str, path: &str) -> Result<(String, AuditOutcome)> {
+        let outcome = self.gate(session_id, FileOperation::Read, &[path], path).await?;
+        let pm = self.permission_manager.read().await;
+        let content = FileSystemHandler::read_text_file(&pm, path).await?;
+        if let Some(tracker) = &self.external_edits {
+            tracker.record_access(session_id, path, Some(&content));
+        }
+        Ok((content, outcome))
+    }
+
+    async fn write(&self, session_id: &str, path: &str, content: &str) -> Result<AuditOutcome> {
+        if let Some(recorder) = self.dry_run_for(session_id) {
+            self.permission_manager.read().await.validate_access(path)?;
+            recorder.record_write(session_id, path, content).await;
+            return Ok(AuditOutcome::Recorded);
+        }
+
+        let outcome = match self.change_sets {
+            // Writes that need approval are reviewed as a batch, unless auto mode approves them
+            Some(ref collector) if !self.is_auto_mode(session_id) => {
+                if !self.needs_approval(FileOperation::Write, &[path]).await? {
+                    AuditOutcome::Allowed
+                } else if collector.submit_write(session_id, path, content).await {
+                    AuditOutcome::Approved
+                } else {
+                    return Err(Error::Sandbox(SandboxError::RejectedByUser(path.to_string())));
+                }
+            }
+            _ => self.gate(session_id, FileOperation::Write, &[path], path).await?,
+        };
+
+        let pm = self.permission_manager.read().await;
+        FileSystemHandler::write_file(&pm, path, content).await?;
+        if let Some(tracker) = &self.external_edits {
+            tracker.record_access(session_id, path, Some(content));
+        }
+        Ok(outcome)
+    }
+
+    async fn list(&self, session_id: &str, path: &str) -> Result<(Vec<FileMetadata>, AuditOutcome)> {
+        let outcome = self.gate(session_id, FileOperation::List, &[path], path).await?;
+        let pm = self.permission_manager.read().await;
+        Ok((FileSystemHandler::list_directory(&pm, path).await?, outcome))
+    }
+
+    async fn delete(&self, session_id: &str, path: &str) -> Result<AuditOutcome> {
+        if let Some(recorder) = self.dry_run_for(session_id) {
+            self.permission_manager.read().await.validate_access(path)?;
+            recorder.record(
+                session_id,
+                ProposedActionKind::DeleteFile {
+                    path: path.to_string(),
+                },
+            );
+            return Ok(AuditOutcome::Recorded);
+        }
+
+        let outcome = self.gate(session_id, FileOperation::Delete, &[path], path).await?;
+        let pm = self.permission_manager.read().await;
+        FileSystemHandler::delete_file(&pm, path).await?;
+        Ok(outcome)
+    }
+
+    async fn rename(&self, session_id: &str, old_path: &str, new_path: &str) -> Result<AuditOutcome> {
+        if let Some(recorder) = self.dry_run_for(session_id) {
+            let pm = self.permission_manager.read().await;
+            pm.validate_access(old_path)?;
+            pm.validate_access(new_path)?;
+            recorder.record(
+                session_id,
+                ProposedActionKind::MoveFile {
+                    old_path: old_path.to_string(),
+                    new_path: new_path.to_string(),
+                },
+            );
+            return Ok(AuditOutcome::Recorded);
+        }
+
+        let target = format!("{} -> {}", old_path, new_path);
+        let outcome = self
+            .gate(session_id, FileOperation::Move, &[old_path, new_path], &target)
+            .await?;
+        let pm = self.permission_manager.read().await;
+        FileSystemHandler::move_file(&pm, old_path, new_path).await?;
+        Ok(outcome)
+    }
+
+    async fn mkdir(&self, session_id: &str, path: &str) -> Result<AuditOutcome> {
+        if let Some(recorder) = self.dry_run_for(session_id) {
+            self.permission_manager.read().await.validate_access(path)?;
+            recorder.record(
+                session_id,
+                ProposedActionKind::CreateDirectory {
+                    path: path.to_string(),
+                },
+            );
+            return Ok(AuditOutcome::Recorded);
+        }
+
+        let outcome = self.gate(session_id, FileOperation::Write, &[path], path).await?;
+        let pm = self.permission_manager.read().await;
+        FileSystemHandler::create_directory(&pm, path).await?;
+        Ok(outcome)
+    }
+
+    async fn execute(
+        &self,
+        session_id: &str,
+        command: &str,
+        args: &[String],
+        cwd: Option<&str>,
+        env: Option<&HashMap<String, String>>,
+    ) -> Result<(TerminalExecuteResult, AuditOutcome)> {
+        // Validate cwd is inside granted paths when provided
+        if let Some(cwd_path) = cwd {
+            let pm = self.permission_manager.read().await;
+            pm.validate_access(cwd_path)?;
+        }
+
+        let policy = self.get_terminal_policy();
+        let command_line = format_command_line(command, args);
+
+        // Commands denied outright are never recorded, so dry run cannot be
+        // used to sneak a blocked command into the review list
+        if let Some(recorder) = self.dry_run_for(session_id) {
+            if let PolicyDecision::Deny { reason } = TerminalHandler::evaluate(&policy, command, args) {
+                return Err(Error::Sandbox(SandboxError::AccessDenied(reason)));
+            }
+            recorder.record(
+                session_id,
+                ProposedActionKind::Execute {
+                    command: command.to_string(),
+                    args: args.to_vec(),
+                    cwd: cwd.map(str::to_string),
+                    env: env.cloned(),
+                },
+            );
+            let result = TerminalExecuteResult {
+                exit_code: 0,
+                stdout: format!("[dry run] Command recorded for review, not executed: {}", command_line),
+                stderr: String::new(),
+            };
+            return Ok((result, AuditOutcome::Recorded));
+        }
+
+        let outcome = self.approve_command(session_id, &policy, command, args, cwd).await?;
+        Ok((self.run_command(&policy, command, args, cwd, env).await?, outcome))
+    }
+
+    /// Decide whether a command may run under the terminal and tool
+    /// policies, asking the user if they call for it
+    async fn approve_command(
+        &self,
+        session_id: &str,
+        policy: &TerminalPolicy,
+        command: &str,
+        args: &[String],
+        cwd: Option<&str>,
+    ) -> Result<AuditOutcome> {
+        let command_line = format_command_line(command, args);
+        let decision = self
+            .get_tool_policy()
+            .terminal_decision(TerminalHandler::evaluate(policy, command, args));
+        match decision {
+            PolicyDecision::Allow => Ok(AuditOutcome::Allowed),
+            PolicyDecision::Deny { reason } => Err(Error::Sandbox(SandboxError::AccessDenied(reason))),
+            PolicyDecision::Confirm { .. } if self.is_auto_mode(session_id) => {
+                debug!("Auto-approved command for session {}: {}", session_id, command_line);
+                Ok(AuditOutcome::AutoApproved)
+            }
+            PolicyDecision::Confirm { reason } => {
+                if self
+                    .confirm_command(session_id, FileOperation::Execute, command_line.clone(), cwd, reason)
+                    .await
+                {
+                    Ok(AuditOutcome::Approved)
+                } else {
+                    Err(Error::Sandbox(SandboxError::RejectedByUser(command_line)))
+                }
+            }
+        }
+    }
+
+    /// Open a long-lived terminal once the policy allows its command
+    async fn open(
+        &self,
+        session_id: &str,
+        command: &str,
+        args: &[String],
+        cwd: Option<&str>,
+        env: Option<&HashMap<String, String>>,
+    ) -> Result<(Arc<LiveTerminal>, AuditOutcome)> {
+        let terminals = self.live_terminals.as_ref().ok_or_else(|| {
+            Error::Sandbox(SandboxError::AccessDenied("Interactive terminals aren't available".to_string()))
+        })?;
+        if let Some(cwd_path) = cwd {
+            self.permission_manager.read().await.validate_access(cwd_path)?;
+        }
+        // A terminal can't be recorded for review, nor kept alive in a throwaway container
+        if self.dry_run_for(session_id).is_some() {
+            return Err(Error::Sandbox(SandboxError::AccessDenied(
+                "Interactive terminals aren't available in dry run".to_string(),
+            )));
+        }
+        let policy = self.get_terminal_policy();
+        if policy.backend == TerminalBackend::Container {
+            return Err(Error::Sandbox(SandboxError::AccessDenied(
+                "Interactive terminals aren't available with container execution".to_string(),
+            )));
+        }
+
+        let outcome = self.approve_command(session_id, &policy, command, args, cwd).await?;
+        let terminal = TerminalHandler::open(session_id, command, args, cwd, env, &policy.limits)?;
+        Ok((terminals.insert(terminal), outcome))
+    }
+
+    /// The open terminal `terminal_id` of the session
+    fn live_terminal(&self, session_id: &str, terminal_id: &str) -> Result<Arc<LiveTerminal>> {
+        match &self.live_terminals {
+            Some(terminals) => terminals.get(session_id, terminal_id),
+            None => Err(Error::Sandbox(SandboxError::AccessDenied(format!("No terminal {}", terminal_id)))),
+        }
+    }
+}
+
+/// How an operation that failed is recorded in the audit log
+fn outcome_for_error(error: &Error) -> AuditOutcome {
+    match error {
+        Error::Sandbox(SandboxError::RejectedByUser(_)) => AuditOutcome::Rejected,
+        Error::Sandbox(SandboxError::LimitExceeded(_)) => AuditOutcome::LimitExceeded,
+        Error::Sandbox(
+            SandboxError::AccessDenied(_) | SandboxError::PathNotGranted(_) | SandboxError::PathOutsideSandbox(_),
+        ) => AuditOutcome::Denied,
+        _ => AuditOutcome::Failed,
+    }
 }
 
 #[async_trait]
 impl AgentClient for AgentClientDelegate {
     async fn read_text_file(&self, session_id: &str, path: &str) -> Result<String> {
         debug!("Reading file for session {}: {}", session_id, path);
-        let pm = self.permission_manager.read().await;
-        FileSystemHandler::read_text_file(&pm, path).await
+        let result = self.read(session_id, path).await;
+        self.audited(session_id, FileOperation::Read, path, result)
     }
 
     async fn write_text_file(&self, session_id: &str, path: &str, content: &str) -> Result<()> {
         debug!("Writing file for session {}: {}", session_id, path);
-        let pm = self.permission_manager.read().await;
-
-        if pm.requires_confirmation(path, FileOperation::Write) {
-            return Err(crate::error::Error::Sandbox(
-                crate::error::SandboxError::AccessDenied(format!(
-                    "Write requires confirmation for: {}",
-                    path
-                )),
-            ));
-        }
-
-        FileSystemHandler::write_file(&pm, path, content).await?;
-        Ok(())
+        let result = self.write(session_id, path, content).await.map(|outcome| ((), outcome));
+        self.audited(session_id, FileOperation::Write, path, result)
     }
 
     async fn list_directory(&self, session_id: &str, path: &str) -> Result<Vec<FileMetadata>> {
         debug!("Listing directory for session {}: {}", session_id, path);
-        let pm = self.permission_manager.read().await;
-        FileSystemHandler::list_directory(&pm, path).await
+        let result = self.list(session_id, path).await;
+        self.audited(session_id, FileOperation::List, path, result)
     }
 
     async fn delete_file(&self, session_id: &str, path: &str) -> Result<()> {
         debug!("Deleting file for session {}: {}", session_id, path);
-        let pm = self.permission_manager.read().await;
-
-        if pm.requires_confirmation(path, FileOperation::Delete) {
-            return Err(crate::error::Error::Sandbox(
-                crate::error::SandboxError::AccessDenied(format!(
-                    "Delete requires confirmation for: {}",
-                    path
-                )),
-            ));
-        }
-
-        FileSystemHandler::delete_file(&pm, path).await
+        let result = self.delete(session_id, path).await.map(|outcome| ((), outcome));
+        self.audited(session_id, FileOperation::Delete, path, result)
     }
 
     async fn move_file(&self, session_id: &str, old_path: &str, new_path: &str) -> Result<()> {
@@ -125,36 +673,17 @@ impl AgentClient for AgentClientDelegate {
             "Moving file for session {}: {} -> {}",
             session_id, old_path, new_path
         );
-        let pm = self.permission_manager.read().await;
-
-        if pm.requires_confirmation(old_path, FileOperation::Move)
-            || pm.requires_confirmation(new_path, FileOperation::Move)
-        {
-            return Err(crate::error::Error::Sandbox(
-                crate::error::SandboxError::AccessDenied(format!(
-                    "Move requires confirmation: {} -> {}",
-                    old_path, new_path
-                )),
-            ));
-        }
-
-        FileSystemHandler::move_file(&pm, old_path, new_path).await
+        let result = self
+            .rename(session_id, old_path, new_path)
+            .await
+            .map(|outcome| ((), outcome));
+        self.audited(session_id, FileOperation::Move, &format!("{} -> {}", old_path, new_path), result)
     }
 
     async fn create_directory(&self, session_id: &str, path: &str) -> Result<()> {
         debug!("Creating directory for session {}: {}", session_id, path);
-        let pm = self.permission_manager.read().await;
-
-        if pm.requires_confirmation(path, FileOperation::Write) {
-            return Err(crate::error::Error::Sandbox(
-                crate::error::SandboxError::AccessDenied(format!(
-                    "Create directory requires confirmation for: {}",
-                    path
-                )),
-            ));
-        }
-
-        FileSystemHandler::create_directory(&pm, path).await
+        let result = self.mkdir(session_id, path).await.map(|outcome| ((), outcome));
+        self.audited(session_id, FileOperation::Write, path, result)
     }
 
     async fn execute_command(
@@ -169,15 +698,56 @@ impl AgentClient for AgentClientDelegate {
             "Executing command for session {}: {} {:?}",
             session_id, command, args
         );
-
-        // Validate cwd is inside granted paths when provided
-        if let Some(cwd_path) = cwd {
-            let pm = self.permission_manager.read().await;
-            pm.validate_access(cwd_path)?;
+        let command_line = format_command_line(command, args);
+        match self.execute(session_id, command, args, cwd, env).await {
+            Ok((result, outcome)) => {
+                let entry = AuditEntry::new(session_id, FileOperation::Execute.as_str(), &command_line, outcome);
+                self.log_audit(if outcome == AuditOutcome::Recorded {
+                    entry
+                } else {
+                    entry.with_detail(format!("exit code {}", result.exit_code))
+                });
+                Ok(result)
+            }
+            Err(e) => self.audited(session_id, FileOperation::Execute, &command_line, Err(e)),
         }
+    }
 
-        let policy = self.get_terminal_policy();
-        TerminalHandler::execute(&policy, command, args, cwd, env).await
+    async fn open_terminal(
+        &self,
+        session_id: &str,
+        command: &str,
+        args: &[String],
+        cwd: Option<&str>,
+        env: Option<&HashMap<String, String>>,
+    ) -> Result<String> {
+        debug!("Opening terminal for session {}: {} {:?}", session_id, command, args);
+        let command_line = format_command_line(command, args);
+        let result = self
+            .open(session_id, command, args, cwd, env)
+            .await
+            .map(|(terminal, outcome)| (terminal.id.clone(), outcome));
+        self.audited(session_id, FileOperation::Execute, &command_line, result)
+    }
+
+    async fn write_terminal(&self, session_id: &str, terminal_id: &str, data: &str) -> Result<()> {
+        self.live_terminal(session_id, terminal_id)?.write(data).await
+    }
+
+    async fn read_terminal(&self, session_id: &str, terminal_id: &str) -> Result<TerminalOutput> {
+        Ok(self.live_terminal(session_id, terminal_id)?.output())
+    }
+
+    async fn kill_terminal(&self, session_id: &str, terminal_id: &str) -> Result<()> {
+        self.live_terminal(session_id, terminal_id)?.kill();
+        Ok(())
+    }
+
+    async fn release_terminal(&self, session_id: &str, terminal_id: &str) -> Result<()> {
+        match &self.live_terminals {
+            Some(terminals) => terminals.release(session_id, terminal_id),
+            None => Err(Error::Sandbox(SandboxError::AccessDenied(format!("No terminal {}", terminal_id)))),
+        }
     }
 
     async fn request_permission(
@@ -191,20 +761,95 @@ impl AgentClient for AgentClientDelegate {
             session_id, operation, resource
         );
 
-        // For now, permissions are handled by the confirmation-based model
-        // This method is a placeholder for future interactive permission requests
-        let pm = self.permission_manager.read().await;
-
         let file_op = match operation {
             "read" => FileOperation::Read,
             "write" => FileOperation::Write,
             "delete" => FileOperation::Delete,
             "move" => FileOperation::Move,
+            "execute" => FileOperation::Execute,
             _ => FileOperation::Read,
         };
 
-        // Return true if no confirmation is needed
-        Ok(!pm.requires_confirmation(resource, file_op))
+        let (paths, command) = match file_op {
+            FileOperation::Execute => (Vec::new(), Some(resource)),
+            _ => (vec![resource.to_string()], None),
+        };
+        let outcome = self
+            .permission_outcome(session_id, file_op, resource, &paths, command)
+            .await
+            .unwrap_or(AuditOutcome::Denied);
+        self.log_audit(AuditEntry::new(session_id, "permission", resource, outcome).with_detail(operation));
+
+        Ok(outcome != AuditOutcome::Denied)
+    }
+
+    async fn request_tool_permission(&self, params: &RequestPermissionParams) -> Result<PermissionOutcome> {
+        let session_id = params.session_id.as_str();
+        let operation = params.operation();
+        let resource = params.resource();
+        debug!(
+            "Tool permission request for session {}: {} on {}",
+            session_id,
+            operation.as_str(),
+            resource
+        );
+
+        let command = params.command_line();
+        let decided = self
+            .permission_outcome(session_id, operation, &resource, &params.paths(), command.as_deref())
+            .await;
+        let (outcome, audit) = match (decided, &self.permission_tx) {
+            (Err(e), _) => {
+                debug!("Refused tool call for session {}: {}", session_id, e);
+                (params.decide(false), AuditOutcome::Denied)
+            }
+            (Ok(AuditOutcome::Denied), Some(tx)) => {
+                let (responder, choice_rx) = oneshot::channel();
+                let request = ToolPermissionRequest {
+                    session_id: session_id.to_string(),
+                    title: params.tool_call.title.clone().unwrap_or_else(|| resource.clone()),
+                    operation,
+                    previews: params.previews(),
+                    options: params.options.clone(),
+                    responder,
+                };
+                let choice = if tx.send(request).is_ok() { choice_rx.await.ok().flatten() } else { None };
+                // Only an option the agent offered counts as a choice
+                match choice.and_then(|id| params.options.iter().find(|option| option.option_id == id)) {
+                    Some(option) => (
+                        PermissionOutcome::Selected { option_id: option.option_id.clone() },
+                        if option.kind.is_allow() { AuditOutcome::Approved } else { AuditOutcome::Rejected },
+                    ),
+                    None => (PermissionOutcome::Cancelled, AuditOutcome::Rejected),
+                }
+            }
+            (Ok(decided), _) => (params.decide(decided != AuditOutcome::Denied), decided),
+        };
+
+        let detail = match &outcome {
+            PermissionOutcome::Selected { option_id } => format!("{}: {}", operation.as_str(), option_id),
+            PermissionOutcome::Cancelled => format!("{}: cancelled", operation.as_str()),
+        };
+        self.log_audit(AuditEntry::new(session_id, "permission", &resource, audit).with_detail(detail));
+        Ok(outcome)
+    }
+
+    async fn ask_user(&self, session_id: &str, message: &str) -> Result<Option<String>> {
+        let Some(tx) = &self.question_tx else {
+            debug!("No one to ask for session {}, declining: {}", session_id, message);
+            return Ok(None);
+        };
+        let (responder, reply_rx) = oneshot::channel();
+        let question = AgentQuestion {
+            session_id: session_id.to_string(),
+            message: message.to_string(),
+            responder,
+        };
+        if tx.send(question).is_err() {
+            return Ok(None);
+        }
+        // A question dropped unanswered counts as dismissed
+        Ok(reply_rx.await.unwrap_or(None))
     }
 
     async fn on_session_notification(&self, notification: SessionNotification) -> Result<()> {
@@ -213,11 +858,16 @@ impl AgentClient for AgentClientDelegate {
         }
         Ok(())
     }
+
+    fn middleware(&self) -> MiddlewareStack {
+        self.middleware.clone()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::acp::PermissionPreview;
 
     #[tokio::test]
     async fn test_delegate_creation() {
@@ -240,4 +890,335 @@ mod tests {
 
         assert!(delegate.notification_tx.is_some());
     }
+
+    #[tokio::test]
+    async fn test_dry_run_records_instead_of_writing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.txt");
+        let path_str = path.to_str().unwrap();
+
+        let mut pm = PermissionManager::new();
+        pm.grant_access(dir.path(), crate::sandbox::SecurityLevel::Trust)
+            .unwrap();
+        let storage = Arc::new(Storage::in_memory().unwrap());
+        let recorder = Arc::new(DryRunRecorder::new());
+        recorder.set_enabled("s1", true);
+
+        let delegate = AgentClientDelegate::new(Arc::new(RwLock::new(pm)), storage)
+            .with_dry_run(Arc::clone(&recorder));
+
+        delegate.write_text_file("s1", path_str, "hi").await.unwrap();
+        assert!(!path.exists());
+        assert_eq!(recorder.actions("s1").len(), 1);
+
+        delegate.write_text_file("s2", path_str, "hi").await.unwrap();
+        assert!(path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_command_confirmation_flow() {
+        let pm = Arc::new(RwLock::new(PermissionManager::new()));
+        let storage = Arc::new(Storage::in_memory().unwrap());
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let delegate = AgentClientDelegate::new(pm, storage).with_command_confirmation(tx);
+
+        // `true` is not in the default allow list, so it must be confirmed
+        let handle = tokio::spawn(async move {
+            delegate
+                .execute_command("s1", "true", &[], None, None)
+                .await
+        });
+
+        let request = rx.recv().await.unwrap();
+        assert_eq!(request.session_id, "s1");
+        assert_eq!(request.command_line, "true");
+        request.respond(false);
+
+        assert!(handle.await.unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_questions_wait_for_reply() {
+        let pm = Arc::new(RwLock::new(PermissionManager::new()));
+        let storage = Arc::new(Storage::in_memory().unwrap());
+        let unasked = AgentClientDelegate::new(Arc::clone(&pm), Arc::clone(&storage));
+        assert_eq!(unasked.ask_user("s1", "Which branch?").await.unwrap(), None);
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let delegate = AgentClientDelegate::new(pm, storage).with_questions(tx);
+        let handle = tokio::spawn(async move { delegate.ask_user("s1", "Which branch?").await });
+
+        let question = rx.recv().await.unwrap();
+        assert_eq!(question.message, "Which branch?");
+        question.reply(Some("main".to_string()));
+        assert_eq!(handle.await.unwrap().unwrap().as_deref(), Some("main"));
+    }
+
+    #[tokio::test]
+    async fn test_tool_permission_asks_with_preview() {
+        let pm = Arc::new(RwLock::new(PermissionManager::new()));
+        let storage = Arc::new(Storage::in_memory().unwrap());
+        let params: RequestPermissionParams = serde_json::from_value(serde_json::json!({
+            "sessionId": "s1",
+            "toolCall": {
+                "toolCallId": "call-1",
+                "title": "Edit main.rs",
+                "kind": "edit",
+                "content": [{ "type": "diff", "path": "/w/main.rs", "oldText": "a\n", "newText": "b\n" }],
+            },
+            "options": [
+                { "optionId": "once", "name": "Allow", "kind": "allow_once" },
+                { "optionId": "no", "name": "Reject", "kind": "reject_once" },
+            ],
+        }))
+        .unwrap();
+        let policy = ToolPolicy { write: crate::sandbox::Approval::Confirm, ..Default::default() };
+        policy.save(&storage.connection().unwrap(), &crate::sandbox::PolicyScope::Global).unwrap();
+
+        let unasked = AgentClientDelegate::new(Arc::clone(&pm), Arc::clone(&storage));
+        assert_eq!(
+            unasked.request_tool_permission(&params).await.unwrap(),
+            PermissionOutcome::Selected { option_id: "no".to_string() }
+        );
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let delegate = AgentClientDelegate::new(pm, storage).with_permission_requests(tx);
+        let asked = params.clone();
+        let handle = tokio::spawn(async move { delegate.request_tool_permission(&asked).await });
+
+        let request = rx.recv().await.unwrap();
+        assert_eq!(request.title, "Edit main.rs");
+        assert!(matches!(&request.previews[..], [PermissionPreview::Diff(diff)] if diff.path == "/w/main.rs"));
+        assert_eq!(request.options.len(), 2);
+        request.choose(Some("once".to_string()));
+        assert_eq!(
+            handle.await.unwrap().unwrap(),
+            PermissionOutcome::Selected { option_id: "once".to_string() }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_auto_accept_keeps_sandbox_checks() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut pm = PermissionManager::new();
+        pm.grant_access(dir.path(), crate::sandbox::SecurityLevel::Strict).unwrap();
+        let storage = Arc::new(Storage::in_memory().unwrap());
+        let policy = ToolPolicy {
+            write: crate::sandbox::Approval::AutoAccept,
+            terminal: crate::sandbox::Approval::AutoAccept,
+            ..Default::default()
+        };
+        policy.save(&storage.connection().unwrap(), &crate::sandbox::PolicyScope::Global).unwrap();
+        let delegate = AgentClientDelegate::new(Arc::new(RwLock::new(pm)), storage);
+
+        let request = |kind: &str, extra: serde_json::Value| -> RequestPermissionParams {
+            let mut tool_call = serde_json::json!({ "toolCallId": "call-1", "title": "Tool", "kind": kind });
+            tool_call.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+            serde_json::from_value(serde_json::json!({
+                "sessionId": "s1",
+                "toolCall": tool_call,
+                "options": [
+                    { "optionId": "once", "name": "Allow", "kind": "allow_once" },
+                    { "optionId": "no", "name": "Reject", "kind": "reject_once" },
+                ],
+            }))
+            .unwrap()
+        };
+        let allowed = PermissionOutcome::Selected { option_id: "once".to_string() };
+        let rejected = PermissionOutcome::Selected { option_id: "no".to_string() };
+
+        let blocked = request("execute", serde_json::json!({ "rawInput": { "command": "sudo rm x" } }));
+        assert_eq!(delegate.request_tool_permission(&blocked).await.unwrap(), rejected);
+        let listed = request("execute", serde_json::json!({ "rawInput": { "command": "ls -la" } }));
+        assert_eq!(delegate.request_tool_permission(&listed).await.unwrap(), allowed);
+
+        let inside = dir.path().join("a.txt").to_string_lossy().into_owned();
+        let write = |path: &str| request("edit", serde_json::json!({ "locations": [{ "path": path }] }));
+        assert_eq!(delegate.request_tool_permission(&write(&inside)).await.unwrap(), allowed);
+        assert_eq!(delegate.request_tool_permission(&write("/etc/passwd")).await.unwrap(), rejected);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_open_terminal() {
+        let pm = Arc::new(RwLock::new(PermissionManager::new()));
+        let storage = Arc::new(Storage::in_memory().unwrap());
+        let plain = AgentClientDelegate::new(Arc::clone(&pm), Arc::clone(&storage));
+        assert!(plain.open_terminal("s1", "cat", &[], None, None).await.is_err());
+
+        let terminals = Arc::new(LiveTerminals::new());
+        let delegate = AgentClientDelegate::new(pm, storage).with_live_terminals(Arc::clone(&terminals));
+        let id = delegate.open_terminal("s1", "cat", &[], None, None).await.unwrap();
+        assert_eq!(terminals.for_session("s1").len(), 1);
+        assert!(delegate.write_terminal("s2", &id, "hi\n").await.is_err());
+
+        delegate.write_terminal("s1", &id, "hi\n").await.unwrap();
+        delegate.kill_terminal("s1", &id).await.unwrap();
+        assert_eq!(terminals.get("s1", &id).unwrap().wait().await, -1);
+        assert_eq!(delegate.read_terminal("s1", &id).await.unwrap().exit_code, Some(-1));
+
+        delegate.release_terminal("s1", &id).await.unwrap();
+        assert!(delegate.read_terminal("s1", &id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_container_backend_needs_workspace() {
+        let pm = Arc::new(RwLock::new(PermissionManager::new()));
+        let storage = Arc::new(Storage::in_memory().unwrap());
+        let policy = TerminalPolicy {
+            backend: TerminalBackend::Container,
+            ..TerminalPolicy::default()
+        };
+        crate::storage::set_setting(
+            &storage.connection().unwrap(),
+            "terminal_policy",
+            &serde_json::to_string(&policy).unwrap(),
+        )
+        .unwrap();
+
+        // `ls` is allowed outright, so this goes straight to the backend
+        let delegate = AgentClientDelegate::new(pm, storage);
+        let err = delegate.execute_command("s1", "ls", &[], None, None).await.unwrap_err();
+        assert!(err.to_string().contains("needs a workspace"));
+    }
+
+    #[tokio::test]
+    async fn test_tool_policy_decides_what_is_confirmed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        std::fs::write(&path, "notes").unwrap();
+        let path_str = path.to_str().unwrap();
+
+        let mut pm = PermissionManager::new();
+        pm.grant_access(dir.path(), crate::sandbox::SecurityLevel::Trust).unwrap();
+        let storage = Arc::new(Storage::in_memory().unwrap());
+        let policy = ToolPolicy {
+            read: crate::sandbox::Approval::Confirm,
+            ..Default::default()
+        };
+        policy
+            .save(
+                &storage.connection().unwrap(),
+                &crate::sandbox::PolicyScope::Workspace(dir.path().to_path_buf()),
+            )
+            .unwrap();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let delegate = Arc::new(
+            AgentClientDelegate::new(Arc::new(RwLock::new(pm)), storage)
+                .with_command_confirmation(tx)
+                .with_policy_scope("claude-code", dir.path()),
+        );
+
+        // Reads are confirmed under this workspace's policy
+        let reader = Arc::clone(&delegate);
+        let path_owned = path_str.to_string();
+        let handle = tokio::spawn(async move { reader.read_text_file("s1", &path_owned).await });
+        let request = rx.recv().await.unwrap();
+        assert_eq!(request.operation, FileOperation::Read);
+        assert_eq!(request.command_line, path_str);
+        request.respond(true);
+        assert_eq!(handle.await.unwrap().unwrap(), "notes");
+
+        // Trusted deletes still go ahead without asking
+        delegate.delete_file("s1", path_str).await.unwrap();
+        assert!(!path.exists());
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_auto_mode_approves_and_logs_but_keeps_hard_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.txt");
+        let path_str = path.to_str().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+
+        let mut pm = PermissionManager::new();
+        pm.grant_access(dir.path(), crate::sandbox::SecurityLevel::Strict).unwrap();
+        let storage = Arc::new(Storage::in_memory().unwrap());
+        let auto_mode = Arc::new(AutoMode::new());
+        auto_mode.set_enabled("s1", true);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let delegate = AgentClientDelegate::new(Arc::new(RwLock::new(pm)), Arc::clone(&storage))
+            .with_command_confirmation(tx)
+            .with_auto_mode(auto_mode);
+
+        // Strict paths need confirmation for writes; auto mode approves them
+        delegate.write_text_file("s1", path_str, "hi").await.unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "hi");
+        delegate.execute_command("s1", "true", &[], None, None).await.unwrap();
+        assert!(rx.try_recv().is_err());
+
+        // Hard blocks still apply
+        let outside_path = outside.path().join("x.txt");
+        assert!(delegate
+            .write_text_file("s1", outside_path.to_str().unwrap(), "no")
+            .await
+            .is_err());
+        let blocked = ["-rf".to_string(), "/".to_string()];
+        assert!(delegate.execute_command("s1", "rm", &blocked, None, None).await.is_err());
+
+        let conn = storage.connection().unwrap();
+        let audit = |outcome| {
+            let filter = crate::types::AuditFilter {
+                session_id: Some("s1".into()),
+                outcome: Some(outcome),
+                ..Default::default()
+            };
+            crate::storage::list_audit_entries(&conn, &filter).unwrap()
+        };
+        let approved: Vec<(String, String)> = audit(AuditOutcome::AutoApproved)
+            .into_iter()
+            .map(|e| (e.operation, e.target))
+            .collect();
+        assert_eq!(
+            approved,
+            vec![("execute".to_string(), "true".to_string()), ("write".to_string(), path_str.to_string())]
+        );
+        assert_eq!(audit(AuditOutcome::Denied).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_operations_are_audited_with_outcome() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        let path_str = path.to_str().unwrap();
+
+        let mut pm = PermissionManager::new();
+        pm.grant_access(dir.path(), crate::sandbox::SecurityLevel::Strict).unwrap();
+        let storage = Arc::new(Storage::in_memory().unwrap());
+        let (tx, mut rx) = mpsc::unbounded_channel::<CommandConfirmation>();
+        let delegate = Arc::new(
+            AgentClientDelegate::new(Arc::new(RwLock::new(pm)), Arc::clone(&storage)).with_command_confirmation(tx),
+        );
+
+        // Strict paths allow reads but ask before writes
+        let writer = Arc::clone(&delegate);
+        let target = path_str.to_string();
+        let handle = tokio::spawn(async move { writer.write_text_file("s1", &target, "hi").await });
+        rx.recv().await.unwrap().respond(true);
+        handle.await.unwrap().unwrap();
+        assert_eq!(delegate.read_text_file("s1", path_str).await.unwrap(), "hi");
+
+        let deleter = Arc::clone(&delegate);
+        let target = path_str.to_string();
+        let handle = tokio::spawn(async move { deleter.delete_file("s1", &target).await });
+        rx.recv().await.unwrap().respond(false);
+        assert!(handle.await.unwrap().is_err());
+        assert!(path.exists());
+
+        let entries = crate::storage::list_audit_entries(&storage.connection().unwrap(), &Default::default()).unwrap();
+        let outcomes: Vec<(&str, AuditOutcome)> = entries.iter().map(|e| (e.operation.as_str(), e.outcome)).collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                ("delete", AuditOutcome::Rejected),
+                ("read", AuditOutcome::Allowed),
+                ("write", AuditOutcome::Approved),
+            ]
+        );
+        assert!(entries[0].detail.as_ref().unwrap().ends_with(&format!("Rejected by user: {}", path_str)));
+    }
 }
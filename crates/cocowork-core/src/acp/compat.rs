@@ -0,0 +1,120 @@
+//! Differences between ACP revisions
+//!
+//! The client offers the newest protocol version it speaks in `initialize`
+//! and the agent answers with the version it will use. Everything that
+//! differs between the versions goes through [`ProtocolDialect`], so the
+//! rest of the connection code is written once:
+//!
+//! | | version 0 | version 1 |
+//! |---|---|---|
+//! | Changing the mode | `session/setMode` | `session/set_mode` |
+//! | Changing the model | `session/setModel` | `session/set_model` |
+//! | Changing an option | `session/setConfig` | `session/set_config_option` |
+//! | Cancelling a turn | request, answered by the agent | notification |
+//! | Writing a file | `fs/write_file` | `fs/write_text_file` |
+//! | Running a command | `terminal/execute` | `terminal/create` |
+
+use crate::types::ACP_PROTOCOL_VERSION;
+use tracing::warn;
+
+/// The revision of ACP a connection speaks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtocolDialect {
+    /// Version 0, spoken by agents written before ACP 1.0
+    Legacy,
+    V1,
+}
+
+impl ProtocolDialect {
+    /// The dialect for the version an agent answered `initialize` with
+    ///
+    /// Versions newer than the client knows are spoken as the newest one it
+    /// does, which is what a conforming agent falls back to.
+    pub fn negotiate(agent_version: u32) -> Self {
+        if agent_version > ACP_PROTOCOL_VERSION {
+            warn!(
+                "Agent speaks ACP version {}, newer than {}; using {}",
+                agent_version, ACP_PROTOCOL_VERSION, ACP_PROTOCOL_VERSION
+            );
+        }
+        match agent_version.min(ACP_PROTOCOL_VERSION) {
+            0 => ProtocolDialect::Legacy,
+            _ => ProtocolDialect::V1,
+        }
+    }
+
+    pub fn version(&self) -> u32 {
+        match self {
+            ProtocolDialect::Legacy => 0,
+            ProtocolDialect::V1 => 1,
+        }
+    }
+
+    pub fn set_mode_method(&self) -> &'static str {
+        match self {
+            ProtocolDialect::Legacy => "session/setMode",
+            ProtocolDialect::V1 => "session/set_mode",
+        }
+    }
+
+    pub fn set_model_method(&self) -> &'static str {
+        match self {
+            ProtocolDialect::Legacy => "session/setModel",
+            ProtocolDialect::V1 => "session/set_model",
+        }
+    }
+
+    pub fn set_config_method(&self) -> &'static str {
+        match self {
+            ProtocolDialect::Legacy => "session/setConfig",
+            ProtocolDialect::V1 => "session/set_config_option",
+        }
+    }
+
+    /// Whether `session/cancel` is a notification, with no response to wait for
+    pub fn cancel_is_notification(&self) -> bool {
+        matches!(self, ProtocolDialect::V1)
+    }
+
+    /// The current name of a method the agent called, for agents using an older one
+    ///
+    /// Agents are accepted whatever name they use, as some mix the two.
+    pub fn canonical_agent_method(method: &str) -> &str {
+        match method {
+            "fs/write_file" => "fs/write_text_file",
+            "terminal/execute" => "terminal/create",
+            other => other,
+        }
+    }
+}
+
+impl Default for ProtocolDialect {
+    fn default() -> Self {
+        Self::negotiate(ACP_PROTOCOL_VERSION)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(ProtocolDialect::negotiate(0), ProtocolDialect::Legacy);
+        assert_eq!(ProtocolDialect::negotiate(1), ProtocolDialect::V1);
+        // Newer agents are spoken to in the newest version known
+        assert_eq!(ProtocolDialect::negotiate(7), ProtocolDialect::V1);
+        assert_eq!(ProtocolDialect::default().version(), ACP_PROTOCOL_VERSION);
+    }
+
+    #[test]
+    fn test_method_names() {
+        assert_eq!(ProtocolDialect::Legacy.set_mode_method(), "session/setMode");
+        assert_eq!(ProtocolDialect::V1.set_mode_method(), "session/set_mode");
+        assert_eq!(ProtocolDialect::V1.set_config_method(), "session/set_config_option");
+        assert!(!ProtocolDialect::Legacy.cancel_is_notification());
+        assert_eq!(ProtocolDialect::canonical_agent_method("fs/write_file"), "fs/write_text_file");
+        assert_eq!(ProtocolDialect::canonical_agent_method("terminal/execute"), "terminal/create");
+        assert_eq!(ProtocolDialect::canonical_agent_method("fs/read_text_file"), "fs/read_text_file");
+    }
+}
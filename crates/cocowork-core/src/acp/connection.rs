@@ -3,6 +3,10 @@
 //! This module implements the AgentConnection trait for communicating with agents
 //! via the Agent Client Protocol (ACP).
 
+use super::compat::ProtocolDialect;
+use super::elicitation::{ElicitationParams, ElicitationResponse};
+use super::middleware::MiddlewareStack;
+use super::permission::RequestPermissionParams;
 use super::protocol::{AcpMessage, ProtocolHandler};
 use super::traits::{
     AgentClient, AgentConnection, ConfigOptionId, LoadSessionResponse, ModelId, NewSessionResponse,
@@ -12,20 +16,129 @@ use super::traits::{
 use super::transport::Transport;
 use crate::error::{AcpError, Error, Result};
 use crate::types::{
-    AgentCapabilities, AgentInfo, ClientCapabilities, ConfigOptionType, ContentBlock,
+    AgentCapabilities, AgentHandshake, AgentInfo, AuthMethod, ClientCapabilities, ConfigOptionType, ContentBlock,
     FsCreateDirectoryParams, FsDeleteFileParams, FsListDirectoryParams, FsMoveFileParams,
     FsReadTextFileParams, FsWriteFileParams, JsonRpcRequest, JsonRpcResponse, McpServerConfig,
-    MessageBlock, PromptResponse, SessionMessageRole, SessionUpdateNotification,
-    TerminalExecuteParams,
+    MessageBlock, PromptResponse, SessionMessageRole, SessionUpdate, SessionUpdateNotification,
+    StopReason, TerminalExecuteParams, TerminalIdParams, TerminalWriteParams,
 };
 use async_trait::async_trait;
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::process::Child;
 use tokio::sync::{broadcast, mpsc, oneshot, Mutex, RwLock};
+use tokio::time::Instant;
 use tracing::{debug, error, info, trace, warn};
 
+/// Tuning for a connection's session notification channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotificationSettings {
+    /// Notifications buffered per subscriber before slow ones lag
+    pub capacity: usize,
+    /// How long to hold a text chunk for following chunks to merge into;
+    /// zero sends every chunk as it arrives
+    pub coalesce_window: Duration,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            capacity: 256,
+            coalesce_window: Duration::from_millis(16),
+        }
+    }
+}
+
+impl NotificationSettings {
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    pub fn with_coalesce_window(mut self, window: Duration) -> Self {
+        self.coalesce_window = window;
+        self
+    }
+}
+
+/// Merges consecutive text chunks for the same session
+///
+/// Fast agents stream a chunk per token; holding each briefly and appending
+/// the ones that follow keeps the broadcast channel from overflowing.
+struct ChunkCoalescer {
+    window: Duration,
+    pending: Option<(SessionUpdateNotification, Instant)>,
+}
+
+impl ChunkCoalescer {
+    fn new(window: Duration) -> Self {
+        Self { window, pending: None }
+    }
+
+    /// When the held chunk is due to be sent
+    fn deadline(&self) -> Option<Instant> {
+        self.pending.as_ref().map(|(_, deadline)| *deadline)
+    }
+
+    /// Take in a notification, returning those ready to send in order
+    fn push(
+        &mut self,
+        mut notification: SessionUpdateNotification,
+        now: Instant,
+    ) -> Vec<SessionUpdateNotification> {
+        if let Some((pending, _)) = self.pending.as_mut() {
+            if append_chunk(pending, &notification) {
+                return Vec::new();
+            }
+        }
+
+        let mut ready = self.flush().into_iter().collect::<Vec<_>>();
+        if !self.window.is_zero() && chunk_text(&mut notification).is_some() {
+            self.pending = Some((notification, now + self.window));
+        } else {
+            ready.push(notification);
+        }
+        ready
+    }
+
+    /// Release the held chunk, if any
+    fn flush(&mut self) -> Option<SessionUpdateNotification> {
+        self.pending.take().map(|(notification, _)| notification)
+    }
+}
+
+/// The text of an agent message or thought chunk
+fn chunk_text(notification: &mut SessionUpdateNotification) -> Option<&mut String> {
+    match &mut notification.update {
+        SessionUpdate::AgentMessageChunk { content: ContentBlock::Text { text } }
+        | SessionUpdate::Thought { content: ContentBlock::Text { text } } => Some(text),
+        _ => None,
+    }
+}
+
+/// Append `next` to `pending` if both are the same kind of text chunk for the same session
+fn append_chunk(pending: &mut SessionUpdateNotification, next: &SessionUpdateNotification) -> bool {
+    if pending.session_id != next.session_id
+        || std::mem::discriminant(&pending.update) != std::mem::discriminant(&next.update)
+    {
+        return false;
+    }
+    let next_text = match &next.update {
+        SessionUpdate::AgentMessageChunk { content: ContentBlock::Text { text } }
+        | SessionUpdate::Thought { content: ContentBlock::Text { text } } => text,
+        _ => return false,
+    };
+    match chunk_text(pending) {
+        Some(text) => {
+            text.push_str(next_text);
+            true
+        }
+        None => false,
+    }
+}
+
 /// ACP Connection for communicating with an agent
 ///
 /// This struct implements the `AgentConnection` trait and provides the full
@@ -39,10 +152,18 @@ pub struct AcpConnection {
     transport: Arc<Transport>,
     /// Child process handle
     child: Arc<Mutex<Child>>,
+    /// OS process ID of the agent, while it was known at spawn
+    pid: Option<u32>,
     /// Agent capabilities from initialization
     capabilities: Arc<RwLock<Option<AgentCapabilities>>>,
     /// Agent info
     agent_info: Arc<RwLock<Option<AgentInfo>>>,
+    /// ACP version the agent answered initialization with
+    protocol_version: Arc<RwLock<Option<u32>>>,
+    /// Authentication methods from initialization
+    auth_methods: Arc<RwLock<Vec<AuthMethod>>>,
+    /// Hooks on every message to and from the agent
+    middleware: MiddlewareStack,
     /// Pending requests (request_id -> response channel)
     pending_requests: Arc<Mutex<HashMap<u64, oneshot::Sender<JsonRpcResponse>>>>,
     /// Notification broadcast channel
@@ -60,6 +181,20 @@ impl AcpConnection {
         env: &HashMap<String, String>,
         cwd: Option<&str>,
         delegate: Arc<dyn AgentClient>,
+    ) -> Result<Self> {
+        Self::with_notification_settings(name, command, args, env, cwd, delegate, NotificationSettings::default())
+            .await
+    }
+
+    /// Create a new ACP connection with a custom notification channel
+    pub async fn with_notification_settings(
+        name: impl Into<String>,
+        command: &str,
+        args: &[String],
+        env: &HashMap<String, String>,
+        cwd: Option<&str>,
+        delegate: Arc<dyn AgentClient>,
+        settings: NotificationSettings,
     ) -> Result<Self> {
         let name = name.into();
         info!("Connecting to agent: {} ({})", name, command);
@@ -67,14 +202,15 @@ impl AcpConnection {
         let (transport, child) = Transport::spawn(command, args, env, cwd).await?;
 
         let transport = Arc::new(transport);
+        let pid = child.id();
         let child = Arc::new(Mutex::new(child));
         let protocol = ProtocolHandler::new();
         let capabilities = Arc::new(RwLock::new(None));
         let agent_info = Arc::new(RwLock::new(None));
         let pending_requests = Arc::new(Mutex::new(HashMap::new()));
 
-        // Create notification broadcast channel with reasonable capacity
-        let (notification_tx, _) = broadcast::channel(256);
+        let (notification_tx, _) = broadcast::channel(settings.capacity.max(1));
+        let middleware = delegate.middleware();
 
         // Start message processing task
         let message_task = tokio::spawn(Self::message_loop(
@@ -82,6 +218,8 @@ impl AcpConnection {
             Arc::clone(&pending_requests),
             notification_tx.clone(),
             delegate,
+            settings.coalesce_window,
+            middleware.clone(),
         ));
 
         Ok(Self {
@@ -89,8 +227,12 @@ impl AcpConnection {
             protocol,
             transport,
             child,
+            pid,
             capabilities,
             agent_info,
+            protocol_version: Arc::new(RwLock::new(None)),
+            auth_methods: Arc::new(RwLock::new(Vec::new())),
+            middleware,
             pending_requests,
             notification_tx,
             _message_task: message_task,
@@ -114,11 +256,18 @@ impl AcpConnection {
             *caps = Some(init_result.get_capabilities());
         }
 
+        // Store auth methods
+        {
+            let mut methods = self.auth_methods.write().await;
+            *methods = init_result.auth_methods.clone();
+        }
+
         // Store agent info
         {
             let mut info = self.agent_info.write().await;
             *info = init_result.agent_info;
         }
+        *self.protocol_version.write().await = Some(init_result.protocol_version);
 
         info!("ACP connection initialized successfully for {}", self.name);
         Ok(())
@@ -134,6 +283,30 @@ impl AcpConnection {
         self.agent_info.read().await.clone()
     }
 
+    /// Prompt content the agent can take: embedded files become links for
+    /// agents that don't advertise embedded context
+    async fn supported_content(&self, content: Vec<ContentBlock>) -> Vec<ContentBlock> {
+        let embedded_context = self
+            .capabilities
+            .read()
+            .await
+            .as_ref()
+            .is_some_and(|caps| caps.embedded_context);
+        if embedded_context {
+            content
+        } else {
+            content.into_iter().map(ContentBlock::without_embedded).collect()
+        }
+    }
+
+    /// Send a request and wait for its response, or just send a notification
+    async fn send_message(&self, request: JsonRpcRequest) -> Result<()> {
+        if request.id.is_none() {
+            return self.send_request_no_wait(request).await;
+        }
+        self.send_request(request).await.map(|_| ())
+    }
+
     /// Send request and wait for response
     async fn send_request(&self, request: JsonRpcRequest) -> Result<JsonRpcResponse> {
         let rx = self.send_request_with_receiver(request).await?;
@@ -153,8 +326,9 @@ impl AcpConnection {
 
     async fn send_request_with_receiver(
         &self,
-        request: JsonRpcRequest,
+        mut request: JsonRpcRequest,
     ) -> Result<oneshot::Receiver<JsonRpcResponse>> {
+        self.middleware.outgoing(&mut request);
         let request_id = request
             .id
             .as_ref()
@@ -187,7 +361,8 @@ impl AcpConnection {
     }
 
     /// Send request without waiting for response
-    async fn send_request_no_wait(&self, request: JsonRpcRequest) -> Result<()> {
+    async fn send_request_no_wait(&self, mut request: JsonRpcRequest) -> Result<()> {
+        self.middleware.outgoing(&mut request);
         self.transport.send_request(&request).await
     }
 
@@ -197,9 +372,17 @@ impl AcpConnection {
         pending_requests: Arc<Mutex<HashMap<u64, oneshot::Sender<JsonRpcResponse>>>>,
         notification_tx: broadcast::Sender<SessionNotification>,
         delegate: Arc<dyn AgentClient>,
+        coalesce_window: Duration,
+        middleware: MiddlewareStack,
     ) {
         let protocol = ProtocolHandler::new();
         let mut buffer = String::new();
+        let mut coalescer = ChunkCoalescer::new(coalesce_window);
+        let send_update = |notification: SessionUpdateNotification| {
+            if notification_tx.send(SessionNotification::Update(notification)).is_err() {
+                warn!("No receivers for session update");
+            }
+        };
 
         let json_start_index = |s: &str| -> Option<usize> {
             let obj = s.find('{');
@@ -213,10 +396,25 @@ impl AcpConnection {
         };
 
         loop {
-            let line = match transport.recv_line().await {
+            let next_line = match coalescer.deadline() {
+                Some(deadline) => tokio::select! {
+                    line = transport.recv_line() => line,
+                    _ = tokio::time::sleep_until(deadline) => {
+                        if let Some(notification) = coalescer.flush() {
+                            send_update(notification);
+                        }
+                        continue;
+                    }
+                },
+                None => transport.recv_line().await,
+            };
+            let line = match next_line {
                 Some(line) => line,
                 None => {
                     debug!("Transport closed");
+                    if let Some(notification) = coalescer.flush() {
+                        send_update(notification);
+                    }
                     let _ = notification_tx.send(SessionNotification::Disconnected);
                     break;
                 }
@@ -236,7 +434,7 @@ impl AcpConnection {
                 continue;
             }
 
-            let value = match serde_json::from_str::<serde_json::Value>(&buffer) {
+            let mut value = match serde_json::from_str::<serde_json::Value>(&buffer) {
                 Ok(v) => {
                     buffer.clear();
                     v
@@ -272,8 +470,18 @@ impl AcpConnection {
             };
 
             debug!("Received message: {}", value);
+            middleware.incoming(&mut value);
+
+            let message = protocol.parse_message(&value);
+            // A held chunk goes out before anything that isn't another update,
+            // so the turn's text lands before its prompt response
+            if !matches!(message, Ok(AcpMessage::SessionUpdate(_))) {
+                if let Some(notification) = coalescer.flush() {
+                    send_update(notification);
+                }
+            }
 
-            match protocol.parse_message(&value) {
+            match message {
                 Ok(AcpMessage::Response(response)) => {
                     debug!("Parsed as Response with id: {:?}", response.id);
                     if let Some(id) = response.id.as_ref().and_then(|v| v.as_u64()) {
@@ -292,13 +500,14 @@ impl AcpConnection {
                         notification.session_id,
                         notification.update
                     );
-                    if notification_tx.send(SessionNotification::Update(notification)).is_err() {
-                        warn!("No receivers for session update");
+                    for notification in coalescer.push(notification, Instant::now()) {
+                        send_update(notification);
                     }
                 }
                 Ok(AcpMessage::AgentRequest(request)) => {
                     debug!("Parsed as AgentRequest: {}", request.method);
-                    let response = Self::handle_agent_request(&protocol, &delegate, &request).await;
+                    let mut response = Self::handle_agent_request(&protocol, &delegate, &request).await;
+                    middleware.response(&mut response);
                     if let Err(e) = transport.send_response(&response).await {
                         error!("Failed to send response: {}", e);
                     }
@@ -325,7 +534,7 @@ impl AcpConnection {
         let request_id = request.id.clone().unwrap_or(serde_json::Value::Null);
         let params = request.params.clone().unwrap_or(serde_json::Value::Null);
 
-        match request.method.as_str() {
+        match ProtocolDialect::canonical_agent_method(&request.method) {
             "fs/read_text_file" => {
                 match serde_json::from_value::<FsReadTextFileParams>(params) {
                     Ok(p) => match delegate.read_text_file(&p.session_id, &p.path).await {
@@ -335,7 +544,7 @@ impl AcpConnection {
                     Err(e) => protocol.create_error_response(request_id, -32602, &e.to_string()),
                 }
             }
-            "fs/write_file" | "fs/write_text_file" => {
+            "fs/write_text_file" => {
                 match serde_json::from_value::<FsWriteFileParams>(params) {
                     Ok(p) => {
                         match delegate
@@ -386,7 +595,7 @@ impl AcpConnection {
                     Err(e) => protocol.create_error_response(request_id, -32602, &e.to_string()),
                 }
             }
-            "terminal/execute" | "terminal/create" => {
+            "terminal/create" => {
                 match serde_json::from_value::<TerminalExecuteParams>(params) {
                     Ok(p) => {
                         let args = p.args.unwrap_or_default();
@@ -409,6 +618,66 @@ impl AcpConnection {
                     Err(e) => protocol.create_error_response(request_id, -32602, &e.to_string()),
                 }
             }
+            "terminal/open" => match serde_json::from_value::<TerminalExecuteParams>(params) {
+                Ok(p) => {
+                    let args = p.args.unwrap_or_default();
+                    match delegate
+                        .open_terminal(&p.session_id, &p.command, &args, p.cwd.as_deref(), p.env.as_ref())
+                        .await
+                    {
+                        Ok(terminal_id) => protocol.create_terminal_open_response(request_id, &terminal_id),
+                        Err(e) => protocol.create_error_response(request_id, -32603, &e.to_string()),
+                    }
+                }
+                Err(e) => protocol.create_error_response(request_id, -32602, &e.to_string()),
+            },
+            "terminal/write" => match serde_json::from_value::<TerminalWriteParams>(params) {
+                Ok(p) => match delegate.write_terminal(&p.session_id, &p.terminal_id, &p.data).await {
+                    Ok(()) => protocol.create_fs_write_response(request_id),
+                    Err(e) => protocol.create_error_response(request_id, -32603, &e.to_string()),
+                },
+                Err(e) => protocol.create_error_response(request_id, -32602, &e.to_string()),
+            },
+            "terminal/output" => match serde_json::from_value::<TerminalIdParams>(params) {
+                Ok(p) => match delegate.read_terminal(&p.session_id, &p.terminal_id).await {
+                    Ok(output) => protocol.create_terminal_output_response(request_id, output),
+                    Err(e) => protocol.create_error_response(request_id, -32603, &e.to_string()),
+                },
+                Err(e) => protocol.create_error_response(request_id, -32602, &e.to_string()),
+            },
+            method @ ("terminal/kill" | "terminal/release") => {
+                match serde_json::from_value::<TerminalIdParams>(params) {
+                    Ok(p) => {
+                        let done = if method == "terminal/kill" {
+                            delegate.kill_terminal(&p.session_id, &p.terminal_id).await
+                        } else {
+                            delegate.release_terminal(&p.session_id, &p.terminal_id).await
+                        };
+                        match done {
+                            Ok(()) => protocol.create_fs_write_response(request_id),
+                            Err(e) => protocol.create_error_response(request_id, -32603, &e.to_string()),
+                        }
+                    }
+                    Err(e) => protocol.create_error_response(request_id, -32602, &e.to_string()),
+                }
+            }
+            "elicitation/create" => match serde_json::from_value::<ElicitationParams>(params) {
+                Ok(p) => match delegate.ask_user(&p.session_id, &p.message).await {
+                    Ok(reply) => protocol.create_elicitation_response(
+                        request_id,
+                        ElicitationResponse::from_reply(p.requested_schema.as_ref(), reply),
+                    ),
+                    Err(e) => protocol.create_error_response(request_id, -32603, &e.to_string()),
+                },
+                Err(e) => protocol.create_error_response(request_id, -32602, &e.to_string()),
+            },
+            "session/request_permission" => match serde_json::from_value::<RequestPermissionParams>(params) {
+                Ok(p) => match delegate.request_tool_permission(&p).await {
+                    Ok(outcome) => protocol.create_permission_response(request_id, outcome),
+                    Err(e) => protocol.create_error_response(request_id, -32603, &e.to_string()),
+                },
+                Err(e) => protocol.create_error_response(request_id, -32602, &e.to_string()),
+            },
             other => protocol.create_error_response(
                 request_id,
                 -32601,
@@ -575,18 +844,16 @@ impl AgentConnection for AcpConnection {
         debug!("Sending prompt to session: {}", session_id);
 
         let mode = message.mode.map(|m| m.0);
+        let content = self.supported_content(message.content).await;
         let request = self
             .protocol
-            .create_session_prompt_request(session_id, message.content, mode);
+            .create_session_prompt_request(session_id, content, mode);
 
         let response = self.send_request(request).await?;
 
         // Parse the prompt response
         if let Some(error) = &response.error {
-            return Err(Error::Acp(AcpError::InvalidMessage(format!(
-                "Prompt failed: {} (code {})",
-                error.message, error.code
-            ))));
+            return Err(super::protocol::request_error("Prompt failed", error));
         }
 
         let result = response.result.as_ref().ok_or_else(|| {
@@ -606,12 +873,29 @@ impl AgentConnection for AcpConnection {
         debug!("Sending streaming prompt to session: {}", session_id);
 
         let mode = message.mode.map(|m| m.0);
+        let content = self.supported_content(message.content).await;
         let request = self
             .protocol
-            .create_session_prompt_request(session_id, message.content, mode);
+            .create_session_prompt_request(session_id.clone(), content, mode);
 
-        // Don't wait for response - updates come via session/update notifications
-        self.send_request_no_wait(request).await?;
+        // Updates come via session/update notifications; the response ends the
+        // turn and is passed on as a PromptResponseReceived update
+        let rx = self.send_request_with_receiver(request).await?;
+        let notification_tx = self.notification_tx.clone();
+        tokio::spawn(async move {
+            // A closed connection already sends Disconnected
+            let Ok(response) = rx.await else {
+                return;
+            };
+            let (stop_reason, error) = prompt_outcome(&response);
+            if let Some(error) = error {
+                let _ = notification_tx.send(SessionNotification::Error(error));
+            }
+            let _ = notification_tx.send(SessionNotification::Update(SessionUpdateNotification {
+                session_id,
+                update: SessionUpdate::PromptResponseReceived { stop_reason },
+            }));
+        });
 
         Ok(())
     }
@@ -620,7 +904,7 @@ impl AgentConnection for AcpConnection {
         debug!("Cancelling session: {}", session_id);
 
         let request = self.protocol.create_session_cancel_request(session_id);
-        let _ = self.send_request(request).await?;
+        self.send_message(request).await?;
 
         Ok(())
     }
@@ -710,6 +994,15 @@ impl AgentConnection for AcpConnection {
         }
     }
 
+    fn process_id(&self) -> Option<u32> {
+        self.pid
+    }
+
+    fn pending_requests(&self) -> usize {
+        // Only a snapshot, so skip it rather than wait while a request is registered
+        self.pending_requests.try_lock().map_or(0, |pending| pending.len())
+    }
+
     async fn terminate(&self) -> Result<()> {
         info!("Terminating agent: {}", self.name);
 
@@ -724,9 +1017,32 @@ impl AgentConnection for AcpConnection {
         Ok(())
     }
 
-    async fn send_response(&self, response: JsonRpcResponse) -> Result<()> {
+    async fn send_response(&self, mut response: JsonRpcResponse) -> Result<()> {
+        self.middleware.response(&mut response);
         self.transport.send_response(&response).await
     }
+
+    async fn auth_methods(&self) -> Vec<AuthMethod> {
+        self.auth_methods.read().await.clone()
+    }
+
+    async fn handshake(&self) -> Option<AgentHandshake> {
+        let protocol_version = (*self.protocol_version.read().await)?;
+        Some(AgentHandshake {
+            protocol_version,
+            agent_info: self.agent_info().await,
+            capabilities: self.capabilities().await.unwrap_or_default(),
+            auth_methods: self.auth_methods.read().await.clone(),
+        })
+    }
+
+    async fn authenticate(&self, method_id: String) -> Result<()> {
+        info!("Authenticating {} with method {}", self.name, method_id);
+
+        let request = self.protocol.create_authenticate_request(method_id);
+        let response = self.send_request(request).await?;
+        self.protocol.parse_void_response(&response)
+    }
 }
 
 // ============================================================================
@@ -753,6 +1069,7 @@ impl AcpConnection {
             .await?;
 
         let transport = Arc::new(transport);
+        let pid = child.id();
         let child = Arc::new(Mutex::new(child));
         let protocol = ProtocolHandler::new();
         let capabilities = Arc::new(RwLock::new(None));
@@ -760,7 +1077,7 @@ impl AcpConnection {
         let pending_requests = Arc::new(Mutex::new(HashMap::new()));
 
         // Create notification broadcast channel
-        let (notification_tx, _) = broadcast::channel(256);
+        let (notification_tx, _) = broadcast::channel(NotificationSettings::default().capacity);
 
         // Start message processing task with legacy channel forwarding
         let message_task = tokio::spawn(Self::legacy_message_loop(
@@ -775,8 +1092,12 @@ impl AcpConnection {
             protocol,
             transport,
             child,
+            pid,
             capabilities,
             agent_info,
+            protocol_version: Arc::new(RwLock::new(None)),
+            auth_methods: Arc::new(RwLock::new(Vec::new())),
+            middleware: MiddlewareStack::default(),
             pending_requests,
             notification_tx,
             _message_task: message_task,
@@ -971,7 +1292,7 @@ impl AcpConnection {
         debug!("Cancelling session: {}", session_id);
 
         let request = self.protocol.create_session_cancel_request(session_id);
-        let _ = self.send_request(request).await?;
+        self.send_message(request).await?;
 
         Ok(())
     }
@@ -1005,9 +1326,53 @@ impl AcpConnection {
 /// Type alias for backward compatibility
 pub type AcpClient = AcpConnection;
 
+/// How a prompt ended, from its response: the stop reason and any error message
+fn prompt_outcome(response: &JsonRpcResponse) -> (Option<StopReason>, Option<String>) {
+    if let Some(error) = &response.error {
+        return (
+            Some(StopReason::Error),
+            Some(format!("{} (code {})", error.message, error.code)),
+        );
+    }
+    let stop_reason = response
+        .result
+        .clone()
+        .and_then(|result| serde_json::from_value::<PromptResponse>(result).ok())
+        .map(|response| response.stop_reason);
+    (stop_reason, None)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::JsonRpcError;
+
+    #[test]
+    fn test_prompt_outcome() {
+        let response = |result: Option<serde_json::Value>, error: Option<JsonRpcError>| JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            id: Some(serde_json::json!(3)),
+            result,
+            error,
+        };
+
+        let done = response(Some(serde_json::json!({ "stopReason": "max_tokens" })), None);
+        assert_eq!(prompt_outcome(&done), (Some(StopReason::MaxTokens), None));
+
+        let failed = response(
+            None,
+            Some(JsonRpcError {
+                code: -32603,
+                message: "Internal error".to_string(),
+                data: None,
+            }),
+        );
+        assert_eq!(
+            prompt_outcome(&failed),
+            (Some(StopReason::Error), Some("Internal error (code -32603)".to_string()))
+        );
+        assert_eq!(prompt_outcome(&response(None, None)), (None, None));
+    }
 
     #[test]
     fn test_session_mode_id() {
@@ -1020,4 +1385,54 @@ mod tests {
         let id = ModelId::new("claude-3-opus");
         assert_eq!(id.as_str(), "claude-3-opus");
     }
+
+    fn text_chunk(session_id: &str, text: &str) -> SessionUpdateNotification {
+        SessionUpdateNotification {
+            session_id: session_id.to_string(),
+            update: SessionUpdate::AgentMessageChunk {
+                content: ContentBlock::Text { text: text.to_string() },
+            },
+        }
+    }
+
+    fn text_of(notification: &SessionUpdateNotification) -> &str {
+        match &notification.update {
+            SessionUpdate::AgentMessageChunk { content: ContentBlock::Text { text } } => text,
+            _ => panic!("not a text chunk"),
+        }
+    }
+
+    #[test]
+    fn test_coalescer_merges_consecutive_chunks() {
+        let mut coalescer = ChunkCoalescer::new(Duration::from_millis(16));
+        let now = Instant::now();
+
+        assert!(coalescer.push(text_chunk("s1", "Hel"), now).is_empty());
+        assert!(coalescer.push(text_chunk("s1", "lo"), now).is_empty());
+        assert_eq!(coalescer.deadline(), Some(now + Duration::from_millis(16)));
+
+        // Another session's chunk releases the merged one and is held in turn
+        let ready = coalescer.push(text_chunk("s2", "Hi"), now);
+        assert_eq!(ready.len(), 1);
+        assert_eq!(text_of(&ready[0]), "Hello");
+
+        // Anything else releases the held chunk ahead of itself
+        let plan = SessionUpdateNotification {
+            session_id: "s2".to_string(),
+            update: SessionUpdate::Plan { entries: Vec::new() },
+        };
+        let ready = coalescer.push(plan, now);
+        assert_eq!(ready.len(), 2);
+        assert_eq!(text_of(&ready[0]), "Hi");
+        assert!(matches!(ready[1].update, SessionUpdate::Plan { .. }));
+        assert!(coalescer.deadline().is_none());
+    }
+
+    #[test]
+    fn test_coalescer_disabled_with_zero_window() {
+        let mut coalescer = ChunkCoalescer::new(Duration::ZERO);
+        let ready = coalescer.push(text_chunk("s1", "Hi"), Instant::now());
+        assert_eq!(ready.len(), 1);
+        assert!(coalescer.flush().is_none());
+    }
 }
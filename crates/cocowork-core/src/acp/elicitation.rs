@@ -0,0 +1,110 @@
+//! Questions agents ask the user mid-turn
+//!
+//! An agent sends `elicitation/create` with a message and, optionally, a JSON
+//! schema for the reply. The agent's turn waits while the question is shown;
+//! the user's reply goes back as the content of an `accept`, and a question
+//! dismissed without a reply as a `decline`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::oneshot;
+
+/// Field the reply is sent in when the schema doesn't name one
+const DEFAULT_FIELD: &str = "answer";
+
+/// elicitation/create request from agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ElicitationParams {
+    pub session_id: String,
+    pub message: String,
+    /// JSON schema of the object the agent expects back
+    #[serde(default)]
+    pub requested_schema: Option<Value>,
+}
+
+/// What the user did with the question
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ElicitationAction {
+    Accept,
+    Decline,
+    Cancel,
+}
+
+/// elicitation/create response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ElicitationResponse {
+    pub action: ElicitationAction,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<Value>,
+}
+
+impl ElicitationResponse {
+    /// Reply to a question asked with `schema`; `None` declines it
+    ///
+    /// The reply is sent as the schema's first string property, or as
+    /// `answer` when it has none.
+    pub fn from_reply(schema: Option<&Value>, reply: Option<String>) -> Self {
+        let Some(reply) = reply else {
+            return Self { action: ElicitationAction::Decline, content: None };
+        };
+        let field = schema
+            .and_then(|schema| schema.get("properties"))
+            .and_then(Value::as_object)
+            .and_then(|properties| {
+                properties
+                    .iter()
+                    .find(|(_, property)| property.get("type").and_then(Value::as_str) == Some("string"))
+            })
+            .map(|(name, _)| name.as_str())
+            .unwrap_or(DEFAULT_FIELD);
+        Self {
+            action: ElicitationAction::Accept,
+            content: Some(serde_json::json!({ field: reply })),
+        }
+    }
+}
+
+/// A question from an agent waiting for the user's reply
+#[derive(Debug)]
+pub struct AgentQuestion {
+    /// Session whose turn is waiting
+    pub session_id: String,
+    pub message: String,
+    /// Channel used to deliver the reply
+    pub responder: oneshot::Sender<Option<String>>,
+}
+
+impl AgentQuestion {
+    /// Deliver the user's reply, or `None` if they dismissed the question;
+    /// a dropped receiver is ignored
+    pub fn reply(self, reply: Option<String>) {
+        let _ = self.responder.send(reply);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_reply_content() {
+        let declined = ElicitationResponse::from_reply(None, None);
+        assert_eq!(serde_json::to_value(&declined).unwrap(), json!({ "action": "decline" }));
+
+        let plain = ElicitationResponse::from_reply(None, Some("main".to_string()));
+        assert_eq!(
+            serde_json::to_value(&plain).unwrap(),
+            json!({ "action": "accept", "content": { "answer": "main" } })
+        );
+
+        let schema = json!({
+            "type": "object",
+            "properties": { "confirm": { "type": "boolean" }, "branch": { "type": "string" } },
+        });
+        let named = ElicitationResponse::from_reply(Some(&schema), Some("main".to_string()));
+        assert_eq!(named.content, Some(json!({ "branch": "main" })));
+    }
+}
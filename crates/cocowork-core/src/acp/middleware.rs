@@ -0,0 +1,312 @@
+//! Hooks on the messages of a connection
+//!
+//! A [`MessageMiddleware`] sees every JSON-RPC message between the client
+//! and the agent, and may change it before it goes on:
+//!
+//! - requests and notifications to the agent, before they're written
+//! - messages from the agent, before they're parsed
+//! - responses to the agent's own requests, before they're written
+//!
+//! Middlewares run in the order they were added. The stack is shared by the
+//! connection and whoever built it, so one added after connecting applies
+//! from the next message on.
+
+use crate::redaction::Redactor;
+use crate::types::{JsonRpcRequest, JsonRpcResponse};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use tracing::warn;
+
+/// Sees and may rewrite the messages of a connection
+///
+/// Middlewares are called from the connection's reading task and from
+/// whichever task sends a request, so state belongs behind a lock.
+pub trait MessageMiddleware: Send + Sync {
+    /// Identifies the middleware, e.g. to remove it again
+    fn name(&self) -> &str;
+
+    fn on_outgoing(&self, _request: &mut JsonRpcRequest) {}
+
+    fn on_incoming(&self, _message: &mut serde_json::Value) {}
+
+    fn on_response(&self, _response: &mut JsonRpcResponse) {}
+}
+
+/// The middlewares of a connection, in the order they run
+#[derive(Clone, Default)]
+pub struct MiddlewareStack {
+    layers: Arc<RwLock<Vec<Arc<dyn MessageMiddleware>>>>,
+}
+
+impl MiddlewareStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a middleware after the others, replacing one with the same name
+    pub fn push(&self, middleware: Arc<dyn MessageMiddleware>) {
+        let mut layers = self.layers.write().unwrap_or_else(|e| e.into_inner());
+        layers.retain(|layer| layer.name() != middleware.name());
+        layers.push(middleware);
+    }
+
+    /// Remove the middleware called `name`, returning whether there was one
+    pub fn remove(&self, name: &str) -> bool {
+        let mut layers = self.layers.write().unwrap_or_else(|e| e.into_inner());
+        let before = layers.len();
+        layers.retain(|layer| layer.name() != name);
+        layers.len() != before
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.layers().iter().map(|layer| layer.name().to_string()).collect()
+    }
+
+    pub fn outgoing(&self, request: &mut JsonRpcRequest) {
+        self.layers().iter().for_each(|layer| layer.on_outgoing(request));
+    }
+
+    pub fn incoming(&self, message: &mut serde_json::Value) {
+        self.layers().iter().for_each(|layer| layer.on_incoming(message));
+    }
+
+    pub fn response(&self, response: &mut JsonRpcResponse) {
+        self.layers().iter().for_each(|layer| layer.on_response(response));
+    }
+
+    /// A snapshot, so a middleware may change the stack without deadlocking
+    fn layers(&self) -> Vec<Arc<dyn MessageMiddleware>> {
+        self.layers.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+impl std::fmt::Debug for MiddlewareStack {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("MiddlewareStack").field(&self.names()).finish()
+    }
+}
+
+/// Appends every message to a JSON Lines file, for debugging an agent's side of the protocol
+///
+/// Each line is `{"at": ..., "direction": "out" | "in" | "response", "message": ...}`,
+/// with secrets the built-in patterns find masked.
+pub struct ProtocolLog {
+    path: PathBuf,
+    file: Mutex<Option<File>>,
+}
+
+impl ProtocolLog {
+    pub const NAME: &'static str = "protocol_log";
+
+    /// Log to `path`, created with its directory on the first message
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into(), file: Mutex::new(None) }
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    fn append(&self, direction: &str, message: serde_json::Value) {
+        let line = serde_json::json!({
+            "at": chrono::Utc::now().to_rfc3339(),
+            "direction": direction,
+            "message": message,
+        });
+        let line = Redactor::new().redact(&line.to_string());
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        if file.is_none() {
+            if let Some(dir) = self.path.parent() {
+                let _ = std::fs::create_dir_all(dir);
+            }
+            match OpenOptions::new().create(true).append(true).open(&self.path) {
+                Ok(opened) => *file = Some(opened),
+                Err(e) => {
+                    warn!("Can't open protocol log {:?}: {}", self.path, e);
+                    return;
+                }
+            }
+        }
+        if let Some(file) = file.as_mut() {
+            if let Err(e) = writeln!(file, "{}", line) {
+                warn!("Failed to write protocol log: {}", e);
+            }
+        }
+    }
+}
+
+impl MessageMiddleware for ProtocolLog {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn on_outgoing(&self, request: &mut JsonRpcRequest) {
+        self.append("out", serde_json::to_value(&*request).unwrap_or_default());
+    }
+
+    fn on_incoming(&self, message: &mut serde_json::Value) {
+        self.append("in", message.clone());
+    }
+
+    fn on_response(&self, response: &mut JsonRpcResponse) {
+        self.append("response", serde_json::to_value(&*response).unwrap_or_default());
+    }
+}
+
+/// Masks secrets in prompts and in terminal output sent back to the agent
+pub struct SecretRedaction {
+    redactor: RwLock<Redactor>,
+    skip_next_prompt: AtomicBool,
+}
+
+impl SecretRedaction {
+    pub const NAME: &'static str = "secret_redaction";
+
+    pub fn new(redactor: Redactor) -> Self {
+        Self { redactor: RwLock::new(redactor), skip_next_prompt: AtomicBool::new(false) }
+    }
+
+    /// Mask with `redactor` from the next message on, e.g. after the user's patterns change
+    pub fn set_redactor(&self, redactor: Redactor) {
+        *self.redactor.write().unwrap_or_else(|e| e.into_inner()) = redactor;
+    }
+
+    pub fn redactor(&self) -> Redactor {
+        self.redactor.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Send the next prompt as it is, for when the user overrides the redaction
+    pub fn skip_next_prompt(&self) {
+        self.skip_next_prompt.store(true, Ordering::SeqCst);
+    }
+}
+
+impl MessageMiddleware for SecretRedaction {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn on_outgoing(&self, request: &mut JsonRpcRequest) {
+        if request.method != "session/prompt" || self.skip_next_prompt.swap(false, Ordering::SeqCst) {
+            return;
+        }
+        let Some(blocks) = request.params.as_mut().and_then(|params| params.get_mut("prompt")) else {
+            return;
+        };
+        let redactor = self.redactor();
+        // Text and embedded files; images and links are left alone
+        for block in blocks.as_array_mut().into_iter().flatten() {
+            for pointer in ["/text", "/resource/text"] {
+                if let Some(serde_json::Value::String(text)) = block.pointer_mut(pointer) {
+                    *text = redactor.redact(text);
+                }
+            }
+        }
+    }
+
+    fn on_response(&self, response: &mut JsonRpcResponse) {
+        let Some(result) = response.result.as_mut().and_then(|result| result.as_object_mut()) else {
+            return;
+        };
+        let redactor = self.redactor();
+        for key in ["stdout", "stderr", "output"] {
+            if let Some(serde_json::Value::String(output)) = result.get_mut(key) {
+                *output = redactor.redact(output);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hides file contents the client sends back
+    struct HideContent;
+
+    impl MessageMiddleware for HideContent {
+        fn name(&self) -> &str {
+            "hide_content"
+        }
+
+        fn on_response(&self, response: &mut JsonRpcResponse) {
+            if let Some(content) = response.result.as_mut().and_then(|result| result.get_mut("content")) {
+                *content = serde_json::json!("[hidden]");
+            }
+        }
+    }
+
+    #[test]
+    fn test_stack_runs_layers_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_path = dir.path().join("logs").join("acp.jsonl");
+        let stack = MiddlewareStack::new();
+        stack.push(Arc::new(HideContent));
+        stack.push(Arc::new(ProtocolLog::new(&log_path)));
+        assert_eq!(stack.names(), ["hide_content", "protocol_log"]);
+
+        let mut request = JsonRpcRequest::new(1, "session/prompt", None);
+        stack.outgoing(&mut request);
+        let mut response = JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            id: Some(serde_json::json!(7)),
+            result: Some(serde_json::json!({ "content": "secret" })),
+            error: None,
+        };
+        stack.response(&mut response);
+        assert_eq!(response.result.unwrap()["content"], "[hidden]");
+
+        // The log comes after the redaction, so it never sees the content
+        let log = std::fs::read_to_string(&log_path).unwrap();
+        let lines: Vec<serde_json::Value> = log.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["direction"], "out");
+        assert_eq!(lines[0]["message"]["method"], "session/prompt");
+        assert_eq!(lines[1]["message"]["result"]["content"], "[hidden]");
+
+        assert!(stack.remove(ProtocolLog::NAME));
+        assert!(!stack.remove(ProtocolLog::NAME));
+        assert_eq!(stack.names(), ["hide_content"]);
+    }
+
+    #[test]
+    fn test_secret_redaction() {
+        let redaction = SecretRedaction::new(Redactor::new());
+        let prompt = |text: &str| {
+            let blocks = serde_json::json!({ "sessionId": "s1", "prompt": [
+                { "type": "text", "text": text },
+                { "type": "resource", "resource": { "uri": "file:///w/.env", "text": "API_KEY=abc123" } },
+            ] });
+            JsonRpcRequest::new(1, "session/prompt", Some(blocks))
+        };
+
+        let mut request = prompt("use sk-abcdefghijklmnop1234");
+        redaction.on_outgoing(&mut request);
+        let params = request.params.unwrap();
+        assert_eq!(params["prompt"][0]["text"], "use [redacted]");
+        assert_eq!(params["prompt"][1]["resource"]["text"], "API_KEY=[redacted]");
+        assert_eq!(params["prompt"][1]["resource"]["uri"], "file:///w/.env");
+
+        // Overridden for one prompt only
+        redaction.skip_next_prompt();
+        let mut request = prompt("use sk-abcdefghijklmnop1234");
+        redaction.on_outgoing(&mut request);
+        assert_eq!(request.params.unwrap()["prompt"][0]["text"], "use sk-abcdefghijklmnop1234");
+        let mut request = prompt("use sk-abcdefghijklmnop1234");
+        redaction.on_outgoing(&mut request);
+        assert_eq!(request.params.unwrap()["prompt"][0]["text"], "use [redacted]");
+
+        redaction.set_redactor(Redactor::new().with_patterns(&["hunter[0-9]".to_string()]).unwrap());
+        let mut response = JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            id: Some(serde_json::json!(7)),
+            result: Some(serde_json::json!({ "exitCode": 0, "stdout": "pw hunter2\n", "stderr": "" })),
+            error: None,
+        };
+        redaction.on_response(&mut response);
+        assert_eq!(response.result.unwrap()["stdout"], "pw [redacted]\n");
+    }
+}
@@ -14,7 +14,11 @@
 //! The main implementation is `AcpConnection` which implements `AgentConnection`.
 
 mod client_delegate;
+mod compat;
 mod connection;
+mod elicitation;
+mod middleware;
+mod permission;
 mod protocol;
 mod runtime;
 mod session;
@@ -23,15 +27,22 @@ mod transport;
 
 // Re-export core traits
 pub use traits::{
-    AgentClient, AgentConnection, AgentServer, AgentServerCommand, ConfigOptionId,
+    AgentClient, AgentConnection, AgentServer, AgentServerCommand, AttachmentMode, ConfigOptionId,
     ConfigValueType, LoadSessionResponse, ModelId, NewSessionResponse, PromptMessage,
     PromptResult, SessionConfigOption, SessionInfo, SessionMode, SessionModeId, SessionModel,
-    SessionNotification,
+    SessionNotification, MAX_INLINE_ATTACHMENT_SIZE,
 };
 
 // Re-export implementations
 pub use client_delegate::AgentClientDelegate;
-pub use connection::AcpConnection;
+pub use compat::ProtocolDialect;
+pub use connection::{AcpConnection, NotificationSettings};
+pub use elicitation::{AgentQuestion, ElicitationAction, ElicitationParams, ElicitationResponse};
+pub use middleware::{MessageMiddleware, MiddlewareStack, ProtocolLog, SecretRedaction};
+pub use permission::{
+    PermissionContent, PermissionLocation, PermissionOption, PermissionOptionKind, PermissionOutcome,
+    PermissionPreview, PermissionToolCall, RequestPermissionParams, RequestPermissionResponse, ToolPermissionRequest,
+};
 pub use protocol::{AcpMessage, ProtocolHandler};
 pub use runtime::{spawn_runtime_tasks_headless, spawn_runtime_tasks_with_ui, AcpChannels};
 pub use session::{Session, SessionManager};
@@ -0,0 +1,304 @@
+//! Permission requests agents make before running a tool
+//!
+//! An agent sends `session/request_permission` with the tool call it wants to
+//! make and the choices it offers (allow once, always allow, reject...). The
+//! tool call can carry the proposed edit as old and new text, or the command
+//! in its raw input; [`PermissionPreview`]s turn these into something to show
+//! next to the choices. The reply names the option picked, or says the
+//! request was cancelled.
+
+use crate::sandbox::diff::DEFAULT_CONTEXT_LINES;
+use crate::sandbox::{compute_file_diff, FileOperation};
+use crate::types::{ContentBlock, FileDiff, ToolCallKind};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::oneshot;
+
+/// Kind of a choice the agent offers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionOptionKind {
+    AllowOnce,
+    AllowAlways,
+    RejectOnce,
+    RejectAlways,
+}
+
+impl PermissionOptionKind {
+    pub fn is_allow(&self) -> bool {
+        matches!(self, PermissionOptionKind::AllowOnce | PermissionOptionKind::AllowAlways)
+    }
+}
+
+/// A choice the agent offers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionOption {
+    pub option_id: String,
+    pub name: String,
+    pub kind: PermissionOptionKind,
+}
+
+/// Proposed content of the tool call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PermissionContent {
+    /// An edit: the file's text before and after; no old text for a new file
+    #[serde(rename_all = "camelCase")]
+    Diff {
+        path: String,
+        #[serde(default)]
+        old_text: Option<String>,
+        new_text: String,
+    },
+    Content { content: ContentBlock },
+    #[serde(other)]
+    Other,
+}
+
+/// A file the tool call touches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PermissionLocation {
+    pub path: String,
+}
+
+/// The tool call permission is asked for
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionToolCall {
+    pub tool_call_id: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub kind: Option<ToolCallKind>,
+    #[serde(default)]
+    pub content: Vec<PermissionContent>,
+    #[serde(default)]
+    pub locations: Vec<PermissionLocation>,
+    /// The tool's input as the agent sent it
+    #[serde(default)]
+    pub raw_input: Option<Value>,
+}
+
+/// session/request_permission request from agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestPermissionParams {
+    pub session_id: String,
+    pub tool_call: PermissionToolCall,
+    pub options: Vec<PermissionOption>,
+}
+
+/// What came of a permission request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum PermissionOutcome {
+    Selected {
+        #[serde(rename = "optionId")]
+        option_id: String,
+    },
+    Cancelled,
+}
+
+/// session/request_permission response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestPermissionResponse {
+    pub outcome: PermissionOutcome,
+}
+
+/// Something to show the user about a tool call
+#[derive(Debug, Clone)]
+pub enum PermissionPreview {
+    /// The edit the call would make
+    Diff(FileDiff),
+    /// The command line the call would run
+    Command(String),
+    Text(String),
+}
+
+impl RequestPermissionParams {
+    /// File operation the tool call amounts to, for the tool policy
+    pub fn operation(&self) -> FileOperation {
+        match self.tool_call.kind {
+            Some(ToolCallKind::Write | ToolCallKind::Edit | ToolCallKind::Create) => FileOperation::Write,
+            Some(ToolCallKind::Delete) => FileOperation::Delete,
+            Some(ToolCallKind::Move) => FileOperation::Move,
+            Some(ToolCallKind::Execute | ToolCallKind::Terminal | ToolCallKind::Bash) => FileOperation::Execute,
+            _ if self.previews().iter().any(|p| matches!(p, PermissionPreview::Diff(_))) => FileOperation::Write,
+            _ => FileOperation::Read,
+        }
+    }
+
+    /// What the tool call acts on: its first file, otherwise its title
+    pub fn resource(&self) -> String {
+        let call = &self.tool_call;
+        call.locations
+            .first()
+            .map(|location| location.path.clone())
+            .or_else(|| {
+                call.content.iter().find_map(|content| match content {
+                    PermissionContent::Diff { path, .. } => Some(path.clone()),
+                    _ => None,
+                })
+            })
+            .or_else(|| call.title.clone())
+            .unwrap_or_else(|| call.tool_call_id.clone())
+    }
+
+    /// Every file the tool call touches: its locations, then the files its
+    /// diffs edit
+    pub fn paths(&self) -> Vec<String> {
+        let call = &self.tool_call;
+        let mut paths: Vec<String> = call.locations.iter().map(|location| location.path.clone()).collect();
+        for content in &call.content {
+            if let PermissionContent::Diff { path, .. } = content {
+                if !paths.contains(path) {
+                    paths.push(path.clone());
+                }
+            }
+        }
+        paths
+    }
+
+    /// The edits, command and text the tool call carries, in that order
+    pub fn previews(&self) -> Vec<PermissionPreview> {
+        let mut diffs = Vec::new();
+        let mut texts = Vec::new();
+        for content in &self.tool_call.content {
+            match content {
+                PermissionContent::Diff { path, old_text, new_text } => diffs.push(PermissionPreview::Diff(
+                    compute_file_diff(path, old_text.as_deref().unwrap_or(""), new_text, DEFAULT_CONTEXT_LINES),
+                )),
+                PermissionContent::Content { content: ContentBlock::Text { text } } if !text.trim().is_empty() => {
+                    texts.push(PermissionPreview::Text(text.clone()))
+                }
+                _ => {}
+            }
+        }
+        diffs.extend(self.command_line().map(PermissionPreview::Command));
+        diffs.extend(texts);
+        diffs
+    }
+
+    /// The command in the raw input, as a string or a list of words
+    pub fn command_line(&self) -> Option<String> {
+        let command = self.tool_call.raw_input.as_ref()?.get("command")?;
+        match command {
+            Value::String(line) => Some(line.clone()),
+            Value::Array(words) => {
+                let words: Vec<&str> = words.iter().filter_map(Value::as_str).collect();
+                (!words.is_empty()).then(|| words.join(" "))
+            }
+            _ => None,
+        }
+    }
+
+    /// The first offered option that allows (or rejects) the call, preferring
+    /// the one-off choices
+    pub fn option_to(&self, allow: bool) -> Option<&PermissionOption> {
+        let (once, always) = if allow {
+            (PermissionOptionKind::AllowOnce, PermissionOptionKind::AllowAlways)
+        } else {
+            (PermissionOptionKind::RejectOnce, PermissionOptionKind::RejectAlways)
+        };
+        self.options
+            .iter()
+            .find(|option| option.kind == once)
+            .or_else(|| self.options.iter().find(|option| option.kind == always))
+    }
+
+    /// Outcome of deciding to allow or reject the call without asking
+    pub fn decide(&self, allow: bool) -> PermissionOutcome {
+        match self.option_to(allow) {
+            Some(option) => PermissionOutcome::Selected { option_id: option.option_id.clone() },
+            None => PermissionOutcome::Cancelled,
+        }
+    }
+}
+
+/// A permission request waiting for the user to pick one of the options
+#[derive(Debug)]
+pub struct ToolPermissionRequest {
+    /// Session whose turn is waiting
+    pub session_id: String,
+    pub title: String,
+    pub operation: FileOperation,
+    pub previews: Vec<PermissionPreview>,
+    pub options: Vec<PermissionOption>,
+    /// Channel used to deliver the id of the option picked
+    pub responder: oneshot::Sender<Option<String>>,
+}
+
+impl ToolPermissionRequest {
+    /// Deliver the option picked, or `None` if the request was dismissed;
+    /// a dropped receiver is ignored
+    pub fn choose(self, option_id: Option<String>) {
+        let _ = self.responder.send(option_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn params(tool_call: Value) -> RequestPermissionParams {
+        serde_json::from_value(json!({
+            "sessionId": "s1",
+            "toolCall": tool_call,
+            "options": [
+                { "optionId": "always", "name": "Always allow", "kind": "allow_always" },
+                { "optionId": "once", "name": "Allow", "kind": "allow_once" },
+                { "optionId": "no", "name": "Reject", "kind": "reject_once" },
+            ],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_edit_previews() {
+        let request = params(json!({
+            "toolCallId": "call-1",
+            "title": "Edit main.rs",
+            "kind": "edit",
+            "content": [
+                { "type": "diff", "path": "/w/main.rs", "oldText": "a\nb\n", "newText": "a\nc\n" },
+                { "type": "terminal", "terminalId": "t1" },
+            ],
+        }));
+        assert_eq!(request.operation(), FileOperation::Write);
+        assert_eq!(request.resource(), "/w/main.rs");
+        match &request.previews()[..] {
+            [PermissionPreview::Diff(diff)] => assert_eq!(diff.path, "/w/main.rs"),
+            other => panic!("unexpected previews: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_command_preview() {
+        let request = params(json!({
+            "toolCallId": "call-2",
+            "title": "Run tests",
+            "kind": "execute",
+            "rawInput": { "command": ["cargo", "test"] },
+        }));
+        assert_eq!(request.operation(), FileOperation::Execute);
+        assert_eq!(request.resource(), "Run tests");
+        assert!(matches!(&request.previews()[..], [PermissionPreview::Command(line)] if line == "cargo test"));
+    }
+
+    #[test]
+    fn test_decide_prefers_one_off_options() {
+        let request = params(json!({ "toolCallId": "call-3" }));
+        assert_eq!(request.decide(true), PermissionOutcome::Selected { option_id: "once".to_string() });
+        assert_eq!(request.decide(false), PermissionOutcome::Selected { option_id: "no".to_string() });
+
+        let response = RequestPermissionResponse { outcome: PermissionOutcome::Cancelled };
+        assert_eq!(serde_json::to_value(&response).unwrap(), json!({ "outcome": { "outcome": "cancelled" } }));
+        let response = RequestPermissionResponse { outcome: request.decide(true) };
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            json!({ "outcome": { "outcome": "selected", "optionId": "once" } })
+        );
+    }
+}
@@ -1,24 +1,35 @@
 //! ACP Protocol message handling
 
+use super::compat::ProtocolDialect;
+use super::elicitation::ElicitationResponse;
+use super::permission::{PermissionOutcome, RequestPermissionResponse};
 use crate::error::{AcpError, Error, Result};
+use crate::sandbox::TerminalOutput;
 use crate::types::{
     ContentBlock, FileMetadata, InitializeParams, InitializeResult, JsonRpcError, JsonRpcRequest,
     JsonRpcResponse, McpServerConfig, SessionNewParams, SessionNewResult, SessionNewResultExtended,
     SessionLoadResult, SessionListResult, SessionPromptParams, SessionUpdateNotification,
-    TerminalExecuteResult, ACP_PROTOCOL_VERSION, ClientCapabilities, ClientInfo,
+    TerminalExecuteResult, ACP_PROTOCOL_VERSION, AUTH_REQUIRED_ERROR_CODE, ClientCapabilities,
+    ClientInfo,
 };
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use tracing::{debug, trace, warn};
 
 /// Protocol handler for ACP messages
+///
+/// Requests are shaped for the protocol version agreed in `initialize`,
+/// the newest one until then.
 pub struct ProtocolHandler {
     request_id: AtomicU64,
+    /// Version of the dialect in use
+    protocol_version: AtomicU32,
 }
 
 impl ProtocolHandler {
     pub fn new() -> Self {
         Self {
             request_id: AtomicU64::new(1),
+            protocol_version: AtomicU32::new(ProtocolDialect::default().version()),
         }
     }
 
@@ -27,6 +38,11 @@ impl ProtocolHandler {
         self.request_id.fetch_add(1, Ordering::SeqCst)
     }
 
+    /// The protocol revision requests are made in
+    pub fn dialect(&self) -> ProtocolDialect {
+        ProtocolDialect::negotiate(self.protocol_version.load(Ordering::SeqCst))
+    }
+
     /// Create initialize request
     pub fn create_initialize_request(
         &self,
@@ -83,12 +99,22 @@ impl ProtocolHandler {
         )
     }
 
-    /// Create session/cancel request
+    /// Create session/cancel message, a notification in dialects that don't answer it
     pub fn create_session_cancel_request(&self, session_id: String) -> JsonRpcRequest {
+        let params = Some(serde_json::json!({ "sessionId": session_id }));
+        if self.dialect().cancel_is_notification() {
+            JsonRpcRequest::notification("session/cancel", params)
+        } else {
+            JsonRpcRequest::new(self.next_id(), "session/cancel", params)
+        }
+    }
+
+    /// Create authenticate request
+    pub fn create_authenticate_request(&self, method_id: String) -> JsonRpcRequest {
         JsonRpcRequest::new(
             self.next_id(),
-            "session/cancel",
-            Some(serde_json::json!({ "sessionId": session_id })),
+            "authenticate",
+            Some(serde_json::json!({ "methodId": method_id })),
         )
     }
 
@@ -110,7 +136,7 @@ impl ProtocolHandler {
         )
     }
 
-    /// Create session/setMode request
+    /// Create session/set_mode request
     pub fn create_session_set_mode_request(
         &self,
         session_id: String,
@@ -118,7 +144,7 @@ impl ProtocolHandler {
     ) -> JsonRpcRequest {
         JsonRpcRequest::new(
             self.next_id(),
-            "session/setMode",
+            self.dialect().set_mode_method(),
             Some(serde_json::json!({
                 "sessionId": session_id,
                 "modeId": mode_id,
@@ -126,7 +152,7 @@ impl ProtocolHandler {
         )
     }
 
-    /// Create session/setModel request
+    /// Create session/set_model request
     pub fn create_session_set_model_request(
         &self,
         session_id: String,
@@ -134,7 +160,7 @@ impl ProtocolHandler {
     ) -> JsonRpcRequest {
         JsonRpcRequest::new(
             self.next_id(),
-            "session/setModel",
+            self.dialect().set_model_method(),
             Some(serde_json::json!({
                 "sessionId": session_id,
                 "modelId": model_id,
@@ -142,7 +168,7 @@ impl ProtocolHandler {
         )
     }
 
-    /// Create session/setConfig request
+    /// Create session/set_config_option request
     pub fn create_session_set_config_request(
         &self,
         session_id: String,
@@ -151,7 +177,7 @@ impl ProtocolHandler {
     ) -> JsonRpcRequest {
         JsonRpcRequest::new(
             self.next_id(),
-            "session/setConfig",
+            self.dialect().set_config_method(),
             Some(serde_json::json!({
                 "sessionId": session_id,
                 "configId": config_id,
@@ -185,16 +211,13 @@ impl ProtocolHandler {
         )
     }
 
-    /// Parse initialize response
+    /// Parse initialize response, switching to the protocol version the agent chose
     pub fn parse_initialize_response(
         &self,
         response: &JsonRpcResponse,
     ) -> Result<InitializeResult> {
         if let Some(error) = &response.error {
-            return Err(Error::Acp(AcpError::InvalidMessage(format!(
-                "Initialize failed: {} (code {})",
-                error.message, error.code
-            ))));
+            return Err(request_error("Initialize failed", error));
         }
 
         let result = response.result.as_ref().ok_or_else(|| {
@@ -205,13 +228,14 @@ impl ProtocolHandler {
 
         let init_result: InitializeResult = serde_json::from_value(result.clone())?;
 
-        // Verify protocol version
-        if init_result.protocol_version != ACP_PROTOCOL_VERSION {
-            warn!(
-                "Protocol version mismatch: expected {}, got {}",
-                ACP_PROTOCOL_VERSION, init_result.protocol_version
+        let dialect = ProtocolDialect::negotiate(init_result.protocol_version);
+        if dialect.version() != ACP_PROTOCOL_VERSION {
+            debug!(
+                "Agent speaks ACP version {}, using {:?} compatibility",
+                init_result.protocol_version, dialect
             );
         }
+        self.protocol_version.store(dialect.version(), Ordering::SeqCst);
 
         if let Some(ref agent_info) = init_result.agent_info {
             debug!(
@@ -231,10 +255,7 @@ impl ProtocolHandler {
         response: &JsonRpcResponse,
     ) -> Result<SessionNewResult> {
         if let Some(error) = &response.error {
-            return Err(Error::Acp(AcpError::InvalidMessage(format!(
-                "Session creation failed: {} (code {})",
-                error.message, error.code
-            ))));
+            return Err(request_error("Session creation failed", error));
         }
 
         let result = response.result.as_ref().ok_or_else(|| {
@@ -255,10 +276,7 @@ impl ProtocolHandler {
         response: &JsonRpcResponse,
     ) -> Result<SessionNewResultExtended> {
         if let Some(error) = &response.error {
-            return Err(Error::Acp(AcpError::InvalidMessage(format!(
-                "Session creation failed: {} (code {})",
-                error.message, error.code
-            ))));
+            return Err(request_error("Session creation failed", error));
         }
 
         let result = response.result.as_ref().ok_or_else(|| {
@@ -293,10 +311,7 @@ impl ProtocolHandler {
         response: &JsonRpcResponse,
     ) -> Result<SessionLoadResult> {
         if let Some(error) = &response.error {
-            return Err(Error::Acp(AcpError::InvalidMessage(format!(
-                "Session load failed: {} (code {})",
-                error.message, error.code
-            ))));
+            return Err(request_error("Session load failed", error));
         }
 
         let result = response.result.as_ref().ok_or_else(|| {
@@ -321,10 +336,7 @@ impl ProtocolHandler {
         response: &JsonRpcResponse,
     ) -> Result<SessionListResult> {
         if let Some(error) = &response.error {
-            return Err(Error::Acp(AcpError::InvalidMessage(format!(
-                "Session list failed: {} (code {})",
-                error.message, error.code
-            ))));
+            return Err(request_error("Session list failed", error));
         }
 
         let result = response.result.as_ref().ok_or_else(|| {
@@ -342,10 +354,7 @@ impl ProtocolHandler {
     /// Parse void response (for setMode, setModel, setConfig)
     pub fn parse_void_response(&self, response: &JsonRpcResponse) -> Result<()> {
         if let Some(error) = &response.error {
-            return Err(Error::Acp(AcpError::InvalidMessage(format!(
-                "Request failed: {} (code {})",
-                error.message, error.code
-            ))));
+            return Err(request_error("Request failed", error));
         }
         Ok(())
     }
@@ -422,7 +431,7 @@ impl ProtocolHandler {
         }
     }
 
-    /// Create response to agent's fs/write_file request
+    /// Create response to agent's fs/write_text_file request
     pub fn create_fs_write_response(
         &self,
         request_id: serde_json::Value,
@@ -449,7 +458,35 @@ impl ProtocolHandler {
         }
     }
 
-    /// Create response to agent's terminal/execute request
+    /// Create response to agent's elicitation/create request
+    pub fn create_elicitation_response(
+        &self,
+        request_id: serde_json::Value,
+        response: ElicitationResponse,
+    ) -> JsonRpcResponse {
+        JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            id: Some(request_id),
+            result: Some(serde_json::to_value(response).unwrap()),
+            error: None,
+        }
+    }
+
+    /// Create response to agent's session/request_permission request
+    pub fn create_permission_response(
+        &self,
+        request_id: serde_json::Value,
+        outcome: PermissionOutcome,
+    ) -> JsonRpcResponse {
+        JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            id: Some(request_id),
+            result: Some(serde_json::to_value(RequestPermissionResponse { outcome }).unwrap()),
+            error: None,
+        }
+    }
+
+    /// Create response to agent's terminal/create request
     pub fn create_terminal_response(
         &self,
         request_id: serde_json::Value,
@@ -463,6 +500,34 @@ impl ProtocolHandler {
         }
     }
 
+    /// Create response to agent's terminal/open request
+    pub fn create_terminal_open_response(
+        &self,
+        request_id: serde_json::Value,
+        terminal_id: &str,
+    ) -> JsonRpcResponse {
+        JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            id: Some(request_id),
+            result: Some(serde_json::json!({ "terminalId": terminal_id })),
+            error: None,
+        }
+    }
+
+    /// Create response to agent's terminal/output request
+    pub fn create_terminal_output_response(
+        &self,
+        request_id: serde_json::Value,
+        output: TerminalOutput,
+    ) -> JsonRpcResponse {
+        JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            id: Some(request_id),
+            result: Some(serde_json::to_value(output).unwrap()),
+            error: None,
+        }
+    }
+
     /// Create error response
     pub fn create_error_response(
         &self,
@@ -504,6 +569,19 @@ pub enum AcpMessage {
     Unknown(serde_json::Value),
 }
 
+/// Convert a JSON-RPC error response into an error, recognizing auth failures
+pub fn request_error(context: &str, error: &JsonRpcError) -> Error {
+    if error.code == AUTH_REQUIRED_ERROR_CODE
+        || error.message.to_lowercase().contains("authentication required")
+    {
+        return Error::Acp(AcpError::AuthRequired(error.message.clone()));
+    }
+    Error::Acp(AcpError::InvalidMessage(format!(
+        "{}: {} (code {})",
+        context, error.message, error.code
+    )))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -542,6 +620,46 @@ mod tests {
         assert_eq!(params["cwd"], "/home/user");
     }
 
+    #[test]
+    fn test_requests_follow_negotiated_version() {
+        let handler = ProtocolHandler::new();
+        let request = handler.create_session_set_mode_request("s1".to_string(), "code".to_string());
+        assert_eq!(request.method, "session/set_mode");
+        let cancel = handler.create_session_cancel_request("s1".to_string());
+        assert!(cancel.id.is_none());
+
+        let response = JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            id: Some(serde_json::json!(1)),
+            result: Some(serde_json::json!({ "protocolVersion": 0 })),
+            error: None,
+        };
+        handler.parse_initialize_response(&response).unwrap();
+        assert_eq!(handler.dialect(), ProtocolDialect::Legacy);
+        let request = handler.create_session_set_model_request("s1".to_string(), "opus".to_string());
+        assert_eq!(request.method, "session/setModel");
+        let cancel = handler.create_session_cancel_request("s1".to_string());
+        assert_eq!(cancel.method, "session/cancel");
+        assert!(cancel.id.is_some());
+    }
+
+    #[test]
+    fn test_auth_required_error() {
+        let auth = JsonRpcError {
+            code: AUTH_REQUIRED_ERROR_CODE,
+            message: "Authentication required".to_string(),
+            data: None,
+        };
+        assert!(request_error("Session creation failed", &auth).is_auth_required());
+
+        let other = JsonRpcError {
+            code: -32603,
+            message: "Internal error".to_string(),
+            data: None,
+        };
+        assert!(!request_error("Session creation failed", &other).is_auth_required());
+    }
+
     #[test]
     fn test_parse_message_response() {
         let handler = ProtocolHandler::new();
@@ -1,6 +1,6 @@
 //! ACP runtime wiring for session updates and agent tool requests
 
-use super::ProtocolHandler;
+use super::{ProtocolDialect, ProtocolHandler};
 use crate::sandbox::{FileOperation, FileSystemHandler, PermissionManager, TerminalHandler};
 use crate::storage::Storage;
 use crate::types::*;
@@ -141,14 +141,14 @@ async fn handle_agent_request(
     let request_id = request.id.clone().unwrap_or(serde_json::Value::Null);
     let params = request.params.clone().unwrap_or(serde_json::Value::Null);
 
-    match request.method.as_str() {
+    match ProtocolDialect::canonical_agent_method(&request.method) {
         "fs/read_text_file" => {
             let p: FsReadTextFileParams = serde_json::from_value(params)?;
             let pm = permission_manager.read().await;
             let content = FileSystemHandler::read_text_file(&pm, &p.path).await?;
             Ok(protocol.create_fs_read_response(request_id, &content))
         }
-        "fs/write_file" | "fs/write_text_file" => {
+        "fs/write_text_file" => {
             let p: FsWriteFileParams = serde_json::from_value(params)?;
             let pm = permission_manager.read().await;
 
@@ -216,7 +216,7 @@ async fn handle_agent_request(
             FileSystemHandler::create_directory(&pm, &p.path).await?;
             Ok(protocol.create_fs_write_response(request_id))
         }
-        "terminal/execute" | "terminal/create" => {
+        "terminal/create" => {
             let p: TerminalExecuteParams = serde_json::from_value(params)?;
 
             // Validate cwd is inside granted paths when provided.
@@ -126,7 +126,10 @@ impl Session {
                         crate::types::StopReason::EndTurn => TaskStatus::Completed,
                         crate::types::StopReason::Error => TaskStatus::Error,
                         crate::types::StopReason::Cancelled => TaskStatus::Cancelled,
-                        crate::types::StopReason::MaxTokens => TaskStatus::Progressing,
+                        crate::types::StopReason::MaxTokens | crate::types::StopReason::MaxTurnRequests => {
+                            TaskStatus::Progressing
+                        }
+                        crate::types::StopReason::Refusal => TaskStatus::Error,
                     };
                 }
             }
@@ -140,7 +143,8 @@ impl Session {
             StopReason::EndTurn => TaskStatus::Completed,
             StopReason::Cancelled => TaskStatus::Cancelled,
             StopReason::Error => TaskStatus::Error,
-            StopReason::MaxTokens => TaskStatus::Completed,
+            StopReason::MaxTokens | StopReason::MaxTurnRequests => TaskStatus::Completed,
+            StopReason::Refusal => TaskStatus::Error,
         };
         self.state.updated_at = chrono::Utc::now();
     }
@@ -186,6 +190,11 @@ impl Session {
                     .messages
                     .push(MessageBlock::System { content, timestamp });
             }
+            MessageBlock::Summary { content, timestamp } => {
+                self.state
+                    .messages
+                    .push(MessageBlock::Summary { content, timestamp });
+            }
         }
     }
 
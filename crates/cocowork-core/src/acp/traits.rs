@@ -5,13 +5,17 @@
 //! - `AgentConnection` - An active connection to an agent
 //! - `AgentClient` - Callback interface for handling agent requests
 
-use crate::error::Result;
+use super::permission::{PermissionOutcome, RequestPermissionParams};
+use crate::error::{AcpError, Error, Result};
+use crate::sandbox::TerminalOutput;
 use crate::types::{
-    ContentBlock, JsonRpcResponse, McpServerConfig, MessageBlock, SessionUpdateNotification,
+    AgentHandshake, AuthMethod, ContentBlock, EmbeddedResource, JsonRpcResponse, McpServerConfig, MessageBlock,
+    SessionUpdateNotification,
 };
+use super::middleware::MiddlewareStack;
 use async_trait::async_trait;
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::broadcast;
 
@@ -246,6 +250,22 @@ pub struct SessionInfo {
     pub message_count: u32,
 }
 
+/// Attached text files up to this size are embedded in the prompt; bigger
+/// and binary files are sent as links for the agent to read
+pub const MAX_INLINE_ATTACHMENT_SIZE: u64 = 64 * 1024;
+
+/// How attached files go into a prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AttachmentMode {
+    /// Text files up to [`MAX_INLINE_ATTACHMENT_SIZE`] embedded, the rest linked
+    #[default]
+    Embed,
+    /// Text files embedded, cut to at most this many bytes each
+    Truncate(u64),
+    /// Every file linked, for the agent to read if it needs to
+    Link,
+}
+
 /// Prompt message to send to agent
 #[derive(Debug, Clone)]
 pub struct PromptMessage {
@@ -265,6 +285,60 @@ impl PromptMessage {
         self.mode = Some(mode.into());
         self
     }
+
+    /// Send `paths` along as resources
+    pub fn with_attachments(self, paths: &[PathBuf]) -> Self {
+        self.with_attachments_as(paths, AttachmentMode::Embed)
+    }
+
+    /// Send `paths` along as resources, embedded or linked per `mode`
+    pub fn with_attachments_as(mut self, paths: &[PathBuf], mode: AttachmentMode) -> Self {
+        self.content.extend(paths.iter().map(|path| attachment_block(path, mode)));
+        self
+    }
+}
+
+/// A file as prompt content: its text if `mode` embeds it, else a link
+pub fn attachment_block(path: &Path, mode: AttachmentMode) -> ContentBlock {
+    let size = std::fs::metadata(path).ok().map(|metadata| metadata.len());
+    let limit = match mode {
+        AttachmentMode::Embed => size.filter(|size| *size <= MAX_INLINE_ATTACHMENT_SIZE),
+        AttachmentMode::Truncate(limit) => size.map(|size| size.min(limit)),
+        AttachmentMode::Link => None,
+    };
+    let text = limit.and_then(|limit| read_text_prefix(path, limit)).map(|text| match size {
+        Some(size) if size > text.len() as u64 => {
+            format!("{}\n[truncated: first {} of {} bytes]", text, text.len(), size)
+        }
+        _ => text,
+    });
+    match (text, ContentBlock::resource_link(path, size)) {
+        (Some(text), ContentBlock::ResourceLink { uri, mime_type, .. }) => ContentBlock::Resource {
+            resource: EmbeddedResource { uri, mime_type: mime_type.or_else(|| Some("text/plain".to_string())), text },
+        },
+        (_, link) => link,
+    }
+}
+
+/// Up to `limit` bytes of a text file, ending on a character boundary;
+/// `None` for binary files
+fn read_text_prefix(path: &Path, limit: u64) -> Option<String> {
+    use std::io::Read;
+
+    let mut bytes = Vec::new();
+    std::fs::File::open(path).ok()?.take(limit).read_to_end(&mut bytes).ok()?;
+    let text = match String::from_utf8(bytes) {
+        Ok(text) => text,
+        // Cut in the middle of a character
+        Err(e) if e.utf8_error().error_len().is_none() => {
+            let valid = e.utf8_error().valid_up_to();
+            let mut bytes = e.into_bytes();
+            bytes.truncate(valid);
+            String::from_utf8(bytes).ok()?
+        }
+        Err(_) => return None,
+    };
+    (!text.contains('\0')).then_some(text)
 }
 
 /// Prompt response from agent
@@ -356,10 +430,14 @@ pub trait AgentServer: Send + Sync {
     async fn is_available(&self) -> bool;
 
     /// Connect to the agent and return a connection
+    ///
+    /// `extra_env` (e.g. from a workspace env profile) overrides the
+    /// agent's own environment.
     async fn connect(
         &self,
         root_dir: Option<&Path>,
         delegate: Arc<dyn AgentClient>,
+        extra_env: &HashMap<String, String>,
     ) -> Result<Arc<dyn AgentConnection>>;
 }
 
@@ -419,11 +497,35 @@ pub trait AgentConnection: Send + Sync {
     /// Check if connection is still active
     async fn is_running(&self) -> bool;
 
+    /// OS process ID of the agent, for connections that run one
+    fn process_id(&self) -> Option<u32> {
+        None
+    }
+
+    /// Requests sent to the agent that haven't been answered yet
+    fn pending_requests(&self) -> usize {
+        0
+    }
+
     /// Terminate the connection
     async fn terminate(&self) -> Result<()>;
 
     /// Send a raw response to the agent (for handling agent requests)
     async fn send_response(&self, response: JsonRpcResponse) -> Result<()>;
+
+    /// Authentication methods the agent advertised
+    async fn auth_methods(&self) -> Vec<AuthMethod> {
+        Vec::new()
+    }
+
+    /// Protocol version, agent info and capabilities from initialization,
+    /// for connections that went through it
+    async fn handshake(&self) -> Option<AgentHandshake> {
+        None
+    }
+
+    /// Authenticate with one of the advertised methods
+    async fn authenticate(&self, method_id: String) -> Result<()>;
 }
 
 // ============================================================================
@@ -464,6 +566,38 @@ pub trait AgentClient: Send + Sync {
         env: Option<&HashMap<String, String>>,
     ) -> Result<crate::types::TerminalExecuteResult>;
 
+    /// Open a long-lived terminal the agent can write to, returning its id
+    async fn open_terminal(
+        &self,
+        _session_id: &str,
+        _command: &str,
+        _args: &[String],
+        _cwd: Option<&str>,
+        _env: Option<&HashMap<String, String>>,
+    ) -> Result<String> {
+        Err(interactive_terminals_unsupported())
+    }
+
+    /// Write to an open terminal's stdin
+    async fn write_terminal(&self, _session_id: &str, _terminal_id: &str, _data: &str) -> Result<()> {
+        Err(interactive_terminals_unsupported())
+    }
+
+    /// What an open terminal has printed so far, and its exit code once it has exited
+    async fn read_terminal(&self, _session_id: &str, _terminal_id: &str) -> Result<TerminalOutput> {
+        Err(interactive_terminals_unsupported())
+    }
+
+    /// Kill an open terminal's process, keeping its output readable
+    async fn kill_terminal(&self, _session_id: &str, _terminal_id: &str) -> Result<()> {
+        Err(interactive_terminals_unsupported())
+    }
+
+    /// Kill an open terminal and forget it
+    async fn release_terminal(&self, _session_id: &str, _terminal_id: &str) -> Result<()> {
+        Err(interactive_terminals_unsupported())
+    }
+
     /// Request permission for an operation
     async fn request_permission(
         &self,
@@ -472,8 +606,37 @@ pub trait AgentClient: Send + Sync {
         resource: &str,
     ) -> Result<bool>;
 
+    /// Pick one of the options an agent offers for a tool call
+    ///
+    /// By default the call is allowed or rejected as [`request_permission`]
+    /// decides for its file operation.
+    ///
+    /// [`request_permission`]: AgentClient::request_permission
+    async fn request_tool_permission(&self, params: &RequestPermissionParams) -> Result<PermissionOutcome> {
+        let allowed = self
+            .request_permission(&params.session_id, params.operation().as_str(), &params.resource())
+            .await?;
+        Ok(params.decide(allowed))
+    }
+
+    /// Ask the user a question for the agent and wait for the reply
+    ///
+    /// `None` means the question was dismissed without a reply.
+    async fn ask_user(&self, _session_id: &str, _message: &str) -> Result<Option<String>> {
+        Ok(None)
+    }
+
     /// Handle a session notification (for forwarding to UI)
     async fn on_session_notification(&self, notification: SessionNotification) -> Result<()>;
+
+    /// Middlewares to run on the messages of connections using this client
+    fn middleware(&self) -> MiddlewareStack {
+        MiddlewareStack::default()
+    }
+}
+
+fn interactive_terminals_unsupported() -> Error {
+    Error::Acp(AcpError::CapabilityNotSupported("interactive terminals".to_string()))
 }
 
 // ============================================================================
@@ -523,4 +686,61 @@ mod tests {
 
         assert_eq!(msg.mode.unwrap().as_str(), "code");
     }
+
+    #[test]
+    fn test_prompt_attachments() {
+        let dir = tempfile::tempdir().unwrap();
+        let small = dir.path().join("notes.md");
+        std::fs::write(&small, "# Notes").unwrap();
+        let big = dir.path().join("big log.txt");
+        std::fs::write(&big, "x".repeat(MAX_INLINE_ATTACHMENT_SIZE as usize + 1)).unwrap();
+        let binary = dir.path().join("data.bin");
+        std::fs::write(&binary, [0u8, 159, 146, 150]).unwrap();
+
+        let msg = PromptMessage::new(Vec::new()).with_attachments(&[small.clone(), big, binary]);
+        match &msg.content[0] {
+            ContentBlock::Resource { resource } => {
+                assert_eq!(resource.uri, crate::types::file_uri(&small));
+                assert_eq!(resource.mime_type.as_deref(), Some("text/markdown"));
+                assert_eq!(resource.text, "# Notes");
+            }
+            other => panic!("expected an embedded resource, got {:?}", other),
+        }
+        match &msg.content[1] {
+            ContentBlock::ResourceLink { uri, name, size, .. } => {
+                assert!(uri.starts_with("file://") && uri.ends_with("/big%20log.txt"));
+                assert_eq!(name, "big log.txt");
+                assert_eq!(*size, Some(MAX_INLINE_ATTACHMENT_SIZE + 1));
+            }
+            other => panic!("expected a resource link, got {:?}", other),
+        }
+        assert!(matches!(&msg.content[2], ContentBlock::ResourceLink { .. }));
+
+        let json = serde_json::to_value(&msg.content[0]).unwrap();
+        assert_eq!(json["type"], "resource");
+        assert_eq!(json["resource"]["mimeType"], "text/markdown");
+        let link = msg.content[0].clone().without_embedded();
+        let json = serde_json::to_value(&link).unwrap();
+        assert_eq!(json["type"], "resource_link");
+        assert_eq!(json["name"], "notes.md");
+        assert_eq!(json["size"], 7);
+    }
+
+    #[test]
+    fn test_attachment_modes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.txt");
+        std::fs::write(&path, "héllo world").unwrap();
+
+        assert!(matches!(attachment_block(&path, AttachmentMode::Link), ContentBlock::ResourceLink { .. }));
+        match attachment_block(&path, AttachmentMode::Truncate(2)) {
+            // The cut doesn't split the two-byte é
+            ContentBlock::Resource { resource } => assert_eq!(resource.text, "h\n[truncated: first 1 of 12 bytes]"),
+            other => panic!("expected an embedded resource, got {:?}", other),
+        }
+        match attachment_block(&path, AttachmentMode::Truncate(100)) {
+            ContentBlock::Resource { resource } => assert_eq!(resource.text, "héllo world"),
+            other => panic!("expected an embedded resource, got {:?}", other),
+        }
+    }
 }
@@ -2,6 +2,7 @@
 
 use crate::error::{AcpError, Error, Result};
 use crate::types::{JsonRpcRequest, JsonRpcResponse};
+use std::path::Path;
 use std::process::Stdio;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command};
@@ -39,7 +40,9 @@ impl Transport {
             .envs(env)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
+            .stderr(Stdio::piped())
+            // A connection dropped without terminate() still stops its agent
+            .kill_on_drop(true);
 
         if let Some(dir) = cwd {
             cmd.current_dir(dir);
@@ -81,7 +84,10 @@ impl Transport {
         let stdout_task = tokio::spawn(Self::read_stdout_task(stdout, stdout_tx));
 
         // Spawn task to drain stderr so the agent can't deadlock on a full pipe.
-        let stderr_task = tokio::spawn(Self::read_stderr_task(stderr));
+        let agent = Path::new(command)
+            .file_stem()
+            .map_or_else(|| command.to_string(), |stem| stem.to_string_lossy().into_owned());
+        let stderr_task = tokio::spawn(Self::read_stderr_task(stderr, agent));
 
         Ok((
             Self {
@@ -146,7 +152,7 @@ impl Transport {
     }
 
     /// Background task to drain stderr.
-    async fn read_stderr_task(stderr: ChildStderr) {
+    async fn read_stderr_task(stderr: ChildStderr, agent: String) {
         let mut reader = BufReader::new(stderr);
         let mut line = String::new();
 
@@ -162,6 +168,7 @@ impl Transport {
                     if !trimmed.is_empty() {
                         // Avoid treating stderr as fatal; agents often log here.
                         warn!("Agent stderr: {}", trimmed);
+                        crate::diagnostics::record_agent_stderr(&agent, trimmed);
                     }
                 }
                 Err(e) => {
@@ -17,9 +17,17 @@
 use crate::acp::traits::{
     AgentClient, AgentConnection, AgentServer, AgentServerCommand, ModelId, SessionModeId,
 };
+use super::auth::AuthAction;
+use super::installer::InstallReporter;
 use crate::acp::AcpConnection;
+use crate::config::AgentPaths;
 use crate::error::Result;
-use crate::types::{AgentConfig, ClientCapabilities, FileSystemCapability, TerminalCapability};
+use crate::net::{self, Downloader, ProxySettings};
+use crate::platform;
+use crate::secrets::{resolve_secret, SecretKey, SecretStore};
+use crate::types::{
+    AgentConfig, ClientCapabilities, EnvProfile, FileSystemCapability, TerminalCapability,
+};
 use async_trait::async_trait;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -62,10 +70,109 @@ pub trait AgentServerAdapter: Send + Sync {
     /// Check if the agent is available (installed)
     async fn is_available(&self) -> bool;
 
+    /// Instructions shown to the user when the agent is not installed
+    fn install_instructions(&self) -> Option<String> {
+        None
+    }
+
+    /// Whether CocoWork can install or update this agent by itself
+    fn can_install(&self) -> bool {
+        false
+    }
+
+    /// Check if the agent's bridge is already installed locally
+    ///
+    /// Unlike `is_available`, this is false for agents that could be
+    /// installed on demand but have not been yet.
+    async fn is_installed(&self) -> bool {
+        self.is_available().await
+    }
+
+    /// Versions of the agent's bridge installed locally, oldest first
+    fn list_installed_versions(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Version the agent is pinned to, if any
+    fn pinned_version(&self) -> Option<String> {
+        None
+    }
+
+    /// Pin the agent to a version, or follow the latest release with `None`
+    fn set_pinned_version(&mut self, version: Option<String>) {
+        let _ = version;
+    }
+
+    /// Use a proxy for the agent's own downloads (bridge installs, updates)
+    fn set_proxy(&mut self, proxy: ProxySettings) {
+        let _ = proxy;
+    }
+
+    /// Installed version to fall back to if the current one misbehaves
+    ///
+    /// This is the newest installed version older than the one in use.
+    fn rollback_target(&self) -> Option<String> {
+        let versions = self.list_installed_versions();
+        let current = self.pinned_version().or_else(|| versions.last().cloned())?;
+        let index = versions.iter().position(|v| *v == current)?;
+        index.checked_sub(1).map(|i| versions[i].clone())
+    }
+
+    /// Install a specific release of the agent's bridge
+    async fn install_version(&self, reporter: &InstallReporter, tag: &str) -> Result<()> {
+        let _ = (reporter, tag);
+        Err(crate::error::Error::Agent(
+            crate::error::AgentError::SetupFailed(format!(
+                "{} does not support installing specific versions",
+                self.name()
+            )),
+        ))
+    }
+
+    /// Install the agent, or update an existing installation when `update`
+    /// is set. Returns the installed version if known.
+    async fn install(&self, reporter: &InstallReporter, update: bool) -> Result<Option<String>> {
+        let _ = (reporter, update);
+        Err(crate::error::Error::Agent(
+            crate::error::AgentError::SetupFailed(format!(
+                "{} cannot be installed automatically",
+                self.name()
+            )),
+        ))
+    }
+
+    /// Ways the user can sign in when the agent reports it needs auth
+    fn auth_actions(&self) -> Vec<AuthAction> {
+        Vec::new()
+    }
+
     /// Get agent configuration
     fn config(&self) -> AgentConfig;
 }
 
+/// Result of probing an adapter's availability
+#[derive(Debug, Clone)]
+pub struct AgentAvailability {
+    pub agent_id: String,
+    pub available: bool,
+    /// Whether the agent's bridge is already installed locally
+    pub installed: bool,
+    /// Whether CocoWork can install the agent itself
+    pub installable: bool,
+    /// How to install the agent when it is missing
+    pub install_instructions: Option<String>,
+    pub checked_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// An agent's own environment with extra variables layered on top
+fn with_extra_env(
+    mut env: HashMap<String, String>,
+    extra_env: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    env.extend(extra_env.iter().map(|(k, v)| (k.clone(), v.clone())));
+    env
+}
+
 // ============================================================================
 // Claude Code Adapter
 // ============================================================================
@@ -89,6 +196,10 @@ pub struct ClaudeCodeAdapter {
     acp_script_path: Option<PathBuf>,
     /// Directory where npm packages are installed
     npm_prefix: Option<PathBuf>,
+    /// Stored API keys, preferred over the environment
+    secrets: Option<Arc<dyn SecretStore>>,
+    /// Proxy passed to npm installs
+    proxy: ProxySettings,
 }
 
 impl ClaudeCodeAdapter {
@@ -107,9 +218,11 @@ impl ClaudeCodeAdapter {
                 created_at: chrono::Utc::now(),
                 updated_at: chrono::Utc::now(),
             },
-            node_path: std::env::var("COCOWORK_NODE_PATH").ok(),
-            acp_script_path: std::env::var("CLAUDE_CODE_ACP_PATH").ok().map(PathBuf::from),
+            node_path: None,
+            acp_script_path: None,
             npm_prefix: Self::default_npm_prefix(),
+            secrets: None,
+            proxy: ProxySettings::default(),
         }
     }
 
@@ -126,19 +239,7 @@ impl ClaudeCodeAdapter {
         }
 
         // Try to find node in PATH
-        if let Ok(output) = tokio::process::Command::new("which")
-            .arg("node")
-            .output()
-            .await
-        {
-            if output.status.success() {
-                if let Ok(path) = String::from_utf8(output.stdout) {
-                    return Some(path.trim().to_string());
-                }
-            }
-        }
-
-        None
+        platform::find_executable("node").map(|path| path.to_string_lossy().to_string())
     }
 
     /// Find the Claude Code ACP bridge script
@@ -164,45 +265,21 @@ impl ClaudeCodeAdapter {
             }
         }
 
-        // Check global npm installation
-        let global_paths = [
-            // macOS/Linux global npm
-            "/usr/local/lib/node_modules/@zed-industries/claude-code-acp/dist/index.js",
-            "/usr/lib/node_modules/@zed-industries/claude-code-acp/dist/index.js",
-            // User's npm prefix (npm config get prefix)
-            &format!(
-                "{}/.npm/lib/node_modules/@zed-industries/claude-code-acp/dist/index.js",
-                std::env::var("HOME").unwrap_or_default()
-            ),
-            // nvm installations
-            &format!(
-                "{}/.nvm/versions/node/*/lib/node_modules/@zed-industries/claude-code-acp/dist/index.js",
-                std::env::var("HOME").unwrap_or_default()
-            ),
-        ];
-
-        for path_pattern in &global_paths {
-            if path_pattern.contains('*') {
-                // Handle glob patterns
-                if let Ok(paths) = glob::glob(path_pattern) {
-                    for entry in paths.flatten() {
-                        if entry.exists() {
-                            debug!("Found Claude Code ACP script at: {:?}", entry);
-                            return Some(entry);
-                        }
-                    }
-                }
-            } else {
-                let path = PathBuf::from(path_pattern);
-                if path.exists() {
-                    debug!("Found Claude Code ACP script at: {:?}", path);
-                    return Some(path);
-                }
+        // Check global npm installations
+        for dir in platform::global_node_modules_dirs() {
+            let script_path = dir
+                .join("@zed-industries")
+                .join("claude-code-acp")
+                .join("dist")
+                .join("index.js");
+            if script_path.exists() {
+                debug!("Found Claude Code ACP script at: {:?}", script_path);
+                return Some(script_path);
             }
         }
 
         // Try to find via npm root
-        if let Ok(output) = tokio::process::Command::new("npm")
+        if let Ok(output) = tokio::process::Command::new(platform::npm_command())
             .args(["root", "-g"])
             .output()
             .await
@@ -233,6 +310,22 @@ impl ClaudeCodeAdapter {
             return Ok(path);
         }
 
+        self.npm_install(false, &InstallReporter::silent("claude-code"))
+            .await?;
+
+        // Find the installed script
+        self.find_acp_script().await.ok_or_else(|| {
+            crate::error::Error::Agent(crate::error::AgentError::SetupFailed(
+                "Package installed but script not found".to_string(),
+            ))
+        })
+    }
+
+    /// Run `npm install` for the bridge package into our prefix, streaming output
+    async fn npm_install(&self, latest: bool, reporter: &InstallReporter) -> Result<()> {
+        use tokio::io::{AsyncBufReadExt, BufReader};
+
+        net::ensure_online("npm install")?;
         info!("Installing Claude Code ACP package...");
 
         // Create npm prefix directory if needed
@@ -249,16 +342,20 @@ impl ClaudeCodeAdapter {
             )))
         })?;
 
+        let spec = if latest {
+            format!("{}@latest", CLAUDE_CODE_ACP_PACKAGE)
+        } else {
+            format!("{}@>={}", CLAUDE_CODE_ACP_PACKAGE, CLAUDE_CODE_ACP_MIN_VERSION)
+        };
+        reporter.progress(None, format!("npm install {}", spec));
+
         // Install the package
-        let output = tokio::process::Command::new("npm")
-            .args([
-                "install",
-                "--prefix",
-                &prefix.to_string_lossy(),
-                &format!("{}@>={}", CLAUDE_CODE_ACP_PACKAGE, CLAUDE_CODE_ACP_MIN_VERSION),
-            ])
-            .output()
-            .await
+        let mut child = tokio::process::Command::new(platform::npm_command())
+            .args(["install", "--prefix", &prefix.to_string_lossy(), &spec])
+            .envs(self.proxy.env_vars())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
             .map_err(|e| {
                 crate::error::Error::Agent(crate::error::AgentError::SetupFailed(format!(
                     "Failed to run npm install: {}",
@@ -266,8 +363,39 @@ impl ClaudeCodeAdapter {
                 )))
             })?;
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
+        // Forward stdout as it arrives; keep stderr for the error message
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let forward_stdout = async {
+            if let Some(stdout) = stdout {
+                let mut lines = BufReader::new(stdout).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    reporter.output(line);
+                }
+            }
+        };
+        let collect_stderr = async {
+            let mut collected = String::new();
+            if let Some(stderr) = stderr {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    reporter.output(line.clone());
+                    collected.push_str(&line);
+                    collected.push('\n');
+                }
+            }
+            collected
+        };
+        let (_, stderr) = tokio::join!(forward_stdout, collect_stderr);
+
+        let status = child.wait().await.map_err(|e| {
+            crate::error::Error::Agent(crate::error::AgentError::SetupFailed(format!(
+                "Failed to run npm install: {}",
+                e
+            )))
+        })?;
+
+        if !status.success() {
             warn!("npm install failed: {}", stderr);
             return Err(crate::error::Error::Agent(
                 crate::error::AgentError::SetupFailed(format!(
@@ -277,12 +405,21 @@ impl ClaudeCodeAdapter {
             ));
         }
 
-        // Find the installed script
-        self.find_acp_script().await.ok_or_else(|| {
-            crate::error::Error::Agent(crate::error::AgentError::SetupFailed(
-                "Package installed but script not found".to_string(),
-            ))
-        })
+        Ok(())
+    }
+
+    /// Version of the bridge package installed in our npm prefix
+    fn installed_package_version(&self) -> Option<String> {
+        let manifest = self
+            .npm_prefix
+            .as_ref()?
+            .join("node_modules")
+            .join("@zed-industries")
+            .join("claude-code-acp")
+            .join("package.json");
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(manifest).ok()?).ok()?;
+        json["version"].as_str().map(|v| v.to_string())
     }
 
     /// Get the command to launch Claude Code ACP
@@ -315,6 +452,11 @@ impl ClaudeCodeAdapter {
         self.npm_prefix = Some(prefix);
         self
     }
+
+    pub fn with_secrets(mut self, secrets: Arc<dyn SecretStore>) -> Self {
+        self.secrets = Some(secrets);
+        self
+    }
 }
 
 impl Default for ClaudeCodeAdapter {
@@ -366,6 +508,59 @@ impl AgentServerAdapter for ClaudeCodeAdapter {
         true
     }
 
+    fn install_instructions(&self) -> Option<String> {
+        Some(format!(
+            "Install Node.js 18 or newer from https://nodejs.org. The {} bridge is installed automatically on first connect.",
+            CLAUDE_CODE_ACP_PACKAGE
+        ))
+    }
+
+    fn set_proxy(&mut self, proxy: ProxySettings) {
+        self.proxy = proxy;
+    }
+
+    fn can_install(&self) -> bool {
+        true
+    }
+
+    async fn is_installed(&self) -> bool {
+        self.find_acp_script().await.is_some()
+    }
+
+    async fn install(&self, reporter: &InstallReporter, update: bool) -> Result<Option<String>> {
+        if self.find_node_path().await.is_none() {
+            return Err(crate::error::Error::Agent(crate::error::AgentError::NotFound(
+                "Node.js not found. Please install Node.js".to_string(),
+            )));
+        }
+        if !update {
+            if let Some(path) = self.find_acp_script().await {
+                reporter.progress(Some(1.0), format!("Already installed at {}", path.display()));
+                return Ok(self.installed_package_version());
+            }
+        }
+        self.npm_install(update, reporter).await?;
+        Ok(self.installed_package_version())
+    }
+
+    fn auth_actions(&self) -> Vec<AuthAction> {
+        vec![
+            AuthAction::RunCommand {
+                command: "claude".to_string(),
+                args: vec!["/login".to_string()],
+                description: "Sign in with your Claude subscription or Anthropic Console account"
+                    .to_string(),
+            },
+            AuthAction::OpenUrl {
+                url: "https://console.anthropic.com/settings/keys".to_string(),
+                description: "Create an API key in the Anthropic Console".to_string(),
+            },
+            AuthAction::SetApiKey {
+                key: SecretKey::AnthropicApiKey,
+            },
+        ]
+    }
+
     fn config(&self) -> AgentConfig {
         self.config.clone()
     }
@@ -400,9 +595,9 @@ impl AgentServer for ClaudeCodeAdapter {
 
     fn get_env(&self) -> HashMap<String, String> {
         let mut env = HashMap::new();
-        // Pass through ANTHROPIC_API_KEY if set
-        if let Ok(key) = std::env::var("ANTHROPIC_API_KEY") {
-            env.insert("ANTHROPIC_API_KEY".to_string(), key);
+        // Pass through the stored ANTHROPIC_API_KEY, or the environment's
+        if let Some(key) = resolve_secret(self.secrets.as_deref(), SecretKey::AnthropicApiKey) {
+            env.insert(SecretKey::AnthropicApiKey.env_var().to_string(), key);
         }
         env
     }
@@ -415,6 +610,7 @@ impl AgentServer for ClaudeCodeAdapter {
         &self,
         root_dir: Option<&Path>,
         delegate: Arc<dyn AgentClient>,
+        extra_env: &HashMap<String, String>,
     ) -> Result<Arc<dyn AgentConnection>> {
         info!("Connecting to Claude Code...");
 
@@ -428,7 +624,7 @@ impl AgentServer for ClaudeCodeAdapter {
             AgentServer::name(self),
             &node_path,
             &args,
-            &AgentServer::get_env(self),
+            &with_extra_env(AgentServer::get_env(self), extra_env),
             cwd.as_deref(),
             delegate,
         )
@@ -441,9 +637,10 @@ impl AgentServer for ClaudeCodeAdapter {
                 write: true,
                 list: true,
             }),
-            terminal: Some(TerminalCapability { execute: true }),
+            terminal: Some(TerminalCapability { execute: true, interactive: true }),
             mcp: None,
             load_session: Some(true),
+            elicitation: Some(true),
         };
         connection.initialize(client_caps).await?;
 
@@ -458,7 +655,10 @@ impl AgentServer for ClaudeCodeAdapter {
 /// Gemini CLI adapter - uses gemini CLI with experimental ACP support
 pub struct GeminiAdapter {
     config: AgentConfig,
+    /// Explicit API key, overriding stored and environment keys
     api_key: Option<String>,
+    /// Stored API keys, preferred over the environment
+    secrets: Option<Arc<dyn SecretStore>>,
 }
 
 impl GeminiAdapter {
@@ -477,7 +677,8 @@ impl GeminiAdapter {
                 created_at: chrono::Utc::now(),
                 updated_at: chrono::Utc::now(),
             },
-            api_key: std::env::var("GEMINI_API_KEY").ok(),
+            api_key: None,
+            secrets: None,
         }
     }
 
@@ -485,6 +686,26 @@ impl GeminiAdapter {
         self.api_key = Some(api_key);
         self
     }
+
+    pub fn with_secrets(mut self, secrets: Arc<dyn SecretStore>) -> Self {
+        self.secrets = Some(secrets);
+        self
+    }
+
+    /// Get environment variables for Gemini
+    fn gemini_env(&self) -> HashMap<String, String> {
+        let mut env = HashMap::new();
+        let api_key = self
+            .api_key
+            .clone()
+            .or_else(|| resolve_secret(self.secrets.as_deref(), SecretKey::GeminiApiKey));
+        if let Some(key) = api_key {
+            env.insert(SecretKey::GeminiApiKey.env_var().to_string(), key);
+        }
+        // Zed sets SURFACE=zed for telemetry
+        env.insert("SURFACE".to_string(), "cocowork".to_string());
+        env
+    }
 }
 
 impl Default for GeminiAdapter {
@@ -508,7 +729,7 @@ impl AgentServerAdapter for GeminiAdapter {
     }
 
     fn get_command(&self, working_dir: Option<&Path>) -> Result<Command> {
-        let mut cmd = Command::new("gemini");
+        let mut cmd = Command::new(platform::resolve_command("gemini"));
         cmd.arg("--experimental-acp");
 
         if let Some(dir) = working_dir {
@@ -523,22 +744,32 @@ impl AgentServerAdapter for GeminiAdapter {
     }
 
     fn get_env(&self) -> HashMap<String, String> {
-        let mut env = HashMap::new();
-        if let Some(key) = &self.api_key {
-            env.insert("GEMINI_API_KEY".to_string(), key.clone());
-        }
-        // Zed sets SURFACE=zed for telemetry
-        env.insert("SURFACE".to_string(), "cocowork".to_string());
-        env
+        self.gemini_env()
     }
 
     async fn is_available(&self) -> bool {
-        tokio::process::Command::new("which")
-            .arg("gemini")
-            .output()
-            .await
-            .map(|o| o.status.success())
-            .unwrap_or(false)
+        platform::find_executable("gemini").is_some()
+    }
+
+    fn install_instructions(&self) -> Option<String> {
+        Some("Run `npm install -g @google/gemini-cli`, then sign in with `gemini` or set GEMINI_API_KEY.".to_string())
+    }
+
+    fn auth_actions(&self) -> Vec<AuthAction> {
+        vec![
+            AuthAction::RunCommand {
+                command: "gemini".to_string(),
+                args: vec![],
+                description: "Start Gemini CLI once and choose \"Login with Google\"".to_string(),
+            },
+            AuthAction::OpenUrl {
+                url: "https://aistudio.google.com/apikey".to_string(),
+                description: "Create an API key in Google AI Studio".to_string(),
+            },
+            AuthAction::SetApiKey {
+                key: SecretKey::GeminiApiKey,
+            },
+        ]
     }
 
     fn config(&self) -> AgentConfig {
@@ -569,31 +800,22 @@ impl AgentServer for GeminiAdapter {
     }
 
     fn get_command(&self) -> Option<AgentServerCommand> {
-        Some(AgentServerCommand::new("gemini").with_args(vec!["--experimental-acp".to_string()]))
+        Some(AgentServerCommand::new(platform::resolve_command("gemini")).with_args(vec!["--experimental-acp".to_string()]))
     }
 
     fn get_env(&self) -> HashMap<String, String> {
-        let mut env = HashMap::new();
-        if let Some(key) = &self.api_key {
-            env.insert("GEMINI_API_KEY".to_string(), key.clone());
-        }
-        env.insert("SURFACE".to_string(), "cocowork".to_string());
-        env
+        self.gemini_env()
     }
 
     async fn is_available(&self) -> bool {
-        tokio::process::Command::new("which")
-            .arg("gemini")
-            .output()
-            .await
-            .map(|o| o.status.success())
-            .unwrap_or(false)
+        platform::find_executable("gemini").is_some()
     }
 
     async fn connect(
         &self,
         root_dir: Option<&Path>,
         delegate: Arc<dyn AgentClient>,
+        extra_env: &HashMap<String, String>,
     ) -> Result<Arc<dyn AgentConnection>> {
         let cmd = AgentServer::get_command(self).expect("Command should be available");
         let cwd = root_dir.map(|p| p.to_string_lossy().to_string());
@@ -602,7 +824,7 @@ impl AgentServer for GeminiAdapter {
             AgentServer::name(self),
             &cmd.command,
             &cmd.args,
-            &AgentServer::get_env(self),
+            &with_extra_env(AgentServer::get_env(self), extra_env),
             cwd.as_deref(),
             delegate,
         )
@@ -614,9 +836,10 @@ impl AgentServer for GeminiAdapter {
                 write: true,
                 list: true,
             }),
-            terminal: Some(TerminalCapability { execute: true }),
+            terminal: Some(TerminalCapability { execute: true, interactive: true }),
             mcp: None,
             load_session: Some(true),
+            elicitation: Some(true),
         };
         connection.initialize(client_caps).await?;
 
@@ -631,6 +854,8 @@ impl AgentServer for GeminiAdapter {
 /// GitHub repo for the codex-acp binary (same as Zed uses)
 const CODEX_ACP_REPO: &str = "zed-industries/codex-acp";
 const CODEX_API_KEY_VAR: &str = "CODEX_API_KEY";
+/// Number of codex-acp releases kept on disk for rollback
+const CODEX_KEPT_VERSIONS: usize = 3;
 const OPEN_AI_API_KEY_VAR: &str = "OPEN_AI_API_KEY";
 
 /// Codex adapter - uses the codex-acp binary from zed-industries/codex-acp
@@ -646,6 +871,12 @@ pub struct CodexAdapter {
     install_dir: PathBuf,
     /// Custom binary path (override auto-download)
     custom_binary_path: Option<PathBuf>,
+    /// Release tag to use instead of the latest
+    pinned_version: Option<String>,
+    /// Stored API keys, preferred over the environment
+    secrets: Option<Arc<dyn SecretStore>>,
+    /// Proxy for release downloads
+    proxy: ProxySettings,
 }
 
 impl CodexAdapter {
@@ -670,7 +901,10 @@ impl CodexAdapter {
                 updated_at: chrono::Utc::now(),
             },
             install_dir,
-            custom_binary_path: std::env::var("CODEX_ACP_PATH").ok().map(PathBuf::from),
+            custom_binary_path: None,
+            pinned_version: None,
+            secrets: None,
+            proxy: ProxySettings::default(),
         }
     }
 
@@ -679,6 +913,34 @@ impl CodexAdapter {
         self
     }
 
+    pub fn with_install_dir(mut self, dir: PathBuf) -> Self {
+        self.install_dir = dir;
+        self
+    }
+
+    pub fn with_secrets(mut self, secrets: Arc<dyn SecretStore>) -> Self {
+        self.secrets = Some(secrets);
+        self
+    }
+
+    pub fn with_proxy(mut self, proxy: ProxySettings) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    pub fn with_pinned_version(mut self, tag: impl Into<String>) -> Self {
+        self.pinned_version = Some(tag.into());
+        self
+    }
+
+    /// Sort key for release tags like `v0.10.2`, so that v0.10 sorts after v0.9
+    fn version_key(tag: &str) -> Vec<u64> {
+        tag.trim_start_matches('v')
+            .split(['.', '-'])
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    }
+
     /// Get the platform-specific asset name for GitHub releases
     fn asset_name(version: &str) -> Option<String> {
         let arch = if cfg!(target_arch = "x86_64") {
@@ -709,23 +971,14 @@ impl CodexAdapter {
     }
 
     /// Binary name for the current platform
-    fn bin_name() -> &'static str {
-        if cfg!(target_os = "windows") {
-            "codex-acp.exe"
-        } else {
-            "codex-acp"
-        }
+    fn bin_name() -> String {
+        platform::exe_name("codex-acp")
     }
 
-    /// Find the latest locally installed version
-    fn find_latest_local_version(&self) -> Option<PathBuf> {
-        let dir = &self.install_dir;
-        if !dir.exists() {
-            return None;
-        }
-
+    /// Locally installed versions and their binaries, oldest first
+    fn local_versions(&self) -> Vec<(String, PathBuf)> {
         let mut versions: Vec<(String, PathBuf)> = Vec::new();
-        if let Ok(entries) = std::fs::read_dir(dir) {
+        if let Ok(entries) = std::fs::read_dir(&self.install_dir) {
             for entry in entries.flatten() {
                 let path = entry.path();
                 if path.is_dir() {
@@ -738,35 +991,62 @@ impl CodexAdapter {
             }
         }
 
-        // Sort by version string (lexicographic is fine for semver with v prefix)
-        versions.sort_by(|(a, _), (b, _)| a.cmp(b));
-        versions.last().map(|(_, path)| path.clone())
+        versions.sort_by_key(|(tag, _)| Self::version_key(tag));
+        versions
     }
 
-    /// Download and install the codex-acp binary from GitHub releases
-    async fn download_latest(&self) -> std::result::Result<PathBuf, String> {
-        info!("Fetching latest codex-acp release from {}...", CODEX_ACP_REPO);
+    /// Find the latest locally installed version
+    fn find_latest_local_version(&self) -> Option<PathBuf> {
+        self.local_versions().pop().map(|(_, path)| path)
+    }
 
-        // Get the latest release info from GitHub API
-        let output = tokio::process::Command::new("curl")
-            .args([
-                "-fsSL",
-                "-H", "Accept: application/vnd.github+json",
-                &format!("https://api.github.com/repos/{}/releases/latest", CODEX_ACP_REPO),
-            ])
-            .output()
-            .await
-            .map_err(|e| format!("Failed to fetch release info: {}", e))?;
+    /// Find the binary for a specific installed version
+    fn find_local_version(&self, tag: &str) -> Option<PathBuf> {
+        let bin_path = self.install_dir.join(tag).join(Self::bin_name());
+        bin_path.exists().then_some(bin_path)
+    }
 
-        if !output.status.success() {
-            return Err(format!(
-                "Failed to fetch release info: {}",
-                String::from_utf8_lossy(&output.stderr)
-            ));
+    /// Remove old versions beyond the rollback window, never the pinned one
+    fn prune_old_versions(&self) {
+        let versions = self.local_versions();
+        let excess = versions.len().saturating_sub(CODEX_KEPT_VERSIONS);
+        for (tag, bin_path) in versions.into_iter().take(excess) {
+            if self.pinned_version.as_deref() == Some(tag.as_str()) {
+                continue;
+            }
+            if let Some(dir) = bin_path.parent() {
+                debug!("Removing old codex-acp {}", tag);
+                let _ = std::fs::remove_dir_all(dir);
+            }
         }
+    }
 
-        let release: serde_json::Value = serde_json::from_slice(&output.stdout)
-            .map_err(|e| format!("Failed to parse release JSON: {}", e))?;
+    /// Download and install a codex-acp release from GitHub, the latest one
+    /// if `tag` is `None`
+    ///
+    /// Returns the binary path and the release tag.
+    async fn download_release(
+        &self,
+        reporter: &InstallReporter,
+        tag: Option<&str>,
+    ) -> std::result::Result<(PathBuf, String), String> {
+        let release_url = match tag {
+            Some(tag) => format!(
+                "https://api.github.com/repos/{}/releases/tags/{}",
+                CODEX_ACP_REPO, tag
+            ),
+            None => format!("https://api.github.com/repos/{}/releases/latest", CODEX_ACP_REPO),
+        };
+        info!("Fetching codex-acp release info from {}...", release_url);
+        reporter.progress(None, "Fetching release info");
+
+        let downloader = Downloader::new(&self.proxy).map_err(|e| e.to_string())?;
+
+        // Get the release info from GitHub API
+        let release = downloader
+            .get_json(&release_url)
+            .await
+            .map_err(|e| format!("Failed to fetch release info: {}", e))?;
 
         let tag_name = release["tag_name"]
             .as_str()
@@ -779,7 +1059,8 @@ impl CodexAdapter {
         let bin_path = version_dir.join(Self::bin_name());
         if bin_path.exists() {
             info!("codex-acp {} already installed", tag_name);
-            return Ok(bin_path);
+            reporter.progress(Some(1.0), format!("codex-acp {} is up to date", tag_name));
+            return Ok((bin_path, tag_name.to_string()));
         }
 
         let asset_name = Self::asset_name(version_number)
@@ -790,11 +1071,15 @@ impl CodexAdapter {
             .as_array()
             .ok_or("Missing assets in release")?;
 
-        let download_url = assets
+        let asset = assets
             .iter()
             .find(|a| a["name"].as_str() == Some(&asset_name))
-            .and_then(|a| a["browser_download_url"].as_str())
             .ok_or_else(|| format!("Asset {} not found in release", asset_name))?;
+        let download_url = asset["browser_download_url"]
+            .as_str()
+            .ok_or_else(|| format!("Asset {} has no download URL", asset_name))?;
+        let total_size = asset["size"].as_u64().filter(|size| *size > 0);
+        let expected_checksum = Self::release_checksum(&downloader, assets, asset, &asset_name).await;
 
         info!("Downloading codex-acp {} from {}...", tag_name, download_url);
 
@@ -802,27 +1087,45 @@ impl CodexAdapter {
         std::fs::create_dir_all(&version_dir)
             .map_err(|e| format!("Failed to create directory: {}", e))?;
 
-        // Download and extract in one piped command: curl | tar
-        let version_dir_str = version_dir.to_string_lossy().to_string();
-        let extract_output = tokio::process::Command::new("sh")
-            .args([
-                "-c",
-                &format!(
-                    "curl -fsSL '{}' | tar xzf - -C '{}'",
-                    download_url, version_dir_str
-                ),
-            ])
-            .output()
+        let archive_path = self.install_dir.join(&asset_name);
+        let mut last_step = 0;
+        let checksum = downloader
+            .download_to_file(download_url, &archive_path, |received, total| {
+                // Report about every 256 KB rather than on each chunk
+                let step = received / (256 * 1024);
+                if step == last_step {
+                    return;
+                }
+                last_step = step;
+                let percent = total.or(total_size).map(|total| received as f32 / total as f32);
+                reporter.progress(
+                    percent,
+                    format!("Downloading codex-acp {} ({} KB)", tag_name, received / 1024),
+                );
+            })
             .await
-            .map_err(|e| format!("Failed to download and extract: {}", e))?;
-
-        if !extract_output.status.success() {
-            return Err(format!(
-                "Failed to download/extract codex-acp: {}",
-                String::from_utf8_lossy(&extract_output.stderr)
-            ));
+            .map_err(|e| format!("Failed to download codex-acp: {}", e))?;
+
+        match &expected_checksum {
+            Some(expected) => {
+                if let Err(e) = net::verify_sha256(&checksum, expected) {
+                    let _ = std::fs::remove_file(&archive_path);
+                    let _ = std::fs::remove_dir(&version_dir);
+                    return Err(format!("Refusing to install codex-acp {}: {}", tag_name, e));
+                }
+                debug!("Verified {} checksum {}", asset_name, checksum);
+            }
+            None => warn!(
+                "No published checksum for {}; installing unverified (sha256 {})",
+                asset_name, checksum
+            ),
         }
 
+        reporter.progress(Some(1.0), "Extracting");
+        let extracted = platform::extract_archive(&archive_path, &version_dir).await;
+        let _ = std::fs::remove_file(&archive_path);
+        extracted.map_err(|e| e.to_string())?;
+
         if !bin_path.exists() {
             return Err(format!(
                 "Binary not found at {} after extraction",
@@ -838,18 +1141,48 @@ impl CodexAdapter {
                 .map_err(|e| format!("Failed to set permissions: {}", e))?;
         }
 
-        // Clean up older versions
-        if let Ok(entries) = std::fs::read_dir(&self.install_dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_dir() && path != version_dir {
-                    let _ = std::fs::remove_dir_all(&path);
+        // Keep a few older versions around for rollback
+        self.prune_old_versions();
+
+        info!("codex-acp {} installed successfully", tag_name);
+        Ok((bin_path, tag_name.to_string()))
+    }
+
+    /// Published SHA-256 of a release asset
+    ///
+    /// Uses the digest GitHub records for the asset, falling back to a
+    /// `<asset>.sha256` or `SHA256SUMS` file attached to the release.
+    async fn release_checksum(
+        downloader: &Downloader,
+        assets: &[serde_json::Value],
+        asset: &serde_json::Value,
+        asset_name: &str,
+    ) -> Option<String> {
+        if let Some(digest) = asset["digest"].as_str().and_then(|d| d.strip_prefix("sha256:")) {
+            return Some(digest.to_lowercase());
+        }
+
+        let sidecar_name = format!("{}.sha256", asset_name);
+        let candidates = [sidecar_name.as_str(), "SHA256SUMS", "checksums.txt"];
+        for candidate in candidates {
+            let Some(url) = assets
+                .iter()
+                .find(|a| a["name"].as_str() == Some(candidate))
+                .and_then(|a| a["browser_download_url"].as_str())
+            else {
+                continue;
+            };
+            match downloader.get_text(url).await {
+                Ok(contents) => {
+                    let file_name = (candidate != sidecar_name).then_some(asset_name);
+                    if let Some(digest) = net::parse_checksum_file(&contents, file_name) {
+                        return Some(digest);
+                    }
                 }
+                Err(e) => warn!("Failed to fetch {}: {}", candidate, e),
             }
         }
-
-        info!("codex-acp {} installed successfully", tag_name);
-        Ok(bin_path)
+        None
     }
 
     /// Get the codex-acp binary path, installing if necessary
@@ -862,6 +1195,18 @@ impl CodexAdapter {
             warn!("Custom codex-acp path {:?} not found, falling back", path);
         }
 
+        // A pinned version wins over whatever else is installed
+        if let Some(tag) = &self.pinned_version {
+            if let Some(path) = self.find_local_version(tag) {
+                debug!("Using pinned codex-acp {} at {:?}", tag, path);
+                return Ok(path);
+            }
+            return self
+                .download_release(&InstallReporter::silent("codex-cli"), Some(tag))
+                .await
+                .map(|(path, _)| path);
+        }
+
         // Check if already installed locally
         if let Some(path) = self.find_latest_local_version() {
             debug!("Found locally installed codex-acp at {:?}", path);
@@ -869,11 +1214,13 @@ impl CodexAdapter {
         }
 
         // Download from GitHub
-        self.download_latest().await
+        self.download_release(&InstallReporter::silent("codex-cli"), None)
+            .await
+            .map(|(path, _)| path)
     }
 
     /// Get environment variables for Codex
-    fn codex_env() -> HashMap<String, String> {
+    fn codex_env(&self) -> HashMap<String, String> {
         let mut env = HashMap::new();
         if let Ok(key) = std::env::var(CODEX_API_KEY_VAR) {
             env.insert(CODEX_API_KEY_VAR.to_string(), key);
@@ -881,6 +1228,15 @@ impl CodexAdapter {
         if let Ok(key) = std::env::var(OPEN_AI_API_KEY_VAR) {
             env.insert(OPEN_AI_API_KEY_VAR.to_string(), key);
         }
+        // A key stored in the keychain takes precedence over the environment
+        let stored = self
+            .secrets
+            .as_deref()
+            .and_then(|store| store.get(SecretKey::OpenAiApiKey).ok().flatten());
+        if let Some(key) = stored {
+            env.insert(CODEX_API_KEY_VAR.to_string(), key.clone());
+            env.insert(SecretKey::OpenAiApiKey.env_var().to_string(), key);
+        }
         env
     }
 }
@@ -921,7 +1277,7 @@ impl AgentServerAdapter for CodexAdapter {
     }
 
     fn get_env(&self) -> HashMap<String, String> {
-        Self::codex_env()
+        self.codex_env()
     }
 
     async fn is_available(&self) -> bool {
@@ -939,6 +1295,93 @@ impl AgentServerAdapter for CodexAdapter {
         true
     }
 
+    fn install_instructions(&self) -> Option<String> {
+        Some(format!(
+            "codex-acp is downloaded from github.com/{} on first connect. Set CODEX_ACP_PATH to use an existing binary.",
+            CODEX_ACP_REPO
+        ))
+    }
+
+    fn can_install(&self) -> bool {
+        true
+    }
+
+    async fn is_installed(&self) -> bool {
+        if self.custom_binary_path.as_ref().is_some_and(|p| p.exists()) {
+            return true;
+        }
+        match &self.pinned_version {
+            Some(tag) => self.find_local_version(tag).is_some(),
+            None => self.find_latest_local_version().is_some(),
+        }
+    }
+
+    fn list_installed_versions(&self) -> Vec<String> {
+        self.local_versions().into_iter().map(|(tag, _)| tag).collect()
+    }
+
+    fn pinned_version(&self) -> Option<String> {
+        self.pinned_version.clone()
+    }
+
+    fn set_pinned_version(&mut self, version: Option<String>) {
+        self.pinned_version = version;
+    }
+
+    fn set_proxy(&mut self, proxy: ProxySettings) {
+        self.proxy = proxy;
+    }
+
+    async fn install_version(&self, reporter: &InstallReporter, tag: &str) -> Result<()> {
+        if self.find_local_version(tag).is_some() {
+            reporter.progress(Some(1.0), format!("codex-acp {} is already installed", tag));
+            return Ok(());
+        }
+        self.download_release(reporter, Some(tag))
+            .await
+            .map(|_| ())
+            .map_err(|e| crate::error::Error::Agent(crate::error::AgentError::SetupFailed(e)))
+    }
+
+    async fn install(&self, reporter: &InstallReporter, update: bool) -> Result<Option<String>> {
+        if let Some(tag) = &self.pinned_version {
+            // Pinned agents are never moved to a newer release
+            self.install_version(reporter, tag).await?;
+            return Ok(Some(tag.clone()));
+        }
+        if !update {
+            if let Some(path) = self.find_latest_local_version() {
+                reporter.progress(Some(1.0), format!("Already installed at {}", path.display()));
+                let tag = path
+                    .parent()
+                    .and_then(|dir| dir.file_name())
+                    .map(|name| name.to_string_lossy().to_string());
+                return Ok(tag);
+            }
+        }
+        let (_, tag) = self.download_release(reporter, None).await.map_err(|e| {
+            crate::error::Error::Agent(crate::error::AgentError::SetupFailed(e))
+        })?;
+        Ok(Some(tag))
+    }
+
+    fn auth_actions(&self) -> Vec<AuthAction> {
+        vec![
+            AuthAction::RunCommand {
+                command: "codex".to_string(),
+                args: vec!["login".to_string()],
+                description: "Sign in with your ChatGPT account".to_string(),
+            },
+            AuthAction::OpenUrl {
+                url: "https://platform.openai.com/api-keys".to_string(),
+                description: "Create an API key on the OpenAI platform".to_string(),
+            },
+            AuthAction::SetApiKey {
+                key: SecretKey::OpenAiApiKey,
+            },
+        ]
+    }
+
     fn config(&self) -> AgentConfig {
         self.config.clone()
     }
@@ -972,7 +1415,7 @@ impl AgentServer for CodexAdapter {
     }
 
     fn get_env(&self) -> HashMap<String, String> {
-        Self::codex_env()
+        self.codex_env()
     }
 
     async fn is_available(&self) -> bool {
@@ -983,6 +1426,7 @@ impl AgentServer for CodexAdapter {
         &self,
         root_dir: Option<&Path>,
         delegate: Arc<dyn AgentClient>,
+        extra_env: &HashMap<String, String>,
     ) -> Result<Arc<dyn AgentConnection>> {
         info!("Connecting to Codex...");
 
@@ -999,7 +1443,7 @@ impl AgentServer for CodexAdapter {
             AgentServer::name(self),
             &bin_path_str,
             &[],
-            &Self::codex_env(),
+            &with_extra_env(self.codex_env(), extra_env),
             cwd.as_deref(),
             delegate,
         )
@@ -1011,9 +1455,10 @@ impl AgentServer for CodexAdapter {
                 write: true,
                 list: true,
             }),
-            terminal: Some(TerminalCapability { execute: true }),
+            terminal: Some(TerminalCapability { execute: true, interactive: true }),
             mcp: None,
             load_session: Some(true),
+            elicitation: Some(true),
         };
         connection.initialize(client_caps).await?;
 
@@ -1071,7 +1516,7 @@ impl AgentServerAdapter for GooseAdapter {
     }
 
     fn get_command(&self, working_dir: Option<&Path>) -> Result<Command> {
-        let mut cmd = Command::new("goose");
+        let mut cmd = Command::new(platform::resolve_command("goose"));
         cmd.arg("--acp");
 
         if let Some(dir) = working_dir {
@@ -1086,12 +1531,11 @@ impl AgentServerAdapter for GooseAdapter {
     }
 
     async fn is_available(&self) -> bool {
-        tokio::process::Command::new("which")
-            .arg("goose")
-            .output()
-            .await
-            .map(|o| o.status.success())
-            .unwrap_or(false)
+        platform::find_executable("goose").is_some()
+    }
+
+    fn install_instructions(&self) -> Option<String> {
+        Some("Install the Goose CLI: https://block.github.io/goose/docs/getting-started/installation".to_string())
     }
 
     fn config(&self) -> AgentConfig {
@@ -1122,7 +1566,7 @@ impl AgentServer for GooseAdapter {
     }
 
     fn get_command(&self) -> Option<AgentServerCommand> {
-        Some(AgentServerCommand::new("goose").with_args(vec!["--acp".to_string()]))
+        Some(AgentServerCommand::new(platform::resolve_command("goose")).with_args(vec!["--acp".to_string()]))
     }
 
     fn get_env(&self) -> HashMap<String, String> {
@@ -1130,18 +1574,14 @@ impl AgentServer for GooseAdapter {
     }
 
     async fn is_available(&self) -> bool {
-        tokio::process::Command::new("which")
-            .arg("goose")
-            .output()
-            .await
-            .map(|o| o.status.success())
-            .unwrap_or(false)
+        platform::find_executable("goose").is_some()
     }
 
     async fn connect(
         &self,
         root_dir: Option<&Path>,
         delegate: Arc<dyn AgentClient>,
+        extra_env: &HashMap<String, String>,
     ) -> Result<Arc<dyn AgentConnection>> {
         let cmd = AgentServer::get_command(self).expect("Command should be available");
         let cwd = root_dir.map(|p| p.to_string_lossy().to_string());
@@ -1150,7 +1590,7 @@ impl AgentServer for GooseAdapter {
             AgentServer::name(self),
             &cmd.command,
             &cmd.args,
-            &AgentServer::get_env(self),
+            &with_extra_env(AgentServer::get_env(self), extra_env),
             cwd.as_deref(),
             delegate,
         )
@@ -1162,9 +1602,10 @@ impl AgentServer for GooseAdapter {
                 write: true,
                 list: true,
             }),
-            terminal: Some(TerminalCapability { execute: true }),
+            terminal: Some(TerminalCapability { execute: true, interactive: true }),
             mcp: None,
             load_session: Some(true),
+            elicitation: Some(true),
         };
         connection.initialize(client_caps).await?;
 
@@ -1240,7 +1681,7 @@ impl AgentServerAdapter for CustomAgentAdapter {
     }
 
     fn get_command(&self, working_dir: Option<&Path>) -> Result<Command> {
-        let mut cmd = Command::new(&self.config.command);
+        let mut cmd = Command::new(platform::resolve_command(&self.config.command));
         cmd.args(&self.config.args);
 
         if let Some(dir) = working_dir {
@@ -1264,12 +1705,14 @@ impl AgentServerAdapter for CustomAgentAdapter {
     }
 
     async fn is_available(&self) -> bool {
-        tokio::process::Command::new("which")
-            .arg(&self.config.command)
-            .output()
-            .await
-            .map(|o| o.status.success())
-            .unwrap_or(false)
+        platform::find_executable(&self.config.command).is_some()
+    }
+
+    fn install_instructions(&self) -> Option<String> {
+        Some(format!(
+            "Make sure `{}` is installed and on your PATH.",
+            self.config.command
+        ))
     }
 
     fn config(&self) -> AgentConfig {
@@ -1301,7 +1744,7 @@ impl AgentServer for CustomAgentAdapter {
 
     fn get_command(&self) -> Option<AgentServerCommand> {
         Some(
-            AgentServerCommand::new(&self.config.command)
+            AgentServerCommand::new(platform::resolve_command(&self.config.command))
                 .with_args(self.config.args.clone()),
         )
     }
@@ -1311,18 +1754,14 @@ impl AgentServer for CustomAgentAdapter {
     }
 
     async fn is_available(&self) -> bool {
-        tokio::process::Command::new("which")
-            .arg(&self.config.command)
-            .output()
-            .await
-            .map(|o| o.status.success())
-            .unwrap_or(false)
+        platform::find_executable(&self.config.command).is_some()
     }
 
     async fn connect(
         &self,
         root_dir: Option<&Path>,
         delegate: Arc<dyn AgentClient>,
+        extra_env: &HashMap<String, String>,
     ) -> Result<Arc<dyn AgentConnection>> {
         let cmd = AgentServer::get_command(self).expect("Command should be available");
         let cwd = root_dir.map(|p| p.to_string_lossy().to_string());
@@ -1331,7 +1770,7 @@ impl AgentServer for CustomAgentAdapter {
             AgentServer::name(self),
             &cmd.command,
             &cmd.args,
-            &AgentServer::get_env(self),
+            &with_extra_env(AgentServer::get_env(self), extra_env),
             cwd.as_deref(),
             delegate,
         )
@@ -1343,9 +1782,10 @@ impl AgentServer for CustomAgentAdapter {
                 write: true,
                 list: true,
             }),
-            terminal: Some(TerminalCapability { execute: true }),
+            terminal: Some(TerminalCapability { execute: true, interactive: true }),
             mcp: None,
             load_session: Some(true),
+            elicitation: Some(true),
         };
         connection.initialize(client_caps).await?;
 
@@ -1367,12 +1807,15 @@ impl<T: AgentServerAdapter + AgentServer> AgentAdapter for T {}
 /// Registry of all available agent adapters
 pub struct AgentAdapterRegistry {
     adapters: Vec<Box<dyn AgentAdapter>>,
+    /// Proxy applied to downloads and passed to agent processes
+    proxy: ProxySettings,
 }
 
 impl AgentAdapterRegistry {
     pub fn new() -> Self {
         Self {
             adapters: Vec::new(),
+            proxy: ProxySettings::default(),
         }
     }
 
@@ -1386,14 +1829,48 @@ impl AgentAdapterRegistry {
         registry
     }
 
+    /// Create registry with all builtin adapters, resolving API keys from a
+    /// secret store before the environment
+    pub fn with_builtins_and_secrets(secrets: Arc<dyn SecretStore>) -> Self {
+        Self::from_config(&AgentPaths::default(), secrets)
+    }
+
+    /// Create registry with all builtin adapters, using the agents at `paths`
+    /// over installing them and API keys from a secret store
+    pub fn from_config(paths: &AgentPaths, secrets: Arc<dyn SecretStore>) -> Self {
+        let mut claude_code = ClaudeCodeAdapter::new().with_secrets(Arc::clone(&secrets));
+        if let Some(node_path) = &paths.node_path {
+            claude_code = claude_code.with_node_path(node_path.clone());
+        }
+        if let Some(script) = &paths.claude_code_acp_path {
+            claude_code = claude_code.with_acp_script_path(script.clone());
+        }
+        let mut codex = CodexAdapter::new().with_secrets(Arc::clone(&secrets));
+        if let Some(binary) = &paths.codex_acp_path {
+            codex = codex.with_binary_path(binary.clone());
+        }
+
+        let mut registry = Self::new();
+        registry.register(Box::new(claude_code));
+        registry.register(Box::new(
+            GeminiAdapter::new().with_secrets(Arc::clone(&secrets)),
+        ));
+        registry.register(Box::new(codex));
+        registry.register(Box::new(GooseAdapter::new()));
+        registry
+    }
+
     /// Register a new adapter
-    pub fn register(&mut self, adapter: Box<dyn AgentAdapter>) {
+    pub fn register(&mut self, mut adapter: Box<dyn AgentAdapter>) {
+        if !self.proxy.is_empty() {
+            adapter.set_proxy(self.proxy.clone());
+        }
         self.adapters.push(adapter);
     }
 
     /// Register a custom agent
     pub fn register_custom(&mut self, config: AgentConfig) {
-        self.adapters.push(Box::new(CustomAgentAdapter::from_config(config)));
+        self.register(Box::new(CustomAgentAdapter::from_config(config)));
     }
 
     /// Get all adapters (legacy)
@@ -1409,6 +1886,30 @@ impl AgentAdapterRegistry {
             .map(|a| a.as_ref() as &dyn AgentServerAdapter)
     }
 
+    /// Get adapter by ID for changing its settings (legacy)
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut dyn AgentAdapter> {
+        self.adapters
+            .iter_mut()
+            .find(|a| AgentServerAdapter::id(a.as_ref()) == id)
+            .map(|a| a.as_mut() as &mut dyn AgentAdapter)
+    }
+
+    /// Apply pinned versions by agent ID, e.g. as loaded from settings
+    pub fn apply_pinned_versions(&mut self, pinned: &HashMap<String, String>) {
+        for adapter in &mut self.adapters {
+            let id = AgentServerAdapter::id(adapter.as_ref()).to_string();
+            adapter.set_pinned_version(pinned.get(&id).cloned());
+        }
+    }
+
+    /// Route downloads and agent traffic through a proxy, e.g. from settings
+    pub fn apply_proxy(&mut self, proxy: &ProxySettings) {
+        for adapter in &mut self.adapters {
+            adapter.set_proxy(proxy.clone());
+        }
+        self.proxy = proxy.clone();
+    }
+
     /// Get adapter by ID as AgentServer (new architecture)
     pub fn get_server(&self, id: &str) -> Option<&dyn AgentServer> {
         self.adapters
@@ -1433,17 +1934,53 @@ impl AgentAdapterRegistry {
         available
     }
 
+    /// Probe every adapter's availability concurrently
+    pub async fn probe_availability(&self) -> Vec<AgentAvailability> {
+        let probes = self.adapters.iter().map(|adapter| async move {
+            let available = AgentServerAdapter::is_available(adapter.as_ref()).await;
+            let installed = available && adapter.is_installed().await;
+            AgentAvailability {
+                agent_id: AgentServerAdapter::id(adapter.as_ref()).to_string(),
+                available,
+                installed,
+                installable: adapter.can_install(),
+                install_instructions: adapter.install_instructions(),
+                checked_at: chrono::Utc::now(),
+            }
+        });
+        futures::future::join_all(probes).await
+    }
+
     /// Connect to an agent by ID (new architecture)
     pub async fn connect(
         &self,
         agent_id: &str,
         root_dir: Option<&Path>,
         delegate: Arc<dyn AgentClient>,
+    ) -> Result<Arc<dyn AgentConnection>> {
+        self.connect_with_profile(agent_id, root_dir, delegate, None)
+            .await
+    }
+
+    /// Connect to an agent by ID with a workspace env profile applied
+    pub async fn connect_with_profile(
+        &self,
+        agent_id: &str,
+        root_dir: Option<&Path>,
+        delegate: Arc<dyn AgentClient>,
+        profile: Option<&EnvProfile>,
     ) -> Result<Arc<dyn AgentConnection>> {
         let server = self.get_server(agent_id).ok_or_else(|| {
             crate::error::Error::Agent(crate::error::AgentError::NotFound(agent_id.to_string()))
         })?;
-        server.connect(root_dir, delegate).await
+        // Profile variables win over the app-wide proxy
+        let mut extra_env = self.proxy.env_vars();
+        extra_env.extend(net::offline_env_vars());
+        if let Some(profile) = profile {
+            info!("Applying env profile '{}' to {}", profile.name, agent_id);
+            profile.apply(&mut extra_env);
+        }
+        server.connect(root_dir, delegate, &extra_env).await
     }
 }
 
@@ -1469,6 +2006,71 @@ mod tests {
         assert!(registry.get("nonexistent").is_none());
     }
 
+    #[tokio::test]
+    async fn test_probe_availability() {
+        let mut registry = AgentAdapterRegistry::new();
+        registry.register(Box::new(CustomAgentAdapter::new(
+            "missing",
+            "Missing Agent",
+            "cocowork-definitely-not-installed",
+            vec![],
+        )));
+
+        let results = registry.probe_availability().await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].agent_id, "missing");
+        assert!(!results[0].available);
+        assert!(results[0]
+            .install_instructions
+            .as_deref()
+            .unwrap()
+            .contains("cocowork-definitely-not-installed"));
+    }
+
+    #[test]
+    fn test_stored_api_key_passed_to_agent() {
+        let store = Arc::new(crate::secrets::MemorySecretStore::new());
+        store.set(SecretKey::GeminiApiKey, "stored-gemini").unwrap();
+
+        let adapter = GeminiAdapter::new().with_secrets(store.clone());
+        assert_eq!(
+            AgentServer::get_env(&adapter).get("GEMINI_API_KEY").map(String::as_str),
+            Some("stored-gemini")
+        );
+
+        // An explicit key still wins
+        let adapter = GeminiAdapter::new()
+            .with_secrets(store)
+            .with_api_key("explicit".to_string());
+        assert_eq!(
+            AgentServerAdapter::get_env(&adapter).get("GEMINI_API_KEY").map(String::as_str),
+            Some("explicit")
+        );
+    }
+
+    #[test]
+    fn test_codex_versions_and_rollback() {
+        let dir = tempfile::tempdir().unwrap();
+        for tag in ["v0.9.0", "v0.10.0", "v0.9.5"] {
+            let version_dir = dir.path().join(tag);
+            std::fs::create_dir_all(&version_dir).unwrap();
+            std::fs::write(version_dir.join(CodexAdapter::bin_name()), "").unwrap();
+        }
+
+        let mut adapter = CodexAdapter::new().with_install_dir(dir.path().to_path_buf());
+        assert_eq!(
+            adapter.list_installed_versions(),
+            vec!["v0.9.0", "v0.9.5", "v0.10.0"]
+        );
+        assert_eq!(adapter.rollback_target().as_deref(), Some("v0.9.5"));
+
+        adapter.set_pinned_version(Some("v0.9.5".to_string()));
+        assert_eq!(adapter.rollback_target().as_deref(), Some("v0.9.0"));
+
+        adapter.set_pinned_version(Some("v0.9.0".to_string()));
+        assert!(adapter.rollback_target().is_none());
+    }
+
     #[test]
     fn test_custom_adapter() {
         let mut registry = AgentAdapterRegistry::new();
@@ -0,0 +1,235 @@
+//! Guided sign-in for agents that need interactive authentication
+//!
+//! Claude Code and Codex refuse to start a session until the user has logged
+//! in. When an agent reports that authentication is required, the UI builds
+//! an [`AgentAuthFlow`] from the adapter's known login options and the
+//! methods the agent advertised during `initialize`, then walks the user
+//! through one of them instead of showing a bare connection error.
+
+use crate::error::{AgentError, Error, Result};
+use crate::secrets::SecretKey;
+use crate::types::AuthMethod;
+use std::process::Stdio;
+use tokio::process::Command;
+use tracing::info;
+
+/// One way to get an agent signed in
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuthAction {
+    /// Ask the agent to authenticate itself via the ACP `authenticate` call
+    AgentMethod {
+        method_id: String,
+        name: String,
+        description: Option<String>,
+    },
+    /// Run the agent's login command in a terminal window
+    RunCommand {
+        command: String,
+        args: Vec<String>,
+        description: String,
+    },
+    /// Open a page in the browser, e.g. to create an API key
+    OpenUrl { url: String, description: String },
+    /// Store an API key in the keychain
+    SetApiKey { key: SecretKey },
+}
+
+impl AuthAction {
+    /// Short button label
+    pub fn label(&self) -> String {
+        match self {
+            AuthAction::AgentMethod { name, .. } => name.clone(),
+            AuthAction::RunCommand { command, args, .. } => {
+                let mut label = format!("Run `{}", command);
+                for arg in args {
+                    label.push(' ');
+                    label.push_str(arg);
+                }
+                label.push('`');
+                label
+            }
+            AuthAction::OpenUrl { .. } => "Open in browser".to_string(),
+            AuthAction::SetApiKey { key } => format!("Enter {}", key.label()),
+        }
+    }
+
+    /// Longer explanation shown under the label
+    pub fn description(&self) -> Option<String> {
+        match self {
+            AuthAction::AgentMethod { description, .. } => description.clone(),
+            AuthAction::RunCommand { description, .. } => Some(description.clone()),
+            AuthAction::OpenUrl { description, .. } => Some(description.clone()),
+            AuthAction::SetApiKey { key } => Some(format!(
+                "Saved in the system keychain and passed to the agent as {}",
+                key.env_var()
+            )),
+        }
+    }
+}
+
+/// Sign-in options for an agent that reported it needs authentication
+#[derive(Debug, Clone)]
+pub struct AgentAuthFlow {
+    pub agent_id: String,
+    pub agent_name: String,
+    /// The agent's own explanation, if it gave one
+    pub message: String,
+    pub actions: Vec<AuthAction>,
+}
+
+impl AgentAuthFlow {
+    /// Combine the agent's advertised methods with the adapter's own options
+    ///
+    /// Advertised methods come first since the agent handles them itself.
+    pub fn new(
+        agent_id: impl Into<String>,
+        agent_name: impl Into<String>,
+        message: impl Into<String>,
+        methods: &[AuthMethod],
+        adapter_actions: Vec<AuthAction>,
+    ) -> Self {
+        let mut actions: Vec<AuthAction> = methods
+            .iter()
+            .map(|m| AuthAction::AgentMethod {
+                method_id: m.id.clone(),
+                name: m.name.clone(),
+                description: m.description.clone(),
+            })
+            .collect();
+        actions.extend(adapter_actions);
+
+        Self {
+            agent_id: agent_id.into(),
+            agent_name: agent_name.into(),
+            message: message.into(),
+            actions,
+        }
+    }
+}
+
+fn launch_failed(what: &str, e: impl std::fmt::Display) -> Error {
+    Error::Agent(AgentError::StartFailed(format!("Failed to {}: {}", what, e)))
+}
+
+/// Open a URL in the default browser
+pub fn open_url(url: &str) -> Result<()> {
+    info!("Opening {}", url);
+
+    #[cfg(target_os = "macos")]
+    let mut cmd = {
+        let mut cmd = std::process::Command::new("open");
+        cmd.arg(url);
+        cmd
+    };
+    #[cfg(target_os = "windows")]
+    let mut cmd = {
+        let mut cmd = std::process::Command::new("cmd");
+        cmd.args(["/C", "start", "", url]);
+        cmd
+    };
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let mut cmd = {
+        let mut cmd = std::process::Command::new("xdg-open");
+        cmd.arg(url);
+        cmd
+    };
+
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| launch_failed("open browser", e))
+}
+
+/// Quote an argument for a POSIX shell
+fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:@".contains(c))
+    {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+/// Run an interactive login command in a new terminal window
+///
+/// Login commands prompt the user and open a browser themselves, so they
+/// need a real terminal rather than the agent's piped stdio.
+pub async fn run_in_terminal(command: &str, args: &[String]) -> Result<()> {
+    let line = std::iter::once(command)
+        .chain(args.iter().map(String::as_str))
+        .map(shell_quote)
+        .collect::<Vec<_>>()
+        .join(" ");
+    info!("Launching login command: {}", line);
+
+    #[cfg(target_os = "macos")]
+    let mut cmd = {
+        let script = format!(
+            "tell application \"Terminal\" to do script \"{}\"",
+            line.replace('\\', "\\\\").replace('"', "\\\"")
+        );
+        let mut cmd = Command::new("osascript");
+        cmd.args(["-e", &script, "-e", "tell application \"Terminal\" to activate"]);
+        cmd
+    };
+    #[cfg(target_os = "windows")]
+    let mut cmd = {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", "start", "", "cmd", "/K"]).arg(command).args(args);
+        cmd
+    };
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let mut cmd = {
+        let mut cmd = Command::new("x-terminal-emulator");
+        cmd.args(["-e", "sh", "-c", &line]);
+        cmd
+    };
+
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| launch_failed("open terminal", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flow_lists_agent_methods_first() {
+        let methods = vec![AuthMethod {
+            id: "oauth".to_string(),
+            name: "Log in with browser".to_string(),
+            description: None,
+        }];
+        let flow = AgentAuthFlow::new(
+            "codex-cli",
+            "Codex CLI",
+            "Not logged in",
+            &methods,
+            vec![AuthAction::SetApiKey {
+                key: SecretKey::OpenAiApiKey,
+            }],
+        );
+
+        assert_eq!(flow.actions.len(), 2);
+        assert!(matches!(
+            &flow.actions[0],
+            AuthAction::AgentMethod { method_id, .. } if method_id == "oauth"
+        ));
+        assert_eq!(flow.actions[1].label(), "Enter OpenAI API key");
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("codex"), "codex");
+        assert_eq!(shell_quote("/login"), "/login");
+        assert_eq!(shell_quote("it's here"), r"'it'\''s here'");
+    }
+}
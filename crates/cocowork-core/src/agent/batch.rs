@@ -0,0 +1,382 @@
+//! Running one prompt across many workspaces
+//!
+//! A batch sends the same prompt to a fresh session in each workspace, e.g.
+//! to apply one refactor across a set of microservice repos. Workspaces run
+//! one at a time by default or with bounded concurrency. Like installs,
+//! progress is streamed as [`BatchEvent`]s and folded into a [`BatchReport`]
+//! that the UI renders as a per-workspace summary.
+
+use crate::acp::{AgentConnection, PromptMessage, SessionNotification};
+use crate::error::Result;
+use crate::types::{ContentBlock, SessionUpdate, StopReason};
+use futures::StreamExt;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{info, warn};
+
+/// What to run and where
+#[derive(Debug, Clone)]
+pub struct BatchRequest {
+    pub agent_id: String,
+    pub prompt: String,
+    pub workspaces: Vec<PathBuf>,
+    /// How many workspaces run at once; 1 runs them in order
+    pub max_concurrency: usize,
+}
+
+impl BatchRequest {
+    pub fn new(
+        agent_id: impl Into<String>,
+        prompt: impl Into<String>,
+        workspaces: Vec<PathBuf>,
+    ) -> Self {
+        Self {
+            agent_id: agent_id.into(),
+            prompt: prompt.into(),
+            workspaces,
+            max_concurrency: 1,
+        }
+    }
+
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+}
+
+/// What happened in one workspace
+#[derive(Debug, Clone, PartialEq)]
+pub enum BatchEventKind {
+    /// Connected and created a session
+    Started { session_id: String },
+    /// Text from the agent's response
+    Output { text: String },
+    /// The agent started a tool call
+    ToolCall,
+    Finished { stop_reason: StopReason },
+    Failed { error: String },
+}
+
+/// Progress event for the workspace at `index` in the request
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchEvent {
+    pub index: usize,
+    pub kind: BatchEventKind,
+}
+
+/// Where a workspace's run currently stands
+#[derive(Debug, Clone, PartialEq)]
+pub enum BatchItemState {
+    Queued,
+    Running,
+    Finished { stop_reason: StopReason },
+    Failed { error: String },
+}
+
+/// Result of the batch in one workspace
+#[derive(Debug, Clone)]
+pub struct BatchItem {
+    pub workspace: PathBuf,
+    pub state: BatchItemState,
+    pub session_id: Option<String>,
+    /// The agent's response text
+    pub response: String,
+    pub tool_calls: usize,
+    pub started_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl BatchItem {
+    fn new(workspace: PathBuf) -> Self {
+        Self {
+            workspace,
+            state: BatchItemState::Queued,
+            session_id: None,
+            response: String::new(),
+            tool_calls: 0,
+            started_at: None,
+            finished_at: None,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        matches!(
+            self.state,
+            BatchItemState::Finished { .. } | BatchItemState::Failed { .. }
+        )
+    }
+
+    /// Whether the agent finished its turn normally
+    pub fn succeeded(&self) -> bool {
+        self.state
+            == BatchItemState::Finished {
+                stop_reason: StopReason::EndTurn,
+            }
+    }
+
+    pub fn duration(&self) -> Option<chrono::Duration> {
+        Some(self.finished_at? - self.started_at?)
+    }
+}
+
+/// Accumulated view of a batch, built by folding its events
+#[derive(Debug, Clone)]
+pub struct BatchReport {
+    pub agent_id: String,
+    pub prompt: String,
+    pub items: Vec<BatchItem>,
+}
+
+impl BatchReport {
+    pub fn new(request: &BatchRequest) -> Self {
+        Self {
+            agent_id: request.agent_id.clone(),
+            prompt: request.prompt.clone(),
+            items: request.workspaces.iter().cloned().map(BatchItem::new).collect(),
+        }
+    }
+
+    /// Update the report with an event
+    pub fn apply(&mut self, event: &BatchEvent) {
+        let Some(item) = self.items.get_mut(event.index) else {
+            return;
+        };
+        match &event.kind {
+            BatchEventKind::Started { session_id } => {
+                item.state = BatchItemState::Running;
+                item.session_id = Some(session_id.clone());
+                item.started_at = Some(chrono::Utc::now());
+            }
+            BatchEventKind::Output { text } => item.response.push_str(text),
+            BatchEventKind::ToolCall => item.tool_calls += 1,
+            BatchEventKind::Finished { stop_reason } => {
+                item.state = BatchItemState::Finished {
+                    stop_reason: *stop_reason,
+                };
+                item.finished_at = Some(chrono::Utc::now());
+            }
+            BatchEventKind::Failed { error } => {
+                item.state = BatchItemState::Failed {
+                    error: error.clone(),
+                };
+                item.finished_at = Some(chrono::Utc::now());
+            }
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.items.iter().any(|item| !item.is_done())
+    }
+
+    /// One-line tally, e.g. "2 succeeded, 1 failed, 1 pending"
+    pub fn summary(&self) -> String {
+        let succeeded = self.items.iter().filter(|i| i.succeeded()).count();
+        let failed = self.items.iter().filter(|i| i.is_done() && !i.succeeded()).count();
+        let pending = self.items.len() - succeeded - failed;
+
+        let mut parts = vec![format!("{} succeeded", succeeded)];
+        if failed > 0 {
+            parts.push(format!("{} failed", failed));
+        }
+        if pending > 0 {
+            parts.push(format!("{} pending", pending));
+        }
+        parts.join(", ")
+    }
+}
+
+/// Run a batch, connecting to the agent once per workspace with `connect`
+///
+/// Each workspace gets its own connection and session, which is terminated
+/// when its turn ends. Returns once every workspace has finished or failed.
+pub async fn run_batch<F, Fut>(
+    request: BatchRequest,
+    connect: F,
+    tx: mpsc::UnboundedSender<BatchEvent>,
+) where
+    F: Fn(PathBuf) -> Fut,
+    Fut: Future<Output = Result<Arc<dyn AgentConnection>>>,
+{
+    info!(
+        "Running batch on {} workspaces (concurrency {})",
+        request.workspaces.len(),
+        request.max_concurrency
+    );
+    let prompt = request.prompt.clone();
+    let connect = &connect;
+    let prompt = &prompt;
+    let tx = &tx;
+
+    futures::stream::iter(request.workspaces.into_iter().enumerate())
+        .for_each_concurrent(request.max_concurrency.max(1), |(index, workspace)| {
+            run_prompt(index, connect(workspace.clone()), workspace, prompt, tx)
+        })
+        .await;
+}
+
+/// Prompt a new session in `cwd` once `connection` is up, reporting as `index`
+///
+/// Sends the final `Finished` or `Failed` event and terminates the
+/// connection afterwards.
+pub(super) async fn run_prompt(
+    index: usize,
+    connection: impl Future<Output = Result<Arc<dyn AgentConnection>>>,
+    cwd: PathBuf,
+    prompt: &str,
+    tx: &mpsc::UnboundedSender<BatchEvent>,
+) {
+    let send = |kind| {
+        let _ = tx.send(BatchEvent { index, kind });
+    };
+    let kind = match prompt_session(connection, cwd.clone(), prompt, &send).await {
+        Ok(stop_reason) => BatchEventKind::Finished { stop_reason },
+        Err(e) => {
+            warn!("Prompt in {} failed: {}", cwd.display(), e);
+            BatchEventKind::Failed {
+                error: e.to_string(),
+            }
+        }
+    };
+    send(kind);
+}
+
+async fn prompt_session(
+    connection: impl Future<Output = Result<Arc<dyn AgentConnection>>>,
+    cwd: PathBuf,
+    prompt: &str,
+    send: &impl Fn(BatchEventKind),
+) -> Result<StopReason> {
+    let connection = connection.await?;
+    let mut updates = connection.subscribe_updates();
+
+    let result = async {
+        let session = connection.new_session(cwd, Vec::new()).await?;
+        let session_id = session.session_id;
+        send(BatchEventKind::Started {
+            session_id: session_id.clone(),
+        });
+
+        let message = PromptMessage::new(vec![ContentBlock::Text {
+            text: prompt.to_string(),
+        }]);
+        let prompt = connection.prompt(session_id.clone(), message);
+        tokio::pin!(prompt);
+
+        // Forward this session's updates until the turn ends
+        let mut updates_open = true;
+        loop {
+            tokio::select! {
+                result = &mut prompt => {
+                    while let Ok(notification) = updates.try_recv() {
+                        forward_update(&session_id, notification, send);
+                    }
+                    return result.map(|r| r.stop_reason);
+                }
+                notification = updates.recv(), if updates_open => match notification {
+                    Ok(notification) => forward_update(&session_id, notification, send),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Dropped {} session updates", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => updates_open = false,
+                },
+            }
+        }
+    }
+    .await;
+
+    if let Err(e) = connection.terminate().await {
+        warn!("Failed to stop agent: {}", e);
+    }
+    result
+}
+
+fn forward_update(
+    session_id: &str,
+    notification: SessionNotification,
+    send: &impl Fn(BatchEventKind),
+) {
+    let SessionNotification::Update(notification) = notification else {
+        return;
+    };
+    if notification.session_id != session_id {
+        return;
+    }
+    match notification.update {
+        SessionUpdate::AgentMessageChunk {
+            content: ContentBlock::Text { text },
+        } => send(BatchEventKind::Output { text }),
+        SessionUpdate::ToolCall { .. } => send(BatchEventKind::ToolCall),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{AgentError, Error};
+
+    #[tokio::test]
+    async fn test_failed_connects_are_reported_per_workspace() {
+        let request = BatchRequest::new(
+            "codex-cli",
+            "Bump the logging crate",
+            vec![PathBuf::from("/work/a"), PathBuf::from("/work/b")],
+        )
+        .with_max_concurrency(4);
+        let mut report = BatchReport::new(&request);
+        assert_eq!(report.summary(), "0 succeeded, 2 pending");
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        run_batch(
+            request,
+            |workspace| async move {
+                Err(Error::Agent(AgentError::StartFailed(workspace.display().to_string())))
+            },
+            tx,
+        )
+        .await;
+
+        while let Ok(event) = rx.try_recv() {
+            report.apply(&event);
+        }
+        assert!(!report.is_running());
+        assert_eq!(report.summary(), "0 succeeded, 2 failed");
+        assert!(matches!(
+            &report.items[1].state,
+            BatchItemState::Failed { error } if error.contains("/work/b")
+        ));
+    }
+
+    #[test]
+    fn test_report_folds_events() {
+        let request = BatchRequest::new("claude-code", "Fix lints", vec![PathBuf::from("/work/a")]);
+        let mut report = BatchReport::new(&request);
+        let events = [
+            BatchEventKind::Started {
+                session_id: "s1".to_string(),
+            },
+            BatchEventKind::Output {
+                text: "Fixed ".to_string(),
+            },
+            BatchEventKind::ToolCall,
+            BatchEventKind::Output {
+                text: "3 lints".to_string(),
+            },
+            BatchEventKind::Finished {
+                stop_reason: StopReason::EndTurn,
+            },
+        ];
+        for kind in events {
+            report.apply(&BatchEvent { index: 0, kind });
+        }
+
+        let item = &report.items[0];
+        assert_eq!(item.response, "Fixed 3 lints");
+        assert_eq!(item.tool_calls, 1);
+        assert!(item.succeeded());
+        assert!(item.duration().is_some());
+        assert_eq!(report.summary(), "1 succeeded");
+    }
+}
@@ -0,0 +1,219 @@
+//! Comparing agents on the same prompt
+//!
+//! A comparison sends one prompt to several agents at once, each in its own
+//! connection and session in the same workspace, so their answers can be
+//! read side by side. It reuses the batch runner's events: the event index
+//! is the agent's position in the request.
+
+use super::batch::{run_prompt, BatchEvent, BatchEventKind, BatchItemState};
+use crate::acp::AgentConnection;
+use crate::error::Result;
+use crate::types::StopReason;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::info;
+
+/// Rough characters-per-token ratio for estimating response size
+const CHARS_PER_TOKEN: usize = 4;
+
+/// The prompt and the agents to compare
+#[derive(Debug, Clone)]
+pub struct ComparisonRequest {
+    pub prompt: String,
+    pub workspace: PathBuf,
+    pub agent_ids: Vec<String>,
+}
+
+impl ComparisonRequest {
+    pub fn new(
+        prompt: impl Into<String>,
+        workspace: impl Into<PathBuf>,
+        agent_ids: Vec<String>,
+    ) -> Self {
+        Self {
+            prompt: prompt.into(),
+            workspace: workspace.into(),
+            agent_ids,
+        }
+    }
+}
+
+/// One agent's side of a comparison
+#[derive(Debug, Clone)]
+pub struct ComparisonSide {
+    pub agent_id: String,
+    pub state: BatchItemState,
+    pub response: String,
+    pub tool_calls: usize,
+    pub started_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub first_output_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl ComparisonSide {
+    fn new(agent_id: String) -> Self {
+        Self {
+            agent_id,
+            state: BatchItemState::Queued,
+            response: String::new(),
+            tool_calls: 0,
+            started_at: None,
+            first_output_at: None,
+            finished_at: None,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        matches!(
+            self.state,
+            BatchItemState::Finished { .. } | BatchItemState::Failed { .. }
+        )
+    }
+
+    pub fn stop_reason(&self) -> Option<StopReason> {
+        match self.state {
+            BatchItemState::Finished { stop_reason } => Some(stop_reason),
+            _ => None,
+        }
+    }
+
+    /// Time from session start to the first response text
+    pub fn time_to_first_output(&self) -> Option<chrono::Duration> {
+        Some(self.first_output_at? - self.started_at?)
+    }
+
+    /// Time from session start to the end of the turn
+    pub fn duration(&self) -> Option<chrono::Duration> {
+        Some(self.finished_at? - self.started_at?)
+    }
+
+    /// Approximate response size in tokens
+    ///
+    /// ACP doesn't report usage, so this is estimated from the text length.
+    pub fn estimated_output_tokens(&self) -> usize {
+        self.response.chars().count().div_ceil(CHARS_PER_TOKEN)
+    }
+
+    fn apply(&mut self, kind: &BatchEventKind) {
+        let now = chrono::Utc::now();
+        match kind {
+            BatchEventKind::Started { .. } => {
+                self.state = BatchItemState::Running;
+                self.started_at = Some(now);
+            }
+            BatchEventKind::Output { text } => {
+                self.first_output_at.get_or_insert(now);
+                self.response.push_str(text);
+            }
+            BatchEventKind::ToolCall => self.tool_calls += 1,
+            BatchEventKind::Finished { stop_reason } => {
+                self.state = BatchItemState::Finished {
+                    stop_reason: *stop_reason,
+                };
+                self.finished_at = Some(now);
+            }
+            BatchEventKind::Failed { error } => {
+                self.state = BatchItemState::Failed {
+                    error: error.clone(),
+                };
+                self.finished_at = Some(now);
+            }
+        }
+    }
+}
+
+/// Accumulated view of a comparison, built by folding its events
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub prompt: String,
+    pub workspace: PathBuf,
+    pub sides: Vec<ComparisonSide>,
+}
+
+impl Comparison {
+    pub fn new(request: &ComparisonRequest) -> Self {
+        Self {
+            prompt: request.prompt.clone(),
+            workspace: request.workspace.clone(),
+            sides: request.agent_ids.iter().cloned().map(ComparisonSide::new).collect(),
+        }
+    }
+
+    /// Update the comparison with an event
+    pub fn apply(&mut self, event: &BatchEvent) {
+        if let Some(side) = self.sides.get_mut(event.index) {
+            side.apply(&event.kind);
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.sides.iter().any(|side| !side.is_done())
+    }
+}
+
+/// Run a comparison, connecting to each agent with `connect`
+///
+/// All agents run in parallel. Returns once every agent has finished or failed.
+pub async fn run_comparison<F, Fut>(
+    request: ComparisonRequest,
+    connect: F,
+    tx: mpsc::UnboundedSender<BatchEvent>,
+) where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<Arc<dyn AgentConnection>>>,
+{
+    info!("Comparing {} agents", request.agent_ids.len());
+    let runs = request.agent_ids.into_iter().enumerate().map(|(index, agent_id)| {
+        run_prompt(
+            index,
+            connect(agent_id),
+            request.workspace.clone(),
+            &request.prompt,
+            &tx,
+        )
+    });
+    futures::future::join_all(runs).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comparison_stats() {
+        let request = ComparisonRequest::new(
+            "Write a haiku",
+            "/work",
+            vec!["claude-code".to_string(), "codex-cli".to_string()],
+        );
+        let mut comparison = Comparison::new(&request);
+        let events = [
+            (0, BatchEventKind::Started { session_id: "a".to_string() }),
+            (1, BatchEventKind::Started { session_id: "b".to_string() }),
+            (0, BatchEventKind::Output { text: "12345678".to_string() }),
+            (0, BatchEventKind::Output { text: "9".to_string() }),
+            (1, BatchEventKind::Failed { error: "not logged in".to_string() }),
+        ];
+        for (index, kind) in events {
+            comparison.apply(&BatchEvent { index, kind });
+        }
+
+        let claude = &comparison.sides[0];
+        assert_eq!(claude.response, "123456789");
+        assert_eq!(claude.estimated_output_tokens(), 3);
+        assert!(claude.time_to_first_output().is_some());
+        assert!(comparison.is_running());
+
+        comparison.apply(&BatchEvent {
+            index: 0,
+            kind: BatchEventKind::Finished {
+                stop_reason: StopReason::EndTurn,
+            },
+        });
+        assert!(!comparison.is_running());
+        assert_eq!(comparison.sides[0].stop_reason(), Some(StopReason::EndTurn));
+        assert_eq!(comparison.sides[1].stop_reason(), None);
+    }
+}
@@ -0,0 +1,299 @@
+//! Installing and updating agent bridges
+//!
+//! Some agents need a helper before they can speak ACP: Claude Code runs an
+//! npm bridge package and Codex runs a downloaded `codex-acp` binary. The
+//! adapters know how to fetch these; [`AgentInstaller`] drives them on demand
+//! and streams [`InstallEvent`]s so the UI can show download progress and
+//! installer output instead of blocking silently at connect time.
+
+use super::adapter::{AgentAdapterRegistry, AgentServerAdapter};
+use crate::error::Result;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+/// Maximum number of output lines kept per install
+const MAX_OUTPUT_LINES: usize = 200;
+
+/// What happened during an install
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstallEventKind {
+    Started {
+        /// Whether an existing installation is being updated
+        update: bool,
+    },
+    Progress {
+        /// Completion from 0.0 to 1.0, when known
+        percent: Option<f32>,
+        message: String,
+    },
+    /// A line of output from an external installer (e.g. npm)
+    Output { line: String },
+    Finished { version: Option<String> },
+    Failed { error: String },
+}
+
+/// Progress event for a single agent's install
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstallEvent {
+    pub agent_id: String,
+    pub kind: InstallEventKind,
+}
+
+/// Handle adapters use to report install progress
+#[derive(Debug, Clone)]
+pub struct InstallReporter {
+    agent_id: String,
+    tx: Option<mpsc::UnboundedSender<InstallEvent>>,
+}
+
+impl InstallReporter {
+    pub fn new(agent_id: impl Into<String>, tx: mpsc::UnboundedSender<InstallEvent>) -> Self {
+        Self {
+            agent_id: agent_id.into(),
+            tx: Some(tx),
+        }
+    }
+
+    /// A reporter that drops every event, for installs nobody is watching
+    pub fn silent(agent_id: impl Into<String>) -> Self {
+        Self {
+            agent_id: agent_id.into(),
+            tx: None,
+        }
+    }
+
+    pub fn progress(&self, percent: Option<f32>, message: impl Into<String>) {
+        self.send(InstallEventKind::Progress {
+            percent: percent.map(|p| p.clamp(0.0, 1.0)),
+            message: message.into(),
+        });
+    }
+
+    pub fn output(&self, line: impl Into<String>) {
+        self.send(InstallEventKind::Output { line: line.into() });
+    }
+
+    fn send(&self, kind: InstallEventKind) {
+        if let Some(tx) = &self.tx {
+            let _ = tx.send(InstallEvent {
+                agent_id: self.agent_id.clone(),
+                kind,
+            });
+        }
+    }
+}
+
+/// Where an install currently stands
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstallState {
+    Running,
+    Succeeded { version: Option<String> },
+    Failed { error: String },
+}
+
+/// Accumulated view of an install, built by folding its events
+#[derive(Debug, Clone)]
+pub struct InstallStatus {
+    pub agent_id: String,
+    pub update: bool,
+    pub state: InstallState,
+    pub message: String,
+    pub percent: Option<f32>,
+    /// Most recent installer output, oldest first
+    pub output: Vec<String>,
+}
+
+impl InstallStatus {
+    pub fn new(agent_id: impl Into<String>) -> Self {
+        Self {
+            agent_id: agent_id.into(),
+            update: false,
+            state: InstallState::Running,
+            message: String::new(),
+            percent: None,
+            output: Vec::new(),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state == InstallState::Running
+    }
+
+    /// Update the status with a new event for this agent
+    pub fn apply(&mut self, event: &InstallEvent) {
+        match &event.kind {
+            InstallEventKind::Started { update } => {
+                *self = Self::new(&event.agent_id);
+                self.update = *update;
+                self.message = if *update {
+                    "Checking for updates...".to_string()
+                } else {
+                    "Installing...".to_string()
+                };
+            }
+            InstallEventKind::Progress { percent, message } => {
+                self.percent = *percent;
+                self.message = message.clone();
+            }
+            InstallEventKind::Output { line } => {
+                self.output.push(line.clone());
+                if self.output.len() > MAX_OUTPUT_LINES {
+                    let excess = self.output.len() - MAX_OUTPUT_LINES;
+                    self.output.drain(..excess);
+                }
+            }
+            InstallEventKind::Finished { version } => {
+                self.percent = Some(1.0);
+                self.message = match version {
+                    Some(v) => format!("Installed {}", v),
+                    None => "Installed".to_string(),
+                };
+                self.state = InstallState::Succeeded {
+                    version: version.clone(),
+                };
+            }
+            InstallEventKind::Failed { error } => {
+                self.message = "Install failed".to_string();
+                self.state = InstallState::Failed {
+                    error: error.clone(),
+                };
+            }
+        }
+    }
+}
+
+/// Runs adapter installs and publishes their progress
+#[derive(Clone)]
+pub struct AgentInstaller {
+    events: mpsc::UnboundedSender<InstallEvent>,
+}
+
+impl AgentInstaller {
+    /// Create an installer and the receiver for its progress events
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<InstallEvent>) {
+        let (events, rx) = mpsc::unbounded_channel();
+        (Self { events }, rx)
+    }
+
+    /// Install an agent, or update it when `update` is set
+    ///
+    /// Emits `Started` and then exactly one of `Finished` / `Failed`.
+    pub async fn install(
+        &self,
+        adapter: &dyn AgentServerAdapter,
+        update: bool,
+    ) -> Result<Option<String>> {
+        let reporter = InstallReporter::new(adapter.id(), self.events.clone());
+        reporter.send(InstallEventKind::Started { update });
+
+        match adapter.install(&reporter, update).await {
+            Ok(version) => {
+                info!("Installed {} ({:?})", adapter.id(), version);
+                reporter.send(InstallEventKind::Finished {
+                    version: version.clone(),
+                });
+                Ok(version)
+            }
+            Err(e) => {
+                warn!("Failed to install {}: {}", adapter.id(), e);
+                reporter.send(InstallEventKind::Failed {
+                    error: e.to_string(),
+                });
+                Err(e)
+            }
+        }
+    }
+
+    /// Install a specific release of an agent's bridge, e.g. to roll back
+    pub async fn install_version(&self, adapter: &dyn AgentServerAdapter, tag: &str) -> Result<()> {
+        let reporter = InstallReporter::new(adapter.id(), self.events.clone());
+        reporter.send(InstallEventKind::Started { update: false });
+
+        match adapter.install_version(&reporter, tag).await {
+            Ok(()) => {
+                info!("Installed {} {}", adapter.id(), tag);
+                reporter.send(InstallEventKind::Finished {
+                    version: Some(tag.to_string()),
+                });
+                Ok(())
+            }
+            Err(e) => {
+                warn!("Failed to install {} {}: {}", adapter.id(), tag, e);
+                reporter.send(InstallEventKind::Failed {
+                    error: e.to_string(),
+                });
+                Err(e)
+            }
+        }
+    }
+
+    /// Update every installable agent that is already installed
+    ///
+    /// Returns the IDs of the agents that were checked, none while offline.
+    pub async fn check_for_updates(&self, registry: &AgentAdapterRegistry) -> Vec<String> {
+        let mut checked = Vec::new();
+        if crate::net::is_offline() {
+            info!("Offline, so not checking for agent updates");
+            return checked;
+        }
+        for adapter in registry.all() {
+            if !adapter.can_install() || !adapter.is_installed().await {
+                continue;
+            }
+            // Failures are reported through events; keep going with the rest
+            let _ = self.install(adapter, true).await;
+            checked.push(adapter.id().to_string());
+        }
+        checked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::CustomAgentAdapter;
+
+    #[test]
+    fn test_install_status_fold() {
+        let event = |kind| InstallEvent {
+            agent_id: "codex-cli".to_string(),
+            kind,
+        };
+
+        let mut status = InstallStatus::new("codex-cli");
+        status.apply(&event(InstallEventKind::Started { update: true }));
+        assert!(status.update);
+        status.apply(&event(InstallEventKind::Progress {
+            percent: Some(0.5),
+            message: "Downloading".to_string(),
+        }));
+        assert_eq!(status.percent, Some(0.5));
+
+        for i in 0..MAX_OUTPUT_LINES + 5 {
+            status.apply(&event(InstallEventKind::Output {
+                line: i.to_string(),
+            }));
+        }
+        assert_eq!(status.output.len(), MAX_OUTPUT_LINES);
+        assert_eq!(status.output[0], "5");
+
+        status.apply(&event(InstallEventKind::Finished {
+            version: Some("v1.0.0".to_string()),
+        }));
+        assert!(!status.is_running());
+        assert_eq!(status.message, "Installed v1.0.0");
+    }
+
+    #[tokio::test]
+    async fn test_install_unsupported_adapter_reports_failure() {
+        let (installer, mut rx) = AgentInstaller::new();
+        let adapter = CustomAgentAdapter::new("custom", "Custom", "custom-agent", vec![]);
+
+        assert!(installer.install(&adapter, false).await.is_err());
+
+        let started = rx.recv().await.unwrap();
+        assert_eq!(started.kind, InstallEventKind::Started { update: false });
+        let failed = rx.recv().await.unwrap();
+        assert!(matches!(failed.kind, InstallEventKind::Failed { .. }));
+    }
+}
@@ -184,9 +184,10 @@ impl AgentManager {
                 write: true,
                 list: true,
             }),
-            terminal: Some(TerminalCapability { execute: true }),
+            terminal: Some(TerminalCapability { execute: true, interactive: true }),
             mcp: None,
             load_session: Some(true),
+            elicitation: Some(true),
         };
 
         if let Err(e) = client.initialize(capabilities).await {
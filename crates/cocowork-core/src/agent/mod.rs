@@ -5,14 +5,33 @@
 //! - Agent process lifecycle (start/stop)
 //! - Agent status tracking
 //! - Agent server adapters (Claude Code, Gemini, Codex, Custom)
+//! - Installing and updating agent bridges
+//! - Guided sign-in for agents that require authentication
+//! - Running one prompt across many workspaces
+//! - Comparing agents side by side on the same prompt
+//! - Stopping agent processes on quit and after a crash
 
 mod adapter;
+mod auth;
+mod batch;
+mod compare;
+mod installer;
 mod manager;
+mod processes;
 mod registry;
 
 pub use adapter::{
-    AgentAdapterRegistry, AgentServerAdapter,
+    AgentAdapterRegistry, AgentAvailability, AgentServerAdapter,
     ClaudeCodeAdapter, CodexAdapter, CustomAgentAdapter, GeminiAdapter, GooseAdapter,
 };
+pub use auth::{open_url, run_in_terminal, AgentAuthFlow, AuthAction};
+pub use batch::{
+    run_batch, BatchEvent, BatchEventKind, BatchItem, BatchItemState, BatchReport, BatchRequest,
+};
+pub use compare::{run_comparison, Comparison, ComparisonRequest, ComparisonSide};
+pub use installer::{
+    AgentInstaller, InstallEvent, InstallEventKind, InstallReporter, InstallState, InstallStatus,
+};
 pub use manager::AgentManager;
+pub use processes::{sweep_stale_agent_processes, AgentProcessTracker};
 pub use registry::AgentRegistry;
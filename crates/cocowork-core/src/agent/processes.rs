@@ -0,0 +1,294 @@
+//! Agent processes started by the app
+//!
+//! Every agent connection's process is recorded in storage while it runs.
+//! Quitting the app terminates the live ones and clears their records; if the
+//! app crashed instead, the next start finds the leftover records and stops
+//! any of those processes that are still running.
+
+use crate::acp::AgentConnection;
+use crate::error::Result;
+use crate::storage::{list_agent_processes, record_agent_process, remove_agent_process, Storage};
+use crate::types::AgentProcess;
+use std::process::Command;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// How long a process gets to exit after being asked before it's killed
+#[cfg(unix)]
+const TERM_GRACE: Duration = Duration::from_secs(1);
+
+/// Tracks the agent processes of live connections
+pub struct AgentProcessTracker {
+    storage: Arc<Storage>,
+    live: Mutex<Vec<(u32, Weak<dyn AgentConnection>)>>,
+}
+
+impl AgentProcessTracker {
+    pub fn new(storage: Arc<Storage>) -> Self {
+        Self {
+            storage,
+            live: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Record a new connection's process so it's stopped on quit or after a crash
+    ///
+    /// Nothing is recorded while the storage is read-only.
+    pub fn track(&self, agent_id: &str, connection: &Arc<dyn AgentConnection>) {
+        let Some(pid) = connection.process_id() else {
+            return;
+        };
+        let process = AgentProcess {
+            pid,
+            agent_id: agent_id.to_string(),
+            command: process_command(pid).unwrap_or_default(),
+            process_started: process_start_time(pid).unwrap_or_default(),
+            started_at: chrono::Utc::now(),
+        };
+        if self.storage.is_read_only() {
+            debug!("Not recording agent process {} in read-only storage", pid);
+        } else if let Err(e) = self
+            .storage
+            .connection()
+            .and_then(|conn| record_agent_process(&conn, &process))
+        {
+            warn!("Failed to record agent process {}: {}", pid, e);
+        }
+
+        let mut live = self.live.lock().unwrap();
+        live.retain(|(_, connection)| connection.strong_count() > 0);
+        live.push((pid, Arc::downgrade(connection)));
+    }
+
+    /// Terminate every live connection, giving each up to `timeout`
+    ///
+    /// Returns how many were terminated. Records of connections that already
+    /// went away are cleared too, since dropping one stops its process.
+    pub async fn shutdown(&self, timeout: Duration) -> usize {
+        let tracked: Vec<_> = self.live.lock().unwrap().drain(..).collect();
+        let mut terminated = 0;
+        for (pid, connection) in tracked {
+            if let Some(connection) = connection.upgrade() {
+                match tokio::time::timeout(timeout, connection.terminate()).await {
+                    Ok(Ok(())) => terminated += 1,
+                    Ok(Err(e)) => warn!("Failed to terminate agent process {}: {}", pid, e),
+                    Err(_) => {
+                        warn!("Agent process {} didn't stop in time, killing it", pid);
+                        kill_process(pid);
+                        terminated += 1;
+                    }
+                }
+            }
+            if self.storage.is_read_only() {
+                continue;
+            }
+            if let Err(e) = self.storage.connection().and_then(|conn| remove_agent_process(&conn, pid)) {
+                warn!("Failed to clear agent process {}: {}", pid, e);
+            }
+        }
+        info!("Terminated {} agent process(es)", terminated);
+        terminated
+    }
+}
+
+/// Stop agent processes left running by an earlier run of the app
+///
+/// A recorded process is only stopped if its PID still runs the same
+/// executable, started at the same time, so a PID the OS has since given to
+/// something else is left alone. Every record is cleared. Returns the
+/// processes that were stopped.
+///
+/// Does nothing while `storage` is read-only: the records are then those of
+/// the process writing the database, whose agents are still in use.
+pub fn sweep_stale_agent_processes(storage: &Storage) -> Result<Vec<AgentProcess>> {
+    if storage.is_read_only() {
+        return Ok(Vec::new());
+    }
+    let conn = &*storage.connection()?;
+    let mut stopped = Vec::new();
+    for process in list_agent_processes(conn)? {
+        match process_command(process.pid) {
+            Some(command)
+                if !process.command.is_empty()
+                    && command == process.command
+                    && process_start_time(process.pid).unwrap_or_default() == process.process_started =>
+            {
+                info!(
+                    "Stopping {} agent process {} left from an earlier run",
+                    process.agent_id, process.pid
+                );
+                kill_process(process.pid);
+                remove_agent_process(conn, process.pid)?;
+                stopped.push(process);
+                continue;
+            }
+            Some(_) => debug!("PID {} now belongs to another process", process.pid),
+            None => {}
+        }
+        remove_agent_process(conn, process.pid)?;
+    }
+    Ok(stopped)
+}
+
+/// Executable name of a running process, or `None` if there's no such process
+fn process_command(pid: u32) -> Option<String> {
+    #[cfg(unix)]
+    let output = Command::new("ps")
+        .args(["-p", &pid.to_string(), "-o", "comm="])
+        .output()
+        .ok()?;
+    #[cfg(windows)]
+    let output = Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    let line = text.lines().next()?.trim();
+    #[cfg(windows)]
+    let line = match line.strip_prefix('"') {
+        // "node.exe","1234",...
+        Some(rest) => rest.split('"').next()?,
+        // "INFO: No tasks are running..."
+        None => return None,
+    };
+    (!line.is_empty()).then(|| line.to_string())
+}
+
+/// When a running process started, as the OS reports it
+///
+/// On Linux this is the start time in clock ticks after boot from
+/// `/proc/<pid>/stat`; elsewhere on Unix it's what `ps -o lstart=` prints.
+/// Either way it's only compared with an earlier reading.
+#[cfg(target_os = "linux")]
+fn process_start_time(pid: u32) -> Option<String> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The executable name in parentheses can hold spaces; the start time is
+    // the 20th field after it
+    let (_, fields) = stat.rsplit_once(')')?;
+    fields.split_whitespace().nth(19).map(str::to_string)
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn process_start_time(pid: u32) -> Option<String> {
+    let output = Command::new("ps")
+        .args(["-p", &pid.to_string(), "-o", "lstart="])
+        .output()
+        .ok()?;
+    let started = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !started.is_empty()).then_some(started)
+}
+
+#[cfg(windows)]
+fn process_start_time(_pid: u32) -> Option<String> {
+    None
+}
+
+/// Ask a process to exit, killing it if it's still there after a moment
+fn kill_process(pid: u32) {
+    #[cfg(unix)]
+    {
+        let signal = |name: &str| {
+            Command::new("kill")
+                .args([name, &pid.to_string()])
+                .output()
+                .is_ok_and(|output| output.status.success())
+        };
+        if !signal("-TERM") {
+            return;
+        }
+        let deadline = std::time::Instant::now() + TERM_GRACE;
+        while std::time::Instant::now() < deadline {
+            if process_command(pid).is_none() {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        signal("-KILL");
+    }
+    #[cfg(windows)]
+    {
+        let _ = Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/T", "/F"])
+            .output();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::storage::StorageConfig;
+
+    #[test]
+    fn test_sweep_stops_leftover_processes() {
+        let storage = Storage::in_memory().unwrap();
+        let conn = storage.connection().unwrap();
+
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        let leftover = AgentProcess {
+            pid: child.id(),
+            agent_id: "gemini".to_string(),
+            command: process_command(child.id()).unwrap(),
+            process_started: process_start_time(child.id()).unwrap(),
+            started_at: chrono::Utc::now(),
+        };
+        // A PID that was reused by a different program is left alone
+        let reused = AgentProcess {
+            pid: std::process::id(),
+            agent_id: "codex".to_string(),
+            command: "codex-acp".to_string(),
+            process_started: process_start_time(std::process::id()).unwrap(),
+            started_at: chrono::Utc::now(),
+        };
+        // So is one reused by the same program, started later
+        let mut restarted = Command::new("sleep").arg("30").spawn().unwrap();
+        let earlier = AgentProcess {
+            pid: restarted.id(),
+            agent_id: "gemini".to_string(),
+            command: process_command(restarted.id()).unwrap(),
+            process_started: "0".to_string(),
+            started_at: chrono::Utc::now(),
+        };
+        record_agent_process(&conn, &leftover).unwrap();
+        record_agent_process(&conn, &reused).unwrap();
+        record_agent_process(&conn, &earlier).unwrap();
+        drop(conn);
+
+        assert_eq!(sweep_stale_agent_processes(&storage).unwrap(), vec![leftover]);
+        assert!(!child.wait().unwrap().success());
+        assert!(restarted.try_wait().unwrap().is_none());
+        restarted.kill().unwrap();
+        restarted.wait().unwrap();
+        assert!(list_agent_processes(&storage.connection().unwrap()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_read_only_storage_leaves_writers_processes() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("cocowork.db");
+        let writer = Storage::from_path(db_path.clone(), StorageConfig::default()).unwrap();
+
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        let running = AgentProcess {
+            pid: child.id(),
+            agent_id: "gemini".to_string(),
+            command: process_command(child.id()).unwrap(),
+            process_started: process_start_time(child.id()).unwrap_or_default(),
+            started_at: chrono::Utc::now(),
+        };
+        record_agent_process(&writer.connection().unwrap(), &running).unwrap();
+
+        let reader = Storage::from_path(db_path, StorageConfig::default()).unwrap();
+        assert!(reader.is_read_only());
+        assert!(sweep_stale_agent_processes(&reader).unwrap().is_empty());
+        assert!(child.try_wait().unwrap().is_none());
+        assert_eq!(list_agent_processes(&writer.connection().unwrap()).unwrap(), vec![running]);
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
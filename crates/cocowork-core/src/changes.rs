@@ -0,0 +1,188 @@
+//! Notifications of configuration changed while the app runs
+//!
+//! `config.toml` can be edited by hand, and the agents and settings tables
+//! are also written by the `cocowork` CLI and other instances.
+//! [`watch_for_changes`] checks them now and then and publishes what changed
+//! on a [`ChangeBus`], so the app can apply it without a restart.
+
+use crate::config::{Config, CONFIG_FILE};
+use crate::error::Result;
+use crate::storage::Storage;
+use rusqlite::types::ValueRef;
+use rusqlite::Connection;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, SystemTime};
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+/// How often the config file and database are checked
+pub const CHANGE_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Changes kept for a subscriber that hasn't caught up
+const BUS_CAPACITY: usize = 16;
+
+/// Something that changed since the last check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// `config.toml` changed: the config reloaded from it, or why it can't be read
+    Config(std::result::Result<Config, String>),
+    /// Custom agents were added, edited or removed
+    Agents,
+    /// Settings changed, tool policies among them
+    Settings,
+}
+
+/// Broadcasts [`Change`]s to whoever subscribed
+#[derive(Debug, Clone)]
+pub struct ChangeBus {
+    tx: broadcast::Sender<Change>,
+}
+
+impl ChangeBus {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(BUS_CAPACITY);
+        Self { tx }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Change> {
+        self.tx.subscribe()
+    }
+
+    /// Send `change` to every subscriber; dropped if there are none
+    pub fn publish(&self, change: Change) {
+        let _ = self.tx.send(change);
+    }
+}
+
+impl Default for ChangeBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What the watched sources looked like at one check
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Snapshot {
+    /// Modification time and size of the config file, if there is one
+    config_file: Option<(SystemTime, u64)>,
+    agents: u64,
+    settings: u64,
+}
+
+/// Compares the config file and database against the last check
+#[derive(Debug)]
+pub struct ChangeWatcher {
+    data_dir: PathBuf,
+    last: Option<Snapshot>,
+}
+
+impl ChangeWatcher {
+    /// Watch `config.toml` in `data_dir`
+    pub fn new(data_dir: impl Into<PathBuf>) -> Self {
+        Self { data_dir: data_dir.into(), last: None }
+    }
+
+    /// What changed since the last call; the first call only takes note
+    pub fn check(&mut self, storage: &Storage) -> Result<Vec<Change>> {
+        let conn = storage.connection()?;
+        let next = Snapshot {
+            config_file: file_stamp(&self.data_dir.join(CONFIG_FILE)),
+            agents: table_hash(&conn, "SELECT * FROM agents ORDER BY id")?,
+            settings: table_hash(&conn, "SELECT key, value FROM settings ORDER BY key")?,
+        };
+        let Some(last) = self.last.replace(next) else {
+            return Ok(Vec::new());
+        };
+
+        let mut changes = Vec::new();
+        if next.config_file != last.config_file {
+            info!("{} changed, reloading", CONFIG_FILE);
+            changes.push(Change::Config(Config::load(&self.data_dir).map_err(|e| e.to_string())));
+        }
+        if next.agents != last.agents {
+            changes.push(Change::Agents);
+        }
+        if next.settings != last.settings {
+            changes.push(Change::Settings);
+        }
+        Ok(changes)
+    }
+}
+
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let meta = std::fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+/// A hash of every row `sql` returns
+fn table_hash(conn: &Connection, sql: &str) -> Result<u64> {
+    let mut stmt = conn.prepare(sql)?;
+    let columns = stmt.column_count();
+    let mut rows = stmt.query([])?;
+    let mut hasher = DefaultHasher::new();
+    while let Some(row) = rows.next()? {
+        for i in 0..columns {
+            match row.get_ref(i)? {
+                ValueRef::Null => 0u8.hash(&mut hasher),
+                ValueRef::Integer(value) => value.hash(&mut hasher),
+                ValueRef::Real(value) => value.to_bits().hash(&mut hasher),
+                ValueRef::Text(bytes) | ValueRef::Blob(bytes) => bytes.hash(&mut hasher),
+            }
+        }
+    }
+    Ok(hasher.finish())
+}
+
+/// Publish changes to `config.toml` in `data_dir` and to the database on
+/// `bus`, checking every `check_every`
+pub async fn watch_for_changes(storage: Arc<Storage>, data_dir: PathBuf, bus: ChangeBus, check_every: Duration) {
+    let mut ticker = tokio::time::interval(check_every);
+    let watcher = Arc::new(Mutex::new(ChangeWatcher::new(data_dir)));
+    loop {
+        ticker.tick().await;
+        let storage = Arc::clone(&storage);
+        let watcher = Arc::clone(&watcher);
+        let result = tokio::task::spawn_blocking(move || {
+            watcher.lock().unwrap_or_else(PoisonError::into_inner).check(&storage)
+        })
+        .await;
+        match result {
+            Ok(Ok(changes)) => changes.into_iter().for_each(|change| bus.publish(change)),
+            Ok(Err(e)) => warn!("Failed to check for configuration changes: {}", e),
+            Err(e) => warn!("Configuration check panicked: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{set_setting, upsert_agent};
+    use crate::AgentConfig;
+
+    #[test]
+    fn test_change_watcher() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::in_memory().unwrap();
+        let mut watcher = ChangeWatcher::new(dir.path());
+        assert!(watcher.check(&storage).unwrap().is_empty());
+        assert!(watcher.check(&storage).unwrap().is_empty());
+
+        set_setting(&storage.connection().unwrap(), "tool_policy", "{}").unwrap();
+        assert_eq!(watcher.check(&storage).unwrap(), [Change::Settings]);
+
+        let agent = AgentConfig::new("local", "Local agent", "local-acp");
+        upsert_agent(&storage.connection().unwrap(), &agent).unwrap();
+        std::fs::write(dir.path().join(CONFIG_FILE), "offline = true\n").unwrap();
+        let changes = watcher.check(&storage).unwrap();
+        assert_eq!(changes.len(), 2);
+        assert!(matches!(&changes[0], Change::Config(Ok(config)) if config.offline));
+        assert_eq!(changes[1], Change::Agents);
+
+        std::fs::write(dir.path().join(CONFIG_FILE), "offline = maybe\n").unwrap();
+        assert!(matches!(&watcher.check(&storage).unwrap()[..], [Change::Config(Err(_))]));
+    }
+}
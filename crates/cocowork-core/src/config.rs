@@ -0,0 +1,238 @@
+//! Application configuration
+//!
+//! Settings are layered, each layer overriding the ones above it:
+//!
+//! 1. Built-in defaults
+//! 2. `config.toml` in the data directory
+//! 3. `COCOWORK_*` environment variables
+//! 4. Settings changed in the app, which are saved in the database
+//!
+//! [`Config`] holds the first three merged. The app applies its saved
+//! settings on top, so a value changed in settings wins until it's reset.
+//!
+//! ```toml
+//! offline = false
+//! protocol_log = false
+//!
+//! [proxy]
+//! https_proxy = "http://proxy.internal:3128"
+//! no_proxy = "localhost,.internal"
+//!
+//! [storage]
+//! pool_size = 10
+//! busy_timeout_secs = 5
+//!
+//! [agents]
+//! node_path = "/opt/node/bin/node"
+//! claude_code_acp_path = "/opt/claude-code-acp/dist/index.js"
+//! codex_acp_path = "/opt/bin/codex-acp"
+//! ```
+//!
+//! | Variable                        | Setting                       |
+//! |---------------------------------|-------------------------------|
+//! | `COCOWORK_OFFLINE`              | `offline`                     |
+//! | `COCOWORK_PROTOCOL_LOG`         | `protocol_log`                |
+//! | `COCOWORK_HTTP_PROXY`           | `proxy.http_proxy`            |
+//! | `COCOWORK_HTTPS_PROXY`          | `proxy.https_proxy`           |
+//! | `COCOWORK_NO_PROXY`             | `proxy.no_proxy`              |
+//! | `COCOWORK_DB_POOL_SIZE`         | `storage.pool_size`           |
+//! | `COCOWORK_DB_BUSY_TIMEOUT_SECS` | `storage.busy_timeout_secs`   |
+//! | `COCOWORK_NODE_PATH`            | `agents.node_path`            |
+//! | `CLAUDE_CODE_ACP_PATH`          | `agents.claude_code_acp_path` |
+//! | `CODEX_ACP_PATH`                | `agents.codex_acp_path`       |
+//!
+//! API keys aren't configuration: they come from the keychain, then the
+//! agents' own variables (see [`crate::secrets`]).
+
+use crate::error::{Error, Result};
+use crate::net::ProxySettings;
+use crate::storage::StorageConfig;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::warn;
+
+/// Name of the config file in the data directory
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Defaults, `config.toml` and environment variables merged
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Start in offline mode: no downloads, npm installs or update checks
+    pub offline: bool,
+    /// Log every protocol message to `logs/acp.jsonl`
+    pub protocol_log: bool,
+    pub proxy: ProxySettings,
+    pub storage: StorageSettings,
+    pub agents: AgentPaths,
+}
+
+/// Database connection settings; unset ones keep [`StorageConfig`]'s defaults
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageSettings {
+    pub pool_size: Option<u32>,
+    pub busy_timeout_secs: Option<u64>,
+}
+
+/// Where to find agents instead of installing them
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AgentPaths {
+    /// Node.js binary for npm-based agents
+    pub node_path: Option<String>,
+    /// Entry script of a local claude-code-acp checkout
+    pub claude_code_acp_path: Option<PathBuf>,
+    /// A codex-acp binary to use instead of downloading one
+    pub codex_acp_path: Option<PathBuf>,
+}
+
+impl Config {
+    /// Read `config.toml` from `data_dir` and apply the environment on top
+    ///
+    /// A missing file is fine; one that can't be parsed is an error.
+    pub fn load(data_dir: &Path) -> Result<Self> {
+        let mut config = Self::from_file(&data_dir.join(CONFIG_FILE))?;
+        config.apply_env(std::env::vars());
+        Ok(config)
+    }
+
+    /// Defaults with the environment applied, for when the file can't be used
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        config.apply_env(std::env::vars());
+        config
+    }
+
+    /// Read a config file, or the defaults if there is none
+    pub fn from_file(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => {
+                Self::from_toml(&text).map_err(|e| Error::InvalidConfig(format!("{}: {}", path.display(), e)))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self> {
+        toml::from_str(text).map_err(|e| Error::InvalidConfig(e.message().to_string()))
+    }
+
+    /// Override settings with the `COCOWORK_*` variables among `vars`
+    ///
+    /// Values that don't parse are logged and skipped.
+    pub fn apply_env(&mut self, vars: impl IntoIterator<Item = (String, String)>) {
+        for (name, value) in vars {
+            let value = value.trim();
+            if value.is_empty() {
+                continue;
+            }
+            match name.as_str() {
+                "COCOWORK_OFFLINE" => set_parsed(&name, value, parse_bool, &mut self.offline),
+                "COCOWORK_PROTOCOL_LOG" => set_parsed(&name, value, parse_bool, &mut self.protocol_log),
+                "COCOWORK_HTTP_PROXY" => self.proxy.http_proxy = Some(value.to_string()),
+                "COCOWORK_HTTPS_PROXY" => self.proxy.https_proxy = Some(value.to_string()),
+                "COCOWORK_NO_PROXY" => self.proxy.no_proxy = Some(value.to_string()),
+                "COCOWORK_DB_POOL_SIZE" => set_parsed(&name, value, parse_some, &mut self.storage.pool_size),
+                "COCOWORK_DB_BUSY_TIMEOUT_SECS" => {
+                    set_parsed(&name, value, parse_some, &mut self.storage.busy_timeout_secs)
+                }
+                "COCOWORK_NODE_PATH" => self.agents.node_path = Some(value.to_string()),
+                "CLAUDE_CODE_ACP_PATH" => self.agents.claude_code_acp_path = Some(PathBuf::from(value)),
+                "CODEX_ACP_PATH" => self.agents.codex_acp_path = Some(PathBuf::from(value)),
+                _ => {}
+            }
+        }
+    }
+
+    /// Connection settings for opening the database
+    pub fn storage_config(&self) -> StorageConfig {
+        let mut config = StorageConfig::default();
+        if let Some(pool_size) = self.storage.pool_size {
+            config = config.with_pool_size(pool_size);
+        }
+        if let Some(secs) = self.storage.busy_timeout_secs {
+            config = config.with_busy_timeout(Duration::from_secs(secs));
+        }
+        config
+    }
+}
+
+fn set_parsed<T>(name: &str, value: &str, parse: impl Fn(&str) -> Option<T>, target: &mut T) {
+    match parse(value) {
+        Some(parsed) => *target = parsed,
+        None => warn!("Ignoring {}: can't use {:?}", name, value),
+    }
+}
+
+fn parse_some<T: std::str::FromStr>(value: &str) -> Option<Option<T>> {
+    value.parse().ok().map(Some)
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_layers() {
+        let mut config = Config::from_toml(
+            r#"
+            protocol_log = true
+
+            [proxy]
+            https_proxy = "http://file:3128"
+
+            [storage]
+            pool_size = 4
+
+            [agents]
+            node_path = "/opt/node"
+            "#,
+        )
+        .unwrap();
+        config.apply_env(vars(&[
+            ("COCOWORK_HTTPS_PROXY", "http://env:3128"),
+            ("COCOWORK_OFFLINE", "yes"),
+            ("COCOWORK_DB_BUSY_TIMEOUT_SECS", "30"),
+            ("COCOWORK_DB_POOL_SIZE", "lots"),
+            ("CODEX_ACP_PATH", "/opt/codex-acp"),
+            ("HOME", "/home/me"),
+        ]));
+
+        assert!(config.offline && config.protocol_log);
+        assert_eq!(config.proxy.https_proxy.as_deref(), Some("http://env:3128"));
+        assert_eq!(config.agents.node_path.as_deref(), Some("/opt/node"));
+        assert_eq!(config.agents.codex_acp_path, Some(PathBuf::from("/opt/codex-acp")));
+
+        // The bad pool size leaves the file's in place
+        let storage = config.storage_config();
+        assert_eq!(storage.pool_size, 4);
+        assert_eq!(storage.busy_timeout, Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(Config::from_file(&dir.path().join(CONFIG_FILE)).unwrap(), Config::default());
+        assert_eq!(Config::default().storage_config(), StorageConfig::default());
+
+        std::fs::write(dir.path().join(CONFIG_FILE), "[storage]\npool_sise = 4\n").unwrap();
+        let err = Config::from_file(&dir.path().join(CONFIG_FILE)).unwrap_err();
+        assert!(matches!(err, Error::InvalidConfig(_)));
+        assert!(err.to_string().contains("pool_sise"));
+    }
+}
@@ -23,10 +23,46 @@ pub enum Error {
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
 
+    #[error("Secret store error: {0}")]
+    Secret(String),
+
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+
+    #[error("Download failed: {0}")]
+    Download(String),
+
+    #[error("Offline mode is on, so {0} is turned off")]
+    Offline(String),
+
+    #[error("Invalid search pattern: {0}")]
+    InvalidPattern(String),
+
+    #[error("Git failed: {0}")]
+    Git(String),
+
+    #[error("Pull request failed: {0}")]
+    PullRequest(String),
+
+    #[error("Issue import failed: {0}")]
+    Issue(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
 }
 
+impl Error {
+    /// Whether the agent refused the request until the user signs in
+    pub fn is_auth_required(&self) -> bool {
+        matches!(self, Error::Acp(AcpError::AuthRequired(_)))
+    }
+
+    /// Whether the database file is damaged and needs restoring from a backup
+    pub fn is_storage_corrupt(&self) -> bool {
+        matches!(self, Error::Storage(StorageError::Corrupt(_)))
+    }
+}
+
 /// ACP-specific errors
 #[derive(Error, Debug)]
 pub enum AcpError {
@@ -53,6 +89,9 @@ pub enum AcpError {
 
     #[error("Capability not supported: {0}")]
     CapabilityNotSupported(String),
+
+    #[error("Authentication required: {0}")]
+    AuthRequired(String),
 }
 
 /// Agent management errors
@@ -100,6 +139,15 @@ pub enum StorageError {
 
     #[error("Connection pool error: {0}")]
     Pool(String),
+
+    #[error("Storage writer unavailable: {0}")]
+    Writer(String),
+
+    #[error("Database is corrupt: {0}")]
+    Corrupt(String),
+
+    #[error("Encryption error: {0}")]
+    Encryption(String),
 }
 
 /// Sandbox/filesystem errors
@@ -125,6 +173,21 @@ pub enum SandboxError {
 
     #[error("Invalid path: {0}")]
     InvalidPath(String),
+
+    #[error("File too large: {0}")]
+    FileTooLarge(String),
+
+    #[error("Not a text file: {0}")]
+    NotTextFile(String),
+
+    #[error("Rejected by user: {0}")]
+    RejectedByUser(String),
+
+    #[error("Limit exceeded: {0}")]
+    LimitExceeded(String),
+
+    #[error("Changed in the workspace too: {0}")]
+    MergeConflict(String),
 }
 
 impl From<rusqlite::Error> for Error {
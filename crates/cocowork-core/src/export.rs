@@ -0,0 +1,740 @@
+//! Exporting threads as documents
+//!
+//! Renders a conversation as Markdown or as a standalone HTML page, with
+//! thinking and compacted summaries in collapsed sections and tool calls
+//! listed with their status, duration and diffs. Lives in core so the app
+//! and command-line tools produce the same documents. Threads also render as
+//! JSON for scripts and as plain or ANSI-colored text for a terminal. The
+//! audit log exports as CSV or JSON for processing elsewhere.
+
+use crate::error::Result;
+use crate::types::{AuditEntry, ContentBlock, DiffLineKind, MessageBlock, ToolCallContent, ToolCallState};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Styles embedded in exported HTML pages
+const HTML_STYLE: &str = r#"
+body { margin: 0; background: #fafaf9; color: #1c1917; font: 15px/1.6 -apple-system, BlinkMacSystemFont, "Segoe UI", sans-serif; }
+main { max-width: 820px; margin: 0 auto; padding: 32px 24px 64px; }
+h1 { font-size: 24px; margin: 0 0 4px; }
+.meta { color: #78716c; font-size: 13px; margin: 0 0 24px; }
+.message { margin: 20px 0; }
+.message h3 { font-size: 13px; color: #78716c; margin: 0 0 4px; font-weight: 600; }
+.message h3 time { font-weight: 400; margin-left: 6px; }
+.user { background: #f0efed; border-radius: 8px; padding: 10px 16px; }
+.system { color: #78716c; font-size: 13px; }
+details { margin: 12px 0; border: 1px solid #e7e5e4; border-radius: 8px; padding: 8px 12px; background: #fff; }
+details summary { cursor: pointer; color: #57534e; font-size: 13px; }
+details.thinking, details.summary { color: #57534e; }
+.status { margin-left: 6px; padding: 1px 6px; border-radius: 4px; font-size: 12px; background: #f5f5f4; }
+.status.completed { color: #2d8f6f; }
+.status.failed, .status.cancelled { color: #dc2626; }
+.duration { color: #a8a29e; margin-left: 6px; }
+pre { background: #1c1917; color: #e7e5e4; padding: 12px; border-radius: 6px; overflow-x: auto; font-size: 13px; }
+code { font-family: "SF Mono", Menlo, Consolas, monospace; }
+:not(pre) > code { background: #f0efed; padding: 1px 4px; border-radius: 4px; }
+.diff .add { color: #86efac; }
+.diff .remove { color: #fca5a5; }
+.diff .path { color: #a8a29e; }
+"#;
+
+/// Document format for an exported thread
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+    /// The messages and tool calls as stored, for scripts
+    Json,
+}
+
+impl ExportFormat {
+    /// File extension without the dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Html => "html",
+            Self::Json => "json",
+        }
+    }
+
+    /// The format for an extension or name like `md` or `markdown`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "md" | "markdown" => Some(Self::Markdown),
+            "html" => Some(Self::Html),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+/// File format for an exported audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditExportFormat {
+    Csv,
+    Json,
+}
+
+impl AuditExportFormat {
+    /// File extension without the dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// Render audit entries as CSV with a header row, or as a JSON array
+pub fn export_audit_log(entries: &[AuditEntry], format: AuditExportFormat) -> String {
+    match format {
+        AuditExportFormat::Json => serde_json::to_string_pretty(entries).unwrap_or_else(|_| "[]".to_string()),
+        AuditExportFormat::Csv => {
+            let mut out = String::from("timestamp,session_id,operation,target,outcome,detail\n");
+            for entry in entries {
+                let fields = [
+                    entry.created_at.to_rfc3339(),
+                    entry.session_id.clone(),
+                    entry.operation.clone(),
+                    entry.target.clone(),
+                    entry.outcome.as_str().to_string(),
+                    entry.detail.clone().unwrap_or_default(),
+                ];
+                let row: Vec<String> = fields.iter().map(|field| escape_csv(field)).collect();
+                let _ = writeln!(out, "{}", row.join(","));
+            }
+            out
+        }
+    }
+}
+
+/// A thread's conversation and tool calls, ready to render
+///
+/// The JSON form reads back in, so a thread can be shared as an archive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadExport {
+    pub title: String,
+    /// Name of the agent the thread ran with
+    pub agent: Option<String>,
+    pub working_dir: Option<String>,
+    pub messages: Vec<MessageBlock>,
+    pub tool_calls: Vec<ToolCallState>,
+    pub exported_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A message or tool call in time order
+enum Entry<'a> {
+    Message(&'a MessageBlock),
+    ToolCall(&'a ToolCallState),
+}
+
+impl ThreadExport {
+    pub fn new(
+        title: impl Into<String>,
+        messages: Vec<MessageBlock>,
+        tool_calls: Vec<ToolCallState>,
+    ) -> Self {
+        Self {
+            title: title.into(),
+            agent: None,
+            working_dir: None,
+            messages,
+            tool_calls,
+            exported_at: chrono::Utc::now(),
+        }
+    }
+
+    pub fn with_agent(mut self, agent: impl Into<String>) -> Self {
+        self.agent = Some(agent.into());
+        self
+    }
+
+    pub fn with_working_dir(mut self, working_dir: impl Into<String>) -> Self {
+        self.working_dir = Some(working_dir.into());
+        self
+    }
+
+    /// A file name for the export, e.g. `fix-the-build.md`
+    pub fn file_name(&self, format: ExportFormat) -> String {
+        let slug = self
+            .title
+            .to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>()
+            .join("-");
+        let slug = if slug.is_empty() { "thread".to_string() } else { slug };
+        format!("{}.{}", slug, format.extension())
+    }
+
+    pub fn render(&self, format: ExportFormat) -> String {
+        match format {
+            ExportFormat::Markdown => self.to_markdown(),
+            ExportFormat::Html => self.to_html(),
+            ExportFormat::Json => self.to_json(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    /// Read a thread written by [`ThreadExport::to_json`]
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// The thread as text for a terminal, with ANSI colors when `color` is set
+    ///
+    /// Thinking and compacted summaries are shown dimmed in full rather than
+    /// collapsed, and tool calls as one line each with their diffs below.
+    pub fn to_terminal(&self, color: bool) -> String {
+        let paint = |code: &str, text: &str| {
+            if color {
+                format!("\x1b[{}m{}\x1b[0m", code, text)
+            } else {
+                text.to_string()
+            }
+        };
+        let indented = |text: &str| {
+            text.trim()
+                .lines()
+                .map(|line| format!("  {}", line))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        let mut out = String::new();
+        let _ = writeln!(out, "{}", paint("1", &self.title));
+        let _ = writeln!(out, "{}\n", paint("2", &self.meta_line(str::to_string, str::to_string)));
+
+        for entry in self.timeline() {
+            match entry {
+                Entry::Message(MessageBlock::User { content, timestamp }) => {
+                    let _ = writeln!(out, "{} {}", paint("1;36", "You"), paint("2", &timestamp.format("%H:%M").to_string()));
+                    let _ = writeln!(out, "{}\n", indented(&content_text(content)));
+                }
+                Entry::Message(MessageBlock::Agent { content, timestamp }) => {
+                    let label = self.agent_label();
+                    let _ = writeln!(out, "{} {}", paint("1;32", &label), paint("2", &timestamp.format("%H:%M").to_string()));
+                    let _ = writeln!(out, "{}\n", indented(&content_text(content)));
+                }
+                Entry::Message(MessageBlock::Thought { content, .. }) => {
+                    let _ = writeln!(out, "{}", paint("2;3", "Thinking"));
+                    let _ = writeln!(out, "{}\n", paint("2", &indented(&content_text(content))));
+                }
+                Entry::Message(MessageBlock::Summary { content, .. }) => {
+                    let _ = writeln!(out, "{}", paint("2;3", "Summary of earlier turns"));
+                    let _ = writeln!(out, "{}\n", paint("2", &indented(content)));
+                }
+                Entry::Message(MessageBlock::System { content, .. }) => {
+                    let _ = writeln!(out, "{}\n", paint("33", &indented(content)));
+                }
+                Entry::ToolCall(tool_call) => {
+                    let _ = writeln!(out, "  {} {}", paint("35", "⏺"), tool_call_heading(tool_call));
+                    for content in &tool_call.content {
+                        let ToolCallContent::Diff { diff } = content else {
+                            continue;
+                        };
+                        for line in diff_text(&diff.path, &diff.hunks).lines() {
+                            let code = if line.starts_with("---") || line.starts_with("+++") {
+                                "2"
+                            } else {
+                                match line.chars().next() {
+                                    Some('+') => "32",
+                                    Some('-') => "31",
+                                    Some('@') => "36",
+                                    _ => "",
+                                }
+                            };
+                            let line = format!("    {}", line);
+                            let _ = writeln!(out, "{}", if code.is_empty() { line } else { paint(code, &line) });
+                        }
+                    }
+                    out.push('\n');
+                }
+            }
+        }
+        out
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# {}\n", self.title);
+        let _ = writeln!(out, "_{}_\n", self.meta_line(str::to_string, |dir| format!("`{}`", dir)));
+
+        for entry in self.timeline() {
+            match entry {
+                Entry::Message(MessageBlock::User { content, timestamp }) => {
+                    let _ = writeln!(out, "### User · {}\n", timestamp.format("%H:%M"));
+                    let _ = writeln!(out, "{}\n", content_text(content).trim());
+                }
+                Entry::Message(MessageBlock::Agent { content, timestamp }) => {
+                    let _ = writeln!(out, "### {} · {}\n", self.agent_label(), timestamp.format("%H:%M"));
+                    let _ = writeln!(out, "{}\n", content_text(content).trim());
+                }
+                Entry::Message(MessageBlock::Thought { content, .. }) => {
+                    push_markdown_details(&mut out, "Thinking", &content_text(content));
+                }
+                Entry::Message(MessageBlock::Summary { content, .. }) => {
+                    push_markdown_details(&mut out, "Summary of earlier turns", content);
+                }
+                Entry::Message(MessageBlock::System { content, .. }) => {
+                    for line in content.lines() {
+                        let _ = writeln!(out, "> {}", line);
+                    }
+                    out.push('\n');
+                }
+                Entry::ToolCall(tool_call) => {
+                    let _ = writeln!(out, "**Tool call:** {}\n", tool_call_heading(tool_call));
+                    for content in &tool_call.content {
+                        match content {
+                            ToolCallContent::Diff { diff } => {
+                                let text = diff_text(&diff.path, &diff.hunks);
+                                push_code_block(&mut out, "diff", &text);
+                            }
+                            ToolCallContent::Content { content } => {
+                                if let ContentBlock::Text { text } = content {
+                                    push_code_block(&mut out, "", text);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut body = String::new();
+        let _ = writeln!(body, "<h1>{}</h1>", escape_html(&self.title));
+        let _ = writeln!(
+            body,
+            "<p class=\"meta\">{}</p>",
+            self.meta_line(escape_html, |dir| format!("<code>{}</code>", escape_html(dir)))
+        );
+
+        for entry in self.timeline() {
+            match entry {
+                Entry::Message(MessageBlock::User { content, timestamp }) => {
+                    push_html_message(&mut body, "user", "User", timestamp, &content_text(content));
+                }
+                Entry::Message(MessageBlock::Agent { content, timestamp }) => {
+                    let label = escape_html(&self.agent_label());
+                    push_html_message(&mut body, "agent", &label, timestamp, &content_text(content));
+                }
+                Entry::Message(MessageBlock::Thought { content, .. }) => {
+                    let _ = writeln!(
+                        body,
+                        "<details class=\"thinking\"><summary>Thinking</summary>\n{}</details>",
+                        markdown_to_html(&content_text(content))
+                    );
+                }
+                Entry::Message(MessageBlock::Summary { content, .. }) => {
+                    let _ = writeln!(
+                        body,
+                        "<details class=\"summary\"><summary>Summary of earlier turns</summary>\n{}</details>",
+                        markdown_to_html(content)
+                    );
+                }
+                Entry::Message(MessageBlock::System { content, .. }) => {
+                    let _ = writeln!(body, "<p class=\"system\">{}</p>", escape_html(content));
+                }
+                Entry::ToolCall(tool_call) => push_html_tool_call(&mut body, tool_call),
+            }
+        }
+
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             <title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<main>\n{}</main>\n</body>\n</html>\n",
+            escape_html(&self.title),
+            HTML_STYLE,
+            body
+        )
+    }
+
+    /// "Agent: … · Workspace: … · Exported …", with the agent formatted by
+    /// `text` and the workspace by `dir`
+    fn meta_line(&self, text: impl Fn(&str) -> String, dir: impl Fn(&str) -> String) -> String {
+        let mut parts = Vec::new();
+        if let Some(agent) = &self.agent {
+            parts.push(format!("Agent: {}", text(agent)));
+        }
+        if let Some(working_dir) = &self.working_dir {
+            parts.push(format!("Workspace: {}", dir(working_dir)));
+        }
+        parts.push(format!("Exported {}", self.exported_at.format("%Y-%m-%d %H:%M UTC")));
+        parts.join(" · ")
+    }
+
+    fn agent_label(&self) -> String {
+        self.agent.clone().unwrap_or_else(|| "Agent".to_string())
+    }
+
+    /// Messages and tool calls by time; messages first when tied
+    fn timeline(&self) -> Vec<Entry<'_>> {
+        let mut entries = self
+            .messages
+            .iter()
+            .map(|m| (m.timestamp(), 0, Entry::Message(m)))
+            .chain(self.tool_calls.iter().map(|tc| (tc.started_at, 1, Entry::ToolCall(tc))))
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|(timestamp, order, _)| (*timestamp, *order));
+        entries.into_iter().map(|(_, _, entry)| entry).collect()
+    }
+}
+
+fn content_text(content: &[ContentBlock]) -> String {
+    content
+        .iter()
+        .filter_map(|c| match c {
+            ContentBlock::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// "Edit src/lib.rs · edit · completed · 1.2s"
+fn tool_call_heading(tool_call: &ToolCallState) -> String {
+    let mut parts = vec![tool_call.title.clone().unwrap_or_else(|| tool_call.id.clone())];
+    if let Some(kind) = tool_call.kind {
+        parts.push(format!("{:?}", kind).to_lowercase());
+    }
+    parts.push(format!("{:?}", tool_call.status).to_lowercase());
+    if let Some(duration) = tool_call.duration() {
+        parts.push(format_duration(duration));
+    }
+    parts.join(" · ")
+}
+
+/// Short form of a duration, e.g. "850ms", "1.2s" or "3m 05s"
+pub fn format_duration(duration: chrono::Duration) -> String {
+    let millis = duration.num_milliseconds().max(0);
+    if millis < 1000 {
+        format!("{}ms", millis)
+    } else if millis < 60_000 {
+        format!("{:.1}s", millis as f64 / 1000.0)
+    } else {
+        format!("{}m {:02}s", millis / 60_000, (millis % 60_000) / 1000)
+    }
+}
+
+/// Unified diff text for a file's hunks
+fn diff_text(path: &str, hunks: &[crate::types::DiffHunk]) -> String {
+    let mut out = format!("--- {}\n+++ {}\n", path, path);
+    for hunk in hunks {
+        let _ = writeln!(
+            out,
+            "@@ -{},{} +{},{} @@",
+            hunk.old_start, hunk.old_lines, hunk.new_start, hunk.new_lines
+        );
+        for line in &hunk.lines {
+            let prefix = match line.kind {
+                DiffLineKind::Context => ' ',
+                DiffLineKind::Add => '+',
+                DiffLineKind::Remove => '-',
+            };
+            let _ = writeln!(out, "{}{}", prefix, line.content.trim_end_matches('\n'));
+        }
+    }
+    out
+}
+
+fn push_markdown_details(out: &mut String, summary: &str, text: &str) {
+    let _ = writeln!(
+        out,
+        "<details>\n<summary>{}</summary>\n\n{}\n\n</details>\n",
+        summary,
+        text.trim()
+    );
+}
+
+/// Fenced code block, with a fence longer than any backtick run in `text`
+fn push_code_block(out: &mut String, language: &str, text: &str) {
+    let longest_run = text
+        .split(|c| c != '`')
+        .map(|run| run.len())
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat((longest_run + 1).max(3));
+    let _ = writeln!(out, "{}{}\n{}\n{}\n", fence, language, text.trim_end(), fence);
+}
+
+fn push_html_message(
+    out: &mut String,
+    class: &str,
+    label: &str,
+    timestamp: &chrono::DateTime<chrono::Utc>,
+    text: &str,
+) {
+    let _ = writeln!(
+        out,
+        "<section class=\"message {}\"><h3>{}<time datetime=\"{}\">{}</time></h3>\n{}</section>",
+        class,
+        label,
+        timestamp.to_rfc3339(),
+        timestamp.format("%H:%M"),
+        markdown_to_html(text)
+    );
+}
+
+fn push_html_tool_call(out: &mut String, tool_call: &ToolCallState) {
+    let status = format!("{:?}", tool_call.status).to_lowercase();
+    let _ = write!(
+        out,
+        "<details class=\"tool-call\"><summary>{}<span class=\"status {}\">{}</span>",
+        escape_html(tool_call.title.as_deref().unwrap_or(&tool_call.id)),
+        status,
+        status
+    );
+    if let Some(duration) = tool_call.duration() {
+        let _ = write!(out, "<span class=\"duration\">{}</span>", format_duration(duration));
+    }
+    out.push_str("</summary>\n");
+
+    for content in &tool_call.content {
+        match content {
+            ToolCallContent::Diff { diff } => {
+                out.push_str("<pre class=\"diff\"><code>");
+                for line in diff_text(&diff.path, &diff.hunks).lines() {
+                    let class = if line.starts_with("+++") || line.starts_with("---") || line.starts_with("@@") {
+                        "path"
+                    } else if line.starts_with('+') {
+                        "add"
+                    } else if line.starts_with('-') {
+                        "remove"
+                    } else {
+                        "context"
+                    };
+                    let _ = writeln!(out, "<span class=\"{}\">{}</span>", class, escape_html(line));
+                }
+                out.push_str("</code></pre>\n");
+            }
+            ToolCallContent::Content { content } => {
+                if let ContentBlock::Text { text } = content {
+                    let _ = writeln!(out, "<pre><code>{}</code></pre>", escape_html(text.trim_end()));
+                }
+            }
+        }
+    }
+    out.push_str("</details>\n");
+}
+
+/// Render Markdown to HTML, showing any raw HTML in it as text
+fn markdown_to_html(text: &str) -> String {
+    use pulldown_cmark::{html, Event, Options, Parser, Tag, TagEnd};
+
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    // Whether each open link or image was kept, so its end tag can follow suit
+    let mut kept = Vec::new();
+    let events = Parser::new_ext(text, options).filter_map(move |event| match event {
+        Event::Html(html) | Event::InlineHtml(html) => Some(Event::Text(html)),
+        // Links and images elsewhere than the web or an email address are left as their text
+        Event::Start(Tag::Link { ref dest_url, .. } | Tag::Image { ref dest_url, .. }) => {
+            let safe = is_safe_url(dest_url);
+            kept.push(safe);
+            safe.then_some(event)
+        }
+        Event::End(TagEnd::Link | TagEnd::Image) => kept.pop().unwrap_or(true).then_some(event),
+        event => Some(event),
+    });
+    let mut out = String::new();
+    html::push_html(&mut out, events);
+    out
+}
+
+/// Whether a link is relative or goes to an http, https or mailto URL
+fn is_safe_url(url: &str) -> bool {
+    // Browsers ignore whitespace and control characters in the scheme
+    let url: String = url.chars().filter(|c| !c.is_whitespace() && !c.is_control()).collect();
+    match url.split_once(':') {
+        Some((scheme, _)) if !scheme.contains(['/', '?', '#']) => {
+            ["http", "https", "mailto"].iter().any(|safe| scheme.eq_ignore_ascii_case(safe))
+        }
+        _ => true,
+    }
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DiffHunk, DiffLine, FileDiff, ToolCallKind, ToolCallStatus};
+
+    fn sample() -> ThreadExport {
+        let start = chrono::Utc::now();
+        let at = |secs| start + chrono::Duration::seconds(secs);
+        let text = |text: &str| vec![ContentBlock::Text { text: text.to_string() }];
+
+        let mut edit = ToolCallState::new(
+            "tc-1".to_string(),
+            Some("Edit src/lib.rs".to_string()),
+            Some(ToolCallKind::Edit),
+        );
+        edit.status = ToolCallStatus::Completed;
+        edit.started_at = at(2);
+        edit.completed_at = Some(at(2) + chrono::Duration::milliseconds(1200));
+        edit.content = vec![ToolCallContent::Diff {
+            diff: FileDiff {
+                path: "src/lib.rs".to_string(),
+                hunks: vec![DiffHunk {
+                    old_start: 1,
+                    old_lines: 1,
+                    new_start: 1,
+                    new_lines: 1,
+                    lines: vec![
+                        DiffLine { kind: DiffLineKind::Remove, content: "fn old() {}".to_string() },
+                        DiffLine { kind: DiffLineKind::Add, content: "fn new() {}".to_string() },
+                    ],
+                }],
+            },
+        }];
+
+        ThreadExport::new(
+            "Rename <old> helper",
+            vec![
+                MessageBlock::User { content: text("Rename `old`"), timestamp: at(0) },
+                MessageBlock::Thought { content: text("Find callers first"), timestamp: at(1) },
+                MessageBlock::Agent { content: text("Renamed it."), timestamp: at(3) },
+            ],
+            vec![edit],
+        )
+        .with_agent("Claude Code")
+        .with_working_dir("/work/app")
+    }
+
+    #[test]
+    fn test_markdown_export() {
+        let markdown = sample().to_markdown();
+
+        assert!(markdown.starts_with("# Rename <old> helper\n"));
+        assert!(markdown.contains("Agent: Claude Code · Workspace: `/work/app`"));
+        assert!(markdown.contains("<details>\n<summary>Thinking</summary>\n\nFind callers first"));
+        assert!(markdown.contains("**Tool call:** Edit src/lib.rs · edit · completed · 1.2s"));
+        assert!(markdown.contains("```diff\n--- src/lib.rs\n+++ src/lib.rs\n@@ -1,1 +1,1 @@\n-fn old() {}\n+fn new() {}\n```"));
+
+        // Tool calls sit between the turns they happened in
+        let thinking = markdown.find("Thinking").unwrap();
+        let tool_call = markdown.find("**Tool call:**").unwrap();
+        let reply = markdown.find("Renamed it.").unwrap();
+        assert!(thinking < tool_call && tool_call < reply);
+    }
+
+    #[test]
+    fn test_html_export() {
+        let export = sample();
+        let html = export.to_html();
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>Rename &lt;old&gt; helper</title>"));
+        assert!(html.contains("<details class=\"thinking\"><summary>Thinking</summary>"));
+        assert!(html.contains("<span class=\"duration\">1.2s</span>"));
+        assert!(html.contains("<span class=\"add\">+fn new() {}</span>"));
+        assert_eq!(export.file_name(ExportFormat::Html), "rename-old-helper.html");
+    }
+
+    #[test]
+    fn test_html_export_neutralizes_agent_text() {
+        let message = "[run](javascript:alert(1)) ![pic](data:text/html,x) \
+                       [site](https://example.com) [doc](docs/a.md)";
+        let html = ThreadExport::new(
+            "Links",
+            vec![MessageBlock::Agent {
+                content: vec![ContentBlock::Text { text: message.to_string() }],
+                timestamp: chrono::Utc::now(),
+            }],
+            Vec::new(),
+        )
+        .with_agent("<img src=x onerror=alert(1)>")
+        .to_html();
+
+        assert!(html.contains("Agent: &lt;img src=x onerror=alert(1)&gt;"));
+        assert!(!html.contains("<img src=x"));
+        assert!(!html.contains("javascript:") && !html.contains("data:"));
+        assert!(html.contains("run") && html.contains("pic"));
+        assert!(html.contains("<a href=\"https://example.com\">site</a>"));
+        assert!(html.contains("<a href=\"docs/a.md\">doc</a>"));
+
+        assert!(!is_safe_url(" Java\tScript:alert(1)"));
+        assert!(is_safe_url("mailto:ana@example.com"));
+        assert!(is_safe_url("docs/a:b.md"));
+    }
+
+    #[test]
+    fn test_terminal_and_json_export() {
+        let export = sample();
+        let plain = export.to_terminal(false);
+        assert!(plain.starts_with("Rename <old> helper\nAgent: Claude Code · Workspace: /work/app"));
+        assert!(plain.contains("  Rename `old`\n"));
+        assert!(plain.contains("  ⏺ Edit src/lib.rs · edit · completed · 1.2s\n    --- src/lib.rs\n"));
+        assert!(plain.contains("    +fn new() {}\n"));
+        assert!(!plain.contains('\x1b'));
+
+        let colored = export.to_terminal(true);
+        assert!(colored.contains("\x1b[1;36mYou\x1b[0m"));
+        assert!(colored.contains("\x1b[32m    +fn new() {}\x1b[0m"));
+
+        let json: serde_json::Value = serde_json::from_str(&export.render(ExportFormat::Json)).unwrap();
+        assert_eq!(json["title"], "Rename <old> helper");
+        assert_eq!(json["messages"][0]["role"], "user");
+        assert_eq!(json["tool_calls"][0]["status"], "completed");
+        let read_back = ThreadExport::from_json(&export.to_json()).unwrap();
+        assert_eq!(read_back.to_markdown(), export.to_markdown());
+        assert!(ThreadExport::from_json("{}").is_err());
+        assert_eq!(ExportFormat::from_name("markdown"), Some(ExportFormat::Markdown));
+        assert_eq!(ExportFormat::from_name("pdf"), None);
+    }
+
+    #[test]
+    fn test_code_block_fence_outgrows_backticks() {
+        let mut out = String::new();
+        push_code_block(&mut out, "", "use ``` for code");
+        assert!(out.starts_with("````\n"));
+        assert!(out.ends_with("\n````\n\n"));
+    }
+
+    #[test]
+    fn test_export_audit_log() {
+        use crate::types::AuditOutcome;
+
+        let entries = vec![
+            AuditEntry::new("s1", "execute", "echo \"a, b\"", AuditOutcome::Approved).with_detail("exit code 0"),
+            AuditEntry::new("s1", "read", "/work/main.rs", AuditOutcome::Allowed),
+        ];
+
+        let csv = export_audit_log(&entries, AuditExportFormat::Csv);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "timestamp,session_id,operation,target,outcome,detail");
+        assert!(lines[1].ends_with(",s1,execute,\"echo \"\"a, b\"\"\",approved,exit code 0"));
+        assert!(lines[2].ends_with(",s1,read,/work/main.rs,allowed,"));
+
+        let json: Vec<AuditEntry> =
+            serde_json::from_str(&export_audit_log(&entries, AuditExportFormat::Json)).unwrap();
+        assert_eq!(json, entries);
+    }
+}
@@ -0,0 +1,550 @@
+//! Pull requests on GitHub and GitLab, and GitHub issues
+//!
+//! The thread's branch is pushed with the user's own git credentials, then
+//! the pull request (a merge request on GitLab) is opened through the
+//! forge's REST API with a token kept in the keychain. Which forge and
+//! repository to use comes from the `origin` remote's URL. Besides
+//! github.com and gitlab.com, only GitLab servers the user has added are
+//! recognized, and each host has a token of its own.
+//!
+//! A thread can also start from a GitHub issue: its title, description and
+//! comments are fetched and become the thread's first prompt.
+
+use crate::error::{Error, Result};
+use crate::git::git;
+use crate::net::{ensure_online, ProxySettings};
+use crate::secrets::{SecretKey, SecretStore};
+use crate::types::TaskCommit;
+use serde::Deserialize;
+use serde_json::json;
+use std::path::Path;
+use std::time::Duration;
+
+/// Remote the branch is pushed to and the pull request opened against
+pub const REMOTE: &str = "origin";
+
+/// The public GitHub, whose token can also come from `GITHUB_TOKEN`
+const GITHUB_HOST: &str = "github.com";
+
+/// The public GitLab, whose token can also come from `GITLAB_TOKEN`
+const GITLAB_HOST: &str = "gitlab.com";
+
+/// How long the forge may take to answer
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A code host pull requests can be opened on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ForgeKind {
+    GitHub,
+    GitLab,
+}
+
+impl ForgeKind {
+    pub const ALL: [ForgeKind; 2] = [ForgeKind::GitHub, ForgeKind::GitLab];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ForgeKind::GitHub => "GitHub",
+            ForgeKind::GitLab => "GitLab",
+        }
+    }
+}
+
+/// A repository on a forge, as named by a git remote
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForgeRemote {
+    pub kind: ForgeKind,
+    pub host: String,
+    /// `owner/repo` on GitHub; `group/subgroup/project` on GitLab
+    pub path: String,
+}
+
+impl ForgeRemote {
+    /// Read an `https://`, `ssh://` or scp-style (`git@host:path`) remote URL
+    ///
+    /// Hosts other than github.com, gitlab.com and the self-hosted GitLab
+    /// servers in `gitlab_hosts` aren't recognized.
+    pub fn parse(url: &str, gitlab_hosts: &[String]) -> Option<Self> {
+        let (host, path) = split_remote(url)?;
+        let kind = if host == GITHUB_HOST {
+            ForgeKind::GitHub
+        } else if host == GITLAB_HOST || gitlab_hosts.contains(&host) {
+            ForgeKind::GitLab
+        } else {
+            return None;
+        };
+        (path.matches('/').count() >= 1).then_some(Self { kind, host, path })
+    }
+
+    /// Base URL of the forge's REST API
+    pub fn api_base(&self) -> String {
+        match self.kind {
+            ForgeKind::GitHub => "https://api.github.com".to_string(),
+            ForgeKind::GitLab => format!("https://{}/api/v4", self.host),
+        }
+    }
+
+    /// The keychain account holding the API token for this host
+    pub fn token_account(&self) -> String {
+        match self.kind {
+            ForgeKind::GitHub => SecretKey::GitHubToken.env_var().to_string(),
+            ForgeKind::GitLab if self.host == GITLAB_HOST => SecretKey::GitLabToken.env_var().to_string(),
+            ForgeKind::GitLab => gitlab_token_account(&self.host),
+        }
+    }
+
+    /// The API token for this host, from the keychain or, for github.com and
+    /// gitlab.com, the environment
+    pub fn token(&self, store: &dyn SecretStore) -> Option<String> {
+        match store.get_account(&self.token_account()) {
+            Ok(Some(token)) if !token.is_empty() => return Some(token),
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Failed to read the {} token from keychain: {}", self.host, e),
+        }
+        let key = match self.kind {
+            ForgeKind::GitHub => SecretKey::GitHubToken,
+            ForgeKind::GitLab if self.host == GITLAB_HOST => SecretKey::GitLabToken,
+            ForgeKind::GitLab => return None,
+        };
+        std::env::var(key.env_var()).ok().filter(|token| !token.is_empty())
+    }
+}
+
+/// The host and path in a remote URL
+fn split_remote(url: &str) -> Option<(String, String)> {
+    let url = url.trim();
+    let (host, path) = match url.split_once("://") {
+        Some((_, rest)) => rest.split_once('/')?,
+        None => url.split_once(':')?,
+    };
+    let host = host.rsplit('@').next()?.split(':').next()?.to_ascii_lowercase();
+    let path = path.trim_matches('/').trim_end_matches(".git").to_string();
+    Some((host, path))
+}
+
+fn gitlab_token_account(host: &str) -> String {
+    format!("{}:{}", SecretKey::GitLabToken.env_var(), host)
+}
+
+/// Every keychain account a forge token may be stored under
+pub fn forge_token_accounts(gitlab_hosts: &[String]) -> Vec<String> {
+    [SecretKey::GitHubToken.env_var().to_string(), SecretKey::GitLabToken.env_var().to_string()]
+        .into_iter()
+        .chain(gitlab_hosts.iter().map(|host| gitlab_token_account(host)))
+        .collect()
+}
+
+/// The host of the `origin` remote in `dir` when it isn't a forge pull
+/// requests can be opened on, so the user can add it as a GitLab server
+pub fn unrecognized_origin_host(dir: &Path, gitlab_hosts: &[String]) -> Option<String> {
+    let url = git(dir, &["remote", "get-url", REMOTE]).ok()?;
+    let (host, _) = split_remote(&url)?;
+    let known = host == GITHUB_HOST || host == GITLAB_HOST || gitlab_hosts.contains(&host);
+    (!known).then_some(host)
+}
+
+/// Where a pull request would go: the repository, the branch to push and
+/// the branch to merge it into
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PullRequestTarget {
+    pub remote: ForgeRemote,
+    pub head: String,
+    pub base: String,
+}
+
+impl PullRequestTarget {
+    /// The target for the branch checked out in `dir`
+    ///
+    /// The base is the remote's default branch, falling back to `main` when
+    /// the remote's HEAD was never fetched.
+    pub fn resolve(dir: &Path, gitlab_hosts: &[String]) -> Result<Self> {
+        let url = git(dir, &["remote", "get-url", REMOTE])?;
+        let remote = ForgeRemote::parse(&url, gitlab_hosts)
+            .ok_or_else(|| Error::PullRequest(format!("{} isn't on GitHub or GitLab", url.trim())))?;
+        let head = git(dir, &["rev-parse", "--abbrev-ref", "HEAD"])?.trim().to_string();
+        if head == "HEAD" {
+            return Err(Error::PullRequest("No branch is checked out".to_string()));
+        }
+        let base = git(dir, &["symbolic-ref", "--short", &format!("refs/remotes/{}/HEAD", REMOTE)])
+            .ok()
+            .and_then(|base| base.trim().strip_prefix(&format!("{}/", REMOTE)).map(str::to_string))
+            .unwrap_or_else(|| "main".to_string());
+        if head == base {
+            return Err(Error::PullRequest(format!("{} is the base branch; switch to another branch first", head)));
+        }
+        Ok(Self { remote, head, base })
+    }
+}
+
+/// Title and description of a pull request
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PullRequestDraft {
+    pub title: String,
+    pub body: String,
+}
+
+impl PullRequestDraft {
+    /// The draft in an agent's reply to [`pull_request_prompt`]: the first
+    /// line is the title and the rest the description
+    pub fn from_reply(reply: &str) -> Self {
+        let reply = crate::git::commit_message_from_reply(reply);
+        let (title, body) = reply.split_once('\n').unwrap_or((&reply, ""));
+        let title = title.trim().trim_start_matches('#').trim();
+        let title = title.strip_prefix("Title:").unwrap_or(title).trim();
+        Self {
+            title: title.to_string(),
+            body: body.trim().to_string(),
+        }
+    }
+}
+
+/// A pull request once it's open
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PullRequest {
+    pub number: u64,
+    pub url: String,
+}
+
+/// Asks the agent for a pull request title and description, from the
+/// thread's summary when it was compacted and the commits made from it
+pub fn pull_request_prompt(summary: Option<&str>, commits: &[TaskCommit]) -> String {
+    let mut prompt = String::from(
+        "Write a pull request for the work in this thread: a title under 72 characters on the first line, \
+         a blank line, then a Markdown description of what changed, why, and how it was tested. \
+         Reply with only the pull request.",
+    );
+    if let Some(summary) = summary {
+        prompt.push_str(&format!("\n\nSummary of the thread so far:\n{}", summary.trim()));
+    }
+    if !commits.is_empty() {
+        prompt.push_str("\n\nCommits:");
+        for commit in commits {
+            prompt.push_str(&format!("\n- {} {}", commit.short_hash(), commit.subject()));
+        }
+    }
+    prompt
+}
+
+/// A GitHub issue, as named by its URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IssueRef {
+    pub remote: ForgeRemote,
+    pub number: u64,
+}
+
+impl IssueRef {
+    /// Read an issue URL like `https://github.com/owner/repo/issues/12`,
+    /// ignoring any query or `#issuecomment-…` anchor
+    pub fn parse(url: &str) -> Option<Self> {
+        let url = url.trim();
+        let rest = url.strip_prefix("https://").or_else(|| url.strip_prefix("http://")).unwrap_or(url);
+        let rest = rest.split(['?', '#']).next()?.trim_end_matches('/');
+        let mut segments = rest.split('/');
+        let host = segments.next()?.to_ascii_lowercase();
+        let host = host.strip_prefix("www.").unwrap_or(&host);
+        let (owner, repo) = (segments.next()?, segments.next()?);
+        if host != GITHUB_HOST || owner.is_empty() || repo.is_empty() || segments.next()? != "issues" {
+            return None;
+        }
+        let number = segments.next()?.parse().ok()?;
+        if segments.next().is_some() {
+            return None;
+        }
+        let remote = ForgeRemote {
+            kind: ForgeKind::GitHub,
+            host: host.to_string(),
+            path: format!("{}/{}", owner, repo),
+        };
+        Some(Self { remote, number })
+    }
+
+    /// Link to the issue on the web
+    pub fn url(&self) -> String {
+        format!("https://{}/{}/issues/{}", self.remote.host, self.remote.path, self.number)
+    }
+
+    /// `owner/repo#12`
+    pub fn label(&self) -> String {
+        format!("{}#{}", self.remote.path, self.number)
+    }
+}
+
+/// An issue's text and discussion, as fetched from the forge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    pub reference: IssueRef,
+    pub title: String,
+    pub body: String,
+    pub author: String,
+    /// Oldest first
+    pub comments: Vec<IssueComment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IssueComment {
+    pub author: String,
+    pub body: String,
+}
+
+impl Issue {
+    /// The first prompt of a thread working on the issue
+    pub fn prompt(&self) -> String {
+        let mut prompt = format!(
+            "Work on GitHub issue {} ({}).\n\n<issue>\nTitle: {}\nOpened by @{}\n",
+            self.reference.label(),
+            self.reference.url(),
+            self.title.trim(),
+            self.author,
+        );
+        if !self.body.trim().is_empty() {
+            prompt.push_str(&format!("\n{}\n", self.body.trim()));
+        }
+        for comment in &self.comments {
+            prompt.push_str(&format!("\nComment by @{}:\n{}\n", comment.author, comment.body.trim()));
+        }
+        prompt.push_str("</issue>");
+        prompt
+    }
+}
+
+#[derive(Deserialize)]
+struct GitHubUser {
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct GitHubIssue {
+    title: String,
+    body: Option<String>,
+    user: GitHubUser,
+}
+
+#[derive(Deserialize)]
+struct GitHubComment {
+    body: Option<String>,
+    user: GitHubUser,
+}
+
+/// Fetch an issue with its first 100 comments
+///
+/// Public issues need no token, though GitHub allows few requests without one.
+pub async fn fetch_issue(reference: &IssueRef, token: Option<&str>, proxy: &ProxySettings) -> Result<Issue> {
+    ensure_online("importing issues")?;
+    let client = proxy.client()?;
+    let api = format!("{}/repos/{}/issues/{}", reference.remote.api_base(), reference.remote.path, reference.number);
+    let issue: GitHubIssue = serde_json::from_str(&github_get(&client, &api, token).await?)?;
+    let comments = github_get(&client, &format!("{}/comments?per_page=100", api), token).await?;
+    let comments: Vec<GitHubComment> = serde_json::from_str(&comments)?;
+    Ok(Issue {
+        reference: reference.clone(),
+        title: issue.title,
+        body: issue.body.unwrap_or_default(),
+        author: issue.user.login,
+        comments: comments
+            .into_iter()
+            .map(|comment| IssueComment { author: comment.user.login, body: comment.body.unwrap_or_default() })
+            .collect(),
+    })
+}
+
+/// GET from GitHub's API, returning the body of a successful answer
+async fn github_get(client: &reqwest::Client, url: &str, token: Option<&str>) -> Result<String> {
+    let mut request = client
+        .get(url)
+        .header("accept", "application/vnd.github+json")
+        .timeout(REQUEST_TIMEOUT);
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let response = request.send().await.map_err(|e| Error::Issue(e.to_string()))?;
+    let status = response.status();
+    let body = response.text().await.map_err(|e| Error::Issue(e.to_string()))?;
+    if !status.is_success() {
+        return Err(Error::Issue(format!("GitHub answered {}: {}", status, body.trim())));
+    }
+    Ok(body)
+}
+
+/// Push the target's branch to [`REMOTE`], setting it as the upstream
+pub fn push_branch(dir: &Path, target: &PullRequestTarget) -> Result<()> {
+    ensure_online("pushing")?;
+    git(dir, &["push", "--set-upstream", REMOTE, &target.head])?;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct GitHubPull {
+    number: u64,
+    html_url: String,
+}
+
+#[derive(Deserialize)]
+struct GitLabMergeRequest {
+    iid: u64,
+    web_url: String,
+}
+
+/// Open a pull request for the target's branch, which must be pushed already
+pub async fn open_pull_request(
+    target: &PullRequestTarget,
+    draft: &PullRequestDraft,
+    token: &str,
+    proxy: &ProxySettings,
+) -> Result<PullRequest> {
+    ensure_online("opening pull requests")?;
+    if draft.title.trim().is_empty() {
+        return Err(Error::PullRequest("The title is empty".to_string()));
+    }
+    let client = proxy.client()?;
+    let api = target.remote.api_base();
+    let request = match target.remote.kind {
+        ForgeKind::GitHub => client
+            .post(format!("{}/repos/{}/pulls", api, target.remote.path))
+            .bearer_auth(token)
+            .header("accept", "application/vnd.github+json")
+            .json(&json!({ "title": draft.title, "body": draft.body, "head": target.head, "base": target.base })),
+        // The project path is one URL segment, slashes and all
+        ForgeKind::GitLab => client
+            .post(format!("{}/projects/{}/merge_requests", api, target.remote.path.replace('/', "%2F")))
+            .header("private-token", token)
+            .json(&json!({
+                "title": draft.title,
+                "description": draft.body,
+                "source_branch": target.head,
+                "target_branch": target.base,
+            })),
+    };
+    let response = request
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| Error::PullRequest(e.to_string()))?;
+    let status = response.status();
+    let body = response.text().await.map_err(|e| Error::PullRequest(e.to_string()))?;
+    if !status.is_success() {
+        return Err(Error::PullRequest(format!("{} answered {}: {}", target.remote.kind.label(), status, body.trim())));
+    }
+    match target.remote.kind {
+        ForgeKind::GitHub => {
+            let pull: GitHubPull = serde_json::from_str(&body)?;
+            Ok(PullRequest { number: pull.number, url: pull.html_url })
+        }
+        ForgeKind::GitLab => {
+            let merge_request: GitLabMergeRequest = serde_json::from_str(&body)?;
+            Ok(PullRequest { number: merge_request.iid, url: merge_request.web_url })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_remote() {
+        let github = ForgeRemote::parse("git@github.com:acme/widgets.git\n", &[]).unwrap();
+        assert_eq!(github.kind, ForgeKind::GitHub);
+        assert_eq!(github.path, "acme/widgets");
+        assert_eq!(github.api_base(), "https://api.github.com");
+        assert_eq!(ForgeRemote::parse("https://user@github.com/acme/widgets", &[]).unwrap(), github);
+
+        let url = "ssh://git@gitlab.example.com:2222/team/sub/app.git";
+        assert!(ForgeRemote::parse(url, &[]).is_none());
+        let gitlab = ForgeRemote::parse(url, &["gitlab.example.com".to_string()]).unwrap();
+        assert_eq!(gitlab.kind, ForgeKind::GitLab);
+        assert_eq!(gitlab.host, "gitlab.example.com");
+        assert_eq!(gitlab.path, "team/sub/app");
+        assert_eq!(gitlab.api_base(), "https://gitlab.example.com/api/v4");
+
+        assert!(ForgeRemote::parse("https://example.com/acme/widgets.git", &[]).is_none());
+        assert!(ForgeRemote::parse("git@github.com:widgets.git", &[]).is_none());
+        assert!(ForgeRemote::parse("/srv/git/widgets", &[]).is_none());
+    }
+
+    #[test]
+    fn test_tokens_are_per_host() {
+        let store = crate::secrets::MemorySecretStore::new();
+        store.set(SecretKey::GitLabToken, "glpat-public").unwrap();
+        let public = ForgeRemote::parse("git@gitlab.com:acme/widgets.git", &[]).unwrap();
+        assert_eq!(public.token(&store).as_deref(), Some("glpat-public"));
+
+        // A host that merely looks like GitLab isn't a forge at all
+        assert!(ForgeRemote::parse("https://gitlab.com.evil.example/acme/widgets", &[]).is_none());
+        assert!(ForgeRemote::parse("https://gitlab-login.example/acme/widgets", &[]).is_none());
+
+        // A server the user added has a token of its own, never gitlab.com's
+        let hosts = vec!["git.corp.example".to_string()];
+        let corp = ForgeRemote::parse("https://git.corp.example/team/app.git", &hosts).unwrap();
+        assert_eq!(corp.token_account(), "GITLAB_TOKEN:git.corp.example");
+        assert_eq!(corp.token(&store), None);
+        store.set_account(&corp.token_account(), "glpat-corp").unwrap();
+        assert_eq!(corp.token(&store).as_deref(), Some("glpat-corp"));
+        assert_eq!(public.token(&store).as_deref(), Some("glpat-public"));
+        assert_eq!(forge_token_accounts(&hosts).len(), 3);
+    }
+
+    #[test]
+    fn test_resolve_target() {
+        if std::process::Command::new("git").arg("--version").output().is_err() {
+            return;
+        }
+        let repo = tempfile::tempdir().unwrap();
+        let run = |args: &[&str]| git(repo.path(), args).unwrap();
+        run(&["init", "-q", "-b", "main"]);
+        run(&["-c", "user.name=t", "-c", "user.email=t@example.com", "commit", "-q", "--allow-empty", "-m", "init"]);
+        assert!(PullRequestTarget::resolve(repo.path(), &[]).is_err());
+
+        run(&["remote", "add", "origin", "git@git.corp.example:acme/widgets.git"]);
+        assert_eq!(unrecognized_origin_host(repo.path(), &[]).as_deref(), Some("git.corp.example"));
+        assert_eq!(unrecognized_origin_host(repo.path(), &["git.corp.example".to_string()]), None);
+        run(&["remote", "set-url", "origin", "git@github.com:acme/widgets.git"]);
+        assert_eq!(unrecognized_origin_host(repo.path(), &[]), None);
+        assert!(PullRequestTarget::resolve(repo.path(), &[]).is_err());
+        run(&["checkout", "-qb", "retry-uploads"]);
+        let target = PullRequestTarget::resolve(repo.path(), &[]).unwrap();
+        assert_eq!(target.remote.path, "acme/widgets");
+        assert_eq!((target.head.as_str(), target.base.as_str()), ("retry-uploads", "main"));
+    }
+
+    #[test]
+    fn test_draft_from_reply() {
+        let draft = PullRequestDraft::from_reply("```markdown\n# Add retries\n\nRetries failed uploads.\n```");
+        assert_eq!(draft.title, "Add retries");
+        assert_eq!(draft.body, "Retries failed uploads.");
+        assert_eq!(PullRequestDraft::from_reply("Title: Fix it").title, "Fix it");
+        assert_eq!(PullRequestDraft::from_reply("Fix it").body, "");
+
+        let commit = TaskCommit {
+            hash: "0123456789abcdef".to_string(),
+            message: "Retry uploads\n\nBody".to_string(),
+            committed_at: chrono::Utc::now(),
+        };
+        let prompt = pull_request_prompt(Some("Worked on uploads"), &[commit]);
+        assert!(prompt.contains("Summary of the thread so far:\nWorked on uploads"));
+        assert!(prompt.ends_with("Commits:\n- 0123456 Retry uploads"));
+    }
+
+    #[test]
+    fn test_issue_ref_and_prompt() {
+        let reference = IssueRef::parse("https://github.com/acme/widgets/issues/42#issuecomment-1").unwrap();
+        assert_eq!(reference.number, 42);
+        assert_eq!(reference.label(), "acme/widgets#42");
+        assert_eq!(reference.url(), "https://github.com/acme/widgets/issues/42");
+        assert_eq!(IssueRef::parse(" github.com/acme/widgets/issues/42/ "), Some(reference.clone()));
+        assert!(IssueRef::parse("https://github.com/acme/widgets/pull/42").is_none());
+        assert!(IssueRef::parse("https://github.com/acme/widgets/issues/new").is_none());
+        assert!(IssueRef::parse("https://gitlab.com/acme/widgets/issues/42").is_none());
+
+        let issue = Issue {
+            reference,
+            title: "Uploads fail on flaky networks".to_string(),
+            body: "They never retry.".to_string(),
+            author: "ana".to_string(),
+            comments: vec![IssueComment { author: "bo".to_string(), body: "Same here.\n".to_string() }],
+        };
+        let prompt = issue.prompt();
+        assert!(prompt.starts_with("Work on GitHub issue acme/widgets#42 (https://github.com/acme/widgets/issues/42)."));
+        assert!(prompt.contains("Title: Uploads fail on flaky networks\nOpened by @ana\n\nThey never retry.\n"));
+        assert!(prompt.ends_with("\nComment by @bo:\nSame here.\n</issue>"));
+    }
+}
@@ -0,0 +1,201 @@
+//! Git commits of an agent's changes
+//!
+//! After a turn edits files, the user can commit them from the thread. The
+//! diff shown is of the files the thread touched against `HEAD`, and only
+//! those files go into the commit, so other work in the tree is left as it is.
+
+use crate::error::{Error, Result};
+use crate::sandbox::diff::{compute_file_diff, diff_stats, DEFAULT_CONTEXT_LINES};
+use crate::types::{FileDiff, TaskCommit};
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Top level of the repository `dir` is in
+pub fn repo_root(dir: &Path) -> Result<PathBuf> {
+    Ok(PathBuf::from(git(dir, &["rev-parse", "--show-toplevel"])?.trim()))
+}
+
+/// Files among `paths` that differ from `HEAD`, one diff each, with paths
+/// relative to the repository root
+///
+/// `paths` are relative to `dir` or absolute; ones outside the repository or
+/// ignored by git are left out.
+pub fn diff_paths(dir: &Path, paths: &[String]) -> Result<Vec<FileDiff>> {
+    let root = repo_root(dir)?;
+    let specs = pathspecs(dir, &root, paths);
+    if specs.is_empty() {
+        return Ok(Vec::new());
+    }
+    let status = ["--literal-pathspecs", "status", "--porcelain", "-z", "--no-renames", "--untracked-files=all", "--"];
+    let args: Vec<&str> = status.into_iter().chain(specs.iter().map(String::as_str)).collect();
+    let out = git(&root, &args)?;
+    Ok(out
+        .split('\0')
+        .filter_map(|entry| entry.get(3..).filter(|path| !path.is_empty()))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|path| {
+            let old = git(&root, &["show", &format!("HEAD:{}", path)]).unwrap_or_default();
+            let new = std::fs::read(root.join(path))
+                .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+                .unwrap_or_default();
+            compute_file_diff(path, &old, &new, DEFAULT_CONTEXT_LINES)
+        })
+        .collect())
+}
+
+/// Commit `paths`, relative to the repository root, with `message`
+///
+/// Only those paths go in, whatever else is staged; the user's identity
+/// and hooks apply as for any commit.
+pub fn commit(dir: &Path, paths: &[String], message: &str) -> Result<TaskCommit> {
+    let message = message.trim();
+    if message.is_empty() {
+        return Err(Error::Git("The commit message is empty".to_string()));
+    }
+    if paths.is_empty() {
+        return Err(Error::Git("Nothing to commit".to_string()));
+    }
+    let root = repo_root(dir)?;
+    let paths = paths.iter().map(String::as_str);
+    let add: Vec<&str> = ["--literal-pathspecs", "add", "-A", "--"].into_iter().chain(paths.clone()).collect();
+    git(&root, &add)?;
+    let commit = ["--literal-pathspecs", "commit", "-q", "--only", "-F", "-", "--"];
+    let commit: Vec<&str> = commit.into_iter().chain(paths).collect();
+    git_bytes(&root, &commit, Some(message.as_bytes()))?;
+    Ok(TaskCommit {
+        hash: git(&root, &["rev-parse", "HEAD"])?.trim().to_string(),
+        message: message.to_string(),
+        committed_at: chrono::Utc::now(),
+    })
+}
+
+/// Asks the agent for a commit message for the files it changed
+///
+/// The agent made the changes, so the files and their line counts are
+/// enough; the diff itself isn't sent.
+pub fn commit_message_prompt(diffs: &[FileDiff]) -> String {
+    let mut prompt = String::from(
+        "Write a git commit message for your changes to these files: a summary line under 72 characters, \
+         a blank line, then a short body saying what changed and why. Reply with only the message.\n",
+    );
+    for diff in diffs {
+        let (added, removed) = diff_stats(diff);
+        prompt.push_str(&format!("\n- {} (+{} -{})", diff.path, added, removed));
+    }
+    prompt
+}
+
+/// The commit message in an agent's reply to [`commit_message_prompt`],
+/// without a code block around it
+pub fn commit_message_from_reply(reply: &str) -> String {
+    let reply = reply.trim();
+    let fenced = reply
+        .strip_prefix("```")
+        .and_then(|rest| rest.strip_suffix("```"))
+        .and_then(|body| body.split_once('\n'))
+        .map(|(_, body)| body);
+    fenced.unwrap_or(reply).trim().to_string()
+}
+
+/// `paths` as paths from the repository root, dropping ones outside it
+fn pathspecs(dir: &Path, root: &Path, paths: &[String]) -> BTreeSet<String> {
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    paths
+        .iter()
+        .filter_map(|path| {
+            let path = dir.join(path);
+            // A deleted file can't be resolved, but its directory may be
+            let resolved = path.canonicalize().ok().or_else(|| {
+                let parent = path.parent()?.canonicalize().ok()?;
+                Some(parent.join(path.file_name()?))
+            })?;
+            let relative = resolved.strip_prefix(root).ok()?;
+            Some(relative.to_string_lossy().replace('\\', "/"))
+        })
+        .filter(|path| !path.is_empty())
+        .collect()
+}
+
+/// Run git in `dir`, returning what it printed
+pub(crate) fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    git_bytes(dir, args, None).map(|out| String::from_utf8_lossy(&out).into_owned())
+}
+
+pub(crate) fn git_bytes(dir: &Path, args: &[&str], input: Option<&[u8]>) -> Result<Vec<u8>> {
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin.write_all(input)?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::Git(format!("git {}: {}", args.join(" "), stderr.trim())));
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn has_git() -> bool {
+        Command::new("git").arg("--version").output().is_ok_and(|out| out.status.success())
+    }
+
+    #[test]
+    fn test_commit_only_given_paths() {
+        if !has_git() {
+            return;
+        }
+        let repo = tempdir().unwrap();
+        let run = |args: &[&str]| git(repo.path(), args).unwrap();
+        run(&["init", "-q"]);
+        run(&["config", "user.name", "t"]);
+        run(&["config", "user.email", "t@example.com"]);
+        std::fs::write(repo.path().join("a.txt"), "one\n").unwrap();
+        std::fs::write(repo.path().join("b.txt"), "two\n").unwrap();
+        run(&["add", "-A"]);
+        run(&["commit", "-qm", "init"]);
+
+        std::fs::write(repo.path().join("a.txt"), "one\n1\n").unwrap();
+        std::fs::write(repo.path().join("b.txt"), "two\n2\n").unwrap();
+        std::fs::create_dir(repo.path().join("src")).unwrap();
+        std::fs::write(repo.path().join("src/c.txt"), "three\n").unwrap();
+        let absolute = repo.path().join("src/c.txt").to_string_lossy().into_owned();
+        let paths = ["a.txt".to_string(), absolute, "missing.txt".to_string(), "/elsewhere/d.txt".to_string()];
+
+        let diffs = diff_paths(repo.path(), &paths).unwrap();
+        let changed: Vec<_> = diffs.iter().map(|diff| diff.path.clone()).collect();
+        assert_eq!(changed, ["a.txt", "src/c.txt"]);
+        assert_eq!(diff_stats(&diffs[0]), (1, 0));
+
+        assert!(commit(repo.path(), &changed, "  ").is_err());
+        let made = commit(repo.path(), &changed, "Add c and extend a\n\nBody.\n").unwrap();
+        assert_eq!(made.hash.len(), 40);
+        assert_eq!(made.message, "Add c and extend a\n\nBody.");
+        assert_eq!(run(&["log", "-1", "--format=%s"]).trim(), "Add c and extend a");
+        assert_eq!(run(&["status", "--porcelain"]).trim(), "M b.txt");
+        assert!(diff_paths(repo.path(), &changed).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_commit_message_prompt_and_reply() {
+        let diff = compute_file_diff("src/lib.rs", "a\n", "a\nb\n", DEFAULT_CONTEXT_LINES);
+        let prompt = commit_message_prompt(&[diff]);
+        assert!(prompt.ends_with("\n- src/lib.rs (+1 -0)"));
+
+        assert_eq!(commit_message_from_reply("  Fix parsing\n\nDetails.\n"), "Fix parsing\n\nDetails.");
+        assert_eq!(commit_message_from_reply("```text\nFix parsing\n```"), "Fix parsing");
+    }
+}
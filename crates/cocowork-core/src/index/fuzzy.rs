@@ -0,0 +1,96 @@
+//! Fuzzy matching of file paths
+
+/// Score for each matched character
+const MATCH: i64 = 16;
+/// Bonus when a match directly follows the previous one
+const CONSECUTIVE: i64 = 24;
+/// Bonus for matching at the start of a word, e.g. after `/`, `_` or a case change
+const WORD_START: i64 = 20;
+/// Bonus per character matched inside the file name rather than a directory
+const FILE_NAME: i64 = 12;
+
+/// Match `query` against `candidate` as an in-order subsequence, ignoring case
+///
+/// Returns the score, higher being better, and the char indices of `candidate`
+/// that matched. Whitespace in the query is ignored.
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<(i64, Vec<usize>)> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    let chars: Vec<char> = candidate.chars().collect();
+    if query.is_empty() {
+        return Some((0, Vec::new()));
+    }
+
+    let name_start = chars
+        .iter()
+        .rposition(|&c| c == '/' || c == '\\')
+        .map_or(0, |i| i + 1);
+
+    // Prefer a match that lies entirely in the file name
+    [name_start, 0]
+        .into_iter()
+        .filter_map(|start| match_from(&query, &chars, start, name_start))
+        .max_by_key(|(score, _)| *score)
+}
+
+fn match_from(query: &[char], chars: &[char], start: usize, name_start: usize) -> Option<(i64, Vec<usize>)> {
+    let mut positions = Vec::with_capacity(query.len());
+    let mut score = 0;
+    let mut next = start;
+
+    for &wanted in query {
+        let found = (next..chars.len()).find(|&i| chars[i].to_lowercase().eq(std::iter::once(wanted)))?;
+        score += MATCH;
+        if positions.last().is_some_and(|&last| last + 1 == found) {
+            score += CONSECUTIVE;
+        }
+        if is_word_start(chars, found) {
+            score += WORD_START;
+        }
+        if found >= name_start {
+            score += FILE_NAME;
+        }
+        // Gaps cost a little, so tighter matches win
+        score -= (found - next) as i64;
+        positions.push(found);
+        next = found + 1;
+    }
+
+    // Shorter candidates win ties
+    score -= chars.len() as i64 / 8;
+    Some((score, positions))
+}
+
+fn is_word_start(chars: &[char], i: usize) -> bool {
+    if i == 0 {
+        return true;
+    }
+    let prev = chars[i - 1];
+    matches!(prev, '/' | '\\' | '_' | '-' | '.' | ' ') || (prev.is_lowercase() && chars[i].is_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_match_ranks_file_names() {
+        assert!(fuzzy_match("xyz", "src/main.rs").is_none());
+
+        let (_, positions) = fuzzy_match("mrs", "src/main.rs").unwrap();
+        assert_eq!(positions, vec![4, 9, 10]);
+
+        // A match in the file name beats one spread across directories
+        let (name, _) = fuzzy_match("conf", "src/storage/config.rs").unwrap();
+        let (dirs, _) = fuzzy_match("conf", "crates/core/notes/fixtures.rs").unwrap();
+        assert!(name > dirs);
+
+        // Word starts beat matches in the middle of words
+        let (camel, _) = fuzzy_match("ts", "TextStore.ts").unwrap();
+        let (middle, _) = fuzzy_match("ts", "tests.md").unwrap();
+        assert!(camel > middle);
+    }
+}
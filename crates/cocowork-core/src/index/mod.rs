@@ -0,0 +1,435 @@
+//! Workspace index
+//!
+//! Keeps the list of files in a workspace, honouring `.gitignore`, along with
+//! the symbols defined in them. The first scan runs on a blocking thread;
+//! afterwards a [`FileWatcher`] keeps the index current one path at a time.
+//! File and symbol search work from memory, text search reads the files on
+//! a few threads at once.
+
+mod fuzzy;
+mod search;
+mod symbols;
+
+pub use fuzzy::fuzzy_match;
+pub use search::SearchQuery;
+pub use symbols::{extract_symbols, Symbol, SymbolKind};
+
+use crate::error::{Error, Result};
+use crate::sandbox::FileWatcher;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::WalkBuilder;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+/// Limits for a [`WorkspaceIndex`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexConfig {
+    /// Files indexed before the scan stops
+    pub max_files: usize,
+    /// Larger files are listed but not read for symbols or text search
+    pub max_file_size: u64,
+    /// Index dot files and folders
+    pub include_hidden: bool,
+}
+
+impl Default for IndexConfig {
+    fn default() -> Self {
+        Self {
+            max_files: 100_000,
+            max_file_size: 1024 * 1024,
+            include_hidden: false,
+        }
+    }
+}
+
+impl IndexConfig {
+    pub fn with_max_files(mut self, max_files: usize) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
+    pub fn with_max_file_size(mut self, max_file_size: u64) -> Self {
+        self.max_file_size = max_file_size;
+        self
+    }
+
+    pub fn with_hidden(mut self, include_hidden: bool) -> Self {
+        self.include_hidden = include_hidden;
+        self
+    }
+}
+
+/// A file in the index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedFile {
+    /// Path relative to the workspace root
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+/// A file matched by [`WorkspaceIndex::search_files`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileMatch {
+    /// Path relative to the workspace root
+    pub path: PathBuf,
+    pub score: i64,
+    /// Char indices of the path that matched the query, for highlighting
+    pub positions: Vec<usize>,
+}
+
+/// A line matched by [`WorkspaceIndex::search_text`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextMatch {
+    /// Path relative to the workspace root
+    pub path: PathBuf,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column, in chars, where the match starts
+    pub column: usize,
+    /// Length of the match in chars
+    pub length: usize,
+    /// The matching line, trimmed of its line ending
+    pub text: String,
+}
+
+#[derive(Default)]
+struct IndexState {
+    files: BTreeMap<PathBuf, IndexedFile>,
+    symbols: HashMap<PathBuf, Vec<Symbol>>,
+    /// `.gitignore` matchers, deepest directory first
+    ignores: Vec<Gitignore>,
+    ready: bool,
+}
+
+/// Searchable index of a workspace's files
+///
+/// Cloning shares the same index.
+#[derive(Clone)]
+pub struct WorkspaceIndex {
+    root: PathBuf,
+    config: IndexConfig,
+    state: Arc<RwLock<IndexState>>,
+}
+
+impl WorkspaceIndex {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            config: IndexConfig::default(),
+            state: Arc::new(RwLock::new(IndexState::default())),
+        }
+    }
+
+    pub fn with_config(mut self, config: IndexConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Whether the first scan has finished
+    pub fn is_ready(&self) -> bool {
+        self.read().ready
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.read().files.len()
+    }
+
+    /// All indexed files, sorted by path
+    pub fn files(&self) -> Vec<IndexedFile> {
+        self.read().files.values().cloned().collect()
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, IndexState> {
+        self.state.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, IndexState> {
+        self.state.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Scan the whole workspace, replacing what was indexed before
+    ///
+    /// Returns the number of files indexed.
+    pub async fn build(&self) -> Result<usize> {
+        let index = self.clone();
+        let state = tokio::task::spawn_blocking(move || index.scan())
+            .await
+            .map_err(|e| Error::Internal(format!("Workspace scan panicked: {}", e)))?;
+        let count = state.files.len();
+        *self.write() = state;
+        info!("Indexed {} files in {:?}", count, self.root);
+        Ok(count)
+    }
+
+    fn scan(&self) -> IndexState {
+        let mut state = IndexState {
+            ready: true,
+            ..Default::default()
+        };
+        let mut ignore_dirs = Vec::new();
+
+        for entry in self.walk(&self.root) {
+            let path = entry.path();
+            let Some(file_type) = entry.file_type() else { continue };
+            if file_type.is_dir() {
+                if path.join(".gitignore").is_file() {
+                    ignore_dirs.push(path.to_path_buf());
+                }
+                continue;
+            }
+            if !file_type.is_file() {
+                continue;
+            }
+            if state.files.len() >= self.config.max_files {
+                warn!("Stopped indexing {:?} at {} files", self.root, self.config.max_files);
+                break;
+            }
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            self.insert_file(&mut state, path, size);
+        }
+
+        state.ignores = ignore_dirs
+            .into_iter()
+            .filter_map(|dir| {
+                let mut builder = GitignoreBuilder::new(&dir);
+                if let Some(e) = builder.add(dir.join(".gitignore")) {
+                    debug!("Skipping bad .gitignore pattern in {:?}: {}", dir, e);
+                }
+                builder.build().ok()
+            })
+            .collect();
+        state
+            .ignores
+            .sort_by_key(|ignore| std::cmp::Reverse(ignore.path().components().count()));
+        state
+    }
+
+    /// Walk `dir` the way git would, skipping ignored and (by default) hidden files
+    fn walk(&self, dir: &Path) -> impl Iterator<Item = ignore::DirEntry> {
+        WalkBuilder::new(dir)
+            .hidden(!self.config.include_hidden)
+            .git_ignore(true)
+            .git_exclude(true)
+            .git_global(true)
+            .require_git(false)
+            .parents(true)
+            .filter_entry(|entry| entry.file_name() != ".git")
+            .build()
+            .filter_map(|entry| entry.ok())
+    }
+
+    fn insert_file(&self, state: &mut IndexState, path: &Path, size: u64) {
+        let Ok(relative) = path.strip_prefix(&self.root) else { return };
+        let relative = relative.to_path_buf();
+
+        if size <= self.config.max_file_size && symbols::has_symbols(&relative) {
+            match std::fs::read_to_string(path) {
+                Ok(text) => {
+                    state.symbols.insert(relative.clone(), extract_symbols(&relative, &text));
+                }
+                Err(_) => {
+                    state.symbols.remove(&relative);
+                }
+            }
+        }
+        state.files.insert(relative.clone(), IndexedFile { path: relative, size });
+    }
+
+    /// Bring one path up to date after it was created, changed or removed
+    ///
+    /// `path` may be absolute or relative to the root. A directory is rescanned
+    /// with everything under it.
+    pub fn refresh(&self, path: &Path) {
+        let path = if path.is_absolute() { path.to_path_buf() } else { self.root.join(path) };
+        let Ok(relative) = path.strip_prefix(&self.root).map(Path::to_path_buf) else {
+            return;
+        };
+        if relative.components().any(|c| c == Component::Normal(".git".as_ref())) {
+            return;
+        }
+
+        let mut state = self.write();
+        if !path.exists() {
+            state.files.retain(|file, _| !file.starts_with(&relative));
+            state.symbols.retain(|file, _| !file.starts_with(&relative));
+            return;
+        }
+        if self.is_ignored(&state, &path) {
+            return;
+        }
+
+        if path.is_dir() {
+            for entry in self.walk(&path) {
+                if entry.file_type().is_some_and(|t| t.is_file()) {
+                    let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                    self.insert_file(&mut state, entry.path(), size);
+                }
+            }
+        } else if let Ok(metadata) = std::fs::metadata(&path) {
+            self.insert_file(&mut state, &path, metadata.len());
+        }
+    }
+
+    fn is_ignored(&self, state: &IndexState, path: &Path) -> bool {
+        let hidden = path
+            .strip_prefix(&self.root)
+            .map(|relative| {
+                relative
+                    .components()
+                    .any(|c| c.as_os_str().to_str().is_some_and(|name| name.starts_with('.')))
+            })
+            .unwrap_or(false);
+        if hidden && !self.config.include_hidden {
+            return true;
+        }
+
+        let is_dir = path.is_dir();
+        // The deepest .gitignore with an opinion wins
+        for ignore in state.ignores.iter().filter(|ignore| path.starts_with(ignore.path())) {
+            let matched = ignore.matched_path_or_any_parents(path, is_dir);
+            if matched.is_ignore() {
+                return true;
+            }
+            if matched.is_whitelist() {
+                return false;
+            }
+        }
+        false
+    }
+
+    /// Keep the index current by watching the root for changes
+    ///
+    /// Must be called inside a Tokio runtime. Watching stops when the returned
+    /// watcher is dropped.
+    pub fn start_watching(&self) -> Result<FileWatcher> {
+        let (tx, mut rx) = mpsc::channel(256);
+        let mut watcher = FileWatcher::new();
+        watcher.set_event_channel(tx);
+        watcher.watch(&self.root)?;
+
+        let index = self.clone();
+        tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                // New ignore rules can change which files belong anywhere below
+                if event.path.file_name().is_some_and(|name| name == ".gitignore") {
+                    if let Err(e) = index.build().await {
+                        warn!("Failed to rebuild workspace index: {}", e);
+                    }
+                    continue;
+                }
+                let index = index.clone();
+                let _ = tokio::task::spawn_blocking(move || index.refresh(&event.path)).await;
+            }
+            debug!("Workspace index watcher stopped");
+        });
+        Ok(watcher)
+    }
+
+    /// Files whose paths fuzzy-match `query`, best first
+    pub fn search_files(&self, query: &str, limit: usize) -> Vec<FileMatch> {
+        let state = self.read();
+        let mut matches: Vec<FileMatch> = state
+            .files
+            .keys()
+            .filter_map(|path| {
+                let (score, positions) = fuzzy_match(query, &path.to_string_lossy())?;
+                Some(FileMatch {
+                    path: path.clone(),
+                    score,
+                    positions,
+                })
+            })
+            .collect();
+        matches.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+        matches.truncate(limit);
+        matches
+    }
+
+    /// Symbols whose names fuzzy-match `query`, best first
+    pub fn search_symbols(&self, query: &str, limit: usize) -> Vec<Symbol> {
+        let state = self.read();
+        let mut matches: Vec<(i64, &Symbol)> = state
+            .symbols
+            .values()
+            .flatten()
+            .filter_map(|symbol| Some((fuzzy_match(query, &symbol.name)?.0, symbol)))
+            .collect();
+        matches.sort_by(|a, b| {
+            b.0.cmp(&a.0)
+                .then_with(|| a.1.path.cmp(&b.1.path))
+                .then_with(|| a.1.line.cmp(&b.1.line))
+        });
+        matches.into_iter().take(limit).map(|(_, symbol)| symbol.clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write(root: &Path, path: &str, contents: &str) {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_index_respects_gitignore() {
+        let dir = tempdir().unwrap();
+        write(dir.path(), ".gitignore", "target/\n*.log\n");
+        write(dir.path(), "src/main.rs", "fn main() {}\n");
+        write(dir.path(), "src/storage/config.rs", "pub struct StorageConfig;\n");
+        write(dir.path(), "target/debug/build.rs", "fn build() {}\n");
+        write(dir.path(), "debug.log", "noise\n");
+        write(dir.path(), ".env", "SECRET=1\n");
+
+        let index = WorkspaceIndex::new(dir.path());
+        assert_eq!(index.build().await.unwrap(), 2);
+        assert!(index.is_ready());
+
+        let found = index.search_files("conf", 10);
+        assert_eq!(found[0].path, PathBuf::from("src/storage/config.rs"));
+        assert!(index.search_files("build", 10).is_empty());
+
+        let symbols = index.search_symbols("StorageConf", 10);
+        assert_eq!(symbols[0].name, "StorageConfig");
+        assert_eq!(symbols[0].kind, SymbolKind::Type);
+
+        let lines = index.search_text(r"fn \w+\(", 10).await.unwrap();
+        assert_eq!(lines.len(), 1);
+        assert_eq!((lines[0].line, lines[0].column), (1, 1));
+        assert!(index.search_text("(", 10).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_tracks_changes() {
+        let dir = tempdir().unwrap();
+        write(dir.path(), ".gitignore", "*.log\n");
+        write(dir.path(), "src/lib.rs", "pub fn old() {}\n");
+        let index = WorkspaceIndex::new(dir.path());
+        index.build().await.unwrap();
+
+        write(dir.path(), "src/lib.rs", "pub fn renamed() {}\n");
+        write(dir.path(), "src/new.rs", "");
+        write(dir.path(), "out.log", "");
+        for path in ["src/lib.rs", "src/new.rs", "out.log"] {
+            index.refresh(&dir.path().join(path));
+        }
+        assert_eq!(index.file_count(), 2);
+        assert!(index.search_symbols("old", 10).is_empty());
+        assert_eq!(index.search_symbols("renamed", 10).len(), 1);
+
+        std::fs::remove_dir_all(dir.path().join("src")).unwrap();
+        index.refresh(&dir.path().join("src"));
+        assert_eq!(index.file_count(), 0);
+        assert!(index.search_symbols("renamed", 10).is_empty());
+    }
+}
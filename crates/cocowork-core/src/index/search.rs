@@ -0,0 +1,204 @@
+//! Text search across the indexed files
+
+use super::{IndexedFile, TextMatch, WorkspaceIndex};
+use crate::error::{Error, Result};
+use regex::{Regex, RegexBuilder};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Most threads a streaming search reads files on
+const MAX_SEARCH_THREADS: usize = 8;
+
+/// What to look for in a text search
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchQuery {
+    pub pattern: String,
+    /// Treat the pattern as a regex rather than literal text
+    pub regex: bool,
+    pub case_sensitive: bool,
+}
+
+impl SearchQuery {
+    /// A case-insensitive search for literal text
+    pub fn new(pattern: impl Into<String>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            regex: false,
+            case_sensitive: false,
+        }
+    }
+
+    pub fn with_regex(mut self, regex: bool) -> Self {
+        self.regex = regex;
+        self
+    }
+
+    pub fn with_case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    /// Compile the query, failing on an empty or invalid pattern
+    pub fn to_regex(&self) -> Result<Regex> {
+        if self.pattern.is_empty() {
+            return Err(Error::InvalidPattern("Pattern is empty".to_string()));
+        }
+        let pattern = if self.regex {
+            self.pattern.clone()
+        } else {
+            regex::escape(&self.pattern)
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .build()
+            .map_err(|e| Error::InvalidPattern(e.to_string()))
+    }
+}
+
+/// Report each matching line of a text file until `on_match` returns false
+///
+/// Binary and non-UTF-8 files are skipped.
+fn search_file(root: &Path, file: &IndexedFile, regex: &Regex, mut on_match: impl FnMut(TextMatch) -> bool) {
+    let Ok(bytes) = std::fs::read(root.join(&file.path)) else { return };
+    if bytes.iter().take(8000).any(|&b| b == 0) {
+        return;
+    }
+    let Ok(text) = String::from_utf8(bytes) else { return };
+    for (i, line) in text.lines().enumerate() {
+        let Some(found) = regex.find(line) else { continue };
+        let keep_going = on_match(TextMatch {
+            path: file.path.clone(),
+            line: i + 1,
+            column: line[..found.start()].chars().count() + 1,
+            length: found.as_str().chars().count(),
+            text: line.to_string(),
+        });
+        if !keep_going {
+            return;
+        }
+    }
+}
+
+impl WorkspaceIndex {
+    /// Files small enough to read for text search
+    fn searchable_files(&self) -> Vec<IndexedFile> {
+        self.read()
+            .files
+            .values()
+            .filter(|file| file.size <= self.config.max_file_size)
+            .cloned()
+            .collect()
+    }
+
+    /// Lines matching the regex `pattern` across indexed text files, in path order
+    ///
+    /// Binary files and files over the size limit are skipped. Stops after
+    /// `limit` matches.
+    pub async fn search_text(&self, pattern: &str, limit: usize) -> Result<Vec<TextMatch>> {
+        let regex = SearchQuery::new(pattern)
+            .with_regex(true)
+            .with_case_sensitive(true)
+            .to_regex()?;
+        let files = self.searchable_files();
+        let root = self.root.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut matches = Vec::new();
+            for file in &files {
+                search_file(&root, file, &regex, |found| {
+                    matches.push(found);
+                    matches.len() < limit
+                });
+                if matches.len() >= limit {
+                    break;
+                }
+            }
+            matches
+        })
+        .await
+        .map_err(|e| Error::Internal(format!("Text search panicked: {}", e)))
+    }
+
+    /// Search on several threads, sending matches as they're found
+    ///
+    /// Matches arrive in no particular order. The search stops after `limit`
+    /// matches or once the receiver is dropped. Must be called inside a Tokio
+    /// runtime.
+    pub fn search_text_streaming(&self, query: &SearchQuery, limit: usize) -> Result<mpsc::Receiver<TextMatch>> {
+        let regex = Arc::new(query.to_regex()?);
+        let files = self.searchable_files();
+        let threads = std::thread::available_parallelism()
+            .map_or(1, |n| n.get())
+            .clamp(1, MAX_SEARCH_THREADS);
+        let (tx, rx) = mpsc::channel(256);
+        let found = Arc::new(AtomicUsize::new(0));
+
+        // Deal files out round-robin so big directories don't land on one thread
+        let mut chunks = vec![Vec::new(); threads];
+        for (i, file) in files.into_iter().enumerate() {
+            chunks[i % threads].push(file);
+        }
+
+        for chunk in chunks.into_iter().filter(|chunk| !chunk.is_empty()) {
+            let root = self.root.clone();
+            let regex = Arc::clone(&regex);
+            let found = Arc::clone(&found);
+            let tx = tx.clone();
+            tokio::task::spawn_blocking(move || {
+                for file in &chunk {
+                    let mut stopped = false;
+                    search_file(&root, file, &regex, |text_match| {
+                        stopped = found.fetch_add(1, Ordering::Relaxed) >= limit || tx.blocking_send(text_match).is_err();
+                        !stopped
+                    });
+                    if stopped {
+                        return;
+                    }
+                }
+            });
+        }
+        Ok(rx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_literal_query_is_escaped() {
+        let regex = SearchQuery::new("fn main()").to_regex().unwrap();
+        assert!(regex.is_match("pub FN MAIN() {}"));
+        assert!(!SearchQuery::new("fn main()").with_case_sensitive(true).to_regex().unwrap().is_match("FN MAIN()"));
+        assert!(SearchQuery::new("(").with_regex(true).to_regex().is_err());
+        assert!(SearchQuery::new("").to_regex().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_streaming_search_finds_every_match() {
+        let dir = tempdir().unwrap();
+        for i in 0..20 {
+            std::fs::write(dir.path().join(format!("file{}.txt", i)), "alpha\nTODO: beta\n").unwrap();
+        }
+        let index = WorkspaceIndex::new(dir.path());
+        index.build().await.unwrap();
+
+        let mut rx = index.search_text_streaming(&SearchQuery::new("todo"), 100).unwrap();
+        let mut matches = Vec::new();
+        while let Some(found) = rx.recv().await {
+            matches.push(found);
+        }
+        assert_eq!(matches.len(), 20);
+        assert!(matches.iter().all(|m| m.line == 2 && m.column == 1 && m.length == 4));
+
+        let mut rx = index.search_text_streaming(&SearchQuery::new("todo"), 5).unwrap();
+        let mut count = 0;
+        while rx.recv().await.is_some() {
+            count += 1;
+        }
+        assert_eq!(count, 5);
+    }
+}
@@ -0,0 +1,130 @@
+//! Basic symbol extraction
+//!
+//! A line-based scan for top-level definitions in common languages, enough to
+//! jump to a function or type by name. It isn't a parser: definitions split
+//! across lines or generated by macros are missed.
+
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// What a symbol defines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolKind {
+    Function,
+    /// Structs, enums, classes, traits, interfaces and type aliases
+    Type,
+    Module,
+    Constant,
+}
+
+/// A definition found in a workspace file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Path relative to the workspace root
+    pub path: PathBuf,
+    /// 1-based line number
+    pub line: usize,
+}
+
+type Rules = Vec<(Regex, SymbolKind)>;
+
+fn rules(patterns: &[(&str, SymbolKind)]) -> Rules {
+    patterns
+        .iter()
+        .map(|(pattern, kind)| (Regex::new(pattern).expect("valid symbol pattern"), *kind))
+        .collect()
+}
+
+fn rules_for(extension: &str) -> Option<&'static Rules> {
+    static RUST: OnceLock<Rules> = OnceLock::new();
+    static PYTHON: OnceLock<Rules> = OnceLock::new();
+    static JAVASCRIPT: OnceLock<Rules> = OnceLock::new();
+    static GO: OnceLock<Rules> = OnceLock::new();
+
+    match extension {
+        "rs" => Some(RUST.get_or_init(|| {
+            rules(&[
+                (r"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:const\s+)?(?:async\s+)?(?:unsafe\s+)?fn\s+(\w+)", SymbolKind::Function),
+                (r"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:struct|enum|trait|type|union)\s+(\w+)", SymbolKind::Type),
+                (r"^\s*(?:pub(?:\([^)]*\))?\s+)?mod\s+(\w+)", SymbolKind::Module),
+                (r"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:const|static)\s+([A-Z_][A-Z0-9_]*)\s*:", SymbolKind::Constant),
+            ])
+        })),
+        "py" => Some(PYTHON.get_or_init(|| {
+            rules(&[
+                (r"^\s*(?:async\s+)?def\s+(\w+)", SymbolKind::Function),
+                (r"^\s*class\s+(\w+)", SymbolKind::Type),
+            ])
+        })),
+        "js" | "jsx" | "ts" | "tsx" | "mjs" | "cjs" => Some(JAVASCRIPT.get_or_init(|| {
+            rules(&[
+                (r"^\s*(?:export\s+)?(?:default\s+)?(?:async\s+)?function\*?\s+(\w+)", SymbolKind::Function),
+                (r"^\s*(?:export\s+)?(?:const|let)\s+(\w+)\s*=\s*(?:async\s*)?(?:\([^)]*\)|\w+)\s*=>", SymbolKind::Function),
+                (r"^\s*(?:export\s+)?(?:default\s+)?(?:abstract\s+)?class\s+(\w+)", SymbolKind::Type),
+                (r"^\s*(?:export\s+)?(?:interface|type|enum)\s+(\w+)", SymbolKind::Type),
+            ])
+        })),
+        "go" => Some(GO.get_or_init(|| {
+            rules(&[
+                (r"^func\s+(?:\([^)]*\)\s*)?(\w+)", SymbolKind::Function),
+                (r"^type\s+(\w+)", SymbolKind::Type),
+            ])
+        })),
+        _ => None,
+    }
+}
+
+/// Whether symbols are extracted for files like `path`
+pub fn has_symbols(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(rules_for)
+        .is_some()
+}
+
+/// Find definitions in `text`, the contents of the file at relative `path`
+pub fn extract_symbols(path: &Path, text: &str) -> Vec<Symbol> {
+    let Some(rules) = path.extension().and_then(|ext| ext.to_str()).and_then(rules_for) else {
+        return Vec::new();
+    };
+
+    text.lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            rules.iter().find_map(|(regex, kind)| {
+                let name = regex.captures(line)?.get(1)?.as_str();
+                Some(Symbol {
+                    name: name.to_string(),
+                    kind: *kind,
+                    path: path.to_path_buf(),
+                    line: i + 1,
+                })
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_rust_symbols() {
+        let source = "pub mod storage;\n\npub(crate) async fn connect() {}\nstruct Index;\nconst MAX_FILES: usize = 10;\nlet fn_like = 1;\n";
+        let symbols = extract_symbols(Path::new("src/lib.rs"), source);
+        let found: Vec<_> = symbols.iter().map(|s| (s.name.as_str(), s.kind, s.line)).collect();
+        assert_eq!(
+            found,
+            vec![
+                ("storage", SymbolKind::Module, 1),
+                ("connect", SymbolKind::Function, 3),
+                ("Index", SymbolKind::Type, 4),
+                ("MAX_FILES", SymbolKind::Constant, 5),
+            ]
+        );
+        assert!(extract_symbols(Path::new("README.md"), "fn main() {}").is_empty());
+    }
+}
@@ -4,6 +4,7 @@
 //! - ACP (Agent Client Protocol) client and session management
 //! - Agent lifecycle management
 //! - File system sandboxing and permissions
+//! - Workspace file, symbol and text search
 //! - SQLite-based persistence
 //!
 //! # Architecture
@@ -14,19 +15,51 @@
 //! ├─────────────────────────────────────────────────────────────┤
 //! │  acp/          - ACP protocol, client, sessions             │
 //! │  agent/        - Agent configuration and lifecycle          │
+//! │  changes.rs    - Config and settings changes while running  │
+//! │  config.rs     - config.toml and environment settings       │
+//! │  diagnostics.rs - Bug report bundles, crash reports         │
+//! │  export.rs     - Thread export to Markdown / HTML           │
+//! │  forge.rs      - Pull requests and issues on GitHub/GitLab  │
+//! │  git.rs        - Diffs and commits of an agent's changes    │
+//! │  index/        - Workspace file list, symbols, search       │
+//! │  metrics.rs    - Local usage metrics, opt-in exporters      │
+//! │  net.rs        - HTTP downloads, proxy settings             │
+//! │  platform.rs   - Executable lookup, archives per OS         │
+//! │  problems.rs   - Build/lint commands, parsed diagnostics    │
+//! │  redaction.rs  - Masking secrets in prompts and logs        │
 //! │  sandbox/      - File permissions, watcher                  │
+//! │  sanitize.rs   - Hiding paths, emails, keys before export   │
+//! │  secrets.rs    - API keys in the OS keychain                │
 //! │  storage/      - SQLite database, queries                   │
+//! │  test_runner.rs - Workspace test command, pass/fail counts  │
 //! │  types/        - Shared type definitions                    │
+//! │  webhook.rs    - Webhook notifications                      │
 //! │  error.rs      - Error types                                │
 //! └─────────────────────────────────────────────────────────────┘
 //! ```
 
 pub mod acp;
 pub mod agent;
+pub mod changes;
+pub mod config;
+pub mod diagnostics;
 pub mod error;
+pub mod export;
+pub mod forge;
+pub mod git;
+pub mod index;
+pub mod metrics;
+pub mod net;
+pub mod platform;
+pub mod problems;
+pub mod redaction;
 pub mod sandbox;
+pub mod sanitize;
+pub mod secrets;
 pub mod storage;
+pub mod test_runner;
 pub mod types;
+pub mod webhook;
 
 // Re-export commonly used types
 pub use error::{Error, Result};
@@ -36,27 +69,89 @@ pub use types::*;
 pub use acp::{
     // Traits
     AgentClient, AgentConnection, AgentServer, AgentServerCommand,
+    // Prompt attachments
+    AttachmentMode, MAX_INLINE_ATTACHMENT_SIZE,
     // ID types
     ConfigOptionId, ModelId, SessionModeId,
     // Session types
     ConfigValueType, LoadSessionResponse, NewSessionResponse, PromptMessage, PromptResult,
     SessionConfigOption, SessionInfo, SessionMode, SessionModel, SessionNotification,
     // Implementations
-    AcpClient, AgentClientDelegate, AcpConnection, AcpMessage, ProtocolHandler, Session,
+    AcpClient, AgentClientDelegate, AcpConnection, AcpMessage, NotificationSettings, ProtocolHandler, Session,
     SessionManager, AcpChannels, spawn_runtime_tasks_headless, spawn_runtime_tasks_with_ui,
+    // Middleware
+    MessageMiddleware, MiddlewareStack, ProtocolLog, SecretRedaction,
+    // Elicitation
+    AgentQuestion, ElicitationAction, ElicitationParams, ElicitationResponse,
+    // Tool permission requests
+    PermissionContent, PermissionLocation, PermissionOption, PermissionOptionKind, PermissionOutcome,
+    PermissionPreview, PermissionToolCall, RequestPermissionParams, RequestPermissionResponse, ToolPermissionRequest,
 };
 
 // Re-export agent components
 pub use agent::{
-    AgentAdapterRegistry, AgentManager, AgentRegistry, AgentServerAdapter,
-    ClaudeCodeAdapter, CodexAdapter, CustomAgentAdapter, GeminiAdapter, GooseAdapter,
+    AgentAdapterRegistry, AgentAuthFlow, AgentAvailability, AgentInstaller, AgentManager, AgentRegistry,
+    AgentServerAdapter, ClaudeCodeAdapter, CodexAdapter, CustomAgentAdapter, GeminiAdapter, GooseAdapter,
+    AuthAction, InstallEvent, InstallEventKind, InstallReporter, InstallState, InstallStatus,
+    run_batch, BatchEvent, BatchEventKind, BatchItem, BatchItemState, BatchReport, BatchRequest,
+    run_comparison, Comparison, ComparisonRequest, ComparisonSide,
+    sweep_stale_agent_processes, AgentProcessTracker,
 };
 
 // Re-export sandbox components
 pub use sandbox::{
-    FileOperation, FileSystemHandler, FileWatcher, PermissionManager, SecurityLevel,
-    TerminalHandler,
+    Approval, AutoMode, ChangeSetCollector, CommandConfirmation, CommandSegment, DryRunRecorder, ExternalEdit,
+    ExternalEditKind, ExternalEditTracker, FileOperation, FilePreview, FileSystemHandler, FileWatcher, PendingChange, PendingChangeSet, PermissionManager, PolicyDecision, PolicyScope,
+    ProposedAction, ProposedActionKind, SecurityLevel, TerminalHandler, ToolPolicy, Isolation, IsolationKind,
+    WorktreeManager, ContainerConfig, ContainerMount, ContainerRuntime, LiveTerminal, LiveTerminals, TerminalOutput,
 };
 
+// Re-export workspace index
+pub use index::{FileMatch, IndexConfig, IndexedFile, SearchQuery, Symbol, SymbolKind, TextMatch, WorkspaceIndex};
+
+// Re-export networking
+pub use net::{is_offline, set_offline, Downloader, ProxySettings};
+
+// Re-export secrets
+pub use secrets::{KeychainStore, MemorySecretStore, SecretKey, SecretStore};
+
+// Re-export secret redaction
+pub use redaction::{RedactingWriter, Redactor, REDACTED};
+
 // Re-export storage
-pub use storage::Storage;
+pub use storage::{BackupInfo, BackupPolicy, ContentCipher, Storage, StorageConfig, StorageRole, StorageWrite, StorageWriter, WriterConfig};
+
+// Re-export test runs
+pub use test_runner::{run_tests, TestConfig, TestFramework, TestRun, TestSummary};
+
+// Re-export build and lint checks
+pub use problems::{
+    parse_diagnostics_json, problems_prompt, run_checks, CheckConfig, CheckResult, CheckRun, DiagnosticsFile, Problem,
+    Severity,
+};
+
+// Re-export git commits
+pub use git::{commit_message_from_reply, commit_message_prompt};
+
+// Re-export pull requests and issues
+pub use forge::{
+    fetch_issue, forge_token_accounts, open_pull_request, pull_request_prompt, push_branch, unrecognized_origin_host,
+    ForgeKind, ForgeRemote, Issue, IssueComment, IssueRef, PullRequest, PullRequestDraft, PullRequestTarget,
+};
+
+// Re-export webhooks
+pub use webhook::{deliver_webhooks, Webhook, WebhookEvent, WebhookPayload};
+
+// Re-export configuration
+pub use changes::{watch_for_changes, Change, ChangeBus, ChangeWatcher, CHANGE_CHECK_INTERVAL};
+pub use config::{AgentPaths, Config, StorageSettings, CONFIG_FILE};
+
+// Re-export diagnostics
+pub use diagnostics::{install_panic_hook, redact, DiagnosticBundle};
+
+// Re-export metrics
+pub use metrics::{Histogram, MetricKind, MetricSample, MetricsExporter, MetricsSummary};
+
+// Re-export thread export
+pub use export::{export_audit_log, format_duration, AuditExportFormat, ExportFormat, ThreadExport};
+pub use sanitize::{find_sensitive, Finding, Redaction, SensitiveKind};
@@ -0,0 +1,205 @@
+//! Local usage metrics
+//!
+//! Metrics are off until the user turns them on. Once on, the app records
+//! a [`MetricSample`] for each session started, error hit and reply timed;
+//! samples are kept in the `metrics` table of the local database and summed
+//! up for the dashboard by [`get_metrics_summary`](crate::storage::get_metrics_summary).
+//!
+//! Nothing leaves the machine unless code registers a [`MetricsExporter`],
+//! e.g. a team's build shipping samples to their own collector. Exporters
+//! are never run in offline mode.
+
+use crate::error::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+/// A session was created
+pub const SESSION_STARTED: &str = "session.started";
+/// A turn ended with an error
+pub const TURN_ERROR: &str = "turn.error";
+/// Connecting to an agent failed
+pub const CONNECT_ERROR: &str = "connect.error";
+/// From sending a prompt to the first streamed text
+pub const FIRST_CHUNK_MS: &str = "turn.first_chunk_ms";
+/// From sending a prompt to the end of the turn
+pub const TURN_MS: &str = "turn.duration_ms";
+
+/// Upper bounds of the latency histogram buckets, in milliseconds
+///
+/// Samples above the last bound fall in one more, open-ended bucket.
+pub const LATENCY_BUCKETS_MS: [f64; 8] = [100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10_000.0, 30_000.0];
+
+/// How a sample's value is read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricKind {
+    /// Added up
+    Count,
+    /// A duration in milliseconds, summarized as a histogram
+    LatencyMs,
+}
+
+impl MetricKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MetricKind::Count => "count",
+            MetricKind::LatencyMs => "latency_ms",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "count" => Some(MetricKind::Count),
+            "latency_ms" => Some(MetricKind::LatencyMs),
+            _ => None,
+        }
+    }
+}
+
+/// One recorded measurement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricSample {
+    pub name: String,
+    pub kind: MetricKind,
+    /// The agent it concerns, if any
+    pub agent_id: Option<String>,
+    pub value: f64,
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl MetricSample {
+    /// One occurrence of `name`
+    pub fn count(name: &str, agent_id: Option<&str>) -> Self {
+        Self::new(name, MetricKind::Count, agent_id, 1.0)
+    }
+
+    /// `name` taking `duration`
+    pub fn latency(name: &str, agent_id: Option<&str>, duration: Duration) -> Self {
+        Self::new(name, MetricKind::LatencyMs, agent_id, duration.as_secs_f64() * 1000.0)
+    }
+
+    fn new(name: &str, kind: MetricKind, agent_id: Option<&str>, value: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            kind,
+            agent_id: agent_id.map(str::to_string),
+            value,
+            recorded_at: Utc::now(),
+        }
+    }
+}
+
+/// Latency samples of one metric, bucketed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Histogram {
+    pub name: String,
+    /// Samples per bucket of [`LATENCY_BUCKETS_MS`], plus the open-ended one
+    pub buckets: Vec<u32>,
+    pub count: u32,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+}
+
+impl Histogram {
+    /// Bucket `values`, in milliseconds
+    pub fn from_values(name: impl Into<String>, mut values: Vec<f64>) -> Self {
+        values.sort_by(|a, b| a.total_cmp(b));
+        let mut buckets = vec![0; LATENCY_BUCKETS_MS.len() + 1];
+        for value in &values {
+            let bucket = LATENCY_BUCKETS_MS
+                .iter()
+                .position(|bound| value <= bound)
+                .unwrap_or(LATENCY_BUCKETS_MS.len());
+            buckets[bucket] += 1;
+        }
+        let percentile = |p: f64| match values.len() {
+            0 => 0.0,
+            len => values[((len - 1) as f64 * p).round() as usize],
+        };
+        Self {
+            name: name.into(),
+            buckets,
+            count: values.len() as u32,
+            p50_ms: percentile(0.5),
+            p95_ms: percentile(0.95),
+        }
+    }
+}
+
+/// Metrics over a time range, for the dashboard
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsSummary {
+    /// Totals of count metrics, by name
+    pub counters: Vec<(String, u64)>,
+    pub histograms: Vec<Histogram>,
+}
+
+impl MetricsSummary {
+    pub fn is_empty(&self) -> bool {
+        self.counters.is_empty() && self.histograms.is_empty()
+    }
+
+    /// The total of a count metric, 0 if it never happened
+    pub fn counter(&self, name: &str) -> u64 {
+        self.counters
+            .iter()
+            .find(|(counter, _)| counter == name)
+            .map_or(0, |(_, total)| *total)
+    }
+
+    pub fn histogram(&self, name: &str) -> Option<&Histogram> {
+        self.histograms.iter().find(|histogram| histogram.name == name)
+    }
+}
+
+/// Ships samples somewhere else, for teams collecting metrics centrally
+///
+/// None is registered by default; an exporter only runs once code adds it.
+#[async_trait]
+pub trait MetricsExporter: Send + Sync {
+    /// Identifies the exporter in logs
+    fn name(&self) -> &str;
+
+    async fn export(&self, samples: &[MetricSample]) -> Result<()>;
+}
+
+/// Hand `samples` to every exporter, logging the ones that fail
+///
+/// Does nothing in offline mode.
+pub async fn export_metrics(exporters: &[Arc<dyn MetricsExporter>], samples: &[MetricSample]) {
+    if exporters.is_empty() || samples.is_empty() || crate::net::is_offline() {
+        return;
+    }
+    for exporter in exporters {
+        if let Err(e) = exporter.export(samples).await {
+            warn!("Metrics exporter {} failed: {}", exporter.name(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets() {
+        let histogram = Histogram::from_values(FIRST_CHUNK_MS, vec![40.0, 900.0, 100.0, 45_000.0, 1200.0]);
+        assert_eq!(histogram.count, 5);
+        assert_eq!(histogram.buckets, [2, 0, 0, 1, 1, 0, 0, 0, 1]);
+        assert_eq!(histogram.p50_ms, 900.0);
+        assert_eq!(histogram.p95_ms, 45_000.0);
+
+        let empty = Histogram::from_values(TURN_MS, Vec::new());
+        assert_eq!((empty.count, empty.p50_ms), (0, 0.0));
+
+        let sample = MetricSample::latency(TURN_MS, Some("codex-cli"), Duration::from_millis(1500));
+        assert_eq!((sample.kind, sample.value), (MetricKind::LatencyMs, 1500.0));
+    }
+}
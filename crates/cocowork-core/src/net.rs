@@ -0,0 +1,321 @@
+//! HTTP downloads and proxy settings
+//!
+//! Agent bridges are fetched from GitHub releases. [`Downloader`] wraps a
+//! reqwest client configured from the app's [`ProxySettings`] and verifies
+//! downloaded archives against their published SHA-256 digest. The same
+//! settings are exported as `HTTP(S)_PROXY` variables for agent processes
+//! so their own API traffic goes through the proxy too.
+//!
+//! Offline mode, for air-gapped machines, turns off everything here that
+//! reaches the network: downloads fail before connecting, and npm installs
+//! and update checks are refused by [`ensure_online`].
+
+use crate::error::{Error, Result};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, info, warn};
+
+/// User agent sent with every request (GitHub's API requires one)
+const USER_AGENT: &str = concat!("cocowork/", env!("CARGO_PKG_VERSION"));
+
+/// Whether offline mode is on, for the whole process
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Turn offline mode on or off, e.g. from settings
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+/// Offline mode held on or off for a test
+///
+/// Offline mode is process-wide and tests run in parallel, so every test that
+/// depends on it holds one of these; they take turns, and offline mode goes
+/// back off when each is dropped.
+#[cfg(test)]
+pub(crate) struct OfflineModeGuard {
+    _turn: std::sync::MutexGuard<'static, ()>,
+}
+
+#[cfg(test)]
+impl OfflineModeGuard {
+    pub(crate) fn new(offline: bool) -> Self {
+        static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+        let turn = LOCK.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        set_offline(offline);
+        Self { _turn: turn }
+    }
+}
+
+#[cfg(test)]
+impl Drop for OfflineModeGuard {
+    fn drop(&mut self) {
+        set_offline(false);
+    }
+}
+
+/// Fail with [`Error::Offline`] if offline mode is on; `action` says what was refused
+pub fn ensure_online(action: &str) -> Result<()> {
+    if is_offline() {
+        Err(Error::Offline(action.to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Environment variables keeping agent processes off the network while offline
+///
+/// Agents launched through `npx` then only run what's already in npm's cache.
+pub fn offline_env_vars() -> HashMap<String, String> {
+    let mut env = HashMap::new();
+    if is_offline() {
+        env.insert("npm_config_offline".to_string(), "true".to_string());
+    }
+    env
+}
+
+/// Proxy configuration from app settings
+///
+/// Empty settings leave reqwest's defaults in place, which already honor
+/// the process `HTTP_PROXY` / `HTTPS_PROXY` / `NO_PROXY` variables.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProxySettings {
+    pub http_proxy: Option<String>,
+    pub https_proxy: Option<String>,
+    /// Comma-separated hosts that bypass the proxy
+    pub no_proxy: Option<String>,
+}
+
+impl ProxySettings {
+    pub fn is_empty(&self) -> bool {
+        self.http_proxy.is_none() && self.https_proxy.is_none()
+    }
+
+    /// Environment variables passing the proxy on to child processes
+    ///
+    /// Both cases are set since tools disagree on which one they read.
+    pub fn env_vars(&self) -> HashMap<String, String> {
+        let mut env = HashMap::new();
+        let vars = [
+            ("HTTP_PROXY", &self.http_proxy),
+            ("HTTPS_PROXY", &self.https_proxy),
+            ("NO_PROXY", &self.no_proxy),
+        ];
+        for (name, value) in vars {
+            if let Some(value) = value {
+                env.insert(name.to_string(), value.clone());
+                env.insert(name.to_lowercase(), value.clone());
+            }
+        }
+        env
+    }
+
+    /// Build an HTTP client that uses these settings
+    pub fn client(&self) -> Result<reqwest::Client> {
+        let no_proxy = self.no_proxy.as_deref().and_then(reqwest::NoProxy::from_string);
+        let invalid = |e: reqwest::Error| Error::InvalidConfig(format!("invalid proxy URL: {}", e));
+
+        let mut builder = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .connect_timeout(Duration::from_secs(30));
+        if let Some(url) = &self.http_proxy {
+            builder = builder.proxy(reqwest::Proxy::http(url).map_err(invalid)?.no_proxy(no_proxy.clone()));
+        }
+        if let Some(url) = &self.https_proxy {
+            builder = builder.proxy(reqwest::Proxy::https(url).map_err(invalid)?.no_proxy(no_proxy));
+        }
+        builder.build().map_err(|e| Error::Download(e.to_string()))
+    }
+}
+
+/// Compare a SHA-256 digest against the expected hex string
+pub fn verify_sha256(actual: &str, expected: &str) -> Result<()> {
+    if actual.eq_ignore_ascii_case(expected.trim()) {
+        Ok(())
+    } else {
+        Err(Error::Download(format!(
+            "checksum mismatch: expected {}, got {}",
+            expected.trim(),
+            actual
+        )))
+    }
+}
+
+/// Parse the first digest out of a `.sha256` / `SHA256SUMS` file
+///
+/// With `file_name`, only a line naming that file matches; otherwise the
+/// first well-formed digest wins.
+pub fn parse_checksum_file(contents: &str, file_name: Option<&str>) -> Option<String> {
+    contents.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let digest = parts.next()?;
+        if digest.len() != 64 || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        match (file_name, parts.next()) {
+            (Some(name), Some(listed)) if listed.trim_start_matches('*') != name => None,
+            _ => Some(digest.to_lowercase()),
+        }
+    })
+}
+
+/// Fetches release metadata and archives over HTTP
+#[derive(Clone)]
+pub struct Downloader {
+    client: reqwest::Client,
+}
+
+impl Downloader {
+    /// A downloader using `proxy`, refused while offline
+    pub fn new(proxy: &ProxySettings) -> Result<Self> {
+        ensure_online("downloading")?;
+        Ok(Self {
+            client: proxy.client()?,
+        })
+    }
+
+    async fn get(&self, url: &str, accept: &str) -> Result<reqwest::Response> {
+        debug!("GET {}", url);
+        self.client
+            .get(url)
+            .header("accept", accept)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| Error::Download(format!("{}: {}", url, e)))
+    }
+
+    /// Fetch and parse a JSON document
+    pub async fn get_json(&self, url: &str) -> Result<serde_json::Value> {
+        self.get(url, "application/vnd.github+json, application/json")
+            .await?
+            .json()
+            .await
+            .map_err(|e| Error::Download(format!("{}: {}", url, e)))
+    }
+
+    /// Fetch a text document
+    pub async fn get_text(&self, url: &str) -> Result<String> {
+        self.get(url, "text/plain, */*")
+            .await?
+            .text()
+            .await
+            .map_err(|e| Error::Download(format!("{}: {}", url, e)))
+    }
+
+    /// Download a file, reporting `(received, total)` bytes as it goes
+    ///
+    /// Returns the SHA-256 of the downloaded bytes as lowercase hex. A
+    /// partial file is removed if the download fails.
+    pub async fn download_to_file(
+        &self,
+        url: &str,
+        dest: &Path,
+        mut on_progress: impl FnMut(u64, Option<u64>),
+    ) -> Result<String> {
+        info!("Downloading {} to {}", url, dest.display());
+        let mut response = self.get(url, "application/octet-stream").await?;
+        let total = response.content_length();
+
+        let result = async {
+            let mut file = tokio::fs::File::create(dest).await?;
+            let mut hasher = Sha256::new();
+            let mut received = 0u64;
+            while let Some(chunk) = response
+                .chunk()
+                .await
+                .map_err(|e| Error::Download(format!("{}: {}", url, e)))?
+            {
+                hasher.update(&chunk);
+                file.write_all(&chunk).await?;
+                received += chunk.len() as u64;
+                on_progress(received, total);
+            }
+            file.flush().await?;
+            Ok(hex::encode(hasher.finalize()))
+        }
+        .await;
+
+        if result.is_err() {
+            if let Err(e) = tokio::fs::remove_file(dest).await {
+                warn!("Failed to remove partial download {}: {}", dest.display(), e);
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::webhook::{deliver_webhooks, Webhook, WebhookEvent, WebhookPayload};
+
+    #[test]
+    fn test_proxy_env_vars() {
+        assert!(ProxySettings::default().env_vars().is_empty());
+
+        let proxy = ProxySettings {
+            https_proxy: Some("http://proxy.corp:3128".to_string()),
+            no_proxy: Some("localhost,.corp".to_string()),
+            ..Default::default()
+        };
+        let env = proxy.env_vars();
+        assert_eq!(env["HTTPS_PROXY"], "http://proxy.corp:3128");
+        assert_eq!(env["https_proxy"], "http://proxy.corp:3128");
+        assert_eq!(env["NO_PROXY"], "localhost,.corp");
+        assert!(!env.contains_key("HTTP_PROXY"));
+    }
+
+    #[test]
+    fn test_offline_mode() {
+        {
+            let _online = OfflineModeGuard::new(false);
+            assert!(Downloader::new(&ProxySettings::default()).is_ok());
+            assert!(offline_env_vars().is_empty());
+            assert!(ensure_online("npm install").is_ok());
+        }
+
+        let _offline = OfflineModeGuard::new(true);
+        let refused = Downloader::new(&ProxySettings::default()).err().unwrap();
+        assert_eq!(refused.to_string(), "Offline mode is on, so downloading is turned off");
+        assert!(ensure_online("npm install").is_err());
+        assert_eq!(offline_env_vars()["npm_config_offline"], "true");
+
+        // Webhooks aren't sent either
+        let receiver = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        receiver.set_nonblocking(true).unwrap();
+        let url = format!("http://{}/hook", receiver.local_addr().unwrap());
+        let hook = Webhook::new(&url, None, vec![WebhookEvent::TurnComplete]).unwrap();
+        let payload = WebhookPayload::new(WebhookEvent::TurnComplete, "s1", "claude-code", "/work");
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(deliver_webhooks(&[hook], &payload, &ProxySettings::default()));
+        assert_eq!(receiver.accept().unwrap_err().kind(), std::io::ErrorKind::WouldBlock);
+    }
+
+    #[test]
+    fn test_checksums() {
+        let digest = "a".repeat(64);
+        let sums = format!(
+            "{}  other.tar.gz\n{} *codex-acp.tar.gz\n",
+            "b".repeat(64),
+            digest
+        );
+        assert_eq!(
+            parse_checksum_file(&sums, Some("codex-acp.tar.gz")),
+            Some(digest.clone())
+        );
+        assert_eq!(parse_checksum_file(&digest, None), Some(digest.clone()));
+        assert_eq!(parse_checksum_file("not a checksum", None), None);
+
+        assert!(verify_sha256(&digest, &digest.to_uppercase()).is_ok());
+        assert!(verify_sha256(&digest, &"b".repeat(64)).is_err());
+    }
+}
@@ -0,0 +1,225 @@
+//! Platform differences for finding and installing agents
+//!
+//! Agents are launched from whatever Node.js or binary install the user has,
+//! which lives in different places on macOS, Linux and Windows. Windows also
+//! needs `.cmd` shims for npm-installed tools and ships release archives as
+//! zip files, so adapters go through these helpers rather than assuming
+//! `which`, `sh` and `tar xzf` are available.
+
+use crate::error::{AgentError, Error, Result};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Extensions tried for bare command names on Windows when `PATHEXT` is unset
+#[cfg(windows)]
+const DEFAULT_PATHEXT: &str = ".COM;.EXE;.BAT;.CMD";
+
+/// Name of an executable file on this platform, e.g. `codex-acp.exe` on Windows
+pub fn exe_name(name: &str) -> String {
+    if cfg!(windows) {
+        format!("{}.exe", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// The npm executable; on Windows npm is a `.cmd` shim
+pub fn npm_command() -> &'static str {
+    if cfg!(windows) {
+        "npm.cmd"
+    } else {
+        "npm"
+    }
+}
+
+/// Find an executable on `PATH`, like `which` (or `where` on Windows)
+pub fn find_executable(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    find_executable_in(name, &path)
+}
+
+/// Find an executable in the given `PATH`-style directory list
+pub fn find_executable_in(name: &str, path: &OsStr) -> Option<PathBuf> {
+    let candidate = Path::new(name);
+    if candidate.components().count() > 1 {
+        return is_executable(candidate).then(|| candidate.to_path_buf());
+    }
+
+    let found = std::env::split_paths(path)
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .flat_map(|dir| candidate_names(name).into_iter().map(move |n| dir.join(n)))
+        .find(|path| is_executable(path));
+    debug!("Resolved {} to {:?}", name, found);
+    found
+}
+
+/// Resolve a command to the full path to spawn, or leave it as given
+///
+/// Needed on Windows where spawning `gemini` doesn't find `gemini.cmd`.
+pub fn resolve_command(name: &str) -> String {
+    find_executable(name)
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_else(|| name.to_string())
+}
+
+#[cfg(windows)]
+fn candidate_names(name: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    if Path::new(name).extension().is_some() {
+        names.push(name.to_string());
+    }
+    let pathext = std::env::var("PATHEXT").unwrap_or_else(|_| DEFAULT_PATHEXT.to_string());
+    names.extend(
+        pathext
+            .split(';')
+            .filter(|ext| !ext.is_empty())
+            .map(|ext| format!("{}{}", name, ext.to_lowercase())),
+    );
+    names
+}
+
+#[cfg(not(windows))]
+fn candidate_names(name: &str) -> Vec<String> {
+    vec![name.to_string()]
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Global `node_modules` directories where npm packages are commonly found
+pub fn global_node_modules_dirs() -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    let home = dirs::home_dir();
+
+    if cfg!(windows) {
+        if let Some(appdata) = std::env::var_os("APPDATA") {
+            found.push(PathBuf::from(appdata).join("npm").join("node_modules"));
+        }
+        if let Some(program_files) = std::env::var_os("ProgramFiles") {
+            found.push(PathBuf::from(program_files).join("nodejs").join("node_modules"));
+        }
+        // nvm-windows links the active version here
+        if let Some(symlink) = std::env::var_os("NVM_SYMLINK") {
+            found.push(PathBuf::from(symlink).join("node_modules"));
+        }
+    } else {
+        found.push(PathBuf::from("/usr/local/lib/node_modules"));
+        found.push(PathBuf::from("/usr/lib/node_modules"));
+        found.push(PathBuf::from("/opt/homebrew/lib/node_modules"));
+        if let Some(home) = &home {
+            found.push(home.join(".npm").join("lib").join("node_modules"));
+            // nvm installs each Node version separately
+            let pattern = home.join(".nvm/versions/node/*/lib/node_modules");
+            if let Ok(paths) = glob::glob(&pattern.to_string_lossy()) {
+                found.extend(paths.flatten());
+            }
+        }
+    }
+
+    found
+}
+
+fn setup_failed(message: String) -> Error {
+    Error::Agent(AgentError::SetupFailed(message))
+}
+
+/// Extract a `.tar.gz` or `.zip` release archive into `dest`
+///
+/// Uses the system `tar`, which also reads zip files on Windows 10 and
+/// later; zips fall back to PowerShell's `Expand-Archive` if that fails.
+pub async fn extract_archive(archive: &Path, dest: &Path) -> Result<()> {
+    let is_zip = archive
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"));
+
+    let output = tokio::process::Command::new("tar")
+        .arg(if is_zip { "-xf" } else { "-xzf" })
+        .arg(archive)
+        .arg("-C")
+        .arg(dest)
+        .output()
+        .await;
+
+    let tar_error = match output {
+        Ok(output) if output.status.success() => return Ok(()),
+        Ok(output) => String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        Err(e) => e.to_string(),
+    };
+    if !(is_zip && cfg!(windows)) {
+        return Err(setup_failed(format!(
+            "Failed to extract {}: {}",
+            archive.display(),
+            tar_error
+        )));
+    }
+
+    debug!("tar failed ({}), trying Expand-Archive", tar_error);
+    let quote = |p: &Path| format!("'{}'", p.to_string_lossy().replace('\'', "''"));
+    let script = format!(
+        "Expand-Archive -LiteralPath {} -DestinationPath {} -Force",
+        quote(archive),
+        quote(dest)
+    );
+    let output = tokio::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .output()
+        .await
+        .map_err(|e| setup_failed(format!("Failed to run PowerShell: {}", e)))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(setup_failed(format!(
+            "Failed to extract {}: {}",
+            archive.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_executable_in() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let empty = tempfile::tempdir().unwrap();
+        let bin = tempfile::tempdir().unwrap();
+        let tool = bin.path().join("mytool");
+        std::fs::write(&tool, "#!/bin/sh\n").unwrap();
+        std::fs::write(bin.path().join("notexec"), "").unwrap();
+
+        let path = std::env::join_paths([empty.path(), bin.path()]).unwrap();
+        // Not executable yet
+        assert_eq!(find_executable_in("mytool", &path), None);
+
+        std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(find_executable_in("mytool", &path), Some(tool.clone()));
+        assert_eq!(find_executable_in("notexec", &path), None);
+        assert_eq!(find_executable_in("missing", &path), None);
+
+        // Paths are checked directly instead of searching
+        let direct = tool.to_string_lossy().to_string();
+        assert_eq!(find_executable_in(&direct, OsStr::new("")), Some(tool));
+    }
+
+    #[tokio::test]
+    async fn test_extract_missing_archive_fails() {
+        let dest = tempfile::tempdir().unwrap();
+        let result = extract_archive(&dest.path().join("missing.tar.gz"), dest.path()).await;
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,671 @@
+//! Build and lint commands and the problems they report
+//!
+//! Each workspace can have a list of build and lint commands, stored like its
+//! test command. They run one after another through the user's shell, and
+//! their output is read for diagnostics: rustc's `error: ...` / `--> file:line:col`
+//! pairs, tsc's `file(line,col): error ...`, eslint's grouped listing, and the
+//! `file:line:col: message` lines most other compilers and linters print.
+//!
+//! A workspace can also name a JSON file an external tool keeps writing
+//! diagnostics to, such as `cargo check --message-format=json` output saved
+//! by a watch task or an LSP `publishDiagnostics` dump. [`DiagnosticsFile`]
+//! reads it again whenever a [`FileWatcher`] reports it changed.
+
+use crate::error::Result;
+use crate::sandbox::FileWatcher;
+use crate::storage::{delete_setting, get_setting, set_setting};
+use crate::test_runner::run_in_shell;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::debug;
+
+const CHECK_CONFIG_KEY: &str = "checks.workspace";
+
+/// Most problems kept from a run
+pub const MAX_PROBLEMS: usize = 200;
+
+/// The build and lint commands of a workspace
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CheckConfig {
+    /// Shell command lines, run in order
+    pub commands: Vec<String>,
+    /// Run the commands after each turn the agent finishes in the workspace
+    pub run_after_turn: bool,
+    /// JSON file of diagnostics another tool writes, relative to the workspace
+    pub diagnostics_file: Option<String>,
+}
+
+impl CheckConfig {
+    fn setting_key(workspace: &Path) -> String {
+        format!("{}.{}", CHECK_CONFIG_KEY, workspace.display())
+    }
+
+    /// The commands stored for `workspace`, if any
+    pub fn load(conn: &rusqlite::Connection, workspace: &Path) -> Result<Option<Self>> {
+        Ok(get_setting(conn, &Self::setting_key(workspace))?.and_then(|raw| serde_json::from_str(&raw).ok()))
+    }
+
+    pub fn save(&self, conn: &rusqlite::Connection, workspace: &Path) -> Result<()> {
+        set_setting(conn, &Self::setting_key(workspace), &serde_json::to_string(self)?)
+    }
+
+    pub fn clear(conn: &rusqlite::Connection, workspace: &Path) -> Result<()> {
+        delete_setting(conn, &Self::setting_key(workspace))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    fn parse(word: &str) -> Option<Self> {
+        match word.to_ascii_lowercase().as_str() {
+            "error" | "fatal error" => Some(Self::Error),
+            "warning" | "warn" => Some(Self::Warning),
+            "note" | "info" | "information" | "help" | "hint" => Some(Self::Note),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Note => "note",
+        })
+    }
+}
+
+/// A diagnostic at a place in a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Problem {
+    /// As printed, relative to the workspace when it was inside it
+    pub path: String,
+    pub line: u32,
+    pub column: Option<u32>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.path, self.line)?;
+        if let Some(column) = self.column {
+            write!(f, ":{}", column)?;
+        }
+        write!(f, ": {}: {}", self.severity, self.message)
+    }
+}
+
+/// How one of the commands ended
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub command: String,
+    /// Exit code; -1 if the command was killed or timed out
+    pub exit_code: i32,
+    /// The end of stdout, then stderr
+    pub output: String,
+}
+
+/// A finished run of a workspace's build and lint commands
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckRun {
+    pub workspace: PathBuf,
+    pub results: Vec<CheckResult>,
+    /// Problems from all the commands, errors first
+    pub problems: Vec<Problem>,
+    pub duration_ms: u64,
+    pub finished_at: DateTime<Utc>,
+}
+
+impl CheckRun {
+    /// Whether every command succeeded without reporting an error
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(|result| result.exit_code == 0) && self.count(Severity::Error) == 0
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.problems.iter().filter(|problem| problem.severity == severity).count()
+    }
+}
+
+/// Run `commands` one after another in `workspace`, each killed after `timeout`
+pub async fn run_checks(workspace: &Path, commands: &[String], timeout: Duration) -> Result<CheckRun> {
+    let started = Instant::now();
+    let mut results = Vec::new();
+    let mut problems = Vec::new();
+    for command in commands {
+        let (exit_code, output) = run_in_shell(workspace, command, timeout).await?;
+        for problem in parse_problems(&output, workspace) {
+            if !problems.contains(&problem) {
+                problems.push(problem);
+            }
+        }
+        results.push(CheckResult { command: command.clone(), exit_code, output });
+    }
+    // Stable, so each severity keeps the order the tools printed
+    problems.sort_by_key(|problem| problem.severity);
+    problems.truncate(MAX_PROBLEMS);
+
+    Ok(CheckRun {
+        workspace: workspace.to_path_buf(),
+        results,
+        problems,
+        duration_ms: started.elapsed().as_millis() as u64,
+        finished_at: Utc::now(),
+    })
+}
+
+/// A prompt asking the agent to fix `problems`
+pub fn problems_prompt(problems: &[Problem]) -> String {
+    let mut prompt = String::from(if problems.len() == 1 {
+        "The build reports this problem:\n"
+    } else {
+        "The build and linters report these problems:\n"
+    });
+    for problem in problems {
+        prompt.push_str(&format!("\n- {}", problem));
+    }
+    prompt.push_str("\n\nFix them, then run the commands again to check.");
+    prompt
+}
+
+/// Diagnostics in the output of a build or lint command run in `workspace`
+pub fn parse_problems(output: &str, workspace: &Path) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    // rustc's `error[E0308]: message`, waiting for the `-->` line under it
+    let mut header: Option<(Severity, String)> = None;
+    // The file eslint lists problems under
+    let mut listed_file: Option<String> = None;
+
+    for line in output.lines() {
+        let line = line.trim_end();
+        let pending = header.take();
+        let trimmed = line.trim_start();
+
+        if let Some(location) = trimmed.strip_prefix("--> ") {
+            if let (Some((severity, message)), Some((path, line, column))) = (pending, split_location(location)) {
+                problems.push(Problem { path: relative(path, workspace), line, column, severity, message });
+            }
+            continue;
+        }
+        if let Some(found) = rustc_header(line) {
+            header = Some(found);
+            continue;
+        }
+        if line.starts_with(char::is_whitespace) {
+            if let Some(path) = &listed_file {
+                if let Some(problem) = listed_problem(trimmed, path) {
+                    problems.push(problem);
+                }
+            }
+            continue;
+        }
+        listed_file = None;
+
+        if let Some(problem) = tsc_problem(line).or_else(|| colon_problem(line)) {
+            problems.push(Problem { path: relative(&problem.path, workspace), ..problem });
+        } else if !line.is_empty() && !line.contains(' ') && Path::new(line).extension().is_some() {
+            listed_file = Some(relative(line, workspace));
+        }
+    }
+    problems
+}
+
+/// Diagnostics in a JSON file or JSON lines: cargo's `--message-format=json`
+/// messages, rustc diagnostics, LSP `publishDiagnostics` parameters, or plain
+/// `{ "file", "line", "column", "severity", "message" }` objects
+pub fn parse_diagnostics_json(text: &str, workspace: &Path) -> Result<Vec<Problem>> {
+    let values = match serde_json::from_str::<Value>(text) {
+        Ok(value) => vec![value],
+        Err(e) => {
+            let lines: Vec<Value> = text
+                .lines()
+                .filter(|line| line.trim_start().starts_with(['{', '[']))
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect();
+            if lines.is_empty() && !text.trim().is_empty() {
+                return Err(e.into());
+            }
+            lines
+        }
+    };
+    let mut problems = Vec::new();
+    for value in &values {
+        collect_diagnostics(value, workspace, &mut problems);
+    }
+    problems.dedup();
+    problems.truncate(MAX_PROBLEMS);
+    Ok(problems)
+}
+
+fn collect_diagnostics(value: &Value, workspace: &Path, problems: &mut Vec<Problem>) {
+    let text = |value: &Value, key: &str| value.get(key).and_then(Value::as_str).map(str::to_string);
+    let number = |value: &Value, key: &str| value.get(key).and_then(Value::as_u64).map(|n| n as u32);
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                collect_diagnostics(item, workspace, problems);
+            }
+        }
+        // cargo wraps compiler diagnostics among its other messages
+        Value::Object(_) if value.get("reason").is_some() => {
+            if let Some(message) = value.get("message").filter(|_| value["reason"] == "compiler-message") {
+                collect_diagnostics(message, workspace, problems);
+            }
+        }
+        // rustc: the primary span is where the problem is
+        Value::Object(_) if value.get("spans").is_some() => {
+            let severity = value.get("level").and_then(Value::as_str).and_then(Severity::parse);
+            let span = value["spans"].as_array().and_then(|spans| {
+                spans.iter().find(|span| span.get("is_primary").and_then(Value::as_bool).unwrap_or(false))
+            });
+            if let (Some(severity), Some(span), Some(message)) = (severity, span, text(value, "message")) {
+                if let (Some(path), Some(line)) = (text(span, "file_name"), number(span, "line_start")) {
+                    let column = number(span, "column_start");
+                    problems.push(Problem { path: relative(&path, workspace), line, column, severity, message });
+                }
+            }
+        }
+        // LSP: zero-based positions, severity 1 to 4
+        Value::Object(_) if value.get("diagnostics").is_some() => {
+            let Some(uri) = text(value, "uri") else {
+                return;
+            };
+            let path = uri.strip_prefix("file://").unwrap_or(&uri);
+            for diagnostic in value["diagnostics"].as_array().into_iter().flatten() {
+                let start = &diagnostic["range"]["start"];
+                let (Some(line), Some(message)) = (number(start, "line"), text(diagnostic, "message")) else {
+                    continue;
+                };
+                let severity = match diagnostic.get("severity").and_then(Value::as_u64) {
+                    Some(2) => Severity::Warning,
+                    Some(3 | 4) => Severity::Note,
+                    _ => Severity::Error,
+                };
+                problems.push(Problem {
+                    path: relative(path, workspace),
+                    line: line + 1,
+                    column: number(start, "character").map(|column| column + 1),
+                    severity,
+                    message,
+                });
+            }
+        }
+        Value::Object(_) => {
+            let path = text(value, "file").or_else(|| text(value, "path")).or_else(|| text(value, "filename"));
+            if let (Some(path), Some(line), Some(message)) = (path, number(value, "line"), text(value, "message")) {
+                let severity =
+                    text(value, "severity").and_then(|word| Severity::parse(&word)).unwrap_or(Severity::Error);
+                let column = number(value, "column").or_else(|| number(value, "col"));
+                problems.push(Problem { path: relative(&path, workspace), line, column, severity, message });
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Diagnostics another tool keeps writing to a JSON file, read again each time it changes
+pub struct DiagnosticsFile {
+    path: PathBuf,
+    workspace: PathBuf,
+    state: Mutex<DiagnosticsState>,
+}
+
+#[derive(Default)]
+struct DiagnosticsState {
+    problems: Vec<Problem>,
+    /// Why the file couldn't be read, or `None` once it could
+    error: Option<String>,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+impl DiagnosticsFile {
+    /// The diagnostics in `path` (relative paths are inside `workspace`), read now
+    pub fn new(workspace: &Path, path: &str) -> Arc<Self> {
+        let file = Arc::new(Self {
+            path: workspace.join(path),
+            workspace: workspace.to_path_buf(),
+            state: Mutex::new(DiagnosticsState::default()),
+        });
+        file.reload();
+        file
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn problems(&self) -> Vec<Problem> {
+        self.state.lock().unwrap().problems.clone()
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.state.lock().unwrap().error.clone()
+    }
+
+    /// When the file was last read successfully
+    pub fn updated_at(&self) -> Option<DateTime<Utc>> {
+        self.state.lock().unwrap().updated_at
+    }
+
+    /// Read the file again; a missing file has no problems
+    pub fn reload(&self) {
+        let result = match std::fs::read_to_string(&self.path) {
+            Ok(text) => parse_diagnostics_json(&text, &self.workspace),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        };
+        let mut state = self.state.lock().unwrap();
+        match result {
+            Ok(problems) => {
+                state.problems = problems;
+                state.error = None;
+                state.updated_at = Some(Utc::now());
+            }
+            Err(e) => state.error = Some(format!("Failed to read {}: {}", self.path.display(), e)),
+        }
+    }
+
+    /// Read the file again whenever it changes, until the returned watcher is dropped
+    pub fn start_watching(self: &Arc<Self>) -> Result<FileWatcher> {
+        let (tx, mut rx) = mpsc::channel(256);
+        let mut watcher = FileWatcher::new();
+        watcher.set_event_channel(tx);
+        // Tools often replace the file rather than write it, so watch its directory
+        let dir = self.path.parent().unwrap_or(&self.workspace);
+        watcher.watch(dir)?;
+
+        let file = Arc::clone(self);
+        let name = self.path.file_name().map(|name| name.to_os_string());
+        tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                if event.path.file_name().map(|name| name.to_os_string()) == name {
+                    file.reload();
+                }
+            }
+            debug!("Diagnostics file watcher stopped");
+        });
+        Ok(watcher)
+    }
+}
+
+/// `error[E0308]: mismatched types` or `warning: unused import`
+fn rustc_header(line: &str) -> Option<(Severity, String)> {
+    let (head, message) = line.split_once(": ")?;
+    let word = head.split_once('[').map_or(head, |(word, _)| word);
+    if word.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((Severity::parse(word)?, message.trim().to_string()))
+}
+
+/// `  3:10  error  'x' is defined but never used  no-unused-vars` under an eslint file heading
+fn listed_problem(line: &str, path: &str) -> Option<Problem> {
+    let (position, rest) = line.split_once(char::is_whitespace)?;
+    let (row, column) = position.split_once(':')?;
+    let rest = rest.trim_start();
+    let (word, message) = rest.split_once(char::is_whitespace)?;
+    // The rule name follows the message after a run of spaces
+    let message = message.trim().split("  ").next().unwrap_or_default();
+    Some(Problem {
+        path: path.to_string(),
+        line: row.parse().ok()?,
+        column: column.parse().ok(),
+        severity: Severity::parse(word)?,
+        message: message.to_string(),
+    })
+}
+
+/// `src/app.ts(12,5): error TS2322: Type 'string' is not assignable to type 'number'.`
+fn tsc_problem(line: &str) -> Option<Problem> {
+    let (place, rest) = line.split_once("): ")?;
+    let (path, position) = place.rsplit_once('(')?;
+    let (row, column) = position.split_once(',')?;
+    let (severity, message) = severity_prefix(rest);
+    Some(Problem {
+        path: path.to_string(),
+        line: row.parse().ok()?,
+        column: column.parse().ok(),
+        severity,
+        message,
+    })
+}
+
+/// `main.c:3:5: error: expected ';'` or `app.py:10:1: E302 expected 2 blank lines`
+fn colon_problem(line: &str) -> Option<Problem> {
+    let (location, rest) = split_after_location(line)?;
+    let (path, line, column) = split_location(location)?;
+    let (severity, message) = severity_prefix(rest);
+    (!message.is_empty()).then(|| Problem { path: path.to_string(), line, column, severity, message })
+}
+
+/// The `file:line[:col]` at the start of `line` and what follows its `: `
+fn split_after_location(line: &str) -> Option<(&str, &str)> {
+    // Skip a Windows drive letter so its colon isn't taken for the separator
+    let skip = if line.as_bytes().get(1) == Some(&b':') { 2 } else { 0 };
+    let end = line[skip..].find(": ")? + skip;
+    Some((&line[..end], &line[end + 2..]))
+}
+
+/// `path:line` or `path:line:col`; the path must look like a file
+fn split_location(location: &str) -> Option<(&str, u32, Option<u32>)> {
+    let location = location.trim();
+    let (rest, last) = location.rsplit_once(':')?;
+    let last: u32 = last.parse().ok()?;
+    let (path, line, column) = match rest.rsplit_once(':') {
+        Some((path, line)) if line.parse::<u32>().is_ok() => (path, line.parse().ok()?, Some(last)),
+        _ => (rest, last, None),
+    };
+    let looks_like_file = !path.is_empty()
+        && !path.contains(char::is_whitespace)
+        && !path.contains("://")
+        && path.contains(['.', '/', '\\']);
+    looks_like_file.then_some((path, line, column))
+}
+
+/// The severity a message starts with, as `error:` or `warning ...`, and the
+/// rest; messages without one (lint codes like `E302`) count as errors
+fn severity_prefix(message: &str) -> (Severity, String) {
+    let message = message.trim();
+    for (word, rest) in [message.split_once(": "), message.split_once(' ')].into_iter().flatten() {
+        if let Some(severity) = Severity::parse(word) {
+            return (severity, rest.trim().to_string());
+        }
+    }
+    (Severity::Error, message.to_string())
+}
+
+/// `path` relative to `workspace` when it's inside it
+fn relative(path: &str, workspace: &Path) -> String {
+    Path::new(path)
+        .strip_prefix(workspace)
+        .map(|relative| relative.display().to_string())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+    use tempfile::tempdir;
+
+    fn problem(path: &str, line: u32, column: Option<u32>, severity: Severity, message: &str) -> Problem {
+        Problem { path: path.into(), line, column, severity, message: message.into() }
+    }
+
+    #[test]
+    fn test_parse_rustc() {
+        let output = "\
+   Compiling app v0.1.0 (/work/app)
+warning: unused import: `std::fs`
+ --> src/lib.rs:1:5
+  |
+1 | use std::fs;
+  |     ^^^^^^^
+error[E0308]: mismatched types
+  --> /work/app/src/main.rs:4:18
+   |
+error: could not compile `app` (bin \"app\") due to 1 previous error
+";
+        assert_eq!(
+            parse_problems(output, Path::new("/work/app")),
+            vec![
+                problem("src/lib.rs", 1, Some(5), Severity::Warning, "unused import: `std::fs`"),
+                problem("src/main.rs", 4, Some(18), Severity::Error, "mismatched types"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_other_formats() {
+        let output = "\
+src/app.ts(12,5): error TS2322: Type 'string' is not assignable to type 'number'.
+main.c:3:5: error: expected ';' before '}' token
+app/models.py:10:1: E302 expected 2 blank lines, found 1
+lint.sh:7: warning: quote this to prevent word splitting
+Listening on http://localhost:8080: ready
+12:30:45: done
+
+/work/web/src/index.js
+  3:10  error    'x' is defined but never used  no-unused-vars
+  8:1   warning  Unexpected console statement   no-console
+
+✖ 2 problems (1 error, 1 warning)
+";
+        assert_eq!(
+            parse_problems(output, Path::new("/work/web")),
+            vec![
+                problem(
+                    "src/app.ts",
+                    12,
+                    Some(5),
+                    Severity::Error,
+                    "TS2322: Type 'string' is not assignable to type 'number'.",
+                ),
+                problem("main.c", 3, Some(5), Severity::Error, "expected ';' before '}' token"),
+                problem("app/models.py", 10, Some(1), Severity::Error, "E302 expected 2 blank lines, found 1"),
+                problem("lint.sh", 7, None, Severity::Warning, "quote this to prevent word splitting"),
+                problem("src/index.js", 3, Some(10), Severity::Error, "'x' is defined but never used"),
+                problem("src/index.js", 8, Some(1), Severity::Warning, "Unexpected console statement"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_diagnostics_json() {
+        let cargo = r#"{"reason":"compiler-artifact","target":{"name":"app"}}
+{"reason":"compiler-message","message":{"level":"warning","message":"unused variable: `x`","spans":[{"file_name":"src/lib.rs","line_start":3,"column_start":9,"is_primary":true}]}}
+{"reason":"compiler-message","message":{"level":"error","message":"aborting due to 1 previous error","spans":[]}}
+{"reason":"build-finished","success":false}
+"#;
+        assert_eq!(
+            parse_diagnostics_json(cargo, Path::new("/work/app")).unwrap(),
+            vec![problem("src/lib.rs", 3, Some(9), Severity::Warning, "unused variable: `x`")]
+        );
+
+        let lsp = r#"[{"uri":"file:///work/app/src/main.ts","diagnostics":[
+            {"range":{"start":{"line":0,"character":4},"end":{"line":0,"character":5}},"severity":1,"message":"Cannot find name 'y'."},
+            {"range":{"start":{"line":9,"character":0},"end":{"line":9,"character":3}},"severity":3,"message":"Unused label."}
+        ]}]"#;
+        assert_eq!(
+            parse_diagnostics_json(lsp, Path::new("/work/app")).unwrap(),
+            vec![
+                problem("src/main.ts", 1, Some(5), Severity::Error, "Cannot find name 'y'."),
+                problem("src/main.ts", 10, Some(1), Severity::Note, "Unused label."),
+            ]
+        );
+
+        let plain = r#"[{"file":"app.py","line":4,"severity":"warning","message":"unused import"}]"#;
+        assert_eq!(
+            parse_diagnostics_json(plain, Path::new("/work")).unwrap(),
+            vec![problem("app.py", 4, None, Severity::Warning, "unused import")]
+        );
+        assert!(parse_diagnostics_json("", Path::new("/work")).unwrap().is_empty());
+        assert!(parse_diagnostics_json("not json", Path::new("/work")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_diagnostics_file_follows_changes() {
+        let workspace = tempdir().unwrap();
+        let file = DiagnosticsFile::new(workspace.path(), "diagnostics.json");
+        assert!(file.problems().is_empty());
+        assert!(file.error().is_none());
+        let _watcher = file.start_watching().unwrap();
+
+        let plain = r#"[{"path":"main.c","line":3,"message":"expected ;"}]"#;
+        std::fs::write(workspace.path().join("diagnostics.json"), plain).unwrap();
+        for _ in 0..100 {
+            if !file.problems().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert_eq!(file.problems(), vec![problem("main.c", 3, None, Severity::Error, "expected ;")]);
+
+        std::fs::write(workspace.path().join("diagnostics.json"), "{").unwrap();
+        file.reload();
+        assert!(file.error().is_some());
+        assert_eq!(file.problems().len(), 1);
+    }
+
+    #[test]
+    fn test_problems_prompt() {
+        let problems = [
+            problem("src/main.rs", 4, Some(18), Severity::Error, "mismatched types"),
+            problem("lint.sh", 7, None, Severity::Warning, "quote this"),
+        ];
+        assert_eq!(
+            problems_prompt(&problems),
+            "The build and linters report these problems:\n\n\
+             - src/main.rs:4:18: error: mismatched types\n\
+             - lint.sh:7: warning: quote this\n\n\
+             Fix them, then run the commands again to check."
+        );
+    }
+
+    #[test]
+    fn test_config_per_workspace() {
+        let storage = Storage::in_memory().unwrap();
+        let conn = storage.connection().unwrap();
+        let workspace = Path::new("/work/app");
+        assert_eq!(CheckConfig::load(&conn, workspace).unwrap(), None);
+
+        let commands = vec!["cargo clippy".to_string(), "cargo fmt --check".to_string()];
+        let config = CheckConfig { commands, run_after_turn: true, diagnostics_file: None };
+        config.save(&conn, workspace).unwrap();
+        assert_eq!(CheckConfig::load(&conn, workspace).unwrap(), Some(config));
+        assert_eq!(CheckConfig::load(&conn, Path::new("/work/other")).unwrap(), None);
+        CheckConfig::clear(&conn, workspace).unwrap();
+        assert_eq!(CheckConfig::load(&conn, workspace).unwrap(), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_checks() {
+        let workspace = tempdir().unwrap();
+        let commands = vec![
+            "echo 'lint.sh:7: warning: quote this'".to_string(),
+            "echo 'main.c:3:5: error: expected ;' >&2; exit 1".to_string(),
+        ];
+        let run = run_checks(workspace.path(), &commands, Duration::from_secs(10)).await.unwrap();
+        assert!(!run.passed());
+        assert_eq!(run.results.iter().map(|result| result.exit_code).collect::<Vec<_>>(), [0, 1]);
+        assert_eq!((run.count(Severity::Error), run.count(Severity::Warning)), (1, 1));
+        assert_eq!(run.problems[0].path, "main.c");
+    }
+}
@@ -0,0 +1,36 @@
+//! Auto mode: approve every agent request without asking
+//!
+//! While auto mode is on for a session, anything that would normally wait on
+//! the user goes ahead. Hard limits still apply: paths outside the granted
+//! workspace are refused and blocked commands stay blocked. Each approval made
+//! this way is written to the audit log.
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+/// Per-session auto mode toggle, shared between the UI and agent delegates
+#[derive(Default)]
+pub struct AutoMode {
+    enabled: Mutex<HashSet<String>>,
+}
+
+impl AutoMode {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Turn auto mode on or off for a session
+    pub fn set_enabled(&self, session_id: &str, enabled: bool) {
+        let mut sessions = self.enabled.lock().unwrap();
+        if enabled {
+            sessions.insert(session_id.to_string());
+        } else {
+            sessions.remove(session_id);
+        }
+    }
+
+    /// Check if auto mode is on for a session
+    pub fn is_enabled(&self, session_id: &str) -> bool {
+        self.enabled.lock().unwrap().contains(session_id)
+    }
+}
@@ -0,0 +1,241 @@
+//! Batched approval of file changes
+//!
+//! Writes that need confirmation are not prompted one by one. Each is added
+//! to the session's [`PendingChangeSet`] and the writer waits until the user
+//! reviews the whole set and accepts or rejects each file.
+
+use super::diff::{compute_file_diff, diff_stats, DEFAULT_CONTEXT_LINES};
+use crate::types::FileDiff;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+/// A single file change waiting for review
+#[derive(Debug)]
+pub struct PendingChange {
+    pub id: String,
+    pub path: String,
+    pub content: String,
+    pub diff: FileDiff,
+    /// Whether the file does not exist yet
+    pub created: bool,
+    /// Current checkbox state in the review sheet
+    pub accepted: bool,
+    responder: Option<oneshot::Sender<bool>>,
+}
+
+impl PendingChange {
+    /// Lines added and removed by this change
+    pub fn stats(&self) -> (usize, usize) {
+        diff_stats(&self.diff)
+    }
+}
+
+/// All changes a session is waiting on, reviewed together
+#[derive(Debug)]
+pub struct PendingChangeSet {
+    pub session_id: String,
+    changes: Vec<PendingChange>,
+    /// When the first change of this batch arrived
+    opened_at: Instant,
+}
+
+impl PendingChangeSet {
+    pub fn new(session_id: impl Into<String>) -> Self {
+        Self {
+            session_id: session_id.into(),
+            changes: Vec::new(),
+            opened_at: Instant::now(),
+        }
+    }
+
+    /// Add a change; the receiver resolves to the user's decision
+    pub fn push(
+        &mut self,
+        path: impl Into<String>,
+        content: impl Into<String>,
+        diff: FileDiff,
+        created: bool,
+    ) -> (String, oneshot::Receiver<bool>) {
+        let (tx, rx) = oneshot::channel();
+        let id = uuid::Uuid::new_v4().to_string();
+        if self.changes.is_empty() {
+            self.opened_at = Instant::now();
+        }
+        self.changes.push(PendingChange {
+            id: id.clone(),
+            path: path.into(),
+            content: content.into(),
+            diff,
+            created,
+            accepted: true,
+            responder: Some(tx),
+        });
+        (id, rx)
+    }
+
+    pub fn changes(&self) -> &[PendingChange] {
+        &self.changes
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// How long the batch has been collecting
+    pub fn age(&self) -> Duration {
+        self.opened_at.elapsed()
+    }
+
+    /// Heading for the review sheet
+    pub fn title(&self) -> String {
+        match self.changes.len() {
+            1 => "Agent wants to modify 1 file".to_string(),
+            n => format!("Agent wants to modify {} files", n),
+        }
+    }
+
+    pub fn set_accepted(&mut self, id: &str, accepted: bool) {
+        if let Some(change) = self.changes.iter_mut().find(|c| c.id == id) {
+            change.accepted = accepted;
+        }
+    }
+
+    pub fn set_all_accepted(&mut self, accepted: bool) {
+        for change in &mut self.changes {
+            change.accepted = accepted;
+        }
+    }
+
+    pub fn accepted_count(&self) -> usize {
+        self.changes.iter().filter(|c| c.accepted).count()
+    }
+
+    /// Send every decision to the waiting writers and empty the set
+    pub fn resolve(&mut self) {
+        for mut change in self.changes.drain(..) {
+            if let Some(tx) = change.responder.take() {
+                let _ = tx.send(change.accepted);
+            }
+        }
+    }
+}
+
+/// Per-session change sets shared between agent delegates and the UI
+#[derive(Default)]
+pub struct ChangeSetCollector {
+    sets: Mutex<HashMap<String, PendingChangeSet>>,
+}
+
+impl ChangeSetCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a write for review and wait for the user's decision
+    ///
+    /// Resolves to false if the review is dropped without an answer.
+    pub async fn submit_write(&self, session_id: &str, path: &str, content: &str) -> bool {
+        let existing = tokio::fs::read_to_string(path).await.ok();
+        let diff = compute_file_diff(
+            path,
+            existing.as_deref().unwrap_or(""),
+            content,
+            DEFAULT_CONTEXT_LINES,
+        );
+
+        let rx = {
+            let mut sets = self.sets.lock().unwrap();
+            let set = sets
+                .entry(session_id.to_string())
+                .or_insert_with(|| PendingChangeSet::new(session_id));
+            set.push(path, content, diff, existing.is_none()).1
+        };
+
+        rx.await.unwrap_or(false)
+    }
+
+    /// The first session whose batch has been collecting for at least `settle`
+    ///
+    /// The delay lets a burst of writes land in one sheet instead of the
+    /// review opening on the first file.
+    pub fn ready_session(&self, settle: Duration) -> Option<String> {
+        self.sets
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|set| !set.is_empty() && set.age() >= settle)
+            .max_by_key(|set| set.age())
+            .map(|set| set.session_id.clone())
+    }
+
+    /// Run a closure against a session's change set, if it has one
+    pub fn with_change_set<R>(
+        &self,
+        session_id: &str,
+        f: impl FnOnce(&mut PendingChangeSet) -> R,
+    ) -> Option<R> {
+        self.sets.lock().unwrap().get_mut(session_id).map(f)
+    }
+
+    /// Deliver the decisions for a session and drop its change set
+    pub fn resolve(&self, session_id: &str) {
+        if let Some(mut set) = self.sets.lock().unwrap().remove(session_id) {
+            set.resolve();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_change_set_decisions() {
+        let mut set = PendingChangeSet::new("s1");
+        let (a, mut rx_a) = set.push("a.txt", "a", FileDiff { path: "a.txt".into(), hunks: vec![] }, true);
+        let (_b, mut rx_b) = set.push("b.txt", "b", FileDiff { path: "b.txt".into(), hunks: vec![] }, false);
+
+        assert_eq!(set.title(), "Agent wants to modify 2 files");
+        set.set_accepted(&a, false);
+        assert_eq!(set.accepted_count(), 1);
+
+        set.resolve();
+        assert!(set.is_empty());
+        assert_eq!(rx_a.try_recv(), Ok(false));
+        assert_eq!(rx_b.try_recv(), Ok(true));
+    }
+
+    #[tokio::test]
+    async fn test_collector_batches_writes() {
+        let collector = Arc::new(ChangeSetCollector::new());
+
+        let writers: Vec<_> = ["x.txt", "y.txt"]
+            .into_iter()
+            .map(|path| {
+                let collector = Arc::clone(&collector);
+                tokio::spawn(async move { collector.submit_write("s1", path, "new").await })
+            })
+            .collect();
+
+        // Wait until both writes are queued
+        while collector.with_change_set("s1", |s| s.len()).unwrap_or(0) < 2 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(collector.ready_session(Duration::ZERO).as_deref(), Some("s1"));
+
+        collector.with_change_set("s1", |s| s.set_all_accepted(false));
+        collector.resolve("s1");
+
+        for writer in writers {
+            assert!(!writer.await.unwrap());
+        }
+        assert!(collector.ready_session(Duration::ZERO).is_none());
+    }
+}
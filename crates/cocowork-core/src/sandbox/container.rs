@@ -0,0 +1,235 @@
+//! Terminal commands run inside a container
+//!
+//! When the terminal policy's backend is [`TerminalBackend::Container`],
+//! agent commands go through `docker run` or `podman run` instead of running
+//! on the host. The workspace is mounted at its own path, so the paths the
+//! agent knows stay valid inside the container, and the network is off
+//! unless the workspace's [`ContainerConfig`] turns it on.
+//!
+//! [`TerminalBackend::Container`]: crate::types::TerminalBackend::Container
+
+use crate::error::{Error, Result};
+use crate::storage::{delete_setting, get_setting, set_setting};
+use crate::types::TerminalLimits;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+const CONTAINER_KEY: &str = "container.workspace";
+
+/// Image used when a workspace doesn't name one
+pub const DEFAULT_IMAGE: &str = "debian:stable-slim";
+
+/// Program that starts the containers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerRuntime {
+    #[default]
+    Docker,
+    Podman,
+}
+
+impl ContainerRuntime {
+    pub fn program(&self) -> &'static str {
+        match self {
+            ContainerRuntime::Docker => "docker",
+            ContainerRuntime::Podman => "podman",
+        }
+    }
+}
+
+/// A host directory made visible inside the container
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContainerMount {
+    pub host: PathBuf,
+    pub container: PathBuf,
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+impl ContainerMount {
+    /// Parse `host:container`, with `:ro` after it for a read-only mount
+    pub fn parse(spec: &str) -> Result<Self> {
+        let spec = spec.trim();
+        let (spec, read_only) = match spec.strip_suffix(":ro") {
+            Some(rest) => (rest, true),
+            None => (spec, false),
+        };
+        let invalid = || Error::InvalidConfig(format!("Mounts are written host:container[:ro], not {:?}", spec));
+        let (host, container) = spec.split_once(':').ok_or_else(invalid)?;
+        let (host, container) = (PathBuf::from(host), PathBuf::from(container));
+        if !host.is_absolute() || !container.is_absolute() {
+            return Err(invalid());
+        }
+        Ok(Self { host, container, read_only })
+    }
+}
+
+impl fmt::Display for ContainerMount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.host.display(), self.container.display())?;
+        if self.read_only {
+            write!(f, ":ro")?;
+        }
+        Ok(())
+    }
+}
+
+/// How a workspace's commands are run in a container
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContainerConfig {
+    pub runtime: ContainerRuntime,
+    pub image: String,
+    /// Directories mounted besides the workspace
+    pub mounts: Vec<ContainerMount>,
+    /// Give the container network access
+    pub network: bool,
+}
+
+impl Default for ContainerConfig {
+    fn default() -> Self {
+        Self {
+            runtime: ContainerRuntime::default(),
+            image: DEFAULT_IMAGE.to_string(),
+            mounts: Vec::new(),
+            network: false,
+        }
+    }
+}
+
+impl ContainerConfig {
+    fn setting_key(workspace: &Path) -> String {
+        format!("{}.{}", CONTAINER_KEY, workspace.display())
+    }
+
+    /// The settings stored for `workspace`, if any
+    pub fn load(conn: &rusqlite::Connection, workspace: &Path) -> Result<Option<Self>> {
+        Ok(get_setting(conn, &Self::setting_key(workspace))?.and_then(|raw| serde_json::from_str(&raw).ok()))
+    }
+
+    /// The settings stored for `workspace`, or the defaults
+    pub fn resolve(conn: &rusqlite::Connection, workspace: &Path) -> Result<Self> {
+        Ok(Self::load(conn, workspace)?.unwrap_or_default())
+    }
+
+    pub fn save(&self, conn: &rusqlite::Connection, workspace: &Path) -> Result<()> {
+        set_setting(conn, &Self::setting_key(workspace), &serde_json::to_string(self)?)
+    }
+
+    pub fn clear(conn: &rusqlite::Connection, workspace: &Path) -> Result<()> {
+        delete_setting(conn, &Self::setting_key(workspace))
+    }
+
+    /// Options for `run` naming the container and applying `limits` inside it
+    ///
+    /// The wall-clock and output limits are enforced outside, by killing
+    /// the container by name.
+    pub fn limit_options(name: &str, limits: &TerminalLimits) -> Vec<String> {
+        let mut options = vec!["--name".to_string(), name.to_string()];
+        if let Some(secs) = limits.cpu_time_secs {
+            options.extend(["--ulimit".to_string(), format!("cpu={}:{}", secs, secs)]);
+        }
+        options
+    }
+
+    /// Program and arguments that run `command` in a throwaway container
+    ///
+    /// The workspace is mounted read-write at its own path, and `cwd` (or
+    /// the workspace) is the working directory inside the container.
+    pub fn command_line(
+        &self,
+        workspace: &Path,
+        command: &str,
+        args: &[String],
+        cwd: Option<&str>,
+        env: Option<&HashMap<String, String>>,
+    ) -> (String, Vec<String>) {
+        let mut run: Vec<String> = vec!["run".into(), "--rm".into(), "-i".into()];
+        if !self.network {
+            run.extend(["--network".into(), "none".into()]);
+        }
+        let workspace = workspace.display().to_string();
+        run.extend(["-v".into(), format!("{}:{}", workspace, workspace)]);
+        for mount in &self.mounts {
+            run.extend(["-v".into(), mount.to_string()]);
+        }
+        run.extend(["-w".into(), cwd.map(str::to_string).unwrap_or(workspace)]);
+
+        let mut env: Vec<_> = env.into_iter().flatten().collect();
+        env.sort();
+        for (name, value) in env {
+            run.extend(["-e".into(), format!("{}={}", name, value)]);
+        }
+
+        run.push(self.image.clone());
+        run.push(command.to_string());
+        run.extend(args.iter().cloned());
+        (self.runtime.program().to_string(), run)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+
+    #[test]
+    fn test_parse_mount() {
+        let mount = ContainerMount::parse("/home/me/.cargo:/root/.cargo:ro").unwrap();
+        assert_eq!(mount.host, PathBuf::from("/home/me/.cargo"));
+        assert!(mount.read_only);
+        assert_eq!(mount.to_string(), "/home/me/.cargo:/root/.cargo:ro");
+
+        assert!(!ContainerMount::parse("/data:/data").unwrap().read_only);
+        assert!(ContainerMount::parse("/data").is_err());
+        assert!(ContainerMount::parse("data:/data").is_err());
+    }
+
+    #[test]
+    fn test_command_line() {
+        let mut config = ContainerConfig {
+            mounts: vec![ContainerMount::parse("/cache:/cache:ro").unwrap()],
+            ..ContainerConfig::default()
+        };
+        let env = HashMap::from([("CI".to_string(), "1".to_string())]);
+        let (program, args) =
+            config.command_line(Path::new("/work/app"), "cargo", &["test".to_string()], None, Some(&env));
+        assert_eq!(program, "docker");
+        assert_eq!(
+            args.join(" "),
+            "run --rm -i --network none -v /work/app:/work/app -v /cache:/cache:ro -w /work/app \
+             -e CI=1 debian:stable-slim cargo test"
+        );
+
+        config.runtime = ContainerRuntime::Podman;
+        config.network = true;
+        let (program, args) = config.command_line(Path::new("/work/app"), "ls", &[], Some("/work/app/src"), None);
+        assert_eq!(program, "podman");
+        assert!(!args.contains(&"none".to_string()));
+        assert_eq!(args[args.len() - 3..], ["/work/app/src", DEFAULT_IMAGE, "ls"]);
+
+        let limits = TerminalLimits { cpu_time_secs: Some(60), ..TerminalLimits::unlimited() };
+        assert_eq!(
+            ContainerConfig::limit_options("cocowork-1", &limits).join(" "),
+            "--name cocowork-1 --ulimit cpu=60:60"
+        );
+    }
+
+    #[test]
+    fn test_stored_per_workspace() {
+        let storage = Storage::in_memory().unwrap();
+        let conn = storage.connection().unwrap();
+        let workspace = Path::new("/work/app");
+        assert_eq!(ContainerConfig::resolve(&conn, workspace).unwrap(), ContainerConfig::default());
+
+        let config = ContainerConfig { image: "rust:1".to_string(), ..ContainerConfig::default() };
+        config.save(&conn, workspace).unwrap();
+        assert_eq!(ContainerConfig::load(&conn, workspace).unwrap(), Some(config));
+        assert_eq!(ContainerConfig::load(&conn, Path::new("/work/other")).unwrap(), None);
+
+        ContainerConfig::clear(&conn, workspace).unwrap();
+        assert_eq!(ContainerConfig::load(&conn, workspace).unwrap(), None);
+    }
+}
@@ -0,0 +1,223 @@
+//! Line-based diffs for previewing file changes
+
+use crate::types::{DiffHunk, DiffLine, DiffLineKind, FileDiff};
+
+/// Number of unchanged lines kept around each change
+pub const DEFAULT_CONTEXT_LINES: usize = 3;
+
+/// Above this many cells the LCS table is skipped and the whole file is
+/// shown as replaced, to keep previews cheap for very large files
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+/// Compute a unified-style diff between two versions of a file
+pub fn compute_file_diff(path: &str, old: &str, new: &str, context: usize) -> FileDiff {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let ops = diff_lines(&old_lines, &new_lines);
+
+    FileDiff {
+        path: path.to_string(),
+        hunks: build_hunks(&ops, context),
+    }
+}
+
+/// Count added and removed lines in a diff
+pub fn diff_stats(diff: &FileDiff) -> (usize, usize) {
+    diff.hunks
+        .iter()
+        .flat_map(|h| h.lines.iter())
+        .fold((0, 0), |(add, del), line| match line.kind {
+            DiffLineKind::Add => (add + 1, del),
+            DiffLineKind::Remove => (add, del + 1),
+            DiffLineKind::Context => (add, del),
+        })
+}
+
+/// Render a diff in unified format
+pub fn format_unified_diff(diff: &FileDiff) -> String {
+    let mut out = format!("--- a/{}\n+++ b/{}\n", diff.path, diff.path);
+    for hunk in &diff.hunks {
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            hunk.old_start, hunk.old_lines, hunk.new_start, hunk.new_lines
+        ));
+        for line in &hunk.lines {
+            let prefix = match line.kind {
+                DiffLineKind::Context => ' ',
+                DiffLineKind::Add => '+',
+                DiffLineKind::Remove => '-',
+            };
+            out.push(prefix);
+            out.push_str(&line.content);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Op<'a> {
+    kind: DiffLineKind,
+    text: &'a str,
+}
+
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Op<'a>> {
+    // Trim the common prefix and suffix before running LCS
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let context = |text: &'a str| Op {
+        kind: DiffLineKind::Context,
+        text,
+    };
+
+    let mut ops: Vec<Op<'a>> = old[..prefix].iter().map(|t| context(t)).collect();
+
+    if old_mid.len() * new_mid.len() > MAX_LCS_CELLS {
+        ops.extend(old_mid.iter().map(|t| Op {
+            kind: DiffLineKind::Remove,
+            text: t,
+        }));
+        ops.extend(new_mid.iter().map(|t| Op {
+            kind: DiffLineKind::Add,
+            text: t,
+        }));
+    } else {
+        let (n, m) = (old_mid.len(), new_mid.len());
+        let mut table = vec![vec![0u32; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                table[i][j] = if old_mid[i] == new_mid[j] {
+                    table[i + 1][j + 1] + 1
+                } else {
+                    table[i + 1][j].max(table[i][j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && old_mid[i] == new_mid[j] {
+                ops.push(context(old_mid[i]));
+                i += 1;
+                j += 1;
+            } else if j < m && (i == n || table[i][j + 1] >= table[i + 1][j]) {
+                ops.push(Op {
+                    kind: DiffLineKind::Add,
+                    text: new_mid[j],
+                });
+                j += 1;
+            } else {
+                ops.push(Op {
+                    kind: DiffLineKind::Remove,
+                    text: old_mid[i],
+                });
+                i += 1;
+            }
+        }
+    }
+
+    ops.extend(old[old.len() - suffix..].iter().map(|t| context(t)));
+    ops
+}
+
+fn build_hunks(ops: &[Op<'_>], context: usize) -> Vec<DiffHunk> {
+    let changed: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, op)| op.kind != DiffLineKind::Context)
+        .map(|(i, _)| i)
+        .collect();
+    if changed.is_empty() {
+        return Vec::new();
+    }
+
+    // Group changes whose context windows overlap
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &idx in &changed {
+        let start = idx.saturating_sub(context);
+        let end = (idx + context + 1).min(ops.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    // Line numbers (1-based) before each op
+    let mut old_no = Vec::with_capacity(ops.len());
+    let mut new_no = Vec::with_capacity(ops.len());
+    let (mut o, mut n) = (1u32, 1u32);
+    for op in ops {
+        old_no.push(o);
+        new_no.push(n);
+        match op.kind {
+            DiffLineKind::Context => {
+                o += 1;
+                n += 1;
+            }
+            DiffLineKind::Remove => o += 1,
+            DiffLineKind::Add => n += 1,
+        }
+    }
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            let slice = &ops[start..end];
+            let old_lines = slice.iter().filter(|op| op.kind != DiffLineKind::Add).count() as u32;
+            let new_lines = slice
+                .iter()
+                .filter(|op| op.kind != DiffLineKind::Remove)
+                .count() as u32;
+            DiffHunk {
+                old_start: old_no[start],
+                old_lines,
+                new_start: new_no[start],
+                new_lines,
+                lines: slice
+                    .iter()
+                    .map(|op| DiffLine {
+                        kind: op.kind,
+                        content: op.text.to_string(),
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_single_change() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\n";
+        let new = "a\nb\nc\nD\ne\nf\ng\nh\n";
+        let diff = compute_file_diff("f.txt", old, new, 1);
+
+        assert_eq!(diff.hunks.len(), 1);
+        let hunk = &diff.hunks[0];
+        assert_eq!((hunk.old_start, hunk.old_lines), (3, 3));
+        assert_eq!((hunk.new_start, hunk.new_lines), (3, 3));
+        assert_eq!(diff_stats(&diff), (1, 1));
+    }
+
+    #[test]
+    fn test_diff_new_file_and_identical() {
+        let diff = compute_file_diff("new.txt", "", "one\ntwo\n", DEFAULT_CONTEXT_LINES);
+        assert_eq!(diff_stats(&diff), (2, 0));
+        assert!(format_unified_diff(&diff).contains("+one\n+two\n"));
+
+        let same = compute_file_diff("same.txt", "x\n", "x\n", DEFAULT_CONTEXT_LINES);
+        assert!(same.hunks.is_empty());
+    }
+}
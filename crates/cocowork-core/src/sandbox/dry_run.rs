@@ -0,0 +1,253 @@
+//! Dry-run mode: record agent side effects instead of applying them
+//!
+//! When dry run is enabled for a session, file writes, deletes, moves and
+//! terminal executions are captured as [`ProposedAction`]s with a preview.
+//! The user can review them afterwards and apply any subset.
+
+use super::diff::{compute_file_diff, DEFAULT_CONTEXT_LINES};
+use super::{FileSystemHandler, PermissionManager, TerminalHandler};
+use crate::error::Result;
+use crate::types::{FileDiff, TerminalExecuteResult};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+/// An action the agent attempted while dry run was enabled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProposedActionKind {
+    WriteFile {
+        path: String,
+        content: String,
+        /// Diff against the file on disk at the time of the request
+        diff: FileDiff,
+        /// Whether the file did not exist yet
+        created: bool,
+    },
+    DeleteFile {
+        path: String,
+    },
+    MoveFile {
+        old_path: String,
+        new_path: String,
+    },
+    CreateDirectory {
+        path: String,
+    },
+    Execute {
+        command: String,
+        args: Vec<String>,
+        cwd: Option<String>,
+        env: Option<HashMap<String, String>>,
+    },
+}
+
+/// A recorded action with its identity and timestamp
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProposedAction {
+    pub id: String,
+    pub session_id: String,
+    pub created_at: DateTime<Utc>,
+    pub action: ProposedActionKind,
+}
+
+impl ProposedAction {
+    /// One-line human readable summary
+    pub fn summary(&self) -> String {
+        match &self.action {
+            ProposedActionKind::WriteFile { path, created, .. } => {
+                if *created {
+                    format!("Create {}", path)
+                } else {
+                    format!("Modify {}", path)
+                }
+            }
+            ProposedActionKind::DeleteFile { path } => format!("Delete {}", path),
+            ProposedActionKind::MoveFile { old_path, new_path } => {
+                format!("Move {} -> {}", old_path, new_path)
+            }
+            ProposedActionKind::CreateDirectory { path } => format!("Create directory {}", path),
+            ProposedActionKind::Execute { command, args, .. } => {
+                format!("Run {}", super::format_command_line(command, args))
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct DryRunState {
+    enabled: HashSet<String>,
+    actions: Vec<ProposedAction>,
+}
+
+/// Per-session dry-run toggle and the actions recorded while it was on
+///
+/// Shared between the agent delegate (which records) and the UI (which
+/// reviews and applies), so it uses interior mutability.
+#[derive(Default)]
+pub struct DryRunRecorder {
+    state: Mutex<DryRunState>,
+}
+
+impl DryRunRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Turn dry run on or off for a session
+    pub fn set_enabled(&self, session_id: &str, enabled: bool) {
+        let mut state = self.state.lock().unwrap();
+        if enabled {
+            state.enabled.insert(session_id.to_string());
+        } else {
+            state.enabled.remove(session_id);
+        }
+    }
+
+    /// Check if dry run is on for a session
+    pub fn is_enabled(&self, session_id: &str) -> bool {
+        self.state.lock().unwrap().enabled.contains(session_id)
+    }
+
+    /// Record an action for a session and return its id
+    pub fn record(&self, session_id: &str, action: ProposedActionKind) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+        self.state.lock().unwrap().actions.push(ProposedAction {
+            id: id.clone(),
+            session_id: session_id.to_string(),
+            created_at: Utc::now(),
+            action,
+        });
+        id
+    }
+
+    /// Record a file write, computing the diff against the current contents
+    pub async fn record_write(&self, session_id: &str, path: &str, content: &str) -> String {
+        let existing = tokio::fs::read_to_string(path).await.ok();
+        let diff = compute_file_diff(
+            path,
+            existing.as_deref().unwrap_or(""),
+            content,
+            DEFAULT_CONTEXT_LINES,
+        );
+        self.record(
+            session_id,
+            ProposedActionKind::WriteFile {
+                path: path.to_string(),
+                content: content.to_string(),
+                diff,
+                created: existing.is_none(),
+            },
+        )
+    }
+
+    /// Actions recorded for a session, oldest first
+    pub fn actions(&self, session_id: &str) -> Vec<ProposedAction> {
+        self.state
+            .lock()
+            .unwrap()
+            .actions
+            .iter()
+            .filter(|a| a.session_id == session_id)
+            .cloned()
+            .collect()
+    }
+
+    /// Remove and return the given actions, preserving recording order
+    pub fn take(&self, ids: &[String]) -> Vec<ProposedAction> {
+        let mut state = self.state.lock().unwrap();
+        let (taken, kept) = std::mem::take(&mut state.actions)
+            .into_iter()
+            .partition(|a| ids.contains(&a.id));
+        state.actions = kept;
+        taken
+    }
+
+    /// Drop all recorded actions for a session
+    pub fn discard_session(&self, session_id: &str) {
+        self.state
+            .lock()
+            .unwrap()
+            .actions
+            .retain(|a| a.session_id != session_id);
+    }
+}
+
+/// Apply a previously recorded action for real
+///
+/// Permission checks still apply; terminal commands were already approved
+/// by the user when they chose to apply them.
+pub async fn apply_action(
+    pm: &PermissionManager,
+    action: &ProposedAction,
+) -> Result<Option<TerminalExecuteResult>> {
+    match &action.action {
+        ProposedActionKind::WriteFile { path, content, .. } => {
+            FileSystemHandler::write_file(pm, path, content).await?;
+        }
+        ProposedActionKind::DeleteFile { path } => {
+            FileSystemHandler::delete_file(pm, path).await?;
+        }
+        ProposedActionKind::MoveFile { old_path, new_path } => {
+            FileSystemHandler::move_file(pm, old_path, new_path).await?;
+        }
+        ProposedActionKind::CreateDirectory { path } => {
+            FileSystemHandler::create_directory(pm, path).await?;
+        }
+        ProposedActionKind::Execute {
+            command,
+            args,
+            cwd,
+            env,
+        } => {
+            if let Some(dir) = cwd {
+                pm.validate_access(dir)?;
+            }
+            let result = TerminalHandler::run(command, args, cwd.as_deref(), env.as_ref()).await?;
+            return Ok(Some(result));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sandbox::SecurityLevel;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_record_and_apply_write() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        let path_str = path.to_str().unwrap();
+        std::fs::write(&path, "one\n").unwrap();
+
+        let recorder = DryRunRecorder::new();
+        recorder.set_enabled("s1", true);
+        assert!(recorder.is_enabled("s1"));
+        assert!(!recorder.is_enabled("s2"));
+
+        let id = recorder.record_write("s1", path_str, "one\ntwo\n").await;
+        recorder.record("s1", ProposedActionKind::DeleteFile { path: path_str.to_string() });
+
+        let actions = recorder.actions("s1");
+        assert_eq!(actions.len(), 2);
+        assert!(actions[0].summary().starts_with("Modify"));
+        // Nothing touched the disk yet
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "one\n");
+
+        let mut pm = PermissionManager::new();
+        pm.grant_access(dir.path(), SecurityLevel::Trust).unwrap();
+
+        let taken = recorder.take(&[id]);
+        assert_eq!(taken.len(), 1);
+        apply_action(&pm, &taken[0]).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "one\ntwo\n");
+
+        recorder.discard_session("s1");
+        assert!(recorder.actions("s1").is_empty());
+    }
+}
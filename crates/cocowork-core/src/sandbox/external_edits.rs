@@ -0,0 +1,293 @@
+//! Noticing files changed outside the agent
+//!
+//! Whenever the agent reads or writes a file through the client, the
+//! tracker stamps it with its size and modification time, keeping the text
+//! too when it's small. A [`FileWatcher`] on the workspace then reports
+//! changes; one to a stamped file while the session's agent isn't working
+//! was made by someone else, e.g. the user's editor, so the agent's picture
+//! of it is out of date. [`ExternalEditTracker::summary`] describes those
+//! changes for the next prompt.
+//!
+//! Whatever changes while a turn runs counts as the agent's, since agents
+//! may edit files with their own tools; files are stamped again when the
+//! turn ends.
+
+use super::diff::{compute_file_diff, diff_stats, format_unified_diff, DEFAULT_CONTEXT_LINES};
+use super::FileWatcher;
+use crate::error::Result;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tokio::sync::mpsc;
+use tracing::debug;
+
+/// Files stamped per session; the least recently used go first
+pub const MAX_TRACKED_FILES: usize = 200;
+
+/// Largest file whose text is kept, so the summary can show a diff
+pub const MAX_KEPT_CONTENT: usize = 64 * 1024;
+
+/// Longest diff shown for one file in the summary
+const MAX_SUMMARY_DIFF: usize = 4000;
+
+/// What happened to a file the agent had seen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternalEditKind {
+    Modified,
+    Deleted,
+}
+
+/// A file changed outside the agent since it last read or wrote it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalEdit {
+    pub path: PathBuf,
+    pub kind: ExternalEditKind,
+    pub detected_at: DateTime<Utc>,
+}
+
+/// Size and modification time of a file, `None` once it's gone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stamp(Option<(u64, Option<SystemTime>)>);
+
+impl Stamp {
+    fn of(path: &Path) -> Self {
+        Self(std::fs::metadata(path).ok().map(|meta| (meta.len(), meta.modified().ok())))
+    }
+}
+
+struct TrackedFile {
+    stamp: Stamp,
+    /// The text as the agent last saw it, if small enough to keep
+    content: Option<String>,
+    /// Order of last use, for dropping the oldest
+    used: u64,
+}
+
+#[derive(Default)]
+struct SessionFiles {
+    files: HashMap<PathBuf, TrackedFile>,
+    changed: Vec<ExternalEdit>,
+    turn_running: bool,
+}
+
+/// Files each session's agent has seen and which of them changed since, shared
+/// between the UI and agent delegates
+#[derive(Default)]
+pub struct ExternalEditTracker {
+    sessions: Mutex<HashMap<String, SessionFiles>>,
+    uses: std::sync::atomic::AtomicU64,
+}
+
+impl ExternalEditTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Note that a session's agent read or wrote `path`, which now holds `content`
+    pub fn record_access(&self, session_id: &str, path: impl AsRef<Path>, content: Option<&str>) {
+        let path = normalize(path.as_ref());
+        let used = self.uses.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let mut sessions = self.sessions.lock().unwrap();
+        let session = sessions.entry(session_id.to_string()).or_default();
+        session.changed.retain(|edit| edit.path != path);
+        session.files.insert(
+            path.clone(),
+            TrackedFile {
+                stamp: Stamp::of(&path),
+                content: content.filter(|text| text.len() <= MAX_KEPT_CONTENT).map(str::to_string),
+                used,
+            },
+        );
+        if session.files.len() > MAX_TRACKED_FILES {
+            if let Some(oldest) = session.files.iter().min_by_key(|(_, file)| file.used).map(|(p, _)| p.clone()) {
+                session.files.remove(&oldest);
+            }
+        }
+    }
+
+    /// Mark a session's turn as running or finished
+    ///
+    /// Changes while it runs are taken to be the agent's; when it finishes,
+    /// every file is stamped afresh.
+    pub fn set_turn_running(&self, session_id: &str, running: bool) {
+        let mut sessions = self.sessions.lock().unwrap();
+        let Some(session) = sessions.get_mut(session_id) else {
+            return;
+        };
+        session.turn_running = running;
+        if !running {
+            for (path, file) in session.files.iter_mut() {
+                let stamp = Stamp::of(path);
+                if stamp != file.stamp {
+                    file.stamp = stamp;
+                    file.content = None;
+                }
+            }
+        }
+    }
+
+    /// Check a path the watcher reported against every session that has seen it
+    pub fn note_change(&self, path: &Path) {
+        let path = normalize(path);
+        let stamp = Stamp::of(&path);
+        let mut sessions = self.sessions.lock().unwrap();
+        for (session_id, session) in sessions.iter_mut().filter(|(_, session)| !session.turn_running) {
+            let Some(file) = session.files.get(&path) else {
+                continue;
+            };
+            if file.stamp == stamp || session.changed.iter().any(|edit| edit.path == path) {
+                continue;
+            }
+            debug!("{:?} changed outside the agent of session {}", path, session_id);
+            session.changed.push(ExternalEdit {
+                path: path.clone(),
+                kind: if stamp.0.is_some() { ExternalEditKind::Modified } else { ExternalEditKind::Deleted },
+                detected_at: Utc::now(),
+            });
+        }
+    }
+
+    /// Files changed outside a session's agent, in the order they were noticed
+    pub fn changes(&self, session_id: &str) -> Vec<ExternalEdit> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .get(session_id)
+            .map(|session| session.changed.clone())
+            .unwrap_or_default()
+    }
+
+    /// Forget the changes noticed so far, stamping the files as they are now
+    pub fn dismiss(&self, session_id: &str) {
+        let mut sessions = self.sessions.lock().unwrap();
+        let Some(session) = sessions.get_mut(session_id) else {
+            return;
+        };
+        for edit in std::mem::take(&mut session.changed) {
+            match edit.kind {
+                ExternalEditKind::Deleted => {
+                    session.files.remove(&edit.path);
+                }
+                ExternalEditKind::Modified => {
+                    if let Some(file) = session.files.get_mut(&edit.path) {
+                        file.stamp = Stamp::of(&edit.path);
+                        file.content = None;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Drop everything about a session
+    pub fn forget(&self, session_id: &str) {
+        self.sessions.lock().unwrap().remove(session_id);
+    }
+
+    /// Text telling the agent which files changed since it saw them, with
+    /// diffs where the old text was kept; `None` if nothing changed
+    pub fn summary(&self, session_id: &str) -> Option<String> {
+        let sessions = self.sessions.lock().unwrap();
+        let session = sessions.get(session_id).filter(|session| !session.changed.is_empty())?;
+        let mut summary = String::from(
+            "These files were changed outside of you since you last read or wrote them; \
+             read them again before relying on what you saw:\n",
+        );
+        for edit in &session.changed {
+            let path = edit.path.display().to_string();
+            if edit.kind == ExternalEditKind::Deleted {
+                summary.push_str(&format!("\n- {} (deleted)\n", path));
+                continue;
+            }
+            let old = session.files.get(&edit.path).and_then(|file| file.content.as_deref());
+            let new = std::fs::read_to_string(&edit.path).ok();
+            let (Some(old), Some(new)) = (old, new) else {
+                summary.push_str(&format!("\n- {} (modified)\n", path));
+                continue;
+            };
+            let diff = compute_file_diff(&path, old, &new, DEFAULT_CONTEXT_LINES);
+            let (added, removed) = diff_stats(&diff);
+            summary.push_str(&format!("\n- {} (modified, +{} -{})\n", path, added, removed));
+            let mut unified = format_unified_diff(&diff);
+            if unified.len() > MAX_SUMMARY_DIFF {
+                let cut = (0..=MAX_SUMMARY_DIFF).rev().find(|&i| unified.is_char_boundary(i)).unwrap_or(0);
+                unified.truncate(cut);
+                unified.push_str("\n[diff truncated]");
+            }
+            summary.push_str(&format!("```diff\n{}\n```\n", unified.trim_end()));
+        }
+        Some(summary)
+    }
+
+    /// Watch `root` for changes to the files sessions have seen
+    ///
+    /// Must be called inside a Tokio runtime. Watching stops when the returned
+    /// watcher is dropped.
+    pub fn start_watching(self: &Arc<Self>, root: impl AsRef<Path>) -> Result<FileWatcher> {
+        let (tx, mut rx) = mpsc::channel(256);
+        let mut watcher = FileWatcher::new();
+        watcher.set_event_channel(tx);
+        watcher.watch(root)?;
+
+        let tracker = Arc::clone(self);
+        tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                tracker.note_change(&event.path);
+            }
+            debug!("External edit watcher stopped");
+        });
+        Ok(watcher)
+    }
+}
+
+/// Resolve links so a path matches what the watcher reports for it
+fn normalize(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_external_edit_detection() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("main.rs");
+        std::fs::write(&path, "fn main() {}\n").unwrap();
+        let tracker = ExternalEditTracker::new();
+        tracker.record_access("s1", &path, Some("fn main() {}\n"));
+
+        // Nothing changed yet
+        tracker.note_change(&path);
+        assert!(tracker.changes("s1").is_empty());
+        assert!(tracker.summary("s1").is_none());
+
+        // Changes during a turn are the agent's
+        tracker.set_turn_running("s1", true);
+        std::fs::write(&path, "fn main() { run(); }\n").unwrap();
+        tracker.note_change(&path);
+        tracker.set_turn_running("s1", false);
+        assert!(tracker.changes("s1").is_empty());
+
+        // The user's editor writes it afterwards
+        tracker.record_access("s1", &path, Some("fn main() { run(); }\n"));
+        std::fs::write(&path, "fn main() {\n    run();\n    log();\n}\n").unwrap();
+        tracker.note_change(&path);
+        let changes = tracker.changes("s1");
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].kind, ExternalEditKind::Modified);
+        let summary = tracker.summary("s1").unwrap();
+        assert!(summary.contains("main.rs (modified, +4 -1)"));
+        assert!(summary.contains("+    log();"));
+
+        // Other sessions never saw the file
+        assert!(tracker.changes("s2").is_empty());
+
+        tracker.dismiss("s1");
+        assert!(tracker.changes("s1").is_empty());
+        std::fs::remove_file(&path).unwrap();
+        tracker.note_change(&path);
+        assert_eq!(tracker.changes("s1")[0].kind, ExternalEditKind::Deleted);
+    }
+}
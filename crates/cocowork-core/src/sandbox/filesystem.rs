@@ -10,6 +10,9 @@ use tokio::io::AsyncReadExt;
 use tracing::{debug, info};
 use walkdir::WalkDir;
 
+/// Largest file `read_preview` loads by default
+pub const MAX_PREVIEW_SIZE: u64 = 2 * 1024 * 1024;
+
 /// File system handler with permission checking
 pub struct FileSystemHandler;
 
@@ -377,6 +380,53 @@ impl FileSystemHandler {
         })
     }
 
+    /// Read a text file for read-only display, with permission check
+    ///
+    /// Fails on files larger than `max_size` bytes and on binary or non-UTF-8
+    /// files rather than loading them.
+    pub async fn read_preview(
+        permission_manager: &PermissionManager,
+        path: impl AsRef<Path>,
+        max_size: u64,
+    ) -> Result<FilePreview> {
+        let path = path.as_ref();
+        permission_manager.validate_access(path)?;
+
+        let metadata = fs::metadata(path).await.map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                Error::Sandbox(SandboxError::FileNotFound(path.to_string_lossy().to_string()))
+            } else {
+                Error::Io(e)
+            }
+        })?;
+        if !metadata.is_file() {
+            return Err(Error::Sandbox(SandboxError::InvalidPath(path.to_string_lossy().to_string())));
+        }
+        if metadata.len() > max_size {
+            return Err(Error::Sandbox(SandboxError::FileTooLarge(format!(
+                "{} is {} bytes, the limit is {}",
+                path.display(),
+                metadata.len(),
+                max_size
+            ))));
+        }
+
+        debug!("Reading file preview: {:?}", path);
+
+        let bytes = fs::read(path).await?;
+        if bytes.iter().take(8000).any(|&b| b == 0) {
+            return Err(Error::Sandbox(SandboxError::NotTextFile(path.to_string_lossy().to_string())));
+        }
+        let content = String::from_utf8(bytes)
+            .map_err(|_| Error::Sandbox(SandboxError::NotTextFile(path.to_string_lossy().to_string())))?;
+
+        Ok(FilePreview {
+            path: path.to_string_lossy().to_string(),
+            size: metadata.len(),
+            content,
+        })
+    }
+
     /// Compute SHA256 hash of a file
     pub async fn compute_file_hash(path: impl AsRef<Path>) -> Result<String> {
         let path = path.as_ref();
@@ -406,6 +456,14 @@ pub struct FileWriteResult {
     pub hash_after: String,
 }
 
+/// A text file loaded for preview
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilePreview {
+    pub path: String,
+    pub size: u64,
+    pub content: String,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -467,6 +525,32 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_read_preview_limits() {
+        let dir = tempdir().unwrap();
+        let mut manager = PermissionManager::new();
+        manager
+            .grant_access(dir.path(), super::super::permissions::SecurityLevel::Strict)
+            .unwrap();
+
+        let text_path = dir.path().join("main.rs");
+        std::fs::write(&text_path, "fn main() {}\n").unwrap();
+        let binary_path = dir.path().join("image.png");
+        std::fs::write(&binary_path, [0x89, b'P', b'N', b'G', 0, 0]).unwrap();
+
+        let preview = FileSystemHandler::read_preview(&manager, &text_path, MAX_PREVIEW_SIZE)
+            .await
+            .unwrap();
+        assert_eq!(preview.content, "fn main() {}\n");
+        assert_eq!(preview.size, 13);
+
+        assert!(FileSystemHandler::read_preview(&manager, &text_path, 4).await.is_err());
+        assert!(FileSystemHandler::read_preview(&manager, &binary_path, MAX_PREVIEW_SIZE).await.is_err());
+        assert!(FileSystemHandler::read_preview(&PermissionManager::new(), &text_path, MAX_PREVIEW_SIZE)
+            .await
+            .is_err());
+    }
+
     #[tokio::test]
     async fn test_move_file() {
         let dir = tempdir().unwrap();
@@ -0,0 +1,284 @@
+//! Long-lived terminals agents open and write to
+//!
+//! Unlike [`TerminalHandler::run`], which waits for a command to finish, a
+//! [`LiveTerminal`] keeps running after it's opened: the agent (or the user,
+//! from the terminal's pane) writes to its stdin, reads what it has printed
+//! so far, and kills it when done. [`LiveTerminals`] keeps the open ones so
+//! both sides can find them by id.
+//!
+//! Opening a terminal goes through the terminal policy like any command;
+//! what's written to it afterwards doesn't.
+//!
+//! Output past the terminal's limit drops the oldest bytes instead of killing
+//! the process, and there's no wall-clock limit: a terminal runs until it
+//! exits, is killed, or is released.
+//!
+//! [`TerminalHandler::run`]: super::TerminalHandler::run
+
+use super::terminal::ProcessSlot;
+use crate::error::{Error, Result, SandboxError};
+use crate::types::TerminalLimits;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::process::{ChildStdin, Command};
+use tokio::sync::{oneshot, watch};
+use tracing::debug;
+
+/// Output kept for a terminal whose limits don't cap it
+const DEFAULT_OUTPUT_BYTES: usize = 1024 * 1024;
+
+/// Number of the next terminal opened, for its id
+static NEXT_TERMINAL: AtomicU64 = AtomicU64::new(1);
+
+/// What a terminal has printed so far and whether it has exited
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalOutput {
+    pub output: String,
+    /// The oldest output was dropped to stay within the limit
+    pub truncated: bool,
+    /// Exit code once the process has exited; -1 if it was killed by a signal
+    pub exit_code: Option<i32>,
+}
+
+/// Output of stdout and stderr interleaved, keeping only the latest bytes
+#[derive(Debug, Default)]
+struct OutputBuffer {
+    bytes: Vec<u8>,
+    truncated: bool,
+}
+
+impl OutputBuffer {
+    fn push(&mut self, chunk: &[u8], max: usize) {
+        self.bytes.extend_from_slice(chunk);
+        if self.bytes.len() > max {
+            let excess = self.bytes.len() - max;
+            self.bytes.drain(..excess);
+            self.truncated = true;
+        }
+    }
+}
+
+/// A running terminal opened by an agent
+#[derive(Debug)]
+pub struct LiveTerminal {
+    pub id: String,
+    /// Session whose agent opened it
+    pub session_id: String,
+    pub command_line: String,
+    /// Order the terminal was opened in
+    number: u64,
+    stdin: tokio::sync::Mutex<Option<ChildStdin>>,
+    output: Arc<Mutex<OutputBuffer>>,
+    max_output: usize,
+    exit: watch::Receiver<Option<i32>>,
+    kill: Mutex<Option<oneshot::Sender<()>>>,
+}
+
+impl LiveTerminal {
+    /// Spawn `cmd` with piped stdin, collecting its output in the background
+    pub(super) fn spawn(mut cmd: Command, session_id: &str, command_line: String, limits: &TerminalLimits) -> Result<Self> {
+        let slot = ProcessSlot::acquire(limits.max_processes)?;
+        cmd.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).kill_on_drop(true);
+        let mut child = cmd.spawn().map_err(|e| {
+            Error::Sandbox(SandboxError::AccessDenied(format!("Failed to open terminal '{}': {}", command_line, e)))
+        })?;
+
+        let max_output = limits.max_output_bytes.unwrap_or(DEFAULT_OUTPUT_BYTES);
+        let output = Arc::new(Mutex::new(OutputBuffer::default()));
+        let readers = [
+            child.stdout.take().map(|pipe| Box::new(pipe) as Box<dyn AsyncRead + Send + Unpin>),
+            child.stderr.take().map(|pipe| Box::new(pipe) as Box<dyn AsyncRead + Send + Unpin>),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|pipe| tokio::spawn(read_into(pipe, Arc::clone(&output), max_output)))
+        .collect::<Vec<_>>();
+
+        let (exit_tx, exit) = watch::channel(None);
+        let (kill_tx, kill_rx) = oneshot::channel();
+        let stdin = child.stdin.take();
+        tokio::spawn(async move {
+            let _slot = slot;
+            let status = tokio::select! {
+                status = child.wait() => status.ok(),
+                _ = kill_rx => {
+                    let _ = child.kill().await;
+                    child.wait().await.ok()
+                }
+            };
+            // Let the last output land before the exit is reported
+            for reader in readers {
+                let _ = reader.await;
+            }
+            let _ = exit_tx.send(Some(status.and_then(|status| status.code()).unwrap_or(-1)));
+        });
+
+        let number = NEXT_TERMINAL.fetch_add(1, Ordering::Relaxed);
+        Ok(Self {
+            id: format!("term-{}", number),
+            number,
+            session_id: session_id.to_string(),
+            command_line,
+            stdin: tokio::sync::Mutex::new(stdin),
+            output,
+            max_output,
+            exit,
+            kill: Mutex::new(Some(kill_tx)),
+        })
+    }
+
+    /// Send `data` to the terminal's stdin
+    pub async fn write(&self, data: &str) -> Result<()> {
+        let mut stdin = self.stdin.lock().await;
+        let pipe = stdin
+            .as_mut()
+            .ok_or_else(|| Error::Sandbox(SandboxError::AccessDenied("The terminal's input is closed".to_string())))?;
+        pipe.write_all(data.as_bytes()).await?;
+        pipe.flush().await?;
+        Ok(())
+    }
+
+    /// Add `text` to the output as if the terminal had printed it, such as
+    /// the line the user typed, for terminals that don't echo input
+    pub fn note(&self, text: &str) {
+        self.output.lock().unwrap_or_else(PoisonError::into_inner).push(text.as_bytes(), self.max_output);
+    }
+
+    /// Everything kept of the terminal's output, and its exit code if it has exited
+    pub fn output(&self) -> TerminalOutput {
+        let buffer = self.output.lock().unwrap_or_else(PoisonError::into_inner);
+        TerminalOutput {
+            output: String::from_utf8_lossy(&buffer.bytes).to_string(),
+            truncated: buffer.truncated,
+            exit_code: *self.exit.borrow(),
+        }
+    }
+
+    pub fn exit_code(&self) -> Option<i32> {
+        *self.exit.borrow()
+    }
+
+    /// Wait for the process to exit and return its exit code
+    pub async fn wait(&self) -> i32 {
+        let mut exit = self.exit.clone();
+        let code = match exit.wait_for(Option::is_some).await {
+            Ok(code) => code.unwrap_or(-1),
+            Err(_) => -1,
+        };
+        code
+    }
+
+    /// Kill the process; does nothing once it has exited
+    pub fn kill(&self) {
+        if let Some(kill) = self.kill.lock().unwrap_or_else(PoisonError::into_inner).take() {
+            let _ = kill.send(());
+        }
+    }
+}
+
+/// Append `pipe` to `output` until it closes
+async fn read_into(mut pipe: Box<dyn AsyncRead + Send + Unpin>, output: Arc<Mutex<OutputBuffer>>, max: usize) {
+    let mut buf = [0u8; 8192];
+    while let Ok(n) = pipe.read(&mut buf).await {
+        if n == 0 {
+            break;
+        }
+        output.lock().unwrap_or_else(PoisonError::into_inner).push(&buf[..n], max);
+    }
+}
+
+/// The terminals agents have open, shared between delegates and the UI
+#[derive(Debug, Default)]
+pub struct LiveTerminals {
+    terminals: Mutex<BTreeMap<String, Arc<LiveTerminal>>>,
+}
+
+impl LiveTerminals {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&self, terminal: LiveTerminal) -> Arc<LiveTerminal> {
+        let terminal = Arc::new(terminal);
+        debug!("Opened terminal {} for session {}: {}", terminal.id, terminal.session_id, terminal.command_line);
+        self.lock().insert(terminal.id.clone(), Arc::clone(&terminal));
+        terminal
+    }
+
+    /// The terminal `id` if `session_id` opened it
+    pub fn get(&self, session_id: &str, id: &str) -> Result<Arc<LiveTerminal>> {
+        self.lock()
+            .get(id)
+            .filter(|terminal| terminal.session_id == session_id)
+            .cloned()
+            .ok_or_else(|| Error::Sandbox(SandboxError::AccessDenied(format!("No terminal {}", id))))
+    }
+
+    /// The session's terminals, oldest first
+    pub fn for_session(&self, session_id: &str) -> Vec<Arc<LiveTerminal>> {
+        let mut terminals: Vec<_> =
+            self.lock().values().filter(|terminal| terminal.session_id == session_id).cloned().collect();
+        terminals.sort_by_key(|terminal| terminal.number);
+        terminals
+    }
+
+    /// Kill the terminal and forget it
+    pub fn release(&self, session_id: &str, id: &str) -> Result<()> {
+        let terminal = self.get(session_id, id)?;
+        terminal.kill();
+        self.lock().remove(id);
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, Arc<LiveTerminal>>> {
+        self.terminals.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sandbox::TerminalHandler;
+    use std::time::Duration;
+
+    #[test]
+    fn test_output_keeps_latest_bytes() {
+        let mut buffer = OutputBuffer::default();
+        buffer.push(b"hello ", 8);
+        assert!(!buffer.truncated);
+        buffer.push(b"world", 8);
+        assert_eq!(buffer.bytes, b"lo world");
+        assert!(buffer.truncated);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_write_read_and_kill() {
+        let terminals = LiveTerminals::new();
+        let terminal =
+            terminals.insert(TerminalHandler::open("s1", "cat", &[], None, None, &TerminalLimits::unlimited()).unwrap());
+        assert!(terminals.get("s2", &terminal.id).is_err());
+
+        terminal.write("ping\n").await.unwrap();
+        for _ in 0..100 {
+            if terminal.output().output == "ping\n" {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(terminal.output().output, "ping\n");
+        assert_eq!(terminal.exit_code(), None);
+
+        terminal.kill();
+        assert_eq!(terminal.wait().await, -1);
+        assert_eq!(terminal.output().exit_code, Some(-1));
+
+        terminals.release("s1", &terminal.id).unwrap();
+        assert!(terminals.for_session("s1").is_empty());
+    }
+}
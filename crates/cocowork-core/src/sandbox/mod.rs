@@ -4,13 +4,42 @@
 //! - Permission management for file access
 //! - File system operations with permission checks
 //! - File watching for change detection
+//! - Dry-run recording of agent side effects
+//! - Batched review of file changes
+//! - Per-kind approval policies
+//! - Auto mode, which approves requests without asking
+//! - Noticing files changed outside the agent
+//! - Isolated worktrees and copies for risky sessions
+//! - Running terminal commands in a container
+//! - Long-lived terminals agents write to
 
+mod auto_mode;
+pub mod changeset;
+mod container;
+pub mod diff;
+pub mod dry_run;
+mod external_edits;
 mod filesystem;
+mod live_terminal;
 pub mod permissions;
+mod policy;
 mod terminal;
 mod watcher;
+pub mod worktree;
 
-pub use filesystem::FileSystemHandler;
+pub use auto_mode::AutoMode;
+pub use changeset::{ChangeSetCollector, PendingChange, PendingChangeSet};
+pub use container::{ContainerConfig, ContainerMount, ContainerRuntime, DEFAULT_IMAGE};
+pub use diff::{compute_file_diff, format_unified_diff};
+pub use dry_run::{apply_action, DryRunRecorder, ProposedAction, ProposedActionKind};
+pub use external_edits::{ExternalEdit, ExternalEditKind, ExternalEditTracker};
+pub use filesystem::{FilePreview, FileSystemHandler, MAX_PREVIEW_SIZE};
+pub use live_terminal::{LiveTerminal, LiveTerminals, TerminalOutput};
 pub use permissions::{PermissionManager, SecurityLevel, FileOperation, PermissionEntry};
-pub use terminal::TerminalHandler;
+pub use policy::{Approval, PolicyScope, ToolPolicy};
+pub use terminal::{
+    format_command_line, parse_command_line, CommandConfirmation, CommandSegment, PolicyDecision,
+    TerminalHandler,
+};
 pub use watcher::FileWatcher;
+pub use worktree::{Isolation, IsolationKind, WorktreeManager};
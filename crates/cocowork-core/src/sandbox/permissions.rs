@@ -86,6 +86,13 @@ impl PermissionManager {
         Ok(())
     }
 
+    /// Grant `to` the access `from` has, for a session moved to a copy of a granted directory
+    pub fn rebind_root(&mut self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()> {
+        self.validate_access(from.as_ref())?;
+        let security_level = self.get_security_level(from);
+        self.grant_access(to, security_level)
+    }
+
     /// Check if a path is accessible
     pub fn check_access(&self, path: impl AsRef<Path>) -> Result<bool> {
         let path = Self::normalize_path(path.as_ref())?;
@@ -243,6 +250,19 @@ pub enum FileOperation {
     Execute,
 }
 
+impl FileOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileOperation::Read => "read",
+            FileOperation::Write => "write",
+            FileOperation::Delete => "delete",
+            FileOperation::List => "list",
+            FileOperation::Move => "move",
+            FileOperation::Execute => "execute",
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -279,6 +299,19 @@ mod tests {
         assert!(!manager.check_access(path).unwrap());
     }
 
+    #[test]
+    fn test_rebind_root() {
+        let mut manager = PermissionManager::new();
+        let workspace = tempdir().unwrap();
+        let copy = tempdir().unwrap();
+
+        assert!(manager.rebind_root(workspace.path(), copy.path()).is_err());
+        manager.grant_access(workspace.path(), SecurityLevel::Strict).unwrap();
+        manager.rebind_root(workspace.path(), copy.path()).unwrap();
+        assert!(manager.check_access(copy.path().join("src/lib.rs")).unwrap());
+        assert_eq!(manager.get_security_level(copy.path()), SecurityLevel::Strict);
+    }
+
     #[test]
     fn test_security_levels() {
         let mut manager = PermissionManager::new();
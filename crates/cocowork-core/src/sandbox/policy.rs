@@ -0,0 +1,221 @@
+//! Per-kind approval policies for agent requests
+//!
+//! A [`ToolPolicy`] says, for reads, writes, deletes and terminal commands,
+//! whether the agent may go ahead without asking, must always ask, or follows
+//! the sandbox defaults. Policies are stored globally, per agent and per
+//! workspace; the most specific one that exists applies.
+
+use super::permissions::FileOperation;
+use super::terminal::PolicyDecision;
+use crate::error::Result;
+use crate::storage::{delete_setting, get_setting, set_setting};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const POLICY_KEY: &str = "tool_policy";
+
+/// How requests of one kind are approved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Approval {
+    /// Follow the path's security level and the terminal allow list
+    #[default]
+    Default,
+    /// Go ahead without asking; the sandbox and terminal block list still apply
+    AutoAccept,
+    /// Ask every time
+    Confirm,
+}
+
+impl Approval {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Approval::Default => "Default",
+            Approval::AutoAccept => "Auto-accept",
+            Approval::Confirm => "Confirm",
+        }
+    }
+}
+
+/// Which agent requests need the user's approval
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolPolicy {
+    /// Reading files and listing directories
+    pub read: Approval,
+    /// Writing, moving and creating files and directories
+    pub write: Approval,
+    pub delete: Approval,
+    pub terminal: Approval,
+}
+
+/// Where a stored policy applies
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PolicyScope {
+    Global,
+    Agent(String),
+    Workspace(PathBuf),
+}
+
+impl PolicyScope {
+    fn setting_key(&self) -> String {
+        match self {
+            PolicyScope::Global => POLICY_KEY.to_string(),
+            PolicyScope::Agent(agent_id) => format!("{}.agent.{}", POLICY_KEY, agent_id),
+            PolicyScope::Workspace(path) => format!("{}.workspace.{}", POLICY_KEY, path.display()),
+        }
+    }
+}
+
+impl ToolPolicy {
+    /// The approval for an operation
+    pub fn approval(&self, operation: FileOperation) -> Approval {
+        match operation {
+            FileOperation::Read | FileOperation::List => self.read,
+            FileOperation::Write | FileOperation::Move => self.write,
+            FileOperation::Delete => self.delete,
+            FileOperation::Execute => self.terminal,
+        }
+    }
+
+    pub fn set_approval(&mut self, operation: FileOperation, approval: Approval) {
+        match operation {
+            FileOperation::Read | FileOperation::List => self.read = approval,
+            FileOperation::Write | FileOperation::Move => self.write = approval,
+            FileOperation::Delete => self.delete = approval,
+            FileOperation::Execute => self.terminal = approval,
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether an operation must be confirmed, given what the sandbox
+    /// defaults would decide
+    pub fn needs_confirmation(&self, operation: FileOperation, by_default: bool) -> bool {
+        match self.approval(operation) {
+            Approval::Default => by_default,
+            Approval::AutoAccept => false,
+            Approval::Confirm => true,
+        }
+    }
+
+    /// Adjust the terminal policy's decision for a command
+    ///
+    /// Denied commands stay denied whatever the approval.
+    pub fn terminal_decision(&self, decision: PolicyDecision) -> PolicyDecision {
+        match (self.terminal, decision) {
+            (_, deny @ PolicyDecision::Deny { .. }) => deny,
+            (Approval::AutoAccept, PolicyDecision::Confirm { .. }) => PolicyDecision::Allow,
+            (Approval::Confirm, PolicyDecision::Allow) => PolicyDecision::Confirm {
+                reason: "Tool policy asks before every command".to_string(),
+            },
+            (_, decision) => decision,
+        }
+    }
+
+    /// Short description of the non-default approvals, e.g. "Reads auto · Terminal confirm"
+    pub fn summary(&self) -> String {
+        let parts: Vec<String> = [
+            ("Reads", self.read),
+            ("Writes", self.write),
+            ("Deletes", self.delete),
+            ("Terminal", self.terminal),
+        ]
+        .into_iter()
+        .filter_map(|(kind, approval)| match approval {
+            Approval::Default => None,
+            Approval::AutoAccept => Some(format!("{} auto", kind)),
+            Approval::Confirm => Some(format!("{} confirm", kind)),
+        })
+        .collect();
+        if parts.is_empty() {
+            "Default permissions".to_string()
+        } else {
+            parts.join(" · ")
+        }
+    }
+
+    /// The policy stored for exactly this scope, if any
+    pub fn load(conn: &rusqlite::Connection, scope: &PolicyScope) -> Result<Option<Self>> {
+        Ok(get_setting(conn, &scope.setting_key())?.and_then(|raw| serde_json::from_str(&raw).ok()))
+    }
+
+    pub fn save(&self, conn: &rusqlite::Connection, scope: &PolicyScope) -> Result<()> {
+        set_setting(conn, &scope.setting_key(), &serde_json::to_string(self)?)
+    }
+
+    /// Remove the policy stored for a scope, so a broader one applies
+    pub fn clear(conn: &rusqlite::Connection, scope: &PolicyScope) -> Result<()> {
+        delete_setting(conn, &scope.setting_key())
+    }
+
+    /// The policy in effect: the workspace's, else the agent's, else the global one
+    pub fn resolve(conn: &rusqlite::Connection, agent_id: Option<&str>, workspace: Option<&Path>) -> Result<Self> {
+        let scopes = [
+            workspace.map(|path| PolicyScope::Workspace(path.to_path_buf())),
+            agent_id.map(|id| PolicyScope::Agent(id.to_string())),
+            Some(PolicyScope::Global),
+        ];
+        for scope in scopes.iter().flatten() {
+            if let Some(policy) = Self::load(conn, scope)? {
+                return Ok(policy);
+            }
+        }
+        Ok(Self::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+
+    #[test]
+    fn test_most_specific_policy_applies() {
+        let storage = Storage::in_memory().unwrap();
+        let conn = storage.connection().unwrap();
+        let workspace = Path::new("/work/app");
+
+        assert!(ToolPolicy::resolve(&conn, Some("claude-code"), Some(workspace)).unwrap().is_default());
+
+        let global = ToolPolicy {
+            read: Approval::AutoAccept,
+            ..Default::default()
+        };
+        let agent = ToolPolicy {
+            terminal: Approval::Confirm,
+            ..Default::default()
+        };
+        global.save(&conn, &PolicyScope::Global).unwrap();
+        agent.save(&conn, &PolicyScope::Agent("claude-code".into())).unwrap();
+
+        assert_eq!(ToolPolicy::resolve(&conn, Some("claude-code"), Some(workspace)).unwrap(), agent);
+        assert_eq!(ToolPolicy::resolve(&conn, Some("gemini"), Some(workspace)).unwrap(), global);
+
+        ToolPolicy::clear(&conn, &PolicyScope::Agent("claude-code".into())).unwrap();
+        assert_eq!(ToolPolicy::resolve(&conn, Some("claude-code"), None).unwrap(), global);
+        assert_eq!(global.summary(), "Reads auto");
+    }
+
+    #[test]
+    fn test_terminal_decisions() {
+        let confirm = PolicyDecision::Confirm { reason: "not allowed".into() };
+        let deny = PolicyDecision::Deny { reason: "blocked".into() };
+
+        let auto = ToolPolicy {
+            terminal: Approval::AutoAccept,
+            ..Default::default()
+        };
+        assert_eq!(auto.terminal_decision(confirm.clone()), PolicyDecision::Allow);
+        assert_eq!(auto.terminal_decision(deny.clone()), deny);
+
+        let strict = ToolPolicy {
+            terminal: Approval::Confirm,
+            ..Default::default()
+        };
+        assert!(matches!(strict.terminal_decision(PolicyDecision::Allow), PolicyDecision::Confirm { .. }));
+        assert_eq!(ToolPolicy::default().terminal_decision(confirm.clone()), confirm);
+    }
+}
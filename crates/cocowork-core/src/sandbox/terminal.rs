@@ -1,16 +1,422 @@
 //! Terminal command execution with policy checks
+//!
+//! Commands requested by agents are parsed into their individual segments
+//! (pipelines, `&&`/`||`/`;` chains, `VAR=value` prefixes and `sh -c` scripts)
+//! so that the allow list and blocked patterns apply to every program that
+//! would actually run, not just the first word of the command line.
 
+use super::container::ContainerConfig;
+use super::live_terminal::LiveTerminal;
+use super::permissions::FileOperation;
 use crate::error::{Error, Result, SandboxError};
-use crate::types::{TerminalExecuteResult, TerminalPolicy};
+use crate::types::{TerminalBackend, TerminalExecuteResult, TerminalLimits, TerminalPolicy};
 use std::collections::HashMap;
+use std::iter::Peekable;
 use std::path::Path;
+use std::process::{ExitStatus, Stdio};
+use std::str::Chars;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
 use tokio::process::Command;
+use tokio::sync::oneshot;
 use tracing::debug;
 
+/// Shells whose `-c` script is parsed and evaluated as well
+const SHELLS: &[&str] = &["sh", "bash", "zsh", "dash", "fish"];
+
+/// A single simple command within a command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandSegment {
+    /// `VAR=value` assignments preceding the program
+    pub env: Vec<(String, String)>,
+    /// Program basename (e.g. `ls` for `/bin/ls`)
+    pub program: String,
+    /// Arguments passed to the program
+    pub args: Vec<String>,
+}
+
+impl CommandSegment {
+    /// Program and arguments joined by single spaces
+    pub fn display(&self) -> String {
+        if self.args.is_empty() {
+            self.program.clone()
+        } else {
+            format!("{} {}", self.program, self.args.join(" "))
+        }
+    }
+}
+
+/// Outcome of evaluating a command against the terminal policy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyDecision {
+    /// Every segment is allowed; run without asking
+    Allow,
+    /// Not covered by the allow list; the user must confirm first
+    Confirm { reason: String },
+    /// Refused outright (disabled terminal or blocked pattern)
+    Deny { reason: String },
+}
+
+/// A command or file operation waiting for the user to approve or reject it
+#[derive(Debug)]
+pub struct CommandConfirmation {
+    /// Session that requested the command
+    pub session_id: String,
+    /// What is being asked for; `Execute` for commands
+    pub operation: FileOperation,
+    /// Full command line as it will be executed, or the path for file operations
+    pub command_line: String,
+    /// Working directory, if provided
+    pub cwd: Option<String>,
+    /// Why the policy did not allow it directly
+    pub reason: String,
+    /// Channel used to deliver the user's decision
+    pub responder: oneshot::Sender<bool>,
+}
+
+impl CommandConfirmation {
+    /// Deliver the user's decision; a dropped receiver is ignored
+    pub fn respond(self, approved: bool) {
+        let _ = self.responder.send(approved);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Operator,
+    /// The script inside a `$(...)` or backtick command substitution
+    Substitution(String),
+}
+
+/// Read a command substitution's script after its opening `$(` or backtick
+///
+/// `$(...)` may nest and hold quoted parentheses; a backtick one ends at the
+/// next unescaped backtick. The raw text is copied to `word` as well.
+fn read_substitution(chars: &mut Peekable<Chars<'_>>, backtick: bool, word: &mut String) -> String {
+    let mut script = String::new();
+    let mut depth = 1;
+    let mut quote = None;
+    while let Some(c) = chars.next() {
+        word.push(c);
+        match c {
+            '\\' if quote != Some('\'') => {
+                script.push(c);
+                if let Some(escaped) = chars.next() {
+                    word.push(escaped);
+                    script.push(escaped);
+                }
+                continue;
+            }
+            '`' if backtick => return script,
+            '\'' | '"' if !backtick => match quote {
+                None => quote = Some(c),
+                Some(open) if open == c => quote = None,
+                Some(_) => {}
+            },
+            '(' if !backtick && quote.is_none() => depth += 1,
+            ')' if !backtick && quote.is_none() => {
+                depth -= 1;
+                if depth == 0 {
+                    return script;
+                }
+            }
+            _ => {}
+        }
+        script.push(c);
+    }
+    script
+}
+
+/// Split a command line into words and control operators, honouring quotes
+///
+/// Parentheses are operators too, so a subshell's commands stand on their
+/// own, and command substitutions outside single quotes come out as tokens
+/// of their own.
+fn tokenize(line: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut chars = line.chars().peekable();
+
+    let flush = |tokens: &mut Vec<Token>, current: &mut String, in_word: &mut bool| {
+        if *in_word {
+            tokens.push(Token::Word(std::mem::take(current)));
+            *in_word = false;
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                for q in chars.by_ref() {
+                    if q == '\'' {
+                        break;
+                    }
+                    current.push(q);
+                }
+            }
+            '"' => {
+                in_word = true;
+                while let Some(q) = chars.next() {
+                    match q {
+                        '"' => break,
+                        '\\' => {
+                            if let Some(escaped) = chars.next() {
+                                current.push(escaped);
+                            }
+                        }
+                        '$' if chars.peek() == Some(&'(') => {
+                            current.push(q);
+                            current.push(chars.next().unwrap_or('('));
+                            let script = read_substitution(&mut chars, false, &mut current);
+                            tokens.push(Token::Substitution(script));
+                        }
+                        '`' => {
+                            current.push(q);
+                            let script = read_substitution(&mut chars, true, &mut current);
+                            tokens.push(Token::Substitution(script));
+                        }
+                        _ => current.push(q),
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'(') => {
+                in_word = true;
+                current.push(c);
+                current.push(chars.next().unwrap_or('('));
+                let script = read_substitution(&mut chars, false, &mut current);
+                tokens.push(Token::Substitution(script));
+            }
+            '`' => {
+                in_word = true;
+                current.push(c);
+                let script = read_substitution(&mut chars, true, &mut current);
+                tokens.push(Token::Substitution(script));
+            }
+            '\\' => {
+                in_word = true;
+                if let Some(escaped) = chars.next() {
+                    current.push(escaped);
+                }
+            }
+            // Redirections such as `2>&1` are part of the word
+            '&' if current.ends_with('>') || current.ends_with('<') => current.push(c),
+            '(' | ')' => {
+                flush(&mut tokens, &mut current, &mut in_word);
+                tokens.push(Token::Operator);
+            }
+            '|' | '&' | ';' | '\n' => {
+                flush(&mut tokens, &mut current, &mut in_word);
+                // Collapse `&&`, `||` and `|&` into a single separator
+                if matches!(chars.peek(), Some('|') | Some('&')) && c != ';' && c != '\n' {
+                    chars.next();
+                }
+                tokens.push(Token::Operator);
+            }
+            c if c.is_whitespace() => flush(&mut tokens, &mut current, &mut in_word),
+            _ => {
+                in_word = true;
+                current.push(c);
+            }
+        }
+    }
+    flush(&mut tokens, &mut current, &mut in_word);
+    tokens
+}
+
+fn is_env_assignment(word: &str) -> Option<(String, String)> {
+    let (name, value) = word.split_once('=')?;
+    let valid = !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    valid.then(|| (name.to_string(), value.to_string()))
+}
+
+fn program_name(word: &str) -> String {
+    Path::new(word)
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or(word)
+        .to_string()
+}
+
+/// Options of `xargs` that take the next word as their value
+const XARGS_VALUE_OPTIONS: &[&str] = &["-a", "-d", "-E", "-I", "-L", "-n", "-P", "-s"];
+
+/// Actions of `find` that run the words after them, up to `;` or `+`
+const FIND_EXEC_ACTIONS: &[&str] = &["-exec", "-execdir", "-ok", "-okdir"];
+
+/// Parse a shell-style command line into its simple commands
+///
+/// Nested `sh -c '<script>'` invocations, subshells, command substitutions
+/// and the commands `find -exec` and `xargs` run are expanded so the
+/// commands inside are evaluated too.
+pub fn parse_command_line(line: &str) -> Vec<CommandSegment> {
+    let mut segments = Vec::new();
+    let mut words: Vec<String> = Vec::new();
+    for token in tokenize(line) {
+        match token {
+            Token::Word(w) => words.push(w),
+            Token::Operator => push_segment(std::mem::take(&mut words), &mut segments),
+            Token::Substitution(script) => segments.extend(parse_command_line(&script)),
+        }
+    }
+    push_segment(words, &mut segments);
+    segments
+}
+
+/// Add the simple command made of `words`, and any command it runs
+fn push_segment(words: Vec<String>, segments: &mut Vec<CommandSegment>) {
+    let mut iter = words.into_iter().peekable();
+    let mut env = Vec::new();
+    while let Some(assignment) = iter.peek().and_then(|w| is_env_assignment(w)) {
+        env.push(assignment);
+        iter.next();
+    }
+    // `env VAR=x cmd` behaves like a plain env prefix
+    if iter.peek().map(|w| program_name(w) == "env").unwrap_or(false) {
+        iter.next();
+        while let Some(assignment) = iter.peek().and_then(|w| is_env_assignment(w)) {
+            env.push(assignment);
+            iter.next();
+        }
+    }
+    let Some(program) = iter.next() else {
+        return;
+    };
+    let program = program_name(&program);
+    let args: Vec<String> = iter.collect();
+
+    if SHELLS.contains(&program.as_str()) {
+        if let Some(pos) = args.iter().position(|a| a == "-c") {
+            if let Some(script) = args.get(pos + 1) {
+                segments.extend(parse_command_line(script));
+                return;
+            }
+        }
+    }
+
+    let mut nested = Vec::new();
+    match program.as_str() {
+        "find" => {
+            let mut rest = args.iter();
+            while let Some(arg) = rest.next() {
+                if FIND_EXEC_ACTIONS.contains(&arg.as_str()) {
+                    nested.push(rest.by_ref().take_while(|w| *w != ";" && *w != "+").cloned().collect());
+                }
+            }
+        }
+        "xargs" => {
+            let mut rest = args.iter();
+            while let Some(arg) = rest.next() {
+                if arg == "--" {
+                    nested.push(rest.cloned().collect());
+                    break;
+                } else if XARGS_VALUE_OPTIONS.contains(&arg.as_str()) {
+                    rest.next();
+                } else if !arg.starts_with('-') {
+                    nested.push(std::iter::once(arg).chain(rest).cloned().collect());
+                    break;
+                }
+            }
+        }
+        _ => {}
+    }
+    segments.push(CommandSegment { env, program, args });
+    for words in nested {
+        push_segment(words, segments);
+    }
+}
+
+/// Build a printable command line from a command and its arguments
+pub fn format_command_line(command: &str, args: &[String]) -> String {
+    let quote = |s: &str| {
+        if s.is_empty() || s.contains(|c: char| c.is_whitespace() || "|&;'\"\\$`()".contains(c)) {
+            format!("'{}'", s.replace('\'', r"'\''"))
+        } else {
+            s.to_string()
+        }
+    };
+    std::iter::once(command.to_string())
+        .chain(args.iter().map(|a| quote(a)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 /// Terminal handler enforcing the configured policy
 pub struct TerminalHandler;
 
 impl TerminalHandler {
+    /// Evaluate a command against the policy without executing it
+    pub fn evaluate(policy: &TerminalPolicy, command: &str, args: &[String]) -> PolicyDecision {
+        if !policy.enabled {
+            return PolicyDecision::Deny {
+                reason: "Terminal execution is disabled by policy".to_string(),
+            };
+        }
+
+        let full_line = format_command_line(command, args);
+        let segments = parse_command_line(&full_line);
+        if segments.is_empty() {
+            return PolicyDecision::Deny {
+                reason: "Empty command".to_string(),
+            };
+        }
+
+        for segment in &segments {
+            let text = segment.display();
+            if let Some(pat) = policy
+                .blocked_patterns
+                .iter()
+                .find(|pat| text.contains(pat.as_str()) || full_line.contains(pat.as_str()))
+            {
+                return PolicyDecision::Deny {
+                    reason: format!("Command blocked by policy (matched '{}'): {}", pat, text),
+                };
+            }
+        }
+
+        // Deleting files is worth a look even when find itself is allowed
+        let deletes = segments
+            .iter()
+            .find(|s| s.program == "find" && s.args.iter().any(|a| a == "-delete"));
+        if let Some(segment) = deletes {
+            return PolicyDecision::Confirm {
+                reason: format!("Command deletes files: {}", segment.display()),
+            };
+        }
+
+        if policy.allowed_commands.is_empty() {
+            return PolicyDecision::Allow;
+        }
+
+        let disallowed: Vec<&str> = segments
+            .iter()
+            .map(|s| s.program.as_str())
+            .filter(|p| !policy.allowed_commands.iter().any(|c| c == p))
+            .collect();
+
+        if disallowed.is_empty() {
+            return PolicyDecision::Allow;
+        }
+
+        let reason = format!("Command '{}' is not in the allowed list", disallowed.join("', '"));
+        if policy.require_confirmation {
+            PolicyDecision::Confirm { reason }
+        } else {
+            PolicyDecision::Deny { reason }
+        }
+    }
+
+    /// Execute a command on the host if the policy allows it outright, within its limits
+    ///
+    /// Commands that would need confirmation are rejected; callers with a way
+    /// to ask the user should use [`TerminalHandler::evaluate`] and
+    /// [`TerminalHandler::run`] instead. A policy with the container backend
+    /// is refused, since there's no workspace to mount.
     pub async fn execute(
         policy: &TerminalPolicy,
         command: &str,
@@ -18,67 +424,412 @@ impl TerminalHandler {
         cwd: Option<&str>,
         env: Option<&HashMap<String, String>>,
     ) -> Result<TerminalExecuteResult> {
-        if !policy.enabled {
+        if policy.backend == TerminalBackend::Container {
             return Err(Error::Sandbox(SandboxError::AccessDenied(
-                "Terminal execution is disabled by policy".to_string(),
+                "Container execution needs a workspace".to_string(),
             )));
         }
+        match Self::evaluate(policy, command, args) {
+            PolicyDecision::Allow => Self::run_limited(command, args, cwd, env, &policy.limits).await,
+            PolicyDecision::Confirm { reason } => Err(Error::Sandbox(SandboxError::AccessDenied(
+                format!("{} (confirmation required)", reason),
+            ))),
+            PolicyDecision::Deny { reason } => {
+                Err(Error::Sandbox(SandboxError::AccessDenied(reason)))
+            }
+        }
+    }
 
-        let cmd_name = Path::new(command)
-            .file_name()
-            .and_then(|s| s.to_str())
-            .unwrap_or(command);
+    /// Spawn the command and collect its output, without policy checks or limits
+    pub async fn run(
+        command: &str,
+        args: &[String],
+        cwd: Option<&str>,
+        env: Option<&HashMap<String, String>>,
+    ) -> Result<TerminalExecuteResult> {
+        Self::run_limited(command, args, cwd, env, &TerminalLimits::unlimited()).await
+    }
 
-        if !policy.allowed_commands.is_empty()
-            && !policy.allowed_commands.iter().any(|c| c == cmd_name)
-        {
-            return Err(Error::Sandbox(SandboxError::AccessDenied(format!(
-                "Command '{}' is not allowed by policy",
-                cmd_name
-            ))));
-        }
+    /// Spawn the command and collect its output, killing it if it goes over
+    /// `limits`, without policy checks
+    pub async fn run_limited(
+        command: &str,
+        args: &[String],
+        cwd: Option<&str>,
+        env: Option<&HashMap<String, String>>,
+        limits: &TerminalLimits,
+    ) -> Result<TerminalExecuteResult> {
+        debug!(
+            "Executing command: {} (cwd: {:?})",
+            format_command_line(command, args),
+            cwd
+        );
+        collect_output(host_command(command, args, cwd, env, limits), command, limits).await
+    }
 
-        let full_cmd = if args.is_empty() {
-            cmd_name.to_string()
+    /// Open a long-lived terminal running the command on the host, without
+    /// policy checks
+    ///
+    /// The CPU and process limits apply; see [`LiveTerminal`] for the others.
+    pub fn open(
+        session_id: &str,
+        command: &str,
+        args: &[String],
+        cwd: Option<&str>,
+        env: Option<&HashMap<String, String>>,
+        limits: &TerminalLimits,
+    ) -> Result<LiveTerminal> {
+        let command_line = format_command_line(command, args);
+        debug!("Opening terminal: {} (cwd: {:?})", command_line, cwd);
+        LiveTerminal::spawn(host_command(command, args, cwd, env, limits), session_id, command_line, limits)
+    }
+
+    /// The shell to open for the user: `$SHELL`, or the platform's default
+    pub fn user_shell() -> String {
+        if cfg!(windows) {
+            std::env::var("COMSPEC").unwrap_or_else(|_| "cmd".to_string())
         } else {
-            format!("{} {}", cmd_name, args.join(" "))
-        };
+            std::env::var("SHELL").ok().filter(|shell| !shell.is_empty()).unwrap_or_else(|| "sh".to_string())
+        }
+    }
 
-        if policy
-            .blocked_patterns
-            .iter()
-            .any(|pat| full_cmd.contains(pat))
-        {
-            return Err(Error::Sandbox(SandboxError::AccessDenied(format!(
-                "Command blocked by policy (matched blocked pattern): {}",
-                full_cmd
-            ))));
+    /// Run the command in a throwaway container with `workspace` mounted,
+    /// killing it if it goes over `limits`, without policy checks
+    pub async fn run_in_container(
+        config: &ContainerConfig,
+        workspace: &Path,
+        command: &str,
+        args: &[String],
+        cwd: Option<&str>,
+        env: Option<&HashMap<String, String>>,
+        limits: &TerminalLimits,
+    ) -> Result<TerminalExecuteResult> {
+        let name = format!("cocowork-{}", &uuid::Uuid::new_v4().simple().to_string()[..12]);
+        let (program, mut run_args) = config.command_line(workspace, command, args, cwd, env);
+        run_args.splice(1..1, ContainerConfig::limit_options(&name, limits));
+        debug!("Executing command in container {}: {}", name, format_command_line(command, args));
+
+        let mut cmd = Command::new(&program);
+        cmd.args(&run_args);
+        let result = collect_output(cmd, &program, limits).await;
+        if matches!(result, Err(Error::Sandbox(SandboxError::LimitExceeded(_)))) {
+            // Killing `docker run` leaves the container itself running
+            let _ = Command::new(&program).args(["kill", &name]).output().await;
+        }
+        result
+    }
+}
+
+/// The command to spawn on the host; with a CPU limit, a shell sets it and
+/// then becomes the command
+fn host_command(
+    command: &str,
+    args: &[String],
+    cwd: Option<&str>,
+    env: Option<&HashMap<String, String>>,
+    limits: &TerminalLimits,
+) -> Command {
+    let mut cmd = match limits.cpu_time_secs {
+        Some(secs) if cfg!(unix) => {
+            let mut cmd = Command::new("sh");
+            cmd.arg("-c").arg(format!("ulimit -t {} && exec \"$0\" \"$@\"", secs));
+            cmd.arg(command);
+            cmd
         }
+        _ => Command::new(command),
+    };
+    cmd.args(args);
+
+    if let Some(dir) = cwd {
+        cmd.current_dir(dir);
+    }
+
+    if let Some(envs) = env {
+        cmd.envs(envs);
+    }
+    cmd
+}
 
-        debug!("Executing command: {} (cwd: {:?})", full_cmd, cwd);
+/// Agent commands running right now, counted against [`TerminalLimits::max_processes`]
+static RUNNING: AtomicUsize = AtomicUsize::new(0);
 
-        let mut cmd = Command::new(command);
-        cmd.args(args);
+/// One of the running commands; the count goes down when it's dropped
+pub(super) struct ProcessSlot;
 
-        if let Some(dir) = cwd {
-            cmd.current_dir(dir);
+impl ProcessSlot {
+    pub(super) fn acquire(max: Option<usize>) -> Result<Self> {
+        let running = RUNNING.fetch_add(1, Ordering::SeqCst);
+        if max.is_some_and(|max| running >= max) {
+            RUNNING.fetch_sub(1, Ordering::SeqCst);
+            return Err(limit_exceeded(format!("{} commands are already running", running)));
         }
+        Ok(Self)
+    }
+}
+
+impl Drop for ProcessSlot {
+    fn drop(&mut self) {
+        RUNNING.fetch_sub(1, Ordering::SeqCst);
+    }
+}
 
-        if let Some(envs) = env {
-            cmd.envs(envs);
+fn limit_exceeded(reason: String) -> Error {
+    Error::Sandbox(SandboxError::LimitExceeded(reason))
+}
+
+/// Spawn `cmd` and gather its output, killing it if it goes over the
+/// wall-clock, output or CPU limit
+async fn collect_output(mut cmd: Command, program: &str, limits: &TerminalLimits) -> Result<TerminalExecuteResult> {
+    let _slot = ProcessSlot::acquire(limits.max_processes)?;
+    cmd.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()).kill_on_drop(true);
+    let mut child = cmd.spawn().map_err(|e| {
+        Error::Sandbox(SandboxError::AccessDenied(format!(
+            "Failed to execute command '{}': {}",
+            program_name(program),
+            e
+        )))
+    })?;
+
+    let (stdout, stderr) = (child.stdout.take(), child.stderr.take());
+    let total = AtomicUsize::new(0);
+    let max_output = limits.max_output_bytes.unwrap_or(usize::MAX);
+    let run = async {
+        let (stdout, stderr) = tokio::try_join!(
+            read_capped(stdout, &total, max_output),
+            read_capped(stderr, &total, max_output)
+        )?;
+        Ok::<_, Error>((child.wait().await?, stdout, stderr))
+    };
+    let finished = match limits.timeout_secs {
+        Some(secs) => tokio::time::timeout(Duration::from_secs(secs), run)
+            .await
+            .unwrap_or_else(|_| Err(limit_exceeded(format!("still running after {}s", secs)))),
+        None => run.await,
+    };
+    let (status, stdout, stderr) = match finished {
+        Ok(finished) => finished,
+        Err(e) => {
+            let _ = child.kill().await;
+            return Err(e);
         }
+    };
 
-        let output = cmd.output().await.map_err(|e| {
-            Error::Sandbox(SandboxError::AccessDenied(format!(
-                "Failed to execute command '{}': {}",
-                cmd_name, e
-            )))
-        })?;
+    if let Some(secs) = limits.cpu_time_secs.filter(|_| killed_for_cpu(&status)) {
+        return Err(limit_exceeded(format!("used more than {}s of CPU time", secs)));
+    }
+    Ok(TerminalExecuteResult {
+        exit_code: status.code().unwrap_or(-1),
+        stdout: String::from_utf8_lossy(&stdout).to_string(),
+        stderr: String::from_utf8_lossy(&stderr).to_string(),
+    })
+}
 
-        Ok(TerminalExecuteResult {
-            exit_code: output.status.code().unwrap_or(-1),
-            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-        })
+/// Read `pipe` to the end, failing once `total` across pipes passes `max` bytes
+async fn read_capped(pipe: Option<impl AsyncRead + Unpin>, total: &AtomicUsize, max: usize) -> Result<Vec<u8>> {
+    let mut output = Vec::new();
+    let Some(mut pipe) = pipe else {
+        return Ok(output);
+    };
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = pipe.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(output);
+        }
+        if total.fetch_add(n, Ordering::Relaxed) + n > max {
+            return Err(limit_exceeded(format!("printed more than {} bytes", max)));
+        }
+        output.extend_from_slice(&buf[..n]);
+    }
+}
+
+/// Whether the process was killed for going over its CPU time: SIGXCPU at
+/// the soft limit, SIGKILL at the hard one, or a container reporting SIGXCPU
+#[cfg(unix)]
+fn killed_for_cpu(status: &ExitStatus) -> bool {
+    use std::os::unix::process::ExitStatusExt;
+    const SIGXCPU: i32 = 24;
+    matches!(status.signal(), Some(SIGXCPU) | Some(9)) || status.code() == Some(128 + SIGXCPU)
+}
+
+#[cfg(not(unix))]
+fn killed_for_cpu(_status: &ExitStatus) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn programs(line: &str) -> Vec<String> {
+        parse_command_line(line).into_iter().map(|s| s.program).collect()
+    }
+
+    #[test]
+    fn test_parse_pipes_and_chains() {
+        assert_eq!(programs("ls -la | grep foo"), vec!["ls", "grep"]);
+        assert_eq!(programs("mkdir out && cp a out/ || echo failed; cat x"), vec!["mkdir", "cp", "echo", "cat"]);
+        assert_eq!(programs("/usr/bin/find . -name '*.rs'"), vec!["find"]);
+        assert_eq!(programs("cat log 2>&1 | grep err"), vec!["cat", "grep"]);
+    }
+
+    #[test]
+    fn test_parse_env_prefix_and_quotes() {
+        let segments = parse_command_line("RUST_LOG=debug FOO='a b' cargo test \"x | y\"");
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].program, "cargo");
+        assert_eq!(segments[0].env[1], ("FOO".to_string(), "a b".to_string()));
+        assert_eq!(segments[0].args, vec!["test", "x | y"]);
+
+        assert_eq!(programs("env A=1 ls"), vec!["ls"]);
+    }
+
+    #[test]
+    fn test_parse_shell_script() {
+        assert_eq!(programs("bash -c 'ls && rm foo'"), vec!["ls", "rm"]);
+        assert_eq!(programs("(cd out && rm -r build)"), vec!["cd", "rm"]);
+    }
+
+    #[test]
+    fn test_parse_command_substitution() {
+        assert_eq!(programs("echo $(rm -rf out)"), vec!["rm", "echo"]);
+        assert_eq!(programs("echo \"user: $(whoami)\""), vec!["whoami", "echo"]);
+        assert_eq!(programs("cat $(ls $(pwd)) | wc -l"), vec!["pwd", "ls", "cat", "wc"]);
+        assert_eq!(programs("echo $(printf ')') done"), vec!["printf", "echo"]);
+        assert_eq!(programs("echo `curl example.com`"), vec!["curl", "echo"]);
+        assert_eq!(programs("echo \"`id`\""), vec!["id", "echo"]);
+        // Single quotes keep them literal
+        assert_eq!(programs("echo '$(rm x)' '`rm y`'"), vec!["echo"]);
+
+        let segments = parse_command_line("echo $(whoami)");
+        assert_eq!(segments[1].args, vec!["$(whoami)"]);
+    }
+
+    fn limits(f: impl FnOnce(&mut TerminalLimits)) -> TerminalLimits {
+        let mut limits = TerminalLimits::unlimited();
+        f(&mut limits);
+        limits
+    }
+
+    fn assert_limit_exceeded(result: Result<TerminalExecuteResult>, reason: &str) {
+        match result {
+            Err(Error::Sandbox(SandboxError::LimitExceeded(message))) => {
+                assert!(message.contains(reason), "{}", message)
+            }
+            other => panic!("expected the limit to be exceeded, got {:?}", other),
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_limits_kill_commands() {
+        let start = std::time::Instant::now();
+        let timeout = limits(|l| l.timeout_secs = Some(1));
+        assert_limit_exceeded(TerminalHandler::run_limited("sleep", &["5".into()], None, None, &timeout).await, "1s");
+        assert!(start.elapsed() < Duration::from_secs(4));
+
+        let output = limits(|l| l.max_output_bytes = Some(1000));
+        let flood = ["-c".to_string(), "yes | head -c 100000".to_string()];
+        assert_limit_exceeded(TerminalHandler::run_limited("sh", &flood, None, None, &output).await, "1000 bytes");
+        let quiet = TerminalHandler::run_limited("echo", &["hi".into()], None, None, &output).await.unwrap();
+        assert_eq!(quiet.stdout, "hi\n");
+
+        let cpu = limits(|l| {
+            l.cpu_time_secs = Some(1);
+            l.timeout_secs = Some(30);
+        });
+        let spin = ["-c".to_string(), "while :; do :; done".to_string()];
+        assert_limit_exceeded(TerminalHandler::run_limited("sh", &spin, None, None, &cpu).await, "CPU");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_max_processes() {
+        let sleeper = tokio::spawn(async { TerminalHandler::run("sleep", &["2".into()], None, None).await });
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        let one_at_a_time = limits(|l| l.max_processes = Some(1));
+        assert_limit_exceeded(
+            TerminalHandler::run_limited("true", &[], None, None, &one_at_a_time).await,
+            "already running",
+        );
+        assert_eq!(sleeper.await.unwrap().unwrap().exit_code, 0);
+    }
+
+    #[test]
+    fn test_evaluate_policy() {
+        let policy = TerminalPolicy::default();
+
+        assert_eq!(
+            TerminalHandler::evaluate(&policy, "ls", &["-la".to_string()]),
+            PolicyDecision::Allow
+        );
+        assert!(matches!(
+            TerminalHandler::evaluate(&policy, "sh", &["-c".to_string(), "ls | curl x".to_string()]),
+            PolicyDecision::Confirm { .. }
+        ));
+        assert!(matches!(
+            TerminalHandler::evaluate(&policy, "bash", &["-c".to_string(), "ls && sudo rm x".to_string()]),
+            PolicyDecision::Deny { .. }
+        ));
+        // What a substitution runs needs approval like any other command
+        for script in ["ls $(curl x)", "ls \"`curl x`\"", "(ls; curl x)"] {
+            assert!(matches!(
+                TerminalHandler::evaluate(&policy, "sh", &["-c".to_string(), script.to_string()]),
+                PolicyDecision::Confirm { .. }
+            ));
+        }
+        // So does what find -exec and xargs run, and find -delete always does
+        for script in [
+            "find . -name '*.log' -exec curl -d @{} x \\;",
+            "find . -execdir curl {} +",
+            "find . -ok curl {} ';' -print",
+            "find . -name '*.tmp' -delete",
+        ] {
+            assert!(matches!(
+                TerminalHandler::evaluate(&policy, "sh", &["-c".to_string(), script.to_string()]),
+                PolicyDecision::Confirm { .. }
+            ));
+        }
+        assert_eq!(programs("find . -exec grep -l x {} \\; -exec ls {} +"), ["find", "grep", "ls"]);
+        assert!(matches!(
+            TerminalHandler::evaluate(&policy, "sh", &["-c".to_string(), "find / -exec sudo ls {} +".to_string()]),
+            PolicyDecision::Deny { .. }
+        ));
+        assert_eq!(
+            TerminalHandler::evaluate(&policy, "sh", &["-c".to_string(), "find . -exec grep -l x {} +".to_string()]),
+            PolicyDecision::Allow
+        );
+        let mut with_xargs = TerminalPolicy::default();
+        with_xargs.allowed_commands.push("xargs".to_string());
+        for script in ["find . | xargs -n 1 -I {} curl {}", "ls | xargs -- curl", "ls | xargs -0 curl"] {
+            assert!(matches!(
+                TerminalHandler::evaluate(&with_xargs, "sh", &["-c".to_string(), script.to_string()]),
+                PolicyDecision::Confirm { .. }
+            ));
+        }
+        assert_eq!(
+            TerminalHandler::evaluate(&with_xargs, "sh", &["-c".to_string(), "ls | xargs -n 1 grep x".to_string()]),
+            PolicyDecision::Allow
+        );
+
+        // Run without a shell, an argument that looks like one is just text
+        assert_eq!(
+            TerminalHandler::evaluate(&policy, "ls", &["$(curl)".to_string()]),
+            PolicyDecision::Allow
+        );
+
+        let strict = TerminalPolicy {
+            require_confirmation: false,
+            ..TerminalPolicy::default()
+        };
+        assert!(matches!(
+            TerminalHandler::evaluate(&strict, "find", &[".".to_string(), "-delete".to_string()]),
+            PolicyDecision::Confirm { .. }
+        ));
+        assert!(matches!(
+            TerminalHandler::evaluate(&strict, "curl", &[]),
+            PolicyDecision::Deny { .. }
+        ));
     }
 }
@@ -0,0 +1,382 @@
+//! Isolated copies of a workspace for risky sessions
+//!
+//! An isolated session works in a git worktree of the workspace, on a branch
+//! of its own, or in a plain copy when the workspace isn't a git checkout, so
+//! nothing the agent does touches the main checkout. Once it's done,
+//! [`Isolation::diff`] shows what changed since the session started and
+//! [`Isolation::merge_back`] applies that to the workspace, uncommitted, for
+//! review. Worktrees start from the last commit, so uncommitted changes in
+//! the main checkout aren't in them. A copy keeps a hash of each file as it
+//! was copied, so edits made in the workspace meanwhile aren't overwritten.
+
+use super::diff::{compute_file_diff, DEFAULT_CONTEXT_LINES};
+use crate::error::{Error, Result, SandboxError};
+use crate::git::{git, git_bytes};
+use crate::types::FileDiff;
+use ignore::WalkBuilder;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Prefix of the branches isolated worktrees are on
+pub const BRANCH_PREFIX: &str = "cocowork/";
+
+/// How a session is kept away from the workspace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IsolationKind {
+    /// A git worktree on `branch`, checked out at commit `base`
+    Worktree { branch: String, base: String },
+    /// A copy of the workspace's files, leaving out ignored ones, with the
+    /// hash of each file as of the copy or the last merge back
+    Copy { base: BTreeMap<PathBuf, [u8; 32]> },
+}
+
+/// Where an isolated session works instead of its workspace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Isolation {
+    /// The checkout the session was isolated from
+    pub workspace: PathBuf,
+    /// Top of the worktree or copy
+    pub root: PathBuf,
+    /// Where the workspace is under `root`, when it's a subdirectory of a repository
+    pub subdir: PathBuf,
+    pub kind: IsolationKind,
+}
+
+/// Creates isolated worktrees and copies in one directory
+#[derive(Debug, Clone)]
+pub struct WorktreeManager {
+    dir: PathBuf,
+}
+
+impl WorktreeManager {
+    /// Keep isolated copies under `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Isolate `workspace` as `name`: a worktree if it's in a git repository
+    /// with at least one commit, otherwise a copy
+    pub fn create(&self, workspace: &Path, name: &str) -> Result<Isolation> {
+        let root = self.dir.join(name);
+        if root.exists() {
+            return Err(Error::Internal(format!("{} already exists", root.display())));
+        }
+        std::fs::create_dir_all(&self.dir)?;
+
+        let repo = git(workspace, &["rev-parse", "--show-toplevel"])
+            .and_then(|top| Ok((PathBuf::from(top.trim()), git(workspace, &["rev-parse", "HEAD"])?)));
+        let Ok((top, base)) = repo else {
+            info!("Copying {} to {}", workspace.display(), root.display());
+            let base = copy_tree(workspace, &root)?;
+            return Ok(Isolation {
+                workspace: workspace.to_path_buf(),
+                root,
+                subdir: PathBuf::new(),
+                kind: IsolationKind::Copy { base },
+            });
+        };
+
+        let base = base.trim().to_string();
+        let branch = format!("{}{}", BRANCH_PREFIX, name);
+        info!("Adding worktree {} on {}", root.display(), branch);
+        let root_arg = root.to_string_lossy();
+        git(&top, &["worktree", "add", "-b", &branch, &root_arg, &base])?;
+
+        // `--show-toplevel` resolves symlinks, so compare against the resolved workspace
+        let resolved = workspace.canonicalize()?;
+        let subdir = resolved.strip_prefix(top.canonicalize()?).unwrap_or(Path::new("")).to_path_buf();
+        Ok(Isolation {
+            workspace: workspace.to_path_buf(),
+            root,
+            subdir,
+            kind: IsolationKind::Worktree { branch, base },
+        })
+    }
+}
+
+impl Isolation {
+    /// Directory the session's agent works in
+    pub fn working_dir(&self) -> PathBuf {
+        self.root.join(&self.subdir)
+    }
+
+    /// What the session changed since it was isolated, one diff per file
+    pub fn diff(&self) -> Result<Vec<FileDiff>> {
+        let diff = |path: &str, old: Option<String>, new: Option<String>| {
+            compute_file_diff(path, &old.unwrap_or_default(), &new.unwrap_or_default(), DEFAULT_CONTEXT_LINES)
+        };
+        match &self.kind {
+            IsolationKind::Worktree { base, .. } => Ok(self
+                .worktree_changes(base)?
+                .into_iter()
+                .map(|(path, status)| {
+                    let old = match status {
+                        'A' => None,
+                        _ => git(&self.root, &["show", &format!("{}:{}", base, path)]).ok(),
+                    };
+                    let new = match status {
+                        'D' => None,
+                        _ => read_lossy(&self.root.join(&path)),
+                    };
+                    diff(&path, old, new)
+                })
+                .collect()),
+            IsolationKind::Copy { base } => Ok(copy_changes(base, &self.root)
+                .into_iter()
+                .map(|path| {
+                    let old = read_lossy(&self.workspace.join(&path));
+                    let new = read_lossy(&self.root.join(&path));
+                    diff(&path.to_string_lossy(), old, new)
+                })
+                .collect()),
+        }
+    }
+
+    /// Apply the session's changes to the workspace, leaving them uncommitted
+    ///
+    /// Worktree changes go in as a patch, which fails rather than overwrite
+    /// lines changed in the workspace since; they're then committed on the
+    /// worktree's branch, so the next diff starts from there. Files the
+    /// session changed in a copy are written over the workspace's; if the
+    /// workspace changed any of them too since, nothing is merged and the
+    /// error names them.
+    pub fn merge_back(&mut self) -> Result<()> {
+        match &mut self.kind {
+            IsolationKind::Worktree { base, .. } => {
+                git(&self.root, &["add", "-A"])?;
+                let patch = git_bytes(&self.root, &["diff", "--cached", "--binary", base.as_str()], None)?;
+                if patch.is_empty() {
+                    return Ok(());
+                }
+                let top = self.workspace.canonicalize()?;
+                let top = top.ancestors().nth(self.subdir.components().count()).unwrap_or(&top);
+                git_bytes(top, &["apply", "--whitespace=nowarn", "-"], Some(&patch))?;
+
+                let identity = ["-c", "user.name=CocoWork", "-c", "user.email=cocowork@localhost"];
+                let commit = ["commit", "-q", "--no-verify", "-m", "Merged back into the workspace"];
+                git(&self.root, &[&identity[..], &commit[..]].concat())?;
+                *base = git(&self.root, &["rev-parse", "HEAD"])?.trim().to_string();
+            }
+            IsolationKind::Copy { base } => {
+                let changes = copy_changes(base, &self.root);
+                let conflicts: Vec<String> = changes
+                    .iter()
+                    .filter(|path| {
+                        let current = hash_file(&self.workspace.join(path));
+                        current != base.get(*path).copied() && current != hash_file(&self.root.join(path))
+                    })
+                    .map(|path| path.display().to_string())
+                    .collect();
+                if !conflicts.is_empty() {
+                    return Err(SandboxError::MergeConflict(conflicts.join(", ")).into());
+                }
+                for path in changes {
+                    let (from, to) = (self.root.join(&path), self.workspace.join(&path));
+                    if from.is_file() {
+                        if let Some(parent) = to.parent() {
+                            std::fs::create_dir_all(parent)?;
+                        }
+                        std::fs::copy(&from, &to)?;
+                    } else if to.exists() {
+                        std::fs::remove_file(&to)?;
+                    }
+                    match hash_file(&from) {
+                        Some(hash) => base.insert(path, hash),
+                        None => base.remove(&path),
+                    };
+                }
+            }
+        }
+        info!("Merged {} back into {}", self.root.display(), self.workspace.display());
+        Ok(())
+    }
+
+    /// Delete the worktree or copy, and a worktree's branch
+    pub fn remove(&self) -> Result<()> {
+        match &self.kind {
+            IsolationKind::Worktree { branch, .. } => {
+                let root = self.root.to_string_lossy();
+                git(&self.workspace, &["worktree", "remove", "--force", &root])?;
+                git(&self.workspace, &["branch", "-D", branch])?;
+            }
+            IsolationKind::Copy { .. } => std::fs::remove_dir_all(&self.root)?,
+        }
+        Ok(())
+    }
+
+    /// Files changed in the worktree since `base`, with git's status letter
+    fn worktree_changes(&self, base: &str) -> Result<Vec<(String, char)>> {
+        git(&self.root, &["add", "-A"])?;
+        let out = git(&self.root, &["diff", "--cached", "--name-status", "--no-renames", "-z", base])?;
+        let mut fields = out.split('\0').filter(|field| !field.is_empty());
+        let mut changes = Vec::new();
+        while let (Some(status), Some(path)) = (fields.next(), fields.next()) {
+            changes.push((path.to_string(), status.chars().next().unwrap_or('M')));
+        }
+        Ok(changes)
+    }
+}
+
+/// Files under `dir` that git wouldn't ignore, relative to it
+fn tree_files(dir: &Path) -> BTreeSet<PathBuf> {
+    WalkBuilder::new(dir)
+        .hidden(false)
+        .git_ignore(true)
+        .git_exclude(true)
+        .require_git(false)
+        .parents(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_some_and(|kind| kind.is_file()))
+        .filter_map(|entry| entry.path().strip_prefix(dir).ok().map(Path::to_path_buf))
+        .collect()
+}
+
+/// Copy the files under `from` to `to`, returning the hash of each
+fn copy_tree(from: &Path, to: &Path) -> Result<BTreeMap<PathBuf, [u8; 32]>> {
+    let mut hashes = BTreeMap::new();
+    for path in tree_files(from) {
+        let target = to.join(&path);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(from.join(&path), &target)?;
+        if let Some(hash) = hash_file(&target) {
+            hashes.insert(path, hash);
+        }
+    }
+    std::fs::create_dir_all(to)?;
+    Ok(hashes)
+}
+
+/// Files in `copy` that were added, changed or deleted since `base`
+fn copy_changes(base: &BTreeMap<PathBuf, [u8; 32]>, copy: &Path) -> Vec<PathBuf> {
+    let files = tree_files(copy);
+    base.keys()
+        .chain(files.iter())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter(|path| hash_file(&copy.join(path)) != base.get(*path).copied())
+        .cloned()
+        .collect()
+}
+
+fn hash_file(path: &Path) -> Option<[u8; 32]> {
+    std::fs::read(path).ok().map(|bytes| Sha256::digest(bytes).into())
+}
+
+fn read_lossy(path: &Path) -> Option<String> {
+    std::fs::read(path).ok().map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    fn has_git() -> bool {
+        Command::new("git").arg("--version").output().is_ok_and(|out| out.status.success())
+    }
+
+    #[test]
+    fn test_copy_isolation() {
+        let workspace = tempdir().unwrap();
+        let dir = tempdir().unwrap();
+        std::fs::write(workspace.path().join(".gitignore"), "target/\n").unwrap();
+        std::fs::write(workspace.path().join("keep.txt"), "one\ntwo\n").unwrap();
+        std::fs::write(workspace.path().join("gone.txt"), "bye\n").unwrap();
+        std::fs::create_dir(workspace.path().join("target")).unwrap();
+        std::fs::write(workspace.path().join("target/out.bin"), "built").unwrap();
+
+        let mut isolation = WorktreeManager::new(dir.path()).create(workspace.path(), "copy").unwrap();
+        assert!(matches!(isolation.kind, IsolationKind::Copy { .. }));
+        assert_eq!(isolation.working_dir(), dir.path().join("copy"));
+        assert!(!isolation.root.join("target").exists());
+
+        std::fs::write(isolation.root.join("keep.txt"), "one\n2\n").unwrap();
+        std::fs::remove_file(isolation.root.join("gone.txt")).unwrap();
+        std::fs::create_dir(isolation.root.join("src")).unwrap();
+        std::fs::write(isolation.root.join("src/new.txt"), "hello\n").unwrap();
+        let paths: Vec<_> = isolation.diff().unwrap().into_iter().map(|diff| diff.path).collect();
+        assert_eq!(paths, ["gone.txt", "keep.txt", "src/new.txt"]);
+
+        isolation.merge_back().unwrap();
+        assert_eq!(std::fs::read_to_string(workspace.path().join("keep.txt")).unwrap(), "one\n2\n");
+        assert_eq!(std::fs::read_to_string(workspace.path().join("src/new.txt")).unwrap(), "hello\n");
+        assert!(!workspace.path().join("gone.txt").exists());
+        assert!(isolation.diff().unwrap().is_empty());
+
+        isolation.remove().unwrap();
+        assert!(!isolation.root.exists());
+    }
+
+    #[test]
+    fn test_copy_merge_back_keeps_workspace_edits() {
+        let workspace = tempdir().unwrap();
+        let dir = tempdir().unwrap();
+        std::fs::write(workspace.path().join("agent.txt"), "one\n").unwrap();
+        std::fs::write(workspace.path().join("user.txt"), "one\n").unwrap();
+        std::fs::write(workspace.path().join("both.txt"), "one\n").unwrap();
+        let mut isolation = WorktreeManager::new(dir.path()).create(workspace.path(), "copy").unwrap();
+
+        // The user keeps working in the workspace while the session runs
+        std::fs::write(workspace.path().join("user.txt"), "mine\n").unwrap();
+        std::fs::write(workspace.path().join("both.txt"), "mine\n").unwrap();
+        std::fs::write(workspace.path().join("created.txt"), "new\n").unwrap();
+        std::fs::write(isolation.root.join("agent.txt"), "theirs\n").unwrap();
+        std::fs::write(isolation.root.join("both.txt"), "theirs\n").unwrap();
+        let paths: Vec<_> = isolation.diff().unwrap().into_iter().map(|diff| diff.path).collect();
+        assert_eq!(paths, ["agent.txt", "both.txt"]);
+
+        let err = isolation.merge_back().unwrap_err();
+        assert!(err.to_string().contains("both.txt"), "{}", err);
+        assert_eq!(std::fs::read_to_string(workspace.path().join("agent.txt")).unwrap(), "one\n");
+
+        std::fs::write(isolation.root.join("both.txt"), "one\n").unwrap();
+        isolation.merge_back().unwrap();
+        let read = |name: &str| std::fs::read_to_string(workspace.path().join(name)).unwrap();
+        assert_eq!(read("agent.txt"), "theirs\n");
+        assert_eq!(read("user.txt"), "mine\n");
+        assert_eq!(read("both.txt"), "mine\n");
+        assert_eq!(read("created.txt"), "new\n");
+        assert!(isolation.diff().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_worktree_isolation() {
+        if !has_git() {
+            return;
+        }
+        let repo = tempdir().unwrap();
+        let dir = tempdir().unwrap();
+        let run = |args: &[&str]| git(repo.path(), args).unwrap();
+        run(&["init", "-q"]);
+        std::fs::create_dir(repo.path().join("app")).unwrap();
+        std::fs::write(repo.path().join("app/main.txt"), "first\n").unwrap();
+        run(&["add", "-A"]);
+        run(&["-c", "user.name=t", "-c", "user.email=t@example.com", "commit", "-qm", "init"]);
+
+        let workspace = repo.path().join("app");
+        let mut isolation = WorktreeManager::new(dir.path()).create(&workspace, "risky").unwrap();
+        assert!(matches!(&isolation.kind, IsolationKind::Worktree { branch, .. } if branch == "cocowork/risky"));
+        assert_eq!(isolation.subdir, Path::new("app"));
+
+        std::fs::write(isolation.working_dir().join("main.txt"), "second\n").unwrap();
+        let diffs = isolation.diff().unwrap();
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].path, "app/main.txt");
+        assert_eq!(std::fs::read_to_string(workspace.join("main.txt")).unwrap(), "first\n");
+
+        isolation.merge_back().unwrap();
+        assert_eq!(std::fs::read_to_string(workspace.join("main.txt")).unwrap(), "second\n");
+        assert!(isolation.diff().unwrap().is_empty());
+
+        isolation.remove().unwrap();
+        assert!(!isolation.root.exists());
+        assert!(run(&["branch", "--list", "cocowork/*"]).trim().is_empty());
+    }
+}
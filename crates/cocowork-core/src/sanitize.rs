@@ -0,0 +1,286 @@
+//! Redacting a thread before it's exported or shared
+//!
+//! [`find_sensitive`] lists the paths, email addresses and secrets in an
+//! export, each distinct string once, so the user can go through them.
+//! A [`Redaction`] holds what they picked — single strings, whole kinds, and
+//! terms of their own — and [`Redaction::apply`] makes a redacted copy of the
+//! export. The stored thread is never changed.
+
+use crate::export::ThreadExport;
+use crate::redaction::{Redactor, REDACTED};
+use crate::types::{ContentBlock, MessageBlock, ToolCallContent};
+use regex::Regex;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::OnceLock;
+
+/// What a sensitive string is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SensitiveKind {
+    Secret,
+    Email,
+    Path,
+}
+
+impl SensitiveKind {
+    pub const ALL: [SensitiveKind; 3] = [SensitiveKind::Secret, SensitiveKind::Email, SensitiveKind::Path];
+
+    /// What the string is replaced with
+    pub fn placeholder(&self) -> &'static str {
+        match self {
+            SensitiveKind::Secret => "[secret]",
+            SensitiveKind::Email => "[email]",
+            SensitiveKind::Path => "[path]",
+        }
+    }
+}
+
+/// A sensitive string found in a thread
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub kind: SensitiveKind,
+    pub text: String,
+    /// How many times it appears
+    pub count: usize,
+}
+
+struct Patterns {
+    email: Regex,
+    path: Regex,
+    windows_path: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| Patterns {
+        email: Regex::new(r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}\b").unwrap(),
+        // Absolute or home-relative, at least two components, not inside a URL
+        path: Regex::new(r#"(?:^|[\s"'`(\[=])((?:~|/)[\w.@+-]+(?:/[\w.@+-]+)+)"#).unwrap(),
+        windows_path: Regex::new(r#"\b[A-Za-z]:\\(?:[^\\\s"'<>|:*?]+\\)*[^\\\s"'<>|:*?]+"#).unwrap(),
+    })
+}
+
+/// The sensitive strings in one piece of text, secrets first since an
+/// assignment or URL holding one can look like a path too
+fn find_in(text: &str) -> Vec<(SensitiveKind, &str)> {
+    let p = patterns();
+    let secrets = Redactor::new().find(text).into_iter().map(|secret| (SensitiveKind::Secret, secret));
+    let emails = p.email.find_iter(text).map(|m| (SensitiveKind::Email, m.as_str()));
+    let paths = p
+        .path
+        .captures_iter(text)
+        .filter_map(|c| c.get(1))
+        .chain(p.windows_path.find_iter(text))
+        .map(|m| (SensitiveKind::Path, m.as_str().trim_end_matches(['.', ',', ':', ';'])));
+    secrets.chain(emails).chain(paths).collect()
+}
+
+/// Every distinct sensitive string in the export, in the order first seen
+pub fn find_sensitive(export: &ThreadExport) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = Vec::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut export = export.clone();
+    visit_text(&mut export, &mut |text| {
+        for (kind, found) in find_in(text) {
+            match seen.get(found) {
+                Some(&idx) => findings[idx].count += 1,
+                None => {
+                    seen.insert(found.to_string(), findings.len());
+                    findings.push(Finding { kind, text: found.to_string(), count: 1 });
+                }
+            }
+        }
+    });
+    findings
+}
+
+/// What to hide when exporting a thread
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Redaction {
+    /// Kinds hidden wherever they're found
+    pub kinds: BTreeSet<SensitiveKind>,
+    /// Single findings hidden, by their text
+    pub strings: BTreeSet<String>,
+    /// The user's own terms, hidden wherever they appear
+    pub terms: BTreeSet<String>,
+}
+
+impl Redaction {
+    /// Every kind hidden, which is where a review starts
+    pub fn all() -> Self {
+        Self { kinds: SensitiveKind::ALL.into_iter().collect(), ..Self::default() }
+    }
+
+    /// Whether `finding` will be hidden
+    pub fn hides(&self, finding: &Finding) -> bool {
+        self.kinds.contains(&finding.kind) || self.strings.contains(&finding.text)
+    }
+
+    /// Hide or show one finding, leaving the rest of its kind as they are
+    pub fn toggle(&mut self, finding: &Finding, findings: &[Finding]) {
+        let hidden = self.hides(finding);
+        if self.kinds.remove(&finding.kind) {
+            // The kind's rule is split into its findings, less this one
+            let others = findings.iter().filter(|other| other.kind == finding.kind && other.text != finding.text);
+            self.strings.extend(others.map(|other| other.text.clone()));
+        }
+        if hidden {
+            self.strings.remove(&finding.text);
+        } else {
+            self.strings.insert(finding.text.clone());
+        }
+    }
+
+    /// Hide or show every finding of `kind`
+    pub fn toggle_kind(&mut self, kind: SensitiveKind, findings: &[Finding]) {
+        if !self.kinds.remove(&kind) {
+            self.kinds.insert(kind);
+        }
+        for finding in findings.iter().filter(|finding| finding.kind == kind) {
+            self.strings.remove(&finding.text);
+        }
+    }
+
+    /// A copy of `export` with the picked findings and terms replaced
+    pub fn apply(&self, export: &ThreadExport, findings: &[Finding]) -> ThreadExport {
+        let mut replacements: Vec<(&str, &str)> = findings
+            .iter()
+            .filter(|finding| self.hides(finding))
+            .map(|finding| (finding.text.as_str(), finding.kind.placeholder()))
+            .chain(self.terms.iter().map(|term| (term.as_str(), REDACTED)))
+            .filter(|(text, _)| !text.is_empty())
+            .collect();
+        // A path inside a longer one mustn't be replaced first and leave the rest showing
+        replacements.sort_by_key(|(text, _)| std::cmp::Reverse(text.len()));
+        let mut export = export.clone();
+        visit_text(&mut export, &mut |text| {
+            for (from, to) in &replacements {
+                if text.contains(from) {
+                    *text = text.replace(from, to);
+                }
+            }
+        });
+        export
+    }
+}
+
+/// Call `f` on every piece of text in the export a reader could see; IDs,
+/// timestamps and enum tags are left out
+fn visit_text(export: &mut ThreadExport, f: &mut dyn FnMut(&mut String)) {
+    f(&mut export.title);
+    export.working_dir.iter_mut().for_each(&mut *f);
+    for message in &mut export.messages {
+        match message {
+            MessageBlock::User { content, .. }
+            | MessageBlock::Agent { content, .. }
+            | MessageBlock::Thought { content, .. } => content.iter_mut().for_each(|block| visit_block(block, f)),
+            MessageBlock::System { content, .. } | MessageBlock::Summary { content, .. } => f(content),
+        }
+    }
+    for tool_call in &mut export.tool_calls {
+        tool_call.title.iter_mut().for_each(&mut *f);
+        for content in &mut tool_call.content {
+            match content {
+                ToolCallContent::Content { content } => visit_block(content, f),
+                ToolCallContent::Diff { diff } => {
+                    f(&mut diff.path);
+                    let lines = diff.hunks.iter_mut().flat_map(|hunk| hunk.lines.iter_mut());
+                    lines.for_each(|line| f(&mut line.content));
+                }
+            }
+        }
+        tool_call.input.iter_mut().chain(tool_call.output.iter_mut()).for_each(|value| visit_json(value, f));
+    }
+}
+
+fn visit_block(block: &mut ContentBlock, f: &mut dyn FnMut(&mut String)) {
+    match block {
+        ContentBlock::Text { text } => f(text),
+        ContentBlock::Image { .. } => {}
+        ContentBlock::ToolUse { input, .. } => visit_json(input, f),
+        ContentBlock::ToolResult { content, .. } => f(content),
+        ContentBlock::ResourceLink { uri, name, .. } => {
+            f(uri);
+            f(name);
+        }
+        ContentBlock::Resource { resource } => {
+            f(&mut resource.uri);
+            f(&mut resource.text);
+        }
+    }
+}
+
+fn visit_json(value: &mut serde_json::Value, f: &mut dyn FnMut(&mut String)) {
+    match value {
+        serde_json::Value::String(text) => f(text),
+        serde_json::Value::Array(items) => items.iter_mut().for_each(|item| visit_json(item, f)),
+        serde_json::Value::Object(map) => map.values_mut().for_each(|item| visit_json(item, f)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ToolCallState, ToolCallStatus};
+
+    fn export() -> ThreadExport {
+        let mut tool_call = ToolCallState::new("t1".to_string(), Some("Read /home/ana/app/.env".to_string()), None);
+        tool_call.status = ToolCallStatus::Completed;
+        let output = "API_KEY=sk-abcdefghijklmnop1234\nowner: ana@example.com";
+        tool_call.output = Some(serde_json::json!({ "text": output }));
+        ThreadExport::new(
+            "Fix config",
+            vec![
+                MessageBlock::user(vec![ContentBlock::Text {
+                    text: "Mail ana@example.com about /home/ana/app/.env and /home/ana/app.".to_string(),
+                }]),
+                MessageBlock::agent(vec![ContentBlock::Text { text: "See https://example.com/a/b".to_string() }]),
+            ],
+            vec![tool_call],
+        )
+        .with_working_dir("/home/ana/app")
+    }
+
+    #[test]
+    fn test_find_sensitive() {
+        let findings = find_sensitive(&export());
+        let found: Vec<_> = findings.iter().map(|f| (f.kind, f.text.as_str(), f.count)).collect();
+        assert_eq!(
+            found,
+            [
+                (SensitiveKind::Path, "/home/ana/app", 2),
+                (SensitiveKind::Email, "ana@example.com", 2),
+                (SensitiveKind::Path, "/home/ana/app/.env", 2),
+                (SensitiveKind::Secret, "sk-abcdefghijklmnop1234", 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply_redaction() {
+        let original = export();
+        let findings = find_sensitive(&original);
+
+        let mut redaction = Redaction::all();
+        redaction.toggle(&findings[0], &findings);
+        assert!(!redaction.hides(&findings[0]) && redaction.hides(&findings[2]));
+        redaction.terms.insert("Fix".to_string());
+
+        let redacted = redaction.apply(&original, &findings);
+        assert_eq!(redacted.title, "[redacted] config");
+        assert_eq!(redacted.working_dir.as_deref(), Some("/home/ana/app"));
+        let text = redacted.to_markdown();
+        assert!(text.contains("Mail [email] about [path] and /home/ana/app."));
+        assert!(text.contains("Read [path]"));
+        assert!(!text.contains("sk-abcdefghijklmnop1234"));
+        assert!(text.contains("https://example.com/a/b"));
+        // The original is left alone
+        assert_eq!(original.title, "Fix config");
+
+        // The rule was split by the toggle, so turning it back on hides every path
+        redaction.toggle_kind(SensitiveKind::Path, &findings);
+        assert!(redaction.hides(&findings[0]) && redaction.strings.is_empty());
+        redaction.toggle_kind(SensitiveKind::Path, &findings);
+        assert!(!redaction.hides(&findings[0]) && !redaction.hides(&findings[2]));
+    }
+}
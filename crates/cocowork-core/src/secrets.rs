@@ -0,0 +1,208 @@
+//! API key storage
+//!
+//! Agent API keys are kept in the OS keychain (macOS Keychain, Windows
+//! Credential Manager, Secret Service on Linux) rather than in the settings
+//! database. Adapters resolve keys through [`resolve_secret`], which prefers
+//! a stored key and falls back to the process environment. The key that
+//! encrypts stored conversations lives here too.
+
+use crate::error::{Error, Result};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Keychain service name all CocoWork secrets are stored under
+const KEYCHAIN_SERVICE: &str = "cocowork";
+
+/// Secrets CocoWork knows how to store
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SecretKey {
+    AnthropicApiKey,
+    GeminiApiKey,
+    OpenAiApiKey,
+    /// Key for encrypting stored conversations; not an agent key
+    DatabaseKey,
+    /// Tokens for opening pull requests; not agent keys either
+    GitHubToken,
+    GitLabToken,
+}
+
+impl SecretKey {
+    /// Agent API keys, as listed in settings
+    pub fn all() -> &'static [SecretKey] {
+        &[
+            SecretKey::AnthropicApiKey,
+            SecretKey::GeminiApiKey,
+            SecretKey::OpenAiApiKey,
+        ]
+    }
+
+    /// Environment variable agents read this key from
+    pub fn env_var(&self) -> &'static str {
+        match self {
+            SecretKey::AnthropicApiKey => "ANTHROPIC_API_KEY",
+            SecretKey::GeminiApiKey => "GEMINI_API_KEY",
+            SecretKey::OpenAiApiKey => "OPENAI_API_KEY",
+            SecretKey::DatabaseKey => "COCOWORK_DATABASE_KEY",
+            SecretKey::GitHubToken => "GITHUB_TOKEN",
+            SecretKey::GitLabToken => "GITLAB_TOKEN",
+        }
+    }
+
+    /// Human readable name for settings
+    pub fn label(&self) -> &'static str {
+        match self {
+            SecretKey::AnthropicApiKey => "Anthropic API key",
+            SecretKey::GeminiApiKey => "Gemini API key",
+            SecretKey::OpenAiApiKey => "OpenAI API key",
+            SecretKey::DatabaseKey => "Database encryption key",
+            SecretKey::GitHubToken => "GitHub token",
+            SecretKey::GitLabToken => "GitLab token",
+        }
+    }
+}
+
+/// Backend for storing secrets
+///
+/// Secrets are stored under an account name: a [`SecretKey`]'s environment
+/// variable, or a name of its own for secrets there can be several of, like
+/// one forge token per host.
+pub trait SecretStore: Send + Sync {
+    fn get_account(&self, account: &str) -> Result<Option<String>>;
+    fn set_account(&self, account: &str, value: &str) -> Result<()>;
+    /// Remove a stored secret; removing a missing secret is not an error
+    fn delete_account(&self, account: &str) -> Result<()>;
+
+    fn get(&self, key: SecretKey) -> Result<Option<String>> {
+        self.get_account(key.env_var())
+    }
+
+    fn set(&self, key: SecretKey, value: &str) -> Result<()> {
+        self.set_account(key.env_var(), value)
+    }
+
+    fn delete(&self, key: SecretKey) -> Result<()> {
+        self.delete_account(key.env_var())
+    }
+}
+
+/// Secrets stored in the OS keychain
+#[derive(Debug, Default)]
+pub struct KeychainStore;
+
+impl KeychainStore {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn entry(account: &str) -> Result<keyring::Entry> {
+        keyring::Entry::new(KEYCHAIN_SERVICE, account)
+            .map_err(|e| Error::Secret(e.to_string()))
+    }
+}
+
+impl SecretStore for KeychainStore {
+    fn get_account(&self, account: &str) -> Result<Option<String>> {
+        match Self::entry(account)?.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(Error::Secret(e.to_string())),
+        }
+    }
+
+    fn set_account(&self, account: &str, value: &str) -> Result<()> {
+        Self::entry(account)?
+            .set_password(value)
+            .map_err(|e| Error::Secret(e.to_string()))
+    }
+
+    fn delete_account(&self, account: &str) -> Result<()> {
+        match Self::entry(account)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(Error::Secret(e.to_string())),
+        }
+    }
+}
+
+/// Secrets kept in memory, for tests and when no keychain is available
+#[derive(Debug, Default)]
+pub struct MemorySecretStore {
+    values: Mutex<HashMap<String, String>>,
+}
+
+impl MemorySecretStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SecretStore for MemorySecretStore {
+    fn get_account(&self, account: &str) -> Result<Option<String>> {
+        Ok(self.values.lock().unwrap().get(account).cloned())
+    }
+
+    fn set_account(&self, account: &str, value: &str) -> Result<()> {
+        self.values.lock().unwrap().insert(account.to_string(), value.to_string());
+        Ok(())
+    }
+
+    fn delete_account(&self, account: &str) -> Result<()> {
+        self.values.lock().unwrap().remove(account);
+        Ok(())
+    }
+}
+
+/// Look up a key, preferring the store over the environment
+///
+/// Store errors are logged and treated as "not stored" so a broken keychain
+/// doesn't hide a key set in the environment.
+pub fn resolve_secret(store: Option<&dyn SecretStore>, key: SecretKey) -> Option<String> {
+    if let Some(store) = store {
+        match store.get(key) {
+            Ok(Some(value)) if !value.is_empty() => return Some(value),
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Failed to read {} from keychain: {}", key.label(), e),
+        }
+    }
+    std::env::var(key.env_var()).ok().filter(|v| !v.is_empty())
+}
+
+/// Show only the last few characters of a key, e.g. `sk-…a1b2`
+pub fn mask_secret(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    if chars.len() <= 8 {
+        return "•".repeat(chars.len());
+    }
+    let prefix: String = chars[..3].iter().collect();
+    let suffix: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}…{}", prefix, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stored_secret_wins_over_env() {
+        let store = MemorySecretStore::new();
+        assert_ne!(
+            resolve_secret(Some(&store), SecretKey::GeminiApiKey).as_deref(),
+            Some("stored-key")
+        );
+
+        store.set(SecretKey::GeminiApiKey, "stored-key").unwrap();
+        assert_eq!(
+            resolve_secret(Some(&store), SecretKey::GeminiApiKey).as_deref(),
+            Some("stored-key")
+        );
+
+        store.delete(SecretKey::GeminiApiKey).unwrap();
+        store.delete(SecretKey::GeminiApiKey).unwrap();
+        assert!(store.get(SecretKey::GeminiApiKey).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_mask_secret() {
+        assert_eq!(mask_secret("sk-ant-1234567890abcd"), "sk-…abcd");
+        assert_eq!(mask_secret("short"), "•••••");
+    }
+}
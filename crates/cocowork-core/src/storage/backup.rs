@@ -0,0 +1,248 @@
+//! Database backups
+//!
+//! Snapshots of `cocowork.db` are written with `VACUUM INTO` to a `backups`
+//! folder beside it and pruned to a retention count. Restoring swaps a
+//! snapshot in before the database is opened: either straight away when the
+//! database is unusable, or staged for the next launch while the app runs.
+
+use super::{backup_database, get_setting, set_setting, Storage};
+use crate::error::{Error, Result, StorageError};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{info, warn};
+
+const BACKUP_INTERVAL_KEY: &str = "backup.interval_hours";
+const BACKUP_KEEP_KEY: &str = "backup.keep";
+
+/// File name prefix of backup snapshots
+const BACKUP_PREFIX: &str = "cocowork-";
+
+/// How often to back up and how many snapshots to keep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackupPolicy {
+    /// Hours between backups; 0 turns scheduled backups off
+    pub interval_hours: u32,
+    /// Snapshots kept after pruning
+    pub keep: usize,
+}
+
+impl Default for BackupPolicy {
+    fn default() -> Self {
+        Self {
+            interval_hours: 24,
+            keep: 7,
+        }
+    }
+}
+
+impl BackupPolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.interval_hours > 0
+    }
+
+    /// Load the policy from settings, falling back to defaults
+    pub fn load(conn: &rusqlite::Connection) -> Result<Self> {
+        let defaults = Self::default();
+        Ok(Self {
+            interval_hours: get_setting(conn, BACKUP_INTERVAL_KEY)?
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.interval_hours),
+            keep: get_setting(conn, BACKUP_KEEP_KEY)?
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.keep),
+        })
+    }
+
+    pub fn save(&self, conn: &rusqlite::Connection) -> Result<()> {
+        set_setting(conn, BACKUP_INTERVAL_KEY, &self.interval_hours.to_string())?;
+        set_setting(conn, BACKUP_KEEP_KEY, &self.keep.to_string())
+    }
+}
+
+/// A snapshot in the backups folder
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupInfo {
+    pub path: PathBuf,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub size: u64,
+}
+
+/// Snapshots in `dir`, newest first
+pub fn list_backups_in(dir: &Path) -> Result<Vec<BackupInfo>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut backups = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let stamp = path
+                .file_name()?
+                .to_str()?
+                .strip_prefix(BACKUP_PREFIX)?
+                .strip_suffix(".db")?
+                .to_string();
+            let created_at = chrono::NaiveDateTime::parse_from_str(&stamp, "%Y%m%d-%H%M%S")
+                .ok()?
+                .and_utc();
+            let size = entry.metadata().ok()?.len();
+            Some(BackupInfo { path, created_at, size })
+        })
+        .collect::<Vec<_>>();
+    backups.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+    Ok(backups)
+}
+
+/// Replace the database at `db_path` with a backup
+///
+/// Must run while the database is closed. The replaced file is kept beside
+/// it, e.g. `cocowork.replaced-20250101-120000.db`.
+pub fn restore_backup(db_path: &Path, backup: &Path) -> Result<()> {
+    if !backup.exists() {
+        return Err(Error::Storage(StorageError::NotFound(backup.display().to_string())));
+    }
+    if db_path.exists() {
+        let stamp = chrono::Utc::now().format("%Y%m%d-%H%M%S");
+        let aside = db_path.with_extension(format!("replaced-{}.db", stamp));
+        std::fs::rename(db_path, &aside)?;
+        info!("Moved replaced database to {:?}", aside);
+    }
+    // Stale WAL files belong to the replaced database
+    for suffix in ["-wal", "-shm"] {
+        let mut sidecar = db_path.as_os_str().to_owned();
+        sidecar.push(suffix);
+        let _ = std::fs::remove_file(PathBuf::from(sidecar));
+    }
+    std::fs::copy(backup, db_path)?;
+    info!("Restored database from {:?}", backup);
+    Ok(())
+}
+
+/// Where a restore chosen while the app runs waits for the next launch
+pub fn staged_restore_path(db_path: &Path) -> PathBuf {
+    db_path.with_extension("db.restore")
+}
+
+impl Storage {
+    /// Folder holding this database's snapshots
+    pub fn backups_dir(&self) -> PathBuf {
+        self.db_path()
+            .parent()
+            .map(|dir| dir.join("backups"))
+            .unwrap_or_else(|| PathBuf::from("backups"))
+    }
+
+    /// Snapshots of this database, newest first
+    pub fn list_backups(&self) -> Result<Vec<BackupInfo>> {
+        list_backups_in(&self.backups_dir())
+    }
+
+    /// Write a snapshot now
+    pub fn create_backup(&self) -> Result<BackupInfo> {
+        let dir = self.backups_dir();
+        std::fs::create_dir_all(&dir)?;
+        let created_at = chrono::Utc::now();
+        let path = dir.join(format!("{}{}.db", BACKUP_PREFIX, created_at.format("%Y%m%d-%H%M%S")));
+
+        let conn = self.connection()?;
+        backup_database(&conn, &path)?;
+        let size = std::fs::metadata(&path)?.len();
+        info!("Backed up database to {:?}", path);
+        Ok(BackupInfo { path, created_at, size })
+    }
+
+    /// Delete all but the newest `keep` snapshots, returning how many went
+    pub fn prune_backups(&self, keep: usize) -> Result<usize> {
+        let stale = self.list_backups()?.into_iter().skip(keep.max(1)).collect::<Vec<_>>();
+        for backup in &stale {
+            std::fs::remove_file(&backup.path)?;
+        }
+        Ok(stale.len())
+    }
+
+    /// Back up if the newest snapshot is older than the policy's interval
+    pub fn backup_if_due(&self, policy: &BackupPolicy) -> Result<Option<BackupInfo>> {
+        if !policy.is_enabled() || self.db_path() == Path::new(":memory:") {
+            return Ok(None);
+        }
+        let interval = chrono::Duration::hours(policy.interval_hours as i64);
+        let due = match self.list_backups()?.first() {
+            Some(newest) => chrono::Utc::now() - newest.created_at >= interval,
+            None => true,
+        };
+        if !due {
+            return Ok(None);
+        }
+        let backup = self.create_backup()?;
+        self.prune_backups(policy.keep)?;
+        Ok(Some(backup))
+    }
+
+    /// Restore `backup` the next time the database is opened
+    pub fn stage_restore(&self, backup: &Path) -> Result<()> {
+        std::fs::copy(backup, staged_restore_path(self.db_path()))?;
+        info!("Staged {:?} to restore on next launch", backup);
+        Ok(())
+    }
+}
+
+/// Check now and then whether a backup is due, re-reading the policy each time
+pub async fn run_scheduled_backups(storage: std::sync::Arc<Storage>, check_every: Duration) {
+    let mut ticker = tokio::time::interval(check_every);
+    loop {
+        ticker.tick().await;
+        // Backups are left to the process writing the database
+        if storage.is_read_only() {
+            continue;
+        }
+        let storage = std::sync::Arc::clone(&storage);
+        let result = tokio::task::spawn_blocking(move || {
+            let policy = BackupPolicy::load(&*storage.connection()?)?;
+            storage.backup_if_due(&policy)
+        })
+        .await;
+        match result {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => warn!("Scheduled backup failed: {}", e),
+            Err(e) => warn!("Scheduled backup panicked: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::StorageConfig;
+
+    #[test]
+    fn test_backup_prune_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("cocowork.db");
+        let storage = Storage::from_path(db_path.clone(), StorageConfig::default()).unwrap();
+        set_setting(&storage.connection().unwrap(), "marker", "before").unwrap();
+
+        let backup = storage.backup_if_due(&BackupPolicy::default()).unwrap().unwrap();
+        assert!(backup.path.starts_with(dir.path().join("backups")));
+        // Not due again until the interval passes
+        assert!(storage.backup_if_due(&BackupPolicy::default()).unwrap().is_none());
+
+        // Older snapshots beyond the retention count are pruned
+        for stamp in ["20240101-000000", "20240102-000000"] {
+            std::fs::copy(&backup.path, dir.path().join(format!("backups/cocowork-{}.db", stamp))).unwrap();
+        }
+        assert_eq!(storage.list_backups().unwrap().len(), 3);
+        assert_eq!(storage.prune_backups(2).unwrap(), 1);
+        assert_eq!(storage.list_backups().unwrap()[0].path, backup.path);
+
+        set_setting(&storage.connection().unwrap(), "marker", "after").unwrap();
+        drop(storage);
+        restore_backup(&db_path, &backup.path).unwrap();
+
+        let storage = Storage::from_path(db_path, StorageConfig::default()).unwrap();
+        let marker = get_setting(&storage.connection().unwrap(), "marker").unwrap();
+        assert_eq!(marker.as_deref(), Some("before"));
+    }
+}
@@ -0,0 +1,104 @@
+//! Connection settings for the SQLite database
+
+use std::time::Duration;
+
+/// SQLite journal mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalMode {
+    Delete,
+    Truncate,
+    Persist,
+    Memory,
+    /// Write-ahead log: readers don't block the writer
+    Wal,
+    Off,
+}
+
+impl JournalMode {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            Self::Delete => "DELETE",
+            Self::Truncate => "TRUNCATE",
+            Self::Persist => "PERSIST",
+            Self::Memory => "MEMORY",
+            Self::Wal => "WAL",
+            Self::Off => "OFF",
+        }
+    }
+}
+
+/// How often SQLite syncs to disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Synchronous {
+    Off,
+    /// Safe with WAL; a power loss may drop the last commits
+    Normal,
+    Full,
+    Extra,
+}
+
+impl Synchronous {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            Self::Off => "OFF",
+            Self::Normal => "NORMAL",
+            Self::Full => "FULL",
+            Self::Extra => "EXTRA",
+        }
+    }
+}
+
+/// Settings applied to every pooled connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageConfig {
+    pub journal_mode: JournalMode,
+    pub synchronous: Synchronous,
+    /// How long a connection waits on a locked database before failing
+    pub busy_timeout: Duration,
+    /// Most connections open at once
+    pub pool_size: u32,
+}
+
+impl Default for StorageConfig {
+    /// WAL with NORMAL sync, as the desktop app uses
+    fn default() -> Self {
+        Self {
+            journal_mode: JournalMode::Wal,
+            synchronous: Synchronous::Normal,
+            busy_timeout: Duration::from_secs(5),
+            pool_size: 10,
+        }
+    }
+}
+
+impl StorageConfig {
+    pub fn with_journal_mode(mut self, journal_mode: JournalMode) -> Self {
+        self.journal_mode = journal_mode;
+        self
+    }
+
+    pub fn with_synchronous(mut self, synchronous: Synchronous) -> Self {
+        self.synchronous = synchronous;
+        self
+    }
+
+    pub fn with_busy_timeout(mut self, busy_timeout: Duration) -> Self {
+        self.busy_timeout = busy_timeout;
+        self
+    }
+
+    pub fn with_pool_size(mut self, pool_size: u32) -> Self {
+        self.pool_size = pool_size.max(1);
+        self
+    }
+
+    /// Apply the pragmas to a freshly opened connection
+    pub(crate) fn apply(&self, conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+        conn.busy_timeout(self.busy_timeout)?;
+        conn.pragma_update_and_check(None, "journal_mode", self.journal_mode.as_sql(), |row| {
+            row.get::<_, String>(0)
+        })?;
+        conn.pragma_update(None, "synchronous", self.synchronous.as_sql())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,441 @@
+//! Encryption at rest for conversation content
+//!
+//! Message bodies, tool call input/output and task prompts are sealed with
+//! AES-256-GCM before they're written. The key lives in the OS keychain, not
+//! the database. Sealed values carry a prefix so plaintext rows written before
+//! encryption was turned on still read back, and [`reseal_content`] converts a
+//! database in either direction. Converting also rewrites the backups and
+//! vacuums the database, so no copy of the old content is left on disk.
+
+use super::{delete_setting, get_setting, set_setting, Storage};
+use crate::error::{Error, Result, StorageError};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use rusqlite::{params, Connection};
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Marks a value sealed by [`ContentCipher`]
+const SEALED_PREFIX: &str = "enc1:";
+
+/// Setting recording that new content is written sealed
+const ENCRYPTED_KEY: &str = "storage.encrypted";
+
+/// Nonce length for AES-GCM
+const NONCE_LEN: usize = 12;
+
+fn encryption_error(message: impl Into<String>) -> Error {
+    Error::Storage(StorageError::Encryption(message.into()))
+}
+
+/// AES-256-GCM cipher for stored content
+pub struct ContentCipher {
+    cipher: Aes256Gcm,
+}
+
+impl std::fmt::Debug for ContentCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ContentCipher").finish_non_exhaustive()
+    }
+}
+
+impl ContentCipher {
+    /// A new random key, base64 encoded for the keychain
+    pub fn generate_key() -> String {
+        BASE64.encode(Aes256Gcm::generate_key(OsRng))
+    }
+
+    /// Build a cipher from a key made by [`ContentCipher::generate_key`]
+    pub fn from_key(encoded: &str) -> Result<Self> {
+        let key = BASE64
+            .decode(encoded.trim())
+            .map_err(|e| encryption_error(format!("Invalid key: {}", e)))?;
+        if key.len() != 32 {
+            return Err(encryption_error(format!("Key is {} bytes, expected 32", key.len())));
+        }
+        Ok(Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)),
+        })
+    }
+
+    /// Whether a stored value was sealed
+    pub fn is_sealed(value: &str) -> bool {
+        value.starts_with(SEALED_PREFIX)
+    }
+
+    /// Encrypt `plaintext` under a fresh nonce
+    pub fn seal(&self, plaintext: &str) -> Result<String> {
+        let nonce = Aes256Gcm::generate_nonce(OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| encryption_error("Failed to encrypt"))?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(format!("{}{}", SEALED_PREFIX, BASE64.encode(sealed)))
+    }
+
+    /// Decrypt a sealed value; plaintext values are returned as they are
+    pub fn open(&self, stored: &str) -> Result<String> {
+        let Some(encoded) = stored.strip_prefix(SEALED_PREFIX) else {
+            return Ok(stored.to_string());
+        };
+        let sealed = BASE64
+            .decode(encoded)
+            .map_err(|e| encryption_error(format!("Malformed value: {}", e)))?;
+        if sealed.len() < NONCE_LEN {
+            return Err(encryption_error("Malformed value: too short"));
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| encryption_error("Failed to decrypt; the key doesn't match"))?;
+        String::from_utf8(plaintext).map_err(|e| encryption_error(e.to_string()))
+    }
+}
+
+/// Seal `value` if there's a cipher
+pub(crate) fn seal(cipher: Option<&ContentCipher>, value: String) -> Result<String> {
+    match cipher {
+        Some(cipher) => cipher.seal(&value),
+        None => Ok(value),
+    }
+}
+
+/// Open `value`, failing if it's sealed and there's no cipher
+pub(crate) fn open(cipher: Option<&ContentCipher>, value: String) -> Result<String> {
+    match cipher {
+        Some(cipher) => cipher.open(&value),
+        None if ContentCipher::is_sealed(&value) => {
+            Err(encryption_error("Content is encrypted but no key is available"))
+        }
+        None => Ok(value),
+    }
+}
+
+/// [`open`] for use inside row mappers
+pub(crate) fn open_column(cipher: Option<&ContentCipher>, value: String, index: usize) -> rusqlite::Result<String> {
+    open(cipher, value).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+/// Whether content is written sealed
+pub fn is_encryption_enabled(conn: &Connection) -> Result<bool> {
+    Ok(get_setting(conn, ENCRYPTED_KEY)?.as_deref() == Some("true"))
+}
+
+/// Columns holding conversation content, by table
+const SEALED_COLUMNS: &[(&str, &str, &[&str])] = &[
+    ("tasks", "id", &["prompt_text"]),
+    ("messages", "id", &["content"]),
+    ("tool_calls", "id", &["raw_input", "raw_output", "content"]),
+    ("stream_journal", "session_id", &["task", "messages"]),
+    ("plan_snapshots", "id", &["entries"]),
+    ("task_commits", "rowid", &["message"]),
+    ("artifacts", "id", &["summary"]),
+];
+
+/// Re-encrypt all stored content from one key to another in one transaction
+///
+/// `from` reads existing rows (plaintext rows need none) and `to` writes them
+/// back; `None` for `to` leaves them as plaintext. Tables a backup from an
+/// older version doesn't have yet are skipped. Records whether encryption is
+/// on and returns how many values changed.
+pub fn reseal_content(conn: &Connection, from: Option<&ContentCipher>, to: Option<&ContentCipher>) -> Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    let mut changed = 0;
+    for (table, id_column, columns) in SEALED_COLUMNS {
+        let exists: bool = tx.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = ?",
+            [table],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            continue;
+        }
+        for column in *columns {
+            let rows = {
+                let mut stmt = tx.prepare(&format!(
+                    "SELECT {id}, {column} FROM {table} WHERE {column} IS NOT NULL",
+                    id = id_column,
+                    column = column,
+                    table = table
+                ))?;
+                let rows = stmt
+                    .query_map([], |row| Ok((row.get::<_, rusqlite::types::Value>(0)?, row.get::<_, String>(1)?)))?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                rows
+            };
+            let mut update = tx.prepare(&format!(
+                "UPDATE {table} SET {column} = ? WHERE {id} = ?",
+                table = table,
+                column = column,
+                id = id_column
+            ))?;
+            for (id, stored) in rows {
+                let value = seal(to, open(from, stored.clone())?)?;
+                if value != stored {
+                    update.execute(params![value, id])?;
+                    changed += 1;
+                }
+            }
+        }
+    }
+    if to.is_some() {
+        set_setting(&tx, ENCRYPTED_KEY, "true")?;
+    } else {
+        delete_setting(&tx, ENCRYPTED_KEY)?;
+    }
+    tx.commit()?;
+    Ok(changed)
+}
+
+/// [`reseal_content`] for a backup file, vacuumed after so the old content is gone
+fn reseal_backup(path: &Path, from: Option<&ContentCipher>, to: Option<&ContentCipher>) -> Result<usize> {
+    let conn = Connection::open(path)?;
+    let changed = reseal_content(&conn, from, to)?;
+    conn.execute_batch("VACUUM")?;
+    Ok(changed)
+}
+
+impl Storage {
+    /// Cipher new content is sealed with, if encryption is on
+    pub fn cipher(&self) -> Option<Arc<ContentCipher>> {
+        self.cipher.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Whether the database holds sealed content
+    pub fn is_encrypted(&self) -> Result<bool> {
+        is_encryption_enabled(&*self.connection()?)
+    }
+
+    /// Use the key for a database that's already encrypted
+    pub fn unlock(&self, cipher: ContentCipher) {
+        *self.cipher.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(cipher));
+    }
+
+    /// Encrypt existing content with `cipher` and seal everything written from now on
+    ///
+    /// Returns how many stored values were converted.
+    pub fn enable_encryption(&self, cipher: ContentCipher) -> Result<usize> {
+        let mut current = self.cipher.write().unwrap_or_else(|e| e.into_inner());
+        let changed = reseal_content(&*self.connection()?, current.as_deref(), Some(&cipher))?;
+        self.reseal_backups(current.as_deref(), Some(&cipher));
+        *current = Some(Arc::new(cipher));
+        self.vacuum()?;
+        info!("Encrypted {} stored values", changed);
+        Ok(changed)
+    }
+
+    /// Decrypt all content back to plaintext and stop sealing new content
+    pub fn disable_encryption(&self) -> Result<usize> {
+        let mut current = self.cipher.write().unwrap_or_else(|e| e.into_inner());
+        let changed = reseal_content(&*self.connection()?, current.as_deref(), None)?;
+        self.reseal_backups(current.as_deref(), None);
+        *current = None;
+        self.vacuum()?;
+        info!("Decrypted {} stored values", changed);
+        Ok(changed)
+    }
+
+    /// Convert the snapshots in the backups folder like the database
+    ///
+    /// A snapshot that can't be converted, e.g. one sealed with an older key,
+    /// is left as it is with a warning.
+    fn reseal_backups(&self, from: Option<&ContentCipher>, to: Option<&ContentCipher>) {
+        if self.db_path() == Path::new(":memory:") {
+            return;
+        }
+        let backups = match self.list_backups() {
+            Ok(backups) => backups,
+            Err(e) => {
+                warn!("Failed to list backups to convert: {}", e);
+                return;
+            }
+        };
+        for backup in backups {
+            if let Err(e) = reseal_backup(&backup.path, from, to) {
+                warn!("Backup {:?} still holds content in its old form: {}", backup.path, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() {
+        let cipher = ContentCipher::from_key(&ContentCipher::generate_key()).unwrap();
+        let sealed = cipher.seal("fn secret() {}").unwrap();
+
+        assert!(ContentCipher::is_sealed(&sealed));
+        assert!(!sealed.contains("secret"));
+        assert_eq!(cipher.open(&sealed).unwrap(), "fn secret() {}");
+        // Plaintext from before encryption was turned on passes through
+        assert_eq!(cipher.open("plain").unwrap(), "plain");
+
+        let other = ContentCipher::from_key(&ContentCipher::generate_key()).unwrap();
+        assert!(other.open(&sealed).is_err());
+        assert!(open(None, sealed).is_err());
+        assert!(ContentCipher::from_key("c2hvcnQ=").is_err());
+    }
+
+    #[test]
+    fn test_encrypt_existing_database() {
+        use crate::storage::{get_task_messages, insert_message, insert_task};
+        use crate::types::{ContentBlock, MessageBlock, TaskState};
+
+        let storage = Storage::in_memory().unwrap();
+        let conn = storage.connection().unwrap();
+        let task = TaskState::new(
+            "t1".to_string(),
+            "s1".to_string(),
+            "codex-cli".to_string(),
+            vec![ContentBlock::Text { text: "Review the billing code".to_string() }],
+            "/work".to_string(),
+        );
+        insert_task(&conn, None, &task).unwrap();
+        let message = MessageBlock::agent(vec![ContentBlock::Text { text: "proprietary".to_string() }]);
+        insert_message(&conn, None, &task.id, &message, 0).unwrap();
+        drop(conn);
+
+        let key = ContentCipher::generate_key();
+        assert_eq!(storage.enable_encryption(ContentCipher::from_key(&key).unwrap()).unwrap(), 2);
+        assert!(storage.is_encrypted().unwrap());
+
+        let conn = storage.connection().unwrap();
+        let raw: String = conn.query_row("SELECT content FROM messages", [], |row| row.get(0)).unwrap();
+        assert!(ContentCipher::is_sealed(&raw));
+        // Unreadable without the key, readable with it
+        assert!(get_task_messages(&conn, None, &task.id).is_err());
+        let cipher = storage.cipher();
+        assert_eq!(get_task_messages(&conn, cipher.as_deref(), &task.id).unwrap().len(), 1);
+        drop(conn);
+
+        storage.disable_encryption().unwrap();
+        assert!(!storage.is_encrypted().unwrap());
+        let conn = storage.connection().unwrap();
+        assert_eq!(get_task_messages(&conn, None, &task.id).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_encryption_covers_plans_commits_and_artifacts() {
+        use crate::storage::{get_task_artifacts, insert_artifact, insert_task, insert_task_commit, list_task_commits};
+        use crate::types::{Artifact, ArtifactSource, ContentBlock, TaskCommit, TaskState};
+
+        let storage = Storage::in_memory().unwrap();
+        let conn = storage.connection().unwrap();
+        let task = TaskState::new(
+            "t1".to_string(),
+            "s1".to_string(),
+            "codex-cli".to_string(),
+            vec![ContentBlock::Text { text: "Review the billing code".to_string() }],
+            "/work".to_string(),
+        );
+        insert_task(&conn, None, &task).unwrap();
+        let commit = TaskCommit {
+            hash: "a".repeat(40),
+            message: "Fix proprietary pricing".to_string(),
+            committed_at: chrono::Utc::now(),
+        };
+        insert_task_commit(&conn, None, &task.id, &commit).unwrap();
+        let artifact = Artifact::new_analysis_result(
+            task.id.clone(),
+            "Pricing lives in billing.rs".to_string(),
+            vec![],
+            ArtifactSource::from_semantic_extraction(),
+        );
+        insert_artifact(&conn, None, &artifact).unwrap();
+        conn.execute(
+            "INSERT INTO plan_snapshots (task_id, entries, captured_at) VALUES (?, ?, ?)",
+            params![task.id, r#"[{"content":"Raise prices"}]"#, chrono::Utc::now().to_rfc3339()],
+        )
+        .unwrap();
+        drop(conn);
+
+        // Existing rows are sealed along with the rest
+        storage.enable_encryption(ContentCipher::from_key(&ContentCipher::generate_key()).unwrap()).unwrap();
+        let conn = storage.connection().unwrap();
+        for query in [
+            "SELECT entries FROM plan_snapshots",
+            "SELECT message FROM task_commits",
+            "SELECT summary FROM artifacts",
+        ] {
+            let raw: String = conn.query_row(query, [], |row| row.get(0)).unwrap();
+            assert!(ContentCipher::is_sealed(&raw), "{}", query);
+        }
+        let cipher = storage.cipher();
+        assert_eq!(list_task_commits(&conn, cipher.as_deref(), &task.id).unwrap(), vec![commit]);
+        let artifacts = get_task_artifacts(&conn, cipher.as_deref(), &task.id).unwrap();
+        assert_eq!(artifacts[0].summary, artifact.summary);
+
+        // New rows are written sealed
+        let later = TaskCommit {
+            hash: "b".repeat(40),
+            message: "Document proprietary pricing".to_string(),
+            committed_at: chrono::Utc::now(),
+        };
+        insert_task_commit(&conn, cipher.as_deref(), &task.id, &later).unwrap();
+        let raw: String = conn
+            .query_row("SELECT message FROM task_commits WHERE hash = ?", [&later.hash], |row| row.get(0))
+            .unwrap();
+        assert!(ContentCipher::is_sealed(&raw));
+        assert_eq!(list_task_commits(&conn, cipher.as_deref(), &task.id).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_encryption_leaves_no_plaintext_on_disk() {
+        use crate::storage::{write_journal, JournalEntry};
+        use crate::storage::StorageConfig;
+        use crate::types::{ContentBlock, MessageBlock, TaskState};
+
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("cocowork.db");
+        let storage = Storage::from_path(db_path.clone(), StorageConfig::default()).unwrap();
+        let task = TaskState::new(
+            "t1".to_string(),
+            "s1".to_string(),
+            "codex-cli".to_string(),
+            vec![ContentBlock::Text { text: "Review the billing code".to_string() }],
+            "/work".to_string(),
+        );
+        let entry = JournalEntry {
+            task,
+            task_recorded: false,
+            first_seq: 0,
+            // Long enough to spill onto overflow pages, which are freed rather than overwritten
+            messages: vec![MessageBlock::agent(vec![ContentBlock::Text { text: "proprietary ".repeat(1000) }])],
+            updated_at: chrono::Utc::now(),
+        };
+        write_journal(&storage.connection().unwrap(), None, &entry).unwrap();
+        let backup = storage.create_backup().unwrap();
+
+        storage.enable_encryption(ContentCipher::from_key(&ContentCipher::generate_key()).unwrap()).unwrap();
+
+        let conn = storage.connection().unwrap();
+        let raw: String = conn.query_row("SELECT messages FROM stream_journal", [], |row| row.get(0)).unwrap();
+        assert!(ContentCipher::is_sealed(&raw));
+        drop(conn);
+        let backup_conn = Connection::open(&backup.path).unwrap();
+        let raw: String = backup_conn
+            .query_row("SELECT messages FROM stream_journal", [], |row| row.get(0))
+            .unwrap();
+        assert!(ContentCipher::is_sealed(&raw));
+        assert!(is_encryption_enabled(&backup_conn).unwrap());
+        drop(backup_conn);
+
+        // The old rows don't linger in free pages or the write-ahead log
+        drop(storage);
+        for path in [db_path.clone(), db_path.with_extension("db-wal"), backup.path] {
+            let bytes = std::fs::read(&path).unwrap_or_default();
+            assert!(!String::from_utf8_lossy(&bytes).contains("proprietary"), "{:?}", path);
+        }
+    }
+}
@@ -0,0 +1,211 @@
+//! Journal of turns still streaming
+//!
+//! A turn's messages are only saved once it finishes. While it runs, the app
+//! keeps the unsaved part of the thread in the `stream_journal` table,
+//! rewriting it every so often as chunks arrive, and clears it when the turn
+//! is saved. An entry still there when the database is next opened belongs
+//! to a turn the app never finished, e.g. because it crashed;
+//! [`recover_interrupted_turns`] saves what it had streamed so far.
+//!
+//! Only the process holding the writer lock recovers turns, so it can't take
+//! one another instance is still streaming.
+
+use super::crypto::{open_column, seal, ContentCipher};
+use super::{insert_message, insert_task, insert_tool_call, update_task_status, update_tool_call, Storage};
+use crate::error::Result;
+use crate::types::{MessageBlock, TaskState, TaskStatus};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use tracing::{info, warn};
+
+/// Error saved on a task whose turn was cut off
+pub const INTERRUPTED_MESSAGE: &str = "The app closed before the response finished";
+
+/// The unsaved part of a running turn
+#[derive(Debug, Clone)]
+pub struct JournalEntry {
+    /// The task as it stands, which names the session
+    pub task: TaskState,
+    /// Whether the task row has been saved already
+    pub task_recorded: bool,
+    /// Position in the thread of the first unsaved message
+    pub first_seq: usize,
+    /// Every message from `first_seq` on, the last one usually still streaming
+    pub messages: Vec<MessageBlock>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Store `entry`, replacing the session's previous one
+pub fn write_journal(conn: &Connection, cipher: Option<&ContentCipher>, entry: &JournalEntry) -> Result<()> {
+    conn.execute(
+        r#"
+        INSERT OR REPLACE INTO stream_journal (session_id, task, task_recorded, first_seq, messages, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+        params![
+            entry.task.session_id,
+            seal(cipher, serde_json::to_string(&entry.task)?)?,
+            entry.task_recorded,
+            entry.first_seq as i64,
+            seal(cipher, serde_json::to_string(&entry.messages)?)?,
+            entry.updated_at.to_rfc3339(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Drop a session's entry, once its turn is saved
+pub fn clear_journal(conn: &Connection, session_id: &str) -> Result<()> {
+    conn.execute("DELETE FROM stream_journal WHERE session_id = ?", params![session_id])?;
+    Ok(())
+}
+
+/// Every entry in the journal, oldest first
+pub fn list_journal(conn: &Connection, cipher: Option<&ContentCipher>) -> Result<Vec<JournalEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT task, task_recorded, first_seq, messages, updated_at FROM stream_journal ORDER BY updated_at",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            open_column(cipher, row.get(0)?, 0)?,
+            row.get::<_, bool>(1)?,
+            row.get::<_, i64>(2)?,
+            open_column(cipher, row.get(3)?, 3)?,
+            row.get::<_, String>(4)?,
+        ))
+    })?;
+
+    let mut entries = Vec::new();
+    for (task, task_recorded, first_seq, messages, updated_at) in rows.filter_map(|r| r.ok()) {
+        let (Ok(task), Ok(messages)) = (serde_json::from_str(&task), serde_json::from_str(&messages)) else {
+            warn!("Skipping unreadable stream journal entry");
+            continue;
+        };
+        entries.push(JournalEntry {
+            task,
+            task_recorded,
+            first_seq: first_seq.max(0) as usize,
+            messages,
+            updated_at: DateTime::parse_from_rfc3339(&updated_at)
+                .map(|at| at.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+        });
+    }
+    Ok(entries)
+}
+
+/// Save the partial output of turns that were still streaming when the app last closed
+///
+/// Each turn's task is marked as an error with [`INTERRUPTED_MESSAGE`] and its
+/// entry removed. Returns the recovered entries, so their threads can be
+/// opened again.
+pub fn recover_interrupted_turns(conn: &mut Connection, cipher: Option<&ContentCipher>) -> Result<Vec<JournalEntry>> {
+    let entries = list_journal(conn, cipher)?;
+    let tx = conn.transaction()?;
+    for entry in &entries {
+        if !entry.task_recorded {
+            insert_task(&tx, cipher, &entry.task)?;
+        }
+        for (offset, message) in entry.messages.iter().enumerate() {
+            insert_message(&tx, cipher, &entry.task.id, message, (entry.first_seq + offset) as i32)?;
+        }
+        for tool_call in entry.task.tool_calls.values() {
+            let recorded: bool = tx.query_row(
+                "SELECT COUNT(*) > 0 FROM tool_calls WHERE id = ?",
+                params![tool_call.id],
+                |row| row.get(0),
+            )?;
+            if !recorded {
+                insert_tool_call(&tx, cipher, &entry.task.id, tool_call)?;
+                update_tool_call(
+                    &tx,
+                    cipher,
+                    &tool_call.id,
+                    tool_call.status,
+                    tool_call.output.as_ref(),
+                    tool_call.completed_at,
+                )?;
+            }
+        }
+        update_task_status(
+            &tx,
+            &entry.task.id,
+            TaskStatus::Error,
+            entry.task.stop_reason,
+            Some(INTERRUPTED_MESSAGE),
+        )?;
+        clear_journal(&tx, &entry.task.session_id)?;
+    }
+    // Entries that couldn't be read can't be recovered either
+    tx.execute("DELETE FROM stream_journal", [])?;
+    tx.commit()?;
+    if !entries.is_empty() {
+        info!("Recovered {} interrupted turn(s)", entries.len());
+    }
+    Ok(entries)
+}
+
+impl Storage {
+    /// Recover the turns the last run left unfinished; none when read-only
+    pub fn recover_interrupted_turns(&self) -> Result<Vec<JournalEntry>> {
+        if self.is_read_only() {
+            return Ok(Vec::new());
+        }
+        let cipher = self.cipher();
+        recover_interrupted_turns(&mut *self.connection()?, cipher.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{get_task, get_task_messages, get_task_tool_calls, run_migrations};
+    use crate::types::{ContentBlock, ToolCallState};
+
+    fn text(text: &str) -> Vec<ContentBlock> {
+        vec![ContentBlock::Text { text: text.to_string() }]
+    }
+
+    #[test]
+    fn test_recover_interrupted_turn() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        let task = TaskState::new(
+            "t1".to_string(),
+            "s1".to_string(),
+            "codex-cli".to_string(),
+            Vec::new(),
+            "/work".to_string(),
+        );
+        let mut entry = JournalEntry {
+            task,
+            task_recorded: false,
+            first_seq: 0,
+            messages: vec![MessageBlock::user(text("Fix the build"))],
+            updated_at: Utc::now(),
+        };
+        write_journal(&conn, None, &entry).unwrap();
+        // Later writes replace the earlier ones
+        entry.messages.push(MessageBlock::agent(text("Looking at the fail")));
+        let tool_call = ToolCallState::new("tc1".to_string(), Some("cargo build".to_string()), None);
+        entry.task.tool_calls.insert(tool_call.id.clone(), tool_call);
+        write_journal(&conn, None, &entry).unwrap();
+        assert_eq!(list_journal(&conn, None).unwrap().len(), 1);
+
+        let recovered = recover_interrupted_turns(&mut conn, None).unwrap();
+        assert_eq!(recovered.len(), 1);
+        assert!(list_journal(&conn, None).unwrap().is_empty());
+
+        let messages = get_task_messages(&conn, None, "t1").unwrap();
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(&messages[1], MessageBlock::Agent { .. }));
+        let task = get_task(&conn, None, "t1").unwrap().unwrap();
+        assert_eq!(task.status, TaskStatus::Error);
+        assert_eq!(get_task_tool_calls(&conn, None, "t1").unwrap().len(), 1);
+
+        // Turns that finish leave nothing to recover
+        write_journal(&conn, None, &entry).unwrap();
+        clear_journal(&conn, "s1").unwrap();
+        assert!(recover_interrupted_turns(&mut conn, None).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,55 @@
+//! Single-writer lock on the database
+//!
+//! Several processes can open the same data directory: two app instances, or
+//! the app and `cocowork run`. Only the one holding the writer lock changes
+//! the database; the others open it read-only. The lock is an exclusive
+//! SQLite lock on a file beside the database, so the OS releases it when the
+//! holder exits, even if it crashed.
+
+use crate::error::Result;
+use rusqlite::{Connection, ErrorCode};
+use std::path::{Path, PathBuf};
+
+/// Whether this process may change the database
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageRole {
+    Writer,
+    /// Another process holds the writer lock; changes fail until it's taken over
+    ReadOnly,
+}
+
+/// Held by the one process allowed to write the database; released when dropped
+pub struct WriterLock {
+    _conn: Connection,
+}
+
+impl WriterLock {
+    /// Take the lock for the database at `db_path`, or `None` while another process holds it
+    pub fn try_acquire(db_path: &Path) -> Result<Option<Self>> {
+        let conn = Connection::open(lock_path(db_path))?;
+        // Exclusive locking mode keeps the lock from the first write until the connection closes
+        conn.pragma_update(None, "locking_mode", "EXCLUSIVE")?;
+        let taken = conn.execute_batch(&format!(
+            "BEGIN EXCLUSIVE;
+             CREATE TABLE IF NOT EXISTS writer (pid INTEGER NOT NULL);
+             DELETE FROM writer;
+             INSERT INTO writer (pid) VALUES ({});
+             COMMIT;",
+            std::process::id()
+        ));
+        match taken {
+            Ok(()) => Ok(Some(Self { _conn: conn })),
+            Err(rusqlite::Error::SqliteFailure(e, _))
+                if matches!(e.code, ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked) =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// The lock file for the database at `db_path`, e.g. `cocowork.lock` for `cocowork.db`
+pub fn lock_path(db_path: &Path) -> PathBuf {
+    db_path.with_extension("lock")
+}
@@ -1,9 +1,175 @@
 //! Database migrations
-
-use crate::error::Result;
+//!
+//! Migrations are numbered and applied in order, each in its own transaction.
+//! The schema version is kept in SQLite's `user_version` pragma; the
+//! `migrations` table keeps a readable history. Add a migration by appending
+//! to [`MIGRATIONS`] with the next version number — never edit or reorder
+//! one that has shipped.
+
+use crate::error::{Error, Result, StorageError};
 use rusqlite::Connection;
+use std::path::Path;
 use tracing::{debug, info};
 
+/// How a migration changes the schema
+pub enum MigrationStep {
+    Sql(&'static str),
+    /// For changes SQL alone can't express, e.g. rewriting JSON columns
+    Rust(fn(&Connection) -> Result<()>),
+}
+
+/// One numbered schema change
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub step: MigrationStep,
+}
+
+/// Every migration, in version order
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "001_initial",
+        step: MigrationStep::Sql(MIGRATION_001_INITIAL),
+    },
+    Migration {
+        version: 2,
+        name: "002_agents",
+        step: MigrationStep::Sql(MIGRATION_002_AGENTS),
+    },
+    Migration {
+        version: 3,
+        name: "003_settings",
+        step: MigrationStep::Sql(MIGRATION_003_SETTINGS),
+    },
+    Migration {
+        version: 4,
+        name: "004_env_profiles",
+        step: MigrationStep::Sql(MIGRATION_004_ENV_PROFILES),
+    },
+    Migration {
+        version: 5,
+        name: "005_prompt_templates",
+        step: MigrationStep::Sql(MIGRATION_005_PROMPT_TEMPLATES),
+    },
+    Migration {
+        version: 6,
+        name: "006_audit_log",
+        step: MigrationStep::Sql(MIGRATION_006_AUDIT_LOG),
+    },
+    Migration {
+        version: 7,
+        name: "007_audit_log_detail",
+        step: MigrationStep::Rust(migrate_audit_log_detail),
+    },
+    Migration {
+        version: 8,
+        name: "008_agent_processes",
+        step: MigrationStep::Sql(MIGRATION_008_AGENT_PROCESSES),
+    },
+    Migration {
+        version: 9,
+        name: "009_task_parents",
+        step: MigrationStep::Rust(migrate_task_parents),
+    },
+    Migration {
+        version: 10,
+        name: "010_metrics",
+        step: MigrationStep::Sql(MIGRATION_010_METRICS),
+    },
+    Migration {
+        version: 11,
+        name: "011_stream_journal",
+        step: MigrationStep::Sql(MIGRATION_011_STREAM_JOURNAL),
+    },
+    Migration {
+        version: 12,
+        name: "012_task_forks",
+        step: MigrationStep::Rust(migrate_task_forks),
+    },
+    Migration {
+        version: 13,
+        name: "013_message_pins",
+        step: MigrationStep::Rust(migrate_message_pins),
+    },
+    Migration {
+        version: 14,
+        name: "014_message_flags",
+        step: MigrationStep::Rust(migrate_message_flags),
+    },
+    Migration {
+        version: 15,
+        name: "015_task_commits",
+        step: MigrationStep::Sql(MIGRATION_015_TASK_COMMITS),
+    },
+    Migration {
+        version: 16,
+        name: "016_task_issues",
+        step: MigrationStep::Rust(migrate_task_issues),
+    },
+    Migration {
+        version: 17,
+        name: "017_agent_process_start_times",
+        step: MigrationStep::Rust(migrate_agent_process_start_times),
+    },
+];
+
+/// Schema version this build expects
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+/// Schema version of the open database
+///
+/// Databases from before versioning have `user_version` 0 but list what ran
+/// in the `migrations` table; their version is the run of known migrations
+/// recorded there.
+pub fn schema_version(conn: &Connection) -> Result<u32> {
+    let version: u32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version > 0 {
+        return Ok(version);
+    }
+
+    let has_history: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'migrations'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_history {
+        return Ok(0);
+    }
+    let mut version = 0;
+    for migration in MIGRATIONS {
+        if !migration_applied(conn, migration.name)? {
+            break;
+        }
+        version = migration.version;
+    }
+    Ok(version)
+}
+
+/// Migrations that `run_migrations` would apply, without applying them
+pub fn pending_migrations(conn: &Connection) -> Result<Vec<&'static Migration>> {
+    let version = schema_version(conn)?;
+    if version > latest_version() {
+        return Err(Error::Storage(StorageError::MigrationFailed(format!(
+            "database schema v{} is newer than this version of CocoWork supports (v{})",
+            version,
+            latest_version()
+        ))));
+    }
+    Ok(MIGRATIONS.iter().filter(|m| m.version > version).collect())
+}
+
+/// Copy the database to `path` in a consistent state
+pub fn backup_database(conn: &Connection, path: &Path) -> Result<()> {
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    conn.execute("VACUUM INTO ?", [path.to_string_lossy()])?;
+    Ok(())
+}
+
 /// Run all database migrations
 pub fn run_migrations(conn: &Connection) -> Result<()> {
     info!("Running database migrations");
@@ -22,23 +188,28 @@ pub fn run_migrations(conn: &Connection) -> Result<()> {
         "#,
     )?;
 
-    // Run migrations in order
-    let migrations: Vec<(&str, &str)> = vec![
-        ("001_initial", MIGRATION_001_INITIAL),
-        ("002_agents", MIGRATION_002_AGENTS),
-        ("003_settings", MIGRATION_003_SETTINGS),
-    ];
-
-    for (name, sql) in migrations {
-        if !migration_applied(conn, name)? {
-            debug!("Applying migration: {}", name);
-            conn.execute_batch(sql)?;
-            mark_migration_applied(conn, name)?;
-            info!("Applied migration: {}", name);
+    for migration in pending_migrations(conn)? {
+        debug!("Applying migration: {}", migration.name);
+        let tx = conn.unchecked_transaction()?;
+        match &migration.step {
+            MigrationStep::Sql(sql) => tx.execute_batch(sql)?,
+            MigrationStep::Rust(apply) => apply(&tx)?,
         }
+        if !migration_applied(&tx, migration.name)? {
+            mark_migration_applied(&tx, migration.name)?;
+        }
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit().map_err(|e| {
+            Error::Storage(StorageError::MigrationFailed(format!("{}: {}", migration.name, e)))
+        })?;
+        info!("Applied migration: {}", migration.name);
     }
 
-    info!("All migrations completed");
+    // Databases adopted from before versioning have nothing pending but no version yet
+    let version = schema_version(conn)?;
+    conn.pragma_update(None, "user_version", version)?;
+
+    info!("All migrations completed (schema v{})", version);
     Ok(())
 }
 
@@ -215,6 +386,168 @@ CREATE TABLE IF NOT EXISTS granted_paths (
 );
 "#;
 
+const MIGRATION_004_ENV_PROFILES: &str = r#"
+-- Per-workspace environment profiles for agents
+CREATE TABLE IF NOT EXISTS env_profiles (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    workspace TEXT NOT NULL,
+    env TEXT NOT NULL,
+    path_prepend TEXT NOT NULL,
+    is_default INTEGER DEFAULT 0,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_env_profiles_workspace ON env_profiles(workspace, name);
+"#;
+
+const MIGRATION_005_PROMPT_TEMPLATES: &str = r#"
+-- Saved prompt templates
+CREATE TABLE IF NOT EXISTS prompt_templates (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    body TEXT NOT NULL,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL
+);
+"#;
+
+const MIGRATION_006_AUDIT_LOG: &str = r#"
+-- Append-only record of agent operations
+CREATE TABLE IF NOT EXISTS audit_log (
+    id TEXT PRIMARY KEY,
+    session_id TEXT NOT NULL,
+    operation TEXT NOT NULL,
+    target TEXT NOT NULL,
+    outcome TEXT NOT NULL,
+    created_at DATETIME NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_session ON audit_log(session_id, created_at);
+"#;
+
+/// Add the audit log's detail column, which can't be done with `IF NOT EXISTS`
+fn migrate_audit_log_detail(conn: &Connection) -> Result<()> {
+    if conn.prepare("SELECT detail FROM audit_log LIMIT 0").is_err() {
+        conn.execute_batch("ALTER TABLE audit_log ADD COLUMN detail TEXT;")?;
+    }
+    conn.execute_batch(MIGRATION_007_AUDIT_LOG_APPEND_ONLY)?;
+    Ok(())
+}
+
+const MIGRATION_007_AUDIT_LOG_APPEND_ONLY: &str = r#"
+CREATE INDEX IF NOT EXISTS idx_audit_log_created ON audit_log(created_at);
+
+-- Entries are never changed or removed once written
+CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'audit log is append-only');
+END;
+
+CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'audit log is append-only');
+END;
+"#;
+
+const MIGRATION_008_AGENT_PROCESSES: &str = r#"
+-- Agent processes started by the app, so a crashed run's leftovers can be stopped
+CREATE TABLE IF NOT EXISTS agent_processes (
+    pid INTEGER PRIMARY KEY,
+    agent_id TEXT NOT NULL,
+    command TEXT NOT NULL,
+    started_at DATETIME NOT NULL
+);
+"#;
+
+/// Link tasks to the session they were spawned from as sub-threads
+fn migrate_task_parents(conn: &Connection) -> Result<()> {
+    if conn.prepare("SELECT parent_session_id FROM tasks LIMIT 0").is_err() {
+        conn.execute_batch("ALTER TABLE tasks ADD COLUMN parent_session_id TEXT;")?;
+    }
+    conn.execute_batch("CREATE INDEX IF NOT EXISTS idx_tasks_parent ON tasks(parent_session_id);")?;
+    Ok(())
+}
+
+const MIGRATION_010_METRICS: &str = r#"
+-- Local usage metrics, recorded once the user opts in
+CREATE TABLE IF NOT EXISTS metrics (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    agent_id TEXT,
+    value REAL NOT NULL,
+    recorded_at DATETIME NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_metrics_recorded ON metrics(recorded_at);
+"#;
+
+const MIGRATION_011_STREAM_JOURNAL: &str = r#"
+-- Unsaved part of each turn still streaming, for recovery after a crash
+CREATE TABLE IF NOT EXISTS stream_journal (
+    session_id TEXT PRIMARY KEY,
+    task TEXT NOT NULL,
+    task_recorded INTEGER NOT NULL DEFAULT 0,
+    first_seq INTEGER NOT NULL,
+    messages TEXT NOT NULL,
+    updated_at DATETIME NOT NULL
+);
+"#;
+
+/// Record how many of the parent's messages a forked thread started with
+fn migrate_task_forks(conn: &Connection) -> Result<()> {
+    if conn.prepare("SELECT forked_at FROM tasks LIMIT 0").is_err() {
+        conn.execute_batch("ALTER TABLE tasks ADD COLUMN forked_at INTEGER;")?;
+    }
+    Ok(())
+}
+
+/// Let messages be pinned within their thread
+fn migrate_message_pins(conn: &Connection) -> Result<()> {
+    if conn.prepare("SELECT pinned FROM messages LIMIT 0").is_err() {
+        conn.execute_batch("ALTER TABLE messages ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;")?;
+    }
+    Ok(())
+}
+
+/// Let messages carry review flags, stored as a comma-separated list
+fn migrate_message_flags(conn: &Connection) -> Result<()> {
+    if conn.prepare("SELECT flags FROM messages LIMIT 0").is_err() {
+        conn.execute_batch("ALTER TABLE messages ADD COLUMN flags TEXT NOT NULL DEFAULT '';")?;
+    }
+    Ok(())
+}
+
+const MIGRATION_015_TASK_COMMITS: &str = r#"
+-- Git commits made from a thread's changes
+CREATE TABLE IF NOT EXISTS task_commits (
+    task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    hash TEXT NOT NULL,
+    message TEXT NOT NULL,
+    committed_at DATETIME NOT NULL,
+    PRIMARY KEY (task_id, hash)
+);
+"#;
+
+/// Link threads to the issue they were started from
+fn migrate_task_issues(conn: &Connection) -> Result<()> {
+    if conn.prepare("SELECT issue_url FROM tasks LIMIT 0").is_err() {
+        conn.execute_batch("ALTER TABLE tasks ADD COLUMN issue_url TEXT;")?;
+    }
+    Ok(())
+}
+
+/// Record when each agent process started, to tell it from a later process
+/// that reuses its PID
+fn migrate_agent_process_start_times(conn: &Connection) -> Result<()> {
+    if conn.prepare("SELECT process_started FROM agent_processes LIMIT 0").is_err() {
+        conn.execute_batch("ALTER TABLE agent_processes ADD COLUMN process_started TEXT NOT NULL DEFAULT '';")?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -239,6 +572,9 @@ mod tests {
         assert!(tables.contains(&"artifacts".to_string()));
         assert!(tables.contains(&"agents".to_string()));
         assert!(tables.contains(&"settings".to_string()));
+        assert!(tables.contains(&"env_profiles".to_string()));
+        assert!(tables.contains(&"prompt_templates".to_string()));
+        assert!(tables.contains(&"audit_log".to_string()));
     }
 
     #[test]
@@ -254,6 +590,42 @@ mod tests {
             .query_row("SELECT COUNT(*) FROM migrations", [], |row| row.get(0))
             .unwrap();
 
-        assert_eq!(count, 3); // 3 migrations
+        assert_eq!(count, 17); // 17 migrations
+    }
+
+    #[test]
+    fn test_schema_version_tracked_in_user_version() {
+        let conn = Connection::open_in_memory().unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), 0);
+        assert_eq!(pending_migrations(&conn).unwrap().len(), MIGRATIONS.len());
+
+        run_migrations(&conn).unwrap();
+        let user_version: u32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(user_version, latest_version());
+        assert!(pending_migrations(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_unversioned_database_is_adopted() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+
+        // Looks like a database from before user_version was set
+        conn.pragma_update(None, "user_version", 0).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), latest_version());
+
+        run_migrations(&conn).unwrap();
+        let user_version: u32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(user_version, latest_version());
+    }
+
+    #[test]
+    fn test_newer_database_is_refused() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        conn.pragma_update(None, "user_version", latest_version() + 1).unwrap();
+
+        assert!(pending_migrations(&conn).is_err());
+        assert!(run_migrations(&conn).is_err());
     }
 }
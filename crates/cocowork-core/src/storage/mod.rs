@@ -4,18 +4,43 @@
 //! - Database initialization and migrations
 //! - CRUD operations for tasks, messages, artifacts, etc.
 //! - Connection pooling
+//! - A background writer that batches inserts off the UI thread
+//! - Scheduled backups and restore
+//! - Optional encryption of conversation content
+//! - A lock so only one process writes a database at a time
 
+mod backup;
+mod config;
+mod crypto;
+mod journal;
+mod lock;
 mod migrations;
 mod queries;
+mod writer;
 
-pub use migrations::run_migrations;
+pub use backup::{
+    list_backups_in, restore_backup, run_scheduled_backups, staged_restore_path, BackupInfo, BackupPolicy,
+};
+pub use config::{JournalMode, StorageConfig, Synchronous};
+pub use crypto::{is_encryption_enabled, reseal_content, ContentCipher};
+pub use journal::{
+    clear_journal, list_journal, recover_interrupted_turns, write_journal, JournalEntry, INTERRUPTED_MESSAGE,
+};
+pub use lock::{lock_path, StorageRole, WriterLock};
+pub use migrations::{
+    backup_database, latest_version, pending_migrations, run_migrations, schema_version, Migration,
+    MigrationStep, MIGRATIONS,
+};
 pub use queries::*;
+pub use writer::{StorageWrite, StorageWriter, WriterConfig};
 
 use crate::error::{Error, Result, StorageError};
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
 use std::path::{Path, PathBuf};
-use tracing::info;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use tracing::{info, warn};
 
 /// Database connection pool type
 pub type DbPool = Pool<SqliteConnectionManager>;
@@ -24,11 +49,24 @@ pub type DbPool = Pool<SqliteConnectionManager>;
 pub struct Storage {
     pool: DbPool,
     db_path: PathBuf,
+    config: StorageConfig,
+    /// Seals conversation content when encryption is on
+    cipher: RwLock<Option<Arc<ContentCipher>>>,
+    /// Held while this process is the database's writer
+    writer_lock: Mutex<Option<WriterLock>>,
+    /// Whether connections are opened `query_only`, as another process is the writer
+    read_only: Arc<AtomicBool>,
 }
 
 impl Storage {
     /// Create a new storage instance with a directory path
     pub fn new_with_path(data_dir: impl AsRef<Path>) -> Result<Self> {
+        Self::new_with_config(data_dir, StorageConfig::default())
+    }
+
+    /// Create a storage instance in a directory, with connection settings
+    /// e.g. from the app's [`Config`](crate::config::Config)
+    pub fn new_with_config(data_dir: impl AsRef<Path>, config: StorageConfig) -> Result<Self> {
         let data_dir = data_dir.as_ref();
 
         // Create directory if it doesn't exist
@@ -41,22 +79,56 @@ impl Storage {
 
         let db_path = data_dir.join("cocowork.db");
         info!("Database path: {:?}", db_path);
+        let lock = WriterLock::try_acquire(&db_path)?;
+
+        // A restore picked in settings is swapped in before anything opens the file,
+        // unless another process has it open
+        let staged = staged_restore_path(&db_path);
+        if staged.exists() && lock.is_some() {
+            restore_backup(&db_path, &staged)?;
+            std::fs::remove_file(&staged)?;
+        }
 
-        Self::from_path(db_path)
+        Self::open(db_path, config, lock)
     }
 
-    /// Create storage from a specific path (useful for testing)
-    pub fn from_path(db_path: PathBuf) -> Result<Self> {
-        let manager = SqliteConnectionManager::file(&db_path);
+    /// Create storage from a specific database file and connection settings
+    ///
+    /// Opens read-only if another process holds the writer lock. Fails with
+    /// [`StorageError::Corrupt`] if the file exists but SQLite can't read it.
+    pub fn from_path(db_path: PathBuf, config: StorageConfig) -> Result<Self> {
+        let lock = WriterLock::try_acquire(&db_path)?;
+        Self::open(db_path, config, lock)
+    }
+
+    fn open(db_path: PathBuf, config: StorageConfig, lock: Option<WriterLock>) -> Result<Self> {
+        check_database_file(&db_path)?;
+        let read_only = Arc::new(AtomicBool::new(lock.is_none()));
+        let query_only = Arc::clone(&read_only);
+        let manager = SqliteConnectionManager::file(&db_path).with_init(move |conn| {
+            config.apply(conn)?;
+            conn.pragma_update(None, "query_only", query_only.load(Ordering::SeqCst))
+        });
         let pool = Pool::builder()
-            .max_size(10)
+            .max_size(config.pool_size.max(1))
             .build(manager)
             .map_err(|e| Error::Storage(StorageError::Pool(e.to_string())))?;
 
-        let storage = Self { pool, db_path };
+        let storage = Self {
+            pool,
+            db_path,
+            config,
+            cipher: RwLock::new(None),
+            writer_lock: Mutex::new(lock),
+            read_only,
+        };
 
-        // Run migrations
-        storage.initialize()?;
+        // The writer brings the schema up to date; readers use it as it is
+        if storage.role() == StorageRole::Writer {
+            storage.initialize()?;
+        } else {
+            warn!("Another process is writing {:?}; opening it read-only", storage.db_path);
+        }
 
         Ok(storage)
     }
@@ -72,6 +144,12 @@ impl Storage {
         let storage = Self {
             pool,
             db_path: PathBuf::from(":memory:"),
+            config: StorageConfig::default()
+                .with_journal_mode(JournalMode::Memory)
+                .with_pool_size(1),
+            cipher: RwLock::new(None),
+            writer_lock: Mutex::new(None),
+            read_only: Arc::new(AtomicBool::new(false)),
         };
 
         storage.initialize()?;
@@ -79,21 +157,65 @@ impl Storage {
         Ok(storage)
     }
 
+    /// Whether this process writes the database or only reads it
+    pub fn role(&self) -> StorageRole {
+        if self.read_only.load(Ordering::SeqCst) {
+            StorageRole::ReadOnly
+        } else {
+            StorageRole::Writer
+        }
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.role() == StorageRole::ReadOnly
+    }
+
+    /// Become the writer if the process that was has let go of the lock
+    ///
+    /// Returns whether this process is now the writer.
+    pub fn try_take_writer_role(&self) -> Result<bool> {
+        if !self.is_read_only() {
+            return Ok(true);
+        }
+        let Some(lock) = WriterLock::try_acquire(&self.db_path)? else {
+            return Ok(false);
+        };
+        *self.writer_lock.lock().unwrap_or_else(|e| e.into_inner()) = Some(lock);
+        self.read_only.store(false, Ordering::SeqCst);
+        self.initialize()?;
+        info!("Took over writing {:?}", self.db_path);
+        Ok(true)
+    }
+
     /// Initialize database with migrations
+    ///
+    /// A database that already has data is copied aside before its schema
+    /// changes, e.g. `cocowork.db` to `cocowork.v3.bak`.
     fn initialize(&self) -> Result<()> {
-        let conn = self.pool.get()?;
+        let conn = self.connection()?;
+        let version = schema_version(&conn)?;
+        if version > 0 && !pending_migrations(&conn)?.is_empty() && self.db_path.exists() {
+            let backup = self.db_path.with_extension(format!("v{}.bak", version));
+            info!("Backing up database to {:?} before migrating", backup);
+            backup_database(&conn, &backup)?;
+        }
         run_migrations(&conn)?;
         info!("Database initialized successfully");
         Ok(())
     }
 
     /// Get a connection from the pool
+    ///
+    /// The connection refuses changes while the storage is read-only.
     pub fn connection(
         &self,
     ) -> Result<r2d2::PooledConnection<SqliteConnectionManager>> {
-        self.pool.get().map_err(|e| {
+        let conn = self.pool.get().map_err(|e| {
             Error::Storage(StorageError::Pool(e.to_string()))
-        })
+        })?;
+        // Pooled connections may predate taking over as the writer
+        conn.pragma_update(None, "query_only", self.is_read_only())?;
+        Ok(conn)
     }
 
     /// Get the database path
@@ -105,6 +227,50 @@ impl Storage {
     pub fn pool(&self) -> &DbPool {
         &self.pool
     }
+
+    /// Settings the connections were opened with
+    pub fn config(&self) -> &StorageConfig {
+        &self.config
+    }
+
+    /// Rebuild the database file to reclaim space left by deleted rows
+    pub fn vacuum(&self) -> Result<()> {
+        let conn = self.connection()?;
+        conn.execute_batch("VACUUM")?;
+        if self.config.journal_mode == JournalMode::Wal {
+            conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE)")?;
+        }
+        info!("Database vacuumed");
+        Ok(())
+    }
+
+    /// Run SQLite's integrity check, returning the problems it finds
+    ///
+    /// An empty list means the database is healthy.
+    pub fn integrity_check(&self) -> Result<Vec<String>> {
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+        let rows = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(rows.into_iter().filter(|row| row != "ok").collect())
+    }
+}
+
+/// Make sure an existing database file is readable before pooling connections to it
+fn check_database_file(db_path: &Path) -> Result<()> {
+    if !db_path.exists() {
+        return Ok(());
+    }
+    let corrupt = |message: String| Error::Storage(StorageError::Corrupt(message));
+    let conn = rusqlite::Connection::open(db_path).map_err(|e| corrupt(e.to_string()))?;
+    let result: String = conn
+        .query_row("PRAGMA quick_check", [], |row| row.get(0))
+        .map_err(|e| corrupt(e.to_string()))?;
+    if result != "ok" {
+        return Err(corrupt(result));
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -116,4 +282,66 @@ mod tests {
         let storage = Storage::in_memory().unwrap();
         assert!(storage.connection().is_ok());
     }
+
+    #[test]
+    fn test_file_storage_uses_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = StorageConfig::default().with_pool_size(2);
+        let storage = Storage::from_path(dir.path().join("test.db"), config).unwrap();
+
+        let conn = storage.connection().unwrap();
+        let journal_mode: String = conn.query_row("PRAGMA journal_mode", [], |row| row.get(0)).unwrap();
+        assert_eq!(journal_mode, "wal");
+        let synchronous: i64 = conn.query_row("PRAGMA synchronous", [], |row| row.get(0)).unwrap();
+        assert_eq!(synchronous, 1);
+        drop(conn);
+
+        assert!(storage.integrity_check().unwrap().is_empty());
+        storage.vacuum().unwrap();
+    }
+
+    #[test]
+    fn test_backup_before_migrating() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("cocowork.db");
+        drop(Storage::from_path(db_path.clone(), StorageConfig::default()).unwrap());
+
+        // Pretend the file was written by an older version
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        conn.execute("DELETE FROM migrations WHERE name = '005_prompt_templates'", []).unwrap();
+        conn.pragma_update(None, "user_version", 4).unwrap();
+        drop(conn);
+
+        drop(Storage::from_path(db_path.clone(), StorageConfig::default()).unwrap());
+        assert!(dir.path().join("cocowork.v4.bak").exists());
+    }
+
+    #[test]
+    fn test_second_process_is_read_only_until_writer_leaves() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("cocowork.db");
+        let writer = Storage::from_path(db_path.clone(), StorageConfig::default()).unwrap();
+        assert_eq!(writer.role(), StorageRole::Writer);
+        set_setting(&writer.connection().unwrap(), "theme", "dark").unwrap();
+
+        let reader = Storage::from_path(db_path, StorageConfig::default()).unwrap();
+        assert_eq!(reader.role(), StorageRole::ReadOnly);
+        assert_eq!(get_setting(&reader.connection().unwrap(), "theme").unwrap().as_deref(), Some("dark"));
+        assert!(set_setting(&reader.connection().unwrap(), "theme", "light").is_err());
+        assert!(!reader.try_take_writer_role().unwrap());
+
+        drop(writer);
+        assert!(reader.try_take_writer_role().unwrap());
+        set_setting(&reader.connection().unwrap(), "theme", "light").unwrap();
+    }
+
+    #[test]
+    fn test_unreadable_database_is_corrupt() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("cocowork.db");
+        std::fs::write(&db_path, "not a database, just some text that is long enough").unwrap();
+
+        let err = Storage::from_path(db_path, StorageConfig::default()).err().unwrap();
+        assert!(err.is_storage_corrupt());
+    }
 }
@@ -1,13 +1,15 @@
 //! Database query implementations
 
+use super::crypto::{open_column, seal, ContentCipher};
 use crate::error::Result;
 use crate::types::*;
 use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{BTreeMap, BTreeSet};
 
 // ===== Task Queries =====
 
-/// Insert a new task
-pub fn insert_task(conn: &Connection, state: &TaskState) -> Result<()> {
+/// Insert a new task, sealing the prompt if there's a cipher
+pub fn insert_task(conn: &Connection, cipher: Option<&ContentCipher>, state: &TaskState) -> Result<()> {
     let prompt_text: String = state
         .prompt
         .iter()
@@ -20,18 +22,24 @@ pub fn insert_task(conn: &Connection, state: &TaskState) -> Result<()> {
 
     conn.execute(
         r#"
-        INSERT INTO tasks (id, session_id, agent_id, status, prompt_text, working_dir, created_at, updated_at)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        INSERT INTO tasks (
+            id, session_id, agent_id, status, prompt_text, working_dir, created_at, updated_at, parent_session_id,
+            forked_at, issue_url
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
         params![
             state.id,
             state.session_id,
             state.agent_id,
             format!("{:?}", state.status).to_lowercase(),
-            prompt_text,
+            seal(cipher, prompt_text)?,
             state.working_directory,
             state.created_at.to_rfc3339(),
             state.updated_at.to_rfc3339(),
+            state.parent_session_id,
+            state.forked_at.map(|count| count as i64),
+            state.issue_url,
         ],
     )?;
 
@@ -71,36 +79,44 @@ pub fn update_task_status(
     Ok(())
 }
 
+/// Columns read into a [`TaskSummary`] by [`task_summary`]
+const TASK_SUMMARY_COLUMNS: &str = r#"
+    id, session_id, agent_id, status, prompt_text, created_at, updated_at,
+    (SELECT COUNT(*) FROM artifacts WHERE task_id = tasks.id) as artifact_count,
+    (SELECT COUNT(*) FROM file_changes WHERE task_id = tasks.id) as file_change_count,
+    working_dir, parent_session_id, forked_at, issue_url
+"#;
+
+fn task_summary(row: &rusqlite::Row, cipher: Option<&ContentCipher>) -> rusqlite::Result<TaskSummary> {
+    Ok(TaskSummary {
+        id: row.get(0)?,
+        session_id: row.get(1)?,
+        agent_id: row.get(2)?,
+        agent_name: row.get(2)?, // Same as agent_id for now
+        prompt_preview: open_column(cipher, row.get(4)?, 4)?.chars().take(100).collect(),
+        status: parse_task_status(&row.get::<_, String>(3)?),
+        working_dir: row.get(9)?,
+        artifact_count: row.get(7)?,
+        file_change_count: row.get(8)?,
+        created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
+            .unwrap()
+            .with_timezone(&chrono::Utc),
+        updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(6)?)
+            .unwrap()
+            .with_timezone(&chrono::Utc),
+        parent_session_id: row.get(10)?,
+        forked_at: row.get::<_, Option<i64>>(11)?.map(|count| count as usize),
+        issue_url: row.get(12)?,
+    })
+}
+
 /// Get task by ID
-pub fn get_task(conn: &Connection, task_id: &str) -> Result<Option<TaskSummary>> {
+pub fn get_task(conn: &Connection, cipher: Option<&ContentCipher>, task_id: &str) -> Result<Option<TaskSummary>> {
     let result = conn
         .query_row(
-            r#"
-            SELECT id, session_id, agent_id, status, prompt_text, created_at, updated_at,
-                   (SELECT COUNT(*) FROM artifacts WHERE task_id = tasks.id) as artifact_count,
-                   (SELECT COUNT(*) FROM file_changes WHERE task_id = tasks.id) as file_change_count
-            FROM tasks
-            WHERE id = ?
-            "#,
+            &format!("SELECT {} FROM tasks WHERE id = ?", TASK_SUMMARY_COLUMNS),
             params![task_id],
-            |row| {
-                Ok(TaskSummary {
-                    id: row.get(0)?,
-                    session_id: row.get(1)?,
-                    agent_id: row.get::<_, String>(2)?.clone(),
-                    agent_name: row.get::<_, String>(2)?, // Same as agent_id for now
-                    prompt_preview: row.get::<_, String>(4)?.chars().take(100).collect(),
-                    status: parse_task_status(&row.get::<_, String>(3)?),
-                    artifact_count: row.get(7)?,
-                    file_change_count: row.get(8)?,
-                    created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
-                        .unwrap()
-                        .with_timezone(&chrono::Utc),
-                    updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(6)?)
-                        .unwrap()
-                        .with_timezone(&chrono::Utc),
-                })
-            },
+            |row| task_summary(row, cipher),
         )
         .optional()?;
 
@@ -108,43 +124,61 @@ pub fn get_task(conn: &Connection, task_id: &str) -> Result<Option<TaskSummary>>
 }
 
 /// List tasks with pagination
-pub fn list_tasks(conn: &Connection, limit: usize, offset: usize) -> Result<Vec<TaskSummary>> {
-    let mut stmt = conn.prepare(
-        r#"
-        SELECT id, session_id, agent_id, status, prompt_text, created_at, updated_at,
-               (SELECT COUNT(*) FROM artifacts WHERE task_id = tasks.id) as artifact_count,
-               (SELECT COUNT(*) FROM file_changes WHERE task_id = tasks.id) as file_change_count
-        FROM tasks
-        ORDER BY updated_at DESC
-        LIMIT ? OFFSET ?
-        "#,
-    )?;
+pub fn list_tasks(
+    conn: &Connection,
+    cipher: Option<&ContentCipher>,
+    limit: usize,
+    offset: usize,
+) -> Result<Vec<TaskSummary>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM tasks ORDER BY updated_at DESC LIMIT ? OFFSET ?",
+        TASK_SUMMARY_COLUMNS
+    ))?;
 
     let tasks = stmt
-        .query_map(params![limit as i64, offset as i64], |row| {
-            Ok(TaskSummary {
-                id: row.get(0)?,
-                session_id: row.get(1)?,
-                agent_id: row.get::<_, String>(2)?.clone(),
-                agent_name: row.get::<_, String>(2)?,
-                prompt_preview: row.get::<_, String>(4)?.chars().take(100).collect(),
-                status: parse_task_status(&row.get::<_, String>(3)?),
-                artifact_count: row.get(7)?,
-                file_change_count: row.get(8)?,
-                created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-                updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(6)?)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-            })
-        })?
+        .query_map(params![limit as i64, offset as i64], |row| task_summary(row, cipher))?
         .filter_map(|r| r.ok())
         .collect();
 
     Ok(tasks)
 }
 
+/// Tasks whose ID or session ID starts with `prefix`, newest first
+///
+/// Lets a task be picked by the first few characters of either ID, as the
+/// command line does.
+pub fn find_tasks(conn: &Connection, cipher: Option<&ContentCipher>, prefix: &str) -> Result<Vec<TaskSummary>> {
+    let pattern = format!("{}%", prefix.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+    let mut stmt = conn.prepare(&format!(
+        r"SELECT {} FROM tasks WHERE id LIKE ?1 ESCAPE '\' OR session_id LIKE ?1 ESCAPE '\' ORDER BY updated_at DESC",
+        TASK_SUMMARY_COLUMNS
+    ))?;
+
+    let tasks = stmt
+        .query_map(params![pattern], |row| task_summary(row, cipher))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(tasks)
+}
+
+/// Tasks spawned as sub-threads of `parent_session_id`, oldest first
+pub fn list_subtasks(
+    conn: &Connection,
+    cipher: Option<&ContentCipher>,
+    parent_session_id: &str,
+) -> Result<Vec<TaskSummary>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM tasks WHERE parent_session_id = ? ORDER BY created_at",
+        TASK_SUMMARY_COLUMNS
+    ))?;
+
+    let tasks = stmt
+        .query_map(params![parent_session_id], |row| task_summary(row, cipher))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(tasks)
+}
+
 /// Delete a task and all related data
 pub fn delete_task(conn: &Connection, task_id: &str) -> Result<()> {
     conn.execute("DELETE FROM tasks WHERE id = ?", params![task_id])?;
@@ -153,9 +187,10 @@ pub fn delete_task(conn: &Connection, task_id: &str) -> Result<()> {
 
 // ===== Message Queries =====
 
-/// Insert a message
+/// Insert a message, sealing its content if there's a cipher
 pub fn insert_message(
     conn: &Connection,
+    cipher: Option<&ContentCipher>,
     task_id: &str,
     message: &MessageBlock,
     seq_order: i32,
@@ -171,6 +206,7 @@ pub fn insert_message(
             ("thought", "content_blocks", serde_json::to_string(content)?)
         }
         MessageBlock::System { content, .. } => ("system", "text", content.clone()),
+        MessageBlock::Summary { content, .. } => ("summary", "text", content.clone()),
     };
 
     conn.execute(
@@ -182,7 +218,7 @@ pub fn insert_message(
             task_id,
             role,
             content_type,
-            content,
+            seal(cipher, content)?,
             seq_order,
             message.timestamp().to_rfc3339(),
         ],
@@ -192,7 +228,13 @@ pub fn insert_message(
 }
 
 /// Get messages for a task
-pub fn get_task_messages(conn: &Connection, task_id: &str) -> Result<Vec<MessageBlock>> {
+///
+/// Fails if a message is sealed and `cipher` can't open it.
+pub fn get_task_messages(
+    conn: &Connection,
+    cipher: Option<&ContentCipher>,
+    task_id: &str,
+) -> Result<Vec<MessageBlock>> {
     let mut stmt = conn.prepare(
         r#"
         SELECT role, content_type, content, created_at
@@ -206,7 +248,7 @@ pub fn get_task_messages(conn: &Connection, task_id: &str) -> Result<Vec<Message
         .query_map(params![task_id], |row| {
             let role: String = row.get(0)?;
             let content_type: String = row.get(1)?;
-            let content: String = row.get(2)?;
+            let content = open_column(cipher, row.get(2)?, 2)?;
             let created_at: String = row.get(3)?;
 
             let timestamp = chrono::DateTime::parse_from_rfc3339(&created_at)
@@ -227,6 +269,7 @@ pub fn get_task_messages(conn: &Connection, task_id: &str) -> Result<Vec<Message
                     timestamp,
                 },
                 ("system", _) => MessageBlock::System { content, timestamp },
+                ("summary", _) => MessageBlock::Summary { content, timestamp },
                 _ => MessageBlock::System {
                     content: "Unknown message type".to_string(),
                     timestamp,
@@ -235,16 +278,149 @@ pub fn get_task_messages(conn: &Connection, task_id: &str) -> Result<Vec<Message
 
             Ok(message)
         })?
-        .filter_map(|r| r.ok())
-        .collect();
+        .collect::<rusqlite::Result<Vec<_>>>()?;
 
     Ok(messages)
 }
 
+/// Pin or unpin the task's message at `seq_order`
+pub fn set_message_pinned(conn: &Connection, task_id: &str, seq_order: i32, pinned: bool) -> Result<()> {
+    conn.execute(
+        "UPDATE messages SET pinned = ? WHERE task_id = ? AND seq_order = ?",
+        params![pinned, task_id, seq_order],
+    )?;
+    Ok(())
+}
+
+/// Positions of the task's pinned messages, in order
+pub fn list_pinned_messages(conn: &Connection, task_id: &str) -> Result<Vec<usize>> {
+    let mut stmt = conn.prepare("SELECT seq_order FROM messages WHERE task_id = ? AND pinned = 1 ORDER BY seq_order")?;
+    let pinned = stmt
+        .query_map(params![task_id], |row| row.get::<_, i64>(0))?
+        .map(|seq| seq.map(|seq| seq as usize))
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(pinned)
+}
+
+/// Replace the flags on the task's message at `seq_order`
+pub fn set_message_flags(
+    conn: &Connection,
+    task_id: &str,
+    seq_order: i32,
+    flags: &BTreeSet<MessageFlag>,
+) -> Result<()> {
+    let flags = flags.iter().map(MessageFlag::as_str).collect::<Vec<_>>().join(",");
+    conn.execute(
+        "UPDATE messages SET flags = ? WHERE task_id = ? AND seq_order = ?",
+        params![flags, task_id, seq_order],
+    )?;
+    Ok(())
+}
+
+/// Flags on the task's messages, by position; unflagged messages are left out
+pub fn list_message_flags(conn: &Connection, task_id: &str) -> Result<BTreeMap<usize, BTreeSet<MessageFlag>>> {
+    let mut stmt = conn.prepare("SELECT seq_order, flags FROM messages WHERE task_id = ? AND flags != ''")?;
+    let rows = stmt
+        .query_map(params![task_id], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(seq, flags)| {
+            let flags: BTreeSet<MessageFlag> = flags.split(',').filter_map(MessageFlag::parse).collect();
+            (!flags.is_empty()).then_some((seq as usize, flags))
+        })
+        .collect())
+}
+
+// ===== Commit Queries =====
+
+/// Record a commit made from the task's changes
+pub fn insert_task_commit(
+    conn: &Connection,
+    cipher: Option<&ContentCipher>,
+    task_id: &str,
+    commit: &TaskCommit,
+) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO task_commits (task_id, hash, message, committed_at) VALUES (?, ?, ?, ?)",
+        params![
+            task_id,
+            commit.hash,
+            seal(cipher, commit.message.clone())?,
+            commit.committed_at.to_rfc3339()
+        ],
+    )?;
+    Ok(())
+}
+
+/// Commits made from the task's changes, oldest first
+pub fn list_task_commits(conn: &Connection, cipher: Option<&ContentCipher>, task_id: &str) -> Result<Vec<TaskCommit>> {
+    let mut stmt = conn.prepare(
+        "SELECT hash, message, committed_at FROM task_commits WHERE task_id = ? ORDER BY committed_at",
+    )?;
+    let commits = stmt
+        .query_map(params![task_id], |row| {
+            let committed_at: String = row.get(2)?;
+            Ok(TaskCommit {
+                hash: row.get(0)?,
+                message: open_column(cipher, row.get(1)?, 1)?,
+                committed_at: chrono::DateTime::parse_from_rfc3339(&committed_at)
+                    .map(|dt| dt.with_timezone(&chrono::Utc))
+                    .unwrap_or_else(|_| chrono::Utc::now()),
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(commits)
+}
+
+/// Text of the most recent distinct prompts sent in any thread, newest first
+///
+/// Prompts that are sealed and `cipher` can't open are skipped.
+pub fn list_recent_prompts(conn: &Connection, cipher: Option<&ContentCipher>, limit: usize) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT content
+        FROM messages
+        WHERE role = 'user' AND content_type = 'content_blocks'
+        ORDER BY created_at DESC, id DESC
+        "#,
+    )?;
+    let mut rows = stmt.query([])?;
+
+    let mut prompts: Vec<String> = Vec::new();
+    while prompts.len() < limit {
+        let Some(row) = rows.next()? else {
+            break;
+        };
+        let Ok(content) = open_column(cipher, row.get(0)?, 0) else {
+            continue;
+        };
+        let blocks: Vec<ContentBlock> = serde_json::from_str(&content).unwrap_or_default();
+        let text = blocks
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("");
+        let text = text.trim();
+        if !text.is_empty() && !prompts.iter().any(|p| p == text) {
+            prompts.push(text.to_string());
+        }
+    }
+    Ok(prompts)
+}
+
 // ===== Tool Call Queries =====
 
-/// Insert a tool call
-pub fn insert_tool_call(conn: &Connection, task_id: &str, tc: &ToolCallState) -> Result<()> {
+/// Insert a tool call, sealing its input and content if there's a cipher
+pub fn insert_tool_call(
+    conn: &Connection,
+    cipher: Option<&ContentCipher>,
+    task_id: &str,
+    tc: &ToolCallState,
+) -> Result<()> {
     conn.execute(
         r#"
         INSERT INTO tool_calls (id, task_id, title, kind, status, raw_input, content, started_at)
@@ -256,8 +432,8 @@ pub fn insert_tool_call(conn: &Connection, task_id: &str, tc: &ToolCallState) ->
             tc.title,
             tc.kind.map(|k| format!("{:?}", k).to_lowercase()),
             format!("{:?}", tc.status).to_lowercase(),
-            tc.input.as_ref().map(|v| v.to_string()),
-            serde_json::to_string(&tc.content)?,
+            tc.input.as_ref().map(|v| seal(cipher, v.to_string())).transpose()?,
+            seal(cipher, serde_json::to_string(&tc.content)?)?,
             tc.started_at.to_rfc3339(),
         ],
     )?;
@@ -265,9 +441,10 @@ pub fn insert_tool_call(conn: &Connection, task_id: &str, tc: &ToolCallState) ->
     Ok(())
 }
 
-/// Update a tool call
+/// Update a tool call, sealing its output if there's a cipher
 pub fn update_tool_call(
     conn: &Connection,
+    cipher: Option<&ContentCipher>,
     tool_call_id: &str,
     status: ToolCallStatus,
     output: Option<&serde_json::Value>,
@@ -281,7 +458,7 @@ pub fn update_tool_call(
         "#,
         params![
             format!("{:?}", status).to_lowercase(),
-            output.map(|v| v.to_string()),
+            output.map(|v| seal(cipher, v.to_string())).transpose()?,
             completed_at.map(|t| t.to_rfc3339()),
             tool_call_id,
         ],
@@ -291,7 +468,11 @@ pub fn update_tool_call(
 }
 
 /// Get tool calls for a task
-pub fn get_task_tool_calls(conn: &Connection, task_id: &str) -> Result<Vec<ToolCallState>> {
+pub fn get_task_tool_calls(
+    conn: &Connection,
+    cipher: Option<&ContentCipher>,
+    task_id: &str,
+) -> Result<Vec<ToolCallState>> {
     let mut stmt = conn.prepare(
         r#"
         SELECT id, title, kind, status, raw_input, raw_output, content, started_at, completed_at
@@ -307,9 +488,15 @@ pub fn get_task_tool_calls(conn: &Connection, task_id: &str) -> Result<Vec<ToolC
             let title: Option<String> = row.get(1)?;
             let kind: Option<String> = row.get(2)?;
             let status: String = row.get(3)?;
-            let input: Option<String> = row.get(4)?;
-            let output: Option<String> = row.get(5)?;
-            let content: String = row.get(6)?;
+            let input = row
+                .get::<_, Option<String>>(4)?
+                .map(|v| open_column(cipher, v, 4))
+                .transpose()?;
+            let output = row
+                .get::<_, Option<String>>(5)?
+                .map(|v| open_column(cipher, v, 5))
+                .transpose()?;
+            let content = open_column(cipher, row.get(6)?, 6)?;
             let started_at: String = row.get(7)?;
             let completed_at: Option<String> = row.get(8)?;
 
@@ -331,16 +518,198 @@ pub fn get_task_tool_calls(conn: &Connection, task_id: &str) -> Result<Vec<ToolC
                 }),
             })
         })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(tool_calls)
+}
+
+// ===== File Change Queries =====
+
+/// Record a file changed during a task
+pub fn insert_file_change(conn: &Connection, change: &FileChange) -> Result<()> {
+    conn.execute(
+        r#"
+        INSERT INTO file_changes (task_id, path, change_type, old_path, size_before, size_after,
+                                  hash_before, hash_after, attribution, tool_call_id, timestamp)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+        params![
+            change.task_id,
+            change.path,
+            format!("{:?}", change.change_type).to_lowercase(),
+            change.old_path,
+            change.size_before.map(|s| s as i64),
+            change.size_after.map(|s| s as i64),
+            change.hash_before,
+            change.hash_after,
+            serde_json::to_string(&change.attribution)?,
+            change.tool_call_id,
+            change.timestamp.to_rfc3339(),
+        ],
+    )?;
+
+    Ok(())
+}
+
+// ===== Usage Queries =====
+
+/// Aggregate usage since `since` for the dashboard
+pub fn get_usage_stats(conn: &Connection, since: chrono::DateTime<chrono::Utc>) -> Result<UsageStats> {
+    let since = since.to_rfc3339();
+
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT date(created_at) AS day, COUNT(*)
+        FROM messages
+        WHERE role = 'user' AND julianday(created_at) >= julianday(?1)
+        GROUP BY day
+        ORDER BY day
+        "#,
+    )?;
+    let prompts_per_day = stmt
+        .query_map(params![since], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, u32>(1)?))
+        })?
+        .filter_map(|r| r.ok())
+        .filter_map(|(day, count)| {
+            let day = chrono::NaiveDate::parse_from_str(&day, "%Y-%m-%d").ok()?;
+            Some((day, count))
+        })
+        .collect();
+
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT COALESCE(kind, 'other') AS kind, COUNT(*) AS calls
+        FROM tool_calls
+        WHERE julianday(started_at) >= julianday(?1)
+        GROUP BY kind
+        ORDER BY calls DESC, kind
+        "#,
+    )?;
+    let tool_calls_by_kind = stmt
+        .query_map(params![since], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let files_modified = conn.query_row(
+        "SELECT COUNT(DISTINCT path) FROM file_changes WHERE julianday(timestamp) >= julianday(?1)",
+        params![since],
+        |row| row.get(0),
+    )?;
+
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT agent_id,
+               COUNT(*) AS task_count,
+               SUM((SELECT COUNT(*) FROM messages m WHERE m.task_id = tasks.id AND m.role = 'user')),
+               SUM((SELECT COUNT(*) FROM tool_calls t WHERE t.task_id = tasks.id)),
+               SUM(CAST(ROUND((julianday(COALESCE(completed_at, updated_at)) - julianday(created_at)) * 86400) AS INTEGER))
+        FROM tasks
+        WHERE julianday(updated_at) >= julianday(?1)
+        GROUP BY agent_id
+        ORDER BY task_count DESC, agent_id
+        "#,
+    )?;
+    let agents: Vec<AgentUsage> = stmt
+        .query_map(params![since], |row| {
+            Ok(AgentUsage {
+                agent_id: row.get(0)?,
+                tasks: row.get(1)?,
+                prompts: row.get(2)?,
+                tool_calls: row.get(3)?,
+                session_secs: row.get(4)?,
+            })
+        })?
         .filter_map(|r| r.ok())
         .collect();
+    let total_session_secs = agents.iter().map(|a| a.session_secs).sum();
+
+    Ok(UsageStats {
+        prompts_per_day,
+        tool_calls_by_kind,
+        files_modified,
+        agents,
+        total_session_secs,
+    })
+}
 
-    Ok(tool_calls)
+// ===== Metric Queries =====
+
+/// Record a metric sample
+pub fn insert_metric(conn: &Connection, sample: &crate::metrics::MetricSample) -> Result<()> {
+    conn.execute(
+        "INSERT INTO metrics (name, kind, agent_id, value, recorded_at) VALUES (?, ?, ?, ?, ?)",
+        params![
+            sample.name,
+            sample.kind.as_str(),
+            sample.agent_id,
+            sample.value,
+            sample.recorded_at.to_rfc3339(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Sum up the metrics recorded since `since` for the dashboard
+pub fn get_metrics_summary(
+    conn: &Connection,
+    since: chrono::DateTime<chrono::Utc>,
+) -> Result<crate::metrics::MetricsSummary> {
+    use crate::metrics::{Histogram, MetricKind, MetricsSummary};
+
+    let since = since.to_rfc3339();
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT name, CAST(SUM(value) AS INTEGER)
+        FROM metrics
+        WHERE kind = ?1 AND julianday(recorded_at) >= julianday(?2)
+        GROUP BY name
+        ORDER BY name
+        "#,
+    )?;
+    let counters = stmt
+        .query_map(params![MetricKind::Count.as_str(), since], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT name, value
+        FROM metrics
+        WHERE kind = ?1 AND julianday(recorded_at) >= julianday(?2)
+        ORDER BY name
+        "#,
+    )?;
+    let mut latencies: Vec<(String, Vec<f64>)> = Vec::new();
+    let rows = stmt.query_map(params![MetricKind::LatencyMs.as_str(), since], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?))
+    })?;
+    for (name, value) in rows.filter_map(|r| r.ok()) {
+        match latencies.last_mut() {
+            Some((last, values)) if *last == name => values.push(value),
+            _ => latencies.push((name, vec![value])),
+        }
+    }
+
+    Ok(MetricsSummary {
+        counters,
+        histograms: latencies
+            .into_iter()
+            .map(|(name, values)| Histogram::from_values(name, values))
+            .collect(),
+    })
+}
+
+/// Delete every recorded metric
+pub fn clear_metrics(conn: &Connection) -> Result<()> {
+    conn.execute("DELETE FROM metrics", [])?;
+    Ok(())
 }
 
 // ===== Artifact Queries =====
 
 /// Insert an artifact
-pub fn insert_artifact(conn: &Connection, artifact: &Artifact) -> Result<()> {
+pub fn insert_artifact(conn: &Connection, cipher: Option<&ContentCipher>, artifact: &Artifact) -> Result<()> {
     conn.execute(
         r#"
         INSERT INTO artifacts (
@@ -363,7 +732,7 @@ pub fn insert_artifact(conn: &Connection, artifact: &Artifact) -> Result<()> {
             artifact.old_path,
             artifact.source.layer as i32,
             artifact.source.tool_call_id,
-            artifact.summary,
+            artifact.summary.clone().map(|s| seal(cipher, s)).transpose()?,
             serde_json::to_string(&artifact.referenced_files)?,
             artifact.created_at.to_rfc3339(),
         ],
@@ -373,7 +742,7 @@ pub fn insert_artifact(conn: &Connection, artifact: &Artifact) -> Result<()> {
 }
 
 /// Get artifacts for a task
-pub fn get_task_artifacts(conn: &Connection, task_id: &str) -> Result<Vec<Artifact>> {
+pub fn get_task_artifacts(conn: &Connection, cipher: Option<&ContentCipher>, task_id: &str) -> Result<Vec<Artifact>> {
     let mut stmt = conn.prepare(
         r#"
         SELECT id, artifact_type, file_path, file_name, file_ext, mime_type,
@@ -398,7 +767,10 @@ pub fn get_task_artifacts(conn: &Connection, task_id: &str) -> Result<Vec<Artifa
             let old_path: Option<String> = row.get(8)?;
             let source_layer: i32 = row.get(9)?;
             let tool_call_id: Option<String> = row.get(10)?;
-            let summary: Option<String> = row.get(11)?;
+            let summary = row
+                .get::<_, Option<String>>(11)?
+                .map(|s| open_column(cipher, s, 11))
+                .transpose()?;
             let referenced_files: String = row.get(12)?;
             let created_at: String = row.get(13)?;
 
@@ -561,6 +933,141 @@ pub fn set_setting(conn: &Connection, key: &str, value: &str) -> Result<()> {
     Ok(())
 }
 
+/// Delete a setting
+pub fn delete_setting(conn: &Connection, key: &str) -> Result<()> {
+    conn.execute("DELETE FROM settings WHERE key = ?", params![key])?;
+    Ok(())
+}
+
+/// Settings key prefix for per-agent pinned versions
+const PINNED_VERSION_PREFIX: &str = "agent_pinned_version.";
+
+/// Get the pinned bridge version of every agent that has one
+pub fn get_pinned_agent_versions(
+    conn: &Connection,
+) -> Result<std::collections::HashMap<String, String>> {
+    Ok(get_all_settings(conn)?
+        .into_iter()
+        .filter_map(|(key, value)| {
+            key.strip_prefix(PINNED_VERSION_PREFIX)
+                .map(|agent_id| (agent_id.to_string(), value))
+        })
+        .collect())
+}
+
+/// Pin an agent's bridge version, or unpin it with `None`
+pub fn set_pinned_agent_version(
+    conn: &Connection,
+    agent_id: &str,
+    version: Option<&str>,
+) -> Result<()> {
+    let key = format!("{}{}", PINNED_VERSION_PREFIX, agent_id);
+    match version {
+        Some(version) => set_setting(conn, &key, version),
+        None => delete_setting(conn, &key),
+    }
+}
+
+/// Settings keys for the network proxy
+const PROXY_HTTP_KEY: &str = "proxy.http";
+const PROXY_HTTPS_KEY: &str = "proxy.https";
+const PROXY_NO_PROXY_KEY: &str = "proxy.no_proxy";
+
+/// Get the proxy used for downloads and agent processes
+pub fn get_proxy_settings(conn: &Connection) -> Result<crate::net::ProxySettings> {
+    Ok(crate::net::ProxySettings {
+        http_proxy: get_setting(conn, PROXY_HTTP_KEY)?,
+        https_proxy: get_setting(conn, PROXY_HTTPS_KEY)?,
+        no_proxy: get_setting(conn, PROXY_NO_PROXY_KEY)?,
+    })
+}
+
+/// Save proxy settings; unset fields are removed
+pub fn set_proxy_settings(conn: &Connection, proxy: &crate::net::ProxySettings) -> Result<()> {
+    let fields = [
+        (PROXY_HTTP_KEY, &proxy.http_proxy),
+        (PROXY_HTTPS_KEY, &proxy.https_proxy),
+        (PROXY_NO_PROXY_KEY, &proxy.no_proxy),
+    ];
+    for (key, value) in fields {
+        match value {
+            Some(value) => set_setting(conn, key, value)?,
+            None => delete_setting(conn, key)?,
+        }
+    }
+    Ok(())
+}
+
+/// Setting holding the configured webhooks as JSON
+const WEBHOOKS_KEY: &str = "webhooks";
+
+/// Get the configured webhooks
+pub fn get_webhooks(conn: &Connection) -> Result<Vec<crate::webhook::Webhook>> {
+    match get_setting(conn, WEBHOOKS_KEY)? {
+        Some(json) => Ok(serde_json::from_str(&json)?),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Replace the configured webhooks
+pub fn set_webhooks(conn: &Connection, webhooks: &[crate::webhook::Webhook]) -> Result<()> {
+    if webhooks.is_empty() {
+        delete_setting(conn, WEBHOOKS_KEY)
+    } else {
+        set_setting(conn, WEBHOOKS_KEY, &serde_json::to_string(webhooks)?)
+    }
+}
+
+/// Setting holding the self-hosted GitLab servers the user added, as JSON
+const GITLAB_HOSTS_KEY: &str = "gitlab_hosts";
+
+/// Get the self-hosted GitLab servers pull requests may be opened on
+pub fn get_gitlab_hosts(conn: &Connection) -> Result<Vec<String>> {
+    match get_setting(conn, GITLAB_HOSTS_KEY)? {
+        Some(json) => Ok(serde_json::from_str(&json)?),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Add a self-hosted GitLab server; adding one twice is not an error
+pub fn add_gitlab_host(conn: &Connection, host: &str) -> Result<()> {
+    let host = host.trim().to_ascii_lowercase();
+    let mut hosts = get_gitlab_hosts(conn)?;
+    if !host.is_empty() && !hosts.contains(&host) {
+        hosts.push(host);
+        set_setting(conn, GITLAB_HOSTS_KEY, &serde_json::to_string(&hosts)?)?;
+    }
+    Ok(())
+}
+
+const WORKSPACE_INSTRUCTIONS_PREFIX: &str = "workspace.instructions:";
+
+fn workspace_instructions_key(workspace: &std::path::Path) -> String {
+    format!("{}{}", WORKSPACE_INSTRUCTIONS_PREFIX, workspace.to_string_lossy())
+}
+
+/// Get the instructions added to new sessions in a workspace
+pub fn get_workspace_instructions(
+    conn: &Connection,
+    workspace: &std::path::Path,
+) -> Result<Option<String>> {
+    get_setting(conn, &workspace_instructions_key(workspace))
+}
+
+/// Save a workspace's instructions; blank instructions are removed
+pub fn set_workspace_instructions(
+    conn: &Connection,
+    workspace: &std::path::Path,
+    instructions: &str,
+) -> Result<()> {
+    let key = workspace_instructions_key(workspace);
+    if instructions.trim().is_empty() {
+        delete_setting(conn, &key)
+    } else {
+        set_setting(conn, &key, instructions)
+    }
+}
+
 /// Get all settings
 pub fn get_all_settings(conn: &Connection) -> Result<std::collections::HashMap<String, String>> {
     let mut stmt = conn.prepare("SELECT key, value FROM settings")?;
@@ -577,6 +1084,309 @@ pub fn get_all_settings(conn: &Connection) -> Result<std::collections::HashMap<S
     Ok(settings)
 }
 
+// ===== Env Profile Queries =====
+
+/// Insert or update an env profile
+///
+/// Saving a default profile clears the default flag on the workspace's
+/// other profiles.
+pub fn upsert_env_profile(conn: &Connection, profile: &EnvProfile) -> Result<()> {
+    let workspace = profile.workspace.to_string_lossy().to_string();
+    if profile.is_default {
+        conn.execute(
+            "UPDATE env_profiles SET is_default = 0 WHERE workspace = ? AND id != ?",
+            params![workspace, profile.id],
+        )?;
+    }
+
+    conn.execute(
+        r#"
+        INSERT INTO env_profiles (id, name, workspace, env, path_prepend, is_default, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(id) DO UPDATE SET
+            name = excluded.name,
+            workspace = excluded.workspace,
+            env = excluded.env,
+            path_prepend = excluded.path_prepend,
+            is_default = excluded.is_default,
+            updated_at = excluded.updated_at
+        "#,
+        params![
+            profile.id,
+            profile.name,
+            workspace,
+            serde_json::to_string(&profile.env)?,
+            serde_json::to_string(&profile.path_prepend)?,
+            profile.is_default as i32,
+            profile.created_at.to_rfc3339(),
+            profile.updated_at.to_rfc3339(),
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// List a workspace's env profiles, default first
+pub fn list_env_profiles(
+    conn: &Connection,
+    workspace: &std::path::Path,
+) -> Result<Vec<EnvProfile>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, name, workspace, env, path_prepend, is_default, created_at, updated_at
+        FROM env_profiles
+        WHERE workspace = ?
+        ORDER BY is_default DESC, name
+        "#,
+    )?;
+
+    let profiles = stmt
+        .query_map(params![workspace.to_string_lossy()], |row| {
+            let workspace: String = row.get(2)?;
+            let env: String = row.get(3)?;
+            let path_prepend: String = row.get(4)?;
+            let is_default: i32 = row.get(5)?;
+            let created_at: String = row.get(6)?;
+            let updated_at: String = row.get(7)?;
+
+            Ok(EnvProfile {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                workspace: std::path::PathBuf::from(workspace),
+                env: serde_json::from_str(&env).unwrap_or_default(),
+                path_prepend: serde_json::from_str(&path_prepend).unwrap_or_default(),
+                is_default: is_default != 0,
+                created_at: chrono::DateTime::parse_from_rfc3339(&created_at)
+                    .unwrap()
+                    .with_timezone(&chrono::Utc),
+                updated_at: chrono::DateTime::parse_from_rfc3339(&updated_at)
+                    .unwrap()
+                    .with_timezone(&chrono::Utc),
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(profiles)
+}
+
+/// Delete an env profile
+pub fn delete_env_profile(conn: &Connection, profile_id: &str) -> Result<()> {
+    conn.execute("DELETE FROM env_profiles WHERE id = ?", params![profile_id])?;
+    Ok(())
+}
+
+// ===== Prompt Template Queries =====
+
+/// Insert or update a prompt template
+pub fn upsert_prompt_template(conn: &Connection, template: &PromptTemplate) -> Result<()> {
+    conn.execute(
+        r#"
+        INSERT INTO prompt_templates (id, name, body, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?)
+        ON CONFLICT(id) DO UPDATE SET
+            name = excluded.name,
+            body = excluded.body,
+            updated_at = excluded.updated_at
+        "#,
+        params![
+            template.id,
+            template.name,
+            template.body,
+            template.created_at.to_rfc3339(),
+            template.updated_at.to_rfc3339(),
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// List all prompt templates by name
+pub fn list_prompt_templates(conn: &Connection) -> Result<Vec<PromptTemplate>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, name, body, created_at, updated_at
+        FROM prompt_templates
+        ORDER BY name COLLATE NOCASE
+        "#,
+    )?;
+
+    let templates = stmt
+        .query_map([], |row| {
+            let created_at: String = row.get(3)?;
+            let updated_at: String = row.get(4)?;
+
+            Ok(PromptTemplate {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                body: row.get(2)?,
+                created_at: chrono::DateTime::parse_from_rfc3339(&created_at)
+                    .unwrap()
+                    .with_timezone(&chrono::Utc),
+                updated_at: chrono::DateTime::parse_from_rfc3339(&updated_at)
+                    .unwrap()
+                    .with_timezone(&chrono::Utc),
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(templates)
+}
+
+/// Delete a prompt template
+pub fn delete_prompt_template(conn: &Connection, template_id: &str) -> Result<()> {
+    conn.execute("DELETE FROM prompt_templates WHERE id = ?", params![template_id])?;
+    Ok(())
+}
+
+// ===== Audit Log Queries =====
+
+/// Append an entry to the audit log
+pub fn insert_audit_entry(conn: &Connection, entry: &AuditEntry) -> Result<()> {
+    conn.execute(
+        r#"
+        INSERT INTO audit_log (id, session_id, operation, target, outcome, detail, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#,
+        params![
+            entry.id,
+            entry.session_id,
+            entry.operation,
+            entry.target,
+            entry.outcome.as_str(),
+            entry.detail,
+            entry.created_at.to_rfc3339(),
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// List audit entries matching a filter, newest first
+pub fn list_audit_entries(conn: &Connection, filter: &AuditFilter) -> Result<Vec<AuditEntry>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, session_id, operation, target, outcome, detail, created_at
+        FROM audit_log
+        WHERE (?1 IS NULL OR session_id = ?1)
+          AND (?2 IS NULL OR operation = ?2)
+          AND (?3 IS NULL OR outcome = ?3)
+          AND (?4 IS NULL OR instr(lower(target), lower(?4)) > 0)
+          AND (?5 IS NULL OR created_at >= ?5)
+        ORDER BY created_at DESC, rowid DESC
+        LIMIT ?6
+        "#,
+    )?;
+
+    let entries = stmt
+        .query_map(
+            params![
+                filter.session_id,
+                filter.operation,
+                filter.outcome.map(|o| o.as_str()),
+                filter.search.as_deref().filter(|s| !s.is_empty()),
+                filter.since.map(|t| t.to_rfc3339()),
+                filter.limit.map(|l| l as i64).unwrap_or(-1),
+            ],
+            |row| {
+                let outcome: String = row.get(4)?;
+                let created_at: String = row.get(6)?;
+
+                Ok(AuditEntry {
+                    id: row.get(0)?,
+                    session_id: row.get(1)?,
+                    operation: row.get(2)?,
+                    target: row.get(3)?,
+                    outcome: parse_audit_outcome(&outcome),
+                    detail: row.get(5)?,
+                    created_at: chrono::DateTime::parse_from_rfc3339(&created_at)
+                        .unwrap()
+                        .with_timezone(&chrono::Utc),
+                })
+            },
+        )?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(entries)
+}
+
+/// Count audit entries matching a filter, ignoring its limit
+pub fn count_audit_entries(conn: &Connection, filter: &AuditFilter) -> Result<usize> {
+    let count: i64 = conn.query_row(
+        r#"
+        SELECT COUNT(*)
+        FROM audit_log
+        WHERE (?1 IS NULL OR session_id = ?1)
+          AND (?2 IS NULL OR operation = ?2)
+          AND (?3 IS NULL OR outcome = ?3)
+          AND (?4 IS NULL OR instr(lower(target), lower(?4)) > 0)
+          AND (?5 IS NULL OR created_at >= ?5)
+        "#,
+        params![
+            filter.session_id,
+            filter.operation,
+            filter.outcome.map(|o| o.as_str()),
+            filter.search.as_deref().filter(|s| !s.is_empty()),
+            filter.since.map(|t| t.to_rfc3339()),
+        ],
+        |row| row.get(0),
+    )?;
+
+    Ok(count as usize)
+}
+
+// ===== Agent Process Queries =====
+
+/// Record a running agent process, replacing any older record for its PID
+pub fn record_agent_process(conn: &Connection, process: &AgentProcess) -> Result<()> {
+    conn.execute(
+        r#"
+        INSERT OR REPLACE INTO agent_processes (pid, agent_id, command, process_started, started_at)
+        VALUES (?, ?, ?, ?, ?)
+        "#,
+        params![
+            process.pid,
+            process.agent_id,
+            process.command,
+            process.process_started,
+            process.started_at.to_rfc3339(),
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// Forget an agent process once it has exited
+pub fn remove_agent_process(conn: &Connection, pid: u32) -> Result<()> {
+    conn.execute("DELETE FROM agent_processes WHERE pid = ?", params![pid])?;
+    Ok(())
+}
+
+/// Agent processes recorded as running, oldest first
+pub fn list_agent_processes(conn: &Connection) -> Result<Vec<AgentProcess>> {
+    let mut stmt = conn.prepare(
+        "SELECT pid, agent_id, command, process_started, started_at FROM agent_processes ORDER BY started_at",
+    )?;
+    let processes = stmt
+        .query_map([], |row| {
+            let started_at: String = row.get(4)?;
+            Ok(AgentProcess {
+                pid: row.get(0)?,
+                agent_id: row.get(1)?,
+                command: row.get(2)?,
+                process_started: row.get(3)?,
+                started_at: chrono::DateTime::parse_from_rfc3339(&started_at)
+                    .map(|dt| dt.with_timezone(&chrono::Utc))
+                    .unwrap_or_else(|_| chrono::Utc::now()),
+            })
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    Ok(processes)
+}
+
 // ===== Helper Functions =====
 
 fn parse_task_status(s: &str) -> TaskStatus {
@@ -629,6 +1439,13 @@ fn parse_artifact_type(s: &str) -> ArtifactType {
     }
 }
 
+fn parse_audit_outcome(s: &str) -> AuditOutcome {
+    AuditOutcome::ALL
+        .into_iter()
+        .find(|outcome| outcome.as_str() == s)
+        .unwrap_or(AuditOutcome::Failed)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -654,10 +1471,10 @@ mod tests {
         );
 
         // Insert
-        insert_task(&conn, &state).unwrap();
+        insert_task(&conn, None, &state).unwrap();
 
         // Get
-        let retrieved = get_task(&conn, "task-1").unwrap();
+        let retrieved = get_task(&conn, None, "task-1").unwrap();
         assert!(retrieved.is_some());
         let task = retrieved.unwrap();
         assert_eq!(task.id, "task-1");
@@ -666,12 +1483,49 @@ mod tests {
         // Update status
         update_task_status(&conn, "task-1", TaskStatus::Completed, Some(StopReason::EndTurn), None).unwrap();
 
-        let updated = get_task(&conn, "task-1").unwrap().unwrap();
+        let updated = get_task(&conn, None, "task-1").unwrap().unwrap();
         assert_eq!(updated.status, TaskStatus::Completed);
 
+        // Find by ID or session ID prefix
+        assert_eq!(find_tasks(&conn, None, "task-").unwrap().len(), 1);
+        assert_eq!(find_tasks(&conn, None, "session-1").unwrap()[0].working_dir, "/home/user");
+        assert!(find_tasks(&conn, None, "task_").unwrap().is_empty());
+
+        // Sub-threads are found from their parent
+        let mut child = TaskState::new(
+            "task-2".to_string(),
+            "session-2".to_string(),
+            "agent-2".to_string(),
+            Vec::new(),
+            "/home/user".to_string(),
+        );
+        child.parent_session_id = Some("session-1".to_string());
+        insert_task(&conn, None, &child).unwrap();
+        let subtasks = list_subtasks(&conn, None, "session-1").unwrap();
+        assert_eq!(subtasks.len(), 1);
+        assert_eq!(subtasks[0].parent_session_id.as_deref(), Some("session-1"));
+        assert_eq!(get_task(&conn, None, "task-1").unwrap().unwrap().parent_session_id, None);
+
+        // Forks remember where they branched off
+        let mut fork = TaskState::new(
+            "task-3".to_string(),
+            "session-3".to_string(),
+            "agent-1".to_string(),
+            Vec::new(),
+            "/home/user".to_string(),
+        );
+        fork.parent_session_id = Some("session-1".to_string());
+        fork.forked_at = Some(4);
+        fork.issue_url = Some("https://github.com/acme/widgets/issues/42".to_string());
+        insert_task(&conn, None, &fork).unwrap();
+        let fork = get_task(&conn, None, "task-3").unwrap().unwrap();
+        assert_eq!(fork.forked_at, Some(4));
+        assert_eq!(fork.issue_url.as_deref(), Some("https://github.com/acme/widgets/issues/42"));
+        assert_eq!(get_task(&conn, None, "task-2").unwrap().unwrap().forked_at, None);
+
         // Delete
         delete_task(&conn, "task-1").unwrap();
-        assert!(get_task(&conn, "task-1").unwrap().is_none());
+        assert!(get_task(&conn, None, "task-1").unwrap().is_none());
     }
 
     #[test]
@@ -685,15 +1539,165 @@ mod tests {
             vec![],
             "/home".to_string(),
         );
-        insert_task(&conn, &state).unwrap();
+        insert_task(&conn, None, &state).unwrap();
 
         let msg = MessageBlock::agent(vec![ContentBlock::Text {
             text: "Hello".to_string(),
         }]);
-        insert_message(&conn, "task-1", &msg, 0).unwrap();
+        insert_message(&conn, None, "task-1", &msg, 0).unwrap();
+        let summary = MessageBlock::summary("Renamed the config loader");
+        insert_message(&conn, None, "task-1", &summary, 1).unwrap();
+
+        let messages = get_task_messages(&conn, None, "task-1").unwrap();
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(
+            &messages[1],
+            MessageBlock::Summary { content, .. } if content == "Renamed the config loader"
+        ));
+
+        // Pins are kept per message
+        set_message_pinned(&conn, "task-1", 1, true).unwrap();
+        assert_eq!(list_pinned_messages(&conn, "task-1").unwrap(), vec![1]);
+        set_message_pinned(&conn, "task-1", 1, false).unwrap();
+        assert!(list_pinned_messages(&conn, "task-1").unwrap().is_empty());
+
+        // So are flags
+        let flags = BTreeSet::from([MessageFlag::Bug, MessageFlag::NeedsReview]);
+        set_message_flags(&conn, "task-1", 0, &flags).unwrap();
+        assert_eq!(list_message_flags(&conn, "task-1").unwrap(), BTreeMap::from([(0, flags)]));
+        set_message_flags(&conn, "task-1", 0, &BTreeSet::new()).unwrap();
+        assert!(list_message_flags(&conn, "task-1").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_task_commits() {
+        let conn = setup_db();
+        let state = TaskState::new(
+            "task-1".to_string(),
+            "session-1".to_string(),
+            "agent-1".to_string(),
+            vec![],
+            "/home".to_string(),
+        );
+        insert_task(&conn, None, &state).unwrap();
+
+        let first = TaskCommit {
+            hash: "a".repeat(40),
+            message: "First".to_string(),
+            committed_at: chrono::Utc::now() - chrono::Duration::minutes(5),
+        };
+        let second = TaskCommit {
+            hash: "b".repeat(40),
+            message: "Second\n\nBody".to_string(),
+            committed_at: chrono::Utc::now(),
+        };
+        insert_task_commit(&conn, None, "task-1", &second).unwrap();
+        insert_task_commit(&conn, None, "task-1", &first).unwrap();
+        let commits = list_task_commits(&conn, None, "task-1").unwrap();
+        assert_eq!(commits.iter().map(TaskCommit::subject).collect::<Vec<_>>(), ["First", "Second"]);
+        assert_eq!(commits[1].short_hash(), "bbbbbbb");
+
+        delete_task(&conn, "task-1").unwrap();
+        assert!(list_task_commits(&conn, None, "task-1").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_recent_prompts() {
+        let conn = setup_db();
+        let state = TaskState::new("task-1".into(), "session-1".into(), "agent-1".into(), vec![], "/home".into());
+        insert_task(&conn, None, &state).unwrap();
+
+        let user = |text: &str| MessageBlock::user(vec![ContentBlock::Text { text: text.to_string() }]);
+        insert_message(&conn, None, "task-1", &user("Fix the build"), 0).unwrap();
+        insert_message(&conn, None, "task-1", &MessageBlock::agent(vec![]), 1).unwrap();
+        insert_message(&conn, None, "task-1", &user("Add tests"), 2).unwrap();
+        insert_message(&conn, None, "task-1", &user("Fix the build"), 3).unwrap();
+
+        assert_eq!(list_recent_prompts(&conn, None, 10).unwrap(), vec!["Fix the build", "Add tests"]);
+        assert_eq!(list_recent_prompts(&conn, None, 1).unwrap(), vec!["Fix the build"]);
+    }
+
+    #[test]
+    fn test_usage_stats() {
+        let conn = setup_db();
+
+        let state = TaskState::new(
+            "task-1".to_string(),
+            "session-1".to_string(),
+            "claude-code".to_string(),
+            vec![],
+            "/home".to_string(),
+        );
+        insert_task(&conn, None, &state).unwrap();
+        let prompt = MessageBlock::user(vec![ContentBlock::Text {
+            text: "Fix the build".to_string(),
+        }]);
+        insert_message(&conn, None, "task-1", &prompt, 0).unwrap();
+        let edit = ToolCallState::new("tc-1".to_string(), None, Some(ToolCallKind::Edit));
+        insert_tool_call(&conn, None, "task-1", &edit).unwrap();
+        for (id, path) in [("tc-1", "src/lib.rs"), ("tc-2", "src/lib.rs")] {
+            let change = FileChange {
+                id: id.to_string(),
+                task_id: "task-1".to_string(),
+                path: path.to_string(),
+                change_type: FileChangeType::Modified,
+                old_path: None,
+                size_before: None,
+                size_after: None,
+                hash_before: None,
+                hash_after: None,
+                attribution: FileChangeAttribution::AcpOperation {
+                    tool_call_id: id.to_string(),
+                    method: "fs/write_text_file".to_string(),
+                },
+                tool_call_id: Some(id.to_string()),
+                timestamp: chrono::Utc::now(),
+            };
+            insert_file_change(&conn, &change).unwrap();
+        }
+
+        let since = chrono::Utc::now() - chrono::Duration::days(7);
+        let stats = get_usage_stats(&conn, since).unwrap();
+        assert_eq!(stats.total_prompts(), 1);
+        assert_eq!(stats.prompts_per_day[0].0, chrono::Utc::now().date_naive());
+        assert_eq!(stats.tool_calls_by_kind, vec![("edit".to_string(), 1)]);
+        assert_eq!(stats.files_modified, 1);
+        assert_eq!(stats.agents.len(), 1);
+        assert_eq!(stats.agents[0].prompts, 1);
+        assert_eq!(stats.agents[0].tool_calls, 1);
+
+        // Nothing newer than now
+        let stats = get_usage_stats(&conn, chrono::Utc::now() + chrono::Duration::hours(1)).unwrap();
+        assert_eq!(stats.total_prompts(), 0);
+        assert!(stats.agents.is_empty());
+    }
+
+    #[test]
+    fn test_metrics_summary() {
+        use crate::metrics::{self, MetricSample};
+        use std::time::Duration;
+
+        let conn = setup_db();
+        for sample in [
+            MetricSample::count(metrics::SESSION_STARTED, Some("claude-code")),
+            MetricSample::count(metrics::SESSION_STARTED, Some("codex-cli")),
+            MetricSample::count(metrics::TURN_ERROR, Some("codex-cli")),
+            MetricSample::latency(metrics::TURN_MS, None, Duration::from_millis(800)),
+            MetricSample::latency(metrics::TURN_MS, None, Duration::from_secs(12)),
+        ] {
+            insert_metric(&conn, &sample).unwrap();
+        }
+
+        let summary = get_metrics_summary(&conn, chrono::Utc::now() - chrono::Duration::days(7)).unwrap();
+        assert_eq!(summary.counter(metrics::SESSION_STARTED), 2);
+        assert_eq!(summary.counter(metrics::TURN_ERROR), 1);
+        assert_eq!(summary.counter(metrics::CONNECT_ERROR), 0);
+        let turns = summary.histogram(metrics::TURN_MS).unwrap();
+        assert_eq!(turns.count, 2);
+        assert_eq!(turns.p95_ms, 12_000.0);
 
-        let messages = get_task_messages(&conn, "task-1").unwrap();
-        assert_eq!(messages.len(), 1);
+        clear_metrics(&conn).unwrap();
+        assert!(get_metrics_summary(&conn, chrono::Utc::now() - chrono::Duration::days(7)).unwrap().is_empty());
     }
 
     #[test]
@@ -711,4 +1715,212 @@ mod tests {
         let none = get_setting(&conn, "nonexistent").unwrap();
         assert!(none.is_none());
     }
+
+    #[test]
+    fn test_pinned_agent_versions() {
+        let conn = setup_db();
+        set_setting(&conn, "theme", "dark").unwrap();
+
+        set_pinned_agent_version(&conn, "codex-cli", Some("v0.3.0")).unwrap();
+        let pinned = get_pinned_agent_versions(&conn).unwrap();
+        assert_eq!(pinned.len(), 1);
+        assert_eq!(pinned["codex-cli"], "v0.3.0");
+
+        set_pinned_agent_version(&conn, "codex-cli", None).unwrap();
+        assert!(get_pinned_agent_versions(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_proxy_settings() {
+        let conn = setup_db();
+        assert!(get_proxy_settings(&conn).unwrap().is_empty());
+
+        let proxy = crate::net::ProxySettings {
+            https_proxy: Some("http://proxy:3128".to_string()),
+            no_proxy: Some("localhost".to_string()),
+            ..Default::default()
+        };
+        set_proxy_settings(&conn, &proxy).unwrap();
+        assert_eq!(get_proxy_settings(&conn).unwrap(), proxy);
+
+        set_proxy_settings(&conn, &crate::net::ProxySettings::default()).unwrap();
+        assert!(get_proxy_settings(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_webhooks() {
+        use crate::webhook::{Webhook, WebhookEvent};
+
+        let conn = setup_db();
+        assert!(get_webhooks(&conn).unwrap().is_empty());
+
+        let hook = Webhook::new(
+            "https://ci.example.com/hook",
+            Some("s3cret".to_string()),
+            vec![WebhookEvent::TurnComplete, WebhookEvent::AgentCrash],
+        )
+        .unwrap();
+        set_webhooks(&conn, std::slice::from_ref(&hook)).unwrap();
+        assert_eq!(get_webhooks(&conn).unwrap(), vec![hook]);
+
+        set_webhooks(&conn, &[]).unwrap();
+        assert!(get_webhooks(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_gitlab_hosts() {
+        let conn = setup_db();
+        assert!(get_gitlab_hosts(&conn).unwrap().is_empty());
+        add_gitlab_host(&conn, " Git.Corp.Example ").unwrap();
+        add_gitlab_host(&conn, "git.corp.example").unwrap();
+        add_gitlab_host(&conn, "").unwrap();
+        assert_eq!(get_gitlab_hosts(&conn).unwrap(), ["git.corp.example"]);
+    }
+
+    #[test]
+    fn test_workspace_instructions() {
+        let conn = setup_db();
+        let workspace = std::path::Path::new("/work/project");
+        assert_eq!(get_workspace_instructions(&conn, workspace).unwrap(), None);
+
+        set_workspace_instructions(&conn, workspace, "Use pnpm, not npm").unwrap();
+        assert_eq!(
+            get_workspace_instructions(&conn, workspace).unwrap().as_deref(),
+            Some("Use pnpm, not npm")
+        );
+        assert_eq!(get_workspace_instructions(&conn, std::path::Path::new("/work")).unwrap(), None);
+
+        set_workspace_instructions(&conn, workspace, "  ").unwrap();
+        assert_eq!(get_workspace_instructions(&conn, workspace).unwrap(), None);
+    }
+
+    #[test]
+    fn test_env_profiles() {
+        let conn = setup_db();
+        let workspace = std::path::PathBuf::from("/work/project");
+
+        let mut staging = EnvProfile::new("staging", &workspace);
+        staging.env.insert("HTTPS_PROXY".to_string(), "http://proxy:8080".to_string());
+        staging.is_default = true;
+        upsert_env_profile(&conn, &staging).unwrap();
+
+        let mut prod = EnvProfile::new("prod", &workspace);
+        prod.path_prepend.push("/opt/tools/bin".to_string());
+        upsert_env_profile(&conn, &prod).unwrap();
+        upsert_env_profile(&conn, &EnvProfile::new("other", "/work/other")).unwrap();
+
+        let profiles = list_env_profiles(&conn, &workspace).unwrap();
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[0], staging);
+
+        // Making another profile the default clears the old one
+        prod.is_default = true;
+        upsert_env_profile(&conn, &prod).unwrap();
+        let profiles = list_env_profiles(&conn, &workspace).unwrap();
+        assert_eq!(profiles[0].id, prod.id);
+        assert!(!profiles[1].is_default);
+
+        delete_env_profile(&conn, &staging.id).unwrap();
+        assert_eq!(list_env_profiles(&conn, &workspace).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_env_profile_apply() {
+        let mut profile = EnvProfile::new("dev", "/work");
+        assert!(profile.set_variables("NO_EQUALS_SIGN").is_err());
+        profile
+            .set_variables("API_BASE=http://localhost;\n PATH+=/opt/bin")
+            .unwrap();
+
+        let mut env = std::collections::HashMap::new();
+        env.insert("API_BASE".to_string(), "https://prod".to_string());
+        env.insert("PATH".to_string(), "/usr/bin".to_string());
+        profile.apply(&mut env);
+
+        assert_eq!(env["API_BASE"], "http://localhost");
+        assert_eq!(profile.variables_text(), "API_BASE=http://localhost; PATH+=/opt/bin");
+        let paths: Vec<_> = std::env::split_paths(&env["PATH"]).collect();
+        assert_eq!(
+            paths,
+            vec![std::path::PathBuf::from("/opt/bin"), std::path::PathBuf::from("/usr/bin")]
+        );
+    }
+
+    #[test]
+    fn test_prompt_templates() {
+        let conn = setup_db();
+
+        let review = PromptTemplate::new("Code review", "Review {file} for bugs");
+        upsert_prompt_template(&conn, &review).unwrap();
+        let mut explain = PromptTemplate::new("explain", "Explain this:\n{selection}");
+        upsert_prompt_template(&conn, &explain).unwrap();
+
+        let templates = list_prompt_templates(&conn).unwrap();
+        assert_eq!(templates.len(), 2);
+        assert_eq!(templates[0], review);
+
+        explain.body = "Explain {selection} simply".to_string();
+        upsert_prompt_template(&conn, &explain).unwrap();
+        assert_eq!(list_prompt_templates(&conn).unwrap()[1].body, explain.body);
+
+        delete_prompt_template(&conn, &review.id).unwrap();
+        assert_eq!(list_prompt_templates(&conn).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_prompt_template_render() {
+        let template = PromptTemplate::new(
+            "Code Review!",
+            "Review {file} and {file}, not {} or {a b}; see {selection}",
+        );
+        assert_eq!(template.command(), "/code-review");
+        assert_eq!(template.placeholders(), vec!["file", "selection"]);
+
+        let mut values = std::collections::HashMap::new();
+        values.insert("file".to_string(), "src/main.rs".to_string());
+        assert_eq!(
+            template.render(&values),
+            "Review src/main.rs and src/main.rs, not {} or {a b}; see {selection}"
+        );
+    }
+
+    #[test]
+    fn test_audit_log_filters_and_is_append_only() {
+        let conn = setup_db();
+
+        let read = AuditEntry::new("s1", "read", "/work/src/main.rs", AuditOutcome::Allowed);
+        let rejected = AuditEntry::new("s1", "execute", "rm -rf target", AuditOutcome::Rejected)
+            .with_detail("Rejected by user: rm -rf target");
+        let other = AuditEntry::new("s2", "write", "/work/src/MAIN.rs", AuditOutcome::Approved);
+        for entry in [&read, &rejected, &other] {
+            insert_audit_entry(&conn, entry).unwrap();
+        }
+
+        let all = list_audit_entries(&conn, &AuditFilter::default()).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0], other);
+
+        let session = AuditFilter {
+            session_id: Some("s1".into()),
+            ..Default::default()
+        };
+        assert_eq!(list_audit_entries(&conn, &session).unwrap(), vec![rejected.clone(), read]);
+
+        let search = AuditFilter {
+            search: Some("main.rs".into()),
+            limit: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(list_audit_entries(&conn, &search).unwrap(), vec![other]);
+        assert_eq!(count_audit_entries(&conn, &search).unwrap(), 2);
+
+        let outcome = AuditFilter {
+            outcome: Some(AuditOutcome::Rejected),
+            ..Default::default()
+        };
+        assert_eq!(list_audit_entries(&conn, &outcome).unwrap(), vec![rejected]);
+
+        assert!(conn.execute("DELETE FROM audit_log", []).is_err());
+        assert!(conn.execute("UPDATE audit_log SET outcome = 'allowed'", []).is_err());
+    }
 }
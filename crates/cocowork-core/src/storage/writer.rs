@@ -0,0 +1,344 @@
+//! Background writer for persistence
+//!
+//! Streaming sessions produce a steady trickle of rows. The writer takes them
+//! off the caller's thread through a bounded queue and applies them on a
+//! worker, batching everything that arrives within a short interval into one
+//! transaction, so SQLite never runs on the render path.
+
+use super::{clear_journal, crypto::ContentCipher, insert_file_change, insert_message, insert_metric, insert_task, insert_task_commit, insert_tool_call, set_message_flags, set_message_pinned, update_task_status, update_tool_call, write_journal, JournalEntry, Storage};
+use crate::error::{Error, Result, StorageError};
+use crate::metrics::MetricSample;
+use crate::types::{
+    FileChange, MessageBlock, MessageFlag, StopReason, TaskCommit, TaskState, TaskStatus, ToolCallState,
+};
+use rusqlite::Connection;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+/// A row change queued for the writer
+#[derive(Debug, Clone)]
+pub enum StorageWrite {
+    InsertTask(Box<TaskState>),
+    UpdateTaskStatus {
+        task_id: String,
+        status: TaskStatus,
+        stop_reason: Option<StopReason>,
+        error_message: Option<String>,
+    },
+    InsertMessage {
+        task_id: String,
+        message: MessageBlock,
+        seq_order: i32,
+    },
+    /// Pin or unpin a message written before
+    SetMessagePinned {
+        task_id: String,
+        seq_order: i32,
+        pinned: bool,
+    },
+    /// Replace the flags on a message written before
+    SetMessageFlags {
+        task_id: String,
+        seq_order: i32,
+        flags: BTreeSet<MessageFlag>,
+    },
+    /// Insert a tool call along with its current status and output
+    InsertToolCall {
+        task_id: String,
+        tool_call: ToolCallState,
+    },
+    InsertFileChange(FileChange),
+    /// Record a commit made from the task's changes
+    InsertTaskCommit {
+        task_id: String,
+        commit: TaskCommit,
+    },
+    InsertMetric(MetricSample),
+    /// Replace the session's entry in the stream journal
+    JournalTurn(Box<JournalEntry>),
+    ClearJournal {
+        session_id: String,
+    },
+}
+
+impl StorageWrite {
+    fn apply(&self, conn: &Connection, cipher: Option<&ContentCipher>) -> Result<()> {
+        match self {
+            Self::InsertTask(task) => insert_task(conn, cipher, task),
+            Self::UpdateTaskStatus {
+                task_id,
+                status,
+                stop_reason,
+                error_message,
+            } => update_task_status(conn, task_id, *status, *stop_reason, error_message.as_deref()),
+            Self::InsertMessage {
+                task_id,
+                message,
+                seq_order,
+            } => insert_message(conn, cipher, task_id, message, *seq_order).map(|_| ()),
+            Self::SetMessagePinned {
+                task_id,
+                seq_order,
+                pinned,
+            } => set_message_pinned(conn, task_id, *seq_order, *pinned),
+            Self::SetMessageFlags {
+                task_id,
+                seq_order,
+                flags,
+            } => set_message_flags(conn, task_id, *seq_order, flags),
+            Self::InsertToolCall { task_id, tool_call } => {
+                insert_tool_call(conn, cipher, task_id, tool_call)?;
+                update_tool_call(
+                    conn,
+                    cipher,
+                    &tool_call.id,
+                    tool_call.status,
+                    tool_call.output.as_ref(),
+                    tool_call.completed_at,
+                )
+            }
+            Self::InsertFileChange(change) => insert_file_change(conn, change),
+            Self::InsertTaskCommit { task_id, commit } => insert_task_commit(conn, cipher, task_id, commit),
+            Self::InsertMetric(sample) => insert_metric(conn, sample),
+            Self::JournalTurn(entry) => write_journal(conn, cipher, entry),
+            Self::ClearJournal { session_id } => clear_journal(conn, session_id),
+        }
+    }
+}
+
+/// Queue and batching limits for a [`StorageWriter`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriterConfig {
+    /// Queued submissions before `try_write` reports the writer busy
+    pub queue_size: usize,
+    /// How long to keep gathering writes into one transaction
+    pub batch_interval: Duration,
+    /// Most submissions applied in one transaction
+    pub max_batch: usize,
+}
+
+impl Default for WriterConfig {
+    fn default() -> Self {
+        Self {
+            queue_size: 256,
+            batch_interval: Duration::from_millis(50),
+            max_batch: 64,
+        }
+    }
+}
+
+enum WriterCommand {
+    Write(Vec<StorageWrite>),
+    Flush(oneshot::Sender<Result<()>>),
+}
+
+/// Handle to the background writer task
+///
+/// Cloning shares the same queue. The worker stops once every handle is dropped,
+/// after applying what was already queued.
+#[derive(Clone)]
+pub struct StorageWriter {
+    tx: mpsc::Sender<WriterCommand>,
+}
+
+impl StorageWriter {
+    /// Start the writer on the current Tokio runtime
+    pub fn spawn(storage: Arc<Storage>, config: WriterConfig) -> Self {
+        let (tx, rx) = mpsc::channel(config.queue_size.max(1));
+        tokio::spawn(run_writer(storage, config, rx));
+        Self { tx }
+    }
+
+    /// Queue writes to be applied together without waiting
+    ///
+    /// Fails rather than blocking when the queue is full, so callers on the UI
+    /// thread can keep the rows and try again later.
+    pub fn try_write(&self, writes: Vec<StorageWrite>) -> Result<()> {
+        if writes.is_empty() {
+            return Ok(());
+        }
+        self.tx.try_send(WriterCommand::Write(writes)).map_err(|e| {
+            let reason = match e {
+                mpsc::error::TrySendError::Full(_) => "queue is full",
+                mpsc::error::TrySendError::Closed(_) => "writer has stopped",
+            };
+            Error::Storage(StorageError::Writer(reason.to_string()))
+        })
+    }
+
+    /// Queue writes, waiting for room in the queue
+    pub async fn write(&self, writes: Vec<StorageWrite>) -> Result<()> {
+        if writes.is_empty() {
+            return Ok(());
+        }
+        self.tx
+            .send(WriterCommand::Write(writes))
+            .await
+            .map_err(|_| Error::Storage(StorageError::Writer("writer has stopped".to_string())))
+    }
+
+    /// Wait until everything queued so far is committed
+    ///
+    /// Reports the first write that failed since the last flush.
+    pub async fn flush(&self) -> Result<()> {
+        let (done_tx, done_rx) = oneshot::channel();
+        self.tx
+            .send(WriterCommand::Flush(done_tx))
+            .await
+            .map_err(|_| Error::Storage(StorageError::Writer("writer has stopped".to_string())))?;
+        done_rx
+            .await
+            .map_err(|_| Error::Storage(StorageError::Writer("writer has stopped".to_string())))?
+    }
+
+    /// Flush from outside the runtime, e.g. while the app shuts down
+    pub fn blocking_flush(&self) -> Result<()> {
+        let (done_tx, done_rx) = oneshot::channel();
+        self.tx
+            .blocking_send(WriterCommand::Flush(done_tx))
+            .map_err(|_| Error::Storage(StorageError::Writer("writer has stopped".to_string())))?;
+        done_rx
+            .blocking_recv()
+            .map_err(|_| Error::Storage(StorageError::Writer("writer has stopped".to_string())))?
+    }
+}
+
+async fn run_writer(storage: Arc<Storage>, config: WriterConfig, mut rx: mpsc::Receiver<WriterCommand>) {
+    let mut first_error: Option<String> = None;
+
+    while let Some(command) = rx.recv().await {
+        let mut batch = Vec::new();
+        let mut flushes = Vec::new();
+        sort_command(command, &mut batch, &mut flushes);
+
+        // Gather whatever else arrives before the interval is up, unless
+        // someone is already waiting on a flush
+        let deadline = Instant::now() + config.batch_interval;
+        while batch.len() < config.max_batch && flushes.is_empty() {
+            match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(Some(command)) => sort_command(command, &mut batch, &mut flushes),
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        if !batch.is_empty() {
+            let storage = Arc::clone(&storage);
+            let result = tokio::task::spawn_blocking(move || apply_batch(&storage, batch))
+                .await
+                .unwrap_or_else(|e| Err(Error::Internal(format!("Storage writer panicked: {}", e))));
+            if let Err(e) = result {
+                warn!("Storage writer: {}", e);
+                first_error.get_or_insert_with(|| e.to_string());
+            }
+        }
+
+        if !flushes.is_empty() {
+            let error = first_error.take();
+            for done in flushes {
+                let _ = done.send(match &error {
+                    Some(e) => Err(Error::Storage(StorageError::Database(e.clone()))),
+                    None => Ok(()),
+                });
+            }
+        }
+    }
+    debug!("Storage writer stopped");
+}
+
+fn sort_command(
+    command: WriterCommand,
+    batch: &mut Vec<Vec<StorageWrite>>,
+    flushes: &mut Vec<oneshot::Sender<Result<()>>>,
+) {
+    match command {
+        WriterCommand::Write(writes) => batch.push(writes),
+        WriterCommand::Flush(done) => flushes.push(done),
+    }
+}
+
+/// Apply submissions in one transaction, skipping rows that fail
+///
+/// A failed statement doesn't abort the SQLite transaction, so one bad row
+/// doesn't cost the rest of the batch. The first failure is returned. Content
+/// is sealed if the storage has encryption turned on.
+fn apply_batch(storage: &Storage, batch: Vec<Vec<StorageWrite>>) -> Result<()> {
+    // Hold the cipher for the whole batch so a reseal can't land in between
+    let cipher = storage.cipher.read().unwrap_or_else(|e| e.into_inner());
+    let mut conn = storage.connection()?;
+    let tx = conn.transaction()?;
+    let mut first_error = None;
+    for write in batch.iter().flatten() {
+        if let Err(e) = write.apply(&tx, cipher.as_deref()) {
+            warn!("Failed to apply {:?}: {}", std::mem::discriminant(write), e);
+            first_error.get_or_insert(e);
+        }
+    }
+    tx.commit()?;
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ContentBlock;
+
+    #[tokio::test]
+    async fn test_writer_batches_and_flushes() {
+        let storage = Arc::new(Storage::in_memory().unwrap());
+        let writer = StorageWriter::spawn(Arc::clone(&storage), WriterConfig::default());
+
+        let task = TaskState::new(
+            "t1".to_string(),
+            "s1".to_string(),
+            "codex-cli".to_string(),
+            Vec::new(),
+            "/work".to_string(),
+        );
+        let task_id = task.id.clone();
+        writer.try_write(vec![StorageWrite::InsertTask(Box::new(task))]).unwrap();
+        writer
+            .try_write(
+                (0..3)
+                    .map(|i| StorageWrite::InsertMessage {
+                        task_id: task_id.clone(),
+                        message: MessageBlock::agent(vec![ContentBlock::Text { text: format!("part {}", i) }]),
+                        seq_order: i,
+                    })
+                    .collect(),
+            )
+            .unwrap();
+        writer.flush().await.unwrap();
+
+        let conn = storage.connection().unwrap();
+        assert_eq!(super::super::get_task_messages(&conn, None, &task_id).unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_flush_reports_failed_writes() {
+        let storage = Arc::new(Storage::in_memory().unwrap());
+        let writer = StorageWriter::spawn(Arc::clone(&storage), WriterConfig::default());
+
+        let task = TaskState::new(
+            "t1".to_string(),
+            "s1".to_string(),
+            "codex-cli".to_string(),
+            Vec::new(),
+            "/work".to_string(),
+        );
+        writer
+            .try_write(vec![StorageWrite::InsertTask(Box::new(task.clone())), StorageWrite::InsertTask(Box::new(task))])
+            .unwrap();
+        assert!(writer.flush().await.is_err());
+
+        // The error is reported once; the first insert still landed
+        assert!(writer.flush().await.is_ok());
+        assert_eq!(super::super::list_tasks(&storage.connection().unwrap(), None, 10, 0).unwrap().len(), 1);
+    }
+}
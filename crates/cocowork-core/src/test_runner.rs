@@ -0,0 +1,390 @@
+//! Running a workspace's tests and reading their results
+//!
+//! Each workspace can have a test command, stored like its other settings;
+//! without one, a command is guessed from the files at the top of the
+//! workspace (`cargo test` next to a `Cargo.toml`, `npm test` next to a
+//! `package.json`, `pytest` for Python projects). The command runs through
+//! the user's shell in the workspace, and its output is read for pass and
+//! fail counts in the formats cargo test, pytest and jest print.
+
+use crate::error::{Error, Result};
+use crate::storage::{delete_setting, get_setting, set_setting};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+const TEST_CONFIG_KEY: &str = "tests.workspace";
+
+/// Output kept from a test run, from the end
+pub const MAX_TEST_OUTPUT: usize = 256 * 1024;
+
+/// Most failing test names kept from a run
+const MAX_FAILURES: usize = 50;
+
+/// How a workspace's tests are run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TestConfig {
+    /// Shell command line that runs the tests
+    pub command: String,
+    /// Run the tests after each turn the agent finishes in the workspace
+    pub run_after_turn: bool,
+}
+
+impl TestConfig {
+    fn setting_key(workspace: &Path) -> String {
+        format!("{}.{}", TEST_CONFIG_KEY, workspace.display())
+    }
+
+    /// The settings stored for `workspace`, if any
+    pub fn load(conn: &rusqlite::Connection, workspace: &Path) -> Result<Option<Self>> {
+        Ok(get_setting(conn, &Self::setting_key(workspace))?.and_then(|raw| serde_json::from_str(&raw).ok()))
+    }
+
+    /// The settings stored for `workspace`, or a guessed command if it has none
+    pub fn resolve(conn: &rusqlite::Connection, workspace: &Path) -> Result<Option<Self>> {
+        Ok(Self::load(conn, workspace)?.or_else(|| {
+            Self::detect(workspace).map(|command| Self { command, run_after_turn: false })
+        }))
+    }
+
+    pub fn save(&self, conn: &rusqlite::Connection, workspace: &Path) -> Result<()> {
+        set_setting(conn, &Self::setting_key(workspace), &serde_json::to_string(self)?)
+    }
+
+    pub fn clear(conn: &rusqlite::Connection, workspace: &Path) -> Result<()> {
+        delete_setting(conn, &Self::setting_key(workspace))
+    }
+
+    /// The usual test command for the project at the top of `workspace`
+    pub fn detect(workspace: &Path) -> Option<String> {
+        let has = |name: &str| workspace.join(name).exists();
+        if has("Cargo.toml") {
+            Some("cargo test".to_string())
+        } else if has("package.json") {
+            Some("npm test".to_string())
+        } else if ["pytest.ini", "pyproject.toml", "setup.py", "tox.ini"].iter().any(|name| has(name)) {
+            Some("pytest".to_string())
+        } else {
+            None
+        }
+    }
+}
+
+/// Tool whose output a summary was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestFramework {
+    Cargo,
+    Pytest,
+    Jest,
+}
+
+/// Pass and fail counts read from a test run's output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestSummary {
+    pub framework: TestFramework,
+    pub passed: u32,
+    /// Tests that failed, and with pytest, errors collecting or running them
+    pub failed: u32,
+    /// Tests skipped, ignored or left to do
+    pub skipped: u32,
+    /// Names of the failing tests, as the tool printed them
+    pub failures: Vec<String>,
+}
+
+impl TestSummary {
+    /// Counts from cargo test, pytest or jest output, whichever it looks like
+    pub fn parse(output: &str) -> Option<Self> {
+        parse_cargo(output).or_else(|| parse_jest(output)).or_else(|| parse_pytest(output))
+    }
+}
+
+/// A finished run of a workspace's tests
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestRun {
+    pub workspace: PathBuf,
+    pub command: String,
+    /// Exit code; -1 if the command was killed or timed out
+    pub exit_code: i32,
+    /// The end of stdout, then stderr
+    pub output: String,
+    pub summary: Option<TestSummary>,
+    pub duration_ms: u64,
+    pub finished_at: DateTime<Utc>,
+}
+
+impl TestRun {
+    /// Whether the tests passed: the command succeeded and no failures were counted
+    pub fn passed(&self) -> bool {
+        self.exit_code == 0 && self.summary.as_ref().map_or(0, |summary| summary.failed) == 0
+    }
+
+    /// The run as text to attach to a prompt: the counts and failing tests, or
+    /// the end of the output when it couldn't be read
+    pub fn report(&self) -> String {
+        let mut report = format!("`{}` exited with code {}", self.command, self.exit_code);
+        match &self.summary {
+            Some(summary) => {
+                report.push_str(&format!(
+                    ": {} passed, {} failed, {} skipped",
+                    summary.passed, summary.failed, summary.skipped
+                ));
+                for name in &summary.failures {
+                    report.push_str(&format!("\nFAILED {}", name));
+                }
+            }
+            None => {
+                let lines: Vec<&str> = self.output.trim_end().lines().collect();
+                let tail = lines[lines.len().saturating_sub(40)..].join("\n");
+                report.push_str(&format!("\n{}", tail));
+            }
+        }
+        report
+    }
+}
+
+/// Run `command` through the user's shell in `workspace`, killing it after `timeout`
+pub async fn run_tests(workspace: &Path, command: &str, timeout: Duration) -> Result<TestRun> {
+    let started = Instant::now();
+    let (exit_code, output) = run_in_shell(workspace, command, timeout).await?;
+    Ok(TestRun {
+        workspace: workspace.to_path_buf(),
+        command: command.to_string(),
+        exit_code,
+        summary: TestSummary::parse(&output),
+        output,
+        duration_ms: started.elapsed().as_millis() as u64,
+        finished_at: Utc::now(),
+    })
+}
+
+/// Exit code and output (stdout, then stderr, keeping the last
+/// `MAX_TEST_OUTPUT` bytes) of `command` run through the user's shell in
+/// `workspace`; the code is -1 if it was killed or timed out
+pub(crate) async fn run_in_shell(workspace: &Path, command: &str, timeout: Duration) -> Result<(i32, String)> {
+    let (shell, flag) = if cfg!(windows) { ("cmd", "/C") } else { ("sh", "-c") };
+    let child = Command::new(shell)
+        .arg(flag)
+        .arg(command)
+        .current_dir(workspace)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| Error::Internal(format!("Failed to run '{}': {}", command, e)))?;
+
+    let (exit_code, stdout, stderr) = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(output) => {
+            let output = output?;
+            (output.status.code().unwrap_or(-1), output.stdout, output.stderr)
+        }
+        Err(_) => (-1, Vec::new(), format!("Timed out after {}s", timeout.as_secs()).into_bytes()),
+    };
+    let mut output = String::from_utf8_lossy(&stdout).into_owned();
+    output.push_str(&String::from_utf8_lossy(&stderr));
+    if output.len() > MAX_TEST_OUTPUT {
+        let mut start = output.len() - MAX_TEST_OUTPUT;
+        while !output.is_char_boundary(start) {
+            start += 1;
+        }
+        output.drain(..start);
+    }
+    Ok((exit_code, output))
+}
+
+/// `N word` pairs in a list like `3 passed; 1 failed` or `1 failed, 2 passed`
+fn counts(list: &str) -> Vec<(u32, &str)> {
+    list.split([',', ';'])
+        .filter_map(|item| {
+            let mut words = item.split_whitespace();
+            let count = words.next()?.parse().ok()?;
+            Some((count, words.next()?))
+        })
+        .collect()
+}
+
+/// Sums of the `test result:` lines cargo prints for each test binary
+fn parse_cargo(output: &str) -> Option<TestSummary> {
+    let mut summary = None;
+    for line in output.lines() {
+        let line = line.trim();
+        if let Some(result) = line.strip_prefix("test result: ") {
+            let summary = summary.get_or_insert_with(|| empty(TestFramework::Cargo));
+            let (_, list) = result.split_once(". ").unwrap_or(("", result));
+            for (count, word) in counts(list) {
+                match word {
+                    "passed" => summary.passed += count,
+                    "failed" => summary.failed += count,
+                    "ignored" => summary.skipped += count,
+                    _ => {}
+                }
+            }
+        } else if let Some(name) = line.strip_prefix("test ").and_then(|rest| rest.strip_suffix(" ... FAILED")) {
+            push_failure(summary.get_or_insert_with(|| empty(TestFramework::Cargo)), name);
+        }
+    }
+    summary
+}
+
+/// The `Tests:` line of jest's summary, and the `●` headings of its failures
+fn parse_jest(output: &str) -> Option<TestSummary> {
+    let line = output.lines().rev().find_map(|line| line.trim().strip_prefix("Tests:"))?;
+    let mut summary = empty(TestFramework::Jest);
+    for (count, word) in counts(line) {
+        match word {
+            "passed" => summary.passed = count,
+            "failed" => summary.failed = count,
+            "skipped" | "todo" => summary.skipped += count,
+            _ => {}
+        }
+    }
+    for line in output.lines() {
+        if let Some(name) = line.trim().strip_prefix("● ") {
+            if !name.starts_with("Console") {
+                push_failure(&mut summary, name);
+            }
+        }
+    }
+    Some(summary)
+}
+
+/// pytest's last line, like `== 1 failed, 4 passed in 0.12s ==`, and the
+/// `FAILED` lines of its short summary
+fn parse_pytest(output: &str) -> Option<TestSummary> {
+    let list = output.lines().rev().find_map(|line| {
+        let (list, time) = line.trim().trim_matches('=').trim().rsplit_once(" in ")?;
+        let timed = time.split_whitespace().next().is_some_and(|time| time.ends_with('s'));
+        let outcomes = counts(list).iter().any(|(_, word)| matches!(*word, "passed" | "failed" | "error" | "errors"));
+        (timed && outcomes).then_some(list)
+    })?;
+    let mut summary = empty(TestFramework::Pytest);
+    for (count, word) in counts(list) {
+        match word {
+            "passed" | "xpassed" => summary.passed += count,
+            "failed" | "error" | "errors" => summary.failed += count,
+            "skipped" | "xfailed" | "deselected" => summary.skipped += count,
+            _ => {}
+        }
+    }
+    for line in output.lines() {
+        let failed = line.strip_prefix("FAILED ").or_else(|| line.strip_prefix("ERROR "));
+        if let Some(rest) = failed {
+            push_failure(&mut summary, rest.split(" - ").next().unwrap_or(rest).trim());
+        }
+    }
+    Some(summary)
+}
+
+fn empty(framework: TestFramework) -> TestSummary {
+    TestSummary { framework, passed: 0, failed: 0, skipped: 0, failures: Vec::new() }
+}
+
+fn push_failure(summary: &mut TestSummary, name: &str) {
+    if summary.failures.len() < MAX_FAILURES && !summary.failures.iter().any(|known| known == name) {
+        summary.failures.push(name.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_cargo() {
+        let output = "\
+running 3 tests
+test lexer::tests::test_numbers ... ok
+test parser::tests::test_nested ... FAILED
+test parser::tests::test_slow ... ignored
+
+failures:
+    parser::tests::test_nested
+
+test result: FAILED. 1 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.01s
+
+running 2 tests
+test result: ok. 2 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s
+";
+        let summary = TestSummary::parse(output).unwrap();
+        assert_eq!(summary.framework, TestFramework::Cargo);
+        assert_eq!((summary.passed, summary.failed, summary.skipped), (3, 1, 1));
+        assert_eq!(summary.failures, ["parser::tests::test_nested"]);
+    }
+
+    #[test]
+    fn test_parse_pytest() {
+        let output = "\
+tests/test_api.py ..F.s                                                  [100%]
+
+=========================== short test summary info ============================
+FAILED tests/test_api.py::test_login - AssertionError: assert 401 == 200
+=================== 1 failed, 3 passed, 1 skipped in 0.42s ====================
+";
+        let summary = TestSummary::parse(output).unwrap();
+        assert_eq!(summary.framework, TestFramework::Pytest);
+        assert_eq!((summary.passed, summary.failed, summary.skipped), (3, 1, 1));
+        assert_eq!(summary.failures, ["tests/test_api.py::test_login"]);
+
+        let quiet = TestSummary::parse("....\n4 passed in 0.03s\n").unwrap();
+        assert_eq!((quiet.passed, quiet.failed), (4, 0));
+    }
+
+    #[test]
+    fn test_parse_jest() {
+        let output = "\
+FAIL src/sum.test.js
+  ● sum › adds negative numbers
+
+    expect(received).toBe(expected)
+
+Test Suites: 1 failed, 2 passed, 3 total
+Tests:       1 failed, 1 skipped, 9 passed, 11 total
+Time:        1.2 s
+";
+        let summary = TestSummary::parse(output).unwrap();
+        assert_eq!(summary.framework, TestFramework::Jest);
+        assert_eq!((summary.passed, summary.failed, summary.skipped), (9, 1, 1));
+        assert_eq!(summary.failures, ["sum › adds negative numbers"]);
+
+        assert!(TestSummary::parse("Compiling app v0.1.0\nerror: could not compile `app`\n").is_none());
+    }
+
+    #[test]
+    fn test_config_per_workspace() {
+        let storage = Storage::in_memory().unwrap();
+        let conn = storage.connection().unwrap();
+        let workspace = tempdir().unwrap();
+        assert_eq!(TestConfig::resolve(&conn, workspace.path()).unwrap(), None);
+
+        std::fs::write(workspace.path().join("Cargo.toml"), "[package]\n").unwrap();
+        assert_eq!(TestConfig::resolve(&conn, workspace.path()).unwrap().unwrap().command, "cargo test");
+
+        let config = TestConfig { command: "cargo nextest run".to_string(), run_after_turn: true };
+        config.save(&conn, workspace.path()).unwrap();
+        assert_eq!(TestConfig::resolve(&conn, workspace.path()).unwrap(), Some(config));
+        TestConfig::clear(&conn, workspace.path()).unwrap();
+        assert_eq!(TestConfig::load(&conn, workspace.path()).unwrap(), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_tests() {
+        let workspace = tempdir().unwrap();
+        let command = "echo 'test result: FAILED. 2 passed; 1 failed; 0 ignored'; exit 101";
+        let run = run_tests(workspace.path(), command, Duration::from_secs(10)).await.unwrap();
+        assert_eq!(run.exit_code, 101);
+        assert!(!run.passed());
+        assert_eq!(run.summary.as_ref().map(|summary| summary.failed), Some(1));
+        assert!(run.report().contains(": 2 passed, 1 failed, 0 skipped"));
+
+        let run = run_tests(workspace.path(), "sleep 5", Duration::from_millis(100)).await.unwrap();
+        assert_eq!(run.exit_code, -1);
+    }
+}
@@ -8,6 +8,9 @@ use std::collections::HashMap;
 /// ACP Protocol version supported by this client
 pub const ACP_PROTOCOL_VERSION: u32 = 1;
 
+/// JSON-RPC error code agents return when the user must sign in first
+pub const AUTH_REQUIRED_ERROR_CODE: i32 = -32000;
+
 /// Client information sent during initialization
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -37,6 +40,9 @@ pub struct ClientCapabilities {
     pub mcp: Option<McpCapability>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub load_session: Option<bool>,
+    /// Agents may ask the user questions with `elicitation/create`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elicitation: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -49,6 +55,9 @@ pub struct FileSystemCapability {
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct TerminalCapability {
     pub execute: bool,
+    /// Long-lived terminals with `terminal/open`, `terminal/write` and friends
+    #[serde(default)]
+    pub interactive: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,6 +81,9 @@ pub struct AgentCapabilities {
     pub load_session: bool,
     #[serde(default)]
     pub available_modes: Vec<AgentMode>,
+    /// Whether prompts may embed file contents as `resource` blocks
+    #[serde(default)]
+    pub embedded_context: bool,
 }
 
 /// Agent mode (e.g., "ask", "code", "architect")
@@ -155,6 +167,19 @@ pub struct InitializeResult {
     /// Gemini CLI uses agentCapabilities instead of capabilities
     #[serde(default)]
     pub agent_capabilities: Option<GeminiAgentCapabilities>,
+    /// Ways the agent can authenticate, for use with `authenticate`
+    #[serde(default)]
+    pub auth_methods: Vec<AuthMethod>,
+}
+
+/// Authentication method advertised by an agent during initialize
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthMethod {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
 }
 
 impl InitializeResult {
@@ -170,6 +195,10 @@ impl InitializeResult {
                 supports_thoughts: false,
                 load_session: gemini_caps.load_session,
                 available_modes: Vec::new(),
+                embedded_context: gemini_caps
+                    .prompt_capabilities
+                    .as_ref()
+                    .is_some_and(|prompt| prompt.embedded_context),
             }
         } else {
             AgentCapabilities::default()
@@ -216,6 +245,16 @@ pub struct AgentInfo {
     pub version: String,
 }
 
+/// What an agent reported about itself when the connection was initialized
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentHandshake {
+    pub protocol_version: u32,
+    pub agent_info: Option<AgentInfo>,
+    pub capabilities: AgentCapabilities,
+    pub auth_methods: Vec<AuthMethod>,
+}
+
 /// Session/new request parameters
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -256,10 +295,27 @@ pub struct PromptResponse {
 pub enum StopReason {
     EndTurn,
     MaxTokens,
+    /// The agent hit its limit on model requests for one turn
+    MaxTurnRequests,
+    /// The agent declined to continue
+    Refusal,
     Cancelled,
     Error,
 }
 
+impl StopReason {
+    pub fn label(&self) -> &'static str {
+        match self {
+            StopReason::EndTurn => "Finished",
+            StopReason::MaxTokens => "Stopped at the token limit",
+            StopReason::MaxTurnRequests => "Stopped at the request limit",
+            StopReason::Refusal => "Refused",
+            StopReason::Cancelled => "Cancelled",
+            StopReason::Error => "Failed",
+        }
+    }
+}
+
 /// Session update notification
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -496,6 +552,23 @@ pub struct TerminalExecuteParams {
     pub env: Option<HashMap<String, String>>,
 }
 
+/// terminal/output, terminal/kill and terminal/release request from agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalIdParams {
+    pub session_id: String,
+    pub terminal_id: String,
+}
+
+/// terminal/write request from agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalWriteParams {
+    pub session_id: String,
+    pub terminal_id: String,
+    pub data: String,
+}
+
 /// terminal/execute response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -171,6 +171,21 @@ impl AgentState {
     }
 }
 
+/// An agent process the app started, recorded while it runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentProcess {
+    pub pid: u32,
+    pub agent_id: String,
+    /// Executable name when the process started, to tell it from a later
+    /// process that reuses the PID
+    pub command: String,
+    /// When the OS says the process started, for the same reason; empty if
+    /// it couldn't tell
+    pub process_started: String,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+}
+
 /// Agent usage statistics (persisted)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -209,3 +224,108 @@ impl AgentStats {
         }
     }
 }
+
+/// Named set of environment overrides for agents launched from a workspace
+///
+/// Lets a workspace use its own API keys, proxy settings, or tool versions
+/// without changing the global environment.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvProfile {
+    pub id: String,
+    pub name: String,
+    /// Workspace root the profile belongs to
+    pub workspace: std::path::PathBuf,
+    /// Variables set for the agent, overriding inherited ones
+    pub env: HashMap<String, String>,
+    /// Directories put in front of the inherited `PATH`
+    pub path_prepend: Vec<String>,
+    /// Whether the profile is picked by default for its workspace
+    pub is_default: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl EnvProfile {
+    pub fn new(name: impl Into<String>, workspace: impl Into<std::path::PathBuf>) -> Self {
+        let now = chrono::Utc::now();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.into(),
+            workspace: workspace.into(),
+            env: HashMap::new(),
+            path_prepend: Vec::new(),
+            is_default: false,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Replace the variables from text like `HTTPS_PROXY=http://proxy:8080; PATH+=/opt/bin`
+    ///
+    /// Assignments are separated by `;` or newlines. `PATH+=dir` adds a
+    /// directory to the front of `PATH` instead of replacing it.
+    pub fn set_variables(&mut self, text: &str) -> crate::error::Result<()> {
+        let mut env = HashMap::new();
+        let mut path_prepend = Vec::new();
+
+        for entry in text.split([';', '\n']).map(str::trim).filter(|e| !e.is_empty()) {
+            if let Some(dir) = entry.strip_prefix("PATH+=") {
+                path_prepend.push(dir.trim().to_string());
+                continue;
+            }
+            let (key, value) = entry.split_once('=').ok_or_else(|| {
+                crate::error::Error::InvalidConfig(format!("expected KEY=VALUE, got '{}'", entry))
+            })?;
+            let key = key.trim();
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                return Err(crate::error::Error::InvalidConfig(format!(
+                    "invalid variable name '{}'",
+                    key
+                )));
+            }
+            env.insert(key.to_string(), value.trim().to_string());
+        }
+
+        self.env = env;
+        self.path_prepend = path_prepend;
+        self.updated_at = chrono::Utc::now();
+        Ok(())
+    }
+
+    /// Variables in the format accepted by [`EnvProfile::set_variables`]
+    pub fn variables_text(&self) -> String {
+        let mut keys: Vec<_> = self.env.keys().collect();
+        keys.sort();
+        keys.into_iter()
+            .map(|k| format!("{}={}", k, self.env[k]))
+            .chain(self.path_prepend.iter().map(|dir| format!("PATH+={}", dir)))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    /// Merge the profile into an agent environment
+    ///
+    /// `PATH` additions go in front of the `PATH` already in `env`, or the
+    /// process `PATH` when `env` has none.
+    pub fn apply(&self, env: &mut HashMap<String, String>) {
+        env.extend(self.env.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+        if self.path_prepend.is_empty() {
+            return;
+        }
+        let inherited = env
+            .get("PATH")
+            .cloned()
+            .or_else(|| std::env::var("PATH").ok())
+            .unwrap_or_default();
+        let paths = self
+            .path_prepend
+            .iter()
+            .map(std::path::PathBuf::from)
+            .chain(std::env::split_paths(&inherited));
+        if let Ok(joined) = std::env::join_paths(paths) {
+            env.insert("PATH".to_string(), joined.to_string_lossy().to_string());
+        }
+    }
+}
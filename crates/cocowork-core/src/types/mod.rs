@@ -25,6 +25,72 @@ pub enum ContentBlock {
     Image { source: ImageSource },
     ToolUse { id: String, name: String, input: serde_json::Value },
     ToolResult { tool_use_id: String, content: String, is_error: Option<bool> },
+    /// A file the agent can read itself
+    ResourceLink {
+        uri: String,
+        name: String,
+        #[serde(rename = "mimeType", default, skip_serializing_if = "Option::is_none")]
+        mime_type: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        size: Option<u64>,
+    },
+    /// A file's contents, sent along
+    Resource { resource: EmbeddedResource },
+}
+
+impl ContentBlock {
+    /// A link to the file at `path`
+    pub fn resource_link(path: &std::path::Path, size: Option<u64>) -> Self {
+        ContentBlock::ResourceLink {
+            uri: file_uri(path),
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.display().to_string()),
+            mime_type: mime_guess::from_path(path).first().map(|mime| mime.to_string()),
+            size,
+        }
+    }
+
+    /// The same block with embedded resources turned into links, for agents
+    /// that don't take embedded context
+    pub fn without_embedded(self) -> Self {
+        match self {
+            ContentBlock::Resource { resource } => ContentBlock::ResourceLink {
+                name: resource.uri.rsplit('/').next().unwrap_or(&resource.uri).to_string(),
+                size: Some(resource.text.len() as u64),
+                uri: resource.uri,
+                mime_type: resource.mime_type,
+            },
+            other => other,
+        }
+    }
+}
+
+/// Text contents of a file embedded in a prompt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbeddedResource {
+    pub uri: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    pub text: String,
+}
+
+/// A `file://` URI for `path`
+pub fn file_uri(path: &std::path::Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from(if path.starts_with('/') { "file://" } else { "file:///" });
+    for c in path.chars() {
+        match c {
+            ' ' => uri.push_str("%20"),
+            '%' => uri.push_str("%25"),
+            '#' => uri.push_str("%23"),
+            '?' => uri.push_str("%3F"),
+            c => uri.push(c),
+        }
+    }
+    uri
 }
 
 /// Image source for content blocks
@@ -136,6 +202,59 @@ pub struct TerminalPolicy {
     pub require_confirmation: bool,
     pub allowed_commands: Vec<String>,
     pub blocked_patterns: Vec<String>,
+    /// Where allowed commands run
+    #[serde(default)]
+    pub backend: TerminalBackend,
+    /// Resources each command may use before it's killed
+    #[serde(default)]
+    pub limits: TerminalLimits,
+}
+
+/// Limits on agents' terminal commands; `None` means no limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TerminalLimits {
+    /// CPU seconds a command may use
+    pub cpu_time_secs: Option<u64>,
+    /// Seconds a command may run
+    pub timeout_secs: Option<u64>,
+    /// Bytes of stdout and stderr together
+    pub max_output_bytes: Option<usize>,
+    /// Commands that may run at once
+    pub max_processes: Option<usize>,
+}
+
+impl TerminalLimits {
+    pub fn unlimited() -> Self {
+        Self {
+            cpu_time_secs: None,
+            timeout_secs: None,
+            max_output_bytes: None,
+            max_processes: None,
+        }
+    }
+}
+
+impl Default for TerminalLimits {
+    fn default() -> Self {
+        Self {
+            cpu_time_secs: None,
+            timeout_secs: Some(30 * 60),
+            max_output_bytes: Some(8 * 1024 * 1024),
+            max_processes: Some(8),
+        }
+    }
+}
+
+/// Where agent terminal commands run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TerminalBackend {
+    /// Directly on this machine
+    #[default]
+    Host,
+    /// In a Docker or Podman container set up per workspace
+    Container,
 }
 
 impl Default for TerminalPolicy {
@@ -159,6 +278,8 @@ impl Default for TerminalPolicy {
                 "chmod".to_string(),
                 "chown".to_string(),
             ],
+            backend: TerminalBackend::default(),
+            limits: TerminalLimits::default(),
         }
     }
 }
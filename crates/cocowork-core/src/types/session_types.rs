@@ -96,6 +96,11 @@ pub enum MessageBlock {
         content: String,
         timestamp: chrono::DateTime<chrono::Utc>,
     },
+    /// Agent-written summary of the turns before it, from compacting the conversation
+    Summary {
+        content: String,
+        timestamp: chrono::DateTime<chrono::Utc>,
+    },
 }
 
 impl MessageBlock {
@@ -127,12 +132,58 @@ impl MessageBlock {
         }
     }
 
+    pub fn summary(content: impl Into<String>) -> Self {
+        Self::Summary {
+            content: content.into(),
+            timestamp: chrono::Utc::now(),
+        }
+    }
+
     pub fn timestamp(&self) -> chrono::DateTime<chrono::Utc> {
         match self {
             Self::User { timestamp, .. } => *timestamp,
             Self::Agent { timestamp, .. } => *timestamp,
             Self::Thought { timestamp, .. } => *timestamp,
             Self::System { timestamp, .. } => *timestamp,
+            Self::Summary { timestamp, .. } => *timestamp,
+        }
+    }
+}
+
+/// Mark left on a message for reviewing the thread later
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageFlag {
+    Good,
+    NeedsReview,
+    Bug,
+}
+
+impl MessageFlag {
+    pub const ALL: [MessageFlag; 3] = [MessageFlag::Good, MessageFlag::NeedsReview, MessageFlag::Bug];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessageFlag::Good => "good",
+            MessageFlag::NeedsReview => "needs_review",
+            MessageFlag::Bug => "bug",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "good" => Some(MessageFlag::Good),
+            "needs_review" => Some(MessageFlag::NeedsReview),
+            "bug" => Some(MessageFlag::Bug),
+            _ => None,
+        }
+    }
+
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            MessageFlag::Good => "👍",
+            MessageFlag::NeedsReview => "⚠",
+            MessageFlag::Bug => "🐞",
         }
     }
 }
@@ -186,3 +237,205 @@ pub struct SessionSummary {
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
+
+/// How an audited agent operation was decided
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    /// Went ahead without needing approval
+    Allowed,
+    /// Went ahead after the user approved it
+    Approved,
+    /// Went ahead without asking because auto mode was on
+    AutoApproved,
+    /// Recorded for review instead of applied, in dry run
+    Recorded,
+    /// The user rejected it
+    Rejected,
+    /// Refused by the sandbox or the terminal policy
+    Denied,
+    /// Approved but the operation itself failed
+    Failed,
+    /// Killed for going over a terminal resource limit
+    LimitExceeded,
+}
+
+impl AuditOutcome {
+    pub const ALL: [AuditOutcome; 8] = [
+        AuditOutcome::Allowed,
+        AuditOutcome::Approved,
+        AuditOutcome::AutoApproved,
+        AuditOutcome::Recorded,
+        AuditOutcome::Rejected,
+        AuditOutcome::Denied,
+        AuditOutcome::Failed,
+        AuditOutcome::LimitExceeded,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditOutcome::Allowed => "allowed",
+            AuditOutcome::Approved => "approved",
+            AuditOutcome::AutoApproved => "auto_approved",
+            AuditOutcome::Recorded => "recorded",
+            AuditOutcome::Rejected => "rejected",
+            AuditOutcome::Denied => "denied",
+            AuditOutcome::Failed => "failed",
+            AuditOutcome::LimitExceeded => "limit_exceeded",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AuditOutcome::Allowed => "Allowed",
+            AuditOutcome::Approved => "Approved",
+            AuditOutcome::AutoApproved => "Auto-approved",
+            AuditOutcome::Recorded => "Dry run",
+            AuditOutcome::Rejected => "Rejected",
+            AuditOutcome::Denied => "Denied",
+            AuditOutcome::Failed => "Failed",
+            AuditOutcome::LimitExceeded => "Limit exceeded",
+        }
+    }
+}
+
+/// An agent operation recorded in the audit log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub id: String,
+    pub session_id: String,
+    /// Operation kind, e.g. `write` or `execute`
+    pub operation: String,
+    /// Path or command line the operation targeted
+    pub target: String,
+    pub outcome: AuditOutcome,
+    /// Error message, exit code or other specifics
+    pub detail: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl AuditEntry {
+    pub fn new(
+        session_id: impl Into<String>,
+        operation: impl Into<String>,
+        target: impl Into<String>,
+        outcome: AuditOutcome,
+    ) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            session_id: session_id.into(),
+            operation: operation.into(),
+            target: target.into(),
+            outcome,
+            detail: None,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+/// Which audit entries to list; unset fields match everything
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AuditFilter {
+    pub session_id: Option<String>,
+    pub operation: Option<String>,
+    pub outcome: Option<AuditOutcome>,
+    /// Substring of the target
+    pub search: Option<String>,
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+    /// Most entries to return, newest first when set
+    pub limit: Option<usize>,
+}
+
+/// Saved prompt from the prompt library
+///
+/// The body may contain placeholders like `{file}` or `{selection}`, which
+/// are filled in when the template is inserted into the input bar.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptTemplate {
+    pub id: String,
+    pub name: String,
+    pub body: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl PromptTemplate {
+    pub fn new(name: impl Into<String>, body: impl Into<String>) -> Self {
+        let now = chrono::Utc::now();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.into(),
+            body: body.into(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Slash command that inserts the template, e.g. `/code-review`
+    pub fn command(&self) -> String {
+        let slug = self
+            .name
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("-")
+            .to_lowercase();
+        format!("/{}", slug)
+    }
+
+    /// Placeholder names used in the body, in order of first use
+    pub fn placeholders(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        for (name, _) in placeholder_spans(&self.body) {
+            if !names.iter().any(|n| n == name) {
+                names.push(name.to_string());
+            }
+        }
+        names
+    }
+
+    /// Fill in placeholders from `values`
+    ///
+    /// Placeholders without a value are left as written so the user can see
+    /// and edit them.
+    pub fn render(&self, values: &std::collections::HashMap<String, String>) -> String {
+        let mut out = String::with_capacity(self.body.len());
+        let mut rest = 0;
+        for (name, range) in placeholder_spans(&self.body) {
+            if let Some(value) = values.get(name) {
+                out.push_str(&self.body[rest..range.start]);
+                out.push_str(value);
+                rest = range.end;
+            }
+        }
+        out.push_str(&self.body[rest..]);
+        out
+    }
+}
+
+/// `{name}` placeholders in a template body with their byte ranges
+fn placeholder_spans(body: &str) -> Vec<(&str, std::ops::Range<usize>)> {
+    let mut spans = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = body[offset..].find('{').map(|i| offset + i) {
+        let Some(len) = body[start + 1..].find('}') else {
+            break;
+        };
+        let name = &body[start + 1..start + 1 + len];
+        let end = start + len + 2;
+        if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            spans.push((name, start..end));
+            offset = end;
+        } else {
+            offset = start + 1;
+        }
+    }
+    spans
+}
@@ -45,6 +45,12 @@ pub struct TaskState {
     pub agent_id: String,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
+    /// Session this task was spawned from as a sub-thread
+    pub parent_session_id: Option<String>,
+    /// How many of the parent session's messages this thread was forked with
+    pub forked_at: Option<usize>,
+    /// Issue the thread was started from
+    pub issue_url: Option<String>,
 
     // Status
     pub status: TaskStatus,
@@ -89,6 +95,9 @@ impl TaskState {
             agent_id,
             created_at: now,
             updated_at: now,
+            parent_session_id: None,
+            forked_at: None,
+            issue_url: None,
             status: TaskStatus::Pending,
             stop_reason: None,
             error_message: None,
@@ -163,10 +172,17 @@ pub struct TaskSummary {
     pub agent_name: String,
     pub prompt_preview: String,
     pub status: TaskStatus,
+    pub working_dir: String,
     pub artifact_count: u32,
     pub file_change_count: u32,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
+    /// Session this task was spawned from as a sub-thread
+    pub parent_session_id: Option<String>,
+    /// How many of the parent session's messages this thread was forked with
+    pub forked_at: Option<usize>,
+    /// Issue the thread was started from
+    pub issue_url: Option<String>,
 }
 
 impl From<&TaskState> for TaskSummary {
@@ -194,14 +210,39 @@ impl From<&TaskState> for TaskSummary {
             agent_name: state.agent_id.clone(), // Will be resolved later
             prompt_preview,
             status: state.status,
+            working_dir: state.working_directory.clone(),
             artifact_count: state.artifacts.len() as u32,
             file_change_count: state.file_changes.len() as u32,
             created_at: state.created_at,
             updated_at: state.updated_at,
+            parent_session_id: state.parent_session_id.clone(),
+            forked_at: state.forked_at,
+            issue_url: state.issue_url.clone(),
         }
     }
 }
 
+/// A git commit made from a thread's changes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskCommit {
+    pub hash: String,
+    pub message: String,
+    pub committed_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl TaskCommit {
+    /// The hash's first seven characters, as git shows it
+    pub fn short_hash(&self) -> &str {
+        self.hash.get(..7).unwrap_or(&self.hash)
+    }
+
+    /// First line of the message
+    pub fn subject(&self) -> &str {
+        self.message.lines().next().unwrap_or_default()
+    }
+}
+
 /// UI event emitted from TaskStateAccumulator
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "event", rename_all = "snake_case")]
@@ -218,3 +259,40 @@ pub enum TaskUiEvent {
     TaskCompleted { task_id: String, stop_reason: super::StopReason },
     TaskError { task_id: String, error: String },
 }
+
+/// Usage totals across stored tasks, for the dashboard
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageStats {
+    /// Prompts sent per day (UTC), oldest first; days without prompts are omitted
+    pub prompts_per_day: Vec<(chrono::NaiveDate, u32)>,
+    /// Tool calls by kind, most used first
+    pub tool_calls_by_kind: Vec<(String, u32)>,
+    /// Distinct files changed by agents
+    pub files_modified: u32,
+    /// Per-agent totals, most used first
+    pub agents: Vec<AgentUsage>,
+    /// Sum of task durations
+    pub total_session_secs: i64,
+}
+
+impl UsageStats {
+    pub fn total_prompts(&self) -> u32 {
+        self.prompts_per_day.iter().map(|(_, count)| count).sum()
+    }
+
+    pub fn total_tool_calls(&self) -> u32 {
+        self.tool_calls_by_kind.iter().map(|(_, count)| count).sum()
+    }
+}
+
+/// One agent's share of [`UsageStats`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentUsage {
+    pub agent_id: String,
+    pub tasks: u32,
+    pub prompts: u32,
+    pub tool_calls: u32,
+    pub session_secs: i64,
+}
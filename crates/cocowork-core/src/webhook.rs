@@ -0,0 +1,240 @@
+//! Webhook notifications
+//!
+//! A [`Webhook`] is a URL that gets a JSON [`WebhookPayload`] POSTed to it
+//! when one of its events happens: a turn finishing, the agent asking for
+//! permission, or the agent going away mid-turn. With a secret, the body is
+//! signed with HMAC-SHA256 and the hex digest sent as
+//! `X-CocoWork-Signature: sha256=<digest>`, so receivers can check the
+//! request came from here. Requests go through the app's proxy settings.
+
+use crate::error::{Error, Result};
+use crate::net::ProxySettings;
+use crate::types::StopReason;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// How long a receiver gets to answer before the delivery is given up
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+/// Longest summary sent in a payload, in characters
+pub const MAX_SUMMARY_CHARS: usize = 2000;
+
+/// Something a webhook can be told about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// A turn ended, however it ended
+    TurnComplete,
+    /// The agent asked to do something that needs approval
+    PermissionRequest,
+    /// The agent's connection dropped while a turn was running
+    AgentCrash,
+}
+
+impl WebhookEvent {
+    pub const ALL: [WebhookEvent; 3] = [
+        WebhookEvent::TurnComplete,
+        WebhookEvent::PermissionRequest,
+        WebhookEvent::AgentCrash,
+    ];
+
+    /// The name used in payloads and the `X-CocoWork-Event` header
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::TurnComplete => "turn_complete",
+            WebhookEvent::PermissionRequest => "permission_request",
+            WebhookEvent::AgentCrash => "agent_crash",
+        }
+    }
+}
+
+/// A URL notified about some events
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: String,
+    pub url: String,
+    /// Key payloads are signed with, if any
+    #[serde(default)]
+    pub secret: Option<String>,
+    pub events: Vec<WebhookEvent>,
+}
+
+impl Webhook {
+    /// A webhook for `url`, which must be an http or https URL
+    pub fn new(url: &str, secret: Option<String>, events: Vec<WebhookEvent>) -> Result<Self> {
+        let url = url.trim();
+        let parsed = reqwest::Url::parse(url).map_err(|e| Error::InvalidConfig(format!("invalid URL: {}", e)))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(Error::InvalidConfig("webhook URLs must start with http:// or https://".to_string()));
+        }
+        if events.is_empty() {
+            return Err(Error::InvalidConfig("pick at least one event".to_string()));
+        }
+        Ok(Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            url: url.to_string(),
+            secret: secret.filter(|secret| !secret.is_empty()),
+            events,
+        })
+    }
+
+    pub fn wants(&self, event: WebhookEvent) -> bool {
+        self.events.contains(&event)
+    }
+}
+
+/// The JSON body POSTed to a webhook
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WebhookPayload {
+    pub event: WebhookEvent,
+    pub session_id: String,
+    pub agent_id: String,
+    pub working_dir: String,
+    /// How the turn ended, for `turn_complete`
+    pub stop_reason: Option<StopReason>,
+    /// What happened in a sentence or two: the end of the agent's reply, the
+    /// command that needs approval, or the error
+    pub summary: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl WebhookPayload {
+    pub fn new(
+        event: WebhookEvent,
+        session_id: impl Into<String>,
+        agent_id: impl Into<String>,
+        working_dir: impl Into<String>,
+    ) -> Self {
+        Self {
+            event,
+            session_id: session_id.into(),
+            agent_id: agent_id.into(),
+            working_dir: working_dir.into(),
+            stop_reason: None,
+            summary: String::new(),
+            timestamp: Utc::now(),
+        }
+    }
+
+    pub fn with_stop_reason(mut self, stop_reason: Option<StopReason>) -> Self {
+        self.stop_reason = stop_reason;
+        self
+    }
+
+    /// Set the summary, keeping its end if it's too long
+    pub fn with_summary(mut self, summary: &str) -> Self {
+        let summary = summary.trim();
+        let count = summary.chars().count();
+        self.summary = if count > MAX_SUMMARY_CHARS {
+            let tail: String = summary.chars().skip(count - (MAX_SUMMARY_CHARS - 1)).collect();
+            format!("…{}", tail)
+        } else {
+            summary.to_string()
+        };
+        self
+    }
+}
+
+/// The `X-CocoWork-Signature` value for a body signed with `secret`
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    format!("sha256={}", hex::encode(hmac_sha256(secret.as_bytes(), body)))
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; 64];
+    if key.len() > block.len() {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let inner = Sha256::new()
+        .chain_update(block.map(|b| b ^ 0x36))
+        .chain_update(message)
+        .finalize();
+    Sha256::new()
+        .chain_update(block.map(|b| b ^ 0x5c))
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+/// POST `payload` to each of `hooks` that wants its event
+///
+/// Deliveries run one after another and failures are only logged, so a
+/// receiver that's down never holds up or fails the session. Nothing is sent
+/// in offline mode.
+pub async fn deliver_webhooks(hooks: &[Webhook], payload: &WebhookPayload, proxy: &ProxySettings) {
+    let hooks: Vec<&Webhook> = hooks.iter().filter(|hook| hook.wants(payload.event)).collect();
+    if hooks.is_empty() {
+        return;
+    }
+    if crate::net::is_offline() {
+        debug!("Offline mode is on, so not sending {} webhooks", payload.event.as_str());
+        return;
+    }
+    let client = match proxy.client() {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("Not sending webhooks: {}", e);
+            return;
+        }
+    };
+    let body = match serde_json::to_vec(payload) {
+        Ok(body) => body,
+        Err(e) => {
+            warn!("Not sending webhooks: {}", e);
+            return;
+        }
+    };
+
+    for hook in hooks {
+        let mut request = client
+            .post(&hook.url)
+            .timeout(DELIVERY_TIMEOUT)
+            .header("content-type", "application/json")
+            .header("x-cocowork-event", payload.event.as_str());
+        if let Some(secret) = &hook.secret {
+            request = request.header("x-cocowork-signature", sign_payload(secret, &body));
+        }
+        match request.body(body.clone()).send().await.and_then(|r| r.error_for_status()) {
+            Ok(_) => debug!("Sent {} webhook to {}", payload.event.as_str(), hook.url),
+            Err(e) => warn!("Webhook {} failed: {}", hook.url, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_payload() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign_payload("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_webhook_and_payload() {
+        assert!(Webhook::new("ftp://example.com", None, vec![WebhookEvent::TurnComplete]).is_err());
+        assert!(Webhook::new("https://example.com/hook", None, vec![]).is_err());
+        let hook = Webhook::new(" https://example.com/hook ", Some(String::new()), vec![WebhookEvent::AgentCrash])
+            .unwrap();
+        assert_eq!(hook.url, "https://example.com/hook");
+        assert_eq!(hook.secret, None);
+        assert!(hook.wants(WebhookEvent::AgentCrash) && !hook.wants(WebhookEvent::TurnComplete));
+
+        let payload = WebhookPayload::new(WebhookEvent::TurnComplete, "s1", "claude-code", "/work")
+            .with_stop_reason(Some(StopReason::EndTurn))
+            .with_summary(&"x".repeat(MAX_SUMMARY_CHARS + 10));
+        assert_eq!(payload.summary.chars().count(), MAX_SUMMARY_CHARS);
+        assert!(payload.summary.starts_with('…'));
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(json["event"], "turn_complete");
+        assert_eq!(json["stop_reason"], "end_turn");
+    }
+}
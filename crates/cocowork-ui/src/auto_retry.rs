@@ -0,0 +1,153 @@
+//! Sending failed commands back to the agent
+//!
+//! With auto-retry on, a turn that ends after one of its commands failed (an
+//! execute tool call that failed or exited non-zero) is followed by a prompt
+//! quoting the failure and asking the agent to fix it. The loop ends once a
+//! turn finishes without a failed command, after the set number of attempts,
+//! or when the user stops it.
+
+use crate::follow_ups::turn_tool_calls;
+use crate::tool_render::{output_text, quote_block};
+use cocowork_core::{MessageBlock, TaskState, ToolCallKind, ToolCallState, ToolCallStatus};
+use serde_json::Value;
+
+/// Most lines of a failed command's output quoted back, keeping the last ones
+pub const MAX_FAILURE_LINES: usize = 60;
+
+/// A command the agent ran that failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandFailure {
+    /// The command line, or the tool call's title when the input doesn't name one
+    pub command: String,
+    pub exit_code: Option<i64>,
+    pub output: String,
+}
+
+/// Where a session's auto-retry loop is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryLoop {
+    /// Retries sent so far
+    pub attempt: u32,
+    pub limit: u32,
+    /// The last retry still ended with a failed command, so no more are sent
+    pub gave_up: bool,
+}
+
+/// Commands that failed in the last turn, in the order they ran
+pub fn failed_commands(messages: &[MessageBlock], task: &TaskState) -> Vec<CommandFailure> {
+    turn_tool_calls(messages, task)
+        .into_iter()
+        .filter(|call| matches!(call.kind, Some(ToolCallKind::Execute | ToolCallKind::Bash | ToolCallKind::Terminal)))
+        .filter_map(|call| {
+            let exit_code = exit_code(call);
+            let failed = call.status == ToolCallStatus::Failed || exit_code.is_some_and(|code| code != 0);
+            failed.then(|| CommandFailure {
+                command: command_line(call),
+                exit_code,
+                output: output_text(call).unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+/// The prompt for retry `attempt` of `limit`, quoting the tail of each failure's output
+pub fn retry_prompt(failures: &[CommandFailure], attempt: u32, limit: u32) -> String {
+    let mut prompt = format!("[Auto-retry {}/{}] ", attempt, limit);
+    prompt.push_str(if failures.len() == 1 { "This command failed:" } else { "These commands failed:" });
+    for failure in failures {
+        prompt.push_str(&format!("\n\n`{}`", failure.command));
+        if let Some(code) = failure.exit_code {
+            prompt.push_str(&format!(" exited with code {}", code));
+        }
+        let lines: Vec<&str> = failure.output.trim_end().lines().collect();
+        if !lines.is_empty() {
+            let first = lines.len().saturating_sub(MAX_FAILURE_LINES);
+            prompt.push('\n');
+            prompt.push_str(&quote_block(&failure.output, Some((first, lines.len() - 1))));
+        }
+    }
+    prompt.push_str("\n\nFix the cause and run it again.");
+    prompt
+}
+
+/// Exit code in a tool call's raw output, under the names agents use for it
+fn exit_code(call: &ToolCallState) -> Option<i64> {
+    let output = call.output.as_ref()?;
+    ["exit_code", "exitCode", "returncode"]
+        .iter()
+        .find_map(|key| output.get(key)?.as_i64())
+}
+
+fn command_line(call: &ToolCallState) -> String {
+    let command = call.input.as_ref().and_then(|input| match input.get("command")? {
+        Value::String(line) => Some(line.clone()),
+        Value::Array(words) => Some(words.iter().filter_map(Value::as_str).collect::<Vec<_>>().join(" ")),
+        _ => None,
+    });
+    command
+        .filter(|command| !command.trim().is_empty())
+        .or_else(|| call.title.clone())
+        .unwrap_or_else(|| call.id.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cocowork_core::ContentBlock;
+    use serde_json::json;
+
+    fn execute(id: &str, status: ToolCallStatus, input: Value, output: Value) -> ToolCallState {
+        let mut call = ToolCallState::new(id.into(), Some(format!("Run {}", id)), Some(ToolCallKind::Execute));
+        call.status = status;
+        call.input = Some(input);
+        call.output = Some(output);
+        call
+    }
+
+    #[test]
+    fn test_failed_commands() {
+        let messages = vec![MessageBlock::user(vec![ContentBlock::Text { text: "Make the tests pass".into() }])];
+        let mut task = TaskState::new("t".into(), "s".into(), "a".into(), Vec::new(), "/repo".into());
+        let calls = [
+            execute("1", ToolCallStatus::Completed, json!({ "command": "cargo build" }), json!({ "exitCode": 0 })),
+            execute(
+                "2",
+                ToolCallStatus::Completed,
+                json!({ "command": ["cargo", "test"] }),
+                json!({ "exit_code": 101, "output": "test parse ... FAILED\n" }),
+            ),
+            execute("3", ToolCallStatus::Failed, json!({}), json!("command not found: rg")),
+        ];
+        for call in calls {
+            task.tool_calls.insert(call.id.clone(), call);
+        }
+
+        let failures = failed_commands(&messages, &task);
+        assert_eq!(
+            failures,
+            vec![
+                CommandFailure {
+                    command: "cargo test".into(),
+                    exit_code: Some(101),
+                    output: "test parse ... FAILED\n".into(),
+                },
+                CommandFailure { command: "Run 3".into(), exit_code: None, output: "command not found: rg".into() },
+            ]
+        );
+        assert_eq!(
+            retry_prompt(&failures[..1], 1, 3),
+            "[Auto-retry 1/3] This command failed:\n\n`cargo test` exited with code 101\n\
+             ```\ntest parse ... FAILED\n```\n\nFix the cause and run it again."
+        );
+    }
+
+    #[test]
+    fn test_long_output_keeps_the_end() {
+        let output: String = (1..=100).map(|n| format!("line {}\n", n)).collect();
+        let failure = CommandFailure { command: "make".into(), exit_code: Some(2), output };
+        let prompt = retry_prompt(&[failure], 2, 3);
+        assert!(!prompt.contains("line 40\n"));
+        assert!(prompt.contains("```\nline 41\n"));
+        assert!(prompt.contains("line 100\n```"));
+    }
+}
@@ -0,0 +1,360 @@
+//! Just enough HTTP/1.1 and WebSocket for the local API and shared threads
+//!
+//! Each connection carries one request and is answered with
+//! `Connection: close`, so a streamed response simply ends when the
+//! connection does. WebSocket messages must fit in one frame; continuation
+//! frames are ignored.
+
+use base64::Engine;
+use serde::Serialize;
+use std::io;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Largest request body or WebSocket message accepted
+pub const MAX_BODY: usize = 1 << 20;
+/// Most header lines read before a request is refused
+const MAX_HEADERS: usize = 100;
+/// Longest request line or header line read before a request is refused
+const MAX_LINE: usize = 8 * 1024;
+/// Appended to the client's key in the WebSocket handshake (RFC 6455)
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+pub const OPCODE_TEXT: u8 = 0x1;
+pub const OPCODE_CLOSE: u8 = 0x8;
+pub const OPCODE_PING: u8 = 0x9;
+pub const OPCODE_PONG: u8 = 0xA;
+
+/// A parsed request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    /// The path without its query string
+    pub path: String,
+    query: String,
+    /// Header names are lowercased
+    headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// A query parameter, as given; values aren't percent-decoded
+    pub fn query(&self, name: &str) -> Option<&str> {
+        self.query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+    }
+
+    pub fn bearer_token(&self) -> Option<&str> {
+        self.header("authorization")?.strip_prefix("Bearer ").map(str::trim)
+    }
+
+    pub fn wants_websocket(&self) -> bool {
+        self.header("upgrade").is_some_and(|value| value.eq_ignore_ascii_case("websocket"))
+    }
+}
+
+/// Read one request, or `None` if the connection closed before sending one
+///
+/// A malformed request is an `InvalidData` error.
+pub async fn read_request(reader: &mut (impl AsyncBufRead + Unpin)) -> io::Result<Option<Request>> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+    let mut line = String::new();
+    if read_line(reader, &mut line).await? == 0 {
+        return Ok(None);
+    }
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target), Some(_version)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(invalid("malformed request line"));
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let mut request = Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        query: query.to_string(),
+        headers: Vec::new(),
+        body: Vec::new(),
+    };
+
+    loop {
+        line.clear();
+        if read_line(reader, &mut line).await? == 0 {
+            return Err(invalid("connection closed in headers"));
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if request.headers.len() == MAX_HEADERS {
+            return Err(invalid("too many headers"));
+        }
+        let (name, value) = header.split_once(':').ok_or_else(|| invalid("malformed header"))?;
+        request.headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+    }
+
+    let length = match request.header("content-length") {
+        Some(value) => value.parse::<usize>().map_err(|_| invalid("malformed Content-Length"))?,
+        None => 0,
+    };
+    if length > MAX_BODY {
+        return Err(invalid("request body too large"));
+    }
+    request.body = vec![0; length];
+    reader.read_exact(&mut request.body).await?;
+    Ok(Some(request))
+}
+
+/// Read one line into `line`, refusing one longer than [`MAX_LINE`] rather
+/// than buffering whatever the peer sends
+async fn read_line(reader: &mut (impl AsyncBufRead + Unpin), line: &mut String) -> io::Result<usize> {
+    let read = reader.take(MAX_LINE as u64 + 1).read_line(line).await?;
+    if read > MAX_LINE {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "line too long"));
+    }
+    Ok(read)
+}
+
+/// A complete response, JSON unless made with [`Response::html`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    pub fn json(status: u16, value: &impl Serialize) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: serde_json::to_string(value).unwrap_or_default(),
+        }
+    }
+
+    pub fn html(status: u16, body: &str) -> Self {
+        Self { status, content_type: "text/html; charset=utf-8", body: body.to_string() }
+    }
+
+    /// A response with body `{"error": message}`
+    pub fn error(status: u16, message: &str) -> Self {
+        Self::json(status, &serde_json::json!({ "error": message }))
+    }
+
+    pub async fn write_to(&self, writer: &mut (impl AsyncWrite + Unpin)) -> io::Result<()> {
+        let head = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.status,
+            reason(self.status),
+            self.content_type,
+            self.body.len()
+        );
+        writer.write_all(head.as_bytes()).await?;
+        writer.write_all(self.body.as_bytes()).await?;
+        writer.flush().await
+    }
+}
+
+/// Start a `200 OK` response whose body runs until the connection closes
+pub async fn write_stream_head(writer: &mut (impl AsyncWrite + Unpin), content_type: &str) -> io::Result<()> {
+    let head = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+        content_type
+    );
+    writer.write_all(head.as_bytes()).await?;
+    writer.flush().await
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        202 => "Accepted",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        415 => "Unsupported Media Type",
+        _ => "Internal Server Error",
+    }
+}
+
+/// The `Sec-WebSocket-Accept` value for a client's `Sec-WebSocket-Key`
+pub fn websocket_accept(key: &str) -> String {
+    let digest = sha1(format!("{}{}", key.trim(), WEBSOCKET_GUID).as_bytes());
+    base64::engine::general_purpose::STANDARD.encode(digest)
+}
+
+/// Switch the connection to WebSocket
+pub async fn write_websocket_handshake(writer: &mut (impl AsyncWrite + Unpin), key: &str) -> io::Result<()> {
+    let head = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+         Sec-WebSocket-Accept: {}\r\n\r\n",
+        websocket_accept(key)
+    );
+    writer.write_all(head.as_bytes()).await?;
+    writer.flush().await
+}
+
+/// A server frame, which is never masked
+pub fn encode_frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        len if len < 126 => frame.push(len as u8),
+        len if len <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// A frame from the client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    Text(String),
+    Ping(Vec<u8>),
+    Close,
+    /// Binary, pong and continuation frames, which the API doesn't use
+    Other,
+}
+
+/// Read one client frame, unmasking its payload
+pub async fn read_frame(reader: &mut (impl AsyncRead + Unpin)) -> io::Result<Frame> {
+    let mut head = [0u8; 2];
+    reader.read_exact(&mut head).await?;
+    let opcode = head[0] & 0x0F;
+    let masked = head[1] & 0x80 != 0;
+    let len = match head[1] & 0x7F {
+        126 => reader.read_u16().await? as usize,
+        127 => reader.read_u64().await? as usize,
+        len => len as usize,
+    };
+    if len > MAX_BODY {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "message too large"));
+    }
+    let mut mask = [0u8; 4];
+    if masked {
+        reader.read_exact(&mut mask).await?;
+    }
+    let mut payload = vec![0; len];
+    reader.read_exact(&mut payload).await?;
+    if masked {
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+    }
+
+    Ok(match opcode {
+        OPCODE_TEXT => Frame::Text(String::from_utf8_lossy(&payload).into_owned()),
+        OPCODE_PING => Frame::Ping(payload),
+        OPCODE_CLOSE => Frame::Close,
+        _ => Frame::Other,
+    })
+}
+
+/// SHA-1, which the WebSocket handshake requires; not used for anything else
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (chunk, state) in digest.chunks_mut(4).zip(h) {
+        chunk.copy_from_slice(&state.to_be_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_request() {
+        let raw = b"POST /sessions/s1/prompt?limit=5&x=1 HTTP/1.1\r\nHost: localhost\r\n\
+                    Authorization: Bearer secret\r\nContent-Length: 15\r\n\r\n{\"text\": \"hi\"}\n";
+        let request = read_request(&mut &raw[..]).await.unwrap().unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/sessions/s1/prompt");
+        assert_eq!(request.query("limit"), Some("5"));
+        assert_eq!(request.header("HOST"), Some("localhost"));
+        assert_eq!(request.bearer_token(), Some("secret"));
+        assert_eq!(request.body, b"{\"text\": \"hi\"}\n");
+        assert!(!request.wants_websocket());
+
+        assert!(read_request(&mut &b""[..]).await.unwrap().is_none());
+        let error = read_request(&mut &b"GET\r\n\r\n"[..]).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        // A line that never ends is refused instead of read into memory
+        let endless = tokio::io::BufReader::new(tokio::io::repeat(b'a'));
+        let error = read_request(&mut Box::pin(endless)).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let mut header = b"GET / HTTP/1.1\r\nX-Long: ".to_vec();
+        header.resize(MAX_LINE * 2, b'a');
+        let error = read_request(&mut &header[..]).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_websocket() {
+        // The example from RFC 6455
+        assert_eq!(websocket_accept("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+        assert_eq!(encode_frame(OPCODE_TEXT, b"Hello"), b"\x81\x05Hello");
+        assert_eq!(&encode_frame(OPCODE_TEXT, &[b'a'; 300])[..4], &[0x81, 126, 1, 44]);
+
+        // A masked "Hello" from the client, also from RFC 6455
+        let masked = [0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58];
+        assert_eq!(read_frame(&mut &masked[..]).await.unwrap(), Frame::Text("Hello".to_string()));
+        assert_eq!(read_frame(&mut &[0x88, 0x00][..]).await.unwrap(), Frame::Close);
+    }
+}
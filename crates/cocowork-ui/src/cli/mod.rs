@@ -0,0 +1,360 @@
+//! Command-line interface
+//!
+//! `cocowork` with no arguments opens the app. With a subcommand it works
+//! straight from the database and agents, so it works without the UI running:
+//!
+//! ```text
+//! cocowork sessions list [--limit <n>]
+//! cocowork sessions show <id>
+//! cocowork sessions export <id> [--format md|html|json]
+//! cocowork run [--agent <id>] [--cwd <dir>] [--approve] [--json] <prompt>
+//! cocowork serve [--port <n>] [--token <token>] [--approve]
+//! ```
+
+mod http;
+mod run;
+mod serve;
+mod share;
+
+pub use run::{run_prompt, ChunkRole, RunEvent, RunOptions, SCHEMA_VERSION};
+pub use serve::{serve, ServeOptions, DEFAULT_PORT};
+pub use share::ShareServer;
+
+use crate::acp_integration::{load_config, open_storage, unlock_storage};
+use cocowork_core::{ContentBlock, ExportFormat, KeychainStore, MessageBlock, Storage, TaskSummary, ThreadExport};
+use std::io::{IsTerminal, Read, Write};
+use std::path::PathBuf;
+
+/// Help printed for `cocowork help` and after a usage error
+pub const USAGE: &str = "\
+Usage:
+  cocowork                                   Open the app
+  cocowork sessions list [--limit <n>]       List stored sessions, newest first
+  cocowork sessions show <id>                Print a session's transcript
+  cocowork sessions export <id> [--format md|html|json]
+                                             Print a session as a document (default md)
+  cocowork run [options] <prompt>            Send a prompt in a new session and print the reply
+      --agent <id>                           Agent to use (default claude-code)
+      --cwd <dir>                            Workspace for the agent (default the current directory)
+      --approve                              Approve permission requests instead of denying them
+      --json                                 Print NDJSON events instead of text
+  cocowork serve [options]                   Serve an HTTP API for editors and scripts on 127.0.0.1
+      --port <n>                             Port to listen on (default 7421)
+      --token <token>                        Require this bearer token (default $COCOWORK_API_TOKEN,
+                                             or a random one printed at startup)
+      --approve                              Approve permission requests instead of denying them
+  cocowork help                              Show this help
+
+<id> is a task or session ID, or enough of its first characters to pick one.
+With no <prompt>, run reads it from stdin.";
+
+/// Agent `run` uses when no `--agent` is given
+const DEFAULT_RUN_AGENT: &str = "claude-code";
+
+/// Sessions listed when no `--limit` is given
+const DEFAULT_LIST_LIMIT: usize = 20;
+/// Longest title shown in the session list
+const MAX_TITLE_CHARS: usize = 60;
+
+/// What the command line asked for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Help,
+    ListSessions { limit: usize },
+    ShowSession { id: String },
+    ExportSession { id: String, format: ExportFormat },
+    Run(RunOptions),
+    Serve(ServeOptions),
+}
+
+/// The command in `args`, without the program name, or `None` to open the app
+///
+/// Arguments starting with `-` that aren't ours are left for the platform,
+/// which passes some when launching apps.
+pub fn parse_args(args: &[String]) -> Result<Option<Command>, String> {
+    let mut args = args.iter().map(String::as_str);
+    let command = match args.next() {
+        None => return Ok(None),
+        Some("help" | "-h" | "--help") => Command::Help,
+        Some("sessions") => match args.next() {
+            Some("list") => {
+                let mut limit = DEFAULT_LIST_LIMIT;
+                while let Some(arg) = args.next() {
+                    match arg {
+                        "--limit" | "-n" => {
+                            limit = args
+                                .next()
+                                .and_then(|value| value.parse().ok())
+                                .ok_or("--limit needs a number")?;
+                        }
+                        _ => return Err(format!("unexpected argument '{}'", arg)),
+                    }
+                }
+                Command::ListSessions { limit }
+            }
+            Some("show") => Command::ShowSession { id: session_id(args.next())? },
+            Some("export") => {
+                let id = session_id(args.next())?;
+                let mut format = ExportFormat::Markdown;
+                while let Some(arg) = args.next() {
+                    match arg {
+                        "--format" | "-f" => {
+                            format = args
+                                .next()
+                                .and_then(ExportFormat::from_name)
+                                .ok_or("--format must be md, html or json")?;
+                        }
+                        _ => return Err(format!("unexpected argument '{}'", arg)),
+                    }
+                }
+                Command::ExportSession { id, format }
+            }
+            Some(other) => return Err(format!("unknown sessions command '{}'", other)),
+            None => return Err("sessions needs a command: list, show or export".to_string()),
+        },
+        Some("run") => {
+            let mut options = RunOptions {
+                prompt: String::new(),
+                agent_id: DEFAULT_RUN_AGENT.to_string(),
+                cwd: PathBuf::from("."),
+                approve: false,
+                json: false,
+            };
+            let mut words = Vec::new();
+            while let Some(arg) = args.next() {
+                match arg {
+                    "--agent" | "-a" => options.agent_id = args.next().ok_or("--agent needs an agent ID")?.to_string(),
+                    "--cwd" | "-C" => options.cwd = PathBuf::from(args.next().ok_or("--cwd needs a directory")?),
+                    "--approve" => options.approve = true,
+                    "--json" => options.json = true,
+                    "--" => words.extend(args.by_ref()),
+                    _ if arg.starts_with('-') => return Err(format!("unexpected argument '{}'", arg)),
+                    _ => words.push(arg),
+                }
+            }
+            options.prompt = words.join(" ");
+            Command::Run(options)
+        }
+        Some("serve") => {
+            let mut options = ServeOptions { port: DEFAULT_PORT, token: None, approve: false };
+            while let Some(arg) = args.next() {
+                match arg {
+                    "--port" | "-p" => {
+                        options.port = args
+                            .next()
+                            .and_then(|value| value.parse().ok())
+                            .ok_or("--port needs a port number")?;
+                    }
+                    "--token" => options.token = Some(args.next().ok_or("--token needs a token")?.to_string()),
+                    "--approve" => options.approve = true,
+                    _ => return Err(format!("unexpected argument '{}'", arg)),
+                }
+            }
+            Command::Serve(options)
+        }
+        Some(arg) if arg.starts_with('-') => return Ok(None),
+        Some(other) => return Err(format!("unknown command '{}'", other)),
+    };
+    Ok(Some(command))
+}
+
+fn session_id(arg: Option<&str>) -> Result<String, String> {
+    arg.filter(|id| !id.starts_with('-'))
+        .map(str::to_string)
+        .ok_or_else(|| "missing session ID".to_string())
+}
+
+/// Run `command` against the stored sessions, printing to stdout
+pub fn run(command: Command) -> Result<(), String> {
+    let open_storage = || {
+        let storage = open_storage(&load_config()).map_err(|e| format!("can't open the database: {}", e))?;
+        unlock_storage(&storage, &KeychainStore::new());
+        Ok::<_, String>(storage)
+    };
+
+    let output = match command {
+        Command::Help => format!("{}\n", USAGE),
+        Command::ListSessions { limit } => format_session_list(&list_sessions(&open_storage()?, limit)?),
+        Command::ShowSession { id } => {
+            let color = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+            load_session(&open_storage()?, &id)?.to_terminal(color)
+        }
+        Command::ExportSession { id, format } => load_session(&open_storage()?, &id)?.render(format),
+        Command::Run(mut options) => {
+            if options.prompt.trim().is_empty() && !std::io::stdin().is_terminal() {
+                std::io::stdin().read_to_string(&mut options.prompt).map_err(|e| e.to_string())?;
+            }
+            if options.prompt.trim().is_empty() {
+                return Err("run needs a prompt".to_string());
+            }
+            return run_prompt(options);
+        }
+        Command::Serve(mut options) => {
+            if options.token.is_none() {
+                options.token = std::env::var("COCOWORK_API_TOKEN").ok().filter(|token| !token.is_empty());
+            }
+            return serve(options);
+        }
+    };
+    print(&output)
+}
+
+/// Write to stdout, stopping quietly if it's closed, as when piped to `head`
+fn print(text: &str) -> Result<(), String> {
+    match std::io::stdout().lock().write_all(text.as_bytes()) {
+        Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => Err(e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// The newest `limit` sessions, each with its title
+fn list_sessions(storage: &Storage, limit: usize) -> Result<Vec<(TaskSummary, String)>, String> {
+    let conn = storage.connection().map_err(|e| e.to_string())?;
+    let cipher = storage.cipher();
+    let sessions = cocowork_core::storage::list_tasks(&conn, cipher.as_deref(), limit, 0).map_err(|e| e.to_string())?;
+    Ok(sessions
+        .into_iter()
+        .map(|session| {
+            // A session that can't be decrypted is still listed, by its stored preview
+            let messages = cocowork_core::storage::get_task_messages(&conn, cipher.as_deref(), &session.id)
+                .unwrap_or_default();
+            let title = title_from(&session, &messages);
+            (session, title)
+        })
+        .collect())
+}
+
+/// The session `id` picks out, with its messages and tool calls
+fn load_session(storage: &Storage, id: &str) -> Result<ThreadExport, String> {
+    let conn = storage.connection().map_err(|e| e.to_string())?;
+    let cipher = storage.cipher();
+    let cipher = cipher.as_deref();
+    let mut matches = cocowork_core::storage::find_tasks(&conn, cipher, id).map_err(|e| e.to_string())?;
+    let session = match matches.len() {
+        0 => return Err(format!("no session matches '{}'", id)),
+        1 => matches.remove(0),
+        n => return Err(format!("'{}' matches {} sessions; give more of the ID", id, n)),
+    };
+    let messages = cocowork_core::storage::get_task_messages(&conn, cipher, &session.id).map_err(|e| e.to_string())?;
+    let tool_calls =
+        cocowork_core::storage::get_task_tool_calls(&conn, cipher, &session.id).map_err(|e| e.to_string())?;
+    let title = title_from(&session, &messages);
+    Ok(ThreadExport::new(title, messages, tool_calls)
+        .with_agent(session.agent_name)
+        .with_working_dir(session.working_dir))
+}
+
+/// The first line of the session's first prompt
+fn title_from(session: &TaskSummary, messages: &[MessageBlock]) -> String {
+    let first_prompt = messages.iter().find_map(|message| match message {
+        MessageBlock::User { content, .. } => content.iter().find_map(|block| match block {
+            ContentBlock::Text { text } => text.lines().map(str::trim).find(|line| !line.is_empty()),
+            _ => None,
+        }),
+        _ => None,
+    });
+    let title = first_prompt.unwrap_or(session.prompt_preview.trim());
+    if title.is_empty() {
+        "Untitled session".to_string()
+    } else {
+        title.to_string()
+    }
+}
+
+/// One row per session: short ID, last update, agent, status and title
+fn format_session_list(rows: &[(TaskSummary, String)]) -> String {
+    if rows.is_empty() {
+        return "No stored sessions\n".to_string();
+    }
+    let agent_width = rows.iter().map(|(session, _)| session.agent_name.len()).max().unwrap_or(0).max(5);
+    let mut out = format!("{:<8}  {:<16}  {:<agent_width$}  {:<10}  TITLE\n", "ID", "UPDATED", "AGENT", "STATUS");
+    for (session, title) in rows {
+        let title = if title.chars().count() > MAX_TITLE_CHARS {
+            format!("{}…", title.chars().take(MAX_TITLE_CHARS - 1).collect::<String>())
+        } else {
+            title.clone()
+        };
+        out.push_str(&format!(
+            "{:<8}  {:<16}  {:<agent_width$}  {:<10}  {}\n",
+            session.id.chars().take(8).collect::<String>(),
+            session.updated_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+            session.agent_name,
+            format!("{:?}", session.status).to_lowercase(),
+            title,
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cocowork_core::TaskState;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse_args(&[]), Ok(None));
+        assert_eq!(parse_args(&args("-psn_0_12345")), Ok(None));
+        assert_eq!(parse_args(&args("--help")), Ok(Some(Command::Help)));
+        assert_eq!(
+            parse_args(&args("sessions list")),
+            Ok(Some(Command::ListSessions { limit: DEFAULT_LIST_LIMIT }))
+        );
+        assert_eq!(parse_args(&args("sessions list --limit 5")), Ok(Some(Command::ListSessions { limit: 5 })));
+        assert_eq!(
+            parse_args(&args("sessions export 3f2a --format json")),
+            Ok(Some(Command::ExportSession { id: "3f2a".to_string(), format: ExportFormat::Json }))
+        );
+        assert!(parse_args(&args("sessions show")).is_err());
+        assert!(parse_args(&args("sessions export 3f2a --format pdf")).is_err());
+        assert!(parse_args(&args("sesions list")).is_err());
+
+        let run = parse_args(&args("run --json --agent codex fix the -- --flaky test"));
+        let Ok(Some(Command::Run(options))) = run else {
+            panic!("expected a run command");
+        };
+        assert_eq!(options.prompt, "fix the --flaky test");
+        assert_eq!(options.agent_id, "codex");
+        assert_eq!(options.cwd, PathBuf::from("."));
+        assert!(options.json && !options.approve);
+        assert!(parse_args(&args("run --cwd")).is_err());
+        assert!(parse_args(&args("run --verbose hi")).is_err());
+        assert_eq!(
+            parse_args(&args("serve --port 9000 --token abc")),
+            Ok(Some(Command::Serve(ServeOptions { port: 9000, token: Some("abc".to_string()), approve: false })))
+        );
+        assert!(parse_args(&args("serve --port http")).is_err());
+    }
+
+    #[test]
+    fn test_load_and_list_sessions() {
+        let storage = Storage::in_memory().unwrap();
+        let conn = storage.connection().unwrap();
+        let task = TaskState::new(
+            "3f2a9c1b-task".to_string(),
+            "session-1".to_string(),
+            "claude-code".to_string(),
+            vec![],
+            "/work/app".to_string(),
+        );
+        cocowork_core::storage::insert_task(&conn, None, &task).unwrap();
+        let prompt = MessageBlock::user(vec![ContentBlock::Text { text: "\nFix the login bug\nIt 500s".to_string() }]);
+        cocowork_core::storage::insert_message(&conn, None, &task.id, &prompt, 0).unwrap();
+        drop(conn);
+
+        let export = load_session(&storage, "3f2a").unwrap();
+        assert_eq!(export.title, "Fix the login bug");
+        assert_eq!(export.working_dir.as_deref(), Some("/work/app"));
+        assert_eq!(export.messages.len(), 1);
+        assert!(load_session(&storage, "nope").is_err());
+
+        let list = format_session_list(&list_sessions(&storage, 10).unwrap());
+        let row = list.lines().nth(1).unwrap();
+        assert!(row.starts_with("3f2a9c1b  "));
+        assert!(row.contains("  claude-code  pending     Fix the login bug"));
+    }
+}
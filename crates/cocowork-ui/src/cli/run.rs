@@ -0,0 +1,407 @@
+//! Running one prompt from the command line
+//!
+//! `cocowork run` starts the agent in the given directory, sends the prompt in
+//! a new session and exits when the turn ends. The reply is printed as text,
+//! or with `--json` as one [`RunEvent`] per line, each carrying the
+//! [`SCHEMA_VERSION`], for other tools to read. Nobody is there to answer
+//! permission requests, so they're denied unless `--approve` is given. The
+//! webhooks set up in the app are told about the run as they would be there.
+
+use crate::acp_integration::{load_adapters, load_config, open_storage, unlock_storage};
+use cocowork_core::{
+    AgentAdapterRegistry, AgentClientDelegate, AgentConnection, CommandConfirmation, ContentBlock, KeychainStore,
+    PermissionManager, PromptMessage, ProxySettings, SecretStore, SecurityLevel, SessionNotification, SessionUpdate,
+    StopReason, Storage, ToolCallKind, ToolCallStatus, Webhook, WebhookEvent, WebhookPayload,
+};
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc, RwLock};
+use tokio::task::JoinHandle;
+
+/// Version of the `--json` event schema, bumped when a field changes meaning
+/// or goes away; new events and fields don't bump it
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// What `cocowork run` was asked to do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunOptions {
+    pub prompt: String,
+    pub agent_id: String,
+    /// Workspace the agent works in; the current directory unless `--cwd` is given
+    pub cwd: PathBuf,
+    /// Approve permission requests instead of denying them
+    pub approve: bool,
+    /// Print NDJSON events instead of text
+    pub json: bool,
+}
+
+/// Who wrote a message chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkRole {
+    /// A prompt, seen only by viewers of a shared thread
+    User,
+    Agent,
+    Thought,
+}
+
+/// Something that happened during the run, printed as one line with `--json`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RunEvent {
+    SessionStarted {
+        session_id: String,
+        agent_id: String,
+        cwd: String,
+    },
+    MessageChunk {
+        session_id: String,
+        role: ChunkRole,
+        text: String,
+    },
+    /// A tool call starting or changing status; `title` and `kind` are only
+    /// known when it starts
+    ToolCall {
+        session_id: String,
+        tool_call_id: String,
+        title: Option<String>,
+        kind: Option<ToolCallKind>,
+        status: ToolCallStatus,
+    },
+    /// A command or file operation the policy wanted approved, and the answer given
+    PermissionRequest {
+        session_id: String,
+        operation: String,
+        target: String,
+        reason: String,
+        approved: bool,
+    },
+    TurnComplete {
+        session_id: String,
+        stop_reason: StopReason,
+    },
+    /// The run or turn failed; the last event of a run
+    Error {
+        message: String,
+    },
+}
+
+/// One line of `--json` output
+#[derive(Serialize)]
+struct EventLine<'a> {
+    schema: u32,
+    #[serde(flatten)]
+    event: &'a RunEvent,
+}
+
+impl RunEvent {
+    /// The event as a line of NDJSON, with the schema version
+    pub fn to_json_line(&self) -> String {
+        let line = EventLine { schema: SCHEMA_VERSION, event: self };
+        format!("{}\n", serde_json::to_string(&line).unwrap_or_default())
+    }
+
+    /// The event for a notification about `session_id`, or `None` for ones scripts don't see
+    pub fn from_notification(session_id: &str, notification: SessionNotification) -> Option<Self> {
+        let SessionNotification::Update(notification) = notification else {
+            return None;
+        };
+        if notification.session_id != session_id {
+            return None;
+        }
+        Self::from_update(session_id, notification.update)
+    }
+
+    /// The event for a session update, or `None` for updates scripts don't see
+    pub fn from_update(session_id: &str, update: SessionUpdate) -> Option<Self> {
+        let session_id = session_id.to_string();
+        match update {
+            SessionUpdate::AgentMessageChunk { content: ContentBlock::Text { text } } => {
+                Some(Self::MessageChunk { session_id, role: ChunkRole::Agent, text })
+            }
+            SessionUpdate::Thought { content: ContentBlock::Text { text } } => {
+                Some(Self::MessageChunk { session_id, role: ChunkRole::Thought, text })
+            }
+            SessionUpdate::ToolCall { tool_call_id, title, kind, status } => {
+                Some(Self::ToolCall { session_id, tool_call_id, title, kind, status })
+            }
+            SessionUpdate::ToolCallUpdate { tool_call_id, status, .. } => {
+                Some(Self::ToolCall { session_id, tool_call_id, title: None, kind: None, status })
+            }
+            _ => None,
+        }
+    }
+
+    /// The event as plain text, and whether it goes to stdout rather than stderr
+    fn to_text(&self) -> Option<(String, bool)> {
+        match self {
+            Self::MessageChunk { role: ChunkRole::Agent, text, .. } => Some((text.clone(), true)),
+            Self::ToolCall { title: Some(title), .. } => Some((format!("⏺ {}\n", title), false)),
+            Self::PermissionRequest { operation, target, approved, .. } => {
+                let answer = if *approved { "approved" } else { "denied (pass --approve to allow)" };
+                Some((format!("{} {}: {}\n", operation, target, answer), false))
+            }
+            Self::TurnComplete { stop_reason: StopReason::EndTurn, .. } => Some(("\n".to_string(), true)),
+            Self::TurnComplete { stop_reason, .. } => Some((format!("\nstopped: {:?}\n", stop_reason), false)),
+            _ => None,
+        }
+    }
+}
+
+/// Send the prompt and print what happens until the turn ends
+pub fn run_prompt(options: RunOptions) -> Result<(), String> {
+    let result = tokio::runtime::Runtime::new()
+        .map_err(|e| e.to_string())
+        .and_then(|runtime| runtime.block_on(prompt_agent(&options)));
+    match result {
+        Err(message) if options.json => {
+            emit(&options, &RunEvent::Error { message: message.clone() });
+            Err(message)
+        }
+        result => result,
+    }
+}
+
+/// The database and agent adapters, set up as the app sets them up
+pub(super) fn open_headless() -> Result<(Arc<Storage>, AgentAdapterRegistry), String> {
+    let config = load_config();
+    let storage = Arc::new(open_storage(&config).map_err(|e| format!("can't open the database: {}", e))?);
+    if storage.is_read_only() {
+        eprintln!("note: another CocoWork instance is using the database, so this run won't be saved");
+    }
+    let secrets: Arc<dyn SecretStore> = Arc::new(KeychainStore::new());
+    unlock_storage(&storage, secrets.as_ref());
+    let adapters = load_adapters(&storage, &secrets, &config);
+    Ok((storage, adapters))
+}
+
+/// Start `agent_id` with access to `cwd`, sending permission requests to `confirmations`
+///
+/// `cwd` should already be canonical, as the policy scope is matched on it.
+pub(super) async fn connect_agent(
+    storage: &Arc<Storage>,
+    adapters: &AgentAdapterRegistry,
+    permissions: &Arc<RwLock<PermissionManager>>,
+    confirmations: mpsc::UnboundedSender<CommandConfirmation>,
+    agent_id: &str,
+    cwd: &Path,
+) -> Result<Arc<dyn AgentConnection>, String> {
+    permissions.write().await.grant_access(cwd, SecurityLevel::default()).map_err(|e| e.to_string())?;
+    let delegate = Arc::new(
+        AgentClientDelegate::new(Arc::clone(permissions), Arc::clone(storage))
+            .with_command_confirmation(confirmations)
+            .with_policy_scope(agent_id.to_string(), cwd.to_path_buf()),
+    );
+    let profile = storage
+        .connection()
+        .and_then(|conn| cocowork_core::storage::list_env_profiles(&conn, cwd))
+        .ok()
+        .and_then(|profiles| profiles.into_iter().find(|p| p.is_default));
+    adapters
+        .connect_with_profile(agent_id, Some(cwd), delegate, profile.as_ref())
+        .await
+        .map_err(|e| format!("can't start {}: {}", agent_id, e))
+}
+
+/// Sends what happens in one agent's sessions to the configured webhooks
+#[derive(Clone)]
+pub(super) struct WebhookNotifier {
+    hooks: Arc<[Webhook]>,
+    proxy: ProxySettings,
+    agent_id: String,
+    cwd: String,
+}
+
+impl WebhookNotifier {
+    pub(super) fn load(storage: &Storage, agent_id: &str, cwd: &Path) -> Self {
+        let (hooks, proxy) = storage
+            .connection()
+            .and_then(|conn| {
+                Ok((cocowork_core::storage::get_webhooks(&conn)?, cocowork_core::storage::get_proxy_settings(&conn)?))
+            })
+            .unwrap_or_default();
+        Self { hooks: hooks.into(), proxy, agent_id: agent_id.to_string(), cwd: cwd.display().to_string() }
+    }
+
+    /// Deliver a payload in the background if any webhook wants `event`
+    pub(super) fn send(
+        &self,
+        session_id: &str,
+        event: WebhookEvent,
+        build: impl FnOnce(WebhookPayload) -> WebhookPayload,
+    ) -> Option<JoinHandle<()>> {
+        if !self.hooks.iter().any(|hook| hook.wants(event)) {
+            return None;
+        }
+        let payload = build(WebhookPayload::new(event, session_id, &self.agent_id, &self.cwd));
+        let hooks = Arc::clone(&self.hooks);
+        let proxy = self.proxy.clone();
+        Some(tokio::spawn(async move {
+            cocowork_core::deliver_webhooks(&hooks, &payload, &proxy).await;
+        }))
+    }
+
+    /// Tell the webhooks about a permission request and the answer given
+    pub(super) fn permission_request(&self, event: &RunEvent) -> Option<JoinHandle<()>> {
+        let RunEvent::PermissionRequest { session_id, operation, target, reason, approved } = event else {
+            return None;
+        };
+        let answer = if *approved { "approved" } else { "denied" };
+        let summary = format!("{} {}: {} ({})", operation, target, reason, answer);
+        self.send(session_id, WebhookEvent::PermissionRequest, |payload| payload.with_summary(&summary))
+    }
+
+    /// Tell the webhooks how a turn ended: its stop reason and reply, or the error that ended it
+    pub(super) fn turn_end(
+        &self,
+        session_id: &str,
+        result: &Result<StopReason, String>,
+        reply: &str,
+    ) -> Option<JoinHandle<()>> {
+        match result {
+            Ok(stop_reason) => self.send(session_id, WebhookEvent::TurnComplete, |payload| {
+                payload.with_stop_reason(Some(*stop_reason)).with_summary(reply)
+            }),
+            Err(error) => self.send(session_id, WebhookEvent::AgentCrash, |payload| payload.with_summary(error)),
+        }
+    }
+}
+
+async fn prompt_agent(options: &RunOptions) -> Result<(), String> {
+    let (storage, adapters) = open_headless()?;
+    let cwd = std::fs::canonicalize(&options.cwd).map_err(|e| format!("{}: {}", options.cwd.display(), e))?;
+    let permissions = Arc::new(RwLock::new(PermissionManager::new()));
+    let (confirmation_tx, mut confirmations) = mpsc::unbounded_channel();
+    let connection =
+        connect_agent(&storage, &adapters, &permissions, confirmation_tx, &options.agent_id, &cwd).await?;
+    let mut updates = connection.subscribe_updates();
+    let webhooks = WebhookNotifier::load(&storage, &options.agent_id, &cwd);
+    let mut deliveries = Vec::new();
+
+    let result = async {
+        let session = connection.new_session(cwd.clone(), Vec::new()).await.map_err(|e| e.to_string())?;
+        let session_id = session.session_id;
+        emit(
+            options,
+            &RunEvent::SessionStarted {
+                session_id: session_id.clone(),
+                agent_id: options.agent_id.clone(),
+                cwd: cwd.display().to_string(),
+            },
+        );
+
+        let message = PromptMessage::new(vec![ContentBlock::Text { text: options.prompt.clone() }]);
+        let prompt = connection.prompt(session_id.clone(), message);
+        tokio::pin!(prompt);
+
+        let mut reply = String::new();
+        let mut updates_open = true;
+        loop {
+            tokio::select! {
+                result = &mut prompt => {
+                    while let Ok(notification) = updates.try_recv() {
+                        forward_update(options, &session_id, notification, &mut reply);
+                    }
+                    let result = result.map(|r| r.stop_reason).map_err(|e| e.to_string());
+                    deliveries.extend(webhooks.turn_end(&session_id, &result, &reply));
+                    emit(options, &RunEvent::TurnComplete { session_id, stop_reason: result? });
+                    return Ok(());
+                }
+                notification = updates.recv(), if updates_open => match notification {
+                    Ok(notification) => forward_update(options, &session_id, notification, &mut reply),
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => updates_open = false,
+                },
+                Some(request) = confirmations.recv() => {
+                    let event = RunEvent::PermissionRequest {
+                        session_id: request.session_id.clone(),
+                        operation: request.operation.as_str().to_string(),
+                        target: request.command_line.clone(),
+                        reason: request.reason.clone(),
+                        approved: options.approve,
+                    };
+                    emit(options, &event);
+                    deliveries.extend(webhooks.permission_request(&event));
+                    request.respond(options.approve);
+                }
+            }
+        }
+    }
+    .await;
+
+    // The agent is stopped either way; a failure here doesn't change the outcome
+    let _ = connection.terminate().await;
+    // Let the webhooks hear about the run before the runtime goes away
+    for delivery in deliveries {
+        let _ = delivery.await;
+    }
+    result
+}
+
+/// Print a session update, collecting the agent's reply for webhooks
+fn forward_update(options: &RunOptions, session_id: &str, notification: SessionNotification, reply: &mut String) {
+    if let Some(event) = RunEvent::from_notification(session_id, notification) {
+        if let RunEvent::MessageChunk { role: ChunkRole::Agent, text, .. } = &event {
+            reply.push_str(text);
+        }
+        emit(options, &event);
+    }
+}
+
+/// Print an event as NDJSON or text, flushing so readers see it straight away
+fn emit(options: &RunOptions, event: &RunEvent) {
+    let (text, to_stdout) = if options.json {
+        (event.to_json_line(), true)
+    } else {
+        match event.to_text() {
+            Some(text) => text,
+            None => return,
+        }
+    };
+    // Nothing useful can be done if the reader has gone away
+    if to_stdout {
+        let mut stdout = std::io::stdout().lock();
+        let _ = stdout.write_all(text.as_bytes()).and_then(|_| stdout.flush());
+    } else {
+        let _ = std::io::stderr().lock().write_all(text.as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_lines() {
+        let chunk = RunEvent::from_update(
+            "s1",
+            SessionUpdate::AgentMessageChunk { content: ContentBlock::Text { text: "Hi".to_string() } },
+        )
+        .unwrap();
+        assert_eq!(
+            chunk.to_json_line(),
+            "{\"schema\":1,\"type\":\"message_chunk\",\"session_id\":\"s1\",\"role\":\"agent\",\"text\":\"Hi\"}\n"
+        );
+
+        let update = RunEvent::from_update(
+            "s1",
+            SessionUpdate::ToolCallUpdate {
+                tool_call_id: "t1".to_string(),
+                status: ToolCallStatus::Completed,
+                content: None,
+            },
+        )
+        .unwrap();
+        let line: serde_json::Value = serde_json::from_str(&update.to_json_line()).unwrap();
+        assert_eq!(line["type"], "tool_call");
+        assert_eq!(line["status"], "completed");
+        assert!(line["title"].is_null());
+
+        let done = RunEvent::TurnComplete { session_id: "s1".to_string(), stop_reason: StopReason::EndTurn };
+        let line: serde_json::Value = serde_json::from_str(&done.to_json_line()).unwrap();
+        assert_eq!(line["schema"], SCHEMA_VERSION);
+        assert_eq!(line["stop_reason"], "end_turn");
+    }
+}
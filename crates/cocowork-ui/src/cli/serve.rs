@@ -0,0 +1,586 @@
+//! Local HTTP API
+//!
+//! `cocowork serve` runs without the window and lets editors and scripts
+//! drive agents over HTTP on 127.0.0.1:
+//!
+//! ```text
+//! GET    /threads                 Stored sessions, newest first (?limit=<n>)
+//! GET    /sessions                Sessions started through the API
+//! POST   /sessions                Start one: {"agent_id": "codex", "cwd": "/path"}
+//! POST   /sessions/<id>/prompt    Send a prompt: {"text": "..."}
+//! GET    /sessions/<id>/events    Stream the session's events as NDJSON, or over
+//!                                 a WebSocket, where each text message is a prompt
+//! DELETE /sessions/<id>           Stop the session's agent
+//! ```
+//!
+//! Events are the [`RunEvent`]s `cocowork run --json` prints. Every request
+//! needs `Authorization: Bearer <token>`; without `--token` or
+//! `$COCOWORK_API_TOKEN`, a random token is printed at startup. So that web
+//! pages can't drive agents through the user's browser, requests must name
+//! localhost as their host, carry no `Origin`, and send bodies as JSON.
+//! Permission requests are answered like `run` answers them, and webhooks
+//! hear about them the same way.
+
+use super::http::{self, Frame, Request, Response};
+use super::run::{connect_agent, open_headless, WebhookNotifier};
+use super::{list_sessions, ChunkRole, RunEvent, DEFAULT_LIST_LIMIT, DEFAULT_RUN_AGENT};
+use cocowork_core::{
+    AgentAdapterRegistry, AgentConnection, CommandConfirmation, ContentBlock, PermissionManager, PromptMessage,
+    Storage,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io;
+use std::net::Ipv4Addr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use tokio::io::{AsyncWriteExt, BufReader};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc, RwLock};
+
+/// Port `serve` listens on when no `--port` is given
+pub const DEFAULT_PORT: u16 = 7421;
+/// Events kept for a slow stream before it starts missing some
+const EVENT_BUFFER: usize = 1024;
+
+/// What `cocowork serve` was asked to do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServeOptions {
+    pub port: u16,
+    /// Bearer token requests must carry; a random one is made if not given
+    pub token: Option<String>,
+    /// Approve permission requests instead of denying them
+    pub approve: bool,
+}
+
+/// A session started through the API
+struct LiveSession {
+    session_id: String,
+    agent_id: String,
+    cwd: PathBuf,
+    connection: Arc<dyn AgentConnection>,
+    events: broadcast::Sender<RunEvent>,
+    prompts: mpsc::UnboundedSender<String>,
+    /// Whether a turn is running; only one runs at a time
+    busy: Arc<AtomicBool>,
+    webhooks: WebhookNotifier,
+}
+
+impl LiveSession {
+    /// Queue a prompt, unless a turn is already running
+    fn start_prompt(&self, text: String) -> Result<(), Response> {
+        if text.trim().is_empty() {
+            return Err(Response::error(400, "the prompt is empty"));
+        }
+        if self.busy.swap(true, Ordering::SeqCst) {
+            return Err(Response::error(409, "a prompt is already running in this session"));
+        }
+        self.prompts.send(text).map_err(|_| Response::error(404, "the session has stopped"))
+    }
+}
+
+#[derive(Serialize)]
+struct SessionInfo<'a> {
+    session_id: &'a str,
+    agent_id: &'a str,
+    cwd: String,
+    busy: bool,
+}
+
+impl<'a> From<&'a LiveSession> for SessionInfo<'a> {
+    fn from(session: &'a LiveSession) -> Self {
+        Self {
+            session_id: &session.session_id,
+            agent_id: &session.agent_id,
+            cwd: session.cwd.display().to_string(),
+            busy: session.busy.load(Ordering::SeqCst),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ThreadInfo {
+    id: String,
+    session_id: String,
+    agent_id: String,
+    title: String,
+    status: String,
+    working_dir: String,
+    updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Deserialize)]
+struct NewSession {
+    agent_id: Option<String>,
+    cwd: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct Prompt {
+    text: String,
+}
+
+struct Server {
+    storage: Arc<Storage>,
+    adapters: AgentAdapterRegistry,
+    permissions: Arc<RwLock<PermissionManager>>,
+    confirmation_tx: mpsc::UnboundedSender<CommandConfirmation>,
+    sessions: RwLock<HashMap<String, Arc<LiveSession>>>,
+    options: ServeOptions,
+    /// The port actually listened on, which requests must name in their `Host`
+    port: u16,
+}
+
+/// Serve the API until interrupted, then stop every session's agent
+pub fn serve(mut options: ServeOptions) -> Result<(), String> {
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    runtime.block_on(async {
+        let (storage, adapters) = open_headless()?;
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, options.port))
+            .await
+            .map_err(|e| format!("can't listen on port {}: {}", options.port, e))?;
+        let address = listener.local_addr().map_err(|e| e.to_string())?;
+        eprintln!("Listening on http://{}", address);
+        if options.token.is_none() {
+            let token = uuid::Uuid::new_v4().simple().to_string();
+            eprintln!("Token: {}", token);
+            options.token = Some(token);
+        }
+
+        let (confirmation_tx, mut confirmations) = mpsc::unbounded_channel();
+        let server = Arc::new(Server {
+            storage,
+            adapters,
+            permissions: Arc::new(RwLock::new(PermissionManager::new())),
+            confirmation_tx,
+            sessions: RwLock::new(HashMap::new()),
+            options,
+            port: address.port(),
+        });
+
+        let answering = Arc::clone(&server);
+        tokio::spawn(async move {
+            while let Some(request) = confirmations.recv().await {
+                answering.answer(request).await;
+            }
+        });
+
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => {
+                        let server = Arc::clone(&server);
+                        tokio::spawn(async move {
+                            if let Err(e) = server.handle(stream).await {
+                                tracing::debug!("API connection ended: {}", e);
+                            }
+                        });
+                    }
+                    Err(e) => tracing::warn!("Failed to accept API connection: {}", e),
+                },
+                _ = tokio::signal::ctrl_c() => break,
+            }
+        }
+
+        let sessions: Vec<_> = server.sessions.write().await.drain().map(|(_, session)| session).collect();
+        for session in sessions {
+            let _ = session.connection.terminate().await;
+        }
+        Ok(())
+    })
+}
+
+impl Server {
+    async fn handle(self: Arc<Self>, stream: TcpStream) -> io::Result<()> {
+        let (read, mut write) = stream.into_split();
+        let mut reader = BufReader::new(read);
+        let request = match http::read_request(&mut reader).await {
+            Ok(Some(request)) => request,
+            Ok(None) => return Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                return Response::error(400, &e.to_string()).write_to(&mut write).await;
+            }
+            Err(e) => return Err(e),
+        };
+        if let Err(response) = check_local(&request, self.port) {
+            return response.write_to(&mut write).await;
+        }
+        if !self.authorized(&request) {
+            return Response::error(401, "missing or wrong bearer token").write_to(&mut write).await;
+        }
+
+        let path = request.path.trim_matches('/').to_string();
+        let segments: Vec<&str> = path.split('/').collect();
+        let response = match (request.method.as_str(), segments.as_slice()) {
+            ("GET", ["threads"]) => self.list_threads(&request),
+            ("GET", ["sessions"]) => self.list_sessions().await,
+            ("POST", ["sessions"]) => self.create_session(&request).await,
+            ("POST", ["sessions", id, "prompt"]) => self.prompt(id, &request).await,
+            ("GET", ["sessions", id, "events"]) => {
+                return self.stream_events(id, &request, reader, write).await;
+            }
+            ("DELETE", ["sessions", id]) => self.stop_session(id).await,
+            (_, ["threads"] | ["sessions"] | ["sessions", _] | ["sessions", _, "prompt" | "events"]) => {
+                Response::error(405, "method not allowed")
+            }
+            _ => Response::error(404, "no such endpoint"),
+        };
+        response.write_to(&mut write).await
+    }
+
+    fn authorized(&self, request: &Request) -> bool {
+        match &self.options.token {
+            Some(token) => request.bearer_token() == Some(token.as_str()),
+            None => false,
+        }
+    }
+
+    async fn session(&self, id: &str) -> Option<Arc<LiveSession>> {
+        self.sessions.read().await.get(id).cloned()
+    }
+
+    fn list_threads(&self, request: &Request) -> Response {
+        let limit = request.query("limit").and_then(|value| value.parse().ok()).unwrap_or(DEFAULT_LIST_LIMIT);
+        match list_sessions(&self.storage, limit) {
+            Ok(rows) => {
+                let threads: Vec<ThreadInfo> = rows
+                    .into_iter()
+                    .map(|(session, title)| ThreadInfo {
+                        id: session.id,
+                        session_id: session.session_id,
+                        agent_id: session.agent_id,
+                        title,
+                        status: format!("{:?}", session.status).to_lowercase(),
+                        working_dir: session.working_dir,
+                        updated_at: session.updated_at,
+                    })
+                    .collect();
+                Response::json(200, &threads)
+            }
+            Err(e) => Response::error(500, &e),
+        }
+    }
+
+    async fn list_sessions(&self) -> Response {
+        let sessions = self.sessions.read().await;
+        let list: Vec<SessionInfo> = sessions.values().map(|session| SessionInfo::from(session.as_ref())).collect();
+        Response::json(200, &list)
+    }
+
+    async fn create_session(&self, request: &Request) -> Response {
+        let new: NewSession = match serde_json::from_slice(&request.body) {
+            Ok(new) => new,
+            Err(e) => return Response::error(400, &format!("expected {{\"cwd\": ...}}: {}", e)),
+        };
+        let agent_id = new.agent_id.unwrap_or_else(|| DEFAULT_RUN_AGENT.to_string());
+        let cwd = match std::fs::canonicalize(&new.cwd) {
+            Ok(cwd) => cwd,
+            Err(e) => return Response::error(400, &format!("{}: {}", new.cwd.display(), e)),
+        };
+        match self.start_session(agent_id, cwd).await {
+            Ok(session) => Response::json(201, &SessionInfo::from(session.as_ref())),
+            Err(e) => Response::error(500, &e),
+        }
+    }
+
+    /// Start the agent and a task that runs the session's prompts in order
+    async fn start_session(&self, agent_id: String, cwd: PathBuf) -> Result<Arc<LiveSession>, String> {
+        let connection = connect_agent(
+            &self.storage,
+            &self.adapters,
+            &self.permissions,
+            self.confirmation_tx.clone(),
+            &agent_id,
+            &cwd,
+        )
+        .await?;
+        let updates = connection.subscribe_updates();
+        let session_id = match connection.new_session(cwd.clone(), Vec::new()).await {
+            Ok(session) => session.session_id,
+            Err(e) => {
+                let _ = connection.terminate().await;
+                return Err(e.to_string());
+            }
+        };
+
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        let (prompts, prompt_rx) = mpsc::unbounded_channel();
+        let session = Arc::new(LiveSession {
+            session_id: session_id.clone(),
+            agent_id: agent_id.clone(),
+            cwd: cwd.clone(),
+            connection: Arc::clone(&connection),
+            events: events.clone(),
+            prompts,
+            busy: Arc::new(AtomicBool::new(false)),
+            webhooks: WebhookNotifier::load(&self.storage, &agent_id, &cwd),
+        });
+        let _ = events.send(RunEvent::SessionStarted {
+            session_id: session_id.clone(),
+            agent_id,
+            cwd: cwd.display().to_string(),
+        });
+        tokio::spawn(drive_session(
+            connection,
+            session_id.clone(),
+            updates,
+            prompt_rx,
+            events,
+            Arc::clone(&session.busy),
+            session.webhooks.clone(),
+        ));
+        self.sessions.write().await.insert(session_id, Arc::clone(&session));
+        Ok(session)
+    }
+
+    async fn prompt(&self, id: &str, request: &Request) -> Response {
+        let Some(session) = self.session(id).await else {
+            return Response::error(404, "no such session");
+        };
+        let prompt: Prompt = match serde_json::from_slice(&request.body) {
+            Ok(prompt) => prompt,
+            Err(e) => return Response::error(400, &format!("expected {{\"text\": ...}}: {}", e)),
+        };
+        match session.start_prompt(prompt.text) {
+            Ok(()) => Response::json(202, &SessionInfo::from(session.as_ref())),
+            Err(response) => response,
+        }
+    }
+
+    async fn stop_session(&self, id: &str) -> Response {
+        let Some(session) = self.sessions.write().await.remove(id) else {
+            return Response::error(404, "no such session");
+        };
+        // Ends a running turn too; the session's task then stops once it's dropped
+        let _ = session.connection.terminate().await;
+        Response::json(200, &SessionInfo::from(session.as_ref()))
+    }
+
+    async fn stream_events(
+        &self,
+        id: &str,
+        request: &Request,
+        reader: BufReader<OwnedReadHalf>,
+        mut write: OwnedWriteHalf,
+    ) -> io::Result<()> {
+        let Some(session) = self.session(id).await else {
+            return Response::error(404, "no such session").write_to(&mut write).await;
+        };
+        let mut events = session.events.subscribe();
+        // Only held weakly, so the stream ends once the session is stopped
+        let session = Arc::downgrade(&session);
+
+        if !request.wants_websocket() {
+            http::write_stream_head(&mut write, "application/x-ndjson").await?;
+            while let Some(event) = next_event(&mut events).await {
+                write.write_all(event.to_json_line().as_bytes()).await?;
+                write.flush().await?;
+            }
+            return Ok(());
+        }
+
+        let Some(key) = request.header("sec-websocket-key") else {
+            return Response::error(400, "missing Sec-WebSocket-Key").write_to(&mut write).await;
+        };
+        http::write_websocket_handshake(&mut write, key).await?;
+        let mut frames = read_frames(reader);
+        loop {
+            tokio::select! {
+                event = next_event(&mut events) => match event {
+                    Some(event) => {
+                        let line = event.to_json_line();
+                        write.write_all(&http::encode_frame(http::OPCODE_TEXT, line.trim_end().as_bytes())).await?;
+                    }
+                    None => break,
+                },
+                frame = frames.recv() => match frame {
+                    Some(Frame::Text(text)) => prompt_from_socket(&session, text),
+                    Some(Frame::Ping(payload)) => {
+                        write.write_all(&http::encode_frame(http::OPCODE_PONG, &payload)).await?;
+                    }
+                    Some(Frame::Other) => {}
+                    Some(Frame::Close) | None => break,
+                },
+            }
+        }
+        write.write_all(&http::encode_frame(http::OPCODE_CLOSE, &[])).await
+    }
+
+    /// Put a permission request on its session's stream and answer it
+    async fn answer(&self, request: CommandConfirmation) {
+        let approved = self.options.approve;
+        if let Some(session) = self.session(&request.session_id).await {
+            let event = RunEvent::PermissionRequest {
+                session_id: request.session_id.clone(),
+                operation: request.operation.as_str().to_string(),
+                target: request.command_line.clone(),
+                reason: request.reason.clone(),
+                approved,
+            };
+            session.webhooks.permission_request(&event);
+            let _ = session.events.send(event);
+        }
+        request.respond(approved);
+    }
+}
+
+/// Refuse what a web page could send: a `Host` other than this machine, as
+/// DNS rebinding gives, any `Origin`, or a body that isn't JSON, which a
+/// cross-site form can post without asking first
+fn check_local(request: &Request, port: u16) -> Result<(), Response> {
+    let host = request.header("host").unwrap_or_default();
+    let (name, host_port) = match host.rsplit_once(':') {
+        Some((name, host_port)) => (name, Some(host_port)),
+        None => (host, None),
+    };
+    let local = matches!(name, "localhost" | "127.0.0.1")
+        && host_port.map_or(true, |host_port| host_port.parse() == Ok(port));
+    if !local {
+        return Err(Response::error(403, "the host must be localhost"));
+    }
+    if request.header("origin").is_some_and(|origin| !origin.is_empty()) {
+        return Err(Response::error(403, "requests from web pages are refused"));
+    }
+    if request.method == "POST" {
+        let media_type = request.header("content-type").unwrap_or_default().split(';').next().unwrap_or_default();
+        if !media_type.trim().eq_ignore_ascii_case("application/json") {
+            return Err(Response::error(415, "the body must be application/json"));
+        }
+    }
+    Ok(())
+}
+
+/// Run each prompt as it arrives, forwarding the session's updates as events
+/// and telling the webhooks how each turn ended
+///
+/// Ends, stopping the agent, once the session is dropped.
+async fn drive_session(
+    connection: Arc<dyn AgentConnection>,
+    session_id: String,
+    mut updates: broadcast::Receiver<cocowork_core::SessionNotification>,
+    mut prompts: mpsc::UnboundedReceiver<String>,
+    events: broadcast::Sender<RunEvent>,
+    busy: Arc<AtomicBool>,
+    webhooks: WebhookNotifier,
+) {
+    let forward = |notification, reply: &mut String| {
+        if let Some(event) = RunEvent::from_notification(&session_id, notification) {
+            if let RunEvent::MessageChunk { role: ChunkRole::Agent, text, .. } = &event {
+                reply.push_str(text);
+            }
+            let _ = events.send(event);
+        }
+    };
+    // The agent's reply in the current turn
+    let mut reply = String::new();
+    let mut updates_open = true;
+    loop {
+        tokio::select! {
+            text = prompts.recv() => {
+                let Some(text) = text else { break };
+                reply.clear();
+                let message = PromptMessage::new(vec![ContentBlock::Text { text }]);
+                let prompt = connection.prompt(session_id.clone(), message);
+                tokio::pin!(prompt);
+                let result = loop {
+                    tokio::select! {
+                        result = &mut prompt => break result,
+                        notification = updates.recv(), if updates_open => match notification {
+                            Ok(notification) => forward(notification, &mut reply),
+                            Err(broadcast::error::RecvError::Lagged(_)) => {}
+                            Err(broadcast::error::RecvError::Closed) => updates_open = false,
+                        },
+                    }
+                };
+                while let Ok(notification) = updates.try_recv() {
+                    forward(notification, &mut reply);
+                }
+                let result = result.map(|r| r.stop_reason).map_err(|e| e.to_string());
+                webhooks.turn_end(&session_id, &result, &reply);
+                let _ = events.send(match result {
+                    Ok(stop_reason) => RunEvent::TurnComplete { session_id: session_id.clone(), stop_reason },
+                    Err(message) => RunEvent::Error { message },
+                });
+                busy.store(false, Ordering::SeqCst);
+            }
+            notification = updates.recv(), if updates_open => match notification {
+                Ok(notification) => forward(notification, &mut reply),
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => updates_open = false,
+            },
+        }
+    }
+    let _ = connection.terminate().await;
+}
+
+/// The next event, skipping any a slow reader missed, or `None` once the session is gone
+async fn next_event(events: &mut broadcast::Receiver<RunEvent>) -> Option<RunEvent> {
+    loop {
+        match events.recv().await {
+            Ok(event) => return Some(event),
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => return None,
+        }
+    }
+}
+
+/// Read WebSocket frames on their own task, as a read can't be cut short mid-frame
+fn read_frames(mut reader: BufReader<OwnedReadHalf>) -> mpsc::UnboundedReceiver<Frame> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while let Ok(frame) = http::read_frame(&mut reader).await {
+            if tx.send(frame).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// Send a WebSocket message as a prompt, reporting a refusal on the stream
+fn prompt_from_socket(session: &Weak<LiveSession>, text: String) {
+    let Some(session) = session.upgrade() else {
+        return;
+    };
+    if let Err(response) = session.start_prompt(text) {
+        let message = serde_json::from_str::<serde_json::Value>(&response.body)
+            .ok()
+            .and_then(|body| body["error"].as_str().map(str::to_string))
+            .unwrap_or(response.body);
+        let _ = session.events.send(RunEvent::Error { message });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn request(raw: &str) -> Request {
+        http::read_request(&mut raw.as_bytes()).await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_only_local_non_browser_requests() {
+        let status = |result: Result<(), Response>| result.err().map(|response| response.status);
+        let get = |host: &str| format!("GET /sessions HTTP/1.1\r\nHost: {}\r\n\r\n", host);
+        assert_eq!(status(check_local(&request(&get("127.0.0.1:7421")).await, 7421)), None);
+        assert_eq!(status(check_local(&request(&get("localhost")).await, 7421)), None);
+        assert_eq!(status(check_local(&request(&get("localhost:8080")).await, 7421)), Some(403));
+        assert_eq!(status(check_local(&request(&get("evil.example:7421")).await, 7421)), Some(403));
+        assert_eq!(status(check_local(&request("GET / HTTP/1.1\r\n\r\n").await, 7421)), Some(403));
+
+        let upgrade = "GET /sessions/s1/events HTTP/1.1\r\nHost: localhost:7421\r\nUpgrade: websocket\r\n\
+                       Origin: https://evil.example\r\n\r\n";
+        assert_eq!(status(check_local(&request(upgrade).await, 7421)), Some(403));
+
+        let post = |content_type: &str| {
+            format!("POST /sessions HTTP/1.1\r\nHost: localhost:7421\r\n{}Content-Length: 2\r\n\r\n{{}}", content_type)
+        };
+        assert_eq!(status(check_local(&request(&post("")).await, 7421)), Some(415));
+        assert_eq!(status(check_local(&request(&post("Content-Type: text/plain\r\n")).await, 7421)), Some(415));
+        let json = post("Content-Type: application/json; charset=utf-8\r\n");
+        assert_eq!(status(check_local(&request(&json).await, 7421)), None);
+    }
+}
@@ -0,0 +1,324 @@
+//! Watching a thread live from another machine
+//!
+//! Sharing a thread serves a read-only view of it over HTTP, so someone can
+//! open the link and follow the agent as it works. It's served on this
+//! machine only unless sharing on the local network is turned on:
+//!
+//! ```text
+//! GET /?key=<key>          The viewer page
+//! GET /events?key=<key>    The thread's events as server-sent events,
+//!                          starting with the latest ones so far
+//! ```
+//!
+//! Events are the [`RunEvent`]s `cocowork run --json` prints. The key is
+//! random and part of the link; requests without it are refused, and nothing
+//! sent to the server changes the thread. To watch from another machine
+//! without sharing on the network, put a tunnel such as `ssh -R` in front of
+//! the port.
+
+use super::http::{self, Response};
+use super::RunEvent;
+use std::collections::VecDeque;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncWriteExt, BufReader};
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, watch};
+
+/// Events kept for a slow viewer before it starts missing some
+const EVENT_BUFFER: usize = 1024;
+/// Most events replayed to a viewer who joins late; older ones are dropped
+const MAX_HISTORY: usize = 10_000;
+
+/// Page a viewer's browser opens; it reads the events from the same address
+const VIEWER_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>CocoWork shared thread</title>
+<style>
+body { font: 14px/1.5 -apple-system, system-ui, sans-serif; max-width: 760px; margin: 24px auto; padding: 0 16px; color: #ddd; background: #1e1e1e; }
+.user { background: #2d2d2d; border-radius: 8px; padding: 10px 14px; margin: 16px 0; }
+.agent, .thought { white-space: pre-wrap; margin: 12px 0; }
+.thought { color: #999; border-left: 2px solid #444; padding-left: 10px; }
+.tool, .status { color: #999; font-size: 12px; margin: 6px 0; }
+.error { color: #e66; }
+</style>
+</head>
+<body>
+<div class="status" id="status">Connecting…</div>
+<div id="thread"></div>
+<script>
+const thread = document.getElementById("thread");
+const status = document.getElementById("status");
+const tools = {};
+let last = null;
+function line(cls, text) {
+  const el = document.createElement("div");
+  el.className = cls;
+  el.textContent = text;
+  thread.appendChild(el);
+  last = null;
+  return el;
+}
+const source = new EventSource("events" + location.search);
+source.onopen = () => status.textContent = "Watching live · read-only";
+source.onerror = () => status.textContent = "Disconnected; the thread may have stopped being shared";
+source.onmessage = (message) => {
+  const event = JSON.parse(message.data);
+  if (event.type === "message_chunk") {
+    if (!last || last.className !== event.role) {
+      line(event.role, "");
+      last = thread.lastChild;
+    }
+    last.textContent += event.text;
+  } else if (event.type === "tool_call") {
+    const el = tools[event.tool_call_id] || (tools[event.tool_call_id] = line("tool", ""));
+    el.dataset.title = event.title || el.dataset.title || event.tool_call_id;
+    el.textContent = "⏺ " + el.dataset.title + " · " + event.status;
+  } else if (event.type === "turn_complete") {
+    line("status", "Turn ended: " + event.stop_reason);
+  } else if (event.type === "error") {
+    line("status error", event.message);
+  }
+  window.scrollTo(0, document.body.scrollHeight);
+};
+</script>
+</body>
+</html>
+"#;
+
+/// What a shared thread's connections read
+struct ShareState {
+    key: String,
+    /// The latest events published, replayed to viewers who join late
+    history: Mutex<VecDeque<RunEvent>>,
+    events: broadcast::Sender<RunEvent>,
+    /// Ends streams and the listener once the share stops
+    stopped: watch::Receiver<()>,
+}
+
+/// A thread being served for others to watch; serving stops when it's dropped
+pub struct ShareServer {
+    session_id: String,
+    address: SocketAddr,
+    state: Arc<ShareState>,
+    _stop: watch::Sender<()>,
+}
+
+impl ShareServer {
+    /// Serve `session_id` on `port` (0 picks a free one), starting the view with `history`
+    ///
+    /// Only this machine can connect unless `lan` is set, which serves on
+    /// every interface. Must be called from within a Tokio runtime.
+    pub fn start(session_id: &str, history: Vec<RunEvent>, port: u16, lan: bool) -> Result<Self, String> {
+        let bind = if lan { Ipv4Addr::UNSPECIFIED } else { Ipv4Addr::LOCALHOST };
+        let listener = std::net::TcpListener::bind((bind, port))
+            .and_then(|listener| listener.set_nonblocking(true).map(|_| listener))
+            .and_then(TcpListener::from_std)
+            .map_err(|e| format!("can't listen on port {}: {}", port, e))?;
+        let ip = if lan { lan_address() } else { IpAddr::V4(Ipv4Addr::LOCALHOST) };
+        let address = SocketAddr::new(ip, listener.local_addr().map_err(|e| e.to_string())?.port());
+        let mut history = VecDeque::from(history);
+        history.drain(..history.len().saturating_sub(MAX_HISTORY));
+
+        let (stop, stopped) = watch::channel(());
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        let state = Arc::new(ShareState {
+            key: uuid::Uuid::new_v4().simple().to_string(),
+            history: Mutex::new(history),
+            events,
+            stopped,
+        });
+
+        let serving = Arc::clone(&state);
+        tokio::spawn(async move {
+            let mut stopped = serving.stopped.clone();
+            loop {
+                tokio::select! {
+                    accepted = listener.accept() => match accepted {
+                        Ok((stream, _)) => {
+                            let state = Arc::clone(&serving);
+                            tokio::spawn(async move {
+                                if let Err(e) = handle(state, stream).await {
+                                    tracing::debug!("Share connection ended: {}", e);
+                                }
+                            });
+                        }
+                        Err(e) => tracing::warn!("Failed to accept share connection: {}", e),
+                    },
+                    _ = stopped.changed() => break,
+                }
+            }
+        });
+
+        Ok(Self { session_id: session_id.to_string(), address, state, _stop: stop })
+    }
+
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    /// Link to give viewers, on this machine's address in the local network
+    /// when shared there
+    pub fn url(&self) -> String {
+        format!("http://{}/?key={}", self.address, self.state.key)
+    }
+
+    /// Viewers watching right now
+    pub fn viewers(&self) -> usize {
+        self.state.events.receiver_count()
+    }
+
+    /// Show an event to current and later viewers
+    pub fn publish(&self, event: RunEvent) {
+        // Sent under the lock, so a viewer joining now sees it exactly once
+        let mut history = self.state.history.lock().unwrap_or_else(|e| e.into_inner());
+        if history.len() == MAX_HISTORY {
+            history.pop_front();
+        }
+        history.push_back(event.clone());
+        let _ = self.state.events.send(event);
+    }
+}
+
+async fn handle(state: Arc<ShareState>, stream: TcpStream) -> io::Result<()> {
+    let (read, mut write) = stream.into_split();
+    let mut reader = BufReader::new(read);
+    let request = match http::read_request(&mut reader).await {
+        Ok(Some(request)) => request,
+        Ok(None) => return Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+            return Response::error(400, &e.to_string()).write_to(&mut write).await;
+        }
+        Err(e) => return Err(e),
+    };
+    if request.query("key") != Some(state.key.as_str()) {
+        return Response::error(403, "this link is wrong or the thread is no longer shared")
+            .write_to(&mut write)
+            .await;
+    }
+
+    let response = match (request.method.as_str(), request.path.trim_matches('/')) {
+        ("GET", "") => Response::html(200, VIEWER_PAGE),
+        ("GET", "events") => return stream_events(&state, write).await,
+        (_, "" | "events") => Response::error(405, "a shared thread is read-only"),
+        _ => Response::error(404, "no such page"),
+    };
+    response.write_to(&mut write).await
+}
+
+/// Send everything so far, then each event as it's published, until the share stops
+async fn stream_events(state: &ShareState, mut write: OwnedWriteHalf) -> io::Result<()> {
+    let (history, mut events) = {
+        let history = state.history.lock().unwrap_or_else(|e| e.into_inner());
+        (history.clone(), state.events.subscribe())
+    };
+    let mut stopped = state.stopped.clone();
+
+    http::write_stream_head(&mut write, "text/event-stream").await?;
+    for event in &history {
+        write.write_all(server_sent_event(event).as_bytes()).await?;
+    }
+    write.flush().await?;
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => {
+                    write.write_all(server_sent_event(&event).as_bytes()).await?;
+                    write.flush().await?;
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            _ = stopped.changed() => break,
+        }
+    }
+    Ok(())
+}
+
+/// An event as a server-sent event message
+fn server_sent_event(event: &RunEvent) -> String {
+    format!("data: {}\n\n", event.to_json_line().trim_end())
+}
+
+/// This machine's address on the network it reaches others through
+///
+/// Connecting a UDP socket picks the outgoing interface without sending
+/// anything; with no network, viewers can only be on this machine.
+fn lan_address() -> IpAddr {
+    UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .and_then(|socket| socket.connect((Ipv4Addr::new(192, 0, 2, 1), 9)).map(|_| socket))
+        .and_then(|socket| socket.local_addr())
+        .map(|address| address.ip())
+        .ok()
+        .filter(|ip| !ip.is_unspecified())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::ChunkRole;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt};
+
+    fn chunk(text: &str) -> RunEvent {
+        RunEvent::MessageChunk { session_id: "s1".to_string(), role: ChunkRole::Agent, text: text.to_string() }
+    }
+
+    async fn get(port: u16, target: &str) -> BufReader<TcpStream> {
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).await.unwrap();
+        let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", target);
+        stream.write_all(request.as_bytes()).await.unwrap();
+        BufReader::new(stream)
+    }
+
+    #[tokio::test]
+    async fn test_share_streams_history_then_live_events() {
+        let share = ShareServer::start("s1", vec![chunk("Hello")], 0, false).unwrap();
+        assert!(share.url().starts_with("http://127.0.0.1:"));
+        let port = share.address.port();
+        let key = share.url().rsplit_once("key=").unwrap().1.to_string();
+
+        let mut refused = String::new();
+        get(port, "/events?key=wrong").await.read_to_string(&mut refused).await.unwrap();
+        assert!(refused.starts_with("HTTP/1.1 403"));
+
+        let mut events = get(port, &format!("/events?key={}", key)).await;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            events.read_line(&mut line).await.unwrap();
+            if line.starts_with("data: ") {
+                break;
+            }
+        }
+        assert!(line.contains("\"text\":\"Hello\""));
+
+        share.publish(chunk(" world"));
+        line.clear();
+        events.read_line(&mut line).await.unwrap();
+        line.clear();
+        events.read_line(&mut line).await.unwrap();
+        assert!(line.contains("\"text\":\" world\""));
+        assert_eq!(share.viewers(), 1);
+
+        // Stopping the share ends the stream
+        drop(share);
+        let mut rest = String::new();
+        events.read_to_string(&mut rest).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_history_keeps_latest_events() {
+        let history = (0..MAX_HISTORY).map(|i| chunk(&i.to_string())).collect();
+        let share = ShareServer::start("s1", history, 0, false).unwrap();
+        share.publish(chunk("last"));
+        let history = share.state.history.lock().unwrap();
+        assert_eq!(history.len(), MAX_HISTORY);
+        assert_eq!(history.front(), Some(&chunk("1")));
+        assert_eq!(history.back(), Some(&chunk("last")));
+    }
+}
@@ -27,6 +27,10 @@ pub enum IconName {
     Settings,
     Pencil,
 
+    // Layout
+    PanelLeft,
+    PanelRight,
+
     // Files
     File,
     Folder,
@@ -37,6 +41,7 @@ pub enum IconName {
     Search,
     Web,
     Play,
+    Chart,
 
     // Agents
     AiClaude,
@@ -65,6 +70,8 @@ impl IconName {
             IconName::CircleCheck => "icons/circle_check.svg",
             IconName::Settings => "icons/settings.svg",
             IconName::Pencil => "icons/pencil.svg",
+            IconName::PanelLeft => "icons/panel_left.svg",
+            IconName::PanelRight => "icons/panel_right.svg",
             IconName::File => "icons/file.svg",
             IconName::Folder => "icons/folder.svg",
             IconName::Plus => "icons/plus.svg",
@@ -72,6 +79,7 @@ impl IconName {
             IconName::Search => "icons/magnifying_glass.svg",
             IconName::Web => "icons/tool_web.svg",
             IconName::Play => "icons/play_outlined.svg",
+            IconName::Chart => "icons/chart_bar.svg",
             IconName::AiClaude => "icons/ai_claude.svg",
             IconName::AiGemini => "icons/ai_gemini.svg",
             IconName::Agent => "icons/zed_agent.svg",
@@ -120,11 +128,11 @@ impl IconSize {
 ///     .text_color(rgb(colors.text_secondary))
 /// ```
 pub fn svg_icon(name: IconName, size: IconSize) -> Svg {
-    let px_size = size.px();
-    // Load SVG via AssetSource (path relative to assets directory)
+    // Load SVG via AssetSource (path relative to assets directory); sized in
+    // rems so icons follow the UI scale
     svg()
         .path(name.path())
-        .size(px(px_size))
+        .size(size.rems())
         .flex_shrink_0()
 }
 
@@ -4,9 +4,13 @@
 
 pub mod icon;
 pub mod text_input;
+pub mod tooltip;
+pub mod vim;
 
 pub use icon::{svg_icon, IconName, IconSize, chevron, status, agent, tool};
 // Keep old exports for backward compatibility during migration
 #[allow(deprecated)]
 pub use icon::{icon, icons, OldIconSize};
-pub use text_input::{TextInput, register_bindings as register_text_input_bindings};
+pub use text_input::{approx_token_count, TextInput, TextInputEvent, register_bindings as register_text_input_bindings};
+pub use tooltip::Tooltip;
+pub use vim::VimMode;
@@ -1,7 +1,20 @@
 //! Text Input Component
 //!
 //! A proper text input using GPUI's ViewInputHandler pattern.
+//!
+//! Inputs are single-line by default. Multi-line inputs soft-wrap to their
+//! width, grow with their content, take Shift+Enter as a newline and emit
+//! [`TextInputEvent::Submit`] on Enter or Cmd/Ctrl+Enter.
+//!
+//! Input methods compose text in place: the platform marks the text being
+//! composed, which is drawn underlined until it's committed, and the candidate
+//! window is placed under it. Offsets exchanged with the platform are UTF-16;
+//! everything else works in UTF-8 byte offsets on grapheme boundaries.
+//!
+//! Any input can also take Vim-style modal editing, see [`super::vim`].
 
+use super::vim::{Vim, VimCommand, VimMode};
+use gpui::prelude::FluentBuilder;
 use gpui::*;
 use std::ops::Range;
 use unicode_segmentation::*;
@@ -13,17 +26,26 @@ actions!(
         Delete,
         Left,
         Right,
+        Up,
+        Down,
         SelectLeft,
         SelectRight,
+        SelectUp,
+        SelectDown,
         SelectAll,
         Home,
         End,
         Paste,
         Cut,
         Copy,
+        Newline,
+        Submit,
     ]
 );
 
+/// Key context added to multi-line inputs, for bindings only they take
+const MULTI_LINE_CONTEXT: &str = "MultiLineInput";
+
 /// Register key bindings for text input
 pub fn register_bindings(cx: &mut AppContext) {
     cx.bind_keys([
@@ -39,34 +61,71 @@ pub fn register_bindings(cx: &mut AppContext) {
         KeyBinding::new("cmd-x", Cut, Some("TextInput")),
         KeyBinding::new("home", Home, Some("TextInput")),
         KeyBinding::new("end", End, Some("TextInput")),
+        KeyBinding::new("up", Up, Some(MULTI_LINE_CONTEXT)),
+        KeyBinding::new("down", Down, Some(MULTI_LINE_CONTEXT)),
+        KeyBinding::new("shift-up", SelectUp, Some(MULTI_LINE_CONTEXT)),
+        KeyBinding::new("shift-down", SelectDown, Some(MULTI_LINE_CONTEXT)),
+        KeyBinding::new("shift-enter", Newline, Some(MULTI_LINE_CONTEXT)),
+        KeyBinding::new("enter", Submit, Some(MULTI_LINE_CONTEXT)),
+        KeyBinding::new("cmd-enter", Submit, Some(MULTI_LINE_CONTEXT)),
+        KeyBinding::new("ctrl-enter", Submit, Some(MULTI_LINE_CONTEXT)),
     ]);
 }
 
+/// Events emitted by a multi-line text input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextInputEvent {
+    /// Enter or Cmd/Ctrl+Enter was pressed
+    Submit,
+}
+
+/// Rough number of tokens a model would see for `text`
+///
+/// About four characters per token for English prose and code; good enough
+/// for a live counter, not for billing.
+pub fn approx_token_count(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// One visual row of text: a byte range of the shown text and its shaped glyphs
+struct Row {
+    range: Range<usize>,
+    line: ShapedLine,
+}
+
 /// Text input component
 pub struct TextInput {
     focus_handle: FocusHandle,
     content: SharedString,
     placeholder: SharedString,
+    multi_line: bool,
     selected_range: Range<usize>,
     selection_reversed: bool,
     marked_range: Option<Range<usize>>,
-    last_layout: Option<ShapedLine>,
+    last_rows: Vec<Row>,
+    last_line_height: Pixels,
     last_bounds: Option<Bounds<Pixels>>,
     is_selecting: bool,
+    vim: Option<Vim>,
 }
 
+impl EventEmitter<TextInputEvent> for TextInput {}
+
 impl TextInput {
     pub fn new(cx: &mut ViewContext<Self>) -> Self {
         Self {
             focus_handle: cx.focus_handle(),
             content: "".into(),
             placeholder: "Type here...".into(),
+            multi_line: false,
             selected_range: 0..0,
             selection_reversed: false,
             marked_range: None,
-            last_layout: None,
+            last_rows: Vec::new(),
+            last_line_height: px(0.),
             last_bounds: None,
             is_selecting: false,
+            vim: None,
         }
     }
 
@@ -74,6 +133,33 @@ impl TextInput {
         self.placeholder = text.into();
     }
 
+    /// Wrap long lines, keep pasted newlines and take Shift+Enter as a newline
+    pub fn set_multi_line(&mut self, multi_line: bool) {
+        self.multi_line = multi_line;
+    }
+
+    /// Turn Vim-style modal editing on or off; it starts in insert mode
+    pub fn set_vim_mode(&mut self, enabled: bool, cx: &mut ViewContext<Self>) {
+        if enabled != self.vim.is_some() {
+            self.vim = enabled.then(Vim::default);
+            cx.notify();
+        }
+    }
+
+    /// The Vim mode, if modal editing is on
+    pub fn vim_mode(&self) -> Option<VimMode> {
+        self.vim.as_ref().map(Vim::mode)
+    }
+
+    /// Keys of an unfinished Vim command, e.g. `d` before a motion
+    pub fn vim_pending_keys(&self) -> String {
+        self.vim.as_ref().map(Vim::pending_keys).unwrap_or_default()
+    }
+
+    fn in_vim_normal_mode(&self) -> bool {
+        self.vim_mode() == Some(VimMode::Normal)
+    }
+
     pub fn content(&self) -> &str {
         &self.content
     }
@@ -108,6 +194,23 @@ impl TextInput {
         }
     }
 
+    /// Move to the row above; on the first row the key goes on to the parent,
+    /// e.g. to recall an earlier prompt
+    fn up(&mut self, _: &Up, cx: &mut ViewContext<Self>) {
+        match self.vertical_offset(self.cursor_offset(), -1) {
+            Some(offset) => self.move_to(offset, cx),
+            None => cx.propagate(),
+        }
+    }
+
+    /// Move to the row below; on the last row the key goes on to the parent
+    fn down(&mut self, _: &Down, cx: &mut ViewContext<Self>) {
+        match self.vertical_offset(self.cursor_offset(), 1) {
+            Some(offset) => self.move_to(offset, cx),
+            None => cx.propagate(),
+        }
+    }
+
     fn select_left(&mut self, _: &SelectLeft, cx: &mut ViewContext<Self>) {
         self.select_to(self.previous_boundary(self.cursor_offset()), cx);
     }
@@ -116,6 +219,18 @@ impl TextInput {
         self.select_to(self.next_boundary(self.cursor_offset()), cx);
     }
 
+    fn select_up(&mut self, _: &SelectUp, cx: &mut ViewContext<Self>) {
+        let offset = self.vertical_offset(self.cursor_offset(), -1).unwrap_or(0);
+        self.select_to(offset, cx);
+    }
+
+    fn select_down(&mut self, _: &SelectDown, cx: &mut ViewContext<Self>) {
+        let offset = self
+            .vertical_offset(self.cursor_offset(), 1)
+            .unwrap_or(self.content.len());
+        self.select_to(offset, cx);
+    }
+
     fn select_all(&mut self, _: &SelectAll, cx: &mut ViewContext<Self>) {
         self.move_to(0, cx);
         self.select_to(self.content.len(), cx)
@@ -130,6 +245,11 @@ impl TextInput {
     }
 
     fn backspace(&mut self, _: &Backspace, cx: &mut ViewContext<Self>) {
+        // Like `h` in normal mode, as in Vim
+        if self.in_vim_normal_mode() {
+            self.move_to(self.previous_boundary(self.cursor_offset()), cx);
+            return;
+        }
         if self.selected_range.is_empty() {
             self.select_to(self.previous_boundary(self.cursor_offset()), cx)
         }
@@ -143,9 +263,22 @@ impl TextInput {
         self.replace_text_in_range(None, "", cx)
     }
 
+    fn newline(&mut self, _: &Newline, cx: &mut ViewContext<Self>) {
+        self.replace_text_in_range(None, "\n", cx)
+    }
+
+    fn submit(&mut self, _: &Submit, cx: &mut ViewContext<Self>) {
+        cx.emit(TextInputEvent::Submit);
+    }
+
     fn paste(&mut self, _: &Paste, cx: &mut ViewContext<Self>) {
         if let Some(text) = cx.read_from_clipboard().and_then(|item| item.text()) {
-            self.replace_text_in_range(None, &text.replace('\n', " "), cx);
+            let text = if self.multi_line {
+                text.replace("\r\n", "\n")
+            } else {
+                text.replace('\n', " ")
+            };
+            self.replace_text_in_range(None, &text, cx);
         }
     }
 
@@ -166,6 +299,49 @@ impl TextInput {
         }
     }
 
+    /// Escape and, in normal mode, typed characters go to the Vim layer
+    /// before they can insert text
+    fn on_key_down(&mut self, event: &KeyDownEvent, cx: &mut ViewContext<Self>) {
+        let cursor = self.cursor_offset();
+        // Keys during a composition belong to the input method
+        if self.marked_range.is_some() {
+            return;
+        }
+        let Some(vim) = self.vim.as_mut() else {
+            return;
+        };
+        let keystroke = &event.keystroke;
+        if keystroke.key == "escape" {
+            // Unhandled Escape goes on to close menus and the like
+            if let Some(offset) = vim.escape(&self.content, cursor) {
+                cx.stop_propagation();
+                self.move_to(offset, cx);
+            }
+            return;
+        }
+        let modifiers = &keystroke.modifiers;
+        if vim.mode() != VimMode::Normal || modifiers.control || modifiers.platform || modifiers.function {
+            return;
+        }
+        let Some(key) = typed_char(keystroke) else {
+            return;
+        };
+
+        cx.stop_propagation();
+        match vim.handle_key(key, &self.content, cursor) {
+            Some(VimCommand::Move(offset)) => self.move_to(offset, cx),
+            Some(VimCommand::Edit { range, text, cursor }) => {
+                self.content =
+                    (self.content[..range.start].to_owned() + &text + &self.content[range.end..]).into();
+                self.selected_range = cursor..cursor;
+                self.marked_range = None;
+                cx.notify();
+            }
+            // Mode or pending keys may have changed
+            None => cx.notify(),
+        }
+    }
+
     fn move_to(&mut self, offset: usize, cx: &mut ViewContext<Self>) {
         self.selected_range = offset..offset;
         cx.notify()
@@ -179,13 +355,51 @@ impl TextInput {
         }
     }
 
-    fn index_for_mouse_position(&self, position: Point<Pixels>) -> usize {
+    /// Index of the row the caret for `offset` is drawn on
+    ///
+    /// An offset where a wrapped row ends belongs to the next row, so the
+    /// caret shows at the start of the continuation.
+    fn row_for_offset(&self, offset: usize) -> Option<usize> {
+        self.last_rows.iter().enumerate().position(|(idx, row)| {
+            let continues = self
+                .last_rows
+                .get(idx + 1)
+                .is_some_and(|next| next.range.start == row.range.end);
+            offset >= row.range.start
+                && (offset < row.range.end || (offset == row.range.end && !continues))
+        })
+    }
+
+    /// Where the caret for `offset` goes, relative to the text's top left
+    fn position_for_offset(&self, offset: usize) -> Option<Point<Pixels>> {
+        let idx = self.row_for_offset(offset)?;
+        let row = &self.last_rows[idx];
+        Some(point(
+            row.line.x_for_index(offset - row.range.start),
+            self.last_line_height * idx as f32,
+        ))
+    }
+
+    /// The offset `rows` rows above (negative) or below the caret at `offset`,
+    /// keeping its horizontal position; `None` past the first or last row
+    fn vertical_offset(&self, offset: usize, rows: isize) -> Option<usize> {
+        // Rows of the placeholder aren't content rows
         if self.content.is_empty() {
+            return None;
+        }
+        let idx = self.row_for_offset(offset)?;
+        let target = self.last_rows.get(idx.checked_add_signed(rows)?)?;
+        let x = self.position_for_offset(offset)?.x;
+        let offset = target.range.start + target.line.closest_index_for_x(x);
+        Some(self.grapheme_boundary(offset.min(target.range.end)))
+    }
+
+    fn index_for_mouse_position(&self, position: Point<Pixels>) -> usize {
+        if self.content.is_empty() || self.last_rows.is_empty() {
             return 0;
         }
 
-        let (Some(bounds), Some(line)) = (self.last_bounds.as_ref(), self.last_layout.as_ref())
-        else {
+        let Some(bounds) = self.last_bounds.as_ref() else {
             return 0;
         };
 
@@ -196,11 +410,16 @@ impl TextInput {
             return self.content.len();
         }
 
-        line.closest_index_for_x(position.x - bounds.left())
+        let idx = ((position.y - bounds.top()) / self.last_line_height).floor().max(0.) as usize;
+        let row = &self.last_rows[idx.min(self.last_rows.len() - 1)];
+        let offset = row.range.start + row.line.closest_index_for_x(position.x - bounds.left());
+        self.grapheme_boundary(offset.min(row.range.end))
     }
 
     fn on_mouse_down(&mut self, event: &MouseDownEvent, cx: &mut ViewContext<Self>) {
         cx.focus(&self.focus_handle);
+        // Clicking away from a composition keeps what was composed so far
+        self.marked_range = None;
         self.is_selecting = true;
 
         if event.modifiers.shift {
@@ -234,18 +453,7 @@ impl TextInput {
     }
 
     fn offset_from_utf16(&self, offset: usize) -> usize {
-        let mut utf8_offset = 0;
-        let mut utf16_count = 0;
-
-        for ch in self.content.chars() {
-            if utf16_count >= offset {
-                break;
-            }
-            utf16_count += ch.len_utf16();
-            utf8_offset += ch.len_utf8();
-        }
-
-        utf8_offset
+        utf8_offset(&self.content, offset)
     }
 
     fn offset_to_utf16(&self, offset: usize) -> usize {
@@ -279,6 +487,20 @@ impl TextInput {
             .unwrap_or(0)
     }
 
+    /// `offset` moved back to the start of the grapheme it falls in, so the
+    /// caret never splits a character built from several code points
+    fn grapheme_boundary(&self, offset: usize) -> usize {
+        if offset >= self.content.len() {
+            return self.content.len();
+        }
+        self.content
+            .grapheme_indices(true)
+            .map(|(idx, _)| idx)
+            .take_while(|&idx| idx <= offset)
+            .last()
+            .unwrap_or(0)
+    }
+
     fn next_boundary(&self, offset: usize) -> usize {
         self.content
             .grapheme_indices(true)
@@ -369,11 +591,15 @@ impl ViewInputHandler for TextInput {
         } else {
             self.marked_range = None;
         }
+        // The new selection is given in UTF-16 within the marked text
         self.selected_range = new_selected_range_utf16
             .as_ref()
-            .map(|range_utf16| self.range_from_utf16(range_utf16))
-            .map(|new_range| new_range.start + range.start..new_range.end + range.end)
+            .map(|selected| {
+                range.start + utf8_offset(new_text, selected.start)
+                    ..range.start + utf8_offset(new_text, selected.end)
+            })
             .unwrap_or_else(|| range.start + new_text.len()..range.start + new_text.len());
+        self.selection_reversed = false;
 
         cx.notify();
     }
@@ -384,16 +610,23 @@ impl ViewInputHandler for TextInput {
         element_bounds: Bounds<Pixels>,
         _cx: &mut ViewContext<Self>,
     ) -> Option<Bounds<Pixels>> {
-        let last_layout = self.last_layout.as_ref()?;
+        // Rows are from the last frame, which may predate the latest keystroke;
+        // past their end, anchor to the end of the last row
+        let last_end = self.last_rows.last()?.range.end;
         let range = self.range_from_utf16(&range_utf16);
+        let range = range.start.min(last_end)..range.end.min(last_end);
+        let start = self.position_for_offset(range.start)?;
+        // Candidate windows anchor to the start row; a range wrapping onto the
+        // next row is cut at the end of the first
+        let end_x = self
+            .position_for_offset(range.end)
+            .filter(|end| end.y == start.y)
+            .map_or(element_bounds.size.width, |end| end.x);
         Some(Bounds::from_corners(
+            point(element_bounds.left() + start.x, element_bounds.top() + start.y),
             point(
-                element_bounds.left() + last_layout.x_for_index(range.start),
-                element_bounds.top(),
-            ),
-            point(
-                element_bounds.left() + last_layout.x_for_index(range.end),
-                element_bounds.bottom(),
+                element_bounds.left() + end_x,
+                element_bounds.top() + start.y + self.last_line_height,
             ),
         ))
     }
@@ -405,15 +638,103 @@ impl FocusableView for TextInput {
     }
 }
 
+/// Byte offset in `text` of a UTF-16 offset, rounded up to a whole character
+fn utf8_offset(text: &str, utf16_offset: usize) -> usize {
+    let mut utf8 = 0;
+    let mut utf16 = 0;
+    for ch in text.chars() {
+        if utf16 >= utf16_offset {
+            break;
+        }
+        utf16 += ch.len_utf16();
+        utf8 += ch.len_utf8();
+    }
+    utf8
+}
+
+/// The character a keystroke types, if it's a single one
+fn typed_char(keystroke: &Keystroke) -> Option<char> {
+    let text = keystroke.ime_key.as_deref().unwrap_or(&keystroke.key);
+    let mut chars = text.chars();
+    let key = chars.next()?;
+    if chars.next().is_some() {
+        // A named key such as "tab" or "space"
+        return None;
+    }
+    Some(if keystroke.modifiers.shift {
+        key.to_ascii_uppercase()
+    } else {
+        key
+    })
+}
+
+/// Byte offsets in `text` to start new rows at so no row is wider than `width`
+///
+/// Rows break after the last whitespace that fits, or mid-word when a word
+/// is wider than a whole row. `x_for_index` gives the unwrapped x position
+/// of a byte offset.
+fn wrap_offsets(text: &str, width: Pixels, x_for_index: impl Fn(usize) -> Pixels) -> Vec<usize> {
+    let mut breaks = Vec::new();
+    let mut row_start = 0;
+    let mut last_space = None;
+    for (idx, grapheme) in text.grapheme_indices(true) {
+        let end = idx + grapheme.len();
+        if idx > row_start && x_for_index(end) - x_for_index(row_start) > width {
+            let at = last_space.filter(|&space| space > row_start).unwrap_or(idx);
+            breaks.push(at);
+            row_start = at;
+            last_space = None;
+        }
+        if grapheme.chars().all(char::is_whitespace) {
+            last_space = Some(end);
+        }
+    }
+    breaks
+}
+
+/// Text runs covering `range` of the shown text, underlining the IME's marked text
+fn runs_for_range(range: &Range<usize>, run: &TextRun, marked_range: Option<&Range<usize>>) -> Vec<TextRun> {
+    let Some(marked) = marked_range.filter(|marked| marked.start < range.end && marked.end > range.start) else {
+        return vec![TextRun {
+            len: range.len(),
+            ..run.clone()
+        }];
+    };
+    let marked_start = marked.start.max(range.start);
+    let marked_end = marked.end.min(range.end);
+    vec![
+        TextRun {
+            len: marked_start - range.start,
+            ..run.clone()
+        },
+        TextRun {
+            len: marked_end - marked_start,
+            underline: Some(UnderlineStyle {
+                color: Some(run.color),
+                thickness: px(1.0),
+                wavy: false,
+            }),
+            ..run.clone()
+        },
+        TextRun {
+            len: range.end - marked_end,
+            ..run.clone()
+        },
+    ]
+    .into_iter()
+    .filter(|run| run.len > 0)
+    .collect()
+}
+
 /// Custom element for rendering text with cursor
 struct TextInputElement {
     input: View<TextInput>,
 }
 
 struct PrepaintState {
-    line: Option<ShapedLine>,
+    rows: Vec<Row>,
     cursor: Option<PaintQuad>,
-    selection: Option<PaintQuad>,
+    selection: Vec<PaintQuad>,
 }
 
 impl IntoElement for TextInputElement {
@@ -437,9 +758,12 @@ impl Element for TextInputElement {
         _id: Option<&GlobalElementId>,
         cx: &mut WindowContext,
     ) -> (LayoutId, Self::RequestLayoutState) {
+        // Rows are counted when painting, so a change in row count shows up
+        // on the frame after it; the input asks for that frame
+        let rows = self.input.read(cx).last_rows.len().max(1);
         let mut style = Style::default();
         style.size.width = relative(1.).into();
-        style.size.height = cx.line_height().into();
+        style.size.height = (cx.line_height() * rows as f32).into();
         (cx.request_layout(style, []), ())
     }
 
@@ -453,8 +777,12 @@ impl Element for TextInputElement {
         let input = self.input.read(cx);
         let content = input.content.clone();
         let selected_range = input.selected_range.clone();
+        let marked_range = input.marked_range.clone();
+        let multi_line = input.multi_line;
+        let block_cursor = input.in_vim_normal_mode();
         let cursor = input.cursor_offset();
         let style = cx.text_style();
+        let line_height = cx.line_height();
 
         let (display_text, text_color) = if content.is_empty() {
             (input.placeholder.clone(), hsla(0., 0., 0.5, 1.0))
@@ -470,71 +798,97 @@ impl Element for TextInputElement {
             underline: None,
             strikethrough: None,
         };
-        let runs = if let Some(marked_range) = input.marked_range.as_ref() {
-            vec![
-                TextRun {
-                    len: marked_range.start,
-                    ..run.clone()
-                },
-                TextRun {
-                    len: marked_range.end - marked_range.start,
-                    underline: Some(UnderlineStyle {
-                        color: Some(run.color),
-                        thickness: px(1.0),
-                        wavy: false,
-                    }),
-                    ..run.clone()
-                },
-                TextRun {
-                    len: display_text.len() - marked_range.end,
-                    ..run
-                },
-            ]
-            .into_iter()
-            .filter(|run| run.len > 0)
-            .collect()
-        } else {
-            vec![run]
+        let font_size = style.font_size.to_pixels(cx.rem_size());
+        let shape = |range: Range<usize>, cx: &WindowContext| {
+            let text: SharedString = display_text[range.clone()].to_string().into();
+            let runs = runs_for_range(&range, &run, marked_range.as_ref());
+            cx.text_system().shape_line(text, font_size, &runs).unwrap()
         };
 
-        let font_size = style.font_size.to_pixels(cx.rem_size());
-        let line = cx
-            .text_system()
-            .shape_line(display_text, font_size, &runs)
-            .unwrap();
-
-        let cursor_pos = line.x_for_index(cursor);
-        let (selection, cursor_quad) = if selected_range.is_empty() {
-            (
-                None,
-                Some(fill(
-                    Bounds::new(
-                        point(bounds.left() + cursor_pos, bounds.top()),
-                        size(px(2.), bounds.bottom() - bounds.top()),
-                    ),
-                    hsla(210. / 360., 0.8, 0.6, 1.0), // Blue cursor
-                )),
-            )
-        } else {
-            (
-                Some(fill(
+        // Split into hard lines, then soft-wrap each to the input's width
+        let mut rows = Vec::new();
+        let mut line_start = 0;
+        for line in display_text.split('\n') {
+            let line_range = line_start..line_start + line.len();
+            let breaks = if multi_line && !line.is_empty() {
+                let unwrapped = shape(line_range.clone(), cx);
+                wrap_offsets(line, bounds.size.width, |idx| unwrapped.x_for_index(idx))
+            } else {
+                Vec::new()
+            };
+            let mut row_start = 0;
+            for row_end in breaks.into_iter().chain([line.len()]) {
+                let range = line_range.start + row_start..line_range.start + row_end;
+                rows.push(Row {
+                    line: shape(range.clone(), cx),
+                    range,
+                });
+                row_start = row_end;
+            }
+            line_start = line_range.end + 1;
+        }
+
+        // Selection highlight on each row it touches
+        let selection = rows
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| {
+                !selected_range.is_empty()
+                    && selected_range.start <= row.range.end
+                    && selected_range.end >= row.range.start
+            })
+            .map(|(idx, row)| {
+                let start = selected_range.start.max(row.range.start) - row.range.start;
+                let end = selected_range.end.min(row.range.end) - row.range.start;
+                let top = bounds.top() + line_height * idx as f32;
+                fill(
                     Bounds::from_corners(
-                        point(
-                            bounds.left() + line.x_for_index(selected_range.start),
-                            bounds.top(),
-                        ),
-                        point(
-                            bounds.left() + line.x_for_index(selected_range.end),
-                            bounds.bottom(),
-                        ),
+                        point(bounds.left() + row.line.x_for_index(start), top),
+                        point(bounds.left() + row.line.x_for_index(end), top + line_height),
                     ),
                     hsla(210. / 360., 0.8, 0.6, 0.3), // Selection highlight
-                )),
-                None,
+                )
+            })
+            .collect();
+
+        let cursor_quad = selected_range.is_empty().then(|| {
+            let (x, y, width) = rows
+                .iter()
+                .enumerate()
+                .find(|(idx, row)| {
+                    let continues = rows
+                        .get(idx + 1)
+                        .is_some_and(|next| next.range.start == row.range.end);
+                    cursor >= row.range.start
+                        && (cursor < row.range.end || (cursor == row.range.end && !continues))
+                })
+                .map_or((px(0.), px(0.), px(2.)), |(idx, row)| {
+                    let x = row.line.x_for_index(cursor - row.range.start);
+                    // Vim's normal mode covers the character under the caret
+                    let width = if block_cursor {
+                        let next = display_text[cursor..]
+                            .chars()
+                            .next()
+                            .map_or(cursor, |c| cursor + c.len_utf8())
+                            .min(row.range.end);
+                        (row.line.x_for_index(next - row.range.start) - x).max(px(8.))
+                    } else {
+                        px(2.)
+                    };
+                    (x, line_height * idx as f32, width)
+                });
+            let alpha = if block_cursor { 0.5 } else { 1.0 };
+            fill(
+                Bounds::new(
+                    point(bounds.left() + x, bounds.top() + y),
+                    size(width, line_height),
+                ),
+                hsla(210. / 360., 0.8, 0.6, alpha), // Blue cursor
             )
-        };
+        });
+
         PrepaintState {
-            line: Some(line),
+            rows,
             cursor: cursor_quad,
             selection,
         }
@@ -553,12 +907,15 @@ impl Element for TextInputElement {
             &focus_handle,
             ElementInputHandler::new(bounds, self.input.clone()),
         );
-        if let Some(selection) = prepaint.selection.take() {
+        for selection in prepaint.selection.drain(..) {
             cx.paint_quad(selection)
         }
-        let line = prepaint.line.take().unwrap();
-        line.paint(bounds.origin, cx.line_height(), cx)
-            .unwrap();
+        let line_height = cx.line_height();
+        let rows = std::mem::take(&mut prepaint.rows);
+        for (idx, row) in rows.iter().enumerate() {
+            let origin = point(bounds.left(), bounds.top() + line_height * idx as f32);
+            row.line.paint(origin, line_height, cx).unwrap();
+        }
 
         if focus_handle.is_focused(cx) {
             if let Some(cursor) = prepaint.cursor.take() {
@@ -566,8 +923,13 @@ impl Element for TextInputElement {
             }
         }
 
-        self.input.update(cx, |input, _cx| {
-            input.last_layout = Some(line);
+        self.input.update(cx, |input, cx| {
+            // Lay out again at the new height when wrapping changed the row count
+            if input.last_rows.len().max(1) != rows.len().max(1) {
+                cx.notify();
+            }
+            input.last_rows = rows;
+            input.last_line_height = line_height;
             input.last_bounds = Some(bounds);
         });
     }
@@ -575,12 +937,18 @@ impl Element for TextInputElement {
 
 impl Render for TextInput {
     fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let mut key_context = KeyContext::default();
+        key_context.add("TextInput");
+        if self.multi_line {
+            key_context.add(MULTI_LINE_CONTEXT);
+        }
+
         div()
             .id("text-input")
-            .key_context("TextInput")
+            .key_context(key_context)
             .track_focus(&self.focus_handle)
             .flex_1()
-            .min_h(px(24.0))
+            .min_h(rems(1.5))
             .cursor(CursorStyle::IBeam)
             .on_action(cx.listener(Self::backspace))
             .on_action(cx.listener(Self::delete))
@@ -594,6 +962,15 @@ impl Render for TextInput {
             .on_action(cx.listener(Self::paste))
             .on_action(cx.listener(Self::cut))
             .on_action(cx.listener(Self::copy))
+            .when(self.multi_line, |el| {
+                el.on_action(cx.listener(Self::up))
+                    .on_action(cx.listener(Self::down))
+                    .on_action(cx.listener(Self::select_up))
+                    .on_action(cx.listener(Self::select_down))
+                    .on_action(cx.listener(Self::newline))
+                    .on_action(cx.listener(Self::submit))
+            })
+            .on_key_down(cx.listener(Self::on_key_down))
             .on_mouse_down(MouseButton::Left, cx.listener(Self::on_mouse_down))
             .on_mouse_up(MouseButton::Left, cx.listener(Self::on_mouse_up))
             .on_mouse_up_out(MouseButton::Left, cx.listener(Self::on_mouse_up))
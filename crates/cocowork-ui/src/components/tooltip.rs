@@ -0,0 +1,33 @@
+//! Text tooltips
+//!
+//! Icon-only controls name themselves with a tooltip, shown on hover.
+
+use gpui::*;
+
+/// A tooltip showing a short line of text
+pub struct Tooltip {
+    text: SharedString,
+}
+
+impl Tooltip {
+    /// Build a tooltip view, for use with `.tooltip(|cx| Tooltip::text("...", cx))`
+    pub fn text(text: impl Into<SharedString>, cx: &mut WindowContext) -> AnyView {
+        let text = text.into();
+        cx.new_view(|_| Self { text }).into()
+    }
+}
+
+impl Render for Tooltip {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        div()
+            .px(px(8.0))
+            .py(px(4.0))
+            .rounded(px(4.0))
+            .bg(hsla(0., 0., 0.12, 1.0))
+            .border_1()
+            .border_color(hsla(0., 0., 0.3, 1.0))
+            .text_xs()
+            .text_color(hsla(0., 0., 0.9, 1.0))
+            .child(self.text.clone())
+    }
+}
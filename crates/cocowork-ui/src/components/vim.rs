@@ -0,0 +1,680 @@
+//! Vim-style modal editing
+//!
+//! An optional layer over [`TextInput`](super::TextInput)'s key handling. In
+//! insert mode keys type as usual; Escape switches to normal mode, where keys
+//! are commands:
+//!
+//! - Motions: `h` `l` `j` `k` `w` `b` `e` `0` `^` `$` `gg` `G`
+//! - Operators `d` `c` `y` over a motion, `iw`/`aw`, or doubled for whole lines
+//! - `x` `X` `s` `S` `D` `C` `Y`, `p` `P` to put what was deleted or yanked
+//! - `i` `a` `I` `A` `o` `O` back into insert mode
+//!
+//! Counts such as `3w` or `2dd` repeat the command. Lines are the input's
+//! hard lines; `j` and `k` ignore soft wrapping.
+
+use std::ops::Range;
+
+/// Most times a count repeats a command, so a stray `9999` can't hang the UI
+const MAX_COUNT: usize = 999;
+
+/// Whether keys type text or run commands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VimMode {
+    Normal,
+    /// Keys type text; inputs start here
+    #[default]
+    Insert,
+}
+
+/// What the input should do after a normal-mode key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VimCommand {
+    /// Put the caret at a byte offset
+    Move(usize),
+    /// Replace a byte range with `text` and put the caret at `cursor`
+    Edit {
+        range: Range<usize>,
+        text: String,
+        cursor: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Motion {
+    Left,
+    Right,
+    Up,
+    Down,
+    WordStart,
+    WordBack,
+    WordEnd,
+    LineStart,
+    FirstNonBlank,
+    LineEnd,
+    FirstLine,
+    LastLine,
+}
+
+impl Motion {
+    fn from_key(key: char) -> Option<Self> {
+        Some(match key {
+            'h' => Self::Left,
+            'l' => Self::Right,
+            'k' => Self::Up,
+            'j' => Self::Down,
+            'w' => Self::WordStart,
+            'b' => Self::WordBack,
+            'e' => Self::WordEnd,
+            '0' => Self::LineStart,
+            '^' => Self::FirstNonBlank,
+            '$' => Self::LineEnd,
+            'G' => Self::LastLine,
+            _ => return None,
+        })
+    }
+
+    /// Operators over this motion take whole lines
+    fn is_linewise(self) -> bool {
+        matches!(self, Self::Up | Self::Down | Self::FirstLine | Self::LastLine)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Delete,
+    Change,
+    Yank,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Motion(Motion),
+    Word { around: bool },
+    Lines,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InsertAt {
+    Cursor,
+    After,
+    LineStart,
+    LineEnd,
+    LineBelow,
+    LineAbove,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Move(Motion),
+    Operate(Operator, Target),
+    Insert(InsertAt),
+    Put { before: bool },
+}
+
+enum Parse {
+    /// More keys are needed, e.g. after `d` or `ci`
+    Pending,
+    Invalid,
+    Done(usize, Action),
+}
+
+/// Modal editing state of one input
+#[derive(Debug, Clone, Default)]
+pub struct Vim {
+    mode: VimMode,
+    /// Keys of the command typed so far
+    pending: Vec<char>,
+    /// Text last deleted or yanked, and whether it was whole lines
+    register: Option<(String, bool)>,
+}
+
+impl Vim {
+    pub fn mode(&self) -> VimMode {
+        self.mode
+    }
+
+    /// Keys of an unfinished command, e.g. `2d`
+    pub fn pending_keys(&self) -> String {
+        self.pending.iter().collect()
+    }
+
+    /// Handle Escape
+    ///
+    /// Insert mode switches to normal mode, stepping the caret back onto the
+    /// last typed character; in normal mode an unfinished command is dropped.
+    /// Returns the new caret offset, or `None` when Escape had nothing to do
+    /// here and should go on to the parent.
+    pub fn escape(&mut self, text: &str, cursor: usize) -> Option<usize> {
+        match self.mode {
+            VimMode::Insert => {
+                self.mode = VimMode::Normal;
+                Some(if cursor > line_start(text, cursor) {
+                    prev_char(text, cursor)
+                } else {
+                    cursor
+                })
+            }
+            VimMode::Normal if !self.pending.is_empty() => {
+                self.pending.clear();
+                Some(cursor)
+            }
+            VimMode::Normal => None,
+        }
+    }
+
+    /// Handle a typed character in normal mode
+    ///
+    /// Returns what to do once a command is complete; `None` while it's
+    /// still being typed or when the keys don't form a command.
+    pub fn handle_key(&mut self, key: char, text: &str, cursor: usize) -> Option<VimCommand> {
+        if self.mode != VimMode::Normal {
+            return None;
+        }
+        self.pending.push(key);
+        let (count, action) = match parse(&self.pending) {
+            Parse::Pending => return None,
+            Parse::Invalid => {
+                self.pending.clear();
+                return None;
+            }
+            Parse::Done(count, action) => (count, action),
+        };
+        self.pending.clear();
+        self.run(count, action, text, cursor)
+    }
+
+    fn run(&mut self, count: usize, action: Action, text: &str, cursor: usize) -> Option<VimCommand> {
+        match action {
+            Action::Move(motion) => {
+                let target = repeat(text, cursor, motion, count);
+                Some(VimCommand::Move(clamp_normal(text, target)))
+            }
+            Action::Operate(operator, target) => self.operate(operator, target, count, text, cursor),
+            Action::Insert(at) => {
+                self.mode = VimMode::Insert;
+                let line_end = line_end(text, cursor);
+                let line_start = line_start(text, cursor);
+                Some(match at {
+                    InsertAt::Cursor => VimCommand::Move(cursor),
+                    InsertAt::After => VimCommand::Move(next_char(text, cursor).min(line_end)),
+                    InsertAt::LineStart => VimCommand::Move(first_non_blank(text, cursor)),
+                    InsertAt::LineEnd => VimCommand::Move(line_end),
+                    InsertAt::LineBelow => VimCommand::Edit {
+                        range: line_end..line_end,
+                        text: "\n".to_string(),
+                        cursor: line_end + 1,
+                    },
+                    InsertAt::LineAbove => VimCommand::Edit {
+                        range: line_start..line_start,
+                        text: "\n".to_string(),
+                        cursor: line_start,
+                    },
+                })
+            }
+            Action::Put { before } => {
+                let (register, linewise) = self.register.clone()?;
+                if linewise {
+                    let lines = vec![register; count].join("\n");
+                    let (at, text) = if before {
+                        (line_start(text, cursor), format!("{}\n", lines))
+                    } else {
+                        (line_end(text, cursor), format!("\n{}", lines))
+                    };
+                    let cursor = if before { at } else { at + 1 };
+                    Some(VimCommand::Edit {
+                        range: at..at,
+                        text,
+                        cursor,
+                    })
+                } else {
+                    let put = register.repeat(count);
+                    let at = if before {
+                        cursor
+                    } else {
+                        next_char(text, cursor).min(line_end(text, cursor))
+                    };
+                    Some(VimCommand::Edit {
+                        range: at..at,
+                        cursor: prev_char(&put, put.len()) + at,
+                        text: put,
+                    })
+                }
+            }
+        }
+    }
+
+    fn operate(
+        &mut self,
+        operator: Operator,
+        target: Target,
+        count: usize,
+        text: &str,
+        cursor: usize,
+    ) -> Option<VimCommand> {
+        let (range, linewise) = match target {
+            Target::Lines => {
+                let last = (1..count).fold(cursor, |pos, _| line_below(text, pos));
+                (line_start(text, cursor)..line_end(text, last), true)
+            }
+            Target::Motion(motion) if motion.is_linewise() => {
+                let target = repeat(text, cursor, motion, count);
+                let (first, last) = (cursor.min(target), cursor.max(target));
+                (line_start(text, first)..line_end(text, last), true)
+            }
+            Target::Motion(motion) => (motion_range(operator, motion, count, text, cursor), false),
+            Target::Word { around } => (word_object(text, cursor, around), false),
+        };
+        if range.is_empty() && !linewise {
+            return None;
+        }
+        self.register = Some((text[range.clone()].to_string(), linewise));
+
+        match operator {
+            Operator::Yank => Some(VimCommand::Move(if linewise {
+                cursor
+            } else {
+                clamp_normal(text, range.start)
+            })),
+            Operator::Change => {
+                self.mode = VimMode::Insert;
+                Some(VimCommand::Edit {
+                    cursor: range.start,
+                    range,
+                    text: String::new(),
+                })
+            }
+            Operator::Delete => {
+                // Whole lines go with their line break
+                let range = if !linewise {
+                    range
+                } else if range.end < text.len() {
+                    range.start..range.end + 1
+                } else {
+                    range.start.saturating_sub(1)..range.end
+                };
+                let rest = format!("{}{}", &text[..range.start], &text[range.end..]);
+                let cursor = if linewise {
+                    first_non_blank(&rest, range.start.min(rest.len()))
+                } else {
+                    clamp_normal(&rest, range.start)
+                };
+                Some(VimCommand::Edit {
+                    range,
+                    text: String::new(),
+                    cursor,
+                })
+            }
+        }
+    }
+}
+
+fn parse(keys: &[char]) -> Parse {
+    let (count, keys) = take_count(keys);
+    let Some((&key, rest)) = keys.split_first() else {
+        return Parse::Pending;
+    };
+    let action = match key {
+        'd' | 'c' | 'y' => {
+            let operator = match key {
+                'd' => Operator::Delete,
+                'c' => Operator::Change,
+                _ => Operator::Yank,
+            };
+            let (motion_count, rest) = take_count(rest);
+            let target = match rest {
+                [] => return Parse::Pending,
+                [k] if *k == key => Target::Lines,
+                ['i' | 'a'] | ['g'] => return Parse::Pending,
+                [object @ ('i' | 'a'), 'w'] => Target::Word {
+                    around: *object == 'a',
+                },
+                ['g', 'g'] => Target::Motion(Motion::FirstLine),
+                [k] => match Motion::from_key(*k) {
+                    Some(motion) => Target::Motion(motion),
+                    None => return Parse::Invalid,
+                },
+                _ => return Parse::Invalid,
+            };
+            return Parse::Done(
+                (count * motion_count).min(MAX_COUNT),
+                Action::Operate(operator, target),
+            );
+        }
+        'g' => match rest {
+            [] => return Parse::Pending,
+            ['g'] => Action::Move(Motion::FirstLine),
+            _ => return Parse::Invalid,
+        },
+        'x' => Action::Operate(Operator::Delete, Target::Motion(Motion::Right)),
+        'X' => Action::Operate(Operator::Delete, Target::Motion(Motion::Left)),
+        'D' => Action::Operate(Operator::Delete, Target::Motion(Motion::LineEnd)),
+        'C' => Action::Operate(Operator::Change, Target::Motion(Motion::LineEnd)),
+        's' => Action::Operate(Operator::Change, Target::Motion(Motion::Right)),
+        'S' => Action::Operate(Operator::Change, Target::Lines),
+        'Y' => Action::Operate(Operator::Yank, Target::Lines),
+        'p' => Action::Put { before: false },
+        'P' => Action::Put { before: true },
+        'i' => Action::Insert(InsertAt::Cursor),
+        'a' => Action::Insert(InsertAt::After),
+        'I' => Action::Insert(InsertAt::LineStart),
+        'A' => Action::Insert(InsertAt::LineEnd),
+        'o' => Action::Insert(InsertAt::LineBelow),
+        'O' => Action::Insert(InsertAt::LineAbove),
+        _ => match Motion::from_key(key) {
+            Some(motion) => Action::Move(motion),
+            None => return Parse::Invalid,
+        },
+    };
+    Parse::Done(count, action)
+}
+
+/// A leading count, 1 if there is none; `0` on its own is a motion
+fn take_count(keys: &[char]) -> (usize, &[char]) {
+    if keys.first() == Some(&'0') {
+        return (1, keys);
+    }
+    let digits = keys.iter().take_while(|key| key.is_ascii_digit()).count();
+    let count = keys[..digits]
+        .iter()
+        .fold(0usize, |count, key| {
+            (count * 10 + key.to_digit(10).unwrap_or(0) as usize).min(MAX_COUNT)
+        });
+    (count.max(1), &keys[digits..])
+}
+
+/// The text an operator acts on for a character-wise motion
+fn motion_range(operator: Operator, motion: Motion, count: usize, text: &str, cursor: usize) -> Range<usize> {
+    let on_word = char_at(text, cursor).is_some_and(|c| !c.is_whitespace());
+    let (target, inclusive) = match motion {
+        // `cw` changes to the end of the word rather than the start of the next
+        Motion::WordStart if operator == Operator::Change && on_word => {
+            let end = word_run_end(text, cursor);
+            (repeat(text, end, Motion::WordEnd, count - 1), true)
+        }
+        Motion::WordStart => {
+            // `dw` on a line's last word stops at the line break
+            let target = repeat(text, cursor, motion, count);
+            let line_end = line_end(text, cursor);
+            (if target > line_end && line_end > cursor { line_end } else { target }, false)
+        }
+        Motion::WordEnd => (repeat(text, cursor, motion, count), true),
+        _ => (repeat(text, cursor, motion, count), false),
+    };
+    let (start, end) = (cursor.min(target), cursor.max(target));
+    let end = if inclusive { next_char(text, end) } else { end };
+    start..end
+}
+
+fn repeat(text: &str, cursor: usize, motion: Motion, count: usize) -> usize {
+    (0..count).fold(cursor, |pos, _| step(text, pos, motion))
+}
+
+/// Where one motion from `pos` lands
+fn step(text: &str, pos: usize, motion: Motion) -> usize {
+    match motion {
+        Motion::Left if pos > line_start(text, pos) => prev_char(text, pos),
+        Motion::Left => pos,
+        Motion::Right if pos < line_end(text, pos) => next_char(text, pos),
+        Motion::Right => pos,
+        Motion::Up => line_above(text, pos),
+        Motion::Down => line_below(text, pos),
+        Motion::WordStart => {
+            let mut pos = pos;
+            if let Some(class) = char_at(text, pos).map(char_class).filter(|&class| class != 0) {
+                while char_at(text, pos).map(char_class) == Some(class) {
+                    pos = next_char(text, pos);
+                }
+            }
+            while char_at(text, pos).is_some_and(char::is_whitespace) {
+                pos = next_char(text, pos);
+            }
+            pos
+        }
+        Motion::WordEnd => {
+            let mut pos = next_char(text, pos);
+            while char_at(text, pos).is_some_and(char::is_whitespace) {
+                pos = next_char(text, pos);
+            }
+            if pos >= text.len() {
+                return pos;
+            }
+            word_run_end(text, pos)
+        }
+        Motion::WordBack => {
+            let mut pos = prev_char(text, pos);
+            while pos > 0 && char_at(text, pos).is_some_and(char::is_whitespace) {
+                pos = prev_char(text, pos);
+            }
+            let class = char_at(text, pos).map(char_class);
+            while pos > 0 && char_at(text, prev_char(text, pos)).map(char_class) == class {
+                pos = prev_char(text, pos);
+            }
+            pos
+        }
+        Motion::LineStart => line_start(text, pos),
+        Motion::FirstNonBlank => first_non_blank(text, pos),
+        Motion::LineEnd => line_end(text, pos),
+        Motion::FirstLine => first_non_blank(text, 0),
+        Motion::LastLine => first_non_blank(text, text.len()),
+    }
+}
+
+/// The word (or run of blanks) under the caret; with `around`, plus the
+/// blanks after it, or before it when there are none after
+fn word_object(text: &str, cursor: usize, around: bool) -> Range<usize> {
+    let Some(class) = char_at(text, cursor).filter(|&c| c != '\n').map(char_class) else {
+        return cursor..cursor;
+    };
+    let same = |pos: usize| char_at(text, pos).is_some_and(|c| c != '\n' && char_class(c) == class);
+    let mut start = cursor;
+    while start > 0 && same(prev_char(text, start)) {
+        start = prev_char(text, start);
+    }
+    let mut end = cursor;
+    while same(end) {
+        end = next_char(text, end);
+    }
+    if !around {
+        return start..end;
+    }
+
+    let is_blank = |pos: usize| char_at(text, pos).is_some_and(|c| c == ' ' || c == '\t');
+    if class == 0 {
+        // Blanks plus the word after them
+        if let Some(next) = char_at(text, end).filter(|&c| c != '\n').map(char_class) {
+            while char_at(text, end).is_some_and(|c| c != '\n' && char_class(c) == next) {
+                end = next_char(text, end);
+            }
+        }
+        return start..end;
+    }
+    let word_end = end;
+    while is_blank(end) {
+        end = next_char(text, end);
+    }
+    if end == word_end {
+        while start > 0 && is_blank(prev_char(text, start)) {
+            start = prev_char(text, start);
+        }
+    }
+    start..end
+}
+
+/// Offset of the last character in the run of same-class characters at `pos`
+fn word_run_end(text: &str, pos: usize) -> usize {
+    let class = char_at(text, pos).map(char_class);
+    let mut pos = pos;
+    while char_at(text, next_char(text, pos)).map(char_class) == class && next_char(text, pos) < text.len() {
+        pos = next_char(text, pos);
+    }
+    pos
+}
+
+/// 0 for whitespace, 1 for word characters, 2 for punctuation
+fn char_class(c: char) -> u8 {
+    if c.is_whitespace() {
+        0
+    } else if c.is_alphanumeric() || c == '_' {
+        1
+    } else {
+        2
+    }
+}
+
+fn char_at(text: &str, pos: usize) -> Option<char> {
+    text.get(pos..)?.chars().next()
+}
+
+fn next_char(text: &str, pos: usize) -> usize {
+    pos + char_at(text, pos).map_or(0, char::len_utf8)
+}
+
+fn prev_char(text: &str, pos: usize) -> usize {
+    text[..pos].chars().next_back().map_or(pos, |c| pos - c.len_utf8())
+}
+
+fn line_start(text: &str, pos: usize) -> usize {
+    text[..pos].rfind('\n').map_or(0, |idx| idx + 1)
+}
+
+fn line_end(text: &str, pos: usize) -> usize {
+    text[pos..].find('\n').map_or(text.len(), |idx| pos + idx)
+}
+
+fn first_non_blank(text: &str, pos: usize) -> usize {
+    let start = line_start(text, pos);
+    let blanks = text[start..line_end(text, pos)]
+        .chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .map(char::len_utf8)
+        .sum::<usize>();
+    start + blanks
+}
+
+/// Keep the caret on a character: normal mode can't sit past a line's end
+fn clamp_normal(text: &str, pos: usize) -> usize {
+    let pos = pos.min(text.len());
+    let end = line_end(text, pos);
+    if pos >= end && end > line_start(text, pos) {
+        prev_char(text, end)
+    } else {
+        pos
+    }
+}
+
+/// Same column on the line `line_start` begins, or that line's end if it's shorter
+fn column_on_line(text: &str, line_start: usize, column: usize) -> usize {
+    let end = line_end(text, line_start);
+    line_start
+        + text[line_start..end]
+            .chars()
+            .take(column)
+            .map(char::len_utf8)
+            .sum::<usize>()
+}
+
+fn line_above(text: &str, pos: usize) -> usize {
+    let start = line_start(text, pos);
+    if start == 0 {
+        return pos;
+    }
+    let column = text[start..pos].chars().count();
+    column_on_line(text, line_start(text, start - 1), column)
+}
+
+fn line_below(text: &str, pos: usize) -> usize {
+    let end = line_end(text, pos);
+    if end == text.len() {
+        return pos;
+    }
+    let column = text[line_start(text, pos)..pos].chars().count();
+    column_on_line(text, end + 1, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Type `keys` in normal mode, applying each command
+    fn run(text: &str, cursor: usize, keys: &str) -> (String, usize, Vim) {
+        let mut vim = Vim {
+            mode: VimMode::Normal,
+            ..Vim::default()
+        };
+        let mut text = text.to_string();
+        let mut cursor = cursor;
+        for key in keys.chars() {
+            match vim.handle_key(key, &text, cursor) {
+                Some(VimCommand::Move(offset)) => cursor = offset,
+                Some(VimCommand::Edit {
+                    range,
+                    text: new,
+                    cursor: offset,
+                }) => {
+                    text.replace_range(range, &new);
+                    cursor = offset;
+                }
+                None => {}
+            }
+        }
+        (text, cursor, vim)
+    }
+
+    #[test]
+    fn test_motions() {
+        let text = "let foo = bar(1);\n  next line";
+        assert_eq!(run(text, 0, "w").1, 4);
+        assert_eq!(run(text, 0, "3w").1, 10);
+        assert_eq!(run(text, 0, "e").1, 2);
+        assert_eq!(run(text, 12, "b").1, 10);
+        assert_eq!(run(text, 0, "$").1, 16);
+        assert_eq!(run(text, 5, "0").1, 0);
+        assert_eq!(run(text, 0, "j^").1, 20);
+        assert_eq!(run(text, 4, "jk").1, 4);
+        assert_eq!(run(text, 0, "G").1, 20);
+        assert_eq!(run(text, 25, "gg").1, 0);
+        // Escape from insert mode steps back onto the last character
+        assert_eq!(Vim::default().escape(text, 17), Some(16));
+    }
+
+    #[test]
+    fn test_operators() {
+        let (text, cursor, vim) = run("one two three", 5, "ciw");
+        assert_eq!((text.as_str(), cursor, vim.mode()), ("one  three", 4, VimMode::Insert));
+
+        let (text, cursor, _) = run("one two three", 4, "daw");
+        assert_eq!((text.as_str(), cursor), ("one three", 4));
+
+        // `dw` on a line's last word keeps the line break
+        let (text, _, _) = run("one two\nthree", 4, "dw");
+        assert_eq!(text, "one \nthree");
+
+        let (text, cursor, _) = run("one two\nthree", 0, "cw");
+        assert_eq!((text.as_str(), cursor), (" two\nthree", 0));
+
+        let (text, cursor, _) = run("one\ntwo\nthree", 5, "dd");
+        assert_eq!((text.as_str(), cursor), ("one\nthree", 4));
+        assert_eq!(run("one\ntwo\nthree", 0, "2dd").0, "three");
+        assert_eq!(run("one\ntwo\nthree", 9, "dd").0, "one\ntwo");
+
+        // Deleted text can be put back elsewhere
+        assert_eq!(run("abc", 0, "xp").0, "bac");
+        assert_eq!(run("one\ntwo", 0, "yyjp").0, "one\ntwo\none");
+        assert_eq!(run("one two", 0, "D").0, "");
+    }
+
+    #[test]
+    fn test_pending_and_invalid_keys() {
+        let (text, _, vim) = run("one two", 0, "d");
+        assert_eq!((text.as_str(), vim.pending_keys().as_str()), ("one two", "d"));
+
+        let (text, _, vim) = run("one two", 0, "dzx");
+        assert_eq!((text.as_str(), vim.pending_keys().as_str()), ("ne two", ""));
+
+        let mut vim = Vim::default();
+        vim.escape("abc", 3);
+        vim.handle_key('c', "abc", 2);
+        assert_eq!(vim.escape("abc", 2), Some(2));
+        assert_eq!(vim.escape("abc", 2), None);
+    }
+}
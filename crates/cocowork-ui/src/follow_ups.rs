@@ -0,0 +1,221 @@
+//! Suggested follow-up prompts for a finished turn
+//!
+//! After the agent ends a turn, a few likely next prompts are offered as chips
+//! under the last message. They come from a local heuristic over the turn: an
+//! offer the agent made at the end of its reply ("Would you like me to..."),
+//! plan entries it didn't finish, tool calls that failed and files it edited.
+
+use cocowork_core::{ContentBlock, MessageBlock, PlanStatus, TaskState, ToolCallKind, ToolCallState, ToolCallStatus};
+
+/// Most suggestions shown for one turn
+pub const MAX_FOLLOW_UPS: usize = 3;
+
+/// Longest suggestion kept; longer ones are dropped rather than cut mid-sentence
+const MAX_FOLLOW_UP_LEN: usize = 120;
+
+/// Openers that turn the rest of a question into an offer, e.g. "Should I add tests?"
+const OFFER_PREFIXES: &[&str] = &[
+    "would you like me to ",
+    "do you want me to ",
+    "shall i ",
+    "should i ",
+    "want me to ",
+    "i can also ",
+    "i could also ",
+];
+
+/// Up to [`MAX_FOLLOW_UPS`] prompts the user might send next
+///
+/// Only the last turn is considered: the messages after the last user message
+/// and the task's plan and tool calls.
+pub fn suggest_follow_ups(messages: &[MessageBlock], task: Option<&TaskState>) -> Vec<String> {
+    let turn_start = messages
+        .iter()
+        .rposition(|message| matches!(message, MessageBlock::User { .. }))
+        .map_or(0, |i| i + 1);
+    let reply = messages[turn_start..]
+        .iter()
+        .rev()
+        .find_map(|message| match message {
+            MessageBlock::Agent { content, .. } => Some(text_of(content)),
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    let mut candidates: Vec<String> = offers(&reply);
+    if let Some(task) = task {
+        candidates.extend(
+            task.plan
+                .iter()
+                .find(|entry| matches!(entry.status, PlanStatus::Pending | PlanStatus::InProgress))
+                .map(|entry| format!("Continue with: {}", entry.content.trim())),
+        );
+
+        let tool_calls = turn_tool_calls(messages, task);
+        candidates.extend(
+            tool_calls
+                .iter()
+                .find(|call| call.status == ToolCallStatus::Failed)
+                .map(|call| match call.title.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+                    Some(title) => format!("Fix the failed step: {}", title),
+                    None => "Fix the step that failed".to_string(),
+                }),
+        );
+        candidates.extend(
+            tool_calls
+                .iter()
+                .rev()
+                .filter(|call| {
+                    call.status == ToolCallStatus::Completed
+                        && matches!(call.kind, Some(ToolCallKind::Edit | ToolCallKind::Write | ToolCallKind::Create))
+                })
+                .find_map(|call| edited_file(call.input.as_ref()?))
+                .map(|file| format!("Add tests for the changes to {}", file)),
+        );
+    }
+
+    let mut suggestions: Vec<String> = Vec::new();
+    for candidate in candidates {
+        if candidate.len() <= MAX_FOLLOW_UP_LEN && !suggestions.iter().any(|s| s.eq_ignore_ascii_case(&candidate)) {
+            suggestions.push(candidate);
+        }
+        if suggestions.len() == MAX_FOLLOW_UPS {
+            break;
+        }
+    }
+    suggestions
+}
+
+/// The task's tool calls made since the last user message, oldest first
+///
+/// The task lives as long as the session, so it also holds the tool calls of
+/// earlier turns.
+pub fn turn_tool_calls<'a>(messages: &[MessageBlock], task: &'a TaskState) -> Vec<&'a ToolCallState> {
+    let turn_started_at = messages.iter().rev().find_map(|message| match message {
+        MessageBlock::User { timestamp, .. } => Some(*timestamp),
+        _ => None,
+    });
+    let mut tool_calls: Vec<_> = task
+        .tool_calls
+        .values()
+        .filter(|call| turn_started_at.map_or(true, |started| call.started_at >= started))
+        .collect();
+    tool_calls.sort_by_key(|call| call.started_at);
+    tool_calls
+}
+
+fn text_of(content: &[ContentBlock]) -> String {
+    content
+        .iter()
+        .filter_map(|block| match block {
+            ContentBlock::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("")
+}
+
+/// Offers in the last paragraph of a reply, as prompts accepting them
+fn offers(reply: &str) -> Vec<String> {
+    let Some(last_paragraph) = reply.split("\n\n").map(str::trim).filter(|p| !p.is_empty()).last() else {
+        return Vec::new();
+    };
+    last_paragraph
+        .split_inclusive(['?', '\n'])
+        .filter_map(|sentence| {
+            let sentence = sentence.trim().trim_start_matches(['-', '*', ' ']);
+            let question = sentence.strip_suffix('?')?;
+            // Questions can follow a statement on the same line: "Done. Should I commit?"
+            let question = question.rsplit(". ").next().unwrap_or(question).trim();
+            let lower = question.to_lowercase();
+            let prefix = OFFER_PREFIXES.iter().find(|prefix| lower.starts_with(*prefix))?;
+            let action = question[prefix.len()..].trim();
+            (!action.is_empty()).then(|| format!("Yes, please {}", action))
+        })
+        .collect()
+}
+
+/// File name a write or edit tool call changed, from its input
+fn edited_file(input: &serde_json::Value) -> Option<String> {
+    let path = ["file_path", "path", "filename"]
+        .iter()
+        .find_map(|key| input.get(key)?.as_str())?;
+    let name = path.rsplit(['/', '\\']).next().filter(|name| !name.is_empty())?;
+    Some(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cocowork_core::{PlanEntry, PlanPriority};
+
+    fn agent(text: &str) -> MessageBlock {
+        MessageBlock::agent(vec![ContentBlock::Text { text: text.to_string() }])
+    }
+
+    fn user(text: &str) -> MessageBlock {
+        MessageBlock::user(vec![ContentBlock::Text { text: text.to_string() }])
+    }
+
+    #[test]
+    fn test_offers_in_last_reply() {
+        let messages = vec![
+            user("Fix the parser"),
+            agent("Should I ignore this?"),
+            user("Now the lexer"),
+            agent("I fixed the lexer.\n\nDone. Would you like me to add a regression test? Should I also update the docs?"),
+        ];
+        assert_eq!(
+            suggest_follow_ups(&messages, None),
+            vec!["Yes, please add a regression test", "Yes, please also update the docs"]
+        );
+
+        let messages = vec![user("Hi"), agent("What should I call it?")];
+        assert!(suggest_follow_ups(&messages, None).is_empty());
+    }
+
+    #[test]
+    fn test_suggestions_from_plan_and_tool_calls() {
+        let mut messages = vec![user("Add a parser"), agent("Would you like me to continue?")];
+        let mut task = TaskState::new("t".into(), "s".into(), "a".into(), Vec::new(), "/repo".into());
+        task.plan = vec![
+            PlanEntry {
+                content: "Write the parser".into(),
+                priority: PlanPriority::High,
+                status: PlanStatus::Completed,
+            },
+            PlanEntry {
+                content: "Wire it into the CLI".into(),
+                priority: PlanPriority::Medium,
+                status: PlanStatus::Pending,
+            },
+        ];
+        let mut edit = ToolCallState::new("1".into(), Some("Edit parser".into()), Some(ToolCallKind::Edit));
+        edit.status = ToolCallStatus::Completed;
+        edit.input = Some(serde_json::json!({ "file_path": "/repo/src/parser.rs" }));
+        let mut build = ToolCallState::new("2".into(), Some("cargo build".into()), Some(ToolCallKind::Execute));
+        build.status = ToolCallStatus::Failed;
+        task.tool_calls.insert(edit.id.clone(), edit);
+        task.tool_calls.insert(build.id.clone(), build);
+
+        let suggestions = suggest_follow_ups(&messages, Some(&task));
+        assert_eq!(
+            suggestions,
+            vec![
+                "Yes, please continue",
+                "Continue with: Wire it into the CLI",
+                "Fix the failed step: cargo build",
+            ]
+        );
+
+        task.plan.clear();
+        assert_eq!(
+            suggest_follow_ups(&messages[..1], Some(&task))[1],
+            "Add tests for the changes to parser.rs"
+        );
+
+        // Tool calls from an earlier turn are left out
+        messages.push(user("Thanks"));
+        assert!(suggest_follow_ups(&messages, Some(&task)).is_empty());
+    }
+}
@@ -0,0 +1,696 @@
+//! English, the catalog other languages fall back to
+
+pub(super) static STRINGS: &[(&str, &str)] = &[
+    // Shared across views
+    ("common.save", "Save"),
+    ("common.remove", "Remove"),
+    ("common.run", "Run"),
+    ("common.dismiss", "Dismiss"),
+    ("common.cancel", "Cancel"),
+    ("common.settings", "Settings"),
+    ("common.loading", "Loading..."),
+    ("common.working", "Working..."),
+    ("common.done", "Done"),
+    ("common.edit", "Edit"),
+    ("common.add", "Add"),
+    ("common.reset", "Reset"),
+    ("common.refresh", "Refresh"),
+    ("common.keep", "Keep"),
+    ("common.default", "Default"),
+    ("common.current", "Current"),
+    ("common.latest", "Latest"),
+    ("common.install", "Install"),
+    ("common.on", "On"),
+    ("common.off", "Off"),
+    ("common.saved", "Saved"),
+    ("common.select_all", "Select all"),
+    ("common.select_none", "Select none"),
+    ("common.deny", "Deny"),
+    ("common.restore", "Restore"),
+    ("common.manage", "Manage..."),
+    ("common.new_badge", "new"),
+    ("common.usage", "Usage"),
+    ("common.checking", "Checking..."),
+    ("common.more", "+{count} more"),
+    ("common.hide", "Hide"),
+    ("common.close", "Close"),
+    ("common.stop", "Stop"),
+    ("common.search", "Search"),
+    ("common.continue", "Continue"),
+    ("common.retry", "Retry"),
+    ("common.failed", "Failed"),
+    ("common.queued", "Queued"),
+    ("common.running", "Running..."),
+    ("common.connecting", "Connecting..."),
+    ("common.none", "None"),
+    ("common.in_dir", "in {dir}"),
+    ("common.tool_calls", "{count} tool calls"),
+    ("common.prompt", "Prompt: {prompt}"),
+
+    // Relative times
+    ("time.just_now", "just now"),
+    ("time.minutes_ago", "{count}m ago"),
+    ("time.hours_ago", "{count}h ago"),
+
+    // File dialog titles
+    ("dialog.export_thread", "Export Thread"),
+    ("dialog.diagnostic_bundle", "Save Diagnostic Bundle"),
+    ("dialog.select_workspace", "Select Agent Workspace"),
+    ("dialog.session_working_dir", "Select Session Working Directory"),
+    ("dialog.add_file", "Add File"),
+    ("dialog.add_batch_workspaces", "Add Batch Workspaces"),
+    ("dialog.export_audit_log", "Export Audit Log"),
+
+    // Thread sidebar
+    ("sidebar.threads", "Threads"),
+    ("sidebar.no_threads", "No threads yet"),
+    ("sidebar.search_placeholder", "Search Threads"),
+    ("sidebar.no_match", "No threads match \"{query}\""),
+    ("sidebar.clear_search", "Clear search"),
+    ("sidebar.new_thread", "New thread"),
+    ("sidebar.fork_name", "{name} (fork)"),
+    ("sidebar.search_files", "Search Files"),
+    ("sidebar.dashboard", "Dashboard"),
+    ("sidebar.audit_log", "Audit Log"),
+    ("sidebar.task_board", "Task Board"),
+    ("sidebar.group_today", "Today"),
+    ("sidebar.group_yesterday", "Yesterday"),
+    ("sidebar.group_this_week", "This Week"),
+    ("sidebar.group_older", "Older"),
+
+    // User menu
+    ("menu.account", "Account and settings"),
+    ("menu.about", "About"),
+    ("menu.check_updates", "Check for agent updates"),
+    ("menu.batch_run", "Batch Run..."),
+    ("menu.diagnostic_bundle", "Generate Diagnostic Bundle..."),
+
+    // Bottom bar
+    ("status.connected", "Connected"),
+    ("status.mcp", "MCP: {count}"),
+    ("status.reset_zoom", "Reset zoom (Cmd+0)"),
+    ("status.show_sidebar", "Show sidebar (Cmd+B)"),
+    ("status.hide_sidebar", "Hide sidebar (Cmd+B)"),
+    ("status.show_context_panel", "Show context panel (Cmd+Alt+B)"),
+    ("status.hide_context_panel", "Hide context panel (Cmd+Alt+B)"),
+    ("status.show_terminal_panel", "Show terminal (Ctrl+`)"),
+    ("status.hide_terminal_panel", "Hide terminal (Ctrl+`)"),
+    ("status.messages", "{count} messages"),
+
+    // MCP server panel
+    ("mcp.title", "MCP Servers"),
+    ("mcp.none", "No MCP servers configured"),
+    ("mcp.add", "+ Add Server"),
+
+    // Thread header
+    ("header.compare", "Compare"),
+    ("header.outline", "Outline"),
+    ("header.thread_actions", "Thread actions"),
+    ("header.idle", "Idle · will reconnect"),
+    ("header.dry_run", "Dry run"),
+    ("header.dry_run_on", "Dry run: on"),
+    ("header.auto", "Auto"),
+    ("header.auto_on", "Auto: on"),
+    ("header.compact", "Compact"),
+    ("header.compacting", "Compacting..."),
+    ("header.new_thread", "New Thread"),
+    ("header.export_markdown", "Export as Markdown..."),
+    ("header.export_html", "Export as HTML..."),
+    ("header.collapse_thinking", "Collapse all thinking"),
+    ("header.hide_thinking", "Hide thinking in this thread"),
+    ("header.show_thinking", "Show thinking in this thread"),
+    ("header.review_flagged", "Review flagged messages"),
+    ("header.exit_review", "Exit review"),
+    ("header.share_live", "Share live view…"),
+    ("header.stop_sharing", "Stop sharing"),
+    ("header.open_pull_request", "Open pull request…"),
+    ("header.agent_capabilities", "Agent capabilities"),
+    ("capabilities.title", "Agent capabilities"),
+    ("capabilities.not_connected", "Connect to an agent to see what it supports."),
+    ("capabilities.not_reported", "Not reported"),
+    ("capabilities.agent", "Agent"),
+    ("capabilities.protocol", "Protocol version"),
+    ("capabilities.auth", "Sign-in methods"),
+    ("capabilities.features", "Features"),
+    ("capabilities.session", "This thread"),
+    ("capabilities.modes", "Modes"),
+    ("capabilities.models", "Models"),
+    ("capabilities.none", "None"),
+    ("capabilities.copy_json", "Copy as JSON"),
+
+    // Banners above the timeline
+    ("banner.auto_mode", "Auto mode is on: the agent's requests are approved without asking"),
+    ("banner.turn_off", "Turn off"),
+    ("banner.retrying", "Auto-retry {attempt} of {limit}: the failed command's output was sent back to the agent"),
+    ("banner.retry_gave_up", "Auto-retry gave up: the command still fails after {count} attempts"),
+    ("banner.stop_retrying", "Stop retrying"),
+    ("banner.forked_from", "Forked from \"{name}\" after {count} messages"),
+    ("banner.forked", "Forked after {count} messages"),
+    ("banner.open_original", "Open original"),
+    ("banner.from_issue", "Started from issue {issue}"),
+    ("banner.open_issue", "Open issue"),
+    ("banner.roll_back", "Roll back to {tag}"),
+    ("banner.restore_backup", "Restore Backup"),
+    ("banner.exported", "Exported thread to {path}"),
+    ("banner.sharing", "Watch live at {url} · {viewers} watching"),
+    ("banner.copy_link", "Copy link"),
+    ("banner.share_failed", "Couldn't share the thread: {error}"),
+    ("banner.read_only", "Another CocoWork instance is using this data folder. Changes made here won't be saved until it closes."),
+    ("banner.read_only_retry", "Try again"),
+    ("banner.no_thread_to_export", "No active thread to export"),
+    ("banner.export_failed", "Failed to export thread: {error}"),
+    ("banner.crashed", "CocoWork quit unexpectedly last time. A diagnostic bundle with logs and settings, secrets removed, helps with reporting it."),
+    ("banner.generate_bundle", "Generate bundle"),
+    ("banner.bundle_saved", "Saved diagnostic bundle to {path}"),
+    ("banner.bundle_failed", "Failed to save diagnostic bundle: {error}"),
+    ("banner.link_no_thread", "Thread {id} isn't open in this window"),
+    ("banner.link_no_workspace", "Can't start a thread from the link: {dir} isn't a folder"),
+    ("banner.link_unknown_agent", "Can't start a thread from the link: there's no agent called {agent}"),
+    ("banner.link_import_failed", "Couldn't open the shared thread {file}: {error}"),
+    ("banner.workspace_mismatch", "This thread works in {thread}, but the workspace is now {workspace}. The agent can only reach files in the current workspace."),
+    ("banner.switch_workspace", "Switch back and send"),
+    ("banner.new_thread_here", "Send in a new thread here"),
+    ("banner.isolated_worktree", "Working in an isolated worktree on {branch}"),
+    ("banner.isolated_copy", "Working in an isolated copy at {dir}"),
+    ("banner.review_changes", "Review changes"),
+    ("banner.attachments_over_limit", "Attached files add about {size} to the prompt, more than the {limit} limit."),
+    ("banner.truncate_attachments", "Truncate to fit"),
+    ("banner.link_attachments", "Send as links"),
+    ("banner.send_anyway", "Send anyway"),
+    ("banner.incompatible", "Couldn't connect to {agent}. The latest release may not be compatible."),
+    ("banner.auto_mode_detail", "Paths outside the workspace and blocked commands are still refused. {count} approved so far, all logged."),
+    ("banner.database_restore", "The database couldn't be opened, so history isn't being saved. Restore the backup from {time}?"),
+    ("banner.database_no_backup", "The database couldn't be opened and there's no backup to restore. History isn't being saved."),
+    ("banner.restore_failed", "Restore failed: {error}"),
+
+    // Message timeline
+    ("timeline.empty_title", "Start a conversation"),
+    ("timeline.empty_hint", "Type a message below to chat with CocoWork's Agent"),
+    ("timeline.empty_tip", "Use 📁 to set workspace, + to attach files"),
+    ("timeline.thinking", "Thinking"),
+    ("timeline.compacted", "Conversation compacted · summary of earlier turns"),
+    ("timeline.turn_took", " · turn took {duration}"),
+    ("timeline.streaming", "Working for {elapsed}"),
+    ("timeline.spawn_subthread", "↳ Spawn sub-thread"),
+    ("timeline.fork", "⑂ Fork from here"),
+    ("timeline.pin", "📌 Pin"),
+    ("timeline.unpin", "Unpin"),
+    ("pinned.title", "PINNED"),
+    ("pinned.you", "You: {text}"),
+    ("review.title", "REVIEWING FLAGGED"),
+    ("review.all", "All"),
+    ("timeline.stream_rate", " · ~{rate} tok/s · ~{tokens} tokens"),
+    ("timeline.tool_call", "Tool call"),
+    ("timeline.max_tokens", "The reply was cut off at the model's output limit. Continue to pick up where it stopped."),
+    ("timeline.max_turn_requests", "The agent used up its requests for this turn. Continue to let it keep going."),
+    ("timeline.refusal", "The agent declined this request. Rephrase it and try again."),
+    ("timeline.cancelled", "The turn stopped before it finished. Retry to run it again."),
+    ("timeline.interrupted", "Response interrupted"),
+    ("timeline.interrupted_detail", "The app closed before the reply finished; this is what had arrived."),
+    ("timeline.resume", "Resume"),
+    ("timeline.regenerate", "Regenerate"),
+    ("timeline.actions", "{count} actions"),
+    ("timeline.actions_files", "{count} files touched"),
+    ("timeline.actions_commands", "{count} commands run"),
+    ("timeline.actions_failed", "{count} failed"),
+    ("timeline.jump_latest_tool_call", "Latest tool call"),
+    ("timeline.jump_bottom", "Jump to bottom"),
+    ("debug.title", "DEBUG (⌘⇧D to hide)"),
+    ("debug.fps", "FPS"),
+    ("debug.timeline", "Timeline rows / items"),
+    ("debug.notifications", "Notification backlog"),
+    ("debug.requests", "Pending requests"),
+    ("debug.tasks", "Tokio tasks / workers"),
+    ("debug.db_pool", "DB connections in use"),
+    ("debug.markdown_cache", "Markdown cache"),
+    ("debug.markdown_cache_use", "Hits / misses / evicted"),
+    ("timeline.error", "The agent reported an error. Retry, or reconnect to the agent if it keeps failing."),
+    ("timeline.error_detail", "{error}. Retry, or reconnect to the agent if it keeps failing."),
+    ("stop_reason.end_turn", "Finished"),
+    ("stop_reason.max_tokens", "Stopped at the token limit"),
+    ("stop_reason.max_turn_requests", "Stopped at the request limit"),
+    ("stop_reason.refusal", "Refused"),
+    ("stop_reason.cancelled", "Cancelled"),
+    ("stop_reason.error", "Failed"),
+
+    // Message input
+    ("input.placeholder", "Message CocoWork's Agent..."),
+    ("input.send", "Send (Enter)"),
+    ("input.size", "~{tokens} tokens · {size}"),
+    ("input.secrets_masked", "{count} secret(s) masked"),
+    ("input.secrets_unmasked", "{count} secret(s) sent as is"),
+    ("input.secrets_unmask", "Send this message without masking"),
+    ("input.secrets_mask", "Mask secrets in this message"),
+    ("input.size_breakdown", "Message {message} · Files {files} · Instructions {instructions}"),
+    ("input.manage_templates", "Manage templates..."),
+    ("input.no_templates", "No saved templates yet"),
+    ("input.indexing", "Indexing workspace..."),
+    ("input.no_matching_files", "No matching files"),
+    ("input.external_edits", "Workspace changed since the agent last read it: {files}"),
+    ("input.attach_external_edits", "Attach summary"),
+    ("input.external_edits_attached", "Summary attached"),
+    ("input.vim_normal", "NORMAL"),
+    ("input.vim_insert", "INSERT"),
+
+    // Prompt history search
+    ("history.title", "HISTORY"),
+    ("history.none", "No matching prompts"),
+    ("history.hint", "Enter to use, Esc to close"),
+    ("history.placeholder", "Search earlier prompts"),
+
+    // Context panel
+    ("context.progress", "Progress"),
+    ("context.instructions", "Instructions"),
+    ("context.tests", "Tests"),
+    ("context.problems", "Problems"),
+    ("context.artifacts", "Artifacts"),
+    ("context.context", "Context"),
+    ("context.no_plan", "No active plan"),
+    ("plan.priority.high", "High"),
+    ("plan.priority.medium", "Med"),
+    ("plan.priority.low", "Low"),
+    ("plan.mark_done", "Mark done"),
+    ("plan.mark_skipped", "Mark skipped"),
+    ("plan.reset", "Use the agent's status"),
+    ("plan.spawn_subthread", "Spawn a sub-thread for this step"),
+    ("context.no_tool_calls", "No tool calls yet"),
+    ("context.proposed_changes", "Proposed Changes"),
+    ("context.dry_run_hint", "Dry run is on. Agent changes will be listed here."),
+    ("context.apply", "Apply {count}"),
+    ("context.discard_all", "Discard all"),
+    ("context.instructions_placeholder", "e.g. Use pnpm. Run `pnpm test` before finishing."),
+    ("context.no_artifacts", "No artifacts yet"),
+    ("context.no_context", "No context added"),
+    ("context.instructions_scope", "Sent with the first prompt of every new thread in {dir}"),
+
+    // Workspace tests
+    ("tests.command_placeholder", "e.g. cargo test"),
+    ("tests.run", "Run tests"),
+    ("tests.running", "Running…"),
+    ("tests.run_after_turn", "Run after each turn"),
+    ("tests.passed", "Passed"),
+    ("tests.passed_count", "{count} passed"),
+    ("tests.failed_count", "{count} failed"),
+    ("tests.exit_code", "Exited with code {code}"),
+    ("tests.counts", "{passed} passed, {failed} failed, {skipped} skipped"),
+    ("tests.last_run", "Last run: {counts} in {seconds}s"),
+    ("tests.attach", "Attach to next prompt"),
+
+    // Build and lint problems
+    ("problems.commands_hint", "Build and lint commands, one per line"),
+    ("problems.commands_placeholder", "e.g. cargo clippy"),
+    ("problems.diagnostics_file_hint", "JSON diagnostics file another tool writes, read again when it changes"),
+    ("problems.diagnostics_file_placeholder", "e.g. target/diagnostics.json"),
+    ("problems.run", "Run"),
+    ("problems.counts", "{errors} errors, {warnings} warnings"),
+    ("problems.none", "No problems"),
+    ("problems.command_failed", "Command failed"),
+    ("problems.exited", "`{command}` exited with code {code}"),
+    ("problems.more", "…and {count} more"),
+    ("problems.send", "Send problems to agent"),
+
+    // Settings dialog
+    ("settings.api_keys.title", "API KEYS"),
+    ("settings.api_keys.description", "Stored in the system keychain and used instead of environment variables. Changes apply on the next connect."),
+    ("settings.api_keys.placeholder", "Paste {label}"),
+    ("settings.api_keys.stored", "Stored: {preview}"),
+    ("settings.api_keys.not_stored", "Not stored (uses ${var})"),
+    ("settings.env.title", "ENVIRONMENT PROFILES"),
+    ("settings.env.badge", "ENV"),
+    ("settings.env.name_placeholder", "Profile name"),
+    ("settings.templates.title", "PROMPT TEMPLATES"),
+    ("settings.templates.name_placeholder", "Template name"),
+    ("settings.permissions.title", "TOOL PERMISSIONS"),
+    ("settings.permissions.description", "Which agent requests to accept without asking. A workspace policy overrides the agent's, which overrides the one used everywhere."),
+    ("settings.permissions.in_effect", "In effect here: {summary}"),
+    ("settings.timestamps.title", "TIMESTAMPS"),
+    ("settings.timestamps.description", "Show when each message was sent, how long turns took and how long each tool call ran."),
+    ("settings.follow_ups.title", "FOLLOW-UP SUGGESTIONS"),
+    ("settings.follow_ups.description", "After the agent finishes a turn, suggest a few next prompts from its reply, plan and tool calls."),
+    ("settings.vim.title", "VIM MODE"),
+    ("settings.vim.description", "Edit messages with Vim keys. Escape switches to normal mode for motions like w, b and $ and commands like dd and ciw; i, a or o go back to typing."),
+    ("settings.clipboard_capture.title", "CLIPBOARD CAPTURE"),
+    ("settings.clipboard_capture.description", "Press Cmd+Alt+Space while CocoWork is active to open the quick prompt with the clipboard, such as a copied stack trace, quoted as a code block."),
+    ("settings.thoughts.title", "SHOW THINKING"),
+    ("settings.thoughts.description", "Show the agent's thinking in threads. Each thread can override this from its ··· menu."),
+    ("settings.thoughts.collapse_title", "AUTO-COLLAPSE THINKING"),
+    ("settings.thoughts.collapse_description", "Fold thinking away once the agent starts its answer. Click a thinking header to open it again."),
+    ("settings.share_lan.title", "SHARE ON LOCAL NETWORK"),
+    ("settings.share_lan.description", "Let other computers on this network open shared threads, not just this one. Anyone on the network with the link can watch."),
+    ("settings.offline.title", "OFFLINE MODE"),
+    ("settings.offline.description", "Never reach the network for CocoWork itself: no agent downloads, npm installs or update checks. Only agents that are already installed can connect."),
+    ("settings.metrics.title", "LOCAL METRICS"),
+    ("settings.metrics.description", "Count sessions and errors and time replies, for the dashboard. Nothing is sent anywhere; metrics stay in this computer's database."),
+    ("settings.protocol_log.title", "PROTOCOL LOG"),
+    ("settings.protocol_log.description", "Write every message to and from the agent to logs/acp.jsonl in the data folder, for debugging an agent. Messages can contain file contents."),
+    ("settings.redaction.title", "SECRET REDACTION"),
+    ("settings.redaction.description", "Mask API keys, cloud credentials, passwords in URLs and secret .env values in prompts and in terminal output before the agent sees them. Logs are always masked."),
+    ("settings.redaction.patterns_description", "Your own patterns to mask as well, as regular expressions."),
+    ("settings.redaction.pattern_placeholder", "e.g. acme-[0-9]{6}"),
+    ("settings.language.title", "LANGUAGE"),
+    ("settings.language.description", "The language of menus, buttons and messages from CocoWork. Agent replies are not translated."),
+    ("settings.density.title", "DENSITY"),
+    ("settings.density.description", "Compact shrinks paddings, rows and text so more of the timeline fits on small screens."),
+    ("settings.density.comfortable", "Comfortable"),
+    ("settings.density.compact", "Compact"),
+    ("settings.idle.title", "IDLE DISCONNECT"),
+    ("settings.idle.description", "Stop the agent after this long without activity to free memory. Threads stay open and reconnect on the next prompt."),
+    ("settings.idle.never", "Never"),
+    ("settings.idle.minutes", "{minutes} min"),
+    ("settings.idle.hour", "1 hour"),
+    ("settings.auto_retry.title", "AUTO-RETRY ON FAILURE"),
+    ("settings.auto_retry.description", "When a command the agent ran fails, send its output back and ask for a fix, up to this many times a turn."),
+    ("settings.auto_retry.off", "Off"),
+    ("settings.auto_retry.times", "{count}×"),
+    ("settings.attachments.title", "ATTACHMENT SIZE LIMIT"),
+    ("settings.attachments.description", "Warn before a prompt embeds more attached text than this, with the option to truncate the files or send them as links."),
+    ("settings.attachments.kb", "{kb} KB"),
+    ("settings.attachments.none", "No limit"),
+    ("settings.markdown_cache.title", "RENDERED MESSAGE CACHE"),
+    ("settings.markdown_cache.description", "How many rendered messages to keep across threads. A larger cache makes switching threads faster but uses more memory."),
+    ("settings.markdown_cache.small", "Small"),
+    ("settings.markdown_cache.default", "Default"),
+    ("settings.markdown_cache.large", "Large"),
+    ("settings.markdown_cache.budget", "{entries} messages, {mb} MB"),
+    ("settings.proxy.title", "NETWORK PROXY"),
+    ("settings.proxy.description", "Used for agent downloads and passed to agents as HTTP(S)_PROXY. Leave blank to use the system environment."),
+    ("settings.proxy.saved", "Saved. Applies to new downloads and agent connections."),
+    ("settings.database.title", "DATABASE"),
+    ("settings.database.encrypt", "Encrypt conversation history"),
+    ("settings.database.encrypt_description", "Messages and tool output are encrypted on disk with a key kept in the system keychain."),
+    ("settings.database.back_up", "Back up"),
+    ("settings.database.daily", "Daily"),
+    ("settings.database.weekly", "Weekly"),
+    ("settings.database.no_backups", "No backups yet"),
+    ("settings.env.description", "Extra variables for agents started in {dir}. Pick one when creating a thread."),
+    ("settings.permissions.everywhere", "Everywhere"),
+    ("settings.permissions.this_workspace", "This workspace"),
+    ("settings.permissions.reads", "Reads"),
+    ("settings.permissions.writes", "Writes"),
+    ("settings.permissions.deletes", "Deletes"),
+    ("settings.permissions.terminal", "Terminal"),
+    ("settings.proxy.http", "HTTP proxy"),
+    ("settings.proxy.https", "HTTPS proxy"),
+    ("settings.proxy.no_proxy", "No proxy for"),
+    ("settings.container.title", "TERMINAL SANDBOX"),
+    ("settings.container.description", "Run agents' commands in a throwaway Docker or Podman container with this workspace mounted. The container settings apply to the current working directory."),
+    ("settings.container.run_on", "Run commands"),
+    ("settings.container.host", "On this machine"),
+    ("settings.container.container", "In a container"),
+    ("settings.container.runtime", "Runtime"),
+    ("settings.container.network", "Network access"),
+    ("settings.container.image", "Image"),
+    ("settings.container.mounts", "Extra mounts"),
+    ("settings.container.saved", "Saved. Applies to the next command."),
+    ("settings.limits.title", "COMMAND LIMITS"),
+    ("settings.limits.description", "Agent commands that go over a limit are killed and reported as \"limit exceeded\". Leave a field blank for no limit."),
+    ("settings.limits.cpu_time", "CPU time (seconds)"),
+    ("settings.limits.timeout", "Timeout (seconds)"),
+    ("settings.limits.output", "Max output (KB)"),
+    ("settings.limits.processes", "Commands at once"),
+    ("settings.limits.none", "No limit"),
+    ("settings.limits.saved", "Saved. Applies to the next command."),
+    ("settings.webhooks.title", "WEBHOOKS"),
+    ("settings.webhooks.description", "POST a JSON payload to these URLs when a turn finishes, an agent asks for permission or an agent crashes. With a secret, the body is signed in X-CocoWork-Signature."),
+    ("settings.webhooks.url_placeholder", "https://example.com/hook"),
+    ("settings.webhooks.secret_placeholder", "Secret (optional)"),
+    ("settings.webhooks.signed", "Signed"),
+    ("settings.webhooks.event.turn_complete", "Turn complete"),
+    ("settings.webhooks.event.permission_request", "Permission request"),
+    ("settings.webhooks.event.agent_crash", "Agent crash"),
+    ("settings.database.check", "Check Integrity"),
+    ("settings.database.vacuum", "Vacuum"),
+    ("settings.database.back_up_now", "Back Up Now"),
+
+    // Tool policy approvals
+    ("approval.default", "Default"),
+    ("approval.auto_accept", "Auto-accept"),
+    ("approval.confirm", "Confirm"),
+    ("approval.reads", "Reads"),
+    ("approval.writes", "Writes"),
+    ("approval.deletes", "Deletes"),
+    ("approval.terminal", "Terminal"),
+    ("approval.kind_auto", "{kind} auto"),
+    ("approval.kind_confirm", "{kind} confirm"),
+    ("approval.default_permissions", "Default permissions"),
+
+    // New thread dialog
+    ("new_thread.title", "New Thread"),
+    ("new_thread.subtask_title", "Spawn Sub-thread"),
+    ("new_thread.select_agent", "Select an agent"),
+    ("new_thread.advanced", "Advanced"),
+    ("new_thread.mcp_servers", "MCP servers"),
+    ("new_thread.variables", "Variables"),
+    ("new_thread.mode", "Mode"),
+    ("new_thread.model", "Model"),
+    ("new_thread.agent_default", "Agent default"),
+    ("new_thread.working_dir", "Working directory"),
+    ("new_thread.choose_dir", "Choose..."),
+    ("new_thread.isolate", "Isolate in a git worktree, or a copy outside git"),
+    ("new_thread.issue", "GitHub issue"),
+    ("new_thread.issue_placeholder", "https://github.com/owner/repo/issues/1"),
+    ("new_thread.fetch_issue", "Fetch"),
+    ("new_thread.fetching_issue", "Fetching the issue…"),
+    ("new_thread.issue_fetched", "{title} · {count} comments"),
+    ("new_thread.issue_not_fetched", "Fetch the issue before picking an agent"),
+
+    // Agent installs
+    ("installs.title", "Agent Installs"),
+    ("installs.none", "No installs in progress"),
+    ("installs.not_installed", "Not installed"),
+    ("installs.versions", "INSTALLED VERSIONS"),
+    ("installs.make_default", "Make default"),
+    ("installs.recheck", "Re-check"),
+
+    // Batch runs
+    ("batch.title", "Batch Run"),
+    ("batch.new", "New Batch"),
+    ("batch.concurrency", "Run at once:"),
+    ("batch.add_folders", "Add folders..."),
+    ("batch.prompt_placeholder", "Prompt to run in every workspace"),
+    ("batch.workspaces", "WORKSPACES ({count})"),
+    ("batch.in_order", "1 (in order)"),
+    ("batch.description", "Runs one prompt with {agent} in a new session per workspace"),
+
+    // Agent comparison
+    ("compare.title", "Compare Agents"),
+    ("compare.description", "Sends one prompt to two agents in parallel sessions"),
+    ("compare.close", "Close comparison"),
+    ("compare.prompt_placeholder", "Prompt to send to both agents"),
+    ("compare.agent", "AGENT {label}"),
+    ("compare.first_output", "first output {time}"),
+    ("compare.total", "total {time}"),
+    ("compare.tokens", "~{count} tokens (est.)"),
+
+    // Agent sign-in
+    ("auth.title", "Sign in to {agent}"),
+    ("auth.signing_in", "Signing in..."),
+    ("auth.retry", "I've signed in, retry"),
+
+    // Change review
+    ("changes.accept", "Accept {accepted} of {total}"),
+    ("changes.selected", "{accepted}/{total} selected"),
+    ("changes.accept_all", "Accept all"),
+    ("isolation.title", "Isolated Changes"),
+    ("isolation.no_changes", "No changes to merge back"),
+    ("isolation.merge_back", "Merge back"),
+    ("isolation.discard", "Discard worktree"),
+    ("isolation.merged", "Merged into {dir}, uncommitted for review"),
+    ("commit.changes", "Commit changes"),
+    ("commit.committed", "Committed {hash}: {subject}"),
+    ("commit.copy_hash", "Copy the commit hash"),
+    ("commit.title", "Commit the Agent's Changes"),
+    ("commit.no_changes", "The files the agent edited match the last commit"),
+    ("commit.placeholder", "Commit message"),
+    ("commit.draft", "Draft with the agent"),
+    ("commit.drafting", "The agent is drafting a message…"),
+    ("commit.commit", "Commit"),
+    ("pr.title", "Open a Pull Request"),
+    ("pr.token_hint", "Pull requests on {host} are opened with a {forge} token that can write to the repository. It is kept in the system keychain."),
+    ("pr.token_placeholder", "Token"),
+    ("pr.save_token", "Save token"),
+    ("pr.target", "{head} → {base} on {repo}"),
+    ("pr.title_placeholder", "Title"),
+    ("pr.body_placeholder", "Description"),
+    ("pr.drafting", "The agent is drafting the pull request…"),
+    ("pr.opening", "Pushing the branch and opening the pull request…"),
+    ("pr.open", "Open pull request"),
+    ("pr.opened", "Opened pull request #{number}"),
+    ("pr.view", "View in browser"),
+    ("pr.add_gitlab_host", "Add {host} as a GitLab server"),
+    ("redact.title", "Review Before Exporting"),
+    ("redact.hint", "Checked strings are replaced in the exported copy. The thread itself isn't changed."),
+    ("redact.kind.secret", "Secret"),
+    ("redact.kind.email", "Email"),
+    ("redact.kind.path", "Path"),
+    ("redact.rule.secret", "All secrets ({count})"),
+    ("redact.rule.email", "All emails ({count})"),
+    ("redact.rule.path", "All paths ({count})"),
+    ("redact.none_found", "No paths, emails or keys found"),
+    ("redact.term_placeholder", "Hide another word or name..."),
+    ("redact.add_term", "Hide"),
+    ("redact.summary", "{hidden} of {count} found strings hidden"),
+    ("redact.copy", "Copy"),
+    ("redact.export", "Export..."),
+
+    // Live terminals
+    ("terminal.running", "Running"),
+    ("terminal.exited", "Exited with code {code}"),
+    ("terminal.kill", "Kill"),
+    ("terminal.close", "Close"),
+    ("terminal.input_placeholder", "Type into the terminal, Enter to send"),
+    ("terminal.earlier_output", "… earlier output not shown"),
+
+    // Terminal panel
+    ("terminal_panel.placeholder", "Run a command in the workspace"),
+    ("terminal_panel.restart", "Restart"),
+    ("terminal_panel.hide", "Hide"),
+
+    // Quoting output
+    ("quote.output", "Quote into prompt"),
+    ("quote.lines", "Quote {count} lines"),
+
+    // Agent questions
+    ("question.title", "The agent is asking"),
+    ("question.placeholder", "Type your answer…"),
+    ("question.reply", "Reply"),
+    ("question.skip", "Skip"),
+
+    // Permission requests
+    ("confirm.more_waiting", "{count} more waiting"),
+    ("confirm.execute", "Run command?"),
+    ("confirm.read", "Allow read?"),
+    ("confirm.write", "Allow write?"),
+    ("confirm.delete", "Allow delete?"),
+
+    // Project search
+    ("search.placeholder", "Search files"),
+    ("search.add_to_context", "Add results to context"),
+    ("search.searching", "Searching... {count} results"),
+    ("search.open_file", "Open File"),
+    ("search.show_in_folder", "Show in Folder"),
+    ("search.idle", "Search the text of every file in the workspace"),
+    ("search.results", "{count} results in {files} files"),
+    ("search.truncated", " (stopped at the limit)"),
+    ("search.read_failed", "Couldn't read file: {error}"),
+    ("search.open_failed", "Couldn't open file: {error}"),
+    ("search.file_info", "{lines} lines · {bytes} bytes · read-only"),
+    ("search.preview_truncated", "Showing the first {shown} of {total} lines"),
+
+    // Audit log
+    ("audit.placeholder", "Filter by path or command"),
+    ("audit.none", "No agent operations match these filters"),
+    ("audit.entries", "{count} entries"),
+    ("audit.showing_latest", "Showing the latest {shown} of {total} entries"),
+    ("audit.export_failed", "Failed to export audit log: {error}"),
+    ("audit.exported", "Exported to {path}"),
+    ("audit.this_thread", "This thread"),
+    ("audit.all_outcomes", "All outcomes"),
+    ("audit.all_operations", "All operations"),
+    ("audit.export_csv", "Export CSV"),
+    ("audit.export_json", "Export JSON"),
+    ("audit.outcome.allowed", "Allowed"),
+    ("audit.outcome.approved", "Approved"),
+    ("audit.outcome.auto_approved", "Auto-approved"),
+    ("audit.outcome.recorded", "Dry run"),
+    ("audit.outcome.rejected", "Rejected"),
+    ("audit.outcome.denied", "Denied"),
+    ("audit.outcome.failed", "Failed"),
+    ("audit.outcome.limit_exceeded", "Limit exceeded"),
+
+    // Usage dashboard
+    ("dashboard.days", "{days} days"),
+    ("task_board.title", "Tasks across threads"),
+    ("task_board.planning", "PLANNING"),
+    ("task_board.in_progress", "IN PROGRESS"),
+    ("task_board.completed", "COMPLETED"),
+    ("task_board.failed", "FAILED"),
+    ("task_board.empty", "Nothing here"),
+    ("task_board.steps", "{done}/{total} steps"),
+    ("dashboard.prompts", "Prompts"),
+    ("dashboard.tool_calls", "Tool calls"),
+    ("dashboard.files_modified", "Files modified"),
+    ("dashboard.session_time", "Session time"),
+    ("dashboard.prompts_per_day", "PROMPTS PER DAY"),
+    ("dashboard.tool_calls_by_kind", "TOOL CALLS BY KIND"),
+    ("dashboard.agents", "AGENTS"),
+    ("dashboard.metrics", "METRICS"),
+    ("dashboard.metrics_off", "Metrics are off. Turn them on in Settings to count sessions and errors and time replies."),
+    ("dashboard.metrics_empty", "Nothing recorded yet."),
+    ("dashboard.metrics_counts", "{sessions} sessions started · {errors} errors"),
+    ("dashboard.first_reply", "First reply"),
+    ("dashboard.turn_time", "Whole turn"),
+    ("dashboard.latency", "{label}: median {p50}, 95% under {p95}"),
+    ("dashboard.clear_metrics", "Clear"),
+
+    // Quick prompt
+    ("quick_prompt.menu", "Quick Prompt…"),
+    ("quick_prompt.placeholder", "Ask the agent anything…"),
+    ("quick_prompt.destination", "New thread with {agent} in {workspace}"),
+    ("quick_prompt.no_agent", "No agent selected"),
+    ("quick_prompt.hint", "Enter to send · Shift+Enter for a new line · Esc to close"),
+
+    // Errors and results from the agent manager
+    ("manager.select_agent", "Select Agent"),
+    ("manager.no_agent_selected", "No agent selected"),
+    ("manager.not_connected", "Not connected to agent"),
+    ("manager.connect_failed", "Failed to connect"),
+    ("manager.connection_cancelled", "Connection task cancelled"),
+    ("manager.sign_in_failed", "Sign-in failed"),
+    ("manager.create_session_failed", "Failed to create session"),
+    ("manager.session_creation_cancelled", "Session creation task cancelled"),
+    ("manager.resume_session_failed", "Failed to resume session"),
+    ("manager.session_resume_cancelled", "Session resume task cancelled"),
+    ("manager.continuing_in_new_session", "{error}; continuing in a new session"),
+    ("manager.isolate_failed", "Failed to isolate the session"),
+    ("manager.send_prompt_failed", "Failed to send prompt: {error}"),
+    ("manager.prompt_empty", "Prompt is empty"),
+    ("manager.agent_busy", "The agent is still working"),
+    ("manager.no_active_thread", "No active thread"),
+    ("manager.thread_not_found", "Thread not found"),
+    ("manager.profile_name_empty", "Profile name is empty"),
+    ("manager.template_name_empty", "Template name is empty"),
+    ("manager.template_text_empty", "Template text is empty"),
+    ("manager.nothing_to_compact", "Nothing to compact right now"),
+    ("manager.compaction_no_summary", "Compaction failed: the agent didn't return a summary"),
+    ("manager.audit_load_failed", "Failed to load audit log: {error}"),
+    ("manager.not_isolated", "Thread isn't isolated"),
+    ("manager.compare_workspace_failed", "Failed to compare with the workspace: {error}"),
+    ("manager.merge_back_failed", "Failed to merge back: {error}"),
+    ("manager.remove_failed", "Failed to remove {path}: {error}"),
+    ("manager.compare_commit_failed", "Failed to compare with the last commit: {error}"),
+    ("manager.commit_failed", "Failed to commit: {error}"),
+    ("manager.nothing_to_commit", "Nothing to commit"),
+    ("manager.title_empty", "The title is empty"),
+    ("manager.forge_token_missing", "Add a {forge} token first"),
+    ("manager.pr_stopped", "Pull request stopped"),
+    ("manager.not_issue_url", "Not a GitHub issue URL"),
+    ("manager.issue_import_stopped", "Issue import stopped"),
+    ("manager.no_workspaces", "Add at least one workspace"),
+    ("manager.pick_two_agents", "Pick two agents to compare"),
+    ("manager.no_test_command", "No test command set for this workspace"),
+    ("manager.test_run_stopped", "Test run stopped"),
+    ("manager.no_check_commands", "No build or lint commands set for this workspace"),
+    ("manager.check_run_stopped", "Build and lint run stopped"),
+    ("manager.no_search_workspace", "Choose a workspace folder to search"),
+    ("manager.file_preview_stopped", "File preview stopped"),
+    ("manager.no_thread_to_share", "No thread to share"),
+    ("manager.vacuum_failed", "Vacuum failed: {error}"),
+    ("manager.vacuumed", "Vacuumed: {before} MB → {after} MB"),
+    ("manager.vacuumed_plain", "Vacuumed"),
+    ("manager.backup_failed", "Backup failed: {error}"),
+    ("manager.backed_up", "Backed up to {path}"),
+    ("manager.no_backup", "No backup to restore"),
+    ("manager.restart_to_restore", "Restart CocoWork to restore the backup from {time}"),
+    ("manager.stage_restore_failed", "Failed to stage restore: {error}"),
+    ("manager.encryption_failed", "Encryption failed: {error}"),
+    ("manager.keychain_save_failed", "Failed to save the key to the keychain: {error}"),
+    ("manager.encrypted", "Encrypted {count} stored values"),
+    ("manager.decryption_failed", "Decryption failed: {error}"),
+    ("manager.decrypted", "Decrypted {count} stored values"),
+    ("manager.integrity_failed", "Integrity check failed: {error}"),
+    ("manager.no_problems", "No problems found"),
+    ("manager.problems_found", "{count} problem(s) found, first: {first}"),
+    ("manager.database_task_panicked", "Database task panicked: {error}"),
+    ("manager.database_task_stopped", "Database task stopped"),
+];
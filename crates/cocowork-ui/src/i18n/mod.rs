@@ -0,0 +1,177 @@
+//! Localized UI strings
+//!
+//! User-facing text is looked up by key in the catalog of the current
+//! language, falling back to English and then to the key itself. Keys are
+//! dotted and grouped by where the text appears, like `sidebar.threads` or
+//! `settings.proxy.title`. Text that includes values uses `{name}`
+//! placeholders, filled in by [`t_args`].
+//!
+//! To add a language, add a catalog module with the same keys as `en` and a
+//! [`Language`] variant pointing at it.
+
+mod en;
+mod zh_cn;
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+/// A language the UI can be shown in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    English,
+    SimplifiedChinese,
+}
+
+impl Language {
+    /// Languages in the order they're offered in settings
+    pub const ALL: [Language; 2] = [Language::English, Language::SimplifiedChinese];
+
+    /// The code the language is stored under
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::English => "en",
+            Self::SimplifiedChinese => "zh-CN",
+        }
+    }
+
+    /// The language's name in itself, so it can be found without reading the current one
+    pub fn native_name(self) -> &'static str {
+        match self {
+            Self::English => "English",
+            Self::SimplifiedChinese => "简体中文",
+        }
+    }
+
+    /// The language for a stored code or a POSIX locale like `zh_CN.UTF-8`
+    ///
+    /// Only the language and region are read, so `en_GB` is English. Chinese
+    /// without a region is taken as Simplified.
+    pub fn from_code(code: &str) -> Option<Self> {
+        let tag = code.split(['.', '@']).next().unwrap_or_default().replace('_', "-").to_ascii_lowercase();
+        let mut parts = tag.split('-');
+        match (parts.next()?, parts.next()) {
+            ("en", _) => Some(Self::English),
+            ("zh", None | Some("cn" | "sg" | "hans")) => Some(Self::SimplifiedChinese),
+            _ => None,
+        }
+    }
+
+    fn catalog(self) -> &'static HashMap<&'static str, &'static str> {
+        static EN: OnceLock<HashMap<&str, &str>> = OnceLock::new();
+        static ZH_CN: OnceLock<HashMap<&str, &str>> = OnceLock::new();
+        let (cell, strings) = match self {
+            Self::English => (&EN, en::STRINGS),
+            Self::SimplifiedChinese => (&ZH_CN, zh_cn::STRINGS),
+        };
+        cell.get_or_init(|| strings.iter().copied().collect())
+    }
+}
+
+/// The language strings are currently looked up in
+static LANGUAGE: AtomicU8 = AtomicU8::new(0);
+
+/// The language the UI is shown in
+pub fn language() -> Language {
+    Language::ALL
+        .get(LANGUAGE.load(Ordering::Relaxed) as usize)
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Show the UI in `language` from the next render on
+pub fn set_language(language: Language) {
+    let idx = Language::ALL.iter().position(|l| *l == language).unwrap_or(0);
+    LANGUAGE.store(idx as u8, Ordering::Relaxed);
+}
+
+/// The language of the user's locale, English if it isn't one we have
+pub fn system_language() -> Language {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .and_then(|locale| Language::from_code(&locale))
+        .unwrap_or_default()
+}
+
+/// The text for `key` in the current language
+pub fn t(key: &'static str) -> &'static str {
+    lookup(language(), key)
+}
+
+/// The text for `key` with each `{name}` placeholder replaced by its value
+pub fn t_args(key: &'static str, args: &[(&str, &dyn Display)]) -> String {
+    fill(lookup(language(), key), args)
+}
+
+fn lookup(language: Language, key: &'static str) -> &'static str {
+    language
+        .catalog()
+        .get(key)
+        .or_else(|| Language::English.catalog().get(key))
+        .copied()
+        .unwrap_or_else(|| {
+            tracing::debug!("Missing UI string {}", key);
+            key
+        })
+}
+
+fn fill(template: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut text = template.to_string();
+    for (name, value) in args {
+        text = text.replace(&format!("{{{}}}", name), &value.to_string());
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholders(text: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = text
+            .split('{')
+            .skip(1)
+            .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn test_catalogs_match_english() {
+        for language in Language::ALL {
+            let strings = match language {
+                Language::English => en::STRINGS,
+                Language::SimplifiedChinese => zh_cn::STRINGS,
+            };
+            assert_eq!(language.catalog().len(), strings.len(), "duplicate key in {}", language.code());
+            assert_eq!(strings.len(), en::STRINGS.len(), "{} is missing strings", language.code());
+            for (key, text) in strings {
+                let english = lookup(Language::English, key);
+                assert_ne!(english, *key, "{} has a key English doesn't: {}", language.code(), key);
+                assert_eq!(placeholders(text), placeholders(english), "placeholders differ for {}", key);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lookup_and_fill() {
+        assert_eq!(lookup(Language::SimplifiedChinese, "common.save"), "保存");
+        assert_eq!(lookup(Language::SimplifiedChinese, "no.such.key"), "no.such.key");
+        assert_eq!(
+            fill(lookup(Language::English, "changes.accept"), &[("accepted", &2), ("total", &5)]),
+            "Accept 2 of 5"
+        );
+        assert_eq!(Language::from_code("zh_CN.UTF-8"), Some(Language::SimplifiedChinese));
+        assert_eq!(Language::from_code("zh-TW"), None);
+        assert_eq!(Language::from_code("en_GB"), Some(Language::English));
+        assert_eq!(Language::from_code("C"), None);
+        for language in Language::ALL {
+            assert_eq!(Language::from_code(language.code()), Some(language));
+        }
+    }
+}
@@ -0,0 +1,696 @@
+//! Simplified Chinese
+
+pub(super) static STRINGS: &[(&str, &str)] = &[
+    // Shared across views
+    ("common.save", "保存"),
+    ("common.remove", "移除"),
+    ("common.run", "运行"),
+    ("common.dismiss", "忽略"),
+    ("common.cancel", "取消"),
+    ("common.settings", "设置"),
+    ("common.loading", "加载中…"),
+    ("common.working", "处理中…"),
+    ("common.done", "完成"),
+    ("common.edit", "编辑"),
+    ("common.add", "添加"),
+    ("common.reset", "重置"),
+    ("common.refresh", "刷新"),
+    ("common.keep", "保留"),
+    ("common.default", "默认"),
+    ("common.current", "当前"),
+    ("common.latest", "最新"),
+    ("common.install", "安装"),
+    ("common.on", "开"),
+    ("common.off", "关"),
+    ("common.saved", "已保存"),
+    ("common.select_all", "全选"),
+    ("common.select_none", "全不选"),
+    ("common.deny", "拒绝"),
+    ("common.restore", "恢复"),
+    ("common.manage", "管理…"),
+    ("common.new_badge", "新"),
+    ("common.usage", "用量"),
+    ("common.checking", "检查中…"),
+    ("common.more", "还有 {count} 个"),
+    ("common.hide", "隐藏"),
+    ("common.close", "关闭"),
+    ("common.stop", "停止"),
+    ("common.search", "搜索"),
+    ("common.continue", "继续"),
+    ("common.retry", "重试"),
+    ("common.failed", "失败"),
+    ("common.queued", "排队中"),
+    ("common.running", "运行中…"),
+    ("common.connecting", "连接中…"),
+    ("common.none", "无"),
+    ("common.in_dir", "位于 {dir}"),
+    ("common.tool_calls", "{count} 次工具调用"),
+    ("common.prompt", "提示词：{prompt}"),
+
+    // Relative times
+    ("time.just_now", "刚刚"),
+    ("time.minutes_ago", "{count} 分钟前"),
+    ("time.hours_ago", "{count} 小时前"),
+
+    // File dialog titles
+    ("dialog.export_thread", "导出对话"),
+    ("dialog.diagnostic_bundle", "保存诊断包"),
+    ("dialog.select_workspace", "选择智能体工作区"),
+    ("dialog.session_working_dir", "选择会话工作目录"),
+    ("dialog.add_file", "添加文件"),
+    ("dialog.add_batch_workspaces", "添加批量工作区"),
+    ("dialog.export_audit_log", "导出审计日志"),
+
+    // Thread sidebar
+    ("sidebar.threads", "对话"),
+    ("sidebar.no_threads", "还没有对话"),
+    ("sidebar.search_placeholder", "搜索对话"),
+    ("sidebar.no_match", "没有匹配“{query}”的对话"),
+    ("sidebar.clear_search", "清除搜索"),
+    ("sidebar.new_thread", "新建对话"),
+    ("sidebar.fork_name", "{name}（分叉）"),
+    ("sidebar.search_files", "搜索文件"),
+    ("sidebar.dashboard", "仪表盘"),
+    ("sidebar.audit_log", "审计日志"),
+    ("sidebar.task_board", "任务看板"),
+    ("sidebar.group_today", "今天"),
+    ("sidebar.group_yesterday", "昨天"),
+    ("sidebar.group_this_week", "本周"),
+    ("sidebar.group_older", "更早"),
+
+    // User menu
+    ("menu.account", "账户与设置"),
+    ("menu.about", "关于"),
+    ("menu.check_updates", "检查智能体更新"),
+    ("menu.batch_run", "批量运行…"),
+    ("menu.diagnostic_bundle", "生成诊断包…"),
+
+    // Bottom bar
+    ("status.connected", "已连接"),
+    ("status.mcp", "MCP：{count}"),
+    ("status.reset_zoom", "重置缩放 (Cmd+0)"),
+    ("status.show_sidebar", "显示侧边栏 (Cmd+B)"),
+    ("status.hide_sidebar", "隐藏侧边栏 (Cmd+B)"),
+    ("status.show_context_panel", "显示上下文面板 (Cmd+Alt+B)"),
+    ("status.hide_context_panel", "隐藏上下文面板 (Cmd+Alt+B)"),
+    ("status.show_terminal_panel", "显示终端 (Ctrl+`)"),
+    ("status.hide_terminal_panel", "隐藏终端 (Ctrl+`)"),
+    ("status.messages", "{count} 条消息"),
+
+    // MCP server panel
+    ("mcp.title", "MCP 服务器"),
+    ("mcp.none", "未配置 MCP 服务器"),
+    ("mcp.add", "+ 添加服务器"),
+
+    // Thread header
+    ("header.compare", "对比"),
+    ("header.outline", "大纲"),
+    ("header.thread_actions", "对话操作"),
+    ("header.idle", "空闲 · 将重新连接"),
+    ("header.dry_run", "试运行"),
+    ("header.dry_run_on", "试运行：开"),
+    ("header.auto", "自动"),
+    ("header.auto_on", "自动：开"),
+    ("header.compact", "压缩"),
+    ("header.compacting", "压缩中…"),
+    ("header.new_thread", "新对话"),
+    ("header.export_markdown", "导出为 Markdown…"),
+    ("header.export_html", "导出为 HTML…"),
+    ("header.collapse_thinking", "折叠全部思考"),
+    ("header.hide_thinking", "在此对话中隐藏思考"),
+    ("header.show_thinking", "在此对话中显示思考"),
+    ("header.review_flagged", "审阅已标记的消息"),
+    ("header.exit_review", "退出审阅"),
+    ("header.share_live", "共享实时视图…"),
+    ("header.stop_sharing", "停止共享"),
+    ("header.open_pull_request", "创建拉取请求…"),
+    ("header.agent_capabilities", "代理能力"),
+    ("capabilities.title", "代理能力"),
+    ("capabilities.not_connected", "连接到代理后即可查看其支持的功能。"),
+    ("capabilities.not_reported", "未提供"),
+    ("capabilities.agent", "代理"),
+    ("capabilities.protocol", "协议版本"),
+    ("capabilities.auth", "登录方式"),
+    ("capabilities.features", "功能"),
+    ("capabilities.session", "当前对话"),
+    ("capabilities.modes", "模式"),
+    ("capabilities.models", "模型"),
+    ("capabilities.none", "无"),
+    ("capabilities.copy_json", "复制为 JSON"),
+
+    // Banners above the timeline
+    ("banner.auto_mode", "自动模式已开启：智能体的请求将不经询问直接批准"),
+    ("banner.turn_off", "关闭"),
+    ("banner.retrying", "自动重试第 {attempt}/{limit} 次：失败命令的输出已发回给智能体"),
+    ("banner.retry_gave_up", "自动重试已放弃：尝试 {count} 次后命令仍然失败"),
+    ("banner.stop_retrying", "停止重试"),
+    ("banner.forked_from", "分叉自“{name}”的前 {count} 条消息"),
+    ("banner.forked", "分叉自前 {count} 条消息"),
+    ("banner.open_original", "打开原对话"),
+    ("banner.from_issue", "从议题 {issue} 开始"),
+    ("banner.open_issue", "打开议题"),
+    ("banner.roll_back", "回滚到 {tag}"),
+    ("banner.restore_backup", "从备份恢复"),
+    ("banner.exported", "对话已导出到 {path}"),
+    ("banner.sharing", "实时观看地址：{url} · {viewers} 人正在观看"),
+    ("banner.copy_link", "复制链接"),
+    ("banner.share_failed", "无法共享对话：{error}"),
+    ("banner.read_only", "另一个 CocoWork 实例正在使用此数据目录。在其关闭前，此处的更改不会被保存。"),
+    ("banner.read_only_retry", "重试"),
+    ("banner.no_thread_to_export", "没有可导出的对话"),
+    ("banner.export_failed", "导出对话失败：{error}"),
+    ("banner.crashed", "CocoWork 上次意外退出。生成包含日志和设置（已去除密钥）的诊断包，便于报告问题。"),
+    ("banner.generate_bundle", "生成诊断包"),
+    ("banner.bundle_saved", "诊断包已保存到 {path}"),
+    ("banner.bundle_failed", "保存诊断包失败：{error}"),
+    ("banner.link_no_thread", "此窗口中没有打开对话 {id}"),
+    ("banner.link_no_workspace", "无法从链接创建对话：{dir} 不是文件夹"),
+    ("banner.link_unknown_agent", "无法从链接创建对话：没有名为 {agent} 的智能体"),
+    ("banner.link_import_failed", "无法打开分享的对话 {file}：{error}"),
+    ("banner.workspace_mismatch", "此对话在 {thread} 中工作，但当前工作区已切换为 {workspace}。智能体只能访问当前工作区中的文件。"),
+    ("banner.switch_workspace", "切换回去并发送"),
+    ("banner.new_thread_here", "在此处的新对话中发送"),
+    ("banner.isolated_worktree", "正在分支 {branch} 的隔离 worktree 中工作"),
+    ("banner.isolated_copy", "正在 {dir} 的隔离副本中工作"),
+    ("banner.review_changes", "查看改动"),
+    ("banner.attachments_over_limit", "附件会给提示增加约 {size}，超过了 {limit} 的上限。"),
+    ("banner.truncate_attachments", "截断以适应"),
+    ("banner.link_attachments", "以链接发送"),
+    ("banner.send_anyway", "仍然发送"),
+    ("banner.incompatible", "无法连接到 {agent}。最新版本可能不兼容。"),
+    ("banner.auto_mode_detail", "工作区外的路径和被禁止的命令仍会被拒绝。目前已批准 {count} 个，均已记录。"),
+    ("banner.database_restore", "无法打开数据库，历史记录未被保存。要从 {time} 的备份恢复吗？"),
+    ("banner.database_no_backup", "无法打开数据库，且没有可恢复的备份。历史记录未被保存。"),
+    ("banner.restore_failed", "恢复失败：{error}"),
+
+    // Message timeline
+    ("timeline.empty_title", "开始对话"),
+    ("timeline.empty_hint", "在下方输入消息，与 CocoWork 智能体对话"),
+    ("timeline.empty_tip", "使用 📁 设置工作区，+ 添加附件"),
+    ("timeline.thinking", "思考"),
+    ("timeline.compacted", "对话已压缩 · 之前轮次的摘要"),
+    ("timeline.turn_took", " · 本轮耗时 {duration}"),
+    ("timeline.streaming", "已运行 {elapsed}"),
+    ("timeline.spawn_subthread", "↳ 创建子对话"),
+    ("timeline.fork", "⑂ 从此处分叉"),
+    ("timeline.pin", "📌 置顶"),
+    ("timeline.unpin", "取消置顶"),
+    ("pinned.title", "已置顶"),
+    ("pinned.you", "你：{text}"),
+    ("review.title", "审阅已标记"),
+    ("review.all", "全部"),
+    ("timeline.stream_rate", " · 约 {rate} token/秒 · 约 {tokens} 个 token"),
+    ("timeline.tool_call", "工具调用"),
+    ("timeline.max_tokens", "回复因达到模型输出上限被截断。继续以从中断处接着生成。"),
+    ("timeline.max_turn_requests", "智能体已用完本轮的请求次数。继续以让它接着执行。"),
+    ("timeline.refusal", "智能体拒绝了此请求。请换个说法再试。"),
+    ("timeline.cancelled", "本轮在完成前停止。重试以再次运行。"),
+    ("timeline.interrupted", "回复已中断"),
+    ("timeline.interrupted_detail", "应用在回复完成前关闭；以下是已收到的内容。"),
+    ("timeline.resume", "继续"),
+    ("timeline.regenerate", "重新生成"),
+    ("timeline.actions", "{count} 个操作"),
+    ("timeline.actions_files", "涉及 {count} 个文件"),
+    ("timeline.actions_commands", "运行 {count} 条命令"),
+    ("timeline.actions_failed", "{count} 个失败"),
+    ("timeline.jump_latest_tool_call", "最新工具调用"),
+    ("timeline.jump_bottom", "跳到底部"),
+    ("debug.title", "调试（⌘⇧D 隐藏）"),
+    ("debug.fps", "帧率"),
+    ("debug.timeline", "时间线行 / 条目"),
+    ("debug.notifications", "待处理通知"),
+    ("debug.requests", "等待响应的请求"),
+    ("debug.tasks", "Tokio 任务 / 工作线程"),
+    ("debug.db_pool", "使用中的数据库连接"),
+    ("debug.markdown_cache", "Markdown 缓存"),
+    ("debug.markdown_cache_use", "命中 / 未命中 / 淘汰"),
+    ("timeline.error", "智能体报告了错误。请重试；如果持续失败，请重新连接智能体。"),
+    ("timeline.error_detail", "{error}。请重试；如果持续失败，请重新连接智能体。"),
+    ("stop_reason.end_turn", "已完成"),
+    ("stop_reason.max_tokens", "因达到令牌上限而停止"),
+    ("stop_reason.max_turn_requests", "因达到请求上限而停止"),
+    ("stop_reason.refusal", "已拒绝"),
+    ("stop_reason.cancelled", "已取消"),
+    ("stop_reason.error", "失败"),
+
+    // Message input
+    ("input.placeholder", "给 CocoWork 智能体发消息…"),
+    ("input.send", "发送 (Enter)"),
+    ("input.size", "约 {tokens} 个 token · {size}"),
+    ("input.secrets_masked", "将遮盖 {count} 处密钥"),
+    ("input.secrets_unmasked", "{count} 处密钥将原样发送"),
+    ("input.secrets_unmask", "发送此消息时不遮盖"),
+    ("input.secrets_mask", "遮盖此消息中的密钥"),
+    ("input.size_breakdown", "消息 {message} · 文件 {files} · 指令 {instructions}"),
+    ("input.manage_templates", "管理模板…"),
+    ("input.no_templates", "还没有保存的模板"),
+    ("input.indexing", "正在索引工作区…"),
+    ("input.no_matching_files", "没有匹配的文件"),
+    ("input.external_edits", "自智能体上次读取后工作区已更改：{files}"),
+    ("input.attach_external_edits", "附加变更摘要"),
+    ("input.external_edits_attached", "已附加摘要"),
+    ("input.vim_normal", "普通"),
+    ("input.vim_insert", "插入"),
+
+    // Prompt history search
+    ("history.title", "历史"),
+    ("history.none", "没有匹配的提示词"),
+    ("history.hint", "Enter 使用，Esc 关闭"),
+    ("history.placeholder", "搜索之前的提示词"),
+
+    // Context panel
+    ("context.progress", "进度"),
+    ("context.instructions", "指令"),
+    ("context.tests", "测试"),
+    ("context.problems", "问题"),
+    ("context.artifacts", "产物"),
+    ("context.context", "上下文"),
+    ("context.no_plan", "没有进行中的计划"),
+    ("plan.priority.high", "高"),
+    ("plan.priority.medium", "中"),
+    ("plan.priority.low", "低"),
+    ("plan.mark_done", "标记为完成"),
+    ("plan.mark_skipped", "标记为跳过"),
+    ("plan.reset", "使用智能体的状态"),
+    ("plan.spawn_subthread", "为此步骤创建子对话"),
+    ("context.no_tool_calls", "还没有工具调用"),
+    ("context.proposed_changes", "待应用的更改"),
+    ("context.dry_run_hint", "试运行已开启。智能体的更改将在此列出。"),
+    ("context.apply", "应用 {count} 项"),
+    ("context.discard_all", "全部丢弃"),
+    ("context.instructions_placeholder", "例如：使用 pnpm。结束前运行 `pnpm test`。"),
+    ("context.no_artifacts", "还没有产物"),
+    ("context.no_context", "未添加上下文"),
+    ("context.instructions_scope", "随 {dir} 中每个新对话的第一条提示词一起发送"),
+
+    // Workspace tests
+    ("tests.command_placeholder", "例如 cargo test"),
+    ("tests.run", "运行测试"),
+    ("tests.running", "运行中…"),
+    ("tests.run_after_turn", "每轮结束后运行"),
+    ("tests.passed", "已通过"),
+    ("tests.passed_count", "{count} 个通过"),
+    ("tests.failed_count", "{count} 个失败"),
+    ("tests.exit_code", "退出码 {code}"),
+    ("tests.counts", "{passed} 个通过，{failed} 个失败，{skipped} 个跳过"),
+    ("tests.last_run", "上次运行：{counts}，用时 {seconds} 秒"),
+    ("tests.attach", "附加到下一条提示词"),
+
+    // Build and lint problems
+    ("problems.commands_hint", "构建和检查命令，每行一条"),
+    ("problems.commands_placeholder", "例如 cargo clippy"),
+    ("problems.diagnostics_file_hint", "其他工具写入的 JSON 诊断文件，变化时重新读取"),
+    ("problems.diagnostics_file_placeholder", "例如 target/diagnostics.json"),
+    ("problems.run", "运行"),
+    ("problems.counts", "{errors} 个错误，{warnings} 个警告"),
+    ("problems.none", "没有问题"),
+    ("problems.command_failed", "命令失败"),
+    ("problems.exited", "`{command}` 退出码 {code}"),
+    ("problems.more", "……还有 {count} 个"),
+    ("problems.send", "将问题发送给智能体"),
+
+    // Settings dialog
+    ("settings.api_keys.title", "API 密钥"),
+    ("settings.api_keys.description", "保存在系统钥匙串中，优先于环境变量使用。更改在下次连接时生效。"),
+    ("settings.api_keys.placeholder", "粘贴 {label}"),
+    ("settings.api_keys.stored", "已保存：{preview}"),
+    ("settings.api_keys.not_stored", "未保存（使用 ${var}）"),
+    ("settings.env.title", "环境配置"),
+    ("settings.env.badge", "环境"),
+    ("settings.env.name_placeholder", "配置名称"),
+    ("settings.templates.title", "提示词模板"),
+    ("settings.templates.name_placeholder", "模板名称"),
+    ("settings.permissions.title", "工具权限"),
+    ("settings.permissions.description", "哪些智能体请求无需询问即可接受。工作区策略优先于智能体策略，智能体策略优先于全局策略。"),
+    ("settings.permissions.in_effect", "当前生效：{summary}"),
+    ("settings.timestamps.title", "时间戳"),
+    ("settings.timestamps.description", "显示每条消息的发送时间、每轮耗时以及每次工具调用的运行时长。"),
+    ("settings.follow_ups.title", "后续建议"),
+    ("settings.follow_ups.description", "智能体完成一轮后，根据其回复、计划和工具调用推荐几条后续提示词。"),
+    ("settings.vim.title", "VIM 模式"),
+    ("settings.vim.description", "用 Vim 按键编辑消息。Esc 切换到普通模式，可使用 w、b、$ 等移动和 dd、ciw 等命令；按 i、a 或 o 返回输入。"),
+    ("settings.clipboard_capture.title", "剪贴板捕获"),
+    ("settings.clipboard_capture.description", "在 CocoWork 处于活动状态时按 Cmd+Alt+Space，打开快速提问并以代码块形式填入剪贴板内容，例如复制的堆栈跟踪。"),
+    ("settings.thoughts.title", "显示思考"),
+    ("settings.thoughts.description", "在对话中显示智能体的思考过程。每个对话可在其 ··· 菜单中单独设置。"),
+    ("settings.thoughts.collapse_title", "自动折叠思考"),
+    ("settings.thoughts.collapse_description", "智能体开始回答后自动折叠思考。点击思考标题可重新展开。"),
+    ("settings.share_lan.title", "在局域网共享"),
+    ("settings.share_lan.description", "让同一网络中的其他电脑也能打开共享的会话，而不只是本机。网络中任何拿到链接的人都能观看。"),
+    ("settings.offline.title", "离线模式"),
+    ("settings.offline.description", "CocoWork 自身不访问网络：不下载智能体、不执行 npm 安装、不检查更新。只能连接已安装的智能体。"),
+    ("settings.metrics.title", "本地指标"),
+    ("settings.metrics.description", "统计会话数和错误数并记录回复耗时，显示在仪表盘中。不会发送到任何地方，指标只保存在本机数据库中。"),
+    ("settings.protocol_log.title", "协议日志"),
+    ("settings.protocol_log.description", "将与智能体往来的每条消息写入数据目录下的 logs/acp.jsonl，用于调试智能体。消息中可能包含文件内容。"),
+    ("settings.redaction.title", "密钥脱敏"),
+    ("settings.redaction.description", "在智能体看到之前，遮盖提示词和终端输出中的 API 密钥、云凭据、URL 中的密码以及 .env 中的机密值。日志始终会被遮盖。"),
+    ("settings.redaction.patterns_description", "另外需要遮盖的自定义模式，使用正则表达式。"),
+    ("settings.redaction.pattern_placeholder", "例如 acme-[0-9]{6}"),
+    ("settings.language.title", "语言"),
+    ("settings.language.description", "CocoWork 菜单、按钮和提示所用的语言。智能体的回复不会被翻译。"),
+    ("settings.density.title", "显示密度"),
+    ("settings.density.description", "紧凑模式会缩小间距、行高和文字，让小屏幕上能显示更多时间线内容。"),
+    ("settings.density.comfortable", "舒适"),
+    ("settings.density.compact", "紧凑"),
+    ("settings.idle.title", "空闲断开"),
+    ("settings.idle.description", "智能体无活动超过此时长后停止以释放内存。对话保持打开，并在下一条提示词时重新连接。"),
+    ("settings.idle.never", "从不"),
+    ("settings.idle.minutes", "{minutes} 分钟"),
+    ("settings.idle.hour", "1 小时"),
+    ("settings.auto_retry.title", "失败时自动重试"),
+    ("settings.auto_retry.description", "智能体运行的命令失败时，将输出发回并要求修复，每轮最多重试此次数。"),
+    ("settings.auto_retry.off", "关闭"),
+    ("settings.auto_retry.times", "{count} 次"),
+    ("settings.attachments.title", "附件大小上限"),
+    ("settings.attachments.description", "提示中嵌入的附件文本超过此大小时发出提醒，可选择截断文件或以链接发送。"),
+    ("settings.attachments.kb", "{kb} KB"),
+    ("settings.attachments.none", "不限制"),
+    ("settings.markdown_cache.title", "消息渲染缓存"),
+    ("settings.markdown_cache.description", "跨会话保留多少条已渲染的消息。缓存越大，切换会话越快，但占用内存更多。"),
+    ("settings.markdown_cache.small", "小"),
+    ("settings.markdown_cache.default", "默认"),
+    ("settings.markdown_cache.large", "大"),
+    ("settings.markdown_cache.budget", "{entries} 条消息，{mb} MB"),
+    ("settings.proxy.title", "网络代理"),
+    ("settings.proxy.description", "用于下载智能体，并作为 HTTP(S)_PROXY 传给智能体。留空则使用系统环境。"),
+    ("settings.proxy.saved", "已保存。对新的下载和智能体连接生效。"),
+    ("settings.database.title", "数据库"),
+    ("settings.database.encrypt", "加密对话历史"),
+    ("settings.database.encrypt_description", "消息和工具输出在磁盘上加密，密钥保存在系统钥匙串中。"),
+    ("settings.database.back_up", "备份"),
+    ("settings.database.daily", "每天"),
+    ("settings.database.weekly", "每周"),
+    ("settings.database.no_backups", "还没有备份"),
+    ("settings.env.description", "在 {dir} 中启动的智能体的额外变量。创建对话时选择。"),
+    ("settings.permissions.everywhere", "全局"),
+    ("settings.permissions.this_workspace", "此工作区"),
+    ("settings.permissions.reads", "读取"),
+    ("settings.permissions.writes", "写入"),
+    ("settings.permissions.deletes", "删除"),
+    ("settings.permissions.terminal", "终端"),
+    ("settings.proxy.http", "HTTP 代理"),
+    ("settings.proxy.https", "HTTPS 代理"),
+    ("settings.proxy.no_proxy", "不使用代理"),
+    ("settings.container.title", "终端沙箱"),
+    ("settings.container.description", "在挂载了此工作区的临时 Docker 或 Podman 容器中运行智能体的命令。容器设置仅适用于当前工作目录。"),
+    ("settings.container.run_on", "命令运行于"),
+    ("settings.container.host", "本机"),
+    ("settings.container.container", "容器"),
+    ("settings.container.runtime", "运行时"),
+    ("settings.container.network", "网络访问"),
+    ("settings.container.image", "镜像"),
+    ("settings.container.mounts", "额外挂载"),
+    ("settings.container.saved", "已保存，将用于下一条命令。"),
+    ("settings.limits.title", "命令限制"),
+    ("settings.limits.description", "超出限制的智能体命令会被终止，并报告为“超出限制”。留空表示不限制。"),
+    ("settings.limits.cpu_time", "CPU 时间（秒）"),
+    ("settings.limits.timeout", "超时（秒）"),
+    ("settings.limits.output", "最大输出（KB）"),
+    ("settings.limits.processes", "同时运行的命令数"),
+    ("settings.limits.none", "不限制"),
+    ("settings.limits.saved", "已保存，将用于下一条命令。"),
+    ("settings.webhooks.title", "Webhook"),
+    ("settings.webhooks.description", "在轮次结束、智能体请求权限或智能体崩溃时，向这些 URL 发送 JSON 数据。设置密钥后，请求体的签名放在 X-CocoWork-Signature 中。"),
+    ("settings.webhooks.url_placeholder", "https://example.com/hook"),
+    ("settings.webhooks.secret_placeholder", "密钥（可选）"),
+    ("settings.webhooks.signed", "已签名"),
+    ("settings.webhooks.event.turn_complete", "轮次完成"),
+    ("settings.webhooks.event.permission_request", "权限请求"),
+    ("settings.webhooks.event.agent_crash", "智能体崩溃"),
+    ("settings.database.check", "检查完整性"),
+    ("settings.database.vacuum", "压缩数据库"),
+    ("settings.database.back_up_now", "立即备份"),
+
+    // Tool policy approvals
+    ("approval.default", "默认"),
+    ("approval.auto_accept", "自动接受"),
+    ("approval.confirm", "确认"),
+    ("approval.reads", "读取"),
+    ("approval.writes", "写入"),
+    ("approval.deletes", "删除"),
+    ("approval.terminal", "终端"),
+    ("approval.kind_auto", "{kind}自动"),
+    ("approval.kind_confirm", "{kind}需确认"),
+    ("approval.default_permissions", "默认权限"),
+
+    // New thread dialog
+    ("new_thread.title", "新建对话"),
+    ("new_thread.subtask_title", "创建子对话"),
+    ("new_thread.select_agent", "选择智能体"),
+    ("new_thread.advanced", "高级"),
+    ("new_thread.mcp_servers", "MCP 服务器"),
+    ("new_thread.variables", "环境变量"),
+    ("new_thread.mode", "模式"),
+    ("new_thread.model", "模型"),
+    ("new_thread.agent_default", "智能体默认"),
+    ("new_thread.working_dir", "工作目录"),
+    ("new_thread.choose_dir", "选择..."),
+    ("new_thread.isolate", "在 git worktree 中隔离运行，非 git 目录则使用副本"),
+    ("new_thread.issue", "GitHub 议题"),
+    ("new_thread.issue_placeholder", "https://github.com/owner/repo/issues/1"),
+    ("new_thread.fetch_issue", "获取"),
+    ("new_thread.fetching_issue", "正在获取议题…"),
+    ("new_thread.issue_fetched", "{title} · {count} 条评论"),
+    ("new_thread.issue_not_fetched", "请先获取议题再选择智能体"),
+
+    // Agent installs
+    ("installs.title", "智能体安装"),
+    ("installs.none", "没有正在进行的安装"),
+    ("installs.not_installed", "未安装"),
+    ("installs.versions", "已安装版本"),
+    ("installs.make_default", "设为默认"),
+    ("installs.recheck", "重新检查"),
+
+    // Batch runs
+    ("batch.title", "批量运行"),
+    ("batch.new", "新建批次"),
+    ("batch.concurrency", "同时运行："),
+    ("batch.add_folders", "添加文件夹…"),
+    ("batch.prompt_placeholder", "在每个工作区运行的提示词"),
+    ("batch.workspaces", "工作区（{count}）"),
+    ("batch.in_order", "1（按顺序）"),
+    ("batch.description", "使用 {agent} 在每个工作区的新会话中运行同一提示词"),
+
+    // Agent comparison
+    ("compare.title", "对比智能体"),
+    ("compare.description", "在两个并行会话中将同一提示词发送给两个智能体"),
+    ("compare.close", "关闭对比"),
+    ("compare.prompt_placeholder", "发送给两个智能体的提示词"),
+    ("compare.agent", "智能体 {label}"),
+    ("compare.first_output", "首次输出 {time}"),
+    ("compare.total", "总计 {time}"),
+    ("compare.tokens", "约 {count} 个 token（估算）"),
+
+    // Agent sign-in
+    ("auth.title", "登录 {agent}"),
+    ("auth.signing_in", "正在登录…"),
+    ("auth.retry", "我已登录，重试"),
+
+    // Change review
+    ("changes.accept", "接受 {total} 项中的 {accepted} 项"),
+    ("changes.selected", "已选 {accepted}/{total}"),
+    ("changes.accept_all", "全部接受"),
+    ("isolation.title", "隔离改动"),
+    ("isolation.no_changes", "没有需要合并的改动"),
+    ("isolation.merge_back", "合并回工作区"),
+    ("isolation.discard", "丢弃 worktree"),
+    ("isolation.merged", "已合并到 {dir}，未提交，待审阅"),
+    ("commit.changes", "提交改动"),
+    ("commit.committed", "已提交 {hash}：{subject}"),
+    ("commit.copy_hash", "复制提交哈希"),
+    ("commit.title", "提交智能体的改动"),
+    ("commit.no_changes", "智能体编辑的文件与上次提交一致"),
+    ("commit.placeholder", "提交信息"),
+    ("commit.draft", "让智能体起草"),
+    ("commit.drafting", "智能体正在起草提交信息…"),
+    ("commit.commit", "提交"),
+    ("pr.title", "创建拉取请求"),
+    ("pr.token_hint", "在 {host} 上创建拉取请求需要一个可写入该仓库的 {forge} 令牌，令牌保存在系统钥匙串中。"),
+    ("pr.token_placeholder", "令牌"),
+    ("pr.save_token", "保存令牌"),
+    ("pr.target", "{head} → {base}，仓库 {repo}"),
+    ("pr.title_placeholder", "标题"),
+    ("pr.body_placeholder", "描述"),
+    ("pr.drafting", "智能体正在起草拉取请求…"),
+    ("pr.opening", "正在推送分支并创建拉取请求…"),
+    ("pr.open", "创建拉取请求"),
+    ("pr.opened", "已创建拉取请求 #{number}"),
+    ("pr.view", "在浏览器中查看"),
+    ("pr.add_gitlab_host", "将 {host} 添加为 GitLab 服务器"),
+    ("redact.title", "导出前检查"),
+    ("redact.hint", "勾选的内容会在导出的副本中被替换，对话本身不会改变。"),
+    ("redact.kind.secret", "密钥"),
+    ("redact.kind.email", "邮箱"),
+    ("redact.kind.path", "路径"),
+    ("redact.rule.secret", "全部密钥（{count}）"),
+    ("redact.rule.email", "全部邮箱（{count}）"),
+    ("redact.rule.path", "全部路径（{count}）"),
+    ("redact.none_found", "未发现路径、邮箱或密钥"),
+    ("redact.term_placeholder", "隐藏其他词或名称…"),
+    ("redact.add_term", "隐藏"),
+    ("redact.summary", "已隐藏 {hidden}/{count} 处发现的内容"),
+    ("redact.copy", "复制"),
+    ("redact.export", "导出…"),
+
+    // Live terminals
+    ("terminal.running", "运行中"),
+    ("terminal.exited", "已退出，退出码 {code}"),
+    ("terminal.kill", "终止"),
+    ("terminal.close", "关闭"),
+    ("terminal.input_placeholder", "输入到终端，按 Enter 发送"),
+    ("terminal.earlier_output", "… 更早的输出未显示"),
+
+    // Terminal panel
+    ("terminal_panel.placeholder", "在工作区中运行命令"),
+    ("terminal_panel.restart", "重启"),
+    ("terminal_panel.hide", "隐藏"),
+
+    // Quoting output
+    ("quote.output", "引用到输入框"),
+    ("quote.lines", "引用 {count} 行"),
+
+    // Agent questions
+    ("question.title", "Agent 提问"),
+    ("question.placeholder", "输入你的回答…"),
+    ("question.reply", "回复"),
+    ("question.skip", "跳过"),
+
+    // Permission requests
+    ("confirm.more_waiting", "还有 {count} 个待确认"),
+    ("confirm.execute", "运行命令？"),
+    ("confirm.read", "允许读取？"),
+    ("confirm.write", "允许写入？"),
+    ("confirm.delete", "允许删除？"),
+
+    // Project search
+    ("search.placeholder", "搜索文件"),
+    ("search.add_to_context", "将结果添加到上下文"),
+    ("search.searching", "搜索中… 已找到 {count} 个结果"),
+    ("search.open_file", "打开文件"),
+    ("search.show_in_folder", "在文件夹中显示"),
+    ("search.idle", "搜索工作区中所有文件的文本"),
+    ("search.results", "{files} 个文件中有 {count} 个结果"),
+    ("search.truncated", "（已达上限而停止）"),
+    ("search.read_failed", "无法读取文件：{error}"),
+    ("search.open_failed", "无法打开文件：{error}"),
+    ("search.file_info", "{lines} 行 · {bytes} 字节 · 只读"),
+    ("search.preview_truncated", "显示 {total} 行中的前 {shown} 行"),
+
+    // Audit log
+    ("audit.placeholder", "按路径或命令筛选"),
+    ("audit.none", "没有符合筛选条件的智能体操作"),
+    ("audit.entries", "共 {count} 条"),
+    ("audit.showing_latest", "显示 {total} 条中最新的 {shown} 条"),
+    ("audit.export_failed", "导出审计日志失败：{error}"),
+    ("audit.exported", "已导出到 {path}"),
+    ("audit.this_thread", "此对话"),
+    ("audit.all_outcomes", "所有结果"),
+    ("audit.all_operations", "所有操作"),
+    ("audit.export_csv", "导出 CSV"),
+    ("audit.export_json", "导出 JSON"),
+    ("audit.outcome.allowed", "已允许"),
+    ("audit.outcome.approved", "已批准"),
+    ("audit.outcome.auto_approved", "自动批准"),
+    ("audit.outcome.recorded", "试运行"),
+    ("audit.outcome.rejected", "已拒绝"),
+    ("audit.outcome.denied", "已禁止"),
+    ("audit.outcome.failed", "失败"),
+    ("audit.outcome.limit_exceeded", "超出限制"),
+
+    // Usage dashboard
+    ("dashboard.days", "{days} 天"),
+    ("task_board.title", "各对话中的任务"),
+    ("task_board.planning", "规划中"),
+    ("task_board.in_progress", "进行中"),
+    ("task_board.completed", "已完成"),
+    ("task_board.failed", "失败"),
+    ("task_board.empty", "暂无任务"),
+    ("task_board.steps", "{done}/{total} 步"),
+    ("dashboard.prompts", "提示词"),
+    ("dashboard.tool_calls", "工具调用"),
+    ("dashboard.files_modified", "修改的文件"),
+    ("dashboard.session_time", "会话时长"),
+    ("dashboard.prompts_per_day", "每日提示词"),
+    ("dashboard.tool_calls_by_kind", "按类型统计的工具调用"),
+    ("dashboard.agents", "智能体"),
+    ("dashboard.metrics", "指标"),
+    ("dashboard.metrics_off", "指标已关闭。在设置中开启后可统计会话数和错误数并记录回复耗时。"),
+    ("dashboard.metrics_empty", "尚无记录。"),
+    ("dashboard.metrics_counts", "已开始 {sessions} 个会话 · {errors} 个错误"),
+    ("dashboard.first_reply", "首次回复"),
+    ("dashboard.turn_time", "整轮耗时"),
+    ("dashboard.latency", "{label}：中位数 {p50}，95% 低于 {p95}"),
+    ("dashboard.clear_metrics", "清除"),
+
+    // Quick prompt
+    ("quick_prompt.menu", "快速提问…"),
+    ("quick_prompt.placeholder", "向智能体提问…"),
+    ("quick_prompt.destination", "在 {workspace} 中与 {agent} 新建会话"),
+    ("quick_prompt.no_agent", "未选择智能体"),
+    ("quick_prompt.hint", "Enter 发送 · Shift+Enter 换行 · Esc 关闭"),
+
+    // Errors and results from the agent manager
+    ("manager.select_agent", "选择智能体"),
+    ("manager.no_agent_selected", "未选择智能体"),
+    ("manager.not_connected", "未连接到智能体"),
+    ("manager.connect_failed", "连接失败"),
+    ("manager.connection_cancelled", "连接任务已取消"),
+    ("manager.sign_in_failed", "登录失败"),
+    ("manager.create_session_failed", "创建会话失败"),
+    ("manager.session_creation_cancelled", "创建会话任务已取消"),
+    ("manager.resume_session_failed", "恢复会话失败"),
+    ("manager.session_resume_cancelled", "恢复会话任务已取消"),
+    ("manager.continuing_in_new_session", "{error}；将在新会话中继续"),
+    ("manager.isolate_failed", "隔离会话失败"),
+    ("manager.send_prompt_failed", "发送提示失败：{error}"),
+    ("manager.prompt_empty", "提示为空"),
+    ("manager.agent_busy", "智能体仍在工作"),
+    ("manager.no_active_thread", "没有活动的对话"),
+    ("manager.thread_not_found", "找不到对话"),
+    ("manager.profile_name_empty", "配置名称为空"),
+    ("manager.template_name_empty", "模板名称为空"),
+    ("manager.template_text_empty", "模板内容为空"),
+    ("manager.nothing_to_compact", "目前没有可压缩的内容"),
+    ("manager.compaction_no_summary", "压缩失败：智能体没有返回摘要"),
+    ("manager.audit_load_failed", "加载审计日志失败：{error}"),
+    ("manager.not_isolated", "此对话未隔离"),
+    ("manager.compare_workspace_failed", "与工作区比较失败：{error}"),
+    ("manager.merge_back_failed", "合并回工作区失败：{error}"),
+    ("manager.remove_failed", "删除 {path} 失败：{error}"),
+    ("manager.compare_commit_failed", "与上次提交比较失败：{error}"),
+    ("manager.commit_failed", "提交失败：{error}"),
+    ("manager.nothing_to_commit", "没有可提交的内容"),
+    ("manager.title_empty", "标题为空"),
+    ("manager.forge_token_missing", "请先添加 {forge} 令牌"),
+    ("manager.pr_stopped", "拉取请求已中止"),
+    ("manager.not_issue_url", "不是 GitHub issue 链接"),
+    ("manager.issue_import_stopped", "Issue 导入已中止"),
+    ("manager.no_workspaces", "请至少添加一个工作区"),
+    ("manager.pick_two_agents", "请选择两个要比较的智能体"),
+    ("manager.no_test_command", "此工作区未设置测试命令"),
+    ("manager.test_run_stopped", "测试运行已中止"),
+    ("manager.no_check_commands", "此工作区未设置构建或检查命令"),
+    ("manager.check_run_stopped", "构建和检查已中止"),
+    ("manager.no_search_workspace", "请选择要搜索的工作区文件夹"),
+    ("manager.file_preview_stopped", "文件预览已中止"),
+    ("manager.no_thread_to_share", "没有可共享的对话"),
+    ("manager.vacuum_failed", "整理数据库失败：{error}"),
+    ("manager.vacuumed", "已整理：{before} MB → {after} MB"),
+    ("manager.vacuumed_plain", "已整理"),
+    ("manager.backup_failed", "备份失败：{error}"),
+    ("manager.backed_up", "已备份到 {path}"),
+    ("manager.no_backup", "没有可恢复的备份"),
+    ("manager.restart_to_restore", "重启 CocoWork 以恢复 {time} 的备份"),
+    ("manager.stage_restore_failed", "无法准备恢复：{error}"),
+    ("manager.encryption_failed", "加密失败：{error}"),
+    ("manager.keychain_save_failed", "无法将密钥保存到钥匙串：{error}"),
+    ("manager.encrypted", "已加密 {count} 个存储的值"),
+    ("manager.decryption_failed", "解密失败：{error}"),
+    ("manager.decrypted", "已解密 {count} 个存储的值"),
+    ("manager.integrity_failed", "完整性检查失败：{error}"),
+    ("manager.no_problems", "未发现问题"),
+    ("manager.problems_found", "发现 {count} 个问题，第一个：{first}"),
+    ("manager.database_task_panicked", "数据库任务崩溃：{error}"),
+    ("manager.database_task_stopped", "数据库任务已中止"),
+];
@@ -32,13 +32,21 @@
 //! ```
 
 pub mod acp_integration;
+pub mod auto_retry;
+pub mod cli;
 pub mod components;
+pub mod follow_ups;
+pub mod i18n;
 pub mod panels;
 pub mod state;
 pub mod theme;
+pub mod tool_render;
 pub mod views;
 
 // Re-exports
-pub use acp_integration::{AcpManager, AcpModel, AcpSession, ConnectionState};
+pub use acp_integration::{
+    AcpManager, AcpModel, AcpSession, ConnectionState, DatabaseTask, FilePreviewState, NewSessionOptions, ProjectSearch,
+    PromptSizeEstimate, StorageRecovery, TaskCard, TaskColumn, TurnEnd, DEFAULT_ATTACHMENT_LIMIT,
+};
 pub use state::{AppState, ContextTab, SessionState, SimpleAppState, TopicNode};
 pub use theme::{layout, Rgba, Spacing, Theme, ThemeColors, Typography};
@@ -2,17 +2,23 @@
 //!
 //! GPUI-based desktop client for interacting with AI coding agents via ACP.
 
+use cocowork_core::RedactingWriter;
+use cocowork_ui::cli;
 use cocowork_ui::components::register_text_input_bindings;
-use cocowork_ui::Theme;
+use cocowork_ui::state::{DeepLink, DEEP_LINK_SCHEME};
+use cocowork_ui::{AcpModel, Theme};
 use gpui::*;
+use cocowork_ui::acp_integration::{app_log_path, logs_dir};
 use std::borrow::Cow;
+use std::fs::{File, OpenOptions};
 use std::path::PathBuf;
+use std::sync::Mutex;
 use tracing::info;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 mod window;
 
-use window::CocoWorkWindow;
+use window::{initial_window_bounds, register_quick_prompt, CocoWorkWindow};
 
 /// Asset source that loads from the filesystem relative to the executable or current directory
 struct FileAssetSource {
@@ -72,37 +78,92 @@ impl AssetSource for FileAssetSource {
     }
 }
 
+/// Size past which the log file is moved aside at startup
+const MAX_LOG_SIZE: u64 = 5 * 1024 * 1024;
+
+/// Open the app's log file for appending, keeping one older file once it grows too big
+fn open_log_file() -> std::io::Result<File> {
+    let path = app_log_path();
+    std::fs::create_dir_all(logs_dir())?;
+    if std::fs::metadata(&path).is_ok_and(|meta| meta.len() > MAX_LOG_SIZE) {
+        std::fs::rename(&path, path.with_extension("log.1"))?;
+    }
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
 fn main() {
-    // Initialize logging
+    // Subcommands work on stored data and exit without opening a window. On
+    // Linux and Windows a `cocowork://` link that launches the app comes in
+    // as an argument instead, and opens the window
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let launch_links: Vec<String> = args.iter().filter(|arg| DeepLink::is_link(arg)).cloned().collect();
+    if launch_links.is_empty() {
+        match cli::parse_args(&args) {
+            Ok(Some(command)) => {
+                if let Err(e) = cli::run(command) {
+                    eprintln!("cocowork: {}", e);
+                    std::process::exit(1);
+                }
+                return;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                eprintln!("cocowork: {}\n\n{}", e, cli::USAGE);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    // Crashes leave a report behind, offered as a diagnostic bundle next launch
+    cocowork_core::install_panic_hook(logs_dir());
+
+    // Initialize logging, to the terminal and to a log file for diagnostic bundles
+    let log_file = open_log_file()
+        .map_err(|e| eprintln!("cocowork: can't open log file: {}", e))
+        .ok();
     tracing_subscriber::registry()
         .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
-        .with(tracing_subscriber::fmt::layer())
+        .with(tracing_subscriber::fmt::layer().with_writer(|| RedactingWriter::new(std::io::stdout())))
+        .with(log_file.map(|file| {
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(Mutex::new(RedactingWriter::new(file)))
+        }))
         .init();
 
     info!("CocoWork v{}", env!("CARGO_PKG_VERSION"));
 
+    // Links opened while the app runs are handed over by the OS
+    let (link_tx, mut link_rx) = tokio::sync::mpsc::unbounded_channel();
+    for link in launch_links {
+        let _ = link_tx.send(link);
+    }
+
     // Start GPUI application with asset loading
-    App::new()
-        .with_assets(FileAssetSource::new())
-        .run(|cx: &mut AppContext| {
-        // Register key bindings for text input
+    let app = App::new().with_assets(FileAssetSource::new());
+    app.on_open_urls(move |urls| {
+        for url in urls {
+            let _ = link_tx.send(url);
+        }
+    });
+    app.run(move |cx: &mut AppContext| {
+        // Register key bindings for text input and moving focus around the window
         register_text_input_bindings(cx);
+        window::register_bindings(cx);
 
         // Initialize theme
         let theme = Theme::dark();
         info!("Theme initialized: dark mode");
 
-        // Open main window
+        // Open main window where it was last left
+        let acp = AcpModel::new();
         let window_options = WindowOptions {
             titlebar: Some(TitlebarOptions {
                 title: Some("CocoWork".into()),
                 appears_transparent: true,
                 traffic_light_position: Some(point(px(9.0), px(9.0))),
             }),
-            window_bounds: Some(WindowBounds::Windowed(Bounds {
-                origin: point(px(100.0), px(100.0)),
-                size: size(px(1200.0), px(800.0)),
-            })),
+            window_bounds: Some(initial_window_bounds(&acp.manager.window_layout, cx)),
             focus: true,
             show: true,
             kind: WindowKind::Normal,
@@ -112,9 +173,25 @@ fn main() {
             ..Default::default()
         };
 
-        cx.open_window(window_options, |cx| {
-            cx.new_view(|cx| CocoWorkWindow::new(cx, theme))
+        let main_window = cx
+            .open_window(window_options, |cx| {
+                cx.new_view(|cx| CocoWorkWindow::new(cx, theme, acp))
+            })
+            .unwrap();
+
+        // Quick prompts go through the main window's agent connection
+        register_quick_prompt(main_window, cx);
+
+        // `cocowork://` links open in the main window
+        cx.register_url_scheme(DEEP_LINK_SCHEME).detach_and_log_err(cx);
+        cx.spawn(|mut cx| async move {
+            while let Some(url) = link_rx.recv().await {
+                let _ = main_window.update(&mut cx, |view, cx| {
+                    cx.activate_window();
+                    view.open_link(&url, cx);
+                });
+            }
         })
-        .unwrap();
+        .detach();
     });
 }
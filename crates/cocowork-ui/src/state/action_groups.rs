@@ -0,0 +1,157 @@
+//! Folding a turn's tool calls into one timeline row
+//!
+//! Between the prompt and the agent's final answer a turn can run dozens
+//! of tool calls, with bits of narration in between. The timeline shows
+//! them as one collapsible "N actions" group per turn: everything from the
+//! prompt up to the last tool call, leaving the answer after it in view.
+
+use cocowork_core::{ToolCallContent, ToolCallKind, ToolCallState, ToolCallStatus};
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// What a timeline entry is, as far as grouping goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimelineEntry {
+    /// A message the user sent, which starts a turn
+    Prompt,
+    /// Anything the agent said or thought
+    Reply,
+    /// A tool call
+    Action,
+}
+
+/// Where entries of the timeline go, in order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimelineSpan {
+    /// One entry shown by itself
+    Single(usize),
+    /// Entries of a turn folded into a group; `turn` counts turns from 0
+    Actions { turn: usize, range: Range<usize> },
+}
+
+/// Lay out `entries` with each turn's tool calls, and what's between them, grouped
+pub fn group_turn_actions(entries: &[TimelineEntry]) -> Vec<TimelineSpan> {
+    let mut spans = Vec::with_capacity(entries.len());
+    let mut turn = 0;
+    let mut start = 0;
+    while start < entries.len() {
+        if entries[start] == TimelineEntry::Prompt {
+            if start > 0 {
+                turn += 1;
+            }
+            spans.push(TimelineSpan::Single(start));
+            start += 1;
+            continue;
+        }
+        let end = entries[start..]
+            .iter()
+            .position(|entry| *entry == TimelineEntry::Prompt)
+            .map_or(entries.len(), |offset| start + offset);
+        match entries[start..end].iter().rposition(|entry| *entry == TimelineEntry::Action) {
+            Some(last_action) => {
+                spans.push(TimelineSpan::Actions { turn, range: start..start + last_action + 1 });
+                spans.extend((start + last_action + 1..end).map(TimelineSpan::Single));
+            }
+            None => spans.extend((start..end).map(TimelineSpan::Single)),
+        }
+        start = end;
+    }
+    spans
+}
+
+/// Counts shown on a collapsed group
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ActionSummary {
+    pub actions: usize,
+    /// Distinct files read, written, moved or deleted
+    pub files_touched: usize,
+    pub commands_run: usize,
+    pub failed: usize,
+    /// Whether any call is still pending or running
+    pub running: bool,
+}
+
+impl ActionSummary {
+    pub fn of<'a>(calls: impl IntoIterator<Item = &'a ToolCallState>) -> Self {
+        let mut summary = Self::default();
+        let mut files = HashSet::new();
+        for call in calls {
+            summary.actions += 1;
+            match call.kind {
+                Some(ToolCallKind::Execute | ToolCallKind::Terminal | ToolCallKind::Bash) => summary.commands_run += 1,
+                Some(
+                    ToolCallKind::Read
+                    | ToolCallKind::Write
+                    | ToolCallKind::Edit
+                    | ToolCallKind::Create
+                    | ToolCallKind::Delete
+                    | ToolCallKind::Move,
+                ) => files.extend(input_paths(call)),
+                _ => {}
+            }
+            files.extend(call.content.iter().filter_map(|content| match content {
+                ToolCallContent::Diff { diff } => Some(diff.path.clone()),
+                _ => None,
+            }));
+            match call.status {
+                ToolCallStatus::Failed => summary.failed += 1,
+                ToolCallStatus::Pending | ToolCallStatus::InProgress => summary.running = true,
+                ToolCallStatus::Completed | ToolCallStatus::Cancelled => {}
+            }
+        }
+        summary.files_touched = files.len();
+        summary
+    }
+}
+
+/// Paths named in a call's input, under the keys agents commonly use
+fn input_paths(call: &ToolCallState) -> Vec<String> {
+    let Some(input) = call.input.as_ref() else {
+        return Vec::new();
+    };
+    ["path", "file_path", "abs_path", "old_path", "new_path"]
+        .iter()
+        .filter_map(|key| input.get(key).and_then(|value| value.as_str()))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use TimelineEntry::{Action, Prompt, Reply};
+
+    #[test]
+    fn test_group_turn_actions() {
+        let spans = group_turn_actions(&[Prompt, Reply, Action, Reply, Action, Reply, Prompt, Reply, Prompt, Action]);
+        assert_eq!(
+            spans,
+            [
+                TimelineSpan::Single(0),
+                TimelineSpan::Actions { turn: 0, range: 1..5 },
+                TimelineSpan::Single(5),
+                TimelineSpan::Single(6),
+                TimelineSpan::Single(7),
+                TimelineSpan::Single(8),
+                TimelineSpan::Actions { turn: 2, range: 9..10 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_action_summary() {
+        let mut read = ToolCallState::new("1".to_string(), None, Some(ToolCallKind::Read));
+        read.input = Some(serde_json::json!({ "path": "/work/src/main.rs" }));
+        read.status = ToolCallStatus::Completed;
+        let mut edit = ToolCallState::new("2".to_string(), None, Some(ToolCallKind::Edit));
+        edit.input = Some(serde_json::json!({ "file_path": "/work/src/main.rs" }));
+        let mut test = ToolCallState::new("3".to_string(), None, Some(ToolCallKind::Bash));
+        test.status = ToolCallStatus::Failed;
+
+        let summary = ActionSummary::of([&read, &edit, &test]);
+        assert_eq!(
+            summary,
+            ActionSummary { actions: 3, files_touched: 1, commands_run: 1, failed: 1, running: true }
+        );
+    }
+}
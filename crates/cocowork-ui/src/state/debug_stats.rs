@@ -0,0 +1,92 @@
+//! Numbers for the debug overlay
+//!
+//! The overlay is for working out why the UI stutters, e.g. during heavy
+//! streaming: how often the window draws, and how backed up the pieces
+//! feeding it are.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Stretch of time frame rates are worked out over
+const FRAME_WINDOW: Duration = Duration::from_secs(1);
+
+/// When the last second's frames were drawn
+#[derive(Debug, Clone, Default)]
+pub struct FrameTimes {
+    frames: VecDeque<Instant>,
+}
+
+impl FrameTimes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a frame drawn at `now`
+    pub fn record(&mut self, now: Instant) {
+        self.frames.push_back(now);
+        while self
+            .frames
+            .front()
+            .is_some_and(|&frame| now.saturating_duration_since(frame) > FRAME_WINDOW)
+        {
+            self.frames.pop_front();
+        }
+    }
+
+    /// Frames drawn in the second before `now`
+    pub fn fps(&self, now: Instant) -> usize {
+        self.frames
+            .iter()
+            .filter(|&&frame| now.saturating_duration_since(frame) <= FRAME_WINDOW)
+            .count()
+    }
+
+    /// Longest gap between two frames of the last second
+    pub fn slowest_frame(&self) -> Duration {
+        self.frames
+            .iter()
+            .zip(self.frames.iter().skip(1))
+            .map(|(a, b)| b.saturating_duration_since(*a))
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+/// What the background side of the app is up to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RuntimeStats {
+    /// Agent notifications received but not yet handled
+    pub notification_backlog: usize,
+    /// Requests sent to the agent still waiting for a response
+    pub pending_requests: usize,
+    /// Tasks alive on the Tokio runtime
+    pub tokio_tasks: usize,
+    pub tokio_workers: usize,
+    /// Database connections open, of which idle, out of the pool's maximum
+    pub db_connections: u32,
+    pub db_idle: u32,
+    pub db_max: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_times() {
+        let start = Instant::now();
+        let mut frames = FrameTimes::new();
+        assert_eq!(frames.fps(start), 0);
+
+        for ms in [0, 16, 32, 150, 166] {
+            frames.record(start + Duration::from_millis(ms));
+        }
+        assert_eq!(frames.fps(start + Duration::from_millis(200)), 5);
+        assert_eq!(frames.slowest_frame(), Duration::from_millis(118));
+
+        // Frames older than a second drop out
+        frames.record(start + Duration::from_millis(1100));
+        assert_eq!(frames.fps(start + Duration::from_millis(1100)), 3);
+        assert_eq!(frames.slowest_frame(), Duration::from_millis(934));
+    }
+}
@@ -0,0 +1,156 @@
+//! `cocowork://` links
+//!
+//! Other apps open CocoWork at a given place with a URL:
+//!
+//! ```text
+//! cocowork://thread/<session-id>                       Show an open thread
+//! cocowork://new?cwd=<dir>&prompt=<text>&agent=<id>    Start a thread in <dir>, the prompt filled in
+//! cocowork://import?path=<file>                        Open a thread exported as JSON
+//! ```
+//!
+//! Query values are percent-encoded; `prompt` and `agent` are optional.
+
+use std::path::PathBuf;
+
+/// URL scheme registered for CocoWork links
+pub const DEEP_LINK_SCHEME: &str = "cocowork";
+
+/// Where a `cocowork://` link asks to go
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeepLink {
+    /// Show the thread for a session
+    OpenThread { session_id: String },
+    /// Start a thread in a workspace, with a prompt ready to send
+    NewThread {
+        working_dir: PathBuf,
+        prompt: Option<String>,
+        agent_id: Option<String>,
+    },
+    /// Open a shared thread archive
+    ImportArchive { path: PathBuf },
+}
+
+impl DeepLink {
+    /// Whether `arg` looks like a `cocowork://` link rather than a command
+    pub fn is_link(arg: &str) -> bool {
+        arg.get(..DEEP_LINK_SCHEME.len())
+            .is_some_and(|scheme| scheme.eq_ignore_ascii_case(DEEP_LINK_SCHEME))
+            && arg[DEEP_LINK_SCHEME.len()..].starts_with("://")
+    }
+
+    pub fn parse(url: &str) -> Result<Self, String> {
+        if !Self::is_link(url) {
+            return Err(format!("not a {}:// link: {}", DEEP_LINK_SCHEME, url));
+        }
+        let rest = &url[DEEP_LINK_SCHEME.len() + 3..];
+        let rest = rest.split('#').next().unwrap_or_default();
+        let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let mut params = Vec::new();
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            params.push((percent_decode(key)?, percent_decode(value)?));
+        }
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone())
+                .filter(|value| !value.trim().is_empty())
+        };
+
+        let mut segments = path.split('/').filter(|segment| !segment.is_empty());
+        let link = match segments.next() {
+            Some("thread") => {
+                let session_id = segments.next().map(percent_decode).transpose()?;
+                match session_id {
+                    Some(session_id) if !session_id.is_empty() => DeepLink::OpenThread { session_id },
+                    _ => return Err("the link doesn't say which thread to open".to_string()),
+                }
+            }
+            Some("new") => DeepLink::NewThread {
+                working_dir: param("cwd")
+                    .map(PathBuf::from)
+                    .ok_or_else(|| "the link doesn't say which workspace to use".to_string())?,
+                prompt: param("prompt"),
+                agent_id: param("agent"),
+            },
+            Some("import") => DeepLink::ImportArchive {
+                path: param("path")
+                    .map(PathBuf::from)
+                    .ok_or_else(|| "the link doesn't say which archive to open".to_string())?,
+            },
+            Some(other) => return Err(format!("unknown link: {}://{}", DEEP_LINK_SCHEME, other)),
+            None => return Err(format!("empty link: {}", url)),
+        };
+        Ok(link)
+    }
+}
+
+/// Decode `%XX` escapes, and `+` as a space as browsers send it
+fn percent_decode(text: &str) -> Result<String, String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = bytes
+                    .get(i + 1..i + 3)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| format!("bad escape in {}", text))?;
+                decoded.push(hex);
+                i += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| format!("not UTF-8: {}", text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            DeepLink::parse("cocowork://thread/abc-123").unwrap(),
+            DeepLink::OpenThread { session_id: "abc-123".to_string() }
+        );
+        assert_eq!(
+            DeepLink::parse("COCOWORK://new?cwd=%2Fhome%2Fme%2Fapp&prompt=Fix+the%20build%21").unwrap(),
+            DeepLink::NewThread {
+                working_dir: PathBuf::from("/home/me/app"),
+                prompt: Some("Fix the build!".to_string()),
+                agent_id: None,
+            }
+        );
+        assert_eq!(
+            DeepLink::parse("cocowork://new/?agent=codex&cwd=/src&prompt=").unwrap(),
+            DeepLink::NewThread { working_dir: PathBuf::from("/src"), prompt: None, agent_id: Some("codex".to_string()) }
+        );
+        assert_eq!(
+            DeepLink::parse("cocowork://import?path=%2Ftmp%2Ffix%20build.json").unwrap(),
+            DeepLink::ImportArchive { path: PathBuf::from("/tmp/fix build.json") }
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_links() {
+        assert!(!DeepLink::is_link("list"));
+        assert!(!DeepLink::is_link("https://example.com"));
+        assert!(DeepLink::parse("cocowork://").is_err());
+        assert!(DeepLink::parse("cocowork://thread/").is_err());
+        assert!(DeepLink::parse("cocowork://new?prompt=hi").is_err());
+        assert!(DeepLink::parse("cocowork://new?cwd=%zz").is_err());
+        assert!(DeepLink::parse("cocowork://settings").is_err());
+    }
+}
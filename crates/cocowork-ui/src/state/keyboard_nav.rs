@@ -0,0 +1,112 @@
+//! Keyboard-only navigation
+//!
+//! Tab and Shift+Tab move focus between the window's regions. Within a region
+//! that shows a list, arrow keys move a highlight and Enter or Space acts on
+//! the highlighted item.
+
+/// A part of the window that takes keyboard focus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusRegion {
+    /// The box filtering the thread list
+    ThreadSearch,
+    Threads,
+    /// The message input
+    Input,
+    /// Sections of the context panel on the right
+    ContextPanel,
+}
+
+impl FocusRegion {
+    /// Regions in Tab order
+    pub const ALL: [FocusRegion; 4] = [
+        FocusRegion::ThreadSearch,
+        FocusRegion::Threads,
+        FocusRegion::Input,
+        FocusRegion::ContextPanel,
+    ];
+
+    /// The region Tab moves to, wrapping around
+    pub fn next(self) -> Self {
+        let idx = Self::ALL.iter().position(|r| *r == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    /// The region Shift+Tab moves to, wrapping around
+    pub fn previous(self) -> Self {
+        let idx = Self::ALL.iter().position(|r| *r == self).unwrap_or(0);
+        Self::ALL[(idx + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+}
+
+/// What a key does in a focused list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListKey {
+    Previous,
+    Next,
+    First,
+    Last,
+    /// Open or toggle the highlighted item
+    Activate,
+}
+
+impl ListKey {
+    /// The list action for a key name, if it has one
+    pub fn from_key(key: &str) -> Option<Self> {
+        Some(match key {
+            "up" => Self::Previous,
+            "down" => Self::Next,
+            "home" | "pageup" => Self::First,
+            "end" | "pagedown" => Self::Last,
+            "enter" | "space" => Self::Activate,
+            _ => return None,
+        })
+    }
+}
+
+/// The highlight after a movement key in a list of `len` items
+///
+/// With nothing highlighted yet, Down starts at the top and Up at the bottom.
+/// Movement stops at either end. `Activate` leaves the highlight as it is.
+pub fn move_highlight(current: Option<usize>, key: ListKey, len: usize) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    let last = len - 1;
+    Some(match (key, current.map(|idx| idx.min(last))) {
+        (ListKey::First, _) | (ListKey::Next, None) => 0,
+        (ListKey::Last, _) | (ListKey::Previous, None) => last,
+        (ListKey::Next, Some(idx)) => (idx + 1).min(last),
+        (ListKey::Previous, Some(idx)) => idx.saturating_sub(1),
+        (ListKey::Activate, idx) => return idx,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_region_order_wraps() {
+        assert_eq!(FocusRegion::ThreadSearch.next(), FocusRegion::Threads);
+        assert_eq!(FocusRegion::ContextPanel.next(), FocusRegion::ThreadSearch);
+        assert_eq!(FocusRegion::ThreadSearch.previous(), FocusRegion::ContextPanel);
+        for region in FocusRegion::ALL {
+            assert_eq!(region.next().previous(), region);
+        }
+    }
+
+    #[test]
+    fn test_move_highlight() {
+        assert_eq!(move_highlight(None, ListKey::Next, 3), Some(0));
+        assert_eq!(move_highlight(None, ListKey::Previous, 3), Some(2));
+        assert_eq!(move_highlight(Some(2), ListKey::Next, 3), Some(2));
+        assert_eq!(move_highlight(Some(0), ListKey::Previous, 3), Some(0));
+        assert_eq!(move_highlight(Some(1), ListKey::Last, 3), Some(2));
+        // A highlight past the end after the list shrank
+        assert_eq!(move_highlight(Some(7), ListKey::Previous, 3), Some(1));
+        assert_eq!(move_highlight(Some(1), ListKey::Activate, 3), Some(1));
+        assert_eq!(move_highlight(Some(1), ListKey::Next, 0), None);
+        assert_eq!(ListKey::from_key("space"), Some(ListKey::Activate));
+        assert_eq!(ListKey::from_key("a"), None);
+    }
+}
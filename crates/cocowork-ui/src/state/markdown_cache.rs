@@ -0,0 +1,226 @@
+//! Cached markdown views and their change tracking
+//!
+//! Parsed markdown views are kept per window, keyed by thread and message,
+//! so switching back to a thread doesn't lay everything out again. The
+//! [`MarkdownCache`] holds them within a [`MarkdownCacheBudget`], dropping the
+//! least recently shown first. Memory is estimated from the source text,
+//! which the parsed view's size follows.
+
+use std::collections::HashMap;
+
+/// How a cached markdown view should catch up with new text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkdownUpdate<'a> {
+    /// Text is the same; leave the view alone
+    Unchanged,
+    /// Text grew by this suffix, as while a message streams in
+    Append(&'a str),
+    /// Text changed in place; re-parse from scratch
+    Reset,
+}
+
+/// The text a cached markdown view was last given
+#[derive(Debug, Clone, Default)]
+pub struct MarkdownSource {
+    text: String,
+}
+
+impl MarkdownSource {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self { text: text.into() }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Record `text` as the view's new source and say how to apply it
+    pub fn update<'a>(&mut self, text: &'a str) -> MarkdownUpdate<'a> {
+        match text.strip_prefix(self.text.as_str()) {
+            Some("") => MarkdownUpdate::Unchanged,
+            Some(suffix) => {
+                self.text.push_str(suffix);
+                MarkdownUpdate::Append(suffix)
+            }
+            None => {
+                self.text = text.to_string();
+                MarkdownUpdate::Reset
+            }
+        }
+    }
+}
+
+/// How much the markdown cache may hold before evicting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarkdownCacheBudget {
+    pub max_entries: usize,
+    /// Source text bytes across all entries
+    pub max_bytes: usize,
+}
+
+impl MarkdownCacheBudget {
+    pub const SMALL: Self = Self { max_entries: 100, max_bytes: 8 * 1024 * 1024 };
+    pub const DEFAULT: Self = Self { max_entries: 500, max_bytes: 32 * 1024 * 1024 };
+    pub const LARGE: Self = Self { max_entries: 2000, max_bytes: 128 * 1024 * 1024 };
+}
+
+impl Default for MarkdownCacheBudget {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// What the markdown cache holds and how it has been used
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MarkdownCacheStats {
+    pub entries: usize,
+    pub bytes: usize,
+    pub hits: u64,
+    pub misses: u64,
+    /// Entries dropped to stay within the budget
+    pub evictions: u64,
+    /// Times everything was dropped, e.g. on a theme change
+    pub invalidations: u64,
+}
+
+struct CachedMarkdown<V> {
+    view: V,
+    source: MarkdownSource,
+    /// Order of last use, for evicting the oldest
+    used: u64,
+}
+
+/// Markdown views by key, evicted least recently used first
+pub struct MarkdownCache<V> {
+    entries: HashMap<String, CachedMarkdown<V>>,
+    budget: MarkdownCacheBudget,
+    uses: u64,
+    stats: MarkdownCacheStats,
+}
+
+impl<V> MarkdownCache<V> {
+    pub fn new(budget: MarkdownCacheBudget) -> Self {
+        Self {
+            entries: HashMap::new(),
+            budget,
+            uses: 0,
+            stats: MarkdownCacheStats::default(),
+        }
+    }
+
+    pub fn budget(&self) -> MarkdownCacheBudget {
+        self.budget
+    }
+
+    /// Change the budget, evicting whatever no longer fits
+    pub fn set_budget(&mut self, budget: MarkdownCacheBudget) {
+        self.budget = budget;
+        self.evict(None);
+    }
+
+    /// The view cached under `key` and the text it was given, marking it used
+    pub fn get_mut(&mut self, key: &str) -> Option<(&V, &mut MarkdownSource)> {
+        self.uses += 1;
+        match self.entries.get_mut(key) {
+            Some(entry) => {
+                entry.used = self.uses;
+                self.stats.hits += 1;
+                Some((&entry.view, &mut entry.source))
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Cache `view`, made from `source`, then evict down to the budget; the new
+    /// entry is kept even if it alone is over
+    pub fn insert(&mut self, key: String, view: V, source: MarkdownSource) {
+        self.uses += 1;
+        self.entries.insert(key.clone(), CachedMarkdown { view, source, used: self.uses });
+        self.evict(Some(&key));
+    }
+
+    /// Drop every entry, as when the views' style changes
+    pub fn invalidate(&mut self) {
+        self.entries.clear();
+        self.stats.invalidations += 1;
+    }
+
+    pub fn stats(&self) -> MarkdownCacheStats {
+        MarkdownCacheStats { entries: self.entries.len(), bytes: self.bytes(), ..self.stats }
+    }
+
+    fn bytes(&self) -> usize {
+        self.entries.values().map(|entry| entry.source.text().len()).sum()
+    }
+
+    fn evict(&mut self, keep: Option<&str>) {
+        let mut bytes = self.bytes();
+        while self.entries.len() > self.budget.max_entries || bytes > self.budget.max_bytes {
+            let oldest = self
+                .entries
+                .iter()
+                .filter(|(key, _)| Some(key.as_str()) != keep)
+                .min_by_key(|(_, entry)| entry.used)
+                .map(|(key, _)| key.clone());
+            let Some(entry) = oldest.and_then(|key| self.entries.remove(&key)) else {
+                break;
+            };
+            bytes -= entry.source.text().len();
+            self.stats.evictions += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let mut cache = MarkdownCache::new(MarkdownCacheBudget { max_entries: 2, max_bytes: 10 });
+        cache.insert("a".to_string(), 1, MarkdownSource::new("aaa"));
+        cache.insert("b".to_string(), 2, MarkdownSource::new("bbb"));
+        assert!(cache.get_mut("a").is_some());
+        cache.insert("c".to_string(), 3, MarkdownSource::new("ccc"));
+        assert!(cache.get_mut("b").is_none());
+        assert_eq!(cache.get_mut("a").map(|(view, _)| *view), Some(1));
+
+        // Over the memory budget goes the oldest, but never the new entry
+        cache.insert("d".to_string(), 4, MarkdownSource::new("dddddddd"));
+        assert!(cache.get_mut("c").is_none());
+        assert!(cache.get_mut("a").is_none());
+        assert_eq!(
+            cache.stats(),
+            MarkdownCacheStats { entries: 1, bytes: 8, hits: 2, misses: 3, evictions: 3, invalidations: 0 }
+        );
+
+        cache.set_budget(MarkdownCacheBudget { max_entries: 0, max_bytes: 0 });
+        assert_eq!(cache.stats().entries, 0);
+        cache.insert("e".to_string(), 5, MarkdownSource::new("e"));
+        cache.invalidate();
+        assert_eq!((cache.stats().entries, cache.stats().invalidations), (0, 1));
+    }
+
+    #[test]
+    fn test_streaming_text_appends() {
+        let mut source = MarkdownSource::new("Hello");
+
+        assert_eq!(source.update("Hello"), MarkdownUpdate::Unchanged);
+        assert_eq!(source.update("Hello, **wor"), MarkdownUpdate::Append(", **wor"));
+        assert_eq!(source.update("Hello, **world**"), MarkdownUpdate::Append("ld**"));
+        assert_eq!(source.text(), "Hello, **world**");
+    }
+
+    #[test]
+    fn test_rewritten_text_resets() {
+        let mut source = MarkdownSource::new("Hello, world");
+
+        assert_eq!(source.update("Hello"), MarkdownUpdate::Reset);
+        assert_eq!(source.text(), "Hello");
+        assert_eq!(source.update("Goodbye"), MarkdownUpdate::Reset);
+        assert_eq!(source.update("Goodbye"), MarkdownUpdate::Unchanged);
+    }
+}
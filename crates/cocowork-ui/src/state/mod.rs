@@ -2,8 +2,30 @@
 //!
 //! Centralized state for the CocoWork UI.
 
+mod action_groups;
 mod app_state;
+mod debug_stats;
+mod deep_link;
+mod keyboard_nav;
+mod markdown_cache;
+mod outline;
+mod prompt_history;
+mod stream_rate;
+mod syntax;
+mod time_format;
 mod topic_tree;
+mod window_layout;
 
+pub use action_groups::*;
 pub use app_state::*;
+pub use debug_stats::*;
+pub use deep_link::*;
+pub use keyboard_nav::*;
+pub use markdown_cache::*;
+pub use outline::*;
+pub use prompt_history::*;
+pub use stream_rate::*;
+pub use syntax::*;
+pub use time_format::*;
 pub use topic_tree::*;
+pub use window_layout::*;
@@ -0,0 +1,184 @@
+//! Outline of a thread, for jumping between its turns
+//!
+//! Rows of the timeline come and go as action groups fold and thoughts are
+//! hidden, so places in it aren't kept by row or message index. A
+//! [`TimelineAnchor`] names a message by its role and timestamp, which are
+//! saved with it, or a tool call by its id, and stays valid across reloads.
+
+use cocowork_core::{ContentBlock, MessageBlock, ToolCallState};
+
+/// Longest preview of a turn's prompt in the outline
+const PREVIEW_CHARS: usize = 80;
+
+/// A message or tool call to scroll to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimelineAnchor {
+    Message(String),
+    ToolCall(String),
+}
+
+impl TimelineAnchor {
+    pub fn of_message(msg: &MessageBlock) -> Self {
+        let role = match msg {
+            MessageBlock::User { .. } => "user",
+            MessageBlock::Agent { .. } => "agent",
+            MessageBlock::Thought { .. } => "thought",
+            MessageBlock::System { .. } => "system",
+            MessageBlock::Summary { .. } => "summary",
+        };
+        let at = msg.timestamp();
+        Self::Message(format!("{}-{}.{:09}", role, at.timestamp(), at.timestamp_subsec_nanos()))
+    }
+
+    pub fn of_tool_call(call: &ToolCallState) -> Self {
+        Self::ToolCall(call.id.clone())
+    }
+
+    pub fn matches_message(&self, msg: &MessageBlock) -> bool {
+        matches!(self, Self::Message(_)) && *self == Self::of_message(msg)
+    }
+
+    pub fn matches_tool_call(&self, call: &ToolCallState) -> bool {
+        matches!(self, Self::ToolCall(id) if *id == call.id)
+    }
+}
+
+/// A user turn as listed in the outline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutlineEntry {
+    pub anchor: TimelineAnchor,
+    /// Turn number, from 1
+    pub turn: usize,
+    /// First line of the prompt, shortened
+    pub preview: String,
+    pub at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Every prompt the user sent in a thread, in order
+pub fn thread_outline(messages: &[MessageBlock]) -> Vec<OutlineEntry> {
+    messages
+        .iter()
+        .filter_map(|msg| match msg {
+            MessageBlock::User { content, .. } => Some((msg, content)),
+            _ => None,
+        })
+        .enumerate()
+        .map(|(i, (msg, content))| OutlineEntry {
+            anchor: TimelineAnchor::of_message(msg),
+            turn: i + 1,
+            preview: preview(content),
+            at: msg.timestamp(),
+        })
+        .collect()
+}
+
+/// A message pinned to the top of its thread
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinnedEntry {
+    pub anchor: TimelineAnchor,
+    /// Index of the message in the thread
+    pub message_idx: usize,
+    /// Whether the user sent it, rather than the agent
+    pub from_user: bool,
+    /// First line of the message, shortened
+    pub preview: String,
+}
+
+/// The messages at `pinned` that are still in the thread, in order
+pub fn pinned_entries<'a>(messages: &[MessageBlock], pinned: impl IntoIterator<Item = &'a usize>) -> Vec<PinnedEntry> {
+    pinned
+        .into_iter()
+        .filter_map(|&idx| {
+            let msg = messages.get(idx)?;
+            let (from_user, preview) = match msg {
+                MessageBlock::User { content, .. } => (true, preview(content)),
+                MessageBlock::Agent { content, .. } | MessageBlock::Thought { content, .. } => {
+                    (false, preview(content))
+                }
+                MessageBlock::System { content, .. } | MessageBlock::Summary { content, .. } => {
+                    (false, preview(&[ContentBlock::Text { text: content.clone() }]))
+                }
+            };
+            Some(PinnedEntry { anchor: TimelineAnchor::of_message(msg), message_idx: idx, from_user, preview })
+        })
+        .collect()
+}
+
+/// The tool call started last, if any
+pub fn latest_tool_call<'a>(calls: impl IntoIterator<Item = &'a ToolCallState>) -> Option<TimelineAnchor> {
+    calls
+        .into_iter()
+        .max_by(|a, b| a.started_at.cmp(&b.started_at).then_with(|| a.id.cmp(&b.id)))
+        .map(TimelineAnchor::of_tool_call)
+}
+
+fn preview(content: &[ContentBlock]) -> String {
+    let line = content
+        .iter()
+        .filter_map(|block| match block {
+            ContentBlock::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .flat_map(str::lines)
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default();
+    if line.chars().count() > PREVIEW_CHARS {
+        let cut: String = line.chars().take(PREVIEW_CHARS - 1).collect();
+        format!("{}…", cut.trim_end())
+    } else {
+        line.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompt(text: &str) -> MessageBlock {
+        MessageBlock::user(vec![ContentBlock::Text { text: text.to_string() }])
+    }
+
+    #[test]
+    fn test_thread_outline() {
+        let messages = vec![
+            prompt("\nFix the build\nIt fails on CI"),
+            MessageBlock::agent(vec![ContentBlock::Text { text: "Done".to_string() }]),
+            prompt(&"x".repeat(100)),
+        ];
+        let outline = thread_outline(&messages);
+        assert_eq!(outline.len(), 2);
+        assert_eq!((outline[0].turn, outline[0].preview.as_str()), (1, "Fix the build"));
+        assert_eq!(outline[1].preview.chars().count(), PREVIEW_CHARS);
+        assert!(outline[1].preview.ends_with('…'));
+
+        // Anchors survive the thread being saved and loaded again
+        let reloaded: Vec<MessageBlock> =
+            serde_json::from_str(&serde_json::to_string(&messages).unwrap()).unwrap();
+        assert!(outline[0].anchor.matches_message(&reloaded[0]));
+        assert!(!outline[0].anchor.matches_message(&reloaded[1]));
+    }
+
+    #[test]
+    fn test_pinned_entries() {
+        let messages = vec![
+            prompt("Use TOML for config"),
+            MessageBlock::agent(vec![ContentBlock::Text { text: "\nAgreed, TOML it is\nMore".to_string() }]),
+        ];
+        let pinned = pinned_entries(&messages, &[1, 4]);
+        assert_eq!(pinned.len(), 1);
+        assert_eq!((pinned[0].message_idx, pinned[0].from_user), (1, false));
+        assert_eq!(pinned[0].preview, "Agreed, TOML it is");
+        assert!(pinned[0].anchor.matches_message(&messages[1]));
+    }
+
+    #[test]
+    fn test_latest_tool_call() {
+        let first = ToolCallState::new("a".to_string(), None, None);
+        let mut second = ToolCallState::new("b".to_string(), None, None);
+        second.started_at = first.started_at + chrono::Duration::seconds(1);
+        let latest = latest_tool_call([&second, &first]).unwrap();
+        assert!(latest.matches_tool_call(&second));
+        assert!(latest_tool_call([]).is_none());
+    }
+}
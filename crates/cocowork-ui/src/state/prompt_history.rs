@@ -0,0 +1,144 @@
+//! Recalling earlier prompts in the message input
+//!
+//! Up and Down in an empty input step through the prompts sent in the current
+//! thread, and Ctrl+R searches the prompts of every thread.
+
+/// Position while stepping through a thread's sent prompts
+#[derive(Debug, Clone, Default)]
+pub struct PromptHistory {
+    /// Index into the prompts, oldest first; `None` when not browsing
+    position: Option<usize>,
+}
+
+impl PromptHistory {
+    /// Whether the input shows a recalled prompt
+    pub fn is_browsing(&self) -> bool {
+        self.position.is_some()
+    }
+
+    /// Stop browsing, e.g. after the prompt was sent or edited
+    pub fn reset(&mut self) {
+        self.position = None;
+    }
+
+    /// Step back to the prompt before the one shown, starting from the newest
+    pub fn older<'a>(&mut self, prompts: &'a [String]) -> Option<&'a str> {
+        let position = match self.position {
+            None => prompts.len().checked_sub(1)?,
+            Some(position) => position.saturating_sub(1).min(prompts.len().checked_sub(1)?),
+        };
+        self.position = Some(position);
+        Some(&prompts[position])
+    }
+
+    /// Step forward to the prompt after the one shown
+    ///
+    /// Returns `None` once past the newest prompt, when the input goes back to empty.
+    pub fn newer<'a>(&mut self, prompts: &'a [String]) -> Option<&'a str> {
+        let next = self.position? + 1;
+        if next >= prompts.len() {
+            self.position = None;
+            return None;
+        }
+        self.position = Some(next);
+        Some(&prompts[next])
+    }
+}
+
+/// How well `query` fuzzy-matches `candidate`, higher is better
+///
+/// Every character of the query has to appear in order, ignoring case.
+/// Consecutive characters and matches at word starts score higher, and
+/// shorter candidates win ties.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let query: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).flat_map(char::to_lowercase).collect();
+    let Some(&first) = query.first() else {
+        return Some(0);
+    };
+    let chars: Vec<char> = candidate.chars().collect();
+    let lower: Vec<char> = chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+
+    // Match greedily from each place the query could start and keep the best
+    let best = (0..lower.len())
+        .filter(|&start| lower[start] == first)
+        .filter_map(|start| {
+            let mut score = 0;
+            let mut matched = 0;
+            let mut last = None;
+            for i in start..lower.len() {
+                if matched == query.len() {
+                    break;
+                }
+                if lower[i] != query[matched] {
+                    continue;
+                }
+                score += 1;
+                if last == Some(i.wrapping_sub(1)) {
+                    score += 4;
+                }
+                if i == 0 || !chars[i - 1].is_alphanumeric() {
+                    score += 3;
+                }
+                matched += 1;
+                last = Some(i);
+            }
+            (matched == query.len()).then_some(score)
+        })
+        .max()?;
+
+    Some(best * 100 - chars.len().min(99) as i64)
+}
+
+/// Prompts matching `query`, best first, at most `limit`
+///
+/// `prompts` is expected newest first; equally good matches keep that order.
+pub fn search_prompts<'a>(query: &str, prompts: &'a [String], limit: usize) -> Vec<&'a str> {
+    let mut matches: Vec<(i64, &str)> = prompts
+        .iter()
+        .filter_map(|prompt| Some((fuzzy_score(query, prompt)?, prompt.as_str())))
+        .collect();
+    matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    matches.into_iter().take(limit).map(|(_, prompt)| prompt).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stepping_through_prompts() {
+        let prompts = vec!["one".to_string(), "two".to_string(), "three".to_string()];
+        let mut history = PromptHistory::default();
+
+        assert_eq!(history.newer(&prompts), None);
+        assert_eq!(history.older(&prompts), Some("three"));
+        assert_eq!(history.older(&prompts), Some("two"));
+        assert_eq!(history.older(&prompts), Some("one"));
+        assert_eq!(history.older(&prompts), Some("one"));
+        assert_eq!(history.newer(&prompts), Some("two"));
+        assert_eq!(history.newer(&prompts), Some("three"));
+        assert_eq!(history.newer(&prompts), None);
+        assert!(!history.is_browsing());
+
+        assert_eq!(history.older(&[]), None);
+    }
+
+    #[test]
+    fn test_search_prompts() {
+        let prompts = vec![
+            "Run the tests again".to_string(),
+            "Refactor the test runner".to_string(),
+            "Explain this error".to_string(),
+        ];
+        // Both contain "test"; the shorter one wins the tie
+        assert_eq!(
+            search_prompts("test", &prompts, 5),
+            vec!["Run the tests again", "Refactor the test runner"]
+        );
+        assert_eq!(search_prompts("ttr", &prompts, 5), vec!["Refactor the test runner"]);
+        // A whole word beats letters scattered across one
+        assert_eq!(search_prompts("err", &prompts, 5)[0], "Explain this error");
+        assert!(search_prompts("xyz", &prompts, 5).is_empty());
+        assert_eq!(search_prompts("", &prompts, 2).len(), 2);
+    }
+}
@@ -0,0 +1,91 @@
+//! Progress of a reply while it streams in
+//!
+//! Tokens are estimated from the size of the text chunks, at about four
+//! bytes a token, so the rate is only a rough guide.
+
+use std::time::{Duration, Instant};
+
+/// Frames of the spinner shown while a turn runs
+const SPINNER_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// How long each spinner frame shows
+const SPINNER_FRAME_TIME: Duration = Duration::from_millis(100);
+
+/// Shortest stretch of streaming a rate is worked out over
+const MIN_RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Time and text streamed since a prompt was sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamRate {
+    started: Instant,
+    first_chunk: Option<Instant>,
+    bytes: usize,
+}
+
+impl StreamRate {
+    pub fn new(started: Instant) -> Self {
+        Self { started, first_chunk: None, bytes: 0 }
+    }
+
+    /// Count a chunk of `bytes` of text that arrived at `now`
+    pub fn record_chunk(&mut self, bytes: usize, now: Instant) {
+        self.first_chunk.get_or_insert(now);
+        self.bytes += bytes;
+    }
+
+    pub fn elapsed(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.started)
+    }
+
+    /// How long the reply took to start arriving, once it has
+    pub fn time_to_first_chunk(&self) -> Option<Duration> {
+        Some(self.first_chunk?.saturating_duration_since(self.started))
+    }
+
+    /// Approximate tokens streamed so far
+    pub fn tokens(&self) -> usize {
+        self.bytes.div_ceil(4)
+    }
+
+    /// Approximate tokens a second since the reply started arriving, once
+    /// there's been long enough to tell
+    pub fn tokens_per_sec(&self, now: Instant) -> Option<f64> {
+        let streaming = now.saturating_duration_since(self.first_chunk?);
+        (streaming >= MIN_RATE_WINDOW).then(|| self.tokens() as f64 / streaming.as_secs_f64())
+    }
+}
+
+/// The spinner frame to show `elapsed` into a turn
+pub fn spinner_frame(elapsed: Duration) -> char {
+    let frame = elapsed.as_millis() / SPINNER_FRAME_TIME.as_millis();
+    SPINNER_FRAMES[(frame % SPINNER_FRAMES.len() as u128) as usize]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_rate() {
+        let start = Instant::now();
+        let mut rate = StreamRate::new(start);
+        assert_eq!(rate.tokens_per_sec(start + Duration::from_secs(5)), None);
+        assert_eq!(rate.time_to_first_chunk(), None);
+
+        // Waiting for the first chunk doesn't count against the rate
+        rate.record_chunk(400, start + Duration::from_secs(3));
+        assert_eq!(rate.tokens_per_sec(start + Duration::from_millis(3500)), None);
+        rate.record_chunk(400, start + Duration::from_secs(4));
+        assert_eq!(rate.tokens(), 200);
+        assert_eq!(rate.tokens_per_sec(start + Duration::from_secs(5)), Some(100.0));
+        assert_eq!(rate.elapsed(start + Duration::from_secs(5)), Duration::from_secs(5));
+        assert_eq!(rate.time_to_first_chunk(), Some(Duration::from_secs(3)));
+    }
+
+    #[test]
+    fn test_spinner_frame() {
+        assert_eq!(spinner_frame(Duration::ZERO), '⠋');
+        assert_eq!(spinner_frame(Duration::from_millis(250)), '⠹');
+        assert_eq!(spinner_frame(Duration::from_secs(1)), '⠋');
+    }
+}
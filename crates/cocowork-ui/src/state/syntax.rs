@@ -0,0 +1,281 @@
+//! Lightweight syntax highlighting for file previews
+//!
+//! A line scanner that picks out keywords, strings, comments and numbers for
+//! common languages. It doesn't parse, so odd constructs can fool it, but it's
+//! cheap enough to run whenever a file is opened.
+
+use std::path::Path;
+
+/// What a highlighted span of text is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Plain,
+    Keyword,
+    String,
+    Comment,
+    Number,
+}
+
+/// A run of text with one highlight
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+}
+
+struct Language {
+    keywords: &'static [&'static str],
+    line_comment: &'static str,
+    block_comment: Option<(&'static str, &'static str)>,
+    /// Characters that open and close a string
+    quotes: &'static [char],
+    /// Whether `'a` is a lifetime rather than the start of a char literal
+    lifetimes: bool,
+}
+
+const RUST: Language = Language {
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "false", "fn",
+        "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self",
+        "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while",
+    ],
+    line_comment: "//",
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"', '\''],
+    lifetimes: true,
+};
+
+const PYTHON: Language = Language {
+    keywords: &[
+        "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif", "else", "except",
+        "False", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "None", "nonlocal", "not",
+        "or", "pass", "raise", "return", "True", "try", "while", "with", "yield",
+    ],
+    line_comment: "#",
+    block_comment: None,
+    quotes: &['"', '\''],
+    lifetimes: false,
+};
+
+const JAVASCRIPT: Language = Language {
+    keywords: &[
+        "async", "await", "break", "case", "catch", "class", "const", "continue", "default", "delete", "do", "else",
+        "enum", "export", "extends", "false", "finally", "for", "from", "function", "if", "implements", "import", "in",
+        "instanceof", "interface", "let", "new", "null", "return", "static", "super", "switch", "this", "throw", "true",
+        "try", "type", "typeof", "undefined", "var", "void", "while", "yield",
+    ],
+    line_comment: "//",
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"', '\'', '`'],
+    lifetimes: false,
+};
+
+const GO: Language = Language {
+    keywords: &[
+        "break", "case", "chan", "const", "continue", "default", "defer", "else", "false", "for", "func", "go", "goto",
+        "if", "import", "interface", "map", "nil", "package", "range", "return", "select", "struct", "switch", "true",
+        "type", "var",
+    ],
+    line_comment: "//",
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"', '\'', '`'],
+    lifetimes: false,
+};
+
+/// C, C++, Java, C#, Kotlin and Swift share enough syntax for a keyword scan
+const C_LIKE: Language = Language {
+    keywords: &[
+        "break", "case", "catch", "class", "const", "continue", "default", "do", "else", "enum", "extends", "false",
+        "final", "for", "fun", "func", "if", "import", "include", "let", "namespace", "new", "null", "nullptr",
+        "override", "package", "private", "protected", "public", "return", "static", "struct", "switch", "this",
+        "throw", "true", "try", "typedef", "val", "var", "void", "while",
+    ],
+    line_comment: "//",
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"', '\''],
+    lifetimes: false,
+};
+
+/// Shell scripts and `#`-commented config files
+const HASH_COMMENTED: Language = Language {
+    keywords: &[
+        "case", "do", "done", "elif", "else", "esac", "export", "false", "fi", "for", "function", "if", "in", "local",
+        "return", "then", "true", "while",
+    ],
+    line_comment: "#",
+    block_comment: None,
+    quotes: &['"', '\''],
+    lifetimes: false,
+};
+
+fn language_for(path: &Path) -> Option<&'static Language> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "rs" => Some(&RUST),
+        "py" | "pyi" => Some(&PYTHON),
+        "js" | "jsx" | "ts" | "tsx" | "mjs" | "cjs" => Some(&JAVASCRIPT),
+        "go" => Some(&GO),
+        "c" | "h" | "cc" | "cpp" | "hpp" | "java" | "cs" | "kt" | "swift" => Some(&C_LIKE),
+        "sh" | "bash" | "zsh" | "toml" | "yaml" | "yml" => Some(&HASH_COMMENTED),
+        _ => None,
+    }
+}
+
+/// Split `text`, the contents of the file at `path`, into highlighted lines
+///
+/// Files in languages without rules come back as plain text, one token per line.
+pub fn highlight_lines(path: &Path, text: &str) -> Vec<Vec<Token>> {
+    let Some(language) = language_for(path) else {
+        return text.lines().map(|line| vec![token(TokenKind::Plain, line)]).collect();
+    };
+
+    let mut in_block_comment = false;
+    text.lines()
+        .map(|line| highlight_line(language, line, &mut in_block_comment))
+        .collect()
+}
+
+fn token(kind: TokenKind, text: &str) -> Token {
+    Token {
+        kind,
+        text: text.to_string(),
+    }
+}
+
+fn highlight_line(language: &Language, line: &str, in_block_comment: &mut bool) -> Vec<Token> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut push = |kind: TokenKind, text: &str| {
+        if text.is_empty() {
+            return;
+        }
+        match tokens.last_mut() {
+            Some(last) if last.kind == kind => last.text.push_str(text),
+            _ => tokens.push(token(kind, text)),
+        }
+    };
+
+    let mut rest = line;
+    while !rest.is_empty() {
+        if *in_block_comment {
+            let (_, end) = language.block_comment.expect("only set for languages with block comments");
+            match rest.find(end) {
+                Some(i) => {
+                    push(TokenKind::Comment, &rest[..i + end.len()]);
+                    rest = &rest[i + end.len()..];
+                    *in_block_comment = false;
+                }
+                None => {
+                    push(TokenKind::Comment, rest);
+                    rest = "";
+                }
+            }
+            continue;
+        }
+
+        if rest.starts_with(language.line_comment) {
+            push(TokenKind::Comment, rest);
+            break;
+        }
+        if let Some((start, _)) = language.block_comment.filter(|(start, _)| rest.starts_with(start)) {
+            push(TokenKind::Comment, start);
+            rest = &rest[start.len()..];
+            *in_block_comment = true;
+            continue;
+        }
+
+        let c = rest.chars().next().expect("rest is not empty");
+        let lifetime = language.lifetimes && c == '\'' && is_lifetime(rest);
+        let len = if language.quotes.contains(&c) && !lifetime {
+            // An unclosed quote is just punctuation
+            match closing_quote(rest, c) {
+                Some(end) => {
+                    push(TokenKind::String, &rest[..end]);
+                    end
+                }
+                None => {
+                    push(TokenKind::Plain, &rest[..c.len_utf8()]);
+                    c.len_utf8()
+                }
+            }
+        } else if c.is_alphanumeric() || c == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            let word = &rest[..end];
+            let kind = if c.is_ascii_digit() {
+                TokenKind::Number
+            } else if language.keywords.contains(&word) {
+                TokenKind::Keyword
+            } else {
+                TokenKind::Plain
+            };
+            push(kind, word);
+            end
+        } else {
+            push(TokenKind::Plain, &rest[..c.len_utf8()]);
+            c.len_utf8()
+        };
+        rest = &rest[len..];
+    }
+    tokens
+}
+
+/// Whether `text` starts with a lifetime like `'a` rather than a char literal like `'a'`
+fn is_lifetime(text: &str) -> bool {
+    let mut chars = text.chars().skip(1);
+    chars.next().is_some_and(|c| c.is_alphanumeric() || c == '_') && chars.next() != Some('\'')
+}
+
+/// Byte length of the string literal opened by `quote` at the start of `text`
+fn closing_quote(text: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in text.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == quote {
+            return Some(i + c.len_utf8());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(line: &[Token]) -> Vec<(TokenKind, &str)> {
+        line.iter().map(|t| (t.kind, t.text.as_str())).collect()
+    }
+
+    #[test]
+    fn test_highlight_rust_line() {
+        let lines = highlight_lines(Path::new("main.rs"), "fn run<'a>(x: &'a str) -> u8 { \"hi\\\"\"; 42 } // done");
+        assert_eq!(
+            kinds(&lines[0]),
+            vec![
+                (TokenKind::Keyword, "fn"),
+                (TokenKind::Plain, " run<'a>(x: &'a str) -> u8 { "),
+                (TokenKind::String, "\"hi\\\"\""),
+                (TokenKind::Plain, "; "),
+                (TokenKind::Number, "42"),
+                (TokenKind::Plain, " } "),
+                (TokenKind::Comment, "// done"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_block_comments_span_lines() {
+        let lines = highlight_lines(Path::new("app.ts"), "let a = 1; /* start\nstill comment\nend */ return");
+        assert_eq!(lines[1], vec![token(TokenKind::Comment, "still comment")]);
+        assert_eq!(
+            kinds(&lines[2]),
+            vec![(TokenKind::Comment, "end */"), (TokenKind::Plain, " "), (TokenKind::Keyword, "return")]
+        );
+
+        let plain = highlight_lines(Path::new("notes.txt"), "fn // not code");
+        assert_eq!(plain, vec![vec![token(TokenKind::Plain, "fn // not code")]]);
+    }
+}
@@ -0,0 +1,97 @@
+//! Human-readable times for the thread timeline and sidebar
+
+use crate::i18n::{t, t_args};
+use chrono::{DateTime, Local, NaiveDate, Utc};
+
+/// Sidebar section a thread is listed under, by when it was last active
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DateGroup {
+    Today,
+    Yesterday,
+    /// The five days before yesterday
+    ThisWeek,
+    Older,
+}
+
+impl DateGroup {
+    /// Sections in the order they're listed, newest first
+    pub const ALL: [DateGroup; 4] = [DateGroup::Today, DateGroup::Yesterday, DateGroup::ThisWeek, DateGroup::Older];
+
+    /// The section for activity at `time`, going by the local calendar day
+    pub fn of(time: DateTime<Utc>, now: DateTime<Utc>) -> Self {
+        Self::for_day(time.with_timezone(&Local).date_naive(), now.with_timezone(&Local).date_naive())
+    }
+
+    pub fn for_day(day: NaiveDate, today: NaiveDate) -> Self {
+        match (today - day).num_days() {
+            ..=0 => DateGroup::Today,
+            1 => DateGroup::Yesterday,
+            2..=6 => DateGroup::ThisWeek,
+            _ => DateGroup::Older,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DateGroup::Today => t("sidebar.group_today"),
+            DateGroup::Yesterday => t("sidebar.group_yesterday"),
+            DateGroup::ThisWeek => t("sidebar.group_this_week"),
+            DateGroup::Older => t("sidebar.group_older"),
+        }
+    }
+}
+
+/// How long ago `time` was, e.g. "just now", "5m ago", "3h ago" or "Mar 4, 14:05"
+///
+/// Anything older than a day shows the local date and time instead, so the
+/// label doesn't need refreshing once it's out of the recent range.
+pub fn format_relative(time: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let elapsed = now - time;
+    if elapsed.num_seconds() < 60 {
+        t("time.just_now").to_string()
+    } else if elapsed.num_minutes() < 60 {
+        t_args("time.minutes_ago", &[("count", &elapsed.num_minutes())])
+    } else if elapsed.num_hours() < 24 {
+        t_args("time.hours_ago", &[("count", &elapsed.num_hours())])
+    } else {
+        time.with_timezone(&Local).format("%b %-d, %H:%M").to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_format_relative() {
+        let now = Utc::now();
+        assert_eq!(format_relative(now - Duration::seconds(20), now), "just now");
+        // Clock skew shouldn't produce negative ages
+        assert_eq!(format_relative(now + Duration::seconds(5), now), "just now");
+        assert_eq!(format_relative(now - Duration::minutes(5), now), "5m ago");
+        assert_eq!(format_relative(now - Duration::minutes(150), now), "2h ago");
+
+        let old = now - Duration::days(3);
+        assert_eq!(
+            format_relative(old, now),
+            old.with_timezone(&Local).format("%b %-d, %H:%M").to_string()
+        );
+    }
+    #[test]
+    fn test_date_group() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let day = |offset: i64| today - Duration::days(offset);
+        assert_eq!(DateGroup::for_day(today, today), DateGroup::Today);
+        // Activity stamped by a clock slightly ahead still counts as today
+        assert_eq!(DateGroup::for_day(day(-1), today), DateGroup::Today);
+        assert_eq!(DateGroup::for_day(day(1), today), DateGroup::Yesterday);
+        assert_eq!(DateGroup::for_day(day(2), today), DateGroup::ThisWeek);
+        assert_eq!(DateGroup::for_day(day(6), today), DateGroup::ThisWeek);
+        assert_eq!(DateGroup::for_day(day(7), today), DateGroup::Older);
+
+        let now = Utc::now();
+        assert_eq!(DateGroup::of(now, now), DateGroup::Today);
+        assert_eq!(DateGroup::of(now - Duration::days(30), now), DateGroup::Older);
+    }
+}
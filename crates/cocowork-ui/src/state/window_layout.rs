@@ -0,0 +1,178 @@
+//! Window size and panel layout kept between launches
+//!
+//! The layout is stored as one JSON setting. Fields missing from an older
+//! value take their defaults, and anything out of range is pulled back in, so
+//! a stored layout never opens a window that can't be used.
+
+use crate::theme::layout;
+use serde::{Deserialize, Serialize};
+
+/// Smallest window size restored from a stored layout
+pub const MIN_WINDOW_WIDTH: f32 = 640.0;
+pub const MIN_WINDOW_HEIGHT: f32 = 400.0;
+
+/// How the window and its panels were laid out when last changed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowLayout {
+    /// `None` until the window has been moved or resized
+    pub window: Option<WindowGeometry>,
+    pub sidebar_width: f32,
+    pub context_panel_width: f32,
+    /// Context panel sections left open
+    pub expanded_sections: Vec<String>,
+    /// Whether the sidebar is collapsed to a rail
+    pub sidebar_collapsed: bool,
+    /// Whether the context panel is collapsed to a rail
+    pub context_panel_collapsed: bool,
+}
+
+impl Default for WindowLayout {
+    fn default() -> Self {
+        Self {
+            window: None,
+            sidebar_width: layout::SIDEBAR_WIDTH,
+            context_panel_width: layout::CONTEXT_PANEL_WIDTH,
+            expanded_sections: vec!["Progress".to_string()],
+            sidebar_collapsed: false,
+            context_panel_collapsed: false,
+        }
+    }
+}
+
+impl WindowLayout {
+    /// Read a stored layout, using the default for anything unreadable
+    pub fn from_json(json: &str) -> Self {
+        let mut stored: Self = serde_json::from_str(json).unwrap_or_default();
+        let defaults = Self::default();
+        stored.sidebar_width = clamp_or(
+            stored.sidebar_width,
+            layout::SIDEBAR_MIN_WIDTH,
+            layout::SIDEBAR_MAX_WIDTH,
+            defaults.sidebar_width,
+        );
+        stored.context_panel_width = clamp_or(
+            stored.context_panel_width,
+            layout::CONTEXT_PANEL_MIN_WIDTH,
+            layout::CONTEXT_PANEL_MAX_WIDTH,
+            defaults.context_panel_width,
+        );
+        stored.window = stored.window.filter(|window| {
+            [window.x, window.y, window.width, window.height].iter().all(|value| value.is_finite())
+        });
+        stored
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// The window's position and size in screen points
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// Whether the window was maximized; the size above is the one it restores to
+    #[serde(default)]
+    pub maximized: bool,
+    #[serde(default)]
+    pub fullscreen: bool,
+}
+
+/// The bounds of a connected display
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DisplayArea {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl WindowGeometry {
+    /// The geometry moved and shrunk to fit the display it mostly sits on
+    ///
+    /// Returns `None` when the window wouldn't show on any display, as after
+    /// the monitor it was on is unplugged.
+    pub fn fit(self, displays: &[DisplayArea]) -> Option<Self> {
+        let overlap = |display: &DisplayArea| {
+            let width = (self.x + self.width).min(display.x + display.width) - self.x.max(display.x);
+            let height = (self.y + self.height).min(display.y + display.height) - self.y.max(display.y);
+            width.max(0.0) * height.max(0.0)
+        };
+        let display = displays
+            .iter()
+            .filter(|display| overlap(display) > 0.0)
+            .max_by(|a, b| overlap(a).total_cmp(&overlap(b)))?;
+
+        let width = self.width.max(MIN_WINDOW_WIDTH).min(display.width);
+        let height = self.height.max(MIN_WINDOW_HEIGHT).min(display.height);
+        Some(Self {
+            x: self.x.clamp(display.x, display.x + display.width - width),
+            y: self.y.clamp(display.y, display.y + display.height - height),
+            width,
+            height,
+            ..self
+        })
+    }
+}
+
+fn clamp_or(value: f32, min: f32, max: f32, default: f32) -> f32 {
+    if value.is_finite() {
+        value.clamp(min, max)
+    } else {
+        default
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(x: f32, y: f32, width: f32, height: f32) -> WindowGeometry {
+        WindowGeometry { x, y, width, height, maximized: false, fullscreen: false }
+    }
+
+    #[test]
+    fn test_from_json() {
+        assert_eq!(WindowLayout::from_json("not json"), WindowLayout::default());
+
+        let layout = WindowLayout::from_json(r#"{"sidebar_width": 9000, "expanded_sections": []}"#);
+        assert_eq!(layout.sidebar_width, layout::SIDEBAR_MAX_WIDTH);
+        assert_eq!(layout.context_panel_width, layout::CONTEXT_PANEL_WIDTH);
+        assert!(layout.expanded_sections.is_empty());
+        assert!(!layout.sidebar_collapsed);
+
+        let layout = WindowLayout {
+            window: Some(window(10.0, 20.0, 900.0, 700.0)),
+            context_panel_collapsed: true,
+            ..WindowLayout::default()
+        };
+        assert_eq!(WindowLayout::from_json(&layout.to_json()), layout);
+    }
+
+    #[test]
+    fn test_fit_to_displays() {
+        let displays = [
+            DisplayArea { x: 0.0, y: 0.0, width: 1440.0, height: 900.0 },
+            DisplayArea { x: 1440.0, y: 0.0, width: 1920.0, height: 1080.0 },
+        ];
+        let on_screen = window(100.0, 100.0, 1200.0, 700.0);
+        assert_eq!(on_screen.fit(&displays), Some(on_screen));
+
+        // Mostly on the second display, hanging off its bottom edge
+        assert_eq!(
+            window(1300.0, 600.0, 1000.0, 800.0).fit(&displays),
+            Some(window(1440.0, 280.0, 1000.0, 800.0))
+        );
+        // Larger than the display it's on
+        assert_eq!(
+            window(0.0, 0.0, 2000.0, 1000.0).fit(&displays[..1]),
+            Some(window(0.0, 0.0, 1440.0, 900.0))
+        );
+        // On a display that's gone
+        assert_eq!(window(4000.0, 0.0, 800.0, 600.0).fit(&displays), None);
+    }
+}
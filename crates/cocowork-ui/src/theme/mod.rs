@@ -1,17 +1,130 @@
 //! Theme system for CocoWork
 //!
 //! Provides color definitions and styling based on the design specification.
+//!
+//! Sizes are given at 100% and multiplied by the UI scale the user zooms to.
+//! [`Theme::with_scale`] scales the spacing and typography values; code that
+//! sizes elements directly goes through [`scaled`], which reads the scale set
+//! with [`set_ui_scale`].
+//!
+//! The display [`Density`] shrinks spacing and text further on top of the
+//! scale. Paddings, gaps and row heights that should follow it go through
+//! [`spaced`] instead of [`scaled`].
 
 mod colors;
 
 pub use colors::*;
 
+use std::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+
+/// Smallest UI scale zooming out reaches
+pub const MIN_UI_SCALE: f32 = 0.7;
+/// Largest UI scale zooming in reaches
+pub const MAX_UI_SCALE: f32 = 2.0;
+/// How much one zoom step changes the scale
+pub const UI_SCALE_STEP: f32 = 0.1;
+
+/// Bits of the current UI scale, an `f32`; starts at 1.0
+static UI_SCALE: AtomicU32 = AtomicU32::new(0x3f80_0000);
+
+/// The current UI scale, 1.0 being 100%
+pub fn ui_scale() -> f32 {
+    f32::from_bits(UI_SCALE.load(Ordering::Relaxed))
+}
+
+/// Set the UI scale, kept between [`MIN_UI_SCALE`] and [`MAX_UI_SCALE`]
+pub fn set_ui_scale(scale: f32) {
+    UI_SCALE.store(clamp_ui_scale(scale).to_bits(), Ordering::Relaxed);
+}
+
+/// `scale` kept in range and rounded to whole percent
+pub fn clamp_ui_scale(scale: f32) -> f32 {
+    if !scale.is_finite() {
+        return 1.0;
+    }
+    (scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE) * 100.0).round() / 100.0
+}
+
+/// A size given at 100%, at the current UI scale
+pub fn scaled(value: f32) -> f32 {
+    value * ui_scale()
+}
+
+/// How tightly the window packs its content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Density {
+    #[default]
+    Comfortable,
+    /// Smaller paddings, rows and text, for narrow screens shared with an editor
+    Compact,
+}
+
+impl Density {
+    /// Densities in the order they're offered in settings
+    pub const ALL: [Density; 2] = [Density::Comfortable, Density::Compact];
+
+    /// The code the density is stored under
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::Comfortable => "comfortable",
+            Self::Compact => "compact",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|density| density.code() == code)
+    }
+
+    /// Multiplier for paddings, gaps and row heights
+    pub fn spacing_factor(self) -> f32 {
+        match self {
+            Self::Comfortable => 1.0,
+            Self::Compact => 0.75,
+        }
+    }
+
+    /// Multiplier for font sizes
+    pub fn text_factor(self) -> f32 {
+        match self {
+            Self::Comfortable => 1.0,
+            Self::Compact => 0.9,
+        }
+    }
+}
+
+/// Index into [`Density::ALL`] of the current density
+static DENSITY: AtomicU8 = AtomicU8::new(0);
+
+/// The current display density
+pub fn density() -> Density {
+    Density::ALL
+        .get(DENSITY.load(Ordering::Relaxed) as usize)
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Lay the UI out at `density` from the next render on
+pub fn set_density(density: Density) {
+    let idx = Density::ALL.iter().position(|d| *d == density).unwrap_or(0);
+    DENSITY.store(idx as u8, Ordering::Relaxed);
+}
+
+/// A padding, gap or row height given at 100% and comfortable density, at the
+/// current UI scale and density
+pub fn spaced(value: f32) -> f32 {
+    scaled(value) * density().spacing_factor()
+}
+
 /// Theme configuration
 #[derive(Debug, Clone)]
 pub struct Theme {
     pub colors: ThemeColors,
     pub spacing: Spacing,
     pub typography: Typography,
+    /// UI scale `spacing` and `typography` are sized for
+    pub scale: f32,
+    /// Density `spacing` and `typography` are sized for
+    pub density: Density,
 }
 
 impl Default for Theme {
@@ -27,8 +140,26 @@ impl Theme {
             colors: ThemeColors::dark(),
             spacing: Spacing::default(),
             typography: Typography::default(),
+            scale: 1.0,
+            density: Density::Comfortable,
         }
     }
+
+    /// Size spacing and typography for `scale`, 1.0 being 100%
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        let scale = clamp_ui_scale(scale);
+        self.spacing = Spacing::default().scaled(scale * self.density.spacing_factor());
+        self.typography = Typography::default().scaled(scale * self.density.text_factor());
+        self.scale = scale;
+        self
+    }
+
+    /// Size spacing and typography for `density` at the current scale
+    pub fn with_density(mut self, density: Density) -> Self {
+        self.density = density;
+        let scale = self.scale;
+        self.with_scale(scale)
+    }
 }
 
 /// Spacing constants
@@ -48,6 +179,20 @@ pub struct Spacing {
     pub xxl: f32,
 }
 
+impl Spacing {
+    /// Every spacing multiplied by `factor`
+    pub fn scaled(&self, factor: f32) -> Self {
+        Self {
+            xs: self.xs * factor,
+            sm: self.sm * factor,
+            md: self.md * factor,
+            lg: self.lg * factor,
+            xl: self.xl * factor,
+            xxl: self.xxl * factor,
+        }
+    }
+}
+
 impl Default for Spacing {
     fn default() -> Self {
         Self {
@@ -76,6 +221,19 @@ pub struct Typography {
     pub line_height: f32,
 }
 
+impl Typography {
+    /// Font sizes multiplied by `factor`; the line height multiplier stays
+    pub fn scaled(&self, factor: f32) -> Self {
+        Self {
+            base_size: self.base_size * factor,
+            small_size: self.small_size * factor,
+            large_size: self.large_size * factor,
+            header_size: self.header_size * factor,
+            line_height: self.line_height,
+        }
+    }
+}
+
 impl Default for Typography {
     fn default() -> Self {
         Self {
@@ -92,8 +250,19 @@ impl Default for Typography {
 pub mod layout {
     /// Sidebar width in pixels
     pub const SIDEBAR_WIDTH: f32 = 220.0;
+    /// Narrowest and widest the sidebar can be dragged
+    pub const SIDEBAR_MIN_WIDTH: f32 = 180.0;
+    pub const SIDEBAR_MAX_WIDTH: f32 = 480.0;
     /// Context panel width in pixels
     pub const CONTEXT_PANEL_WIDTH: f32 = 280.0;
+    /// Narrowest and widest the context panel can be dragged
+    pub const CONTEXT_PANEL_MIN_WIDTH: f32 = 200.0;
+    pub const CONTEXT_PANEL_MAX_WIDTH: f32 = 500.0;
+    /// Width of the rail a collapsed sidebar or context panel leaves behind
+    pub const RAIL_WIDTH: f32 = 36.0;
+    /// Window size on first launch
+    pub const WINDOW_WIDTH: f32 = 1200.0;
+    pub const WINDOW_HEIGHT: f32 = 800.0;
     /// Header height in pixels
     pub const HEADER_HEIGHT: f32 = 48.0;
     /// Input bar height in pixels
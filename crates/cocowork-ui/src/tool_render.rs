@@ -0,0 +1,191 @@
+//! Structured views of tool output
+//!
+//! Search, grep and glob tools report their results as plain text in a few
+//! well-known shapes: one path per line, `path:line:text` matches, or an
+//! indented `- name` listing. Parsing those into file locations lets a thread
+//! show clickable paths instead of the raw text.
+//!
+//! Output can also be quoted into the prompt, whole or a few of its lines, as
+//! a fenced block the user can ask the agent about.
+
+use cocowork_core::{ContentBlock, ToolCallContent, ToolCallKind, ToolCallState};
+
+/// A file, or a line in one, named by a tool's output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileLocation {
+    /// Path as the tool printed it, often relative to the working directory
+    pub path: String,
+    /// 1-based line number for matches
+    pub line: Option<usize>,
+    /// The matching line's text
+    pub text: Option<String>,
+}
+
+/// File locations in a finished tool call's output, for kinds with a known format
+///
+/// Returns `None` when the kind has no parser or the output doesn't look like
+/// the expected format, in which case callers should show it as text.
+pub fn parse_tool_output(tool_call: &ToolCallState) -> Option<Vec<FileLocation>> {
+    match tool_call.kind? {
+        ToolCallKind::Search | ToolCallKind::Grep | ToolCallKind::Glob => parse_locations(&output_text(tool_call)?),
+        _ => None,
+    }
+}
+
+/// The text a tool call produced, from its content or raw output
+pub fn output_text(tool_call: &ToolCallState) -> Option<String> {
+    let text: Vec<&str> = tool_call
+        .content
+        .iter()
+        .filter_map(|content| match content {
+            ToolCallContent::Content {
+                content: ContentBlock::Text { text },
+            } => Some(text.as_str()),
+            ToolCallContent::Content {
+                content: ContentBlock::ToolResult { content, .. },
+            } => Some(content.as_str()),
+            _ => None,
+        })
+        .collect();
+    if !text.is_empty() {
+        return Some(text.join("\n"));
+    }
+
+    match tool_call.output.as_ref()? {
+        serde_json::Value::String(text) => Some(text.clone()),
+        // Glob-style tools may return the matched paths as an array
+        serde_json::Value::Array(items) => items
+            .iter()
+            .map(|item| item.as_str().map(str::to_string))
+            .collect::<Option<Vec<_>>>()
+            .map(|paths| paths.join("\n")),
+        output => ["output", "content", "stdout"]
+            .iter()
+            .find_map(|key| output.get(key)?.as_str().map(str::to_string)),
+    }
+}
+
+/// `text` as a fenced code block, or only its lines `first..=last` (0-based)
+///
+/// The fence is longer than any run of backticks in the text, so quoted
+/// Markdown can't close it early.
+pub fn quote_block(text: &str, lines: Option<(usize, usize)>) -> String {
+    let quoted = match lines {
+        Some((first, last)) => text.lines().skip(first).take(last + 1 - first).collect::<Vec<_>>().join("\n"),
+        None => text.trim_end().to_string(),
+    };
+    let longest_run = quoted.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    format!("{}\n{}\n{}", fence, quoted, fence)
+}
+
+/// Parse tool output made of file paths or `path:line:text` matches
+///
+/// Code fences, blank lines and summaries like `Found 3 files` are skipped.
+/// Any other line that isn't a location makes the whole output unparsed.
+pub fn parse_locations(text: &str) -> Option<Vec<FileLocation>> {
+    let mut locations = Vec::new();
+    for line in text.lines() {
+        let line = line.trim_end();
+        let entry = line.trim_start().strip_prefix("- ").unwrap_or(line.trim_start());
+        if entry.is_empty() || entry.starts_with("```") || is_summary(entry) {
+            continue;
+        }
+        locations.push(parse_location(entry)?);
+    }
+    (!locations.is_empty()).then_some(locations)
+}
+
+fn is_summary(line: &str) -> bool {
+    (line.starts_with('(') && line.ends_with(')'))
+        || line.starts_with("No files found")
+        || line.starts_with("No matches found")
+        || (line.starts_with("Found ") && line.split_whitespace().nth(1).is_some_and(|n| n.parse::<usize>().is_ok()))
+}
+
+fn parse_location(entry: &str) -> Option<FileLocation> {
+    // Keep a Windows drive letter with the path
+    let split_from = if entry.as_bytes().get(1) == Some(&b':') { 2 } else { 0 };
+    if let Some(i) = entry[split_from..].find(':').map(|i| i + split_from) {
+        let (path, rest) = (&entry[..i], &entry[i + 1..]);
+        let (line, text) = rest.split_once(':').unwrap_or((rest, ""));
+        let line = line.parse().ok()?;
+        return is_path(path).then(|| FileLocation {
+            path: path.to_string(),
+            line: Some(line),
+            text: (!text.is_empty()).then(|| text.to_string()),
+        });
+    }
+    is_path(entry).then(|| FileLocation {
+        path: entry.to_string(),
+        line: None,
+        text: None,
+    })
+}
+
+/// Whether `text` looks like a file or directory path rather than prose
+fn is_path(text: &str) -> bool {
+    !text.is_empty()
+        && text.len() < 4096
+        && text.trim() == text
+        && !text.contains(": ")
+        && (text.contains('/') || text.contains('\\') || text.contains('.'))
+        && !text.ends_with('.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(path: &str, line: Option<usize>, text: Option<&str>) -> FileLocation {
+        FileLocation {
+            path: path.to_string(),
+            line,
+            text: text.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_parse_grep_and_glob_output() {
+        let grep = "```\nsrc/main.rs:12:fn main() {\nsrc/lib.rs:3:pub mod index; // a: b\n```";
+        assert_eq!(
+            parse_locations(grep).unwrap(),
+            vec![
+                location("src/main.rs", Some(12), Some("fn main() {")),
+                location("src/lib.rs", Some(3), Some("pub mod index; // a: b")),
+            ]
+        );
+
+        let glob = "Found 2 files\n/repo/a.rs\nC:\\repo\\b.rs\n(Results are truncated.)";
+        assert_eq!(
+            parse_locations(glob).unwrap(),
+            vec![location("/repo/a.rs", None, None), location("C:\\repo\\b.rs", None, None)]
+        );
+
+        let listing = "- /repo/\n  - src/\n    - main.rs\n";
+        assert_eq!(parse_locations(listing).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_prose_is_not_parsed() {
+        assert!(parse_locations("The search found nothing useful.\nsrc/main.rs").is_none());
+        assert!(parse_locations("No files found").is_none());
+
+        let mut call = ToolCallState::new("t1".into(), None, Some(ToolCallKind::Read));
+        call.output = Some(serde_json::json!("src/main.rs"));
+        assert!(parse_tool_output(&call).is_none());
+        call.kind = Some(ToolCallKind::Glob);
+        assert_eq!(parse_tool_output(&call).unwrap(), vec![location("src/main.rs", None, None)]);
+    }
+
+    #[test]
+    fn test_quote_block() {
+        let output = "error[E0308]: mismatched types\n --> src/main.rs:4:5\n  |\n4 |     1\n";
+        assert_eq!(quote_block(output, None), format!("```\n{}\n```", output.trim_end()));
+        assert_eq!(
+            quote_block(output, Some((0, 1))),
+            "```\nerror[E0308]: mismatched types\n --> src/main.rs:4:5\n```"
+        );
+        assert_eq!(quote_block("use ```rust fences", None), "````\nuse ```rust fences\n````");
+    }
+}
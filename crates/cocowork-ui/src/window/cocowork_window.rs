@@ -5,14 +5,231 @@
 //! - MainPanel (flex-1): Header + Messages + Input
 //! - ContextPanel (280px): State/Artifacts/Context
 
-use cocowork_core::{ContentBlock, MessageBlock, PlanEntry, PlanStatus, ToolCallKind, ToolCallState, ToolCallStatus};
+use cocowork_core::{
+    metrics, export_audit_log, format_duration, Approval, AuditEntry, AuditExportFormat, AuditFilter, AuditOutcome, BackupPolicy, BatchItemState, ContentBlock, DiffLineKind, ExportFormat, FileOperation, InstallState, MessageBlock, PlanEntry, PlanPriority, PlanStatus, PolicyScope, ProposedAction,
+    ProposedActionKind, SearchQuery, SecretKey, StopReason, TextMatch, ThreadExport, ToolCallContent, ToolCallKind, ToolCallState,
+    ToolCallStatus, ToolPolicy, WebhookEvent, AttachmentMode, ExternalEdit, ModelId, SessionModeId,
+    FileDiff, IsolationKind, ContainerRuntime, TerminalBackend, PermissionOptionKind, PermissionPreview, LiveTerminal,
+    TestConfig, CheckConfig, Severity, MessageFlag, TaskCommit, PullRequestDraft, PullRequestTarget, IssueRef,
+    find_sensitive, Finding, Redaction, SensitiveKind,
+};
 use cocowork_ui::{
-    components::{svg_icon, IconName, IconSize, TextInput},
-    layout, AcpModel, Rgba as ThemeRgba, Spacing, Theme,
+    components::{approx_token_count, svg_icon, IconName, IconSize, TextInput, TextInputEvent, Tooltip, VimMode},
+    i18n::{self, t, t_args, Language},
+    layout,
+    state::{
+        format_relative, group_turn_actions, FrameTimes, highlight_lines, latest_tool_call, pinned_entries, thread_outline, ActionSummary, DateGroup, spinner_frame, DeepLink, StreamRate, move_highlight, search_prompts, DisplayArea, FocusRegion, ListKey, MarkdownSource,
+        MarkdownCache, MarkdownCacheBudget, MarkdownUpdate, TimelineAnchor, TimelineEntry, TimelineSpan, Token, TokenKind, WindowGeometry, WindowLayout,
+    },
+    theme::{self, clamp_ui_scale, scaled, set_ui_scale, spaced, ui_scale, Density, UI_SCALE_STEP},
+    tool_render::{output_text, parse_tool_output, quote_block, FileLocation},
+    acp_integration::logs_dir,
+    auto_retry::RetryLoop,
+    AcpManager, AcpModel, DatabaseTask, NewSessionOptions, DEFAULT_ATTACHMENT_LIMIT, PromptSizeEstimate, TaskCard, TaskColumn, FilePreviewState, Rgba as ThemeRgba, Spacing, StorageRecovery, Theme, TurnEnd,
 };
 use gpui::prelude::FluentBuilder;
 use gpui::*;
 use markdown::{Markdown, MarkdownStyle};
+use std::path::{Path, PathBuf};
+
+/// Most project search matches listed at once
+const MAX_SEARCH_ROWS: usize = 500;
+/// Lines shown on each side of a match in the search preview
+const SEARCH_PREVIEW_CONTEXT: usize = 20;
+/// Most lines of a file the preview panel renders
+const MAX_PREVIEW_LINES: usize = 5000;
+/// Most audit log entries listed at once; exports include every match
+const MAX_AUDIT_ROWS: usize = 500;
+/// Operations the audit log view can filter by
+const AUDIT_OPERATIONS: [&str; 7] = ["read", "list", "write", "move", "delete", "execute", "permission"];
+/// Block id the user's terminal panel picks lines for quoting under
+const USER_TERMINAL_BLOCK: &str = "user-terminal";
+/// Most file locations listed under a search tool call
+const MAX_TOOL_LOCATIONS: usize = 20;
+/// Most earlier prompts searched with Ctrl+R
+const MAX_HISTORY_PROMPTS: usize = 500;
+/// Most prompt history matches listed at once
+const MAX_HISTORY_MATCHES: usize = 8;
+/// How long the layout must stay unchanged before it's saved
+const LAYOUT_SAVE_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+/// Most problems listed in the context panel; all of them go to the agent
+const MAX_PROBLEMS_SHOWN: usize = 50;
+/// Context panel sections, in the order arrow keys move through them
+const CONTEXT_SECTIONS: [&str; 6] = ["Progress", "Instructions", "Tests", "Problems", "Artifacts", "Context"];
+
+/// The title shown for one of `CONTEXT_SECTIONS`
+fn section_label(section: &str) -> &'static str {
+    match section {
+        "Progress" => t("context.progress"),
+        "Instructions" => t("context.instructions"),
+        "Tests" => t("context.tests"),
+        "Problems" => t("context.problems"),
+        "Artifacts" => t("context.artifacts"),
+        _ => t("context.context"),
+    }
+}
+
+/// How a turn ended, as shown at its end
+fn stop_reason_label(reason: StopReason) -> &'static str {
+    match reason {
+        StopReason::EndTurn => t("stop_reason.end_turn"),
+        StopReason::MaxTokens => t("stop_reason.max_tokens"),
+        StopReason::MaxTurnRequests => t("stop_reason.max_turn_requests"),
+        StopReason::Refusal => t("stop_reason.refusal"),
+        StopReason::Cancelled => t("stop_reason.cancelled"),
+        StopReason::Error => t("stop_reason.error"),
+    }
+}
+
+fn approval_label(approval: Approval) -> &'static str {
+    match approval {
+        Approval::Default => t("approval.default"),
+        Approval::AutoAccept => t("approval.auto_accept"),
+        Approval::Confirm => t("approval.confirm"),
+    }
+}
+
+/// Short description of a policy's non-default approvals, e.g. "Reads auto · Terminal confirm"
+fn policy_summary(policy: &ToolPolicy) -> String {
+    let parts: Vec<String> = [
+        (t("approval.reads"), policy.read),
+        (t("approval.writes"), policy.write),
+        (t("approval.deletes"), policy.delete),
+        (t("approval.terminal"), policy.terminal),
+    ]
+    .into_iter()
+    .filter_map(|(kind, approval)| match approval {
+        Approval::Default => None,
+        Approval::AutoAccept => Some(t_args("approval.kind_auto", &[("kind", &kind)])),
+        Approval::Confirm => Some(t_args("approval.kind_confirm", &[("kind", &kind)])),
+    })
+    .collect();
+    if parts.is_empty() {
+        t("approval.default_permissions").to_string()
+    } else {
+        parts.join(" · ")
+    }
+}
+
+fn audit_outcome_label(outcome: AuditOutcome) -> &'static str {
+    match outcome {
+        AuditOutcome::Allowed => t("audit.outcome.allowed"),
+        AuditOutcome::Approved => t("audit.outcome.approved"),
+        AuditOutcome::AutoApproved => t("audit.outcome.auto_approved"),
+        AuditOutcome::Recorded => t("audit.outcome.recorded"),
+        AuditOutcome::Rejected => t("audit.outcome.rejected"),
+        AuditOutcome::Denied => t("audit.outcome.denied"),
+        AuditOutcome::Failed => t("audit.outcome.failed"),
+        AuditOutcome::LimitExceeded => t("audit.outcome.limit_exceeded"),
+    }
+}
+
+/// Base rem size at 100%, which text sizes like `text_sm` are relative to
+pub(super) const REM_SIZE: f32 = 16.0;
+
+actions!(
+    cocowork_window,
+    [
+        FocusNextRegion,
+        FocusPreviousRegion,
+        ZoomIn,
+        ZoomOut,
+        ResetZoom,
+        ToggleSidebar,
+        ToggleContextPanel,
+        ToggleTerminalPanel,
+        ToggleDebugOverlay
+    ]
+);
+
+/// Register the window's key bindings
+pub fn register_bindings(cx: &mut AppContext) {
+    cx.bind_keys([
+        KeyBinding::new("tab", FocusNextRegion, Some("CocoWorkWindow")),
+        KeyBinding::new("shift-tab", FocusPreviousRegion, Some("CocoWorkWindow")),
+        KeyBinding::new("cmd-=", ZoomIn, Some("CocoWorkWindow")),
+        KeyBinding::new("cmd-+", ZoomIn, Some("CocoWorkWindow")),
+        KeyBinding::new("cmd--", ZoomOut, Some("CocoWorkWindow")),
+        KeyBinding::new("cmd-0", ResetZoom, Some("CocoWorkWindow")),
+        KeyBinding::new("cmd-b", ToggleSidebar, Some("CocoWorkWindow")),
+        KeyBinding::new("cmd-alt-b", ToggleContextPanel, Some("CocoWorkWindow")),
+        KeyBinding::new("ctrl-`", ToggleTerminalPanel, Some("CocoWorkWindow")),
+        KeyBinding::new("cmd-shift-d", ToggleDebugOverlay, Some("CocoWorkWindow")),
+    ]);
+}
+
+/// Where to open the window: as it was last left if that still fits a display,
+/// otherwise at the default size
+pub fn initial_window_bounds(saved: &WindowLayout, cx: &AppContext) -> WindowBounds {
+    let displays: Vec<DisplayArea> = cx
+        .displays()
+        .iter()
+        .map(|display| {
+            let bounds = display.bounds();
+            DisplayArea {
+                x: f32::from(bounds.origin.x),
+                y: f32::from(bounds.origin.y),
+                width: f32::from(bounds.size.width),
+                height: f32::from(bounds.size.height),
+            }
+        })
+        .collect();
+    let Some(window) = saved.window.and_then(|window| window.fit(&displays)) else {
+        return WindowBounds::Windowed(Bounds {
+            origin: point(gpui::px(100.0), gpui::px(100.0)),
+            size: size(gpui::px(layout::WINDOW_WIDTH), gpui::px(layout::WINDOW_HEIGHT)),
+        });
+    };
+    let bounds = Bounds {
+        origin: point(gpui::px(window.x), gpui::px(window.y)),
+        size: size(gpui::px(window.width), gpui::px(window.height)),
+    };
+    if window.fullscreen {
+        WindowBounds::Fullscreen(bounds)
+    } else if window.maximized {
+        WindowBounds::Maximized(bounds)
+    } else {
+        WindowBounds::Windowed(bounds)
+    }
+}
+
+/// The stored form of the window's bounds
+fn window_geometry(bounds: WindowBounds) -> WindowGeometry {
+    let (bounds, maximized, fullscreen) = match bounds {
+        WindowBounds::Windowed(bounds) => (bounds, false, false),
+        WindowBounds::Maximized(bounds) => (bounds, true, false),
+        WindowBounds::Fullscreen(bounds) => (bounds, false, true),
+    };
+    WindowGeometry {
+        x: f32::from(bounds.origin.x),
+        y: f32::from(bounds.origin.y),
+        width: f32::from(bounds.size.width),
+        height: f32::from(bounds.size.height),
+        maximized,
+        fullscreen,
+    }
+}
+
+/// `gpui::px` at the current UI scale
+///
+/// Shadows the glob-imported `px` so every size in the window follows zoom.
+/// Sizes already scaled, such as the theme's typography, use `gpui::px`.
+fn px(value: f32) -> Pixels {
+    gpui::px(scaled(value))
+}
+
+/// `px` that also follows the display density, for paddings, gaps and row heights
+fn dense(value: f32) -> Pixels {
+    gpui::px(spaced(value))
+}
+
+/// Ctrl+R search over prompts sent in any thread
+struct HistorySearch {
+    /// Prompts to search, newest first
+    prompts: Vec<String>,
+    /// Index of the highlighted match
+    selected: usize,
+}
 
 /// A thread entry in the sidebar
 #[derive(Clone, Debug)]
@@ -20,18 +237,30 @@ pub struct ThreadEntry {
     pub id: String,
     pub name: String,
     pub agent_id: String,
+    /// Directory the thread's session was started in
+    pub working_dir: PathBuf,
     pub message_count: usize,
     pub is_active: bool,
+    /// Thread this one was spawned from as a sub-thread
+    pub parent_id: Option<String>,
+    /// Whether the thread was forked from its parent rather than spawned from it
+    pub forked: bool,
+    /// When the thread last had a message, which decides its date group in the sidebar
+    pub last_activity: chrono::DateTime<chrono::Utc>,
 }
 
 impl ThreadEntry {
-    pub fn new(id: &str, name: &str, agent_id: &str, message_count: usize) -> Self {
+    pub fn new(id: &str, name: &str, agent_id: &str, working_dir: PathBuf, message_count: usize) -> Self {
         Self {
             id: id.to_string(),
             name: name.to_string(),
             agent_id: agent_id.to_string(),
+            working_dir,
             message_count,
             is_active: false,
+            parent_id: None,
+            forked: false,
+            last_activity: chrono::Utc::now(),
         }
     }
 }
@@ -45,6 +274,18 @@ pub struct CocoWorkWindow {
     acp: AcpModel,
     /// Message input component
     message_input: View<TextInput>,
+    /// Reply box of the question card an agent's turn is waiting on
+    question_input: View<TextInput>,
+    /// Input boxes of the agent's live terminals, by terminal id
+    terminal_inputs: std::collections::HashMap<String, View<TextInput>>,
+    /// Show the user's own terminal at the bottom of the main panel
+    show_terminal_panel: bool,
+    /// Command line of the user's terminal
+    terminal_panel_input: View<TextInput>,
+    /// Output lines picked for quoting: the terminal's id, then the first and last line
+    quote_selection: Option<(String, usize, usize)>,
+    /// Why the user's terminal couldn't be opened
+    terminal_panel_error: Option<String>,
     /// Search input for filtering threads
     search_input: View<TextInput>,
     /// Thread list for sidebar
@@ -81,20 +322,307 @@ pub struct CocoWorkWindow {
     show_mcp_panel: bool,
     /// Configured MCP servers
     mcp_servers: Vec<McpServerConfig>,
-    /// Collapsed thinking blocks (by message index)
-    collapsed_thinking: std::collections::HashSet<usize>,
+    /// Thinking blocks the user folded (true) or opened (false), by message index;
+    /// the rest follow the auto-collapse setting
+    collapsed_thinking: std::collections::HashMap<usize, bool>,
+    /// Indices of conversation summaries the user expanded
+    expanded_summaries: std::collections::HashSet<usize>,
+    /// Turns whose actions group the user opened (true) or closed (false); the rest
+    /// are closed except the running turn
+    open_action_groups: std::collections::HashMap<usize, bool>,
+    /// Sidebar date groups whose threads are hidden
+    collapsed_date_groups: std::collections::HashSet<DateGroup>,
+    /// Start of a plan step's activity to bring into view on the next draw of the timeline
+    plan_scroll_target: Option<chrono::DateTime<chrono::Utc>>,
     /// Scroll handle for message list (auto-scroll)
     message_scroll_handle: ScrollHandle,
     /// Track whether we should keep auto-scrolling to the latest output
     stick_to_bottom: bool,
     /// Cached timeline length for detecting new content
     last_timeline_len: usize,
-    /// Cached markdown views for messages
-    message_markdown_cache: std::collections::HashMap<String, View<Markdown>>,
+    /// Rows the timeline rendered last, spacer included; fewer than `timeline_len`
+    /// when action groups are folded
+    rendered_timeline_len: usize,
+    /// Scroll to the last row on the next render, once new content is laid out
+    scroll_to_bottom_pending: bool,
+    /// Message or tool call to scroll to on the next render, opening its action group
+    jump_target: Option<TimelineAnchor>,
+    /// Show render and runtime stats over the window
+    show_debug_overlay: bool,
+    /// Frames drawn lately, counted while the debug overlay shows
+    frame_times: FrameTimes,
+    /// Cached markdown views for messages of every thread, with the text each was last given
+    message_markdown_cache: MarkdownCache<View<Markdown>>,
     /// Show new thread dialog (with agent selection)
     show_new_thread_dialog: bool,
     /// Show user menu dropdown
     show_user_menu: bool,
+    /// Show the active thread's "···" menu
+    show_thread_menu: bool,
+    /// Show the list of the thread's turns to jump to
+    show_outline_menu: bool,
+    /// Whether the timeline shows only flagged messages, for reviewing the thread
+    review_mode: bool,
+    /// Flag review mode narrows the timeline to; `None` shows every flagged message
+    review_flag: Option<MessageFlag>,
+    /// Work the new thread dialog is picking an agent for, as a sub-thread of the active one
+    subtask_seed: Option<String>,
+    /// Show the new thread dialog's session overrides
+    show_new_thread_advanced: bool,
+    /// MCP servers picked for the new thread, by name
+    new_thread_mcp: std::collections::HashSet<String>,
+    /// Working directory picked for the new thread instead of the workspace
+    new_thread_dir: Option<PathBuf>,
+    /// Extra variables for the new thread's agent
+    new_thread_env_input: View<TextInput>,
+    /// Mode and model to start the new thread in
+    new_thread_mode_input: View<TextInput>,
+    new_thread_model_input: View<TextInput>,
+    /// URL of the GitHub issue the new thread starts from
+    new_thread_issue_input: View<TextInput>,
+    /// Why the new thread's overrides can't be used
+    new_thread_error: Option<String>,
+    /// Run the new thread in a worktree or copy of the workspace
+    new_thread_isolate: bool,
+    /// The isolated thread's changes being reviewed, or why they can't be listed
+    isolation_review: Option<Result<Vec<FileDiff>, String>>,
+    /// Workspace the isolated changes were merged into, or why merging failed
+    isolation_status: Option<Result<PathBuf, String>>,
+    /// The active thread's uncommitted changes being committed, or why they can't be listed
+    commit_review: Option<Result<Vec<FileDiff>, String>>,
+    /// Message of the commit being made
+    commit_message_input: View<TextInput>,
+    /// Why the last commit or message draft failed
+    commit_error: Option<String>,
+    /// Where the pull request being opened would go, or why there's nowhere
+    pull_request_wizard: Option<Result<PullRequestTarget, String>>,
+    /// Whether there's a token for the wizard's forge, checked when it opens
+    /// rather than reading the keychain on every frame
+    pull_request_has_token: bool,
+    /// The remote's host when the wizard can't open pull requests on it yet
+    pull_request_unknown_host: Option<String>,
+    /// Forge token entered in the pull request wizard
+    pull_request_token_input: View<TextInput>,
+    pull_request_title_input: View<TextInput>,
+    pull_request_body_input: View<TextInput>,
+    /// Why the last step of the pull request wizard failed
+    pull_request_error: Option<String>,
+    /// Where the last thread export was saved, or why it failed
+    export_status: Option<Result<std::path::PathBuf, String>>,
+    /// The thread export being checked for sensitive strings, in the format picked
+    export_review: Option<(ExportFormat, ThreadExport, Vec<Finding>)>,
+    /// What the reviewed export will have hidden
+    export_redaction: Redaction,
+    /// A term of the user's own to hide from the export
+    redact_term_input: View<TextInput>,
+    /// Why the active thread couldn't be shared
+    share_error: Option<String>,
+    /// Whether the last run crashed and a diagnostic bundle hasn't been offered for it yet
+    crash_report_pending: bool,
+    /// Where the last diagnostic bundle was saved, or why it failed
+    bundle_status: Option<Result<std::path::PathBuf, String>>,
+    /// Whether the banner saying another instance is writing the database was dismissed
+    read_only_dismissed: bool,
+    /// Why the last `cocowork://` link couldn't be opened
+    link_error: Option<String>,
+    /// Whether sending was held back because the workspace no longer matches the thread's
+    show_workspace_warning: bool,
+    /// Whether sending was held back because the attachments are over the size limit
+    show_attachment_warning: bool,
+    /// How the attachments go with the next send, once picked from the size warning
+    attachment_choice: Option<AttachmentMode>,
+    /// Proposed dry-run actions the user unchecked in the review panel
+    deselected_actions: std::collections::HashSet<String>,
+    /// Show agent install/update progress dialog
+    show_install_dialog: bool,
+    /// Show settings dialog
+    show_settings_dialog: bool,
+    /// API key inputs in the settings dialog
+    secret_inputs: Vec<(SecretKey, View<TextInput>)>,
+    /// Last API key save/clear error shown in settings
+    secret_error: Option<String>,
+    /// New env profile name input in settings
+    env_profile_name_input: View<TextInput>,
+    /// New env profile variables input in settings
+    env_profile_vars_input: View<TextInput>,
+    /// Last env profile save/delete error shown in settings
+    env_profile_error: Option<String>,
+    /// Proxy inputs in settings: HTTP, HTTPS, no-proxy hosts
+    proxy_inputs: [View<TextInput>; 3],
+    /// Result of the last proxy save shown in settings
+    proxy_status: Option<Result<(), String>>,
+    /// Container inputs in settings: image, extra mounts
+    container_inputs: [View<TextInput>; 2],
+    /// Container runtime and network access being edited in settings
+    container_runtime: ContainerRuntime,
+    container_network: bool,
+    /// Result of the last terminal backend or container save shown in settings
+    container_status: Option<Result<(), String>>,
+    /// Command limit inputs in settings: CPU seconds, seconds, output KB, concurrent commands
+    limit_inputs: [View<TextInput>; 4],
+    /// Result of the last command limits save shown in settings
+    limits_status: Option<Result<(), String>>,
+    /// New webhook inputs in settings: URL, secret
+    webhook_inputs: [View<TextInput>; 2],
+    /// Events the next webhook added in settings fires on
+    webhook_events: Vec<WebhookEvent>,
+    /// Last webhook add/remove error shown in settings
+    webhook_error: Option<String>,
+    /// New secret pattern input in settings
+    redaction_pattern_input: View<TextInput>,
+    /// Why the last secret pattern couldn't be added
+    redaction_pattern_error: Option<String>,
+    /// Scope being edited in the tool permissions settings, an index into `policy_scopes()`
+    policy_scope_idx: usize,
+    /// Last tool policy save error shown in settings
+    policy_error: Option<String>,
+    /// Whether the prompt template menu is open
+    show_template_menu: bool,
+    /// Prompt template inputs in the settings dialog (name, text)
+    template_name_input: View<TextInput>,
+    template_body_input: View<TextInput>,
+    /// Why the last prompt template change failed
+    template_error: Option<String>,
+    /// Workspace instructions editor in the context panel
+    instructions_input: View<TextInput>,
+    /// Result of the last instructions save
+    instructions_status: Option<Result<(), String>>,
+    /// Workspace test command editor in the context panel
+    test_command_input: View<TextInput>,
+    /// Result of the last test settings change
+    test_command_status: Option<Result<(), String>>,
+    /// Workspace build and lint commands editor in the context panel, one per line
+    check_commands_input: View<TextInput>,
+    /// Path of the JSON diagnostics file another tool writes, in the context panel
+    diagnostics_file_input: View<TextInput>,
+    /// Result of the last build and lint settings change
+    check_commands_status: Option<Result<(), String>>,
+    /// Whether the batch run dialog is open
+    show_batch_dialog: bool,
+    /// Show what the connected agent reported about itself
+    show_capabilities: bool,
+    /// Prompt to run in every batch workspace
+    batch_prompt_input: View<TextInput>,
+    /// Workspaces picked for the next batch run
+    batch_workspaces: Vec<std::path::PathBuf>,
+    /// How many batch workspaces run at once
+    batch_concurrency: usize,
+    /// Why the batch couldn't start
+    batch_error: Option<String>,
+    /// Whether the agent comparison dialog is open
+    show_compare_dialog: bool,
+    /// Prompt to send to both compared agents
+    compare_prompt_input: View<TextInput>,
+    /// Agents picked for the comparison (A, B)
+    compare_agents: [Option<String>; 2],
+    /// Why the comparison couldn't start
+    compare_error: Option<String>,
+    /// Whether the main panel shows the usage dashboard instead of a thread
+    show_dashboard: bool,
+    /// How many days the dashboard covers
+    dashboard_days: i64,
+    /// Whether the main panel shows project search instead of a thread
+    show_search: bool,
+    /// Query input for project search
+    project_search_input: View<TextInput>,
+    /// Treat the search query as a regex
+    search_regex: bool,
+    search_case_sensitive: bool,
+    /// Why the last search couldn't start
+    search_error: Option<String>,
+    /// Whether the main panel shows the audit log instead of a thread
+    show_audit: bool,
+    /// Whether the main panel shows the task board instead of a thread
+    show_task_board: bool,
+    /// Filter for the audit log; the search text comes from `audit_search_input`
+    audit_filter: AuditFilter,
+    audit_search_input: View<TextInput>,
+    /// Entries listed in the audit log, newest first
+    audit_entries: Vec<AuditEntry>,
+    /// Entries matching the filter, including ones past the listed limit
+    audit_total: usize,
+    /// Where the last audit export went, or why loading or exporting failed
+    audit_status: Option<Result<PathBuf, String>>,
+    /// The file around the selected search match
+    search_preview: Option<SearchPreview>,
+    /// Highlighted lines of the file in the preview panel, once loaded
+    preview_lines: Option<Vec<Vec<Token>>>,
+    /// Open prompt history search; the query comes from `history_search_input`
+    history_search: Option<HistorySearch>,
+    history_search_input: View<TextInput>,
+    /// Prompt put in the input with Up/Down, to tell whether it's been edited since
+    recalled_prompt: Option<String>,
+    /// Focus for moving through the thread list with the keyboard
+    threads_focus: FocusHandle,
+    /// Position in the shown threads highlighted with the arrow keys
+    highlighted_thread: Option<usize>,
+    /// Focus for moving through the context panel's sections with the keyboard
+    context_panel_focus: FocusHandle,
+    /// Index into `CONTEXT_SECTIONS` highlighted with the arrow keys
+    highlighted_section: Option<usize>,
+    /// Whether the sidebar is collapsed to a rail
+    sidebar_collapsed: bool,
+    /// Whether the context panel is collapsed to a rail
+    context_panel_collapsed: bool,
+    /// Window bounds to restore on the next launch
+    window_geometry: Option<WindowGeometry>,
+    /// Pending save of the layout, replaced by each change so it runs once things settle
+    layout_save: Option<Task<()>>,
+}
+
+/// A message or tool call of the thread, as placed in the timeline
+enum TimelineItem {
+    Message { idx: usize, msg: MessageBlock },
+    ToolCall { idx: usize, call: ToolCallState },
+}
+
+impl TimelineItem {
+    fn timestamp(&self) -> chrono::DateTime<chrono::Utc> {
+        match self {
+            Self::Message { msg, .. } => msg.timestamp(),
+            Self::ToolCall { call, .. } => call.started_at,
+        }
+    }
+
+    fn kind_order(&self) -> u8 {
+        match self {
+            Self::ToolCall { .. } => 0,
+            Self::Message { .. } => 1,
+        }
+    }
+
+    fn tie_index(&self) -> usize {
+        match self {
+            Self::Message { idx, .. } => *idx,
+            Self::ToolCall { idx, .. } => *idx,
+        }
+    }
+
+    fn matches(&self, anchor: &TimelineAnchor) -> bool {
+        match self {
+            Self::Message { msg, .. } => anchor.matches_message(msg),
+            Self::ToolCall { call, .. } => anchor.matches_tool_call(call),
+        }
+    }
+
+    fn entry(&self) -> TimelineEntry {
+        match self {
+            Self::Message { msg: MessageBlock::User { .. }, .. } => TimelineEntry::Prompt,
+            Self::Message { .. } => TimelineEntry::Reply,
+            Self::ToolCall { .. } => TimelineEntry::Action,
+        }
+    }
+}
+
+/// Lines around a project search match
+struct SearchPreview {
+    path: PathBuf,
+    line: usize,
+    column: usize,
+    length: usize,
+    /// Line number of the first entry in `lines`
+    first_line: usize,
+    lines: Result<Vec<String>, String>,
 }
 
 /// MCP Server configuration
@@ -105,31 +633,159 @@ pub struct McpServerConfig {
     pub enabled: bool,
 }
 
+impl McpServerConfig {
+    /// The server as an agent starts it for a session
+    fn to_session_server(&self) -> cocowork_core::McpServerConfig {
+        let mut words = self.command.split_whitespace().map(str::to_string);
+        cocowork_core::McpServerConfig {
+            name: self.name.clone(),
+            command: words.next().unwrap_or_default(),
+            args: words.collect(),
+            env: Default::default(),
+            transport: Default::default(),
+            enabled: true,
+        }
+    }
+}
+
 impl CocoWorkWindow {
-    pub fn new(cx: &mut ViewContext<Self>, theme: Theme) -> Self {
-        let acp = AcpModel::new();
+    pub fn new(cx: &mut ViewContext<Self>, theme: Theme, acp: AcpModel) -> Self {
+        set_ui_scale(acp.manager.ui_scale);
+        i18n::set_language(acp.manager.language);
+        theme::set_density(acp.manager.density);
+        let theme = theme.with_scale(acp.manager.ui_scale).with_density(acp.manager.density);
+        let saved_layout = acp.manager.window_layout.clone();
+        let markdown_cache_budget = acp.manager.markdown_cache_budget;
 
-        // Initialize with empty threads - user will create on demand
-        let threads = vec![];
+        // Threads cut off when the app last closed come back; others are created on demand
+        let threads = acp
+            .manager
+            .recovered_sessions()
+            .into_iter()
+            .map(|session| {
+                let name = session
+                    .sent_prompts()
+                    .first()
+                    .and_then(|prompt| prompt.lines().next())
+                    .map(|line| line.chars().take(60).collect::<String>())
+                    .unwrap_or_else(|| "New thread".to_string());
+                let mut thread = ThreadEntry::new(
+                    &session.session_id,
+                    &name,
+                    &session.agent_id,
+                    session.working_dir.clone(),
+                    session.messages.len(),
+                );
+                thread.parent_id = session.parent_session_id.clone();
+                thread.forked = session.forked_at.is_some();
+                if let Some(last_activity) = session.last_activity() {
+                    thread.last_activity = last_activity;
+                }
+                thread
+            })
+            .collect();
 
         let focus_handle = cx.focus_handle();
 
         // Create message input
+        let vim_mode = acp.manager.vim_mode;
         let message_input = cx.new_view(|cx| {
             let mut input = TextInput::new(cx);
-            input.set_placeholder("Message CocoWork's Agent...");
+            input.set_multi_line(true);
+            input.set_vim_mode(vim_mode, cx);
             input
         });
 
         // Re-render when message input changes (e.g. enable/disable send button)
         cx.observe(&message_input, |_, _, cx| cx.notify()).detach();
 
-        // Create thread search input
-        let search_input = cx.new_view(|cx| {
+        // Enter or Cmd+Enter sends; Shift+Enter is a newline inside the input
+        cx.subscribe(&message_input, |this, _, event: &TextInputEvent, cx| match event {
+            TextInputEvent::Submit => this.submit_message_input(cx),
+        })
+        .detach();
+
+        let question_input = cx.new_view(|cx| {
+            let mut input = TextInput::new(cx);
+            input.set_multi_line(true);
+            input.set_placeholder(t("question.placeholder"));
+            input
+        });
+        cx.subscribe(&question_input, |this, _, event: &TextInputEvent, cx| match event {
+            TextInputEvent::Submit => this.reply_to_question(true, cx),
+        })
+        .detach();
+        cx.observe(&question_input, |_, _, cx| cx.notify()).detach();
+
+        let terminal_panel_input = cx.new_view(|cx| {
+            let mut input = TextInput::new(cx);
+            input.set_multi_line(true);
+            input.set_placeholder(t("terminal_panel.placeholder"));
+            input
+        });
+        cx.subscribe(&terminal_panel_input, |this, _, event: &TextInputEvent, cx| match event {
+            TextInputEvent::Submit => this.run_in_terminal_panel(cx),
+        })
+        .detach();
+
+        let commit_message_input = cx.new_view(|cx| {
+            let mut input = TextInput::new(cx);
+            input.set_multi_line(true);
+            input.set_placeholder(t("commit.placeholder"));
+            input
+        });
+        cx.subscribe(&commit_message_input, |this, _, event: &TextInputEvent, cx| match event {
+            TextInputEvent::Submit => this.commit_changes(cx),
+        })
+        .detach();
+        cx.observe(&commit_message_input, |_, _, cx| cx.notify()).detach();
+
+        let pull_request_token_input = cx.new_view(|cx| {
+            let mut input = TextInput::new(cx);
+            input.set_placeholder(t("pr.token_placeholder"));
+            input
+        });
+        cx.subscribe(&pull_request_token_input, |this, _, event: &TextInputEvent, cx| match event {
+            TextInputEvent::Submit => this.save_forge_token(cx),
+        })
+        .detach();
+        let pull_request_title_input = cx.new_view(|cx| {
+            let mut input = TextInput::new(cx);
+            input.set_placeholder(t("pr.title_placeholder"));
+            input
+        });
+        let pull_request_body_input = cx.new_view(|cx| {
             let mut input = TextInput::new(cx);
-            input.set_placeholder("Search Threads");
+            input.set_multi_line(true);
+            input.set_placeholder(t("pr.body_placeholder"));
             input
         });
+        for input in [&pull_request_title_input, &pull_request_body_input] {
+            cx.subscribe(input, |this, _, event: &TextInputEvent, cx| match event {
+                TextInputEvent::Submit => this.open_pull_request(cx),
+            })
+            .detach();
+            cx.observe(input, |_, _, cx| cx.notify()).detach();
+        }
+
+        // Remember where the window is once it settles
+        cx.observe_window_bounds(|this, cx| {
+            this.window_geometry = Some(window_geometry(cx.window_bounds()));
+            this.save_layout_soon(cx);
+        })
+        .detach();
+
+        // Let queued turns reach the database and stop agent processes before the app exits
+        cx.on_app_quit(|this, _cx| {
+            this.save_layout();
+            this.acp.manager.flush_storage();
+            this.acp.manager.shutdown_agents();
+            async {}
+        })
+        .detach();
+
+        // Create thread search input
+        let search_input = cx.new_view(TextInput::new);
 
         // Keep thread filtering state in sync with the search input.
         cx.observe(&search_input, |this, search_input, cx| {
@@ -138,6 +794,179 @@ impl CocoWorkWindow {
         })
         .detach();
 
+        // Create API key inputs for the settings dialog
+        let secret_inputs = SecretKey::all()
+            .iter()
+            .map(|key| {
+                let input = cx.new_view(TextInput::new);
+                cx.observe(&input, |_, _, cx| cx.notify()).detach();
+                (*key, input)
+            })
+            .collect();
+
+        // Create env profile inputs for the settings dialog
+        let env_profile_name_input = cx.new_view(TextInput::new);
+        cx.observe(&env_profile_name_input, |_, _, cx| cx.notify()).detach();
+        let env_profile_vars_input = cx.new_view(|cx| {
+            let mut input = TextInput::new(cx);
+            input.set_placeholder("HTTPS_PROXY=http://proxy:8080; PATH+=/opt/bin");
+            input
+        });
+
+        // Create proxy inputs for the settings dialog
+        let proxy_inputs = ["http://proxy:8080", "http://proxy:8080", "localhost,.internal"].map(|placeholder| {
+            cx.new_view(|cx| {
+                let mut input = TextInput::new(cx);
+                input.set_placeholder(placeholder);
+                input
+            })
+        });
+
+        // Create container inputs for the settings dialog
+        let container_inputs = [cocowork_core::sandbox::DEFAULT_IMAGE, "/host/cache:/cache:ro"].map(|placeholder| {
+            cx.new_view(|cx| {
+                let mut input = TextInput::new(cx);
+                input.set_placeholder(placeholder);
+                input
+            })
+        });
+
+        // Create command limit inputs for the settings dialog
+        let limit_inputs = [(); 4].map(|_| {
+            cx.new_view(|cx| {
+                let mut input = TextInput::new(cx);
+                input.set_placeholder(t("settings.limits.none"));
+                input
+            })
+        });
+
+        // Create webhook inputs for the settings dialog
+        let webhook_inputs = [cx.new_view(TextInput::new), cx.new_view(TextInput::new)];
+        cx.observe(&webhook_inputs[0], |_, _, cx| cx.notify()).detach();
+
+        // Create the secret pattern input for the settings dialog
+        let redaction_pattern_input = cx.new_view(|cx| {
+            let mut input = TextInput::new(cx);
+            input.set_placeholder(t("settings.redaction.pattern_placeholder"));
+            input
+        });
+        cx.subscribe(&redaction_pattern_input, |this, _, event: &TextInputEvent, cx| match event {
+            TextInputEvent::Submit => this.add_redaction_pattern(cx),
+        })
+        .detach();
+
+        // Create prompt template inputs for the settings dialog
+        let template_name_input = cx.new_view(TextInput::new);
+        cx.observe(&template_name_input, |_, _, cx| cx.notify()).detach();
+        let template_body_input = cx.new_view(|cx| {
+            let mut input = TextInput::new(cx);
+            input.set_placeholder("Review {file} for bugs. Focus on: {selection}");
+            input
+        });
+
+        // Create the workspace instructions editor, filled from storage
+        let instructions = acp.manager.workspace_instructions.clone();
+        let instructions_input = cx.new_view(|cx| {
+            let mut input = TextInput::new(cx);
+            input.set_content(instructions, cx);
+            input
+        });
+        cx.observe(&instructions_input, |this, _, cx| {
+            this.instructions_status = None;
+            cx.notify();
+        })
+        .detach();
+
+        // Create the workspace test command editor, filled from its settings
+        let test_command = acp.manager.test_config().map(|config| config.command).unwrap_or_default();
+        let test_command_input = cx.new_view(|cx| {
+            let mut input = TextInput::new(cx);
+            input.set_content(test_command, cx);
+            input
+        });
+        cx.observe(&test_command_input, |this, _, cx| {
+            this.test_command_status = None;
+            cx.notify();
+        })
+        .detach();
+
+        // Create the workspace build and lint commands editor
+        let check_commands = acp.manager.check_config().map(|config| config.commands.join("\n")).unwrap_or_default();
+        let check_commands_input = cx.new_view(|cx| {
+            let mut input = TextInput::new(cx);
+            input.set_multi_line(true);
+            input.set_content(check_commands, cx);
+            input
+        });
+        cx.observe(&check_commands_input, |this, _, cx| {
+            this.check_commands_status = None;
+            cx.notify();
+        })
+        .detach();
+        let diagnostics_file =
+            acp.manager.check_config().and_then(|config| config.diagnostics_file).unwrap_or_default();
+        let diagnostics_file_input = cx.new_view(|cx| {
+            let mut input = TextInput::new(cx);
+            input.set_content(diagnostics_file, cx);
+            input
+        });
+        cx.observe(&diagnostics_file_input, |this, _, cx| {
+            this.check_commands_status = None;
+            cx.notify();
+        })
+        .detach();
+
+        // Create the new thread dialog's override inputs
+        let new_thread_env_input = cx.new_view(|cx| {
+            let mut input = TextInput::new(cx);
+            input.set_placeholder("API_BASE=http://localhost:8080; PATH+=/opt/bin");
+            input
+        });
+        let new_thread_mode_input = cx.new_view(TextInput::new);
+        let new_thread_model_input = cx.new_view(TextInput::new);
+        let new_thread_issue_input = cx.new_view(|cx| {
+            let mut input = TextInput::new(cx);
+            input.set_placeholder(t("new_thread.issue_placeholder"));
+            input
+        });
+        cx.subscribe(&new_thread_issue_input, |this, _, event: &TextInputEvent, cx| match event {
+            TextInputEvent::Submit => this.import_issue(cx),
+        })
+        .detach();
+        let redact_term_input = cx.new_view(|cx| {
+            let mut input = TextInput::new(cx);
+            input.set_placeholder(t("redact.term_placeholder"));
+            input
+        });
+        cx.subscribe(&redact_term_input, |this, _, event: &TextInputEvent, cx| match event {
+            TextInputEvent::Submit => this.add_redact_term(cx),
+        })
+        .detach();
+
+        // Create the batch prompt input
+        let batch_prompt_input = cx.new_view(TextInput::new);
+        cx.observe(&batch_prompt_input, |_, _, cx| cx.notify()).detach();
+
+        // Create the comparison prompt input
+        let compare_prompt_input = cx.new_view(TextInput::new);
+        cx.observe(&compare_prompt_input, |_, _, cx| cx.notify()).detach();
+
+        // Create the project search input
+        let project_search_input = cx.new_view(TextInput::new);
+
+        // Create the audit log search input
+        let audit_search_input = cx.new_view(TextInput::new);
+
+        // Create the prompt history search input; a new query highlights the best match
+        let history_search_input = cx.new_view(TextInput::new);
+        cx.observe(&history_search_input, |this, _, cx| {
+            if let Some(search) = &mut this.history_search {
+                search.selected = 0;
+            }
+            cx.notify();
+        })
+        .detach();
+
         // Spawn a timer to poll for ACP updates
         cx.spawn(|view, mut cx| async move {
             loop {
@@ -149,10 +978,20 @@ impl CocoWorkWindow {
                 // Poll and process updates
                 let _ = view.update(&mut cx, |this, cx| {
                     let current_len = this.timeline_len();
-                    let near_bottom = this.is_near_bottom(current_len);
+                    let near_bottom = this.is_near_bottom(this.rendered_timeline_len);
                     this.stick_to_bottom = near_bottom;
 
                     this.acp.poll_and_process_updates();
+                    if let Some(draft) = this.acp.take_commit_draft() {
+                        this.commit_message_input.update(cx, |input, cx| input.set_content(draft, cx));
+                    }
+                    if let Some(draft) = this.acp.take_pull_request_draft() {
+                        this.pull_request_title_input.update(cx, |input, cx| input.set_content(draft.title, cx));
+                        this.pull_request_body_input.update(cx, |input, cx| input.set_content(draft.body, cx));
+                    }
+                    if std::mem::take(&mut this.acp.appearance_changed) {
+                        this.apply_saved_appearance(cx);
+                    }
                     // Sync thread list in case async operations completed
                     this.sync_thread_list();
 
@@ -160,7 +999,7 @@ impl CocoWorkWindow {
                     let has_new_content = new_len > current_len;
                     let streaming = this.acp.is_loading();
                     if this.stick_to_bottom && new_len > 0 && (has_new_content || streaming) {
-                        this.scroll_to_bottom_if_needed(new_len);
+                        this.scroll_to_bottom_pending = true;
                     }
                     this.last_timeline_len = new_len;
                     cx.notify();
@@ -169,23 +1008,29 @@ impl CocoWorkWindow {
         })
         .detach();
 
-        Self {
+        let mut this = Self {
             theme,
             acp,
             message_input,
+            question_input,
+            terminal_inputs: std::collections::HashMap::new(),
+            show_terminal_panel: false,
+            terminal_panel_input,
+            quote_selection: None,
+            terminal_panel_error: None,
             search_input,
             threads,
             active_thread_idx: None,
-            expanded_sections: vec!["Progress".to_string()],
+            expanded_sections: saved_layout.expanded_sections,
             focus_handle,
-            sidebar_width: layout::SIDEBAR_WIDTH,
+            sidebar_width: saved_layout.sidebar_width,
             resizing_sidebar: false,
             sidebar_resize_start_x: 0.0,
-            sidebar_resize_start_width: layout::SIDEBAR_WIDTH,
-            context_panel_width: layout::CONTEXT_PANEL_WIDTH,
+            sidebar_resize_start_width: saved_layout.sidebar_width,
+            context_panel_width: saved_layout.context_panel_width,
             resizing_context_panel: false,
             context_panel_resize_start_x: 0.0,
-            context_panel_resize_start_width: layout::CONTEXT_PANEL_WIDTH,
+            context_panel_resize_start_width: saved_layout.context_panel_width,
             search_text: String::new(),
             show_agent_menu: false,
             show_mode_menu: false,
@@ -204,31 +1049,295 @@ impl CocoWorkWindow {
                     enabled: false,
                 },
             ],
-            collapsed_thinking: std::collections::HashSet::new(),
+            collapsed_thinking: std::collections::HashMap::new(),
+            expanded_summaries: std::collections::HashSet::new(),
+            open_action_groups: std::collections::HashMap::new(),
+            collapsed_date_groups: std::collections::HashSet::new(),
+            plan_scroll_target: None,
             message_scroll_handle: ScrollHandle::new(),
             stick_to_bottom: true,
             last_timeline_len: 0,
-            message_markdown_cache: std::collections::HashMap::new(),
+            rendered_timeline_len: 0,
+            scroll_to_bottom_pending: false,
+            jump_target: None,
+            show_debug_overlay: false,
+            frame_times: FrameTimes::new(),
+            message_markdown_cache: MarkdownCache::new(markdown_cache_budget),
             show_new_thread_dialog: false,
             show_user_menu: false,
-        }
+            show_thread_menu: false,
+            show_outline_menu: false,
+            review_mode: false,
+            review_flag: None,
+            subtask_seed: None,
+            show_new_thread_advanced: false,
+            new_thread_mcp: std::collections::HashSet::new(),
+            new_thread_dir: None,
+            new_thread_env_input,
+            new_thread_mode_input,
+            new_thread_model_input,
+            new_thread_issue_input,
+            new_thread_error: None,
+            new_thread_isolate: false,
+            isolation_review: None,
+            isolation_status: None,
+            commit_review: None,
+            commit_message_input,
+            commit_error: None,
+            pull_request_wizard: None,
+            pull_request_has_token: false,
+            pull_request_unknown_host: None,
+            pull_request_token_input,
+            pull_request_title_input,
+            pull_request_body_input,
+            pull_request_error: None,
+            export_status: None,
+            export_review: None,
+            export_redaction: Redaction::default(),
+            redact_term_input,
+            share_error: None,
+            crash_report_pending: cocowork_core::diagnostics::pending_crash_report(&logs_dir()).is_some(),
+            bundle_status: None,
+            read_only_dismissed: false,
+            link_error: None,
+            show_workspace_warning: false,
+            show_attachment_warning: false,
+            attachment_choice: None,
+            deselected_actions: std::collections::HashSet::new(),
+            show_install_dialog: false,
+            show_settings_dialog: false,
+            secret_inputs,
+            secret_error: None,
+            env_profile_name_input,
+            env_profile_vars_input,
+            env_profile_error: None,
+            proxy_inputs,
+            proxy_status: None,
+            container_inputs,
+            container_runtime: ContainerRuntime::default(),
+            container_network: false,
+            container_status: None,
+            limit_inputs,
+            limits_status: None,
+            webhook_inputs,
+            webhook_events: WebhookEvent::ALL.to_vec(),
+            webhook_error: None,
+            redaction_pattern_input,
+            redaction_pattern_error: None,
+            policy_scope_idx: 0,
+            policy_error: None,
+            show_template_menu: false,
+            template_name_input,
+            template_body_input,
+            template_error: None,
+            instructions_input,
+            instructions_status: None,
+            test_command_input,
+            test_command_status: None,
+            check_commands_input,
+            diagnostics_file_input,
+            check_commands_status: None,
+            show_batch_dialog: false,
+            show_capabilities: false,
+            batch_prompt_input,
+            batch_workspaces: Vec::new(),
+            batch_concurrency: 1,
+            batch_error: None,
+            show_compare_dialog: false,
+            compare_prompt_input,
+            compare_agents: [None, None],
+            compare_error: None,
+            show_dashboard: false,
+            dashboard_days: 7,
+            show_search: false,
+            project_search_input,
+            search_regex: false,
+            search_case_sensitive: false,
+            search_error: None,
+            search_preview: None,
+            show_audit: false,
+            show_task_board: false,
+            audit_filter: AuditFilter::default(),
+            audit_search_input,
+            audit_entries: Vec::new(),
+            audit_total: 0,
+            audit_status: None,
+            preview_lines: None,
+            history_search: None,
+            history_search_input,
+            recalled_prompt: None,
+            threads_focus: cx.focus_handle(),
+            highlighted_thread: None,
+            context_panel_focus: cx.focus_handle(),
+            highlighted_section: None,
+            sidebar_collapsed: saved_layout.sidebar_collapsed,
+            context_panel_collapsed: saved_layout.context_panel_collapsed,
+            window_geometry: saved_layout.window,
+            layout_save: None,
+        };
+        this.apply_placeholders(cx);
+        this
     }
 
     // ========================================================================
     // Event Handlers
     // ========================================================================
 
+    /// Push settings that affect the message input into it
+    fn apply_input_settings(&mut self, cx: &mut ViewContext<Self>) {
+        let vim_mode = self.acp.manager.vim_mode;
+        self.message_input.update(cx, |input, cx| input.set_vim_mode(vim_mode, cx));
+    }
+
+    /// Fill the inputs' placeholders in the current language
+    fn apply_placeholders(&mut self, cx: &mut ViewContext<Self>) {
+        let placeholders = [
+            (&self.message_input, "input.placeholder"),
+            (&self.search_input, "sidebar.search_placeholder"),
+            (&self.env_profile_name_input, "settings.env.name_placeholder"),
+            (&self.webhook_inputs[0], "settings.webhooks.url_placeholder"),
+            (&self.webhook_inputs[1], "settings.webhooks.secret_placeholder"),
+            (&self.redaction_pattern_input, "settings.redaction.pattern_placeholder"),
+            (&self.template_name_input, "settings.templates.name_placeholder"),
+            (&self.instructions_input, "context.instructions_placeholder"),
+            (&self.test_command_input, "tests.command_placeholder"),
+            (&self.check_commands_input, "problems.commands_placeholder"),
+            (&self.diagnostics_file_input, "problems.diagnostics_file_placeholder"),
+            (&self.new_thread_mode_input, "new_thread.agent_default"),
+            (&self.new_thread_model_input, "new_thread.agent_default"),
+            (&self.new_thread_issue_input, "new_thread.issue_placeholder"),
+            (&self.batch_prompt_input, "batch.prompt_placeholder"),
+            (&self.compare_prompt_input, "compare.prompt_placeholder"),
+            (&self.project_search_input, "search.placeholder"),
+            (&self.audit_search_input, "audit.placeholder"),
+            (&self.history_search_input, "history.placeholder"),
+            (&self.commit_message_input, "commit.placeholder"),
+            (&self.pull_request_token_input, "pr.token_placeholder"),
+            (&self.pull_request_title_input, "pr.title_placeholder"),
+            (&self.pull_request_body_input, "pr.body_placeholder"),
+            (&self.redact_term_input, "redact.term_placeholder"),
+        ];
+        for (input, key) in placeholders {
+            input.update(cx, |input, _| input.set_placeholder(t(key)));
+        }
+        for (key, input) in &self.secret_inputs {
+            let placeholder = t_args("settings.api_keys.placeholder", &[("label", &key.label())]);
+            input.update(cx, |input, _| input.set_placeholder(placeholder));
+        }
+    }
+
+    /// Show the UI in another language and remember the choice
+    fn set_language(&mut self, language: Language, cx: &mut ViewContext<Self>) {
+        if let Err(e) = self.acp.manager.set_language(language) {
+            tracing::warn!("Failed to save language: {}", e);
+        }
+        i18n::set_language(language);
+        self.apply_placeholders(cx);
+        cx.notify();
+    }
+
+    /// The theme the window is drawn with, for popovers opened from it
+    pub(super) fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// Whether the clipboard capture shortcut is turned on
+    pub(super) fn clipboard_capture_enabled(&self) -> bool {
+        self.acp.manager.clipboard_capture
+    }
+
+    /// The agent and workspace a quick prompt starts its thread with
+    pub(super) fn quick_prompt_target(&self) -> String {
+        let agent = self
+            .acp
+            .manager
+            .selected_agent_config()
+            .map(|config| config.name)
+            .unwrap_or_else(|| t("quick_prompt.no_agent").to_string());
+        let workspace = self.acp.manager.get_working_dir();
+        let workspace = workspace.file_name().map_or_else(
+            || workspace.display().to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+        t_args("quick_prompt.destination", &[("agent", &agent), ("workspace", &workspace)])
+    }
+
+    /// Send `text` from the quick prompt in a new thread, which becomes the open one
+    pub(super) fn send_quick_prompt(&mut self, text: String, cx: &mut ViewContext<Self>) {
+        tracing::info!("Sending quick prompt: {}", text);
+        self.acp.active_session_id = None;
+        self.acp.start_send_message(text);
+        self.sync_thread_list();
+        cx.notify();
+    }
+
+    /// Pack the timeline and sidebars at `density`, and remember it
+    fn set_density(&mut self, density: Density, cx: &mut ViewContext<Self>) {
+        if let Err(e) = self.acp.manager.set_density(density) {
+            tracing::warn!("Failed to save density: {}", e);
+        }
+        theme::set_density(density);
+        self.theme = self.theme.clone().with_density(density);
+        self.theme_changed(cx);
+    }
+
+    /// Send the input, or while an @-mention is being typed, pick its top suggestion
+    fn submit_message_input(&mut self, cx: &mut ViewContext<Self>) {
+        let mention = self
+            .mention_query(cx)
+            .and_then(|query| self.acp.manager.search_workspace_files(&query, 1).pop());
+        match mention {
+            Some(found) => self.insert_mention(&found.path, cx),
+            None => self.handle_send_message(cx),
+        }
+    }
+
     fn handle_send_message(&mut self, cx: &mut ViewContext<Self>) {
+        let attachment_choice = self.attachment_choice.take();
         // Get content from the TextInput entity
         let text = self.message_input.read(cx).content().to_string();
         if text.trim().is_empty() {
             return;
         }
 
+        // `/template-name` expands into the input for review instead of sending
+        if let Some((template, rest)) = self.acp.manager.match_template_command(&text) {
+            let template = template.clone();
+            let rest = rest.to_string();
+            self.insert_prompt_template(&template, &rest, cx);
+            return;
+        }
+
+        // The thread's agent can't reach its files from another workspace;
+        // ask where to go on instead of sending
+        if self.acp.workspace_mismatch().is_some() {
+            self.show_workspace_warning = true;
+            cx.notify();
+            return;
+        }
+        self.show_workspace_warning = false;
+
+        // Too much to embed; ask whether to cut the files down or link them
+        let files: Vec<PathBuf> = self.attached_files.iter().map(PathBuf::from).collect();
+        let attachment_mode = match attachment_choice {
+            Some(mode) => mode,
+            None => {
+                let estimate = self.prompt_size_estimate(&text);
+                if self.acp.manager.attachments_over_limit(&estimate) {
+                    self.show_attachment_warning = true;
+                    cx.notify();
+                    return;
+                }
+                AttachmentMode::Embed
+            }
+        };
+        self.show_attachment_warning = false;
+
         // Clear the input
         self.message_input.update(cx, |input, cx| {
             input.clear(cx);
         });
+        self.recalled_prompt = None;
 
         tracing::info!("Sending message: {}", text);
 
@@ -238,7 +1347,8 @@ impl CocoWorkWindow {
         // 2. If not connected: queue message and start connection
         // 3. When connected: start thread creation
         // 4. When thread ready: send the queued message
-        self.acp.start_send_message(text);
+        self.attached_files.clear();
+        self.acp.start_send_message_with_files(text, files, attachment_mode);
 
         // Update UI thread list if we have a new active thread
         self.sync_thread_list();
@@ -248,6 +1358,18 @@ impl CocoWorkWindow {
 
     /// Sync the thread list with the ACP manager state
     fn sync_thread_list(&mut self) {
+        // Compacted and forked threads continue in a fresh session
+        for (old_id, new_id) in self.acp.session_renames.drain(..) {
+            for thread in self.threads.iter_mut() {
+                if thread.id == old_id {
+                    thread.id = new_id.clone();
+                }
+                if thread.parent_id.as_ref() == Some(&old_id) {
+                    thread.parent_id = Some(new_id.clone());
+                }
+            }
+        }
+
         // Check if there's a new active thread we need to add to UI
         if let Some(thread_id) = &self.acp.active_session_id {
             // Check if this thread is already in our list
@@ -256,7 +1378,14 @@ impl CocoWorkWindow {
                 // Add the new thread to the UI list
                 let agent_id = self.acp.manager.selected_agent_id.clone().unwrap_or_default();
                 let thread_name = "New thread".to_string();
-                let new_thread = ThreadEntry::new(thread_id, &thread_name, &agent_id, 0);
+                let working_dir = self
+                    .acp
+                    .active_session()
+                    .map(|session| session.working_dir.clone())
+                    .unwrap_or_else(|| self.acp.get_working_dir());
+                let mut new_thread = ThreadEntry::new(thread_id, &thread_name, &agent_id, working_dir, 0);
+                new_thread.parent_id = self.acp.active_session().and_then(|session| session.parent_session_id.clone());
+                new_thread.forked = self.acp.active_session().is_some_and(|session| session.forked_at.is_some());
 
                 self.threads.insert(0, new_thread);
                 self.active_thread_idx = Some(0);
@@ -275,6 +1404,12 @@ impl CocoWorkWindow {
                 }
             }
         }
+
+        for thread in &mut self.threads {
+            if let Some(last_activity) = self.acp.manager.get_session(&thread.id).and_then(|s| s.last_activity()) {
+                thread.last_activity = last_activity;
+            }
+        }
     }
 
     fn timeline_len(&self) -> usize {
@@ -308,14 +1443,6 @@ impl CocoWorkWindow {
         distance <= bottom_pad + px(8.0)
     }
 
-    fn scroll_to_bottom_if_needed(&self, item_count: usize) {
-        if item_count == 0 {
-            return;
-        }
-
-        self.message_scroll_handle.scroll_to_item(item_count - 1);
-    }
-
     fn select_thread(&mut self, idx: usize, cx: &mut ViewContext<Self>) {
         if idx < self.threads.len() {
             // Deselect previous
@@ -327,15 +1454,24 @@ impl CocoWorkWindow {
             // Select new
             self.threads[idx].is_active = true;
             self.active_thread_idx = Some(idx);
+            self.show_workspace_warning = false;
 
             // Update the ACP model's active session to match
+            self.show_dashboard = false;
+            self.show_search = false;
+            self.show_audit = false;
+            self.show_task_board = false;
             let session_id = self.threads[idx].id.clone();
             self.acp.active_session_id = Some(session_id.clone());
             tracing::info!("Switched to thread: {}", session_id);
-            self.message_markdown_cache.clear();
             self.collapsed_thinking.clear();
+            self.expanded_summaries.clear();
+            self.open_action_groups.clear();
+            self.review_mode = false;
+            self.review_flag = None;
             self.stick_to_bottom = true;
             self.last_timeline_len = 0;
+            self.rendered_timeline_len = 0;
             self.message_scroll_handle
                 .set_offset(point(px(0.0), px(0.0)));
 
@@ -349,54 +1485,652 @@ impl CocoWorkWindow {
         } else {
             self.expanded_sections.push(section.to_string());
         }
+        self.save_layout_soon(cx);
         cx.notify();
     }
 
-    fn close_menus(&mut self, cx: &mut ViewContext<Self>) {
-        if self.show_agent_menu || self.show_mode_menu || self.show_new_thread_dialog || self.show_user_menu {
-            self.show_agent_menu = false;
-            self.show_mode_menu = false;
-            self.show_new_thread_dialog = false;
-            self.show_user_menu = false;
-            cx.notify();
+    /// Draw the UI at `scale`, 1.0 being 100%, and remember it
+    fn set_zoom(&mut self, scale: f32, cx: &mut ViewContext<Self>) {
+        let scale = clamp_ui_scale(scale);
+        if let Err(e) = self.acp.manager.set_ui_scale(scale) {
+            tracing::warn!("Failed to save UI scale: {}", e);
         }
+        set_ui_scale(scale);
+        self.theme = self.theme.clone().with_scale(scale);
+        self.theme_changed(cx);
     }
 
-    fn toggle_user_menu(&mut self, cx: &mut ViewContext<Self>) {
-        self.show_user_menu = !self.show_user_menu;
-        self.show_agent_menu = false;
-        self.show_mode_menu = false;
+    /// Redraw at the scale, density and language in settings, after another
+    /// instance or the CLI changed them
+    fn apply_saved_appearance(&mut self, cx: &mut ViewContext<Self>) {
+        let manager = &self.acp.manager;
+        let (scale, density, language) = (manager.ui_scale, manager.density, manager.language);
+        set_ui_scale(scale);
+        theme::set_density(density);
+        i18n::set_language(language);
+        self.theme = self.theme.clone().with_scale(scale).with_density(density);
+        self.apply_placeholders(cx);
+        self.theme_changed(cx);
+    }
+
+    /// Redraw after `self.theme` changed
+    fn theme_changed(&mut self, cx: &mut ViewContext<Self>) {
+        // Rendered markdown keeps the colors and text size it was laid out with
+        self.message_markdown_cache.invalidate();
         cx.notify();
     }
 
-    fn select_workspace(&mut self, cx: &mut ViewContext<Self>) {
+    /// Keep at most `budget` of rendered markdown, and remember it
+    fn set_markdown_cache_budget(&mut self, budget: MarkdownCacheBudget, cx: &mut ViewContext<Self>) {
+        if let Err(e) = self.acp.manager.set_markdown_cache_budget(budget) {
+            tracing::warn!("Failed to save markdown cache budget: {}", e);
+        }
+        self.message_markdown_cache.set_budget(budget);
+        cx.notify();
+    }
+
+    /// Whether a dialog covers the window, so Tab shouldn't move focus behind it
+    fn is_modal_open(&self) -> bool {
+        self.show_new_thread_dialog
+            || self.show_batch_dialog
+            || self.show_capabilities
+            || self.show_compare_dialog
+            || self.show_settings_dialog
+            || self.show_install_dialog
+            || self.isolation_review.is_some()
+            || self.commit_review.is_some()
+            || self.pull_request_wizard.is_some()
+            || self.export_review.is_some()
+            || self.acp.manager.ready_change_set().is_some()
+            || self.acp.manager.auth_flow.is_some()
+            || self.acp.manager.file_preview.is_some()
+            || self.acp.manager.current_confirmation().is_some()
+            || self.acp.manager.current_permission_request().is_some()
+    }
+
+    /// The region holding keyboard focus, if any
+    fn focused_region(&self, cx: &ViewContext<Self>) -> Option<FocusRegion> {
+        if self.search_input.read(cx).focus_handle(cx).is_focused(cx) {
+            Some(FocusRegion::ThreadSearch)
+        } else if self.threads_focus.is_focused(cx) {
+            Some(FocusRegion::Threads)
+        } else if self.message_input.read(cx).focus_handle(cx).is_focused(cx) {
+            Some(FocusRegion::Input)
+        } else if self.context_panel_focus.contains_focused(cx) {
+            Some(FocusRegion::ContextPanel)
+        } else {
+            None
+        }
+    }
+
+    fn focus_region(&mut self, region: FocusRegion, cx: &mut ViewContext<Self>) {
+        let focus = match region {
+            FocusRegion::ThreadSearch => self.search_input.read(cx).focus_handle(cx),
+            FocusRegion::Threads => {
+                // Start from the open thread
+                if self.highlighted_thread.is_none() {
+                    self.highlighted_thread = self
+                        .visible_threads()
+                        .iter()
+                        .position(|idx| Some(*idx) == self.active_thread_idx)
+                        .or(Some(0));
+                }
+                self.threads_focus.clone()
+            }
+            FocusRegion::Input => self.message_input.read(cx).focus_handle(cx),
+            FocusRegion::ContextPanel => {
+                self.highlighted_section.get_or_insert(0);
+                self.context_panel_focus.clone()
+            }
+        };
+        cx.focus(&focus);
+        cx.notify();
+    }
+
+    /// Whether a region's panel is open; the input always is
+    fn is_region_shown(&self, region: FocusRegion) -> bool {
+        match region {
+            FocusRegion::ThreadSearch | FocusRegion::Threads => !self.sidebar_collapsed,
+            FocusRegion::Input => true,
+            FocusRegion::ContextPanel => !self.context_panel_collapsed,
+        }
+    }
+
+    fn focus_next_region(&mut self, _: &FocusNextRegion, cx: &mut ViewContext<Self>) {
+        if self.is_modal_open() {
+            return;
+        }
+        let mut region = self.focused_region(cx).map_or(FocusRegion::ThreadSearch, FocusRegion::next);
+        while !self.is_region_shown(region) {
+            region = region.next();
+        }
+        self.focus_region(region, cx);
+    }
+
+    fn focus_previous_region(&mut self, _: &FocusPreviousRegion, cx: &mut ViewContext<Self>) {
+        if self.is_modal_open() {
+            return;
+        }
+        let mut region = self.focused_region(cx).map_or(FocusRegion::ContextPanel, FocusRegion::previous);
+        while !self.is_region_shown(region) {
+            region = region.previous();
+        }
+        self.focus_region(region, cx);
+    }
+
+    fn toggle_sidebar(&mut self, _: &ToggleSidebar, cx: &mut ViewContext<Self>) {
+        self.sidebar_collapsed = !self.sidebar_collapsed;
+        self.after_panel_toggle(cx);
+    }
+
+    fn toggle_context_panel(&mut self, _: &ToggleContextPanel, cx: &mut ViewContext<Self>) {
+        self.context_panel_collapsed = !self.context_panel_collapsed;
+        self.after_panel_toggle(cx);
+    }
+
+    fn toggle_terminal_panel(&mut self, _: &ToggleTerminalPanel, cx: &mut ViewContext<Self>) {
+        self.show_terminal_panel = !self.show_terminal_panel;
+        if self.show_terminal_panel {
+            self.open_terminal_panel(cx);
+        }
+        cx.notify();
+    }
+
+    /// Open the user's shell in the workspace, or keep the one already running there
+    fn open_terminal_panel(&mut self, cx: &mut ViewContext<Self>) {
+        self.terminal_panel_error = self.acp.manager.open_user_terminal().err();
+        let focus = self.terminal_panel_input.read(cx).focus_handle(cx);
+        cx.focus(&focus);
+    }
+
+    fn toggle_debug_overlay(&mut self, _: &ToggleDebugOverlay, cx: &mut ViewContext<Self>) {
+        self.show_debug_overlay = !self.show_debug_overlay;
+        self.frame_times = FrameTimes::new();
+        cx.notify();
+    }
+
+    /// Keep focus out of a panel that was just collapsed, and remember the layout
+    fn after_panel_toggle(&mut self, cx: &mut ViewContext<Self>) {
+        if self.focused_region(cx).is_some_and(|region| !self.is_region_shown(region)) {
+            self.focus_region(FocusRegion::Input, cx);
+        }
+        self.save_layout_soon(cx);
+        cx.notify();
+    }
+
+    /// Arrow keys move through the shown threads; Enter or Space opens one
+    fn handle_threads_key(&mut self, event: &KeyDownEvent, cx: &mut ViewContext<Self>) {
+        let Some(key) = ListKey::from_key(&event.keystroke.key) else {
+            return;
+        };
+        let visible = self.visible_threads();
+        self.highlighted_thread = move_highlight(self.highlighted_thread, key, visible.len());
+        if key == ListKey::Activate {
+            if let Some(&idx) = self.highlighted_thread.and_then(|pos| visible.get(pos)) {
+                self.select_thread(idx, cx);
+            }
+        }
+        cx.stop_propagation();
+        cx.notify();
+    }
+
+    /// Arrow keys move through the context panel's sections; Enter or Space
+    /// expands or collapses one
+    fn handle_context_panel_key(&mut self, event: &KeyDownEvent, cx: &mut ViewContext<Self>) {
+        // Keys typed into the instructions editor inside the panel aren't ours
+        if !self.context_panel_focus.is_focused(cx) {
+            return;
+        }
+        let Some(key) = ListKey::from_key(&event.keystroke.key) else {
+            return;
+        };
+        self.highlighted_section = move_highlight(self.highlighted_section, key, CONTEXT_SECTIONS.len());
+        if key == ListKey::Activate {
+            if let Some(section) = self.highlighted_section.map(|idx| CONTEXT_SECTIONS[idx]) {
+                self.toggle_section(section, cx);
+            }
+        }
+        cx.stop_propagation();
+        cx.notify();
+    }
+
+    /// Whether `section`'s header carries the keyboard highlight
+    fn is_section_highlighted(&self, section: &str, cx: &ViewContext<Self>) -> bool {
+        self.context_panel_focus.is_focused(cx)
+            && self.highlighted_section.map(|idx| CONTEXT_SECTIONS[idx]) == Some(section)
+    }
+
+    /// Indices into `threads` of the threads shown, in list order: those
+    /// matching the search, leaving out collapsed date groups unless searching
+    fn visible_threads(&self) -> Vec<usize> {
+        self.thread_groups()
+            .into_iter()
+            .filter(|(group, _)| !self.search_text.is_empty() || !self.collapsed_date_groups.contains(group))
+            .flat_map(|(_, threads)| threads)
+            .collect()
+    }
+
+    /// Threads matching the search by date group, most recently active first
+    fn thread_groups(&self) -> Vec<(DateGroup, Vec<usize>)> {
+        let search_query = self.search_text.to_lowercase();
+        let mut matching: Vec<usize> = self
+            .threads
+            .iter()
+            .enumerate()
+            .filter(|(_, thread)| {
+                search_query.is_empty()
+                    || thread.name.to_lowercase().contains(&search_query)
+                    || thread.agent_id.to_lowercase().contains(&search_query)
+            })
+            .map(|(idx, _)| idx)
+            .collect();
+        matching.sort_by_key(|&idx| std::cmp::Reverse(self.threads[idx].last_activity));
+
+        // Sub-threads are listed under the thread they were spawned from, in its group
+        let shown_parent = |idx: usize| {
+            self.threads[idx]
+                .parent_id
+                .as_ref()
+                .is_some_and(|parent| matching.iter().any(|&other| &self.threads[other].id == parent))
+        };
+        let now = chrono::Utc::now();
+        let mut groups: Vec<(DateGroup, Vec<usize>)> = Vec::new();
+        for &idx in matching.iter().filter(|&&idx| !shown_parent(idx)) {
+            let group = DateGroup::of(self.threads[idx].last_activity, now);
+            if groups.last().map(|(last, _)| *last) != Some(group) {
+                groups.push((group, Vec::new()));
+            }
+            if let Some((_, ordered)) = groups.last_mut() {
+                self.push_thread_with_children(idx, &matching, ordered);
+            }
+        }
+        groups
+    }
+
+    fn push_thread_with_children(&self, idx: usize, matching: &[usize], ordered: &mut Vec<usize>) {
+        ordered.push(idx);
+        let id = &self.threads[idx].id;
+        for &child in matching {
+            if self.threads[child].parent_id.as_ref() == Some(id) && !ordered.contains(&child) {
+                self.push_thread_with_children(child, matching, ordered);
+            }
+        }
+    }
+
+    /// How many sub-thread levels down the thread at `idx` is
+    fn thread_depth(&self, idx: usize) -> usize {
+        let mut depth = 0;
+        let mut parent = self.threads[idx].parent_id.as_ref();
+        while let Some(thread) = parent.and_then(|id| self.threads.iter().find(|thread| &thread.id == id)) {
+            depth += 1;
+            if depth >= self.threads.len() {
+                break;
+            }
+            parent = thread.parent_id.as_ref();
+        }
+        depth
+    }
+
+    fn close_menus(&mut self, cx: &mut ViewContext<Self>) {
+        if self.show_agent_menu
+            || self.show_mode_menu
+            || self.show_new_thread_dialog
+            || self.show_user_menu
+            || self.show_template_menu
+            || self.show_thread_menu
+            || self.show_outline_menu
+        {
+            self.show_agent_menu = false;
+            self.show_mode_menu = false;
+            self.show_new_thread_dialog = false;
+            self.show_user_menu = false;
+            self.show_template_menu = false;
+            self.show_thread_menu = false;
+            self.show_outline_menu = false;
+            cx.notify();
+        }
+    }
+
+    /// Scroll the timeline to a message or tool call
+    fn jump_to(&mut self, anchor: TimelineAnchor, cx: &mut ViewContext<Self>) {
+        self.show_outline_menu = false;
+        self.stick_to_bottom = false;
+        self.jump_target = Some(anchor);
+        cx.notify();
+    }
+
+    /// Scroll to the end of the timeline and keep following new output
+    fn jump_to_bottom(&mut self, cx: &mut ViewContext<Self>) {
+        self.show_outline_menu = false;
+        self.stick_to_bottom = true;
+        self.scroll_to_bottom_pending = true;
+        cx.notify();
+    }
+
+    fn toggle_thread_menu(&mut self, cx: &mut ViewContext<Self>) {
+        self.show_thread_menu = !self.show_thread_menu;
+        self.show_user_menu = false;
+        cx.notify();
+    }
+
+    /// Check the active thread for sensitive strings before it's saved as a
+    /// Markdown or HTML document; every one found starts out hidden
+    fn export_thread(&mut self, format: ExportFormat, cx: &mut ViewContext<Self>) {
+        self.show_thread_menu = false;
+        let title = self
+            .active_thread_idx
+            .and_then(|idx| self.threads.get(idx))
+            .map(|thread| thread.name.clone())
+            .unwrap_or_else(|| "Thread".to_string());
+        let Some(export) = self.acp.export_active_thread(&title) else {
+            self.export_status = Some(Err(t("banner.no_thread_to_export").to_string()));
+            cx.notify();
+            return;
+        };
+        let findings = find_sensitive(&export);
+        self.export_review = Some((format, export, findings));
+        self.export_redaction = Redaction::all();
+        self.redact_term_input.update(cx, |input, cx| input.clear(cx));
+        cx.notify();
+    }
+
+    /// The reviewed export with what the user picked hidden; the thread itself is left as it is
+    fn redacted_export(&self) -> Option<(ExportFormat, ThreadExport)> {
+        let (format, export, findings) = self.export_review.as_ref()?;
+        Some((*format, self.export_redaction.apply(export, findings)))
+    }
+
+    /// Copy the redacted document, to paste wherever it's being shared
+    fn copy_redacted_export(&mut self, cx: &mut ViewContext<Self>) {
+        if let Some((format, export)) = self.redacted_export() {
+            cx.write_to_clipboard(ClipboardItem::new_string(export.render(format)));
+        }
+        self.export_review = None;
+        cx.notify();
+    }
+
+    /// Save the redacted document where the user picks
+    fn save_redacted_export(&mut self, cx: &mut ViewContext<Self>) {
+        let Some((format, export)) = self.redacted_export() else {
+            return;
+        };
+        self.export_review = None;
+        let document = export.render(format);
+        let file_name = export.file_name(format);
+        let filter = match format {
+            ExportFormat::Markdown => "Markdown",
+            ExportFormat::Html => "HTML",
+            ExportFormat::Json => "JSON",
+        };
+
+        cx.spawn(|view, mut cx| async move {
+            let file = rfd::AsyncFileDialog::new()
+                .set_title(t("dialog.export_thread"))
+                .set_file_name(file_name)
+                .add_filter(filter, &[format.extension()])
+                .save_file()
+                .await;
+
+            if let Some(file) = file {
+                let path = file.path().to_path_buf();
+                let status = std::fs::write(&path, document)
+                    .map(|_| path)
+                    .map_err(|e| t_args("banner.export_failed", &[("error", &e)]));
+                let _ = view.update(&mut cx, |this, cx| {
+                    this.export_status = Some(status);
+                    cx.notify();
+                });
+            }
+        })
+        .detach();
+        cx.notify();
+    }
+
+    /// Hide a term of the user's own wherever it appears in the export
+    fn add_redact_term(&mut self, cx: &mut ViewContext<Self>) {
+        let term = self.redact_term_input.read(cx).content().trim().to_string();
+        if !term.is_empty() {
+            self.export_redaction.terms.insert(term);
+            self.redact_term_input.update(cx, |input, cx| input.clear(cx));
+        }
+        cx.notify();
+    }
+
+    /// Save logs, settings and version info for a bug report as a zip
+    fn generate_diagnostic_bundle(&mut self, cx: &mut ViewContext<Self>) {
+        self.show_user_menu = false;
+        self.dismiss_crash_report();
+        let bundle = self.acp.manager.diagnostic_bundle();
+        let file_name = format!("cocowork-diagnostics-{}.zip", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+
+        cx.spawn(|view, mut cx| async move {
+            let file = rfd::AsyncFileDialog::new()
+                .set_title(t("dialog.diagnostic_bundle"))
+                .set_file_name(file_name)
+                .add_filter("Zip", &["zip"])
+                .save_file()
+                .await;
+
+            if let Some(file) = file {
+                let path = file.path().to_path_buf();
+                let status = bundle
+                    .write_zip(&path)
+                    .map(|_| path)
+                    .map_err(|e| t_args("banner.bundle_failed", &[("error", &e)]));
+                let _ = view.update(&mut cx, |this, cx| {
+                    this.bundle_status = Some(status);
+                    cx.notify();
+                });
+            }
+        })
+        .detach();
+        cx.notify();
+    }
+
+    /// Stop offering a bundle for the last crash; the report stays for later bundles
+    fn dismiss_crash_report(&mut self) {
+        if std::mem::take(&mut self.crash_report_pending) {
+            if let Err(e) = cocowork_core::diagnostics::dismiss_crash_report(&logs_dir()) {
+                tracing::warn!("Failed to dismiss crash report: {}", e);
+            }
+        }
+    }
+
+    fn toggle_template_menu(&mut self, cx: &mut ViewContext<Self>) {
+        self.show_template_menu = !self.show_template_menu;
+        self.show_user_menu = false;
+        cx.notify();
+    }
+
+    /// Replace the message input with a filled-in prompt template
+    ///
+    /// `{selection}` is taken from the clipboard; `extra` is text typed after
+    /// a slash command and goes on its own line.
+    fn insert_prompt_template(
+        &mut self,
+        template: &cocowork_core::PromptTemplate,
+        extra: &str,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let selection = cx.read_from_clipboard().and_then(|item| item.text());
+        let mut text = self
+            .acp
+            .manager
+            .expand_prompt_template(template, &self.attached_files, selection);
+        if !extra.is_empty() {
+            text.push('\n');
+            text.push_str(extra);
+        }
+        self.message_input.update(cx, |input, cx| input.set_content(text, cx));
+        self.show_template_menu = false;
+        cx.notify();
+    }
+
+    /// Go where a `cocowork://` link points
+    pub fn open_link(&mut self, url: &str, cx: &mut ViewContext<Self>) {
+        tracing::info!("Opening link: {}", url);
+        self.link_error = None;
+        match DeepLink::parse(url) {
+            Ok(DeepLink::OpenThread { session_id }) => {
+                match self.threads.iter().position(|thread| thread.id == session_id) {
+                    Some(idx) => self.select_thread(idx, cx),
+                    None => self.link_error = Some(t_args("banner.link_no_thread", &[("id", &session_id)])),
+                }
+            }
+            Ok(DeepLink::NewThread { working_dir, prompt, agent_id }) => {
+                let known_agent = agent_id
+                    .as_ref()
+                    .map_or(true, |id| self.acp.available_agents().iter().any(|agent| &agent.id == id));
+                if !working_dir.is_dir() {
+                    self.link_error = Some(t_args("banner.link_no_workspace", &[("dir", &working_dir.display())]));
+                } else if !known_agent {
+                    let agent_id = agent_id.unwrap_or_default();
+                    self.link_error = Some(t_args("banner.link_unknown_agent", &[("agent", &agent_id)]));
+                } else {
+                    self.set_workspace(working_dir, cx);
+                    match agent_id {
+                        Some(agent_id) => self.create_new_thread_with_agent(&agent_id, cx),
+                        None => self.acp.start_new_thread(),
+                    }
+                    if let Some(prompt) = prompt {
+                        self.message_input.update(cx, |input, cx| input.set_content(prompt, cx));
+                    }
+                }
+            }
+            Ok(DeepLink::ImportArchive { path }) => {
+                let archive = std::fs::read_to_string(&path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|json| ThreadExport::from_json(&json).map_err(|e| e.to_string()));
+                match archive {
+                    Ok(archive) => {
+                        let title = archive.title.clone();
+                        let session_id = self.acp.import_thread(archive);
+                        self.sync_thread_list();
+                        if let Some(idx) = self.threads.iter().position(|thread| thread.id == session_id) {
+                            self.threads[idx].name = title;
+                            self.select_thread(idx, cx);
+                        }
+                    }
+                    Err(e) => {
+                        let file = path.display();
+                        self.link_error = Some(t_args("banner.link_import_failed", &[("file", &file), ("error", &e)]));
+                    }
+                }
+            }
+            Err(e) => self.link_error = Some(e),
+        }
+        cx.notify();
+    }
+
+    fn toggle_user_menu(&mut self, cx: &mut ViewContext<Self>) {
+        self.show_user_menu = !self.show_user_menu;
+        self.show_agent_menu = false;
+        self.show_mode_menu = false;
+        cx.notify();
+    }
+
+    fn select_workspace(&mut self, cx: &mut ViewContext<Self>) {
         // Open native folder picker dialog asynchronously
         cx.spawn(|view, mut cx| async move {
             let folder = rfd::AsyncFileDialog::new()
-                .set_title("Select Agent Workspace")
+                .set_title(t("dialog.select_workspace"))
                 .pick_folder()
                 .await;
 
             if let Some(folder) = folder {
                 let path = folder.path().to_path_buf();
-                let path_str = path.display().to_string();
-                let _ = view.update(&mut cx, |this, cx| {
-                    this.workspace_path = Some(path_str.clone());
-                    // Update ACP working directory so agent uses this directory
-                    this.acp.set_working_dir(Some(path));
-                    tracing::info!("Workspace set to: {}", path_str);
-                    cx.notify();
-                });
+                let _ = view.update(&mut cx, |this, cx| this.set_workspace(path, cx));
             }
         })
         .detach();
     }
 
+    fn set_workspace(&mut self, path: PathBuf, cx: &mut ViewContext<Self>) {
+        let path_str = path.display().to_string();
+        self.workspace_path = Some(path_str.clone());
+        // Update ACP working directory so agent uses this directory
+        self.acp.set_working_dir(Some(path));
+        self.reload_instructions(cx);
+        self.reload_test_command(cx);
+        self.reload_check_commands(cx);
+        tracing::info!("Workspace set to: {}", path_str);
+        cx.notify();
+    }
+
+    /// Show the current workspace's instructions in the editor
+    fn reload_instructions(&mut self, cx: &mut ViewContext<Self>) {
+        let instructions = self.acp.manager.workspace_instructions.clone();
+        self.instructions_input
+            .update(cx, |input, cx| input.set_content(instructions, cx));
+        self.instructions_status = None;
+    }
+
+    fn save_instructions(&mut self, cx: &mut ViewContext<Self>) {
+        let instructions = self.instructions_input.read(cx).content().to_string();
+        self.instructions_status = Some(self.acp.manager.save_workspace_instructions(&instructions));
+        cx.notify();
+    }
+
+    /// Show the current workspace's test command in the editor
+    fn reload_test_command(&mut self, cx: &mut ViewContext<Self>) {
+        let command = self.acp.manager.test_config().map(|config| config.command).unwrap_or_default();
+        self.test_command_input.update(cx, |input, cx| input.set_content(command, cx));
+        self.test_command_status = None;
+    }
+
+    /// Store the workspace's test command as typed, and whether it runs after each turn
+    fn save_test_config(&mut self, run_after_turn: bool, cx: &mut ViewContext<Self>) {
+        let command = self.test_command_input.read(cx).content().to_string();
+        let result = self.acp.manager.set_test_config(TestConfig { command, run_after_turn });
+        if result.is_ok() && self.test_command_input.read(cx).content().trim().is_empty() {
+            // Cleared settings fall back to the guessed command
+            self.reload_test_command(cx);
+        }
+        self.test_command_status = Some(result);
+        cx.notify();
+    }
+
+    /// Show the current workspace's build and lint commands and diagnostics file in the editors
+    fn reload_check_commands(&mut self, cx: &mut ViewContext<Self>) {
+        let config = self.acp.manager.check_config().unwrap_or_default();
+        let commands = config.commands.join("\n");
+        let diagnostics_file = config.diagnostics_file.unwrap_or_default();
+        self.check_commands_input.update(cx, |input, cx| input.set_content(commands, cx));
+        self.diagnostics_file_input.update(cx, |input, cx| input.set_content(diagnostics_file, cx));
+        self.check_commands_status = None;
+    }
+
+    /// Store the workspace's build and lint commands and diagnostics file as
+    /// typed, and whether the commands run after each turn
+    fn save_check_config(&mut self, run_after_turn: bool, cx: &mut ViewContext<Self>) {
+        let commands = self.check_commands_input.read(cx).content().lines().map(str::to_string).collect();
+        let diagnostics_file = Some(self.diagnostics_file_input.read(cx).content().to_string());
+        let config = CheckConfig { commands, run_after_turn, diagnostics_file };
+        let result = self.acp.manager.set_check_config(config);
+        if result.is_ok() {
+            // Show the commands as stored, without blank lines
+            self.reload_check_commands(cx);
+        }
+        self.check_commands_status = Some(result);
+        cx.notify();
+    }
+
+    /// Ask the agent to fix the workspace's problems
+    fn send_problems(&mut self, cx: &mut ViewContext<Self>) {
+        let problems = self.acp.manager.problems();
+        if problems.is_empty() {
+            return;
+        }
+        let prompt = cocowork_core::problems_prompt(&problems);
+        self.message_input.update(cx, |input, cx| input.set_content(prompt, cx));
+        self.handle_send_message(cx);
+    }
+
     fn add_attachment(&mut self, cx: &mut ViewContext<Self>) {
         // Open native file picker dialog asynchronously
         cx.spawn(|view, mut cx| async move {
             let files = rfd::AsyncFileDialog::new()
-                .set_title("Add File")
+                .set_title(t("dialog.add_file"))
                 .pick_files()
                 .await;
 
@@ -416,6 +2150,30 @@ impl CocoWorkWindow {
         .detach();
     }
 
+    /// The `@query` being typed at the end of the message, if any
+    fn mention_query(&self, cx: &ViewContext<Self>) -> Option<String> {
+        self.acp.manager.workspace_index.as_ref()?;
+        let content = self.message_input.read(cx).content();
+        let token = content.rsplit(char::is_whitespace).next()?;
+        token.strip_prefix('@').map(str::to_string)
+    }
+
+    /// Replace the `@query` with an attachment of the workspace file at `path`
+    fn insert_mention(&mut self, path: &std::path::Path, cx: &mut ViewContext<Self>) {
+        let content = self.message_input.read(cx).content().to_string();
+        let kept = match content.rfind(char::is_whitespace) {
+            Some(i) => content[..=i].to_string(),
+            None => String::new(),
+        };
+        self.message_input.update(cx, |input, cx| input.set_content(kept, cx));
+
+        let full_path = self.acp.manager.get_working_dir().join(path).display().to_string();
+        if !self.attached_files.contains(&full_path) {
+            self.attached_files.push(full_path);
+        }
+        cx.notify();
+    }
+
     fn remove_attachment(&mut self, file_path: &str, cx: &mut ViewContext<Self>) {
         self.attached_files.retain(|f| f != file_path);
         cx.notify();
@@ -438,46 +2196,355 @@ impl CocoWorkWindow {
 
     /// Show new thread dialog with agent selection
     fn show_new_thread_dialog(&mut self, cx: &mut ViewContext<Self>) {
+        // Probe agent availability the first time the dialog opens
+        if !self.acp.has_availability_results() {
+            self.acp.start_availability_check();
+        }
+        self.subtask_seed = None;
         self.show_new_thread_dialog = true;
         self.show_agent_menu = false;
         self.show_mode_menu = false;
+
+        // Session overrides start from the global MCP servers each time
+        self.show_new_thread_advanced = false;
+        self.new_thread_mcp = self.mcp_servers.iter().filter(|s| s.enabled).map(|s| s.name.clone()).collect();
+        self.new_thread_dir = None;
+        self.new_thread_error = None;
+        self.new_thread_isolate = false;
+        let inputs = [
+            &self.new_thread_env_input,
+            &self.new_thread_mode_input,
+            &self.new_thread_model_input,
+            &self.new_thread_issue_input,
+        ];
+        for input in inputs {
+            input.update(cx, |input, cx| input.clear(cx));
+        }
+        if !self.acp.manager.is_importing_issue() {
+            self.acp.manager.issue = None;
+        }
         cx.notify();
     }
 
-    /// Create a new thread with the specified agent (non-blocking)
-    fn create_new_thread_with_agent(&mut self, agent_id: &str, cx: &mut ViewContext<Self>) {
-        tracing::info!("Creating new thread with agent: {}", agent_id);
-
-        // Close the dialog
-        self.show_new_thread_dialog = false;
-
-        // Start creating the new thread with the selected agent
-        self.acp.start_new_thread_with_agent(agent_id);
-
+    /// Fetch the issue whose URL is in the new thread dialog
+    fn import_issue(&mut self, cx: &mut ViewContext<Self>) {
+        let url = self.new_thread_issue_input.read(cx).content().trim().to_string();
+        if url.is_empty() {
+            self.acp.manager.issue = None;
+        } else if let Err(e) = self.acp.manager.start_issue_import(&url) {
+            self.acp.manager.issue = Some(Err(e));
+        }
         cx.notify();
     }
 
-    /// Legacy: create new session (now shows dialog)
-    fn create_new_thread(&mut self, cx: &mut ViewContext<Self>) {
-        // Show the new thread dialog instead of immediately creating
-        self.show_new_thread_dialog(cx);
+    /// Overrides from the new thread dialog's advanced section
+    fn new_session_options(&self, cx: &ViewContext<Self>) -> Result<NewSessionOptions, String> {
+        let mut options = NewSessionOptions {
+            working_dir: self.new_thread_dir.clone(),
+            isolate: self.new_thread_isolate,
+            mcp_servers: self
+                .mcp_servers
+                .iter()
+                .filter(|server| self.new_thread_mcp.contains(&server.name))
+                .map(McpServerConfig::to_session_server)
+                .collect(),
+            ..Default::default()
+        };
+        options.set_variables(self.new_thread_env_input.read(cx).content())?;
+        let text = |input: &View<TextInput>| {
+            Some(input.read(cx).content().trim().to_string()).filter(|text| !text.is_empty())
+        };
+        options.mode = text(&self.new_thread_mode_input).map(SessionModeId::new);
+        options.model = text(&self.new_thread_model_input).map(ModelId::new);
+        Ok(options)
     }
 
-    fn start_resizing_sidebar(&mut self, event: &MouseDownEvent, cx: &mut ViewContext<Self>) {
-        self.resizing_sidebar = true;
-        self.sidebar_resize_start_x = f32::from(event.position.x);
-        self.sidebar_resize_start_width = self.sidebar_width;
+    /// Open the active isolated thread's changes for review
+    fn review_isolation(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(session_id) = self.acp.active_session_id.clone() else {
+            return;
+        };
+        self.isolation_review = Some(self.acp.manager.isolation_diff(&session_id));
+        self.isolation_status = None;
         cx.notify();
     }
 
-    fn resize_sidebar(&mut self, event: &MouseMoveEvent, cx: &mut ViewContext<Self>) {
-        if !self.resizing_sidebar {
+    fn merge_back_isolation(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(session_id) = self.acp.active_session_id.clone() else {
             return;
-        }
-
+        };
+        let workspace = self
+            .acp
+            .active_session()
+            .and_then(|session| session.isolation.as_ref())
+            .map(|isolation| isolation.workspace.clone());
+        self.isolation_status = match (self.acp.manager.merge_back(&session_id), workspace) {
+            (Ok(()), Some(workspace)) => Some(Ok(workspace)),
+            (Ok(()), None) => None,
+            (Err(e), _) => Some(Err(e)),
+        };
+        self.isolation_review = Some(self.acp.manager.isolation_diff(&session_id));
+        cx.notify();
+    }
+
+    fn discard_isolation(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(session_id) = self.acp.active_session_id.clone() else {
+            return;
+        };
+        match self.acp.manager.discard_isolation(&session_id) {
+            Ok(()) => {
+                self.isolation_review = None;
+                self.isolation_status = None;
+            }
+            Err(e) => self.isolation_status = Some(Err(e)),
+        }
+        cx.notify();
+    }
+
+    /// Open what the active thread's agent changed since its last commit, to commit it
+    fn open_commit_dialog(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(session_id) = self.acp.active_session_id.clone() else {
+            return;
+        };
+        self.commit_review = Some(self.acp.manager.commit_diff(&session_id));
+        self.commit_error = None;
+        cx.notify();
+    }
+
+    fn commit_changes(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(session_id) = self.acp.active_session_id.clone() else {
+            return;
+        };
+        let message = self.commit_message_input.read(cx).content().to_string();
+        match self.acp.manager.commit_changes(&session_id, &message) {
+            Ok(_) => {
+                self.commit_review = None;
+                self.commit_error = None;
+                self.commit_message_input.update(cx, |input, cx| input.clear(cx));
+            }
+            Err(e) => self.commit_error = Some(e),
+        }
+        cx.notify();
+    }
+
+    /// Ask the agent for the commit message; its reply fills the message box
+    fn draft_commit_message(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(session_id) = self.acp.active_session_id.clone() else {
+            return;
+        };
+        self.commit_error = self.acp.manager.draft_commit_message(&session_id).err();
+        cx.notify();
+    }
+
+    /// Open the wizard that pushes the active thread's branch and opens a pull request for it
+    fn open_pull_request_wizard(&mut self, cx: &mut ViewContext<Self>) {
+        self.show_thread_menu = false;
+        let Some(session_id) = self.acp.active_session_id.clone() else {
+            return;
+        };
+        if !self.acp.manager.is_opening_pull_request() {
+            self.acp.manager.pull_request = None;
+        }
+        let target = self.acp.manager.pull_request_target(&session_id);
+        self.pull_request_has_token = target
+            .as_ref()
+            .is_ok_and(|target| self.acp.manager.forge_token(&target.remote).is_some());
+        self.pull_request_unknown_host = match target {
+            Ok(_) => None,
+            Err(_) => self.acp.manager.unrecognized_forge_host(&session_id),
+        };
+        self.pull_request_wizard = Some(target);
+        self.pull_request_error = None;
+        cx.notify();
+    }
+
+    fn close_pull_request_wizard(&mut self, cx: &mut ViewContext<Self>) {
+        self.pull_request_wizard = None;
+        if self.acp.manager.pull_request.as_ref().is_some_and(|result| result.is_ok()) {
+            self.acp.manager.pull_request = None;
+            self.pull_request_title_input.update(cx, |input, cx| input.clear(cx));
+            self.pull_request_body_input.update(cx, |input, cx| input.clear(cx));
+        }
+        cx.notify();
+    }
+
+    /// Store the token the wizard asked for in the keychain
+    fn save_forge_token(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(Ok(target)) = &self.pull_request_wizard else {
+            return;
+        };
+        let remote = target.remote.clone();
+        let value = self.pull_request_token_input.read(cx).content().to_string();
+        match self.acp.manager.save_forge_token(&remote, &value) {
+            Ok(()) => {
+                self.pull_request_has_token = true;
+                self.pull_request_error = None;
+                self.pull_request_token_input.update(cx, |input, cx| input.clear(cx));
+            }
+            Err(e) => self.pull_request_error = Some(e),
+        }
+        cx.notify();
+    }
+
+    /// Let pull requests be opened on the remote's self-hosted GitLab server,
+    /// then look at the remote again
+    fn add_gitlab_host(&mut self, host: &str, cx: &mut ViewContext<Self>) {
+        match self.acp.manager.add_gitlab_host(host) {
+            Ok(()) => self.open_pull_request_wizard(cx),
+            Err(e) => {
+                self.pull_request_error = Some(e);
+                cx.notify();
+            }
+        }
+    }
+
+    /// Ask the agent for the title and description; its reply fills them in
+    fn draft_pull_request(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(session_id) = self.acp.active_session_id.clone() else {
+            return;
+        };
+        self.pull_request_error = self.acp.manager.draft_pull_request(&session_id).err();
+        cx.notify();
+    }
+
+    fn open_pull_request(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(session_id) = self.acp.active_session_id.clone() else {
+            return;
+        };
+        let draft = PullRequestDraft {
+            title: self.pull_request_title_input.read(cx).content().trim().to_string(),
+            body: self.pull_request_body_input.read(cx).content().trim().to_string(),
+        };
+        self.pull_request_error = self.acp.manager.start_pull_request(&session_id, draft).err();
+        cx.notify();
+    }
+
+    fn select_new_thread_dir(&mut self, cx: &mut ViewContext<Self>) {
+        cx.spawn(|view, mut cx| async move {
+            let folder = rfd::AsyncFileDialog::new()
+                .set_title(t("dialog.session_working_dir"))
+                .pick_folder()
+                .await;
+
+            if let Some(folder) = folder {
+                let _ = view.update(&mut cx, |this, cx| {
+                    this.new_thread_dir = Some(folder.path().to_path_buf());
+                    cx.notify();
+                });
+            }
+        })
+        .detach();
+    }
+
+    /// Create a new thread with the specified agent (non-blocking)
+    fn create_new_thread_with_agent(&mut self, agent_id: &str, cx: &mut ViewContext<Self>) {
+        tracing::info!("Creating new thread with agent: {}", agent_id);
+
+        // Session overrides, like a sub-thread, only come from the dialog
+        let options = if self.show_new_thread_dialog {
+            match self.new_session_options(cx) {
+                Ok(options) => options,
+                Err(e) => {
+                    self.show_new_thread_advanced = true;
+                    self.new_thread_error = Some(e);
+                    cx.notify();
+                    return;
+                }
+            }
+        } else {
+            NewSessionOptions::default()
+        };
+        self.acp.manager.session_options = options;
+
+        // A sub-thread is only spawned when its agent was picked in the dialog
+        let subtask = self.subtask_seed.take().filter(|_| self.show_new_thread_dialog);
+
+        // An issue URL in the dialog must have been fetched; the issue seeds the first prompt
+        let issue_url = Some(self.new_thread_issue_input.read(cx).content().trim().to_string())
+            .filter(|url| self.show_new_thread_dialog && subtask.is_none() && !url.is_empty());
+        let reference = issue_url.as_deref().and_then(IssueRef::parse);
+        let issue = match &self.acp.manager.issue {
+            Some(Ok(issue)) if reference.as_ref() == Some(&issue.reference) => Some(issue.clone()),
+            _ => None,
+        };
+        if issue_url.is_some() && issue.is_none() {
+            if !self.acp.manager.is_importing_issue() {
+                self.acp.manager.issue = Some(Err(t("new_thread.issue_not_fetched").to_string()));
+            }
+            cx.notify();
+            return;
+        }
+        self.acp.manager.session_options.issue_url = issue.as_ref().map(|issue| issue.reference.url());
+
+        // Close the dialog
+        self.show_new_thread_dialog = false;
+
+        // Start creating the new thread with the selected agent
+        match subtask.and_then(|task| self.acp.spawn_subthread(agent_id, &task)) {
+            Some(prompt) => self.message_input.update(cx, |input, cx| input.set_content(prompt, cx)),
+            None => {
+                self.acp.start_new_thread_with_agent(agent_id);
+                if let Some(issue) = issue {
+                    self.message_input.update(cx, |input, cx| input.set_content(issue.prompt(), cx));
+                }
+            }
+        }
+
+        cx.notify();
+    }
+
+    /// Pick an agent for a sub-thread working on `task` from the active thread
+    fn spawn_subthread(&mut self, task: String, cx: &mut ViewContext<Self>) {
+        self.show_new_thread_dialog(cx);
+        self.subtask_seed = Some(task);
+    }
+
+    /// Branch the active thread at message `idx` into a new thread and switch to it
+    fn fork_thread(&mut self, idx: usize, cx: &mut ViewContext<Self>) {
+        let parent_name = self.active_thread_idx.and_then(|idx| self.threads.get(idx)).map(|t| t.name.clone());
+        let Some(session_id) = self.acp.fork_thread(idx) else {
+            return;
+        };
+        self.sync_thread_list();
+        if let Some(idx) = self.threads.iter().position(|thread| thread.id == session_id) {
+            if let Some(name) = parent_name {
+                self.threads[idx].name = t_args("sidebar.fork_name", &[("name", &name)]);
+            }
+            self.select_thread(idx, cx);
+        }
+        self.focus_region(FocusRegion::Input, cx);
+    }
+
+    /// Switch to the thread the active one was forked from
+    fn open_fork_parent(&mut self, cx: &mut ViewContext<Self>) {
+        let parent = self.acp.active_session().and_then(|session| session.parent_session_id.clone());
+        if let Some(idx) = parent.and_then(|id| self.threads.iter().position(|thread| thread.id == id)) {
+            self.select_thread(idx, cx);
+        }
+    }
+
+    /// Legacy: create new session (now shows dialog)
+    fn create_new_thread(&mut self, cx: &mut ViewContext<Self>) {
+        // Show the new thread dialog instead of immediately creating
+        self.show_new_thread_dialog(cx);
+    }
+
+    fn start_resizing_sidebar(&mut self, event: &MouseDownEvent, cx: &mut ViewContext<Self>) {
+        self.resizing_sidebar = true;
+        self.sidebar_resize_start_x = f32::from(event.position.x);
+        self.sidebar_resize_start_width = self.sidebar_width;
+        cx.notify();
+    }
+
+    fn resize_sidebar(&mut self, event: &MouseMoveEvent, cx: &mut ViewContext<Self>) {
+        if !self.resizing_sidebar {
+            return;
+        }
+
+        // Widths are kept at 100% scale
         let current_x = f32::from(event.position.x);
-        let delta_x = current_x - self.sidebar_resize_start_x;
-        let new_width = (self.sidebar_resize_start_width + delta_x).clamp(180.0, 480.0);
+        let delta_x = (current_x - self.sidebar_resize_start_x) / ui_scale();
+        let new_width = (self.sidebar_resize_start_width + delta_x).clamp(layout::SIDEBAR_MIN_WIDTH, layout::SIDEBAR_MAX_WIDTH);
 
         if (new_width - self.sidebar_width).abs() > 0.5 {
             self.sidebar_width = new_width;
@@ -488,6 +2555,7 @@ impl CocoWorkWindow {
     fn stop_resizing_sidebar(&mut self, _event: &MouseUpEvent, cx: &mut ViewContext<Self>) {
         if self.resizing_sidebar {
             self.resizing_sidebar = false;
+            self.save_layout_soon(cx);
             cx.notify();
         }
     }
@@ -506,8 +2574,8 @@ impl CocoWorkWindow {
 
         let current_x = f32::from(event.position.x);
         // Right sidebar: delta is inverted (dragging left increases width)
-        let delta_x = self.context_panel_resize_start_x - current_x;
-        let new_width = (self.context_panel_resize_start_width + delta_x).clamp(200.0, 500.0);
+        let delta_x = (self.context_panel_resize_start_x - current_x) / ui_scale();
+        let new_width = (self.context_panel_resize_start_width + delta_x).clamp(layout::CONTEXT_PANEL_MIN_WIDTH, layout::CONTEXT_PANEL_MAX_WIDTH);
 
         if (new_width - self.context_panel_width).abs() > 0.5 {
             self.context_panel_width = new_width;
@@ -518,10 +2586,33 @@ impl CocoWorkWindow {
     fn stop_resizing_context_panel(&mut self, _event: &MouseUpEvent, cx: &mut ViewContext<Self>) {
         if self.resizing_context_panel {
             self.resizing_context_panel = false;
+            self.save_layout_soon(cx);
             cx.notify();
         }
     }
 
+    /// Save the layout once it has stopped changing for a moment
+    fn save_layout_soon(&mut self, cx: &mut ViewContext<Self>) {
+        self.layout_save = Some(cx.spawn(|view, mut cx| async move {
+            cx.background_executor().timer(LAYOUT_SAVE_DELAY).await;
+            let _ = view.update(&mut cx, |this, _| this.save_layout());
+        }));
+    }
+
+    fn save_layout(&mut self) {
+        let layout = WindowLayout {
+            window: self.window_geometry,
+            sidebar_width: self.sidebar_width,
+            context_panel_width: self.context_panel_width,
+            expanded_sections: self.expanded_sections.clone(),
+            sidebar_collapsed: self.sidebar_collapsed,
+            context_panel_collapsed: self.context_panel_collapsed,
+        };
+        if let Err(e) = self.acp.manager.set_window_layout(layout) {
+            tracing::warn!("Failed to save window layout: {}", e);
+        }
+    }
+
     // ========================================================================
     // Top Bar
     // ========================================================================
@@ -555,35 +2646,98 @@ impl CocoWorkWindow {
                             .font_weight(FontWeight::SEMIBOLD)
                             .text_color(rgb(colors.text_primary))
                             .child("cocowork"),
+                    )
+                    .child(
+                        self.panel_button(
+                            "toggle-sidebar-btn",
+                            IconName::PanelLeft,
+                            if self.sidebar_collapsed { t("status.show_sidebar") } else { t("status.hide_sidebar") },
+                            !self.sidebar_collapsed,
+                        )
+                        .on_click(cx.listener(|this, _, cx| this.toggle_sidebar(&ToggleSidebar, cx))),
                     ),
             )
-            // Right side: User avatar with dropdown menu (coconut icon)
+            // Right side: context panel toggle, then the user avatar with dropdown menu (coconut icon)
             .child(
                 div()
-                    .relative()
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
                     .child(
-                        div()
-                            .id("user-btn")
-                            .w(px(28.0))
-                            .h(px(28.0))
-                            .flex()
-                            .items_center()
-                            .justify_center()
-                            .rounded_full()
-                            .bg(rgb(colors.surface_elevated))
-                            .border_1()
-                            .border_color(rgb(colors.border))
-                            .cursor_pointer()
-                            .hover(|s| s.bg(rgba(colors.hover)))
-                            .on_click(cx.listener(|this, _, cx| {
-                                this.toggle_user_menu(cx);
-                            }))
-                            .child("🥥"),
+                        self.panel_button(
+                            "toggle-context-panel-btn",
+                            IconName::PanelRight,
+                            if self.context_panel_collapsed {
+                                t("status.show_context_panel")
+                            } else {
+                                t("status.hide_context_panel")
+                            },
+                            !self.context_panel_collapsed,
+                        )
+                        .on_click(cx.listener(|this, _, cx| this.toggle_context_panel(&ToggleContextPanel, cx))),
                     )
-                    // User menu dropdown
-                    .when(show_user_menu, |el| {
-                        el.child(self.render_user_menu(cx))
-                    }),
+                    .child(
+                        self.panel_button(
+                            "toggle-terminal-panel-btn",
+                            IconName::Terminal,
+                            if self.show_terminal_panel {
+                                t("status.hide_terminal_panel")
+                            } else {
+                                t("status.show_terminal_panel")
+                            },
+                            self.show_terminal_panel,
+                        )
+                        .on_click(cx.listener(|this, _, cx| this.toggle_terminal_panel(&ToggleTerminalPanel, cx))),
+                    )
+                    .child(
+                        div()
+                            .relative()
+                            .child(
+                                div()
+                                    .id("user-btn")
+                                    .tooltip(|cx| Tooltip::text(t("menu.account"), cx))
+                                    .w(px(28.0))
+                                    .h(px(28.0))
+                                    .flex()
+                                    .items_center()
+                                    .justify_center()
+                                    .rounded_full()
+                                    .bg(rgb(colors.surface_elevated))
+                                    .border_1()
+                                    .border_color(rgb(colors.border))
+                                    .cursor_pointer()
+                                    .hover(|s| s.bg(rgba(colors.hover)))
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.toggle_user_menu(cx);
+                                    }))
+                                    .child("🥥"),
+                            )
+                            // User menu dropdown
+                            .when(show_user_menu, |el| {
+                                el.child(self.render_user_menu(cx))
+                            }),
+                    ),
+            )
+    }
+
+    /// A square icon button for showing panels, highlighted while `active`
+    fn panel_button(&self, id: &'static str, icon: IconName, tooltip: &'static str, active: bool) -> Stateful<Div> {
+        let colors = &self.theme.colors;
+
+        div()
+            .id(id)
+            .tooltip(move |cx| Tooltip::text(tooltip, cx))
+            .w(px(24.0))
+            .h(px(24.0))
+            .flex()
+            .items_center()
+            .justify_center()
+            .rounded(px(4.0))
+            .cursor_pointer()
+            .hover(|s| s.bg(rgba(colors.hover)))
+            .child(
+                svg_icon(icon, IconSize::Small)
+                    .text_color(rgb(if active { colors.text_primary } else { colors.text_secondary })),
             )
     }
 
@@ -603,7 +2757,7 @@ impl CocoWorkWindow {
             .py(px(4.0))
             .flex()
             .flex_col()
-            // Settings option (placeholder - not implemented)
+            // Settings option
             .child(
                 div()
                     .id("user-menu-settings")
@@ -617,9 +2771,7 @@ impl CocoWorkWindow {
                     .hover(|s| s.bg(rgba(colors.hover)))
                     .on_click(cx.listener(|this, _, cx| {
                         this.show_user_menu = false;
-                        // TODO: Open settings panel
-                        tracing::info!("Settings clicked - not yet implemented");
-                        cx.notify();
+                        this.open_settings(cx);
                     }))
                     .child(
                         // Settings icon (gear shape using CSS)
@@ -630,7 +2782,60 @@ impl CocoWorkWindow {
                         div()
                             .text_sm()
                             .text_color(rgb(colors.text_primary))
-                            .child("Settings"),
+                            .child(t("common.settings")),
+                    ),
+            )
+            // Batch run
+            .child(
+                div()
+                    .id("user-menu-batch")
+                    .w_full()
+                    .px(px(12.0))
+                    .py(px(8.0))
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .cursor_pointer()
+                    .hover(|s| s.bg(rgba(colors.hover)))
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.show_user_menu = false;
+                        this.open_batch_dialog(cx);
+                    }))
+                    .child(
+                        svg_icon(IconName::Play, IconSize::Small)
+                            .text_color(rgb(colors.text_secondary)),
+                    )
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(rgb(colors.text_primary))
+                            .child(t("menu.batch_run")),
+                    ),
+            )
+            // Diagnostic bundle
+            .child(
+                div()
+                    .id("user-menu-diagnostics")
+                    .w_full()
+                    .px(px(12.0))
+                    .py(px(8.0))
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .cursor_pointer()
+                    .hover(|s| s.bg(rgba(colors.hover)))
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.generate_diagnostic_bundle(cx);
+                    }))
+                    .child(
+                        svg_icon(IconName::File, IconSize::Small)
+                            .text_color(rgb(colors.text_secondary)),
+                    )
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(rgb(colors.text_primary))
+                            .child(t("menu.diagnostic_bundle")),
                     ),
             )
             // Separator
@@ -676,7 +2881,7 @@ impl CocoWorkWindow {
                         div()
                             .text_sm()
                             .text_color(rgb(colors.text_primary))
-                            .child("About"),
+                            .child(t("menu.about")),
                     ),
             )
     }
@@ -724,7 +2929,7 @@ impl CocoWorkWindow {
                                 div()
                                     .text_xs()
                                     .text_color(rgb(colors.text_secondary))
-                                    .child("Connected"),
+                                    .child(t("status.connected")),
                             ),
                     )
                     // Message count
@@ -732,9 +2937,9 @@ impl CocoWorkWindow {
                         div()
                             .text_xs()
                             .text_color(rgb(colors.text_secondary))
-                            .child(format!(
-                                "{} messages",
-                                self.acp.active_session().map(|s| s.messages.len()).unwrap_or(0)
+                            .child(t_args(
+                                "status.messages",
+                                &[("count", &self.acp.active_session().map(|s| s.messages.len()).unwrap_or(0))],
                             )),
                     ),
             )
@@ -744,6 +2949,22 @@ impl CocoWorkWindow {
                     .flex()
                     .items_center()
                     .gap(px(12.0))
+                    // Zoom level while not at 100%; click to reset
+                    .when((self.theme.scale - 1.0).abs() > f32::EPSILON, |el| {
+                        el.child(
+                            div()
+                                .id("zoom-level")
+                                .tooltip(|cx| Tooltip::text(t("status.reset_zoom"), cx))
+                                .px(px(6.0))
+                                .rounded(px(4.0))
+                                .text_xs()
+                                .text_color(rgb(colors.text_secondary))
+                                .cursor_pointer()
+                                .hover(|s| s.bg(rgba(colors.hover)))
+                                .on_click(cx.listener(|this, _, cx| this.set_zoom(1.0, cx)))
+                                .child(format!("{:.0}%", self.theme.scale * 100.0)),
+                        )
+                    })
                     // MCP servers button with popup
                     .child(
                         div()
@@ -779,7 +3000,7 @@ impl CocoWorkWindow {
                                         div()
                                             .text_xs()
                                             .text_color(rgb(colors.text_secondary))
-                                            .child(format!("MCP: {}", enabled_count)),
+                                            .child(t_args("status.mcp", &[("count", &enabled_count)])),
                                     ),
                             )
                             // MCP Panel popup
@@ -825,7 +3046,7 @@ impl CocoWorkWindow {
                             .text_sm()
                             .font_weight(FontWeight::SEMIBOLD)
                             .text_color(rgb(colors.text_primary))
-                            .child("MCP Servers"),
+                            .child(t("mcp.title")),
                     )
                     .child(
                         div()
@@ -923,7 +3144,7 @@ impl CocoWorkWindow {
                             div()
                                 .text_sm()
                                 .text_color(rgb(colors.text_secondary))
-                                .child("No MCP servers configured"),
+                                .child(t("mcp.none")),
                         ),
                 )
             })
@@ -945,7 +3166,7 @@ impl CocoWorkWindow {
                         div()
                             .text_sm()
                             .text_color(rgb(colors.text_secondary))
-                            .child("+ Add Server"),
+                            .child(t("mcp.add")),
                     ),
             )
     }
@@ -974,15 +3195,74 @@ impl CocoWorkWindow {
             .child(self.render_threads_header(cx))
             // Threads list
             .child(self.render_threads_list(cx))
+            // Project search and dashboard switches
+            .child(self.render_search_button(cx))
+            .child(self.render_dashboard_button(cx))
+            .child(self.render_task_board_button(cx))
+            .child(self.render_audit_button(cx))
     }
 
-    fn render_sidebar_resizer(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+    /// What's left of the sidebar while it's collapsed: buttons to bring it
+    /// back, start a thread or search files
+    fn render_sidebar_rail(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
         let colors = &self.theme.colors;
-        let resizing = self.resizing_sidebar;
 
         div()
-            .id("sidebar-resizer")
-            .w(px(4.0))
+            .id("sidebar-rail")
+            .w(px(layout::RAIL_WIDTH))
+            .flex_shrink_0()
+            .h_full()
+            .pt(px(8.0))
+            .flex()
+            .flex_col()
+            .items_center()
+            .gap(px(4.0))
+            .bg(rgb(colors.sidebar_bg))
+            .border_r_1()
+            .border_color(rgb(colors.border))
+            .child(
+                self.panel_button("rail-show-sidebar", IconName::PanelLeft, t("status.show_sidebar"), false)
+                    .on_click(cx.listener(|this, _, cx| this.toggle_sidebar(&ToggleSidebar, cx))),
+            )
+            .child(
+                self.panel_button("rail-new-thread", IconName::Plus, t("sidebar.new_thread"), false)
+                    .on_click(cx.listener(|this, _, cx| this.create_new_thread(cx))),
+            )
+            .child(
+                self.panel_button("rail-search-files", IconName::Search, t("sidebar.search_files"), self.show_search)
+                    .on_click(cx.listener(|this, _, cx| this.toggle_project_search(cx))),
+            )
+    }
+
+    /// What's left of the context panel while it's collapsed
+    fn render_context_panel_rail(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+
+        div()
+            .id("context-panel-rail")
+            .w(px(layout::RAIL_WIDTH))
+            .flex_shrink_0()
+            .h_full()
+            .pt(px(8.0))
+            .flex()
+            .flex_col()
+            .items_center()
+            .bg(rgb(colors.sidebar_bg))
+            .border_l_1()
+            .border_color(rgb(colors.border))
+            .child(
+                self.panel_button("rail-show-context-panel", IconName::PanelRight, t("status.show_context_panel"), false)
+                    .on_click(cx.listener(|this, _, cx| this.toggle_context_panel(&ToggleContextPanel, cx))),
+            )
+    }
+
+    fn render_sidebar_resizer(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let resizing = self.resizing_sidebar;
+
+        div()
+            .id("sidebar-resizer")
+            .w(px(4.0))
             .h_full()
             .cursor(CursorStyle::ResizeLeftRight)
             .when(resizing, |el| {
@@ -1020,6 +3300,7 @@ impl CocoWorkWindow {
         let colors = &self.theme.colors;
         let search_text = self.search_text.clone();
         let has_search = !search_text.is_empty();
+        let search_focused = self.search_input.read(cx).focus_handle(cx).is_focused(cx);
 
         div()
             .id("search-box-container")
@@ -1029,13 +3310,19 @@ impl CocoWorkWindow {
                 div()
                     .id("search-box")
                     .w_full()
-                    .h(px(32.0))
-                    .px(px(12.0))
+                    .h(dense(32.0))
+                    .px(dense(12.0))
                     .flex()
                     .items_center()
-                    .gap(px(8.0))
+                    .gap(dense(8.0))
                     .rounded(px(6.0))
                     .bg(rgb(colors.input_bg))
+                    .border_1()
+                    .border_color(if search_focused {
+                        rgb(colors.focus_ring)
+                    } else {
+                        rgb(colors.input_bg)
+                    })
                     // Search icon
                     .child(
                         div()
@@ -1054,6 +3341,7 @@ impl CocoWorkWindow {
                         el.child(
                             div()
                                 .id("clear-search")
+                                .tooltip(|cx| Tooltip::text(t("sidebar.clear_search"), cx))
                                 .text_sm()
                                 .text_color(rgb(colors.text_secondary))
                                 .cursor_pointer()
@@ -1072,8 +3360,8 @@ impl CocoWorkWindow {
 
         div()
             .w_full()
-            .h(px(32.0))
-            .px(px(16.0))
+            .h(dense(32.0))
+            .px(dense(16.0))
             .flex()
             .items_center()
             .justify_between()
@@ -1082,11 +3370,12 @@ impl CocoWorkWindow {
                     .text_xs()
                     .font_weight(FontWeight::SEMIBOLD)
                     .text_color(rgb(colors.text_secondary))
-                    .child("Threads"),
+                    .child(t("sidebar.threads")),
             )
             .child(
                 div()
                     .id("new-session-btn")
+                    .tooltip(|cx| Tooltip::text(t("sidebar.new_thread"), cx))
                     .w(px(20.0))
                     .h(px(20.0))
                     .flex()
@@ -1109,43 +3398,46 @@ impl CocoWorkWindow {
 
     fn render_threads_list(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
         let colors = &self.theme.colors;
-        let search_query = self.search_text.to_lowercase();
-
-        // Filter threads based on search query
-        let filtered_threads: Vec<(usize, &ThreadEntry)> = self
-            .threads
-            .iter()
-            .enumerate()
-            .filter(|(_, thread)| {
-                if search_query.is_empty() {
-                    true
-                } else {
-                    thread.name.to_lowercase().contains(&search_query)
-                        || thread.agent_id.to_lowercase().contains(&search_query)
-                }
-            })
-            .collect();
+        let groups = self.thread_groups();
+        let no_results = groups.is_empty() && !self.search_text.is_empty();
+        let list_focused = self.threads_focus.is_focused(cx);
+        let highlighted = self.highlighted_thread.filter(|_| list_focused);
 
-        let no_results = filtered_threads.is_empty() && !search_query.is_empty();
+        // Rows keep their position among the shown threads, which keyboard highlighting counts in
+        let mut pos = 0;
+        let mut rows: Vec<AnyElement> = Vec::new();
+        for (group, threads) in groups {
+            let collapsed = self.search_text.is_empty() && self.collapsed_date_groups.contains(&group);
+            rows.push(self.render_date_group_header(group, threads.len(), collapsed, cx).into_any_element());
+            if collapsed {
+                continue;
+            }
+            for idx in threads {
+                rows.push(self.render_thread_row(pos, idx, highlighted == Some(pos), cx).into_any_element());
+                pos += 1;
+            }
+        }
 
         div()
             .id("threads-list")
+            .track_focus(&self.threads_focus)
+            .on_key_down(cx.listener(Self::handle_threads_key))
             .flex_1()
             .min_h_0()  // Critical: Allow shrinking for scrolling to work
             .overflow_y_scroll()
-            .px(px(8.0))
-            .py(px(4.0))
+            .px(dense(8.0))
+            .py(dense(4.0))
             .child(
                 div()
                     .flex()
                     .flex_col()
-                    .gap(px(2.0))
+                    .gap(dense(2.0))
                     // No results message
                     .when(no_results, |el| {
                         el.child(
                             div()
                                 .w_full()
-                                .py(px(16.0))
+                                .py(dense(16.0))
                                 .flex()
                                 .items_center()
                                 .justify_center()
@@ -1153,58 +3445,108 @@ impl CocoWorkWindow {
                                     div()
                                         .text_sm()
                                         .text_color(rgb(colors.text_secondary))
-                                        .child(format!("No threads match \"{}\"", self.search_text)),
+                                        .child(t_args("sidebar.no_match", &[("query", &self.search_text)])),
                                 ),
                         )
                     })
-                    .children(filtered_threads.iter().map(|(idx, session)| {
-                        let idx = *idx;
-                        let is_active = self.active_thread_idx == Some(idx);
-                        let session_name = session.name.clone();
-                        let session_id = session.id.clone();
-                        let agent_icon_name = match session.agent_id.as_str() {
-                            "claude-code" => IconName::AiClaude,
-                            "gemini" => IconName::AiGemini,
-                            _ => IconName::Chat,
-                        };
+                    .children(rows),
+            )
+    }
 
-                        div()
-                            .id(SharedString::from(format!("session-{}", session_id)))
-                            .w_full()
-                            .h(px(28.0))
-                            .px(px(8.0))
-                            .flex()
-                            .items_center()
-                            .gap(px(8.0))
-                            .rounded(px(4.0))
-                            .cursor_pointer()
-                            .when(is_active, |el| {
-                                el.bg(rgba(colors.primary.with_alpha(0.15)))
-                            })
-                            .when(!is_active, |el| el.hover(|s| s.bg(rgba(colors.hover))))
-                            .on_click(cx.listener(move |this, _, cx| {
-                                this.select_thread(idx, cx);
-                            }))
-                            .child(
-                                svg_icon(agent_icon_name, IconSize::Small)
-                                    .text_color(rgb(colors.text_secondary)),
-                            )
-                            .child(
-                                div()
-                                    .flex_1()
-                                    .min_w_0()
-                                    .text_sm()
-                                    .text_color(rgb(colors.text_primary))
-                                    .text_ellipsis()
-                                    .child(session_name),
-                            )
-                            .child(
-                                div()
-                                    .text_xs()
-                                    .text_color(rgb(colors.text_secondary))
-                                    .child(format!("{}", session.message_count)),
-                            )
-                    })),
+    fn render_date_group_header(
+        &self,
+        group: DateGroup,
+        count: usize,
+        collapsed: bool,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        let colors = &self.theme.colors;
+
+        div()
+            .id(SharedString::from(format!("date-group-{:?}", group)))
+            .w_full()
+            .px(dense(8.0))
+            .pt(dense(8.0))
+            .pb(dense(2.0))
+            .flex()
+            .items_center()
+            .gap(dense(6.0))
+            .cursor_pointer()
+            .text_xs()
+            .text_color(rgb(colors.text_secondary))
+            .hover(|s| s.text_color(rgb(colors.text_primary)))
+            .on_click(cx.listener(move |this, _, cx| {
+                if !this.collapsed_date_groups.remove(&group) {
+                    this.collapsed_date_groups.insert(group);
+                }
+                this.highlighted_thread = None;
+                cx.notify();
+            }))
+            .child(if collapsed { "▸" } else { "▾" })
+            .child(div().flex_1().child(group.label()))
+            .when(collapsed, |el| el.child(format!("{}", count)))
+    }
+
+    fn render_thread_row(
+        &self,
+        pos: usize,
+        idx: usize,
+        is_highlighted: bool,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let session = &self.threads[idx];
+        let is_active = self.active_thread_idx == Some(idx);
+        let depth = self.thread_depth(idx);
+        let agent_icon_name = match session.agent_id.as_str() {
+            "claude-code" => IconName::AiClaude,
+            "gemini" => IconName::AiGemini,
+            _ => IconName::Chat,
+        };
+
+        div()
+            .id(SharedString::from(format!("session-{}", session.id)))
+            .w_full()
+            .h(dense(28.0))
+            .px(dense(8.0))
+            .pl(dense(8.0 + 12.0 * depth as f32))
+            .flex()
+            .items_center()
+            .gap(dense(8.0))
+            .rounded(px(4.0))
+            .cursor_pointer()
+            .when(is_active, |el| {
+                el.bg(rgba(colors.primary.with_alpha(0.15)))
+            })
+            .when(!is_active, |el| el.hover(|s| s.bg(rgba(colors.hover))))
+            .when(is_highlighted, |el| {
+                el.border_1().border_color(rgb(colors.focus_ring))
+            })
+            .on_click(cx.listener(move |this, _, cx| {
+                this.highlighted_thread = Some(pos);
+                this.select_thread(idx, cx);
+            }))
+            .child(
+                svg_icon(agent_icon_name, IconSize::Small)
+                    .text_color(rgb(colors.text_secondary)),
+            )
+            .when(session.forked, |el| {
+                el.child(div().text_xs().text_color(rgb(colors.text_secondary)).child("⑂"))
+            })
+            .child(
+                div()
+                    .flex_1()
+                    .min_w_0()
+                    .text_sm()
+                    .text_color(rgb(colors.text_primary))
+                    .text_ellipsis()
+                    .child(session.name.clone()),
+            )
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(colors.text_secondary))
+                    .child(format!("{}", session.message_count)),
             )
     }
 
@@ -1225,1307 +3567,13057 @@ impl CocoWorkWindow {
             .flex_col()
             .overflow_hidden()  // Clip overflow from this panel, children handle their own scroll
             .bg(rgb(colors.panel_bg))
-            .child(self.render_session_header(cx))
-            .child(self.render_message_area(cx))
-            .child(self.render_input_bar(cx))
+            .when(!self.showing_other_view(), |el| el.child(self.render_session_header(cx)))
+            .when(!self.showing_other_view() && self.acp.is_auto_mode(), |el| {
+                el.child(self.render_auto_mode_banner(cx))
+            })
+            .when_some(
+                self.acp.active_session().and_then(|session| session.retry).filter(|_| !self.showing_other_view()),
+                |el, retry| el.child(self.render_retry_banner(retry, cx)),
+            )
+            .when_some(
+                self.acp.active_session().and_then(|session| session.forked_at).filter(|_| !self.showing_other_view()),
+                |el, forked_at| el.child(self.render_fork_banner(forked_at, cx)),
+            )
+            .when_some(
+                self.acp
+                    .active_session()
+                    .and_then(|session| session.issue_url.clone())
+                    .filter(|_| !self.showing_other_view()),
+                |el, url| el.child(self.render_issue_banner(url, cx)),
+            )
+            .when(!self.showing_other_view(), |el| el.children(self.render_pinned_strip(cx)))
+            .when(!self.showing_other_view() && self.review_mode, |el| el.child(self.render_review_bar(cx)))
+            .when_some(self.acp.manager.rollback_suggestion.clone(), |el, (agent_id, tag)| {
+                el.child(self.render_rollback_banner(&agent_id, &tag, cx))
+            })
+            .when_some(self.export_status.clone(), |el, status| {
+                el.child(self.render_export_banner(status, cx))
+            })
+            .when(!self.showing_other_view(), |el| {
+                el.when_some(
+                    self.acp.active_session().and_then(|session| session.isolation.as_ref()),
+                    |el, isolation| el.child(self.render_isolation_banner(&isolation.kind, &isolation.root, cx)),
+                )
+            })
+            .when(self.crash_report_pending || self.bundle_status.is_some(), |el| {
+                el.child(self.render_diagnostics_banner(cx))
+            })
+            .when(
+                !self.showing_other_view() && (self.acp.active_share().is_some() || self.share_error.is_some()),
+                |el| el.child(self.render_share_banner(cx)),
+            )
+            .when_some(self.link_error.clone(), |el, error| {
+                el.child(self.render_link_error_banner(error, cx))
+            })
+            .when_some(self.acp.manager.storage_recovery.clone(), |el, recovery| {
+                el.child(self.render_recovery_banner(recovery, cx))
+            })
+            .when(self.acp.manager.storage_read_only() && !self.read_only_dismissed, |el| {
+                el.child(self.render_read_only_banner(cx))
+            })
+            .map(|el| {
+                if self.show_dashboard {
+                    el.child(self.render_dashboard(cx))
+                } else if self.show_search {
+                    el.child(self.render_project_search(cx))
+                } else if self.show_audit {
+                    el.child(self.render_audit_log(cx))
+                } else if self.show_task_board {
+                    el.child(self.render_task_board(cx))
+                } else if self.acp.manager.comparison.is_some() {
+                    el.child(self.render_comparison(cx))
+                } else {
+                    el.child(self.render_message_area(cx))
+                        .when_some(
+                            self.acp.workspace_mismatch().filter(|_| self.show_workspace_warning),
+                            |el, thread_dir| el.child(self.render_workspace_warning(thread_dir, cx)),
+                        )
+                        .when(self.show_attachment_warning, |el| el.child(self.render_attachment_warning(cx)))
+                        .child(self.render_input_bar(cx))
+                }
+            })
+            .when(self.show_terminal_panel, |el| el.child(self.render_terminal_panel(cx)))
     }
 
-    fn render_session_header(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+    fn render_rollback_banner(&self, agent_id: &str, tag: &str, cx: &mut ViewContext<Self>) -> impl IntoElement {
         let colors = &self.theme.colors;
-        let is_preparing = self.acp.is_creating_thread() ||
-            self.acp.connection_state() == cocowork_ui::ConnectionState::Connecting;
-
-        let agent_name = self.acp.selected_agent_name();
-
-        // Determine title based on state
-        let (title, title_color, show_spinner) = if is_preparing {
-            (format!("{} Preparing...", agent_name), colors.text_secondary, true)
-        } else if let Some(session) = self.active_thread_idx.and_then(|idx| self.threads.get(idx)) {
-            (session.name.clone(), colors.text_primary, false)
-        } else {
-            ("New Thread".to_string(), colors.text_secondary, false)
-        };
+        let agent_name = self
+            .acp
+            .available_agents()
+            .into_iter()
+            .find(|a| a.id == agent_id)
+            .map(|a| a.name)
+            .unwrap_or_else(|| agent_id.to_string());
 
         div()
-            .id("session-header")
             .w_full()
-            .h(px(40.0))  // Aligned with context panel sections
-            .flex_shrink_0()  // Never shrink, keep fixed height
+            .flex_shrink_0()
             .px(px(16.0))
+            .py(px(8.0))
             .flex()
             .items_center()
-            .justify_between()
+            .gap(px(12.0))
+            .bg(rgba(colors.warning.with_alpha(0.12)))
             .border_b_1()
             .border_color(rgb(colors.border))
             .child(
                 div()
-                    .flex()
                     .flex_1()
-                    .min_w_0()
-                    .items_center()
-                    .gap(px(8.0))
-                    // Spinner or arrow (using SVG icons)
-                    .child(
-                        svg_icon(
-                            if show_spinner { IconName::Circle } else { IconName::ChevronRight },
-                            IconSize::XSmall
-                        ).text_color(rgb(colors.text_secondary)),
-                    )
-                    .child(
-                        div()
-                            .text_sm()
-                            .min_w_0()
-                            .font_weight(FontWeight::MEDIUM)
-                            .text_color(rgb(title_color))
-                            .text_ellipsis()
-                            .child(title),
-                    ),
+                    .text_sm()
+                    .text_color(rgb(colors.text_primary))
+                    .child(t_args("banner.incompatible", &[("agent", &agent_name)])),
             )
             .child(
                 div()
-                    .flex()
-                    .items_center()
-                    .gap(px(4.0))
-                    // New session button
-                    .child(
-                        div()
-                            .id("header-new-session-btn")
-                            .px(px(8.0))
-                            .py(px(4.0))
-                            .rounded(px(4.0))
-                            .cursor_pointer()
-                            .hover(|s| s.bg(rgba(colors.hover)))
-                            .on_click(cx.listener(|this, _, cx| {
-                                this.create_new_thread(cx);
-                            }))
-                            .child(
-                                div()
-                                    .text_sm()
-                                    .text_color(rgb(colors.text_secondary))
-                                    .child("+"),
-                            ),
-                    )
-                    // More options button
-                    .child(self.render_header_button("···")),
+                    .id("rollback-agent-btn")
+                    .px(px(10.0))
+                    .py(px(4.0))
+                    .rounded(px(6.0))
+                    .bg(rgb(colors.warning))
+                    .text_xs()
+                    .text_color(white())
+                    .cursor_pointer()
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.acp.manager.rollback_agent();
+                        cx.notify();
+                    }))
+                    .child(t_args("banner.roll_back", &[("tag", &tag)])),
             )
-    }
-
-    fn render_header_button(&self, label: &str) -> impl IntoElement {
-        let colors = &self.theme.colors;
-
-        div()
-            .px(px(8.0))
-            .py(px(4.0))
-            .rounded(px(4.0))
-            .cursor_pointer()
-            .hover(|s| s.bg(rgba(colors.hover)))
             .child(
                 div()
-                    .text_sm()
+                    .id("dismiss-rollback-btn")
+                    .text_xs()
                     .text_color(rgb(colors.text_secondary))
-                    .child(label.to_string()),
+                    .cursor_pointer()
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.acp.manager.rollback_suggestion = None;
+                        cx.notify();
+                    }))
+                    .child(t("common.dismiss")),
             )
     }
 
-    fn render_message_area(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
-        let colors = self.theme.colors.clone();
-        let messages = self.acp.messages().into_iter().cloned().collect::<Vec<_>>();
-        let mut tool_calls = self.acp.tool_calls().into_iter().cloned().collect::<Vec<_>>();
-        tool_calls.sort_by(|a, b| {
-            a.started_at
-                .cmp(&b.started_at)
-                .then_with(|| a.id.cmp(&b.id))
-        });
-        let has_timeline = !messages.is_empty() || !tool_calls.is_empty();
-        let timeline_children = if has_timeline {
-            self.build_timeline_children(&messages, &tool_calls, cx)
-        } else {
-            Vec::new()
-        };
+    fn render_auto_mode_banner(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let approved = self.acp.auto_approval_count();
 
-        // NOTE: In GPUI layouts, relying on `size_full()` (100% height) inside a flex item can
-        // fail to produce a definite height, which prevents overflow scrolling and causes the
-        // message list to expand and "push" other UI off-screen. Keep the scroll container as a
-        // real flex child (`flex_1 + min_h_0`) so it always has a constrained height.
         div()
-            .id("message-area-container")
-            .flex_1()
-            .min_h_0()  // Critical: Allow shrinking in flex column for scrolling to work
             .w_full()
-            .overflow_hidden()
+            .flex_shrink_0()
+            .px(px(16.0))
+            .py(px(8.0))
             .flex()
-            .flex_col()
+            .items_center()
+            .gap(px(12.0))
+            .bg(rgba(colors.error.with_alpha(0.18)))
+            .border_b_1()
+            .border_color(rgb(colors.error))
+            .child(
+                svg_icon(IconName::Terminal, IconSize::Small)
+                    .text_color(rgb(colors.error)),
+            )
             .child(
                 div()
-                    .id("message-area")
                     .flex_1()
-                    .min_h_0()
-                    .w_full()
-                    .overflow_y_scroll()
-                    .track_scroll(&self.message_scroll_handle)
                     .flex()
                     .flex_col()
-            .when(!has_timeline, |el| {
-                // Empty state - centered with nice styling
-                el.items_center()
-                    .justify_center()
-                    .p(px(32.0))
+                    .gap(px(2.0))
                     .child(
                         div()
-                            .flex()
-                            .flex_col()
-                            .items_center()
-                            .gap(px(16.0))
-                            // Logo image
-                            .child(
-                                img("images/cocowork-logo-256.png")
-                                    .size(px(200.0)),
-                            )
-                            // Title
-                            .child(
-                                div()
-                                    .text_lg()
-                                    .font_weight(FontWeight::MEDIUM)
-                                    .text_color(rgb(colors.text_primary))
-                                    .child("Start a conversation"),
-                            )
-                            // Subtitle
-                            .child(
-                                div()
-                                    .text_sm()
-                                    .text_color(rgb(colors.text_secondary))
-                                    .child("Type a message below to chat with CocoWork's Agent"),
-                            )
-                            // Hint
-                            .child(
-                                div()
-                                    .mt(px(8.0))
-                                    .px(px(12.0))
-                                    .py(px(6.0))
-                                    .rounded(px(6.0))
-                                    .bg(rgb(colors.surface))
-                                    .child(
-                                        div()
-                                            .text_xs()
-                                            .text_color(rgb(colors.text_secondary))
-                                            .child("Use 📁 to set workspace, + to attach files"),
-                                    ),
-                            ),
+                            .text_sm()
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .text_color(rgb(colors.text_primary))
+                            .child(t("banner.auto_mode")),
                     )
-            })
-            .when(has_timeline, move |el| {
-                el.px(px(16.0))
-                    .pt(px(16.0))
-                    .gap(px(12.0))
-                    .children(timeline_children)
-            }),
-            )  // Close the outer .child()
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t_args("banner.auto_mode_detail", &[("count", &approved)])),
+                    ),
+            )
+            .child(
+                div()
+                    .id("auto-mode-off-btn")
+                    .px(px(10.0))
+                    .py(px(4.0))
+                    .rounded(px(6.0))
+                    .bg(rgb(colors.error))
+                    .text_xs()
+                    .text_color(white())
+                    .cursor_pointer()
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.acp.toggle_auto_mode();
+                        cx.notify();
+                    }))
+                    .child(t("banner.turn_off")),
+            )
     }
 
-    fn build_timeline_children(
-        &mut self,
-        messages: &[MessageBlock],
-        tool_calls: &[ToolCallState],
-        cx: &mut ViewContext<Self>,
-    ) -> Vec<AnyElement> {
-        enum TimelineItem {
-            Message { idx: usize, msg: MessageBlock },
-            ToolCall { idx: usize, call: ToolCallState },
-        }
-
-        impl TimelineItem {
-            fn timestamp(&self) -> chrono::DateTime<chrono::Utc> {
-                match self {
-                    Self::Message { msg, .. } => msg.timestamp(),
-                    Self::ToolCall { call, .. } => call.started_at,
-                }
-            }
-
-            fn kind_order(&self) -> u8 {
-                match self {
-                    Self::ToolCall { .. } => 0,
-                    Self::Message { .. } => 1,
-                }
-            }
-
-            fn tie_index(&self) -> usize {
-                match self {
-                    Self::Message { idx, .. } => *idx,
-                    Self::ToolCall { idx, .. } => *idx,
-                }
-            }
-        }
-
-        let mut timeline = Vec::with_capacity(messages.len() + tool_calls.len());
-        for (idx, msg) in messages.iter().cloned().enumerate() {
-            timeline.push(TimelineItem::Message { idx, msg });
-        }
-        for (idx, call) in tool_calls.iter().cloned().enumerate() {
-            timeline.push(TimelineItem::ToolCall { idx, call });
-        }
+    /// Where the active thread's auto-retry loop is, with a button to stop it
+    fn render_retry_banner(&self, retry: RetryLoop, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let (message, button) = if retry.gave_up {
+            (t_args("banner.retry_gave_up", &[("count", &retry.limit)]), t("common.dismiss"))
+        } else {
+            (
+                t_args("banner.retrying", &[("attempt", &retry.attempt), ("limit", &retry.limit)]),
+                t("banner.stop_retrying"),
+            )
+        };
 
-        timeline.sort_by(|a, b| {
-            a.timestamp()
-                .cmp(&b.timestamp())
-                .then_with(|| a.kind_order().cmp(&b.kind_order()))
-                .then_with(|| a.tie_index().cmp(&b.tie_index()))
-        });
+        div()
+            .w_full()
+            .flex_shrink_0()
+            .px(px(16.0))
+            .py(px(8.0))
+            .flex()
+            .items_center()
+            .gap(px(12.0))
+            .bg(rgba(colors.primary.with_alpha(0.12)))
+            .border_b_1()
+            .border_color(rgb(colors.border))
+            .child(svg_icon(IconName::Terminal, IconSize::Small).text_color(rgb(colors.primary)))
+            .child(div().flex_1().text_sm().text_color(rgb(colors.text_primary)).child(message))
+            .child(
+                div()
+                    .id("stop-retry-btn")
+                    .px(px(10.0))
+                    .py(px(4.0))
+                    .rounded(px(6.0))
+                    .border_1()
+                    .border_color(rgb(colors.border))
+                    .text_xs()
+                    .text_color(rgb(colors.text_primary))
+                    .cursor_pointer()
+                    .hover(|el| el.bg(rgb(colors.hover)))
+                    .on_click(cx.listener(|this, _, cx| {
+                        if let Some(session_id) = this.acp.active_session_id.clone() {
+                            this.acp.manager.stop_retry(&session_id);
+                        }
+                        cx.notify();
+                    }))
+                    .child(button),
+            )
+    }
 
-        let mut children = Vec::with_capacity(timeline.len() + 1);
-        for item in timeline {
-            match item {
-                TimelineItem::Message { idx, msg } => {
-                    children.push(self.render_message(idx, &msg, cx).into_any_element());
-                }
-                TimelineItem::ToolCall { call, .. } => {
-                    children.push(self.render_tool_call(&call, cx).into_any_element());
-                }
-            }
+    /// The active thread's pinned messages, each jumping to its message; `None` with no pins
+    fn render_pinned_strip(&self, cx: &mut ViewContext<Self>) -> Option<impl IntoElement> {
+        let colors = &self.theme.colors;
+        let session = self.acp.active_session()?;
+        let pinned = pinned_entries(&session.messages, &session.pinned_messages);
+        if pinned.is_empty() {
+            return None;
         }
 
-        // Spacer at the bottom to avoid jitter and keep a comfortable gap.
-        children.push(
+        Some(
             div()
                 .w_full()
-                .h(px(32.0))
                 .flex_shrink_0()
-                .into_any_element(),
-        );
-
-        children
+                .px(px(16.0))
+                .py(px(6.0))
+                .flex()
+                .flex_wrap()
+                .items_center()
+                .gap(px(6.0))
+                .border_b_1()
+                .border_color(rgb(colors.border))
+                .child(
+                    div()
+                        .text_xs()
+                        .font_weight(FontWeight::SEMIBOLD)
+                        .text_color(rgb(colors.text_secondary))
+                        .child(t("pinned.title")),
+                )
+                .children(pinned.into_iter().map(|entry| {
+                    let idx = entry.message_idx;
+                    let anchor = entry.anchor;
+                    div()
+                        .max_w(px(280.0))
+                        .h(px(22.0))
+                        .pl(px(8.0))
+                        .pr(px(4.0))
+                        .flex()
+                        .items_center()
+                        .gap(px(4.0))
+                        .rounded(px(4.0))
+                        .border_1()
+                        .border_color(rgb(colors.border_subtle))
+                        .text_xs()
+                        .child(
+                            div()
+                                .id(SharedString::from(format!("pinned-{}", idx)))
+                                .min_w_0()
+                                .overflow_hidden()
+                                .whitespace_nowrap()
+                                .text_ellipsis()
+                                .text_color(rgb(colors.text_primary))
+                                .cursor_pointer()
+                                .hover(|s| s.text_color(rgb(colors.primary)))
+                                .on_click(cx.listener(move |this, _, cx| this.jump_to(anchor.clone(), cx)))
+                                .child(if entry.from_user {
+                                    t_args("pinned.you", &[("text", &entry.preview)])
+                                } else {
+                                    entry.preview
+                                }),
+                        )
+                        .child(
+                            div()
+                                .id(SharedString::from(format!("unpin-{}", idx)))
+                                .px(px(2.0))
+                                .text_color(rgb(colors.text_secondary))
+                                .cursor_pointer()
+                                .hover(|s| s.text_color(rgb(colors.text_primary)))
+                                .tooltip(|cx| Tooltip::text(t("timeline.unpin"), cx))
+                                .on_click(cx.listener(move |this, _, cx| {
+                                    this.acp.toggle_pinned(idx);
+                                    cx.notify();
+                                }))
+                                .child("×"),
+                        )
+                })),
+        )
     }
 
-    fn render_message(&mut self, idx: usize, message: &MessageBlock, cx: &mut ViewContext<Self>) -> impl IntoElement {
-        let colors = self.theme.colors.clone();
-
-        match message {
-            // User message: Dark rounded pill style (like Zed's input box)
-            MessageBlock::User { content, .. } => {
-                let text = content
-                    .iter()
-                    .filter_map(|c| match c {
-                        ContentBlock::Text { text } => Some(text.clone()),
-                        _ => None,
-                    })
-                    .collect::<Vec<_>>()
-                    .join("");
+    /// Review mode's flag filter, with how many messages carry each flag
+    fn render_review_bar(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let flags = self.acp.active_session().map(|session| session.message_flags.clone()).unwrap_or_default();
+        let count = |flag: Option<MessageFlag>| {
+            flags.values().filter(|flags| flag.map_or(true, |flag| flags.contains(&flag))).count()
+        };
+        let choices = std::iter::once((None, t("review.all").to_string()))
+            .chain(MessageFlag::ALL.into_iter().map(|flag| (Some(flag), flag.emoji().to_string())));
 
+        div()
+            .w_full()
+            .flex_shrink_0()
+            .px(px(16.0))
+            .py(px(6.0))
+            .flex()
+            .items_center()
+            .gap(px(6.0))
+            .bg(rgba(colors.primary.with_alpha(0.08)))
+            .border_b_1()
+            .border_color(rgb(colors.border))
+            .child(
                 div()
-                    .w_full()
-                    .flex_shrink_0()
-                    .overflow_hidden()
-                    .child(
-                        div()
-                            .w_full()
-                            .px(px(16.0))
-                            .py(px(12.0))
-                            .rounded(px(8.0))
-                            .bg(rgb(colors.input_bg))
-                            .overflow_hidden()
-                            .child(
-                                div()
-                                    .w_full()
-                                    .text_sm()
-                                    .text_color(rgb(colors.text_primary))
-                                    .overflow_x_hidden()
-                                    .child(text),
-                            ),
-                    )
-            }
-
-            // Thinking block: Zed style with left border and lightbulb icon
-            MessageBlock::Thought { content, .. } => {
-                let text = content
-                    .iter()
-                    .filter_map(|c| match c {
-                        ContentBlock::Text { text } => Some(text.clone()),
-                        _ => None,
+                    .mr(px(6.0))
+                    .text_xs()
+                    .font_weight(FontWeight::SEMIBOLD)
+                    .text_color(rgb(colors.text_secondary))
+                    .child(t("review.title")),
+            )
+            .children(choices.map(|(flag, label)| {
+                let selected = flag == self.review_flag;
+                div()
+                    .id(SharedString::from(format!("review-{}", flag.map_or("all", |flag| flag.as_str()))))
+                    .px(px(8.0))
+                    .py(px(2.0))
+                    .rounded(px(4.0))
+                    .text_xs()
+                    .cursor_pointer()
+                    .when(selected, |el| {
+                        el.bg(rgba(colors.primary.with_alpha(0.2))).text_color(rgb(colors.text_primary))
                     })
-                    .collect::<Vec<_>>()
-                    .join("");
+                    .when(!selected, |el| {
+                        el.text_color(rgb(colors.text_secondary)).hover(|el| el.bg(rgb(colors.hover)))
+                    })
+                    .on_click(cx.listener(move |this, _, cx| {
+                        this.review_flag = flag;
+                        cx.notify();
+                    }))
+                    .child(format!("{} {}", label, count(flag)))
+            }))
+            .child(div().flex_1())
+            .child(
+                div()
+                    .id("exit-review-btn")
+                    .text_xs()
+                    .text_color(rgb(colors.text_secondary))
+                    .cursor_pointer()
+                    .hover(|el| el.text_color(rgb(colors.primary)))
+                    .on_click(cx.listener(|this, _, cx| this.toggle_review_mode(cx)))
+                    .child(t("header.exit_review")),
+            )
+    }
 
-                let is_collapsed = self.collapsed_thinking.contains(&idx);
-                let markdown = self.render_markdown_view(&format!("thought-{}", idx), &text, true, cx);
+    /// Which thread the active one was forked from and where, linking back to it
+    fn render_fork_banner(&self, forked_at: usize, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let parent = self
+            .acp
+            .active_session()
+            .and_then(|session| session.parent_session_id.as_ref())
+            .and_then(|id| self.threads.iter().find(|thread| &thread.id == id));
+        let message = match parent {
+            Some(parent) => t_args("banner.forked_from", &[("name", &parent.name), ("count", &forked_at)]),
+            None => t_args("banner.forked", &[("count", &forked_at)]),
+        };
 
-                div()
-                    .w_full()
-                    .flex_shrink_0()
-                    .overflow_hidden()
-                    .flex()
-                    .flex_col()
+        div()
+            .w_full()
+            .flex_shrink_0()
+            .px(px(16.0))
+            .py(px(6.0))
+            .flex()
+            .items_center()
+            .gap(px(8.0))
+            .border_b_1()
+            .border_color(rgb(colors.border))
+            .text_xs()
+            .text_color(rgb(colors.text_secondary))
+            .child("⑂")
+            .child(div().flex_1().min_w_0().text_ellipsis().child(message))
+            .when(parent.is_some(), |el| {
+                el.child(
+                    div()
+                        .id("open-fork-parent-btn")
+                        .cursor_pointer()
+                        .hover(|el| el.text_color(rgb(colors.primary)))
+                        .on_click(cx.listener(|this, _, cx| this.open_fork_parent(cx)))
+                        .child(t("banner.open_original")),
+                )
+            })
+    }
+
+    /// The issue the active thread was started from, linking to it
+    fn render_issue_banner(&self, url: String, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let issue = IssueRef::parse(&url).map(|reference| reference.label()).unwrap_or_else(|| url.clone());
+
+        div()
+            .w_full()
+            .flex_shrink_0()
+            .px(px(16.0))
+            .py(px(6.0))
+            .flex()
+            .items_center()
+            .gap(px(8.0))
+            .border_b_1()
+            .border_color(rgb(colors.border))
+            .text_xs()
+            .text_color(rgb(colors.text_secondary))
+            .child(div().flex_1().min_w_0().text_ellipsis().child(t_args("banner.from_issue", &[("issue", &issue)])))
+            .child(
+                div()
+                    .id("open-issue-btn")
+                    .cursor_pointer()
+                    .hover(|el| el.text_color(rgb(colors.primary)))
+                    .on_click(cx.listener(move |_, _, _| {
+                        if let Err(e) = cocowork_core::agent::open_url(&url) {
+                            tracing::warn!("Failed to open {}: {}", url, e);
+                        }
+                    }))
+                    .child(t("banner.open_issue")),
+            )
+    }
+
+    fn render_recovery_banner(&self, recovery: StorageRecovery, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let message = match &recovery.backup {
+            Some(backup) => t_args(
+                "banner.database_restore",
+                &[("time", &backup.created_at.with_timezone(&chrono::Local).format("%b %-d, %H:%M"))],
+            ),
+            None => t("banner.database_no_backup").to_string(),
+        };
+
+        div()
+            .w_full()
+            .flex_shrink_0()
+            .px(px(16.0))
+            .py(px(8.0))
+            .flex()
+            .items_center()
+            .gap(px(12.0))
+            .bg(rgba(colors.error.with_alpha(0.12)))
+            .border_b_1()
+            .border_color(rgb(colors.border))
+            .child(
+                div()
+                    .flex_1()
+                    .flex()
+                    .flex_col()
+                    .gap(px(2.0))
+                    .child(div().text_sm().text_color(rgb(colors.text_primary)).child(message))
+                    .child(div().text_xs().text_color(rgb(colors.text_secondary)).child(recovery.error.clone())),
+            )
+            .when(recovery.backup.is_some(), |el| {
+                el.child(
+                    div()
+                        .id("restore-backup-btn")
+                        .px(px(10.0))
+                        .py(px(4.0))
+                        .rounded(px(6.0))
+                        .bg(rgb(colors.primary))
+                        .text_xs()
+                        .text_color(white())
+                        .cursor_pointer()
+                        .on_click(cx.listener(|this, _, cx| {
+                            if let Err(e) = this.acp.manager.recover_storage() {
+                                if let Some(recovery) = this.acp.manager.storage_recovery.as_mut() {
+                                    recovery.error = t_args("banner.restore_failed", &[("error", &e)]);
+                                }
+                            }
+                            cx.notify();
+                        }))
+                        .child(t("banner.restore_backup")),
+                )
+            })
+            .child(
+                div()
+                    .id("dismiss-recovery-btn")
+                    .text_xs()
+                    .text_color(rgb(colors.text_secondary))
+                    .cursor_pointer()
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.acp.manager.storage_recovery = None;
+                        cx.notify();
+                    }))
+                    .child(t("common.dismiss")),
+            )
+    }
+
+    /// Offer a diagnostic bundle after a crash, or say where the last one went
+    fn render_diagnostics_banner(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let (message, tint) = match &self.bundle_status {
+            _ if self.crash_report_pending => (t("banner.crashed").to_string(), colors.warning),
+            Some(Ok(path)) => (t_args("banner.bundle_saved", &[("path", &path.display())]), colors.success),
+            Some(Err(e)) => (e.clone(), colors.error),
+            None => (String::new(), colors.border),
+        };
+        let saved = self.bundle_status.clone().and_then(Result::ok).filter(|_| !self.crash_report_pending);
+
+        div()
+            .w_full()
+            .flex_shrink_0()
+            .px(px(16.0))
+            .py(px(8.0))
+            .flex()
+            .items_center()
+            .gap(px(12.0))
+            .bg(rgba(tint.with_alpha(0.12)))
+            .border_b_1()
+            .border_color(rgb(colors.border))
+            .child(
+                div()
+                    .flex_1()
+                    .min_w_0()
+                    .text_sm()
+                    .text_color(rgb(colors.text_primary))
+                    .text_ellipsis()
+                    .child(message),
+            )
+            .when(self.crash_report_pending, |el| {
+                el.child(
+                    div()
+                        .id("crash-bundle-btn")
+                        .px(px(10.0))
+                        .py(px(4.0))
+                        .rounded(px(6.0))
+                        .bg(rgb(colors.warning))
+                        .text_xs()
+                        .text_color(white())
+                        .cursor_pointer()
+                        .on_click(cx.listener(|this, _, cx| {
+                            this.generate_diagnostic_bundle(cx);
+                        }))
+                        .child(t("banner.generate_bundle")),
+                )
+            })
+            .when_some(saved, |el, path| {
+                el.child(
+                    div()
+                        .id("reveal-bundle-btn")
+                        .text_xs()
+                        .text_color(rgb(colors.primary))
+                        .cursor_pointer()
+                        .on_click(cx.listener(move |_, _, cx| {
+                            cx.reveal_path(&path);
+                        }))
+                        .child(t("search.show_in_folder")),
+                )
+            })
+            .child(
+                div()
+                    .id("dismiss-diagnostics-btn")
+                    .text_xs()
+                    .text_color(rgb(colors.text_secondary))
+                    .cursor_pointer()
+                    .on_click(cx.listener(|this, _, cx| {
+                        if this.crash_report_pending {
+                            this.dismiss_crash_report();
+                        } else {
+                            this.bundle_status = None;
+                        }
+                        cx.notify();
+                    }))
+                    .child(t("common.dismiss")),
+            )
+    }
+
+    fn render_read_only_banner(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+
+        div()
+            .w_full()
+            .flex_shrink_0()
+            .px(px(16.0))
+            .py(px(8.0))
+            .flex()
+            .items_center()
+            .gap(px(12.0))
+            .bg(rgba(colors.warning.with_alpha(0.12)))
+            .border_b_1()
+            .border_color(rgb(colors.border))
+            .child(
+                div()
+                    .flex_1()
+                    .min_w_0()
+                    .text_sm()
+                    .text_color(rgb(colors.text_primary))
+                    .child(t("banner.read_only")),
+            )
+            .child(
+                div()
+                    .id("take-over-storage-btn")
+                    .text_xs()
+                    .text_color(rgb(colors.primary))
+                    .cursor_pointer()
+                    .on_click(cx.listener(|this, _, cx| {
+                        if let Err(e) = this.acp.manager.take_over_storage() {
+                            tracing::warn!("Failed to take over the database: {}", e);
+                        }
+                        cx.notify();
+                    }))
+                    .child(t("banner.read_only_retry")),
+            )
+            .child(
+                div()
+                    .id("dismiss-read-only-btn")
+                    .text_xs()
+                    .text_color(rgb(colors.text_secondary))
+                    .cursor_pointer()
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.read_only_dismissed = true;
+                        cx.notify();
+                    }))
+                    .child(t("common.dismiss")),
+            )
+    }
+
+    fn render_export_banner(&self, status: Result<std::path::PathBuf, String>, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let (message, tint) = match &status {
+            Ok(path) => (t_args("banner.exported", &[("path", &path.display())]), colors.success),
+            Err(e) => (e.clone(), colors.error),
+        };
+
+        div()
+            .w_full()
+            .flex_shrink_0()
+            .px(px(16.0))
+            .py(px(8.0))
+            .flex()
+            .items_center()
+            .gap(px(12.0))
+            .bg(rgba(tint.with_alpha(0.12)))
+            .border_b_1()
+            .border_color(rgb(colors.border))
+            .child(
+                div()
+                    .flex_1()
+                    .min_w_0()
+                    .text_sm()
+                    .text_color(rgb(colors.text_primary))
+                    .text_ellipsis()
+                    .child(message),
+            )
+            .when_some(status.ok(), |el, path| {
+                el.child(
+                    div()
+                        .id("reveal-export-btn")
+                        .text_xs()
+                        .text_color(rgb(colors.primary))
+                        .cursor_pointer()
+                        .on_click(cx.listener(move |_, _, cx| {
+                            cx.reveal_path(&path);
+                        }))
+                        .child(t("search.show_in_folder")),
+                )
+            })
+            .child(
+                div()
+                    .id("dismiss-export-btn")
+                    .text_xs()
+                    .text_color(rgb(colors.text_secondary))
+                    .cursor_pointer()
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.export_status = None;
+                        cx.notify();
+                    }))
+                    .child(t("common.dismiss")),
+            )
+    }
+
+    /// Where the active thread works instead of the workspace, with its changes a click away
+    fn render_isolation_banner(&self, kind: &IsolationKind, root: &Path, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let message = match kind {
+            IsolationKind::Worktree { branch, .. } => t_args("banner.isolated_worktree", &[("branch", branch)]),
+            IsolationKind::Copy { .. } => t_args("banner.isolated_copy", &[("dir", &root.display())]),
+        };
+        let busy = self.acp.is_loading();
+
+        div()
+            .w_full()
+            .flex_shrink_0()
+            .px(px(16.0))
+            .py(px(8.0))
+            .flex()
+            .items_center()
+            .gap(px(12.0))
+            .bg(rgba(colors.primary.with_alpha(0.08)))
+            .border_b_1()
+            .border_color(rgb(colors.border))
+            .child(
+                div()
+                    .flex_1()
+                    .min_w_0()
+                    .text_sm()
+                    .text_color(rgb(colors.text_primary))
+                    .text_ellipsis()
+                    .child(message),
+            )
+            .when(!busy, |el| {
+                el.child(
+                    div()
+                        .id("review-isolation-btn")
+                        .text_xs()
+                        .text_color(rgb(colors.primary))
+                        .cursor_pointer()
+                        .on_click(cx.listener(|this, _, cx| this.review_isolation(cx)))
+                        .child(t("banner.review_changes")),
+                )
+            })
+    }
+
+    /// Offer to go back to the thread's workspace or carry on in a new thread
+    fn render_workspace_warning(&self, thread_dir: PathBuf, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let workspace = self.acp.get_working_dir();
+        let action = |id: &'static str, label: &'static str| {
+            div()
+                .id(id)
+                .px(px(10.0))
+                .py(px(4.0))
+                .rounded(px(4.0))
+                .text_xs()
+                .bg(rgb(colors.surface))
+                .text_color(rgb(colors.text_primary))
+                .cursor_pointer()
+                .hover(|el| el.bg(rgb(colors.border)))
+                .child(label)
+        };
+
+        div()
+            .w_full()
+            .flex_shrink_0()
+            .px(px(16.0))
+            .py(px(8.0))
+            .flex()
+            .items_center()
+            .gap(px(12.0))
+            .bg(rgba(colors.warning.with_alpha(0.12)))
+            .border_t_1()
+            .border_color(rgb(colors.border))
+            .child(
+                div()
+                    .flex_1()
+                    .min_w_0()
+                    .text_sm()
+                    .text_color(rgb(colors.text_primary))
+                    .child(t_args(
+                        "banner.workspace_mismatch",
+                        &[("thread", &thread_dir.display()), ("workspace", &workspace.display())],
+                    )),
+            )
+            .child(action("switch-workspace-btn", t("banner.switch_workspace")).on_click(cx.listener(
+                move |this, _, cx| {
+                    this.set_workspace(thread_dir.clone(), cx);
+                    this.handle_send_message(cx);
+                },
+            )))
+            .child(action("new-thread-here-btn", t("banner.new_thread_here")).on_click(cx.listener(
+                |this, _, cx| {
+                    this.acp.active_session_id = None;
+                    this.handle_send_message(cx);
+                },
+            )))
+            .child(
+                div()
+                    .id("dismiss-workspace-warning-btn")
+                    .text_xs()
+                    .text_color(rgb(colors.text_secondary))
+                    .cursor_pointer()
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.show_workspace_warning = false;
+                        cx.notify();
+                    }))
+                    .child(t("common.dismiss")),
+            )
+    }
+
+    /// Offer to cut attachments down to the size limit, link them, or embed them anyway
+    fn render_attachment_warning(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let estimate = self.prompt_size_estimate(self.message_input.read(cx).content());
+        let limit = self.acp.manager.attachment_limit.unwrap_or(DEFAULT_ATTACHMENT_LIMIT);
+        // Each file gets an even share of the limit
+        let truncate = AttachmentMode::Truncate((limit / self.attached_files.len().max(1) as u64).max(1));
+        let action = |id: &'static str, label: &'static str, mode: AttachmentMode| {
+            div()
+                .id(id)
+                .px(px(10.0))
+                .py(px(4.0))
+                .rounded(px(4.0))
+                .text_xs()
+                .bg(rgb(colors.surface))
+                .text_color(rgb(colors.text_primary))
+                .cursor_pointer()
+                .hover(|el| el.bg(rgb(colors.border)))
+                .on_click(cx.listener(move |this, _, cx| {
+                    this.attachment_choice = Some(mode);
+                    this.handle_send_message(cx);
+                }))
+                .child(label)
+        };
+
+        div()
+            .w_full()
+            .flex_shrink_0()
+            .px(px(16.0))
+            .py(px(8.0))
+            .flex()
+            .items_center()
+            .gap(px(12.0))
+            .bg(rgba(colors.warning.with_alpha(0.12)))
+            .border_t_1()
+            .border_color(rgb(colors.border))
+            .child(
+                div()
+                    .flex_1()
+                    .min_w_0()
+                    .text_sm()
+                    .text_color(rgb(colors.text_primary))
+                    .child(t_args(
+                        "banner.attachments_over_limit",
+                        &[("size", &format_size(estimate.attachments)), ("limit", &format_size(limit))],
+                    )),
+            )
+            .child(action("truncate-attachments-btn", t("banner.truncate_attachments"), truncate))
+            .child(action("link-attachments-btn", t("banner.link_attachments"), AttachmentMode::Link))
+            .child(action("embed-attachments-btn", t("banner.send_anyway"), AttachmentMode::Embed))
+            .child(
+                div()
+                    .id("dismiss-attachment-warning-btn")
+                    .text_xs()
+                    .text_color(rgb(colors.text_secondary))
+                    .cursor_pointer()
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.show_attachment_warning = false;
+                        cx.notify();
+                    }))
+                    .child(t("common.dismiss")),
+            )
+    }
+
+    fn render_link_error_banner(&self, error: String, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+
+        div()
+            .w_full()
+            .flex_shrink_0()
+            .px(px(16.0))
+            .py(px(8.0))
+            .flex()
+            .items_center()
+            .gap(px(12.0))
+            .bg(rgba(colors.error.with_alpha(0.12)))
+            .border_b_1()
+            .border_color(rgb(colors.border))
+            .child(
+                div()
+                    .flex_1()
+                    .min_w_0()
+                    .text_sm()
+                    .text_color(rgb(colors.text_primary))
+                    .text_ellipsis()
+                    .child(error),
+            )
+            .child(
+                div()
+                    .id("dismiss-link-error-btn")
+                    .text_xs()
+                    .text_color(rgb(colors.text_secondary))
+                    .cursor_pointer()
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.link_error = None;
+                        cx.notify();
+                    }))
+                    .child(t("common.dismiss")),
+            )
+    }
+
+    fn render_session_header(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let is_preparing = self.acp.is_creating_thread() ||
+            self.acp.connection_state() == cocowork_ui::ConnectionState::Connecting;
+
+        let agent_name = self.acp.selected_agent_name();
+
+        // Determine title based on state
+        let (title, title_color, show_spinner) = if is_preparing {
+            (format!("{} Preparing...", agent_name), colors.text_secondary, true)
+        } else if let Some(session) = self.active_thread_idx.and_then(|idx| self.threads.get(idx)) {
+            (session.name.clone(), colors.text_primary, false)
+        } else {
+            (t("header.new_thread").to_string(), colors.text_secondary, false)
+        };
+        let thread_dir = self
+            .active_thread_idx
+            .and_then(|idx| self.threads.get(idx))
+            .map(|thread| thread.working_dir.clone())
+            .filter(|_| !is_preparing);
+        let dir_mismatch = self.acp.workspace_mismatch().is_some();
+        let has_outline = self.acp.messages().iter().any(|msg| matches!(msg, MessageBlock::User { .. }));
+
+        div()
+            .id("session-header")
+            .w_full()
+            .h(px(40.0))  // Aligned with context panel sections
+            .flex_shrink_0()  // Never shrink, keep fixed height
+            .px(px(16.0))
+            .flex()
+            .items_center()
+            .justify_between()
+            .border_b_1()
+            .border_color(rgb(colors.border))
+            .child(
+                div()
+                    .flex()
+                    .flex_1()
+                    .min_w_0()
+                    .items_center()
+                    .gap(px(8.0))
+                    // Spinner or arrow (using SVG icons)
+                    .child(
+                        svg_icon(
+                            if show_spinner { IconName::Circle } else { IconName::ChevronRight },
+                            IconSize::XSmall
+                        ).text_color(rgb(colors.text_secondary)),
+                    )
+                    .child(
+                        div()
+                            .text_sm()
+                            .min_w_0()
+                            .font_weight(FontWeight::MEDIUM)
+                            .text_color(rgb(title_color))
+                            .text_ellipsis()
+                            .child(title),
+                    )
+                    // Where the thread's agent works, flagged once the workspace moves on
+                    .when_some(thread_dir, |el, dir| {
+                        let name = dir
+                            .file_name()
+                            .map(|name| name.to_string_lossy().to_string())
+                            .unwrap_or_else(|| dir.display().to_string());
+                        let full_path = dir.display().to_string();
+                        let tint = if dir_mismatch { colors.warning } else { colors.text_secondary };
+                        el.child(
+                            div()
+                                .id("thread-working-dir")
+                                .flex_shrink_0()
+                                .flex()
+                                .items_center()
+                                .gap(px(4.0))
+                                .text_xs()
+                                .text_color(rgb(tint))
+                                .child(svg_icon(IconName::Folder, IconSize::XSmall).text_color(rgb(tint)))
+                                .child(name)
+                                .tooltip(move |cx| Tooltip::text(full_path.clone(), cx)),
+                        )
+                    }),
+            )
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(4.0))
+                    // Agent stopped for being idle
+                    .when(self.acp.active_session_id.is_some() && self.acp.is_idle_disconnected(), |el| {
+                        el.child(
+                            div()
+                                .px(px(8.0))
+                                .text_xs()
+                                .text_color(rgb(colors.text_secondary))
+                                .child(t("header.idle")),
+                        )
+                    })
+                    // Dry run toggle
+                    .when(self.acp.active_session_id.is_some(), |el| {
+                        let dry_run = self.acp.is_dry_run();
+                        el.child(
+                            div()
+                                .id("dry-run-toggle")
+                                .px(px(8.0))
+                                .py(px(4.0))
+                                .rounded(px(4.0))
+                                .cursor_pointer()
+                                .when(dry_run, |el| el.bg(rgba(colors.warning.with_alpha(0.2))))
+                                .hover(|s| s.bg(rgba(colors.hover)))
+                                .on_click(cx.listener(|this, _, cx| {
+                                    this.acp.toggle_dry_run();
+                                    cx.notify();
+                                }))
+                                .child(
+                                    div()
+                                        .text_xs()
+                                        .text_color(if dry_run {
+                                            rgb(colors.warning)
+                                        } else {
+                                            rgb(colors.text_secondary)
+                                        })
+                                        .child(if dry_run { t("header.dry_run_on") } else { t("header.dry_run") }),
+                                ),
+                        )
+                    })
+                    // Auto mode toggle
+                    .when(self.acp.active_session_id.is_some(), |el| {
+                        let auto_mode = self.acp.is_auto_mode();
+                        el.child(
+                            div()
+                                .id("auto-mode-toggle")
+                                .px(px(8.0))
+                                .py(px(4.0))
+                                .rounded(px(4.0))
+                                .cursor_pointer()
+                                .when(auto_mode, |el| el.bg(rgba(colors.error.with_alpha(0.2))))
+                                .hover(|s| s.bg(rgba(colors.hover)))
+                                .on_click(cx.listener(|this, _, cx| {
+                                    this.acp.toggle_auto_mode();
+                                    cx.notify();
+                                }))
+                                .child(
+                                    div()
+                                        .text_xs()
+                                        .text_color(if auto_mode {
+                                            rgb(colors.error)
+                                        } else {
+                                            rgb(colors.text_secondary)
+                                        })
+                                        .child(if auto_mode { t("header.auto_on") } else { t("header.auto") }),
+                                ),
+                        )
+                    })
+                    // Compact conversation button
+                    .when(self.acp.can_compact() || self.acp.is_compacting(), |el| {
+                        let compacting = self.acp.is_compacting();
+                        el.child(
+                            div()
+                                .id("compact-btn")
+                                .px(px(8.0))
+                                .py(px(4.0))
+                                .rounded(px(4.0))
+                                .when(!compacting, |el| {
+                                    el.cursor_pointer()
+                                        .hover(|s| s.bg(rgba(colors.hover)))
+                                        .on_click(cx.listener(|this, _, cx| {
+                                            if let Err(e) = this.acp.compact_active_session() {
+                                                tracing::warn!("Failed to compact thread: {}", e);
+                                            }
+                                            cx.notify();
+                                        }))
+                                })
+                                .child(
+                                    div()
+                                        .text_xs()
+                                        .text_color(rgb(colors.text_secondary))
+                                        .child(if compacting { t("header.compacting") } else { t("header.compact") }),
+                                ),
+                        )
+                    })
+                    // Outline of the thread's turns
+                    .when(has_outline, |el| {
+                        el.child(
+                            div()
+                                .relative()
+                                .child(
+                                    div()
+                                        .id("header-outline-btn")
+                                        .px(px(8.0))
+                                        .py(px(4.0))
+                                        .rounded(px(4.0))
+                                        .cursor_pointer()
+                                        .hover(|s| s.bg(rgba(colors.hover)))
+                                        .on_click(cx.listener(|this, _, cx| {
+                                            this.show_outline_menu = !this.show_outline_menu;
+                                            this.show_thread_menu = false;
+                                            cx.notify();
+                                        }))
+                                        .child(
+                                            div()
+                                                .text_xs()
+                                                .text_color(rgb(colors.text_secondary))
+                                                .child(t("header.outline")),
+                                        ),
+                                )
+                                .when(self.show_outline_menu, |el| el.child(self.render_outline_menu(cx))),
+                        )
+                    })
+                    // Compare agents button
+                    .child(
+                        div()
+                            .id("header-compare-btn")
+                            .px(px(8.0))
+                            .py(px(4.0))
+                            .rounded(px(4.0))
+                            .cursor_pointer()
+                            .hover(|s| s.bg(rgba(colors.hover)))
+                            .on_click(cx.listener(|this, _, cx| {
+                                this.open_compare_dialog(cx);
+                            }))
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .child(t("header.compare")),
+                            ),
+                    )
+                    // New session button
+                    .child(
+                        div()
+                            .id("header-new-session-btn")
+                            .tooltip(|cx| Tooltip::text(t("sidebar.new_thread"), cx))
+                            .px(px(8.0))
+                            .py(px(4.0))
+                            .rounded(px(4.0))
+                            .cursor_pointer()
+                            .hover(|s| s.bg(rgba(colors.hover)))
+                            .on_click(cx.listener(|this, _, cx| {
+                                this.create_new_thread(cx);
+                            }))
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .child("+"),
+                            ),
+                    )
+                    // More options button
+                    .child(
+                        div()
+                            .relative()
+                            .child(
+                                div()
+                                    .id("thread-menu-btn")
+                                    .tooltip(|cx| Tooltip::text(t("header.thread_actions"), cx))
+                                    .px(px(8.0))
+                                    .py(px(4.0))
+                                    .rounded(px(4.0))
+                                    .cursor_pointer()
+                                    .hover(|s| s.bg(rgba(colors.hover)))
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.toggle_thread_menu(cx);
+                                    }))
+                                    .child(
+                                        div()
+                                            .text_sm()
+                                            .text_color(rgb(colors.text_secondary))
+                                            .child("···"),
+                                    ),
+                            )
+                            .when(self.show_thread_menu, |el| {
+                                el.child(self.render_thread_menu(cx))
+                            }),
+                    ),
+            )
+    }
+
+    /// The thread's prompts, newest last, each jumping to its turn
+    fn render_outline_menu(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let messages: Vec<MessageBlock> = self.acp.messages().into_iter().cloned().collect();
+        let outline = thread_outline(&messages);
+        let latest_call = latest_tool_call(self.acp.tool_calls());
+        let now = chrono::Utc::now();
+
+        div()
+            .id("outline-menu")
+            .absolute()
+            .top(px(28.0))
+            .right(px(0.0))
+            .w(px(320.0))
+            .max_h(px(400.0))
+            .overflow_y_scroll()
+            .bg(rgb(colors.surface_elevated))
+            .border_1()
+            .border_color(rgb(colors.border))
+            .rounded(px(8.0))
+            .shadow_lg()
+            .py(px(4.0))
+            .flex()
+            .flex_col()
+            .children(outline.into_iter().map(|entry| {
+                let anchor = entry.anchor.clone();
+                div()
+                    .id(SharedString::from(format!("outline-turn-{}", entry.turn)))
+                    .w_full()
+                    .px(px(12.0))
+                    .py(px(6.0))
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .text_sm()
+                    .cursor_pointer()
+                    .hover(|s| s.bg(rgba(colors.hover)))
+                    .on_click(cx.listener(move |this, _, cx| this.jump_to(anchor.clone(), cx)))
+                    .child(
+                        div()
+                            .flex_shrink_0()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(entry.turn.to_string()),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .min_w_0()
+                            .overflow_hidden()
+                            .whitespace_nowrap()
+                            .text_ellipsis()
+                            .text_color(rgb(colors.text_primary))
+                            .child(entry.preview),
+                    )
+                    .child(
+                        div()
+                            .flex_shrink_0()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(format_relative(entry.at, now)),
+                    )
+            }))
+            .child(div().my(px(4.0)).h(px(1.0)).bg(rgb(colors.border_subtle)))
+            .child(
+                div()
+                    .id("outline-latest-tool-call")
+                    .w_full()
+                    .px(px(12.0))
+                    .py(px(6.0))
+                    .text_sm()
+                    .map(|el| match latest_call {
+                        Some(anchor) => el
+                            .text_color(rgb(colors.text_primary))
+                            .cursor_pointer()
+                            .hover(|s| s.bg(rgba(colors.hover)))
+                            .on_click(cx.listener(move |this, _, cx| this.jump_to(anchor.clone(), cx))),
+                        None => el.text_color(rgb(colors.text_secondary)),
+                    })
+                    .child(t("timeline.jump_latest_tool_call")),
+            )
+            .child(
+                div()
+                    .id("outline-bottom")
+                    .w_full()
+                    .px(px(12.0))
+                    .py(px(6.0))
+                    .text_sm()
+                    .text_color(rgb(colors.text_primary))
+                    .cursor_pointer()
+                    .hover(|s| s.bg(rgba(colors.hover)))
+                    .on_click(cx.listener(|this, _, cx| this.jump_to_bottom(cx)))
+                    .child(t("timeline.jump_bottom")),
+            )
+    }
+
+    fn render_thread_menu(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let has_thread = self.acp.active_session_id.is_some();
+
+        div()
+            .absolute()
+            .top(px(28.0))
+            .right(px(0.0))
+            .w(px(200.0))
+            .bg(rgb(colors.surface_elevated))
+            .border_1()
+            .border_color(rgb(colors.border))
+            .rounded(px(8.0))
+            .shadow_lg()
+            .py(px(4.0))
+            .flex()
+            .flex_col()
+            .children(
+                [
+                    ("thread-menu-export-md", t("header.export_markdown"), ExportFormat::Markdown),
+                    ("thread-menu-export-html", t("header.export_html"), ExportFormat::Html),
+                ]
+                .into_iter()
+                .map(|(id, label, format)| {
+                    div()
+                        .id(id)
+                        .w_full()
+                        .px(px(12.0))
+                        .py(px(8.0))
+                        .text_sm()
+                        .when(has_thread, |el| {
+                            el.text_color(rgb(colors.text_primary))
+                                .cursor_pointer()
+                                .hover(|s| s.bg(rgba(colors.hover)))
+                                .on_click(cx.listener(move |this, _, cx| {
+                                    this.export_thread(format, cx);
+                                }))
+                        })
+                        .when(!has_thread, |el| el.text_color(rgb(colors.text_secondary)))
+                        .child(label)
+                }),
+            )
+            .child(div().my(px(4.0)).h(px(1.0)).bg(rgb(colors.border_subtle)))
+            .child(
+                div()
+                    .id("thread-menu-collapse-thinking")
+                    .w_full()
+                    .px(px(12.0))
+                    .py(px(8.0))
+                    .text_sm()
+                    .when(has_thread, |el| {
+                        el.text_color(rgb(colors.text_primary))
+                            .cursor_pointer()
+                            .hover(|s| s.bg(rgba(colors.hover)))
+                            .on_click(cx.listener(|this, _, cx| this.collapse_all_thinking(cx)))
+                    })
+                    .when(!has_thread, |el| el.text_color(rgb(colors.text_secondary)))
+                    .child(t("header.collapse_thinking")),
+            )
+            .child(
+                div()
+                    .id("thread-menu-toggle-thinking")
+                    .w_full()
+                    .px(px(12.0))
+                    .py(px(8.0))
+                    .text_sm()
+                    .when(has_thread, |el| {
+                        el.text_color(rgb(colors.text_primary))
+                            .cursor_pointer()
+                            .hover(|s| s.bg(rgba(colors.hover)))
+                            .on_click(cx.listener(|this, _, cx| this.toggle_thread_thoughts(cx)))
+                    })
+                    .when(!has_thread, |el| el.text_color(rgb(colors.text_secondary)))
+                    .child(if self.acp.thoughts_visible() {
+                        t("header.hide_thinking")
+                    } else {
+                        t("header.show_thinking")
+                    }),
+            )
+            .child(
+                div()
+                    .id("thread-menu-review")
+                    .w_full()
+                    .px(px(12.0))
+                    .py(px(8.0))
+                    .text_sm()
+                    .when(has_thread, |el| {
+                        el.text_color(rgb(colors.text_primary))
+                            .cursor_pointer()
+                            .hover(|s| s.bg(rgba(colors.hover)))
+                            .on_click(cx.listener(|this, _, cx| this.toggle_review_mode(cx)))
+                    })
+                    .when(!has_thread, |el| el.text_color(rgb(colors.text_secondary)))
+                    .child(if self.review_mode { t("header.exit_review") } else { t("header.review_flagged") }),
+            )
+            .child(div().my(px(4.0)).h(px(1.0)).bg(rgb(colors.border_subtle)))
+            .child(
+                div()
+                    .id("thread-menu-share")
+                    .w_full()
+                    .px(px(12.0))
+                    .py(px(8.0))
+                    .text_sm()
+                    .when(has_thread, |el| {
+                        el.text_color(rgb(colors.text_primary))
+                            .cursor_pointer()
+                            .hover(|s| s.bg(rgba(colors.hover)))
+                            .on_click(cx.listener(|this, _, cx| this.toggle_sharing(cx)))
+                    })
+                    .when(!has_thread, |el| el.text_color(rgb(colors.text_secondary)))
+                    .child(if self.acp.active_share().is_some() {
+                        t("header.stop_sharing")
+                    } else {
+                        t("header.share_live")
+                    }),
+            )
+            .child(
+                div()
+                    .id("thread-menu-pull-request")
+                    .w_full()
+                    .px(px(12.0))
+                    .py(px(8.0))
+                    .text_sm()
+                    .when(has_thread, |el| {
+                        el.text_color(rgb(colors.text_primary))
+                            .cursor_pointer()
+                            .hover(|s| s.bg(rgba(colors.hover)))
+                            .on_click(cx.listener(|this, _, cx| this.open_pull_request_wizard(cx)))
+                    })
+                    .when(!has_thread, |el| el.text_color(rgb(colors.text_secondary)))
+                    .child(t("header.open_pull_request")),
+            )
+            .child(
+                div()
+                    .id("thread-menu-capabilities")
+                    .w_full()
+                    .px(px(12.0))
+                    .py(px(8.0))
+                    .text_sm()
+                    .text_color(rgb(colors.text_primary))
+                    .cursor_pointer()
+                    .hover(|s| s.bg(rgba(colors.hover)))
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.show_thread_menu = false;
+                        this.show_capabilities = true;
+                        cx.notify();
+                    }))
+                    .child(t("header.agent_capabilities")),
+            )
+    }
+
+    /// Start serving the active thread for others to watch, copying the link, or stop
+    fn toggle_sharing(&mut self, cx: &mut ViewContext<Self>) {
+        self.show_thread_menu = false;
+        if self.acp.active_share().is_some() {
+            self.acp.stop_sharing();
+        } else {
+            match self.acp.start_sharing() {
+                Ok(url) => {
+                    cx.write_to_clipboard(ClipboardItem::new_string(url));
+                    self.share_error = None;
+                }
+                Err(e) => self.share_error = Some(t_args("banner.share_failed", &[("error", &e)])),
+            }
+        }
+        cx.notify();
+    }
+
+    fn render_share_banner(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let share = self.acp.active_share();
+        let (message, tint) = match (share, &self.share_error) {
+            (Some(share), _) => (
+                t_args("banner.sharing", &[("url", &share.url()), ("viewers", &share.viewers())]),
+                colors.primary,
+            ),
+            (None, Some(error)) => (error.clone(), colors.error),
+            (None, None) => (String::new(), colors.primary),
+        };
+        let url = share.map(|share| share.url());
+
+        div()
+            .w_full()
+            .flex_shrink_0()
+            .px(px(16.0))
+            .py(px(8.0))
+            .flex()
+            .items_center()
+            .gap(px(12.0))
+            .bg(rgba(tint.with_alpha(0.12)))
+            .border_b_1()
+            .border_color(rgb(colors.border))
+            .child(
+                div()
+                    .flex_1()
+                    .min_w_0()
+                    .text_sm()
+                    .text_color(rgb(colors.text_primary))
+                    .text_ellipsis()
+                    .child(message),
+            )
+            .when_some(url, |el, url| {
+                el.child(
+                    div()
+                        .id("copy-share-link-btn")
+                        .text_xs()
+                        .text_color(rgb(colors.primary))
+                        .cursor_pointer()
+                        .on_click(cx.listener(move |_, _, cx| {
+                            cx.write_to_clipboard(ClipboardItem::new_string(url.clone()));
+                        }))
+                        .child(t("banner.copy_link")),
+                )
+                .child(
+                    div()
+                        .id("stop-sharing-btn")
+                        .text_xs()
+                        .text_color(rgb(colors.text_secondary))
+                        .cursor_pointer()
+                        .on_click(cx.listener(|this, _, cx| {
+                            this.acp.stop_sharing();
+                            cx.notify();
+                        }))
+                        .child(t("header.stop_sharing")),
+                )
+            })
+            .when(share.is_none(), |el| {
+                el.child(
+                    div()
+                        .id("dismiss-share-btn")
+                        .text_xs()
+                        .text_color(rgb(colors.text_secondary))
+                        .cursor_pointer()
+                        .on_click(cx.listener(|this, _, cx| {
+                            this.share_error = None;
+                            cx.notify();
+                        }))
+                        .child(t("common.dismiss")),
+                )
+            })
+    }
+
+    fn render_message_area(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = self.theme.colors.clone();
+        let messages = self.acp.messages().into_iter().cloned().collect::<Vec<_>>();
+        let mut tool_calls = self.acp.tool_calls().into_iter().cloned().collect::<Vec<_>>();
+        tool_calls.sort_by(|a, b| {
+            a.started_at
+                .cmp(&b.started_at)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        let has_timeline = !messages.is_empty() || !tool_calls.is_empty();
+        let timeline_children = if has_timeline {
+            self.build_timeline_children(&messages, &tool_calls, cx)
+        } else {
+            Vec::new()
+        };
+
+        // NOTE: In GPUI layouts, relying on `size_full()` (100% height) inside a flex item can
+        // fail to produce a definite height, which prevents overflow scrolling and causes the
+        // message list to expand and "push" other UI off-screen. Keep the scroll container as a
+        // real flex child (`flex_1 + min_h_0`) so it always has a constrained height.
+        let latest_call = latest_tool_call(tool_calls.iter());
+        let show_jump_buttons = has_timeline && !self.stick_to_bottom;
+
+        div()
+            .id("message-area-container")
+            .relative()
+            .flex_1()
+            .min_h_0()  // Critical: Allow shrinking in flex column for scrolling to work
+            .w_full()
+            .overflow_hidden()
+            .flex()
+            .flex_col()
+            .child(
+                div()
+                    .id("message-area")
+                    .flex_1()
+                    .min_h_0()
+                    .w_full()
+                    .overflow_y_scroll()
+                    .track_scroll(&self.message_scroll_handle)
+                    .flex()
+                    .flex_col()
+            .when(!has_timeline, |el| {
+                // Empty state - centered with nice styling
+                el.items_center()
+                    .justify_center()
+                    .p(dense(32.0))
+                    .child(
+                        div()
+                            .flex()
+                            .flex_col()
+                            .items_center()
+                            .gap(dense(16.0))
+                            // Logo image
+                            .child(
+                                img("images/cocowork-logo-256.png")
+                                    .size(px(200.0)),
+                            )
+                            // Title
+                            .child(
+                                div()
+                                    .text_lg()
+                                    .font_weight(FontWeight::MEDIUM)
+                                    .text_color(rgb(colors.text_primary))
+                                    .child(t("timeline.empty_title")),
+                            )
+                            // Subtitle
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .child(t("timeline.empty_hint")),
+                            )
+                            // Hint
+                            .child(
+                                div()
+                                    .mt(dense(8.0))
+                                    .px(dense(12.0))
+                                    .py(dense(6.0))
+                                    .rounded(px(6.0))
+                                    .bg(rgb(colors.surface))
+                                    .child(
+                                        div()
+                                            .text_xs()
+                                            .text_color(rgb(colors.text_secondary))
+                                            .child(t("timeline.empty_tip")),
+                                    ),
+                            ),
+                    )
+            })
+            .when(has_timeline, move |el| {
+                el.px(dense(16.0))
+                    .pt(dense(16.0))
+                    .gap(dense(12.0))
+                    .children(timeline_children)
+            }),
+            )  // Close the outer .child()
+            .when(show_jump_buttons, |el| el.child(self.render_jump_buttons(latest_call, cx)))
+    }
+
+    /// Shortcuts shown while scrolled away from the end of the timeline
+    fn render_jump_buttons(
+        &self,
+        latest_call: Option<TimelineAnchor>,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let button = |id: &'static str, label: &'static str| {
+            div()
+                .id(id)
+                .px(dense(10.0))
+                .py(dense(4.0))
+                .rounded(px(12.0))
+                .bg(rgb(colors.surface_elevated))
+                .border_1()
+                .border_color(rgb(colors.border))
+                .shadow_md()
+                .text_xs()
+                .text_color(rgb(colors.text_secondary))
+                .cursor_pointer()
+                .hover(|s| s.text_color(rgb(colors.text_primary)))
+                .child(label)
+        };
+
+        div()
+            .absolute()
+            .bottom(dense(12.0))
+            .right(dense(16.0))
+            .flex()
+            .gap(dense(6.0))
+            .when_some(latest_call, |el, anchor| {
+                el.child(
+                    button("jump-latest-tool-call", t("timeline.jump_latest_tool_call"))
+                        .on_click(cx.listener(move |this, _, cx| this.jump_to(anchor.clone(), cx))),
+                )
+            })
+            .child(
+                button("jump-bottom", t("timeline.jump_bottom"))
+                    .on_click(cx.listener(|this, _, cx| this.jump_to_bottom(cx))),
+            )
+    }
+
+    fn build_timeline_children(
+        &mut self,
+        messages: &[MessageBlock],
+        tool_calls: &[ToolCallState],
+        cx: &mut ViewContext<Self>,
+    ) -> Vec<AnyElement> {
+        let mut timeline = Vec::with_capacity(messages.len() + tool_calls.len());
+        // Review mode leaves only the flagged messages
+        let review = self.review_mode.then(|| {
+            let flags = self.acp.active_session().map(|session| session.message_flags.clone()).unwrap_or_default();
+            (flags, self.review_flag)
+        });
+        let in_review = |idx: usize| match &review {
+            Some((flags, flag)) => {
+                flags.get(&idx).is_some_and(|flags| flag.map_or(!flags.is_empty(), |flag| flags.contains(&flag)))
+            }
+            None => true,
+        };
+        for (idx, msg) in messages.iter().cloned().enumerate().filter(|(idx, _)| in_review(*idx)) {
+            timeline.push(TimelineItem::Message { idx, msg });
+        }
+        if review.is_none() {
+            for (idx, call) in tool_calls.iter().cloned().enumerate() {
+                timeline.push(TimelineItem::ToolCall { idx, call });
+            }
+        }
+
+        timeline.sort_by(|a, b| {
+            a.timestamp()
+                .cmp(&b.timestamp())
+                .then_with(|| a.kind_order().cmp(&b.kind_order()))
+                .then_with(|| a.tie_index().cmp(&b.tie_index()))
+        });
+
+        let entries: Vec<TimelineEntry> = timeline.iter().map(TimelineItem::entry).collect();
+        let spans = group_turn_actions(&entries);
+        // The running turn's group stays open so its progress shows
+        let prompts = entries.iter().filter(|entry| **entry == TimelineEntry::Prompt).count();
+        let current_turn = prompts.saturating_sub(1);
+        let running = self.acp.is_loading();
+        let mut timeline: Vec<Option<TimelineItem>> = timeline.into_iter().map(Some).collect();
+
+        let show_thoughts = self.acp.thoughts_visible();
+        let scroll_target = self.plan_scroll_target.take();
+        let jump_target = self.jump_target.take();
+        let mut scroll_to = None;
+        let mut children = Vec::with_capacity(timeline.len() + 1);
+        for span in spans {
+            let (turn, range) = match span {
+                TimelineSpan::Single(i) => (None, i..i + 1),
+                TimelineSpan::Actions { turn, range } => (Some(turn), range),
+            };
+            let items: Vec<TimelineItem> = range.filter_map(|i| timeline[i].take()).collect();
+            let reaches_target =
+                scroll_target.is_some_and(|target| items.iter().any(|item| item.timestamp() >= target));
+            if scroll_to.is_none() && reaches_target {
+                scroll_to = Some(children.len());
+            }
+            let Some(turn) = turn else {
+                for item in items {
+                    if jump_target.as_ref().is_some_and(|anchor| item.matches(anchor)) {
+                        scroll_to = Some(children.len());
+                    }
+                    self.push_timeline_item(item, show_thoughts, &mut children, cx);
+                }
+                continue;
+            };
+
+            let summary = ActionSummary::of(items.iter().filter_map(|item| match item {
+                TimelineItem::ToolCall { call, .. } => Some(call),
+                TimelineItem::Message { .. } => None,
+            }));
+            if jump_target.as_ref().is_some_and(|anchor| items.iter().any(|item| item.matches(anchor))) {
+                self.open_action_groups.insert(turn, true);
+            }
+            let open = self
+                .open_action_groups
+                .get(&turn)
+                .copied()
+                .unwrap_or(running && turn == current_turn);
+            children.push(self.render_action_group(turn, summary, open, cx).into_any_element());
+            if open {
+                for item in items {
+                    if jump_target.as_ref().is_some_and(|anchor| item.matches(anchor)) {
+                        scroll_to = Some(children.len());
+                    }
+                    self.push_timeline_item(item, show_thoughts, &mut children, cx);
+                }
+            } else if let Some(turn_end) = self.acp.turn_end().cloned() {
+                // A turn that stopped mid-actions keeps its footer in view
+                let ends_here = items
+                    .iter()
+                    .any(|item| matches!(item, TimelineItem::Message { idx, .. } if *idx == turn_end.message_idx));
+                if ends_here {
+                    children.push(self.render_turn_end(turn_end, cx).into_any_element());
+                }
+            }
+        }
+
+        let follow_ups = self.acp.follow_ups();
+        if !follow_ups.is_empty() {
+            children.push(self.render_follow_ups(follow_ups, cx).into_any_element());
+        }
+
+        let last_commit = self.acp.active_session().and_then(|session| session.commits.last().cloned());
+        let uncommitted = !self.acp.is_loading() && self.acp.has_uncommitted_changes();
+        if uncommitted || last_commit.is_some() {
+            children.push(self.render_commit_bar(uncommitted, last_commit, cx).into_any_element());
+        }
+
+        let terminals = match self.acp.active_session_id.as_deref() {
+            Some(session_id) => self.acp.manager.live_terminals(session_id),
+            None => Vec::new(),
+        };
+        self.terminal_inputs.retain(|id, _| terminals.iter().any(|terminal| &terminal.id == id));
+        for terminal in terminals {
+            let input = self.terminal_input(&terminal.id, cx);
+            children.push(self.render_live_terminal(&terminal, input, cx).into_any_element());
+        }
+
+        let question = self.acp.active_session_id.as_deref().and_then(|id| self.acp.manager.question_for(id));
+        if let Some(message) = question.map(|question| question.message.clone()) {
+            children.push(self.render_question_card(message, cx).into_any_element());
+        }
+
+        if let Some(rate) = self.acp.stream_rate().copied() {
+            children.push(self.render_stream_status(rate).into_any_element());
+        }
+
+        // Spacer at the bottom to avoid jitter and keep a comfortable gap.
+        children.push(
+            div()
+                .w_full()
+                .h(dense(32.0))
+                .flex_shrink_0()
+                .into_any_element(),
+        );
+
+        if std::mem::take(&mut self.scroll_to_bottom_pending) {
+            scroll_to = scroll_to.or(Some(children.len() - 1));
+        }
+        if let Some(ix) = scroll_to {
+            self.message_scroll_handle.scroll_to_item(ix);
+        }
+        self.rendered_timeline_len = children.len();
+
+        children
+    }
+
+    /// Render a message, with its turn's footer if the turn ended on it, or a tool call
+    fn push_timeline_item(
+        &mut self,
+        item: TimelineItem,
+        show_thoughts: bool,
+        children: &mut Vec<AnyElement>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        match item {
+            TimelineItem::Message { msg: MessageBlock::Thought { .. }, .. } if !show_thoughts => {}
+            TimelineItem::Message { idx, msg } => {
+                children.push(self.render_message(idx, &msg, cx).into_any_element());
+                if let Some(turn_end) = self.acp.turn_end().filter(|end| end.message_idx == idx).cloned() {
+                    children.push(self.render_turn_end(turn_end, cx).into_any_element());
+                }
+            }
+            TimelineItem::ToolCall { call, .. } => {
+                children.push(self.render_tool_call(&call, cx).into_any_element());
+            }
+        }
+    }
+
+    /// Header of a turn's tool calls: how many, what they touched, and a toggle to show them
+    fn render_action_group(
+        &self,
+        turn: usize,
+        summary: ActionSummary,
+        open: bool,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let arrow_icon = if open { IconName::ChevronDown } else { IconName::ChevronRight };
+        let mut stats = Vec::new();
+        if summary.files_touched > 0 {
+            stats.push(t_args("timeline.actions_files", &[("count", &summary.files_touched)]));
+        }
+        if summary.commands_run > 0 {
+            stats.push(t_args("timeline.actions_commands", &[("count", &summary.commands_run)]));
+        }
+        let failed = (summary.failed > 0).then(|| t_args("timeline.actions_failed", &[("count", &summary.failed)]));
+
+        div()
+            .id(SharedString::from(format!("action-group-{}", turn)))
+            .w_full()
+            .flex_shrink_0()
+            .px(dense(8.0))
+            .py(dense(4.0))
+            .flex()
+            .items_center()
+            .gap(dense(8.0))
+            .rounded(px(6.0))
+            .text_xs()
+            .cursor_pointer()
+            .hover(|el| el.bg(rgba(colors.hover)))
+            .on_click(cx.listener(move |this, _, cx| {
+                this.open_action_groups.insert(turn, !open);
+                cx.notify();
+            }))
+            .child(svg_icon(arrow_icon, IconSize::XSmall).text_color(rgb(colors.text_secondary)))
+            .when(summary.running, |el| {
+                // Any steadily advancing clock turns the spinner
+                let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default();
+                el.child(div().text_color(rgb(colors.primary)).child(spinner_frame(now).to_string()))
+            })
+            .child(
+                div()
+                    .text_color(rgb(colors.text_primary))
+                    .child(t_args("timeline.actions", &[("count", &summary.actions)])),
+            )
+            .when(!stats.is_empty(), |el| {
+                el.child(div().text_color(rgb(colors.text_secondary)).child(stats.join(" · ")))
+            })
+            .when_some(failed, |el, failed| el.child(div().text_color(rgb(colors.error)).child(failed)))
+    }
+
+    /// Spinner, elapsed time and token rate under the last message while a reply streams
+    ///
+    /// Redrawn with each poll of the notification stream, which also turns the spinner.
+    fn render_stream_status(&self, rate: StreamRate) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let now = std::time::Instant::now();
+        let elapsed = rate.elapsed(now);
+        let mut status = t_args(
+            "timeline.streaming",
+            &[("elapsed", &format_duration(chrono::Duration::seconds(elapsed.as_secs() as i64)))],
+        );
+        if let Some(tokens_per_sec) = rate.tokens_per_sec(now) {
+            status.push_str(&t_args(
+                "timeline.stream_rate",
+                &[("rate", &format!("{:.0}", tokens_per_sec)), ("tokens", &rate.tokens())],
+            ));
+        }
+
+        div()
+            .w_full()
+            .flex_shrink_0()
+            .flex()
+            .items_center()
+            .gap(dense(6.0))
+            .text_xs()
+            .text_color(rgba(colors.text_secondary.with_alpha(0.7)))
+            .child(div().text_color(rgb(colors.primary)).child(spinner_frame(elapsed).to_string()))
+            .child(status)
+    }
+
+    /// How the last turn ended, with what to do next when it didn't finish
+    fn render_turn_end(&self, turn_end: TurnEnd, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        // The app closed mid-reply; carry on from the partial output or start the reply over
+        if turn_end.interrupted {
+            let mut actions = vec![(t("timeline.resume"), None)];
+            actions.extend(self.acp.last_prompt_text().map(|prompt| (t("timeline.regenerate"), Some(prompt))));
+            let guidance = Some(t("timeline.interrupted_detail").to_string());
+            return self.render_turn_end_row(t("timeline.interrupted"), guidance, actions, colors.warning, cx);
+        }
+        let stop_reason = match (turn_end.stop_reason, &turn_end.error) {
+            (None, Some(_)) => Some(StopReason::Error),
+            (stop_reason, _) => stop_reason,
+        };
+        let Some(stop_reason) = stop_reason else {
+            return div();
+        };
+        let guidance = match stop_reason {
+            StopReason::EndTurn => None,
+            StopReason::MaxTokens => {
+                Some(t("timeline.max_tokens").to_string())
+            }
+            StopReason::MaxTurnRequests => {
+                Some(t("timeline.max_turn_requests").to_string())
+            }
+            StopReason::Refusal => Some(t("timeline.refusal").to_string()),
+            StopReason::Cancelled => Some(t("timeline.cancelled").to_string()),
+            StopReason::Error => Some(match &turn_end.error {
+                Some(error) => t_args("timeline.error_detail", &[("error", error)]),
+                None => t("timeline.error").to_string(),
+            }),
+        };
+        // Continue a cut-off reply; resend a prompt that didn't run to the end
+        let action = match stop_reason {
+            StopReason::MaxTokens | StopReason::MaxTurnRequests => Some((t("common.continue"), None)),
+            StopReason::Cancelled | StopReason::Error => self.acp.last_prompt_text().map(|prompt| (t("common.retry"), Some(prompt))),
+            StopReason::EndTurn | StopReason::Refusal => None,
+        };
+        let color = match stop_reason {
+            StopReason::EndTurn => colors.text_secondary,
+            StopReason::Error | StopReason::Refusal => colors.error,
+            _ => colors.warning,
+        };
+        self.render_turn_end_row(stop_reason_label(stop_reason), guidance, action.into_iter().collect(), color, cx)
+    }
+
+    /// A turn footer: what happened, what to do about it, and buttons that send a prompt
+    ///
+    /// An action without a prompt sends "Continue".
+    fn render_turn_end_row(
+        &self,
+        label: &'static str,
+        guidance: Option<String>,
+        actions: Vec<(&'static str, Option<String>)>,
+        color: ThemeRgba,
+        cx: &mut ViewContext<Self>,
+    ) -> Div {
+        let colors = &self.theme.colors;
+        div()
+            .w_full()
+            .flex_shrink_0()
+            .flex()
+            .items_center()
+            .gap(dense(8.0))
+            .text_xs()
+            .child(
+                div()
+                    .flex_shrink_0()
+                    .text_color(rgba(color.with_alpha(0.8)))
+                    .child(label),
+            )
+            .when_some(guidance, |el, guidance| {
+                el.child(
+                    div()
+                        .flex_1()
+                        .min_w_0()
+                        .text_color(rgb(colors.text_secondary))
+                        .child(guidance),
+                )
+            })
+            .children(actions.into_iter().enumerate().map(|(i, (label, prompt))| {
+                div()
+                    .id(SharedString::from(format!("turn-end-action-{}", i)))
+                    .flex_shrink_0()
+                    .px(dense(8.0))
+                    .py(dense(2.0))
+                    .rounded(px(4.0))
+                    .border_1()
+                    .border_color(rgb(colors.border))
+                    .text_color(rgb(colors.text_primary))
+                    .cursor_pointer()
+                    .hover(|el| el.bg(rgb(colors.hover)))
+                    .on_click(cx.listener(move |this, _, cx| {
+                        let text = prompt.clone().unwrap_or_else(|| "Continue".to_string());
+                        if this.acp.workspace_mismatch().is_some() {
+                            // Held in the input until the workspace warning is answered
+                            this.message_input.update(cx, |input, cx| input.set_content(text, cx));
+                            this.show_workspace_warning = true;
+                        } else {
+                            this.acp.start_send_message(text);
+                        }
+                        cx.notify();
+                    }))
+                    .child(label)
+            }))
+    }
+
+    /// Suggested next prompts under the last message; clicking one puts it in the input
+    fn render_follow_ups(&self, follow_ups: Vec<String>, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        div()
+            .flex()
+            .flex_wrap()
+            .gap(dense(6.0))
+            .children(follow_ups.into_iter().enumerate().map(|(i, prompt)| {
+                div()
+                    .id(SharedString::from(format!("follow-up-{}", i)))
+                    .px(dense(10.0))
+                    .py(dense(4.0))
+                    .rounded(px(12.0))
+                    .border_1()
+                    .border_color(rgb(colors.border))
+                    .text_xs()
+                    .text_color(rgb(colors.text_secondary))
+                    .cursor_pointer()
+                    .hover(|el| el.bg(rgb(colors.hover)).text_color(rgb(colors.text_primary)))
+                    .on_click(cx.listener({
+                        let prompt = prompt.clone();
+                        move |this, _, cx| {
+                            let prompt = prompt.clone();
+                            this.message_input.update(cx, |input, cx| input.set_content(prompt, cx));
+                            cx.notify();
+                        }
+                    }))
+                    .child(prompt)
+            }))
+    }
+
+    /// The thread's last commit, and a button to commit what the agent changed since
+    fn render_commit_bar(
+        &self,
+        uncommitted: bool,
+        last_commit: Option<TaskCommit>,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        div()
+            .w_full()
+            .flex_shrink_0()
+            .flex()
+            .items_center()
+            .gap(dense(8.0))
+            .text_xs()
+            .when(uncommitted, |el| {
+                el.child(
+                    div()
+                        .id("commit-changes-btn")
+                        .flex_shrink_0()
+                        .px(dense(8.0))
+                        .py(dense(2.0))
+                        .rounded(px(4.0))
+                        .border_1()
+                        .border_color(rgb(colors.border))
+                        .text_color(rgb(colors.text_primary))
+                        .cursor_pointer()
+                        .hover(|el| el.bg(rgb(colors.hover)))
+                        .on_click(cx.listener(|this, _, cx| this.open_commit_dialog(cx)))
+                        .child(t("commit.changes")),
+                )
+            })
+            .when_some(last_commit, |el, commit| {
+                let hash = commit.hash.clone();
+                el.child(
+                    div()
+                        .id("last-commit")
+                        .flex_1()
+                        .min_w_0()
+                        .text_ellipsis()
+                        .text_color(rgb(colors.text_secondary))
+                        .cursor_pointer()
+                        .tooltip(|cx| Tooltip::text(t("commit.copy_hash"), cx))
+                        .on_click(move |_, cx| cx.write_to_clipboard(ClipboardItem::new_string(hash.clone())))
+                        .child(t_args(
+                            "commit.committed",
+                            &[("hash", &commit.short_hash()), ("subject", &commit.subject())],
+                        )),
+                )
+            })
+    }
+
+    /// A question the agent's turn is waiting on, with a box to answer it
+    fn render_question_card(&self, message: String, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let can_reply = !self.question_input.read(cx).content().trim().is_empty();
+        div()
+            .w_full()
+            .flex_shrink_0()
+            .flex()
+            .flex_col()
+            .gap(dense(8.0))
+            .px(dense(14.0))
+            .py(dense(12.0))
+            .rounded(px(8.0))
+            .border_1()
+            .border_color(rgb(colors.primary))
+            .bg(rgb(colors.surface_elevated))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(dense(6.0))
+                    .text_xs()
+                    .font_weight(FontWeight::SEMIBOLD)
+                    .text_color(rgb(colors.text_secondary))
+                    .child(svg_icon(IconName::Chat, IconSize::Small).text_color(rgb(colors.primary)))
+                    .child(t("question.title")),
+            )
+            .child(div().text_sm().text_color(rgb(colors.text_primary)).child(message))
+            .child(
+                div()
+                    .w_full()
+                    .px(dense(10.0))
+                    .py(dense(6.0))
+                    .rounded(px(6.0))
+                    .bg(rgb(colors.input_bg))
+                    .border_1()
+                    .border_color(rgb(colors.border))
+                    .text_sm()
+                    .child(self.question_input.clone()),
+            )
+            .child(
+                div()
+                    .flex()
+                    .justify_end()
+                    .gap(dense(8.0))
+                    .child(
+                        div()
+                            .id("skip-question-btn")
+                            .px(dense(12.0))
+                            .py(dense(6.0))
+                            .rounded(px(6.0))
+                            .bg(rgb(colors.surface))
+                            .text_sm()
+                            .text_color(rgb(colors.text_secondary))
+                            .cursor_pointer()
+                            .hover(|el| el.bg(rgb(colors.border)))
+                            .on_click(cx.listener(|this, _, cx| this.reply_to_question(false, cx)))
+                            .child(t("question.skip")),
+                    )
+                    .child(
+                        div()
+                            .id("reply-question-btn")
+                            .px(dense(12.0))
+                            .py(dense(6.0))
+                            .rounded(px(6.0))
+                            .bg(rgb(colors.primary))
+                            .text_sm()
+                            .text_color(white())
+                            .when(can_reply, |el| {
+                                el.cursor_pointer()
+                                    .hover(|el| el.bg(rgb(colors.primary_hover)))
+                                    .on_click(cx.listener(|this, _, cx| this.reply_to_question(true, cx)))
+                            })
+                            .when(!can_reply, |el| el.opacity(0.5))
+                            .child(t("question.reply")),
+                    ),
+            )
+    }
+
+    /// The input box of a live terminal, made the first time it's shown
+    fn terminal_input(&mut self, terminal_id: &str, cx: &mut ViewContext<Self>) -> View<TextInput> {
+        if let Some(input) = self.terminal_inputs.get(terminal_id) {
+            return input.clone();
+        }
+        let input = cx.new_view(|cx| {
+            let mut input = TextInput::new(cx);
+            input.set_multi_line(true);
+            input.set_placeholder(t("terminal.input_placeholder"));
+            input
+        });
+        let id = terminal_id.to_string();
+        cx.subscribe(&input, move |this, input, event: &TextInputEvent, cx| match event {
+            TextInputEvent::Submit => this.send_to_terminal(&id, &input, cx),
+        })
+        .detach();
+        self.terminal_inputs.insert(terminal_id.to_string(), input.clone());
+        input
+    }
+
+    /// Send the line typed in a terminal's input box to it
+    fn send_to_terminal(&mut self, terminal_id: &str, input: &View<TextInput>, cx: &mut ViewContext<Self>) {
+        let Some(session_id) = self.acp.active_session_id.clone() else {
+            return;
+        };
+        let line = format!("{}\n", input.read(cx).content());
+        input.update(cx, |input, cx| input.set_content(String::new(), cx));
+        self.acp.manager.write_to_terminal(&session_id, terminal_id, line);
+        cx.notify();
+    }
+
+    /// A terminal the agent opened: what it printed, whether it's still
+    /// running, and a box to type into it
+    fn render_live_terminal(
+        &self,
+        terminal: &LiveTerminal,
+        input: View<TextInput>,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        const SHOWN_LINES: usize = 40;
+        let colors = &self.theme.colors;
+        let output = terminal.output();
+        let (status, status_color) = match output.exit_code {
+            None => (t("terminal.running").to_string(), colors.success),
+            Some(code) => (t_args("terminal.exited", &[("code", &code)]), colors.text_secondary),
+        };
+        let running = output.exit_code.is_none();
+        let button = |id: String, label: String| {
+            div()
+                .id(SharedString::from(id))
+                .px(dense(8.0))
+                .py(dense(2.0))
+                .rounded(px(4.0))
+                .text_xs()
+                .text_color(rgb(colors.text_secondary))
+                .cursor_pointer()
+                .hover(|el| el.bg(rgb(colors.hover)).text_color(rgb(colors.text_primary)))
+                .child(label)
+        };
+
+        div()
+            .w_full()
+            .flex_shrink_0()
+            .flex()
+            .flex_col()
+            .rounded(px(8.0))
+            .border_1()
+            .border_color(rgb(colors.border))
+            .bg(rgb(colors.code_bg))
+            .overflow_hidden()
+            // Header
+            .child(
+                div()
+                    .px(dense(10.0))
+                    .py(dense(6.0))
+                    .flex()
+                    .items_center()
+                    .gap(dense(8.0))
+                    .border_b_1()
+                    .border_color(rgb(colors.border))
+                    .child(svg_icon(IconName::Terminal, IconSize::Small).text_color(rgb(colors.text_secondary)))
+                    .child(
+                        div()
+                            .flex_1()
+                            .min_w_0()
+                            .font_family("monospace")
+                            .text_xs()
+                            .text_color(rgb(colors.text_primary))
+                            .text_ellipsis()
+                            .child(terminal.command_line.clone()),
+                    )
+                    .child(div().text_xs().text_color(rgb(status_color)).child(status))
+                    .child(
+                        button(format!("quote-terminal-{}", terminal.id), self.quote_label(&terminal.id)).on_click({
+                            let (id, text) = (terminal.id.clone(), output.output.clone());
+                            cx.listener(move |this, _, cx| this.quote_output(&id, &text, cx))
+                        }),
+                    )
+                    .when(running, |el| {
+                        let id = terminal.id.clone();
+                        el.child(button(format!("kill-terminal-{}", terminal.id), t("terminal.kill").to_string()).on_click(
+                            cx.listener(move |this, _, cx| {
+                                if let Some(session_id) = this.acp.active_session_id.clone() {
+                                    this.acp.manager.kill_terminal(&session_id, &id);
+                                }
+                                cx.notify();
+                            }),
+                        ))
+                    })
+                    .child({
+                        let id = terminal.id.clone();
+                        button(format!("close-terminal-{}", terminal.id), t("terminal.close").to_string()).on_click(
+                            cx.listener(move |this, _, cx| {
+                                if let Some(session_id) = this.acp.active_session_id.clone() {
+                                    this.acp.manager.close_terminal(&session_id, &id);
+                                }
+                                cx.notify();
+                            }),
+                        )
+                    }),
+            )
+            // Output
+            .child(
+                div()
+                    .px(dense(10.0))
+                    .py(dense(6.0))
+                    .flex()
+                    .flex_col()
+                    .font_family("monospace")
+                    .text_xs()
+                    .text_color(rgb(colors.code_text))
+                    .when(output.truncated || output.output.lines().count() > SHOWN_LINES, |el| {
+                        el.child(div().text_color(rgb(colors.text_secondary)).child(t("terminal.earlier_output")))
+                    })
+                    .child(self.render_output_lines(&terminal.id, &output.output, SHOWN_LINES, cx)),
+            )
+            // Input
+            .when(running, |el| {
+                el.child(
+                    div()
+                        .px(dense(10.0))
+                        .py(dense(6.0))
+                        .border_t_1()
+                        .border_color(rgb(colors.border))
+                        .font_family("monospace")
+                        .text_xs()
+                        .child(input),
+                )
+            })
+    }
+
+    /// Run the line typed in the terminal panel in the user's shell
+    fn run_in_terminal_panel(&mut self, cx: &mut ViewContext<Self>) {
+        let line = self.terminal_panel_input.read(cx).content().to_string();
+        self.terminal_panel_input.update(cx, |input, cx| input.set_content(String::new(), cx));
+        self.acp.manager.run_in_user_terminal(&line);
+        cx.notify();
+    }
+
+    /// The user's own shell in the workspace, below whatever the main panel shows
+    fn render_terminal_panel(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        const SHOWN_LINES: usize = 200;
+        let colors = self.theme.colors.clone();
+        let terminal = self.acp.manager.user_terminal().cloned();
+        let output = terminal.as_ref().map(|terminal| terminal.output()).unwrap_or_default();
+        let running = terminal.is_some() && output.exit_code.is_none();
+        let status = match (&self.terminal_panel_error, output.exit_code) {
+            (Some(error), _) => Some((error.clone(), colors.error)),
+            (None, Some(code)) => Some((t_args("terminal.exited", &[("code", &code)]), colors.text_secondary)),
+            (None, None) => None,
+        };
+        let button = |id: &'static str, label: String| {
+            div()
+                .id(id)
+                .px(dense(8.0))
+                .py(dense(2.0))
+                .rounded(px(4.0))
+                .text_xs()
+                .text_color(rgb(colors.text_secondary))
+                .cursor_pointer()
+                .hover(|el| el.bg(rgb(colors.hover)).text_color(rgb(colors.text_primary)))
+                .child(label)
+        };
+
+        div()
+            .id("terminal-panel")
+            .w_full()
+            .h(px(220.0))
+            .flex_shrink_0()
+            .flex()
+            .flex_col()
+            .border_t_1()
+            .border_color(rgb(colors.border))
+            .bg(rgb(colors.code_bg))
+            // Header
+            .child(
+                div()
+                    .px(dense(12.0))
+                    .py(dense(4.0))
+                    .flex()
+                    .items_center()
+                    .gap(dense(8.0))
+                    .border_b_1()
+                    .border_color(rgb(colors.border))
+                    .child(svg_icon(IconName::Terminal, IconSize::Small).text_color(rgb(colors.text_secondary)))
+                    .child(
+                        div()
+                            .flex_1()
+                            .min_w_0()
+                            .font_family("monospace")
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .text_ellipsis()
+                            .child(self.acp.manager.get_working_dir().display().to_string()),
+                    )
+                    .when_some(status, |el, (status, color)| {
+                        el.child(div().text_xs().text_color(rgb(color)).child(status))
+                    })
+                    .child(button("terminal-panel-quote", self.quote_label(USER_TERMINAL_BLOCK)).on_click({
+                        let text = output.output.clone();
+                        cx.listener(move |this, _, cx| this.quote_output(USER_TERMINAL_BLOCK, &text, cx))
+                    }))
+                    .child(button("terminal-panel-restart", t("terminal_panel.restart").to_string()).on_click(cx.listener(
+                        |this, _, cx| {
+                            this.acp.manager.close_user_terminal();
+                            this.open_terminal_panel(cx);
+                            cx.notify();
+                        },
+                    )))
+                    .child(
+                        button("terminal-panel-hide", t("terminal_panel.hide").to_string())
+                            .on_click(cx.listener(|this, _, cx| this.toggle_terminal_panel(&ToggleTerminalPanel, cx))),
+                    ),
+            )
+            // Output, newest at the bottom
+            .child(
+                div()
+                    .id("terminal-panel-output")
+                    .flex_1()
+                    .min_h_0()
+                    .overflow_y_scroll()
+                    .px(dense(12.0))
+                    .py(dense(6.0))
+                    .flex()
+                    .flex_col_reverse()
+                    .font_family("monospace")
+                    .text_xs()
+                    .text_color(rgb(colors.code_text))
+                    .child(
+                        div()
+                            .flex()
+                            .flex_col()
+                            .when(output.truncated || output.output.lines().count() > SHOWN_LINES, |el| {
+                                el.child(
+                                    div().text_color(rgb(colors.text_secondary)).child(t("terminal.earlier_output")),
+                                )
+                            })
+                            .child(self.render_output_lines(USER_TERMINAL_BLOCK, &output.output, SHOWN_LINES, cx)),
+                    ),
+            )
+            // Command line
+            .when(running, |el| {
+                el.child(
+                    div()
+                        .px(dense(12.0))
+                        .py(dense(4.0))
+                        .flex()
+                        .items_center()
+                        .gap(dense(6.0))
+                        .border_t_1()
+                        .border_color(rgb(colors.border))
+                        .font_family("monospace")
+                        .text_xs()
+                        .child(div().text_color(rgb(colors.text_secondary)).child("$"))
+                        .child(div().flex_1().min_w_0().child(self.terminal_panel_input.clone())),
+                )
+            })
+    }
+
+    /// The last `shown` lines of a terminal's output; clicking one picks it
+    /// for quoting and shift-clicking another extends the pick to it
+    fn render_output_lines(&self, block: &str, output: &str, shown: usize, cx: &mut ViewContext<Self>) -> Div {
+        let colors = &self.theme.colors;
+        let lines: Vec<&str> = output.lines().collect();
+        let first = lines.len().saturating_sub(shown);
+        let picked = self
+            .quote_selection
+            .as_ref()
+            .filter(|(picked_block, _, _)| picked_block == block)
+            .map(|(_, start, end)| *start..=*end);
+
+        div().flex().flex_col().children(lines[first..].iter().enumerate().map(|(i, line)| {
+            let n = first + i;
+            let block = block.to_string();
+            div()
+                .id(SharedString::from(format!("{}-line-{}", block, n)))
+                .rounded(px(2.0))
+                .cursor_pointer()
+                .hover(|el| el.bg(rgba(colors.hover)))
+                .when(picked.as_ref().is_some_and(|picked| picked.contains(&n)), |el| {
+                    el.bg(rgba(colors.selection))
+                })
+                .child(line.to_string())
+                .on_click(cx.listener(move |this, event: &ClickEvent, cx| {
+                    this.pick_output_line(&block, n, event.down.modifiers.shift);
+                    cx.notify();
+                }))
+        }))
+    }
+
+    /// Pick output line `n` for quoting, or extend the pick to it; picking
+    /// the only picked line again drops it
+    fn pick_output_line(&mut self, block: &str, n: usize, extend: bool) {
+        self.quote_selection = match self.quote_selection.take() {
+            Some((picked, start, end)) if picked == block && extend => Some((picked, start.min(n), end.max(n))),
+            Some((picked, start, end)) if picked == block && start == n && end == n => None,
+            _ => Some((block.to_string(), n, n)),
+        };
+    }
+
+    /// Label of a quote button: the lines picked in `block`, or all of it
+    fn quote_label(&self, block: &str) -> String {
+        match &self.quote_selection {
+            Some((picked, start, end)) if picked == block => t_args("quote.lines", &[("count", &(end - start + 1))]),
+            _ => t("quote.output").to_string(),
+        }
+    }
+
+    /// Add the lines picked in `block`, or all of `text`, to the prompt as a fenced block
+    fn quote_output(&mut self, block: &str, text: &str, cx: &mut ViewContext<Self>) {
+        let lines = match self.quote_selection.take() {
+            Some((picked, start, end)) if picked == block => Some((start, end)),
+            other => {
+                self.quote_selection = other;
+                None
+            }
+        };
+        let quote = quote_block(text, lines);
+        let prompt = self.message_input.read(cx).content().trim_end().to_string();
+        let prompt = if prompt.is_empty() { format!("{}\n", quote) } else { format!("{}\n\n{}\n", prompt, quote) };
+        self.message_input.update(cx, |input, cx| input.set_content(prompt, cx));
+        self.focus_region(FocusRegion::Input, cx);
+        cx.notify();
+    }
+
+    /// Answer the active session's question with the reply box's text, or skip it
+    fn reply_to_question(&mut self, answer: bool, cx: &mut ViewContext<Self>) {
+        let Some(session_id) = self.acp.active_session_id.clone() else {
+            return;
+        };
+        let text = self.question_input.read(cx).content().trim().to_string();
+        if answer && text.is_empty() {
+            return;
+        }
+        self.question_input.update(cx, |input, cx| input.set_content(String::new(), cx));
+        self.acp.manager.reply_to_question(&session_id, answer.then_some(text));
+        cx.notify();
+    }
+
+    /// "5m ago", plus how long the turn took on a turn's last message
+    fn message_meta(&self, idx: usize, message: &MessageBlock) -> Option<String> {
+        if !self.acp.manager.show_timestamps {
+            return None;
+        }
+        let mut meta = format_relative(message.timestamp(), chrono::Utc::now());
+        if let Some(duration) = self.acp.turn_duration(idx) {
+            meta.push_str(&t_args("timeline.turn_took", &[("duration", &format_duration(duration))]));
+        }
+        Some(meta)
+    }
+
+    /// Flags on message `idx`, and actions shown while hovering it: flag or pin it, fork
+    /// the thread there, and for a reply with `subtask` text, spawn a sub-thread on it
+    fn render_message_actions(
+        &self,
+        idx: usize,
+        subtask: Option<String>,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let flags = self.acp.message_flags(idx);
+        let action = |id: String| {
+            div()
+                .id(SharedString::from(id))
+                .text_xs()
+                .text_color(rgba(colors.text_secondary.with_alpha(0.7)))
+                .cursor_pointer()
+                .hover(|s| s.text_color(rgb(colors.primary)))
+        };
+        let flag_button = |flag: MessageFlag, set: bool| {
+            div()
+                .id(SharedString::from(format!("flag-{}-{}", flag.as_str(), idx)))
+                .px(px(4.0))
+                .rounded(px(4.0))
+                .text_xs()
+                .cursor_pointer()
+                .when(set, |el| el.bg(rgba(colors.primary.with_alpha(0.2))))
+                .hover(|s| s.bg(rgba(colors.hover)))
+                .on_click(cx.listener(move |this, _, cx| {
+                    this.acp.toggle_flag(idx, flag);
+                    cx.notify();
+                }))
+                .child(flag.emoji())
+        };
+
+        div()
+            .mt(dense(4.0))
+            .flex()
+            .items_center()
+            .gap(dense(12.0))
+            // Flags stay in view; the rest shows on hover
+            .when(!flags.is_empty(), |el| {
+                el.child(div().flex().gap(px(2.0)).children(flags.iter().map(|&flag| flag_button(flag, true))))
+            })
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(dense(12.0))
+                    .invisible()
+                    .group_hover(SharedString::from(format!("message-{}", idx)), |s| s.visible())
+                    .child(
+                        div()
+                            .flex()
+                            .gap(px(2.0))
+                            .children(
+                                MessageFlag::ALL
+                                    .into_iter()
+                                    .filter(|flag| !flags.contains(flag))
+                                    .map(|flag| flag_button(flag, false)),
+                            ),
+                    )
+                    .child(
+                        action(format!("pin-{}", idx))
+                            .on_click(cx.listener(move |this, _, cx| {
+                                this.acp.toggle_pinned(idx);
+                                cx.notify();
+                            }))
+                            .child(if self.acp.is_pinned(idx) { t("timeline.unpin") } else { t("timeline.pin") }),
+                    )
+                    .child(
+                        action(format!("fork-from-{}", idx))
+                            .on_click(cx.listener(move |this, _, cx| this.fork_thread(idx, cx)))
+                            .child(t("timeline.fork")),
+                    )
+                    .when_some(subtask, |el, text| {
+                        el.child(
+                            action(format!("spawn-from-{}", idx))
+                                .on_click(cx.listener(move |this, _, cx| this.spawn_subthread(text.clone(), cx)))
+                                .child(t("timeline.spawn_subthread")),
+                        )
+                    }),
+            )
+    }
+
+    fn render_message(&mut self, idx: usize, message: &MessageBlock, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = self.theme.colors.clone();
+        let meta = self.message_meta(idx, message);
+        let meta_color = rgba(colors.text_secondary.with_alpha(0.7));
+        let meta_row = move |meta: String| div().mt(dense(4.0)).text_xs().text_color(meta_color).child(meta);
+
+        match message {
+            // User message: Dark rounded pill style (like Zed's input box)
+            MessageBlock::User { content, .. } => {
+                let text = content
+                    .iter()
+                    .filter_map(|c| match c {
+                        ContentBlock::Text { text } => Some(text.clone()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("");
+                let attachments: Vec<String> = content
+                    .iter()
+                    .filter_map(|c| match c {
+                        ContentBlock::ResourceLink { name, .. } => Some(name.clone()),
+                        _ => None,
+                    })
+                    .collect();
+
+                div()
+                    .group(SharedString::from(format!("message-{}", idx)))
+                    .w_full()
+                    .flex_shrink_0()
+                    .overflow_hidden()
+                    .child(
+                        div()
+                            .w_full()
+                            .px(dense(16.0))
+                            .py(dense(12.0))
+                            .rounded(px(8.0))
+                            .bg(rgb(colors.input_bg))
+                            .overflow_hidden()
+                            .child(
+                                div()
+                                    .w_full()
+                                    .text_sm()
+                                    .text_color(rgb(colors.text_primary))
+                                    .overflow_x_hidden()
+                                    .child(text),
+                            )
+                            .when(!attachments.is_empty(), |el| {
+                                el.child(
+                                    div()
+                                        .mt(dense(8.0))
+                                        .flex()
+                                        .flex_wrap()
+                                        .gap(px(4.0))
+                                        .children(attachments.into_iter().map(|name| {
+                                            div()
+                                                .h(px(22.0))
+                                                .px(px(6.0))
+                                                .flex()
+                                                .items_center()
+                                                .gap(px(4.0))
+                                                .rounded(px(4.0))
+                                                .bg(rgba(colors.primary.with_alpha(0.2)))
+                                                .text_xs()
+                                                .text_color(rgb(colors.text_primary))
+                                                .child(svg_icon(IconName::File, IconSize::XSmall))
+                                                .child(name)
+                                        })),
+                                )
+                            }),
+                    )
+                    .when_some(meta, |el, meta| el.child(meta_row(meta)))
+                    .child(self.render_message_actions(idx, None, cx))
+            }
+
+            // Thinking block: Zed style with left border and lightbulb icon
+            MessageBlock::Thought { content, .. } => {
+                let text = content
+                    .iter()
+                    .filter_map(|c| match c {
+                        ContentBlock::Text { text } => Some(text.clone()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("");
+
+                let is_collapsed = self.is_thinking_collapsed(idx);
+                let markdown = self.render_markdown_view(&format!("thought-{}", idx), &text, true, cx);
+
+                div()
+                    .w_full()
+                    .flex_shrink_0()
+                    .overflow_hidden()
+                    .flex()
+                    .flex_col()
+                    .child(
+                        // Thinking header (clickable to collapse)
+                        div()
+                            .id(SharedString::from(format!("thinking-header-{}", idx)))
+                            .flex()
+                            .items_center()
+                            .gap(dense(8.0))
+                            .cursor_pointer()
+                            .on_click(cx.listener(move |this, _, cx| {
+                                this.toggle_thinking(idx, cx);
+                            }))
+                            .child(
+                                // Lightbulb icon
+                                div()
+                                    .text_sm()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .child("💡"),
+                            )
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .child(t("timeline.thinking")),
+                            )
+                            .child(
+                                // Collapse indicator
+                                div()
+                                    .text_xs()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .child(if is_collapsed { "▶" } else { "▼" }),
+                            ),
+                    )
+                    // Thinking content with left border
+                    .when(!is_collapsed, move |el| {
+                        el.child(
+                            div()
+                                .w_full()
+                                .mt(dense(8.0))
+                                .pl(dense(12.0))
+                                .overflow_hidden()
+                                .border_l_2()
+                                .border_color(rgb(colors.border))
+                                .child(
+                                    div()
+                                        .w_full()
+                                        .overflow_x_hidden()
+                                        .text_sm()
+                                        .text_color(rgba(colors.text_secondary.with_alpha(0.9)))
+                                        .child(markdown),
+                                ),
+                        )
+                    })
+            }
+
+            // Agent response: Markdown (Zed renderer)
+            MessageBlock::Agent { content, .. } => {
+                let text = content
+                    .iter()
+                    .filter_map(|c| match c {
+                        ContentBlock::Text { text } => Some(text.clone()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("");
+
+                let markdown = self.render_markdown_view(&format!("agent-{}", idx), &text, false, cx);
+
+                div()
+                    .group(SharedString::from(format!("message-{}", idx)))
+                    .w_full()
+                    .flex_shrink_0()
+                    .overflow_hidden()
+                    .child(markdown)
+                    .when_some(meta, |el, meta| el.child(meta_row(meta)))
+                    .child(self.render_message_actions(idx, Some(text), cx))
+            }
+
+            // Conversation summary: collapsed until clicked
+            MessageBlock::Summary { content, .. } => {
+                let is_expanded = self.expanded_summaries.contains(&idx);
+                let markdown = is_expanded
+                    .then(|| self.render_markdown_view(&format!("summary-{}", idx), content, true, cx));
+
+                div()
+                    .w_full()
+                    .flex_shrink_0()
+                    .px(dense(12.0))
+                    .py(dense(8.0))
+                    .rounded(px(8.0))
+                    .border_1()
+                    .border_color(rgb(colors.border_subtle))
+                    .flex()
+                    .flex_col()
+                    .child(
+                        div()
+                            .id(SharedString::from(format!("summary-header-{}", idx)))
+                            .flex()
+                            .items_center()
+                            .gap(dense(8.0))
+                            .cursor_pointer()
+                            .on_click(cx.listener(move |this, _, cx| {
+                                this.toggle_summary(idx, cx);
+                            }))
+                            .child(
+                                div()
+                                    .flex_1()
+                                    .text_xs()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .child(t("timeline.compacted")),
+                            )
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .child(if is_expanded { "▼" } else { "▶" }),
+                            ),
+                    )
+                    .when_some(markdown, |el, markdown| {
+                        el.child(div().w_full().mt(dense(8.0)).text_sm().child(markdown))
+                    })
+            }
+
+            // System message: Muted style
+            MessageBlock::System { content, .. } => {
+                div()
+                    .w_full()
+                    .flex_shrink_0()
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(content.clone()),
+                    )
+            }
+        }
+    }
+
+    fn render_markdown_view(
+        &mut self,
+        key: &str,
+        text: &str,
+        muted: bool,
+        cx: &mut ViewContext<Self>,
+    ) -> AnyElement {
+        let view = self.markdown_view(key, text, muted, cx);
+        div()
+            .w_full()
+            .min_w_0()
+            .overflow_x_hidden()
+            .child(view)
+            .into_any_element()
+    }
+
+    fn markdown_view(
+        &mut self,
+        key: &str,
+        text: &str,
+        muted: bool,
+        cx: &mut ViewContext<Self>,
+    ) -> View<Markdown> {
+        let session = self.acp.active_session_id.as_deref().unwrap_or_default();
+        let cache_key = format!("{}:{}:{}", session, key, if muted { "muted" } else { "normal" });
+        if let Some((view, source)) = self.message_markdown_cache.get_mut(&cache_key) {
+            // Only touch views whose text changed, and append while streaming
+            // rather than handing the whole message over again
+            match source.update(text) {
+                MarkdownUpdate::Unchanged => {}
+                MarkdownUpdate::Append(suffix) => {
+                    view.update(cx, |markdown, cx| markdown.append(suffix, cx));
+                }
+                MarkdownUpdate::Reset => {
+                    view.update(cx, |markdown, cx| markdown.reset(text.to_string(), cx));
+                }
+            }
+            return view.clone();
+        }
+
+        let style = self.markdown_style(muted, cx);
+        let view = cx.new_view(|cx| Markdown::new(text.to_string(), style, None, cx, None));
+        self.message_markdown_cache
+            .insert(cache_key, view.clone(), MarkdownSource::new(text));
+        view
+    }
+
+    fn markdown_style(&self, muted: bool, cx: &mut ViewContext<Self>) -> MarkdownStyle {
+        let colors = &self.theme.colors;
+        let base_color = if muted {
+            rgba(colors.text_secondary.with_alpha(0.9))
+        } else {
+            rgb(colors.text_primary)
+        };
+        let code_bg = rgb(colors.code_bg);
+        let code_text = rgb(colors.code_text);
+        let link_color = rgb(colors.text_link);
+
+        let mut base_text_style = cx.text_style();
+        base_text_style.color = Hsla::from(base_color);
+        base_text_style.font_size = gpui::px(self.theme.typography.base_size).into();
+
+        MarkdownStyle {
+            base_text_style,
+            code_block: StyleRefinement {
+                background: Some(code_bg.into()),
+                padding: EdgesRefinement {
+                    top: Some(px(8.0).into()),
+                    left: Some(px(10.0).into()),
+                    right: Some(px(10.0).into()),
+                    bottom: Some(px(8.0).into()),
+                },
+                margin: EdgesRefinement {
+                    top: Some(Length::Definite(px(6.0).into())),
+                    left: Some(Length::Definite(px(0.0).into())),
+                    right: Some(Length::Definite(px(0.0).into())),
+                    bottom: Some(Length::Definite(px(6.0).into())),
+                },
+                border_color: Some(rgba(colors.border).into()),
+                border_widths: EdgesRefinement {
+                    top: Some(px(1.0).into()),
+                    left: Some(px(1.0).into()),
+                    right: Some(px(1.0).into()),
+                    bottom: Some(px(1.0).into()),
+                },
+                text: Some(TextStyleRefinement {
+                    font_family: Some("monospace".into()),
+                    color: Some(Hsla::from(code_text)),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            inline_code: TextStyleRefinement {
+                font_family: Some("monospace".into()),
+                background_color: Some(Hsla::from(code_bg)),
+                color: Some(Hsla::from(code_text)),
+                ..Default::default()
+            },
+            block_quote: TextStyleRefinement {
+                color: Some(Hsla::from(rgba(colors.text_secondary))),
+                ..Default::default()
+            },
+            link: TextStyleRefinement {
+                color: Some(Hsla::from(link_color)),
+                underline: Some(UnderlineStyle {
+                    thickness: px(1.0),
+                    color: Some(Hsla::from(link_color)),
+                    wavy: false,
+                }),
+                ..Default::default()
+            },
+            rule_color: Hsla::from(rgba(colors.divider)),
+            block_quote_border_color: Hsla::from(rgba(colors.border)),
+            selection_background_color: Hsla::from(rgba(colors.selection)),
+            ..Default::default()
+        }
+    }
+
+    fn toggle_summary(&mut self, idx: usize, cx: &mut ViewContext<Self>) {
+        if !self.expanded_summaries.remove(&idx) {
+            self.expanded_summaries.insert(idx);
+        }
+        cx.notify();
+    }
+
+    fn is_thinking_collapsed(&self, idx: usize) -> bool {
+        self.collapsed_thinking
+            .get(&idx)
+            .copied()
+            .unwrap_or_else(|| self.acp.thought_auto_collapsed(idx))
+    }
+
+    fn toggle_thinking(&mut self, idx: usize, cx: &mut ViewContext<Self>) {
+        let collapsed = self.is_thinking_collapsed(idx);
+        self.collapsed_thinking.insert(idx, !collapsed);
+        cx.notify();
+    }
+
+    /// Fold every thinking block in the active thread
+    fn collapse_all_thinking(&mut self, cx: &mut ViewContext<Self>) {
+        self.show_thread_menu = false;
+        for (idx, message) in self.acp.messages().into_iter().enumerate() {
+            if matches!(message, MessageBlock::Thought { .. }) {
+                self.collapsed_thinking.insert(idx, true);
+            }
+        }
+        cx.notify();
+    }
+
+    /// Show only the active thread's flagged messages, or everything again
+    fn toggle_review_mode(&mut self, cx: &mut ViewContext<Self>) {
+        self.show_thread_menu = false;
+        self.review_mode = !self.review_mode;
+        self.review_flag = None;
+        self.stick_to_bottom = !self.review_mode;
+        cx.notify();
+    }
+
+    /// Show or hide thinking in the active thread only
+    fn toggle_thread_thoughts(&mut self, cx: &mut ViewContext<Self>) {
+        self.show_thread_menu = false;
+        self.acp.toggle_thread_thoughts();
+        cx.notify();
+    }
+
+    fn render_tool_call(&self, tool_call: &ToolCallState, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+
+        // Status color
+        let status_color = match tool_call.status {
+            ToolCallStatus::Pending => rgb(colors.text_secondary),
+            ToolCallStatus::InProgress => rgb(colors.primary),
+            ToolCallStatus::Completed => rgb(ThemeRgba::rgb(0x4ADE80)),
+            ToolCallStatus::Failed => rgb(ThemeRgba::rgb(0xF87171)),
+            ToolCallStatus::Cancelled => rgb(colors.text_secondary),
+        };
+
+        // Tool kind icon
+        let kind_icon = match tool_call.kind {
+            Some(ToolCallKind::Read) => IconName::File,
+            Some(ToolCallKind::Write) => IconName::Pencil,
+            Some(ToolCallKind::Edit) => IconName::Pencil,
+            Some(ToolCallKind::Delete) => IconName::Close,
+            Some(ToolCallKind::Execute) | Some(ToolCallKind::Bash) | Some(ToolCallKind::Terminal) => IconName::Terminal,
+            Some(ToolCallKind::Search) | Some(ToolCallKind::Grep) | Some(ToolCallKind::Glob) => IconName::Search,
+            Some(ToolCallKind::Fetch) => IconName::Web,
+            Some(ToolCallKind::Task) => IconName::CircleCheck,
+            Some(ToolCallKind::Plan) => IconName::CircleCheck,
+            Some(ToolCallKind::Think) => IconName::Chat,
+            _ => IconName::Settings,
+        };
+
+        // Status icon based on status
+        let status_icon = match tool_call.status {
+            ToolCallStatus::Pending => IconName::Circle,
+            ToolCallStatus::InProgress => IconName::Circle,
+            ToolCallStatus::Completed => IconName::Check,
+            ToolCallStatus::Failed => IconName::Close,
+            ToolCallStatus::Cancelled => IconName::Close,
+        };
+
+        let title = tool_call.title.as_deref().unwrap_or(t("timeline.tool_call"));
+
+        div()
+            .w_full()
+            .flex_shrink_0()
+            .px(dense(12.0))
+            .py(dense(6.0))
+            .rounded(px(6.0))
+            .bg(rgb(colors.surface))
+            .border_1()
+            .border_color(rgb(colors.border))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(dense(8.0))
+                    // Status indicator (SVG icon)
+                    .child(
+                        svg_icon(status_icon, IconSize::XSmall)
+                            .text_color(status_color),
+                    )
+                    // Kind icon (SVG icon)
+                    .child(
+                        svg_icon(kind_icon, IconSize::Small)
+                            .text_color(rgb(colors.text_secondary)),
+                    )
+                    // Title
+                    .child(
+                        div()
+                            .flex_1()
+                            .text_sm()
+                            .text_color(rgb(colors.text_primary))
+                            .child(title.to_string()),
+                    )
+                    .when_some(
+                        tool_call.duration().filter(|_| self.acp.manager.show_timestamps),
+                        |el, duration| {
+                            el.child(
+                                div()
+                                    .text_xs()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .child(format_duration(duration)),
+                            )
+                        },
+                    )
+                    .when_some(
+                        output_text(tool_call).filter(|_| {
+                            matches!(tool_call.status, ToolCallStatus::Completed | ToolCallStatus::Failed)
+                        }),
+                        |el, text| {
+                            el.child(
+                                div()
+                                    .id(SharedString::from(format!("quote-tool-{}", tool_call.id)))
+                                    .text_xs()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .cursor_pointer()
+                                    .hover(|s| s.text_color(rgb(colors.primary)))
+                                    .child(t("quote.output"))
+                                    .on_click(cx.listener(move |this, _, cx| this.quote_output("", &text, cx))),
+                            )
+                        },
+                    )
+                    // Tool ID (dimmed)
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(format!("#{}", &tool_call.id[..8.min(tool_call.id.len())])),
+                    ),
+            )
+            // File the tool touched, opening the preview
+            .when_some(tool_call_path(tool_call), |el, path| {
+                el.child(
+                    div()
+                        .id(SharedString::from(format!("tool-path-{}", tool_call.id)))
+                        .mt(dense(4.0))
+                        .pl(px(40.0))
+                        .text_xs()
+                        .font_family("monospace")
+                        .text_color(rgb(colors.text_secondary))
+                        .overflow_hidden()
+                        .whitespace_nowrap()
+                        .text_ellipsis()
+                        .cursor_pointer()
+                        .hover(|s| s.text_color(rgb(colors.primary)))
+                        .child(path.clone())
+                        .on_click(cx.listener(move |this, _, cx| {
+                            this.acp.manager.open_file_preview(&path);
+                            cx.notify();
+                        })),
+                )
+            })
+            // Search results as clickable locations
+            .when_some(parse_tool_output(tool_call), |el, locations| {
+                el.child(self.render_tool_locations(&tool_call.id, locations, cx))
+            })
+    }
+
+    fn render_tool_locations(
+        &self,
+        tool_call_id: &str,
+        locations: Vec<FileLocation>,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let hidden = locations.len().saturating_sub(MAX_TOOL_LOCATIONS);
+
+        div()
+            .mt(dense(6.0))
+            .pt(dense(6.0))
+            .border_t_1()
+            .border_color(rgb(colors.border_subtle))
+            .flex()
+            .flex_col()
+            .text_xs()
+            .font_family("monospace")
+            .children(locations.into_iter().take(MAX_TOOL_LOCATIONS).enumerate().map(|(i, location)| {
+                let label = match location.line {
+                    Some(line) => format!("{}:{}", location.path, line),
+                    None => location.path.clone(),
+                };
+                div()
+                    .id(SharedString::from(format!("tool-location-{}-{}", tool_call_id, i)))
+                    .px(dense(4.0))
+                    .py(dense(1.0))
+                    .flex()
+                    .gap(dense(8.0))
+                    .rounded(px(4.0))
+                    .cursor_pointer()
+                    .hover(|s| s.bg(rgba(colors.hover)))
+                    .child(
+                        div()
+                            .flex_shrink_0()
+                            .text_color(rgb(colors.primary_hover))
+                            .child(label),
+                    )
+                    .when_some(location.text.clone(), |el, text| {
+                        el.child(
+                            div()
+                                .flex_1()
+                                .min_w_0()
+                                .overflow_hidden()
+                                .whitespace_nowrap()
+                                .text_ellipsis()
+                                .text_color(rgb(colors.text_secondary))
+                                .child(text.trim().to_string()),
+                        )
+                    })
+                    .on_click(cx.listener(move |this, _, cx| {
+                        this.acp.manager.open_file_preview_at(&location.path, location.line);
+                        cx.notify();
+                    }))
+            }))
+            .when(hidden > 0, |el| {
+                el.child(
+                    div()
+                        .px(dense(4.0))
+                        .pt(dense(2.0))
+                        .text_color(rgb(colors.text_secondary))
+                        .child(t_args("common.more", &[("count", &hidden)])),
+                )
+            })
+    }
+
+    fn render_input_bar(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let input_focused = self.message_input.read(cx).focus_handle(cx).is_focused(cx);
+        let external_edits = self.acp.external_edits();
+
+        div()
+            .id("input-bar")
+            .w_full()
+            .flex_shrink_0()  // Never shrink, keep natural height
+            .p(px(8.0))
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .bg(rgb(colors.panel_bg))
+            .border_t_1()
+            .border_color(rgb(colors.border))
+            // Up/Down and Ctrl+R for earlier prompts; Enter is handled by the input
+            .on_key_down(cx.listener(|this, event: &KeyDownEvent, cx| {
+                let key = event.keystroke.key.as_str();
+                if this.history_search.is_some() {
+                    this.handle_history_search_key(key, cx);
+                    return;
+                }
+                if key == "r" && event.keystroke.modifiers.control {
+                    this.open_history_search(cx);
+                    return;
+                }
+                if key == "up" || key == "down" {
+                    this.recall_prompt(key == "up", cx);
+                }
+            }))
+            .when(self.history_search.is_some(), |el| {
+                el.child(self.render_history_search(cx))
+            })
+            .when_some(self.mention_query(cx), |el, query| {
+                el.child(self.render_mention_menu(&query, cx))
+            })
+            .when(!external_edits.is_empty(), |el| {
+                el.child(self.render_external_edits_chip(&external_edits, cx))
+            })
+            // Editor container (like Zed's message editor)
+            .child(
+                div()
+                    .w_full()
+                    .rounded(px(8.0))
+                    .bg(rgb(colors.surface))
+                    .border_1()
+                    .border_color(if input_focused {
+                        rgb(colors.focus_ring)
+                    } else {
+                        rgb(colors.border_subtle)
+                    })
+                    .flex()
+                    .flex_col()
+                    // Text input area - grows with the text, then scrolls
+                    .child(
+                        div()
+                            .id("message-editor")
+                            .w_full()
+                            .min_h(px(48.0))
+                            .max_h(px(200.0))
+                            .p(px(12.0))
+                            .overflow_y_scroll()
+                            .child(self.message_input.clone()),
+                    )
+                    // Bottom controls inside the editor box
+                    .child(
+                        div()
+                            .w_full()
+                            .px(px(8.0))
+                            .py(px(6.0))
+                            .flex()
+                            .items_center()
+                            .justify_between()
+                            .border_t_1()
+                            .border_color(rgb(colors.border_subtle))
+                            // Left: Context button
+                            .child(self.render_context_button(cx))
+                            // Right: tool policy and send button (agent selection moved to new thread dialog)
+                            .child(
+                                div()
+                                    .flex()
+                                    .items_center()
+                                    .gap(px(6.0))
+                                    .child(self.render_policy_chip(cx))
+                                    .child(self.render_vim_mode(cx))
+                                    .child(self.render_secrets_chip(cx))
+                                    .child(self.render_input_size(cx))
+                                    .child(self.render_send_button(cx)),
+                            ),
+                    ),
+            )
+    }
+
+    /// Warning that files the agent saw were changed outside it, e.g. in an editor
+    fn render_external_edits_chip(&self, edits: &[ExternalEdit], cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let attaching = self.acp.attaching_external_edits();
+        let files = edits
+            .iter()
+            .map(|edit| {
+                let name = edit.path.file_name().map(|name| name.to_string_lossy().to_string());
+                name.unwrap_or_else(|| edit.path.display().to_string())
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        div()
+            .id("external-edits-chip")
+            .w_full()
+            .px(px(10.0))
+            .py(px(4.0))
+            .flex()
+            .items_center()
+            .gap(px(8.0))
+            .rounded(px(6.0))
+            .text_xs()
+            .bg(rgba(colors.warning.with_alpha(0.12)))
+            .child(
+                div()
+                    .flex_1()
+                    .min_w_0()
+                    .text_ellipsis()
+                    .text_color(rgb(colors.text_primary))
+                    .child(t_args("input.external_edits", &[("files", &files)])),
+            )
+            .child(
+                div()
+                    .id("attach-external-edits-btn")
+                    .flex_shrink_0()
+                    .px(px(8.0))
+                    .py(px(2.0))
+                    .rounded(px(4.0))
+                    .cursor_pointer()
+                    .when(attaching, |el| el.bg(rgba(colors.primary.with_alpha(0.2))))
+                    .text_color(rgb(colors.text_primary))
+                    .hover(|el| el.bg(rgba(colors.hover)))
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.acp.toggle_attach_external_edits();
+                        cx.notify();
+                    }))
+                    .child(t(if attaching { "input.external_edits_attached" } else { "input.attach_external_edits" })),
+            )
+            .child(
+                div()
+                    .id("dismiss-external-edits-btn")
+                    .flex_shrink_0()
+                    .text_color(rgb(colors.text_secondary))
+                    .cursor_pointer()
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.acp.dismiss_external_edits();
+                        cx.notify();
+                    }))
+                    .child(t("common.dismiss")),
+            )
+    }
+
+    /// Chip showing the tool policy in effect; opens the permissions settings
+    fn render_policy_chip(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let policy = self.acp.manager.tool_policy;
+
+        div()
+            .id("tool-policy-chip")
+            .px(px(8.0))
+            .py(px(2.0))
+            .rounded(px(4.0))
+            .text_xs()
+            .max_w(px(220.0))
+            .text_ellipsis()
+            .cursor_pointer()
+            .when(!policy.is_default(), |el| el.bg(rgba(colors.primary.with_alpha(0.2))))
+            .text_color(if policy.is_default() {
+                rgb(colors.text_secondary)
+            } else {
+                rgb(colors.text_primary)
+            })
+            .hover(|s| s.bg(rgba(colors.hover)))
+            .on_click(cx.listener(|this, _, cx| {
+                this.open_settings(cx);
+                // Start on the most specific scope, where the policy is usually set
+                this.policy_scope_idx = this.acp.manager.policy_scopes().len() - 1;
+            }))
+            .child(policy_summary(&policy))
+    }
+
+    fn render_context_button(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let workspace_display = self.workspace_path.as_ref().map(|p| {
+            // Show only the last folder name
+            std::path::Path::new(p)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| p.clone())
+        });
+
+        div()
+            .flex()
+            .items_center()
+            .gap(px(4.0))
+            // Folder button (workspace selector)
+            .child(
+                div()
+                    .id("folder-btn")
+                    .h(px(26.0))
+                    .px(px(8.0))
+                    .flex()
+                    .items_center()
+                    .gap(px(4.0))
+                    .rounded(px(4.0))
+                    .cursor_pointer()
+                    .hover(|s| s.bg(rgba(colors.hover)))
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.select_workspace(cx);
+                    }))
+                    .child(
+                        svg_icon(IconName::Folder, IconSize::Small)
+                            .text_color(rgb(colors.text_secondary)),
+                    )
+                    .when_some(workspace_display.clone(), |el, name| {
+                        el.child(
+                            div()
+                                .text_xs()
+                                .text_color(rgb(colors.text_secondary))
+                                .max_w(px(120.0))
+                                .text_ellipsis()
+                                .child(name),
+                        )
+                    }),
+            )
+            // + button (add attachment)
+            .child(
+                div()
+                    .id("add-btn")
+                    .h(px(26.0))
+                    .px(px(6.0))
+                    .flex()
+                    .items_center()
+                    .rounded(px(4.0))
+                    .cursor_pointer()
+                    .hover(|s| s.bg(rgba(colors.hover)))
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.add_attachment(cx);
+                    }))
+                    .child(
+                        svg_icon(IconName::Plus, IconSize::Small)
+                            .text_color(rgb(colors.text_secondary)),
+                    ),
+            )
+            // Prompt templates dropdown
+            .child(
+                div()
+                    .relative()
+                    .child(
+                        div()
+                            .id("templates-btn")
+                            .h(px(26.0))
+                            .px(px(6.0))
+                            .flex()
+                            .items_center()
+                            .rounded(px(4.0))
+                            .cursor_pointer()
+                            .hover(|s| s.bg(rgba(colors.hover)))
+                            .on_click(cx.listener(|this, _, cx| {
+                                this.toggle_template_menu(cx);
+                            }))
+                            .child(
+                                svg_icon(IconName::Pencil, IconSize::Small)
+                                    .text_color(rgb(colors.text_secondary)),
+                            ),
+                    )
+                    .when(self.show_template_menu, |el| {
+                        el.child(self.render_template_menu(cx))
+                    }),
+            )
+            // Show attached files as chips
+            .children(self.attached_files.iter().map(|file| {
+                let file_name = file.clone();
+                let display_name = std::path::Path::new(file)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| file.clone());
+
+                div()
+                    .id(SharedString::from(format!("attach-{}", file)))
+                    .h(px(22.0))
+                    .px(px(6.0))
+                    .flex()
+                    .items_center()
+                    .gap(px(4.0))
+                    .rounded(px(4.0))
+                    .bg(rgba(colors.primary.with_alpha(0.2)))
+                    .child({
+                        let preview_path = file.clone();
+                        div()
+                            .id(SharedString::from(format!("preview-{}", file)))
+                            .text_xs()
+                            .text_color(rgb(colors.text_primary))
+                            .max_w(px(100.0))
+                            .text_ellipsis()
+                            .cursor_pointer()
+                            .hover(|s| s.text_color(rgb(colors.primary)))
+                            .on_click(cx.listener(move |this, _, cx| {
+                                this.acp.manager.open_file_preview(&preview_path);
+                                cx.notify();
+                            }))
+                            .child(display_name)
+                    })
+                    .child(
+                        div()
+                            .id(SharedString::from(format!("remove-{}", file)))
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .cursor_pointer()
+                            .hover(|s| s.text_color(rgb(colors.error)))
+                            .on_click(cx.listener(move |this, _, cx| {
+                                this.remove_attachment(&file_name, cx);
+                            }))
+                            .child("×"),
+                    )
+            }))
+    }
+
+    /// Put the active thread's previous (`older`) or next prompt in the input
+    ///
+    /// Only works from an empty input or one still showing a recalled prompt.
+    fn recall_prompt(&mut self, older: bool, cx: &mut ViewContext<Self>) {
+        let content = self.message_input.read(cx).content().to_string();
+        let unedited = self.acp.is_recalling_prompt() && self.recalled_prompt.as_deref() == Some(content.as_str());
+        if !unedited {
+            if !content.is_empty() {
+                return;
+            }
+            self.acp.reset_prompt_history();
+        }
+
+        let recalled = if older {
+            let Some(prompt) = self.acp.recall_older_prompt() else {
+                return;
+            };
+            Some(prompt)
+        } else if self.acp.is_recalling_prompt() {
+            self.acp.recall_newer_prompt()
+        } else {
+            return;
+        };
+        let text = recalled.clone().unwrap_or_default();
+        self.recalled_prompt = recalled;
+        self.message_input.update(cx, |input, cx| input.set_content(text, cx));
+    }
+
+    fn open_history_search(&mut self, cx: &mut ViewContext<Self>) {
+        self.history_search = Some(HistorySearch {
+            prompts: self.acp.manager.recent_prompts(MAX_HISTORY_PROMPTS),
+            selected: 0,
+        });
+        self.history_search_input.update(cx, |input, cx| input.clear(cx));
+        let focus = self.history_search_input.read(cx).focus_handle(cx);
+        cx.focus(&focus);
+        cx.notify();
+    }
+
+    fn close_history_search(&mut self, cx: &mut ViewContext<Self>) {
+        self.history_search = None;
+        let focus = self.message_input.read(cx).focus_handle(cx);
+        cx.focus(&focus);
+        cx.notify();
+    }
+
+    /// Earlier prompts matching the history search query, best first
+    fn history_search_matches(&self, cx: &ViewContext<Self>) -> Vec<String> {
+        let Some(search) = &self.history_search else {
+            return Vec::new();
+        };
+        let query = self.history_search_input.read(cx).content().to_string();
+        search_prompts(&query, &search.prompts, MAX_HISTORY_MATCHES)
+            .into_iter()
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn handle_history_search_key(&mut self, key: &str, cx: &mut ViewContext<Self>) {
+        let matches = self.history_search_matches(cx);
+        let Some(search) = &mut self.history_search else {
+            return;
+        };
+        match key {
+            "escape" => self.close_history_search(cx),
+            "enter" => {
+                if let Some(prompt) = matches.get(search.selected).cloned() {
+                    self.pick_history_prompt(prompt, cx);
+                }
+            }
+            "up" => {
+                search.selected = search.selected.saturating_sub(1);
+                cx.notify();
+            }
+            "down" => {
+                search.selected = (search.selected + 1).min(matches.len().saturating_sub(1));
+                cx.notify();
+            }
+            _ => {}
+        }
+    }
+
+    fn pick_history_prompt(&mut self, prompt: String, cx: &mut ViewContext<Self>) {
+        self.acp.reset_prompt_history();
+        self.recalled_prompt = None;
+        self.message_input.update(cx, |input, cx| input.set_content(prompt, cx));
+        self.close_history_search(cx);
+    }
+
+    fn render_history_search(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let matches = self.history_search_matches(cx);
+        let selected = self.history_search.as_ref().map_or(0, |search| search.selected);
+
+        div()
+            .id("history-search")
+            .w_full()
+            .py(px(4.0))
+            .flex()
+            .flex_col()
+            .rounded(px(8.0))
+            .bg(rgb(colors.surface_elevated))
+            .border_1()
+            .border_color(rgb(colors.border))
+            .child(
+                div()
+                    .px(px(12.0))
+                    .py(px(6.0))
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .border_b_1()
+                    .border_color(rgb(colors.border_subtle))
+                    .child(
+                        div()
+                            .text_xs()
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("history.title")),
+                    )
+                    .child(div().flex_1().text_sm().child(self.history_search_input.clone()))
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("history.hint")),
+                    ),
+            )
+            .when(matches.is_empty(), |el| {
+                el.child(
+                    div()
+                        .px(px(12.0))
+                        .py(px(6.0))
+                        .text_xs()
+                        .text_color(rgb(colors.text_secondary))
+                        .child(t("history.none")),
+                )
+            })
+            .children(matches.into_iter().enumerate().map(|(idx, prompt)| {
+                let label = prompt.lines().next().unwrap_or_default().to_string();
+                div()
+                    .id(SharedString::from(format!("history-{}", idx)))
+                    .w_full()
+                    .px(px(12.0))
+                    .py(px(4.0))
+                    .text_sm()
+                    .overflow_hidden()
+                    .whitespace_nowrap()
+                    .text_ellipsis()
+                    .cursor_pointer()
+                    .text_color(rgb(colors.text_primary))
+                    .when(idx == selected, |el| el.bg(rgba(colors.primary.with_alpha(0.2))))
+                    .hover(|s| s.bg(rgba(colors.hover)))
+                    .on_click(cx.listener(move |this, _, cx| {
+                        this.pick_history_prompt(prompt.clone(), cx);
+                    }))
+                    .child(label)
+            }))
+    }
+
+    fn render_mention_menu(&self, query: &str, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let indexing = !self
+            .acp
+            .manager
+            .workspace_index
+            .as_ref()
+            .is_some_and(|index| index.is_ready());
+        let matches = self.acp.manager.search_workspace_files(query, 8);
+
+        div()
+            .id("mention-menu")
+            .w_full()
+            .py(px(4.0))
+            .flex()
+            .flex_col()
+            .rounded(px(8.0))
+            .bg(rgb(colors.surface_elevated))
+            .border_1()
+            .border_color(rgb(colors.border))
+            .when(matches.is_empty(), |el| {
+                el.child(
+                    div()
+                        .px(px(12.0))
+                        .py(px(6.0))
+                        .text_xs()
+                        .text_color(rgb(colors.text_secondary))
+                        .child(if indexing { t("input.indexing") } else { t("input.no_matching_files") }),
+                )
+            })
+            .children(matches.into_iter().map(|found| {
+                let path = found.path.clone();
+                let file_name = found
+                    .path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let parent = found
+                    .path
+                    .parent()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_default();
+
+                div()
+                    .id(SharedString::from(format!("mention-{}", found.path.display())))
+                    .w_full()
+                    .px(px(12.0))
+                    .py(px(4.0))
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .cursor_pointer()
+                    .hover(|s| s.bg(rgba(colors.hover)))
+                    .on_click(cx.listener(move |this, _, cx| {
+                        this.insert_mention(&path, cx);
+                    }))
+                    .child(
+                        svg_icon(IconName::File, IconSize::Small)
+                            .text_color(rgb(colors.text_secondary)),
+                    )
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(rgb(colors.text_primary))
+                            .child(file_name),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .overflow_hidden()
+                            .whitespace_nowrap()
+                            .text_ellipsis()
+                            .child(parent),
+                    )
+            }))
+    }
+
+    fn render_template_menu(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let templates = &self.acp.manager.prompt_templates;
+
+        div()
+            .id("template-menu")
+            .absolute()
+            .bottom(px(30.0))
+            .left(px(0.0))
+            .w(px(260.0))
+            .max_h(px(320.0))
+            .overflow_y_scroll()
+            .bg(rgb(colors.surface_elevated))
+            .border_1()
+            .border_color(rgb(colors.border))
+            .rounded(px(8.0))
+            .shadow_lg()
+            .py(px(4.0))
+            .flex()
+            .flex_col()
+            .on_mouse_down(MouseButton::Left, |_, cx| cx.stop_propagation())
+            .when(templates.is_empty(), |el| {
+                el.child(
+                    div()
+                        .px(px(12.0))
+                        .py(px(8.0))
+                        .text_xs()
+                        .text_color(rgb(colors.text_secondary))
+                        .child(t("input.no_templates")),
+                )
+            })
+            .children(templates.iter().map(|template| {
+                let selected = template.clone();
+
+                div()
+                    .id(SharedString::from(format!("template-{}", template.id)))
+                    .w_full()
+                    .px(px(12.0))
+                    .py(px(6.0))
+                    .flex()
+                    .flex_col()
+                    .cursor_pointer()
+                    .hover(|s| s.bg(rgba(colors.hover)))
+                    .on_click(cx.listener(move |this, _, cx| {
+                        this.insert_prompt_template(&selected, "", cx);
+                    }))
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .justify_between()
+                            .gap(px(8.0))
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .text_color(rgb(colors.text_primary))
+                                    .child(template.name.clone()),
+                            )
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .font_family("monospace")
+                                    .text_color(rgb(colors.text_secondary))
+                                    .child(template.command()),
+                            ),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .overflow_hidden()
+                            .whitespace_nowrap()
+                            .text_ellipsis()
+                            .child(template.body.clone()),
+                    )
+            }))
+            // Separator
+            .child(
+                div()
+                    .w_full()
+                    .h(px(1.0))
+                    .my(px(4.0))
+                    .bg(rgb(colors.border)),
+            )
+            .child(
+                div()
+                    .id("template-menu-manage")
+                    .w_full()
+                    .px(px(12.0))
+                    .py(px(6.0))
+                    .text_sm()
+                    .text_color(rgb(colors.text_primary))
+                    .cursor_pointer()
+                    .hover(|s| s.bg(rgba(colors.hover)))
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.show_template_menu = false;
+                        this.open_settings(cx);
+                    }))
+                    .child(t("input.manage_templates")),
+            )
+    }
+
+    /// Approximate token and character count of the input
+    /// Vim mode of the message input and any half-typed command, when Vim keys are on
+    fn render_vim_mode(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let input = self.message_input.read(cx);
+        let label = input.vim_mode().map(|mode| {
+            let mode = match mode {
+                VimMode::Normal => t("input.vim_normal"),
+                VimMode::Insert => t("input.vim_insert"),
+            };
+            format!("{} {}", mode, input.vim_pending_keys()).trim_end().to_string()
+        });
+
+        div()
+            .text_xs()
+            .font_family("monospace")
+            .text_color(rgb(colors.text_secondary))
+            .children(label)
+    }
+
+    /// What sending `text` with the attached files would add to the active thread's context
+    fn prompt_size_estimate(&self, text: &str) -> PromptSizeEstimate {
+        let files: Vec<PathBuf> = self.attached_files.iter().map(PathBuf::from).collect();
+        self.acp.manager.estimate_prompt_size(self.acp.active_session_id.as_deref(), text, &files)
+    }
+
+    /// Estimated size of the prompt: the message, attachments and any workspace instructions
+    fn render_input_size(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let content = self.message_input.read(cx).content();
+        let estimate = self.prompt_size_estimate(content);
+        let over_limit = self.acp.manager.attachments_over_limit(&estimate);
+        let tokens = approx_token_count(content) as u64 + (estimate.instructions + estimate.attachments).div_ceil(4);
+        let label = (!content.is_empty() || !self.attached_files.is_empty())
+            .then(|| t_args("input.size", &[("tokens", &tokens), ("size", &format_size(estimate.total()))]));
+        let breakdown = t_args(
+            "input.size_breakdown",
+            &[
+                ("message", &format_size(estimate.text)),
+                ("files", &format_size(estimate.attachments)),
+                ("instructions", &format_size(estimate.instructions)),
+            ],
+        );
+
+        div()
+            .id("input-size")
+            .text_xs()
+            .text_color(if over_limit { rgb(colors.warning) } else { rgb(colors.text_secondary) })
+            .when(label.is_some(), |el| el.tooltip(move |cx| Tooltip::text(breakdown.clone(), cx)))
+            .children(label)
+    }
+
+    /// How many secrets in the message will be masked; clicking sends this message as it is
+    fn render_secrets_chip(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let count = self.acp.manager.secrets_in(self.message_input.read(cx).content()).len();
+        let unmasked = self.acp.manager.send_unredacted;
+        let (label, tint) = if unmasked {
+            (t_args("input.secrets_unmasked", &[("count", &count)]), colors.error)
+        } else {
+            (t_args("input.secrets_masked", &[("count", &count)]), colors.warning)
+        };
+
+        div().when(count > 0, |el| {
+            el.child(
+                div()
+                    .id("secrets-chip")
+                    .px(px(6.0))
+                    .py(px(1.0))
+                    .rounded(px(4.0))
+                    .bg(rgba(tint.with_alpha(0.15)))
+                    .text_xs()
+                    .text_color(rgb(tint))
+                    .cursor_pointer()
+                    .tooltip(move |cx| {
+                        Tooltip::text(if unmasked { t("input.secrets_mask") } else { t("input.secrets_unmask") }, cx)
+                    })
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.acp.manager.send_unredacted = !this.acp.manager.send_unredacted;
+                        cx.notify();
+                    }))
+                    .child(label),
+            )
+        })
+    }
+
+    fn render_send_button(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let has_text = !self.message_input.read(cx).content().is_empty();
+
+        div()
+            .id("send-button")
+            .tooltip(|cx| Tooltip::text(t("input.send"), cx))
+            .h(px(26.0))
+            .w(px(26.0))
+            .flex()
+            .items_center()
+            .justify_center()
+            .rounded(px(4.0))
+            .when(has_text, |el| {
+                el.bg(rgb(colors.primary))
+                    .cursor_pointer()
+                    .hover(|s| s.bg(rgb(colors.primary_hover)))
+            })
+            .when(!has_text, |el| {
+                el.bg(rgb(colors.surface))
+                    .cursor_default()
+            })
+            .on_click(cx.listener(|this, _, cx| {
+                this.handle_send_message(cx);
+            }))
+            .child(
+                svg_icon(IconName::ArrowUp, IconSize::Small)
+                    .text_color(if has_text { white() } else { rgb(colors.text_secondary) }),
+            )
+    }
+
+    // ========================================================================
+    // Context Panel
+    // ========================================================================
+
+    fn render_context_panel(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+
+        div()
+            .id("context-panel")
+            .track_focus(&self.context_panel_focus)
+            .on_key_down(cx.listener(Self::handle_context_panel_key))
+            .w(px(self.context_panel_width))
+            .h_full()
+            .flex_shrink_0()
+            .overflow_hidden()
+            .flex()
+            .flex_col()
+            .bg(rgb(colors.sidebar_bg))  // Same as left sidebar
+            .border_l_1()                 // Left border for separation
+            .border_color(rgb(colors.border))
+            .child(self.render_progress_section(cx))
+            .when(self.acp.is_dry_run() || !self.acp.proposed_actions().is_empty(), |el| {
+                el.child(self.render_proposed_actions_section(cx))
+            })
+            .child(self.render_instructions_section(cx))
+            .child(self.render_tests_section(cx))
+            .child(self.render_problems_section(cx))
+            .child(self.render_collapsible_section("Artifacts", cx))
+            .child(self.render_collapsible_section("Context", cx))
+    }
+
+    /// Render the workspace instructions editor
+    fn render_instructions_section(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let is_expanded = self.expanded_sections.contains(&"Instructions".to_string());
+        let arrow_icon = if is_expanded { IconName::ChevronDown } else { IconName::ChevronRight };
+        let saved = self.acp.manager.workspace_instructions.clone();
+        let current = self.instructions_input.read(cx).content().trim().to_string();
+        let has_changes = current != saved;
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .border_b_1()
+            .border_color(rgb(colors.border))
+            .child(
+                div()
+                    .id("section-instructions")
+                    .w_full()
+                    .h(dense(40.0))
+                    .px(dense(16.0))
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .cursor_pointer()
+                    .hover(|s| s.bg(rgba(colors.hover)))
+                    .when(self.is_section_highlighted("Instructions", cx), |el| {
+                        el.border_1().border_color(rgb(colors.focus_ring))
+                    })
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.toggle_section("Instructions", cx);
+                    }))
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .gap(dense(8.0))
+                            .child(
+                                svg_icon(arrow_icon, IconSize::XSmall)
+                                    .text_color(rgb(colors.text_secondary)),
+                            )
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .font_weight(FontWeight::MEDIUM)
+                                    .text_color(rgb(colors.text_primary))
+                                    .child(t("context.instructions")),
+                            ),
+                    )
+                    .when(!saved.is_empty(), |el| {
+                        el.child(
+                            div()
+                                .text_xs()
+                                .text_color(rgb(colors.success))
+                                .child(t("common.on")),
+                        )
+                    }),
+            )
+            .when(is_expanded, |el| {
+                el.child(
+                    div()
+                        .w_full()
+                        .px(dense(16.0))
+                        .pb(dense(12.0))
+                        .flex()
+                        .flex_col()
+                        .gap(dense(8.0))
+                        .child(
+                            div()
+                                .text_xs()
+                                .text_color(rgb(colors.text_secondary))
+                                .child(t_args(
+                                    "context.instructions_scope",
+                                    &[("dir", &self.acp.manager.get_working_dir().display())],
+                                )),
+                        )
+                        .child(
+                            div()
+                                .w_full()
+                                .min_h(dense(60.0))
+                                .p(dense(8.0))
+                                .rounded(px(6.0))
+                                .border_1()
+                                .border_color(rgb(colors.border))
+                                .bg(rgb(colors.surface))
+                                .text_sm()
+                                .child(self.instructions_input.clone()),
+                        )
+                        .child(
+                            div()
+                                .flex()
+                                .items_center()
+                                .justify_between()
+                                .child(match &self.instructions_status {
+                                    Some(Ok(())) => div()
+                                        .text_xs()
+                                        .text_color(rgb(colors.success))
+                                        .child(t("common.saved")),
+                                    Some(Err(e)) => div()
+                                        .text_xs()
+                                        .text_color(rgb(colors.error))
+                                        .child(e.clone()),
+                                    None => div(),
+                                })
+                                .child(
+                                    div()
+                                        .id("save-instructions-btn")
+                                        .px(dense(12.0))
+                                        .py(dense(4.0))
+                                        .rounded(px(6.0))
+                                        .text_xs()
+                                        .when(has_changes, |el| {
+                                            el.bg(rgb(colors.primary))
+                                                .text_color(white())
+                                                .cursor_pointer()
+                                                .hover(|el| el.bg(rgb(colors.primary_hover)))
+                                                .on_click(cx.listener(|this, _, cx| {
+                                                    this.save_instructions(cx);
+                                                }))
+                                        })
+                                        .when(!has_changes, |el| {
+                                            el.bg(rgb(colors.surface))
+                                                .text_color(rgb(colors.text_secondary))
+                                        })
+                                        .child(t("common.save")),
+                                ),
+                        ),
+                )
+            })
+    }
+
+    /// Render the workspace's test command and the counts of its last run
+    fn render_tests_section(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let is_expanded = self.expanded_sections.contains(&"Tests".to_string());
+        let arrow_icon = if is_expanded { IconName::ChevronDown } else { IconName::ChevronRight };
+        let config = self.acp.manager.test_config();
+        let run_after_turn = config.as_ref().is_some_and(|config| config.run_after_turn);
+        let current = self.test_command_input.read(cx).content().trim().to_string();
+        let has_changes = current != config.as_ref().map(|config| config.command.as_str()).unwrap_or_default();
+        let testing = self.acp.manager.is_testing();
+        let run = self.acp.manager.test_run();
+        let attaching = self.acp.attaching_test_results();
+
+        let status = if testing {
+            Some((t("tests.running").to_string(), colors.text_secondary))
+        } else {
+            run.map(|run| match &run.summary {
+                Some(summary) if summary.failed > 0 => {
+                    (t_args("tests.failed_count", &[("count", &summary.failed)]), colors.error)
+                }
+                Some(summary) => (t_args("tests.passed_count", &[("count", &summary.passed)]), colors.success),
+                None if run.passed() => (t("tests.passed").to_string(), colors.success),
+                None => (t_args("tests.exit_code", &[("code", &run.exit_code)]), colors.error),
+            })
+        };
+        let chip = |id: &'static str, label: &'static str, selected: bool| {
+            div()
+                .id(id)
+                .px(dense(8.0))
+                .py(dense(2.0))
+                .rounded(px(4.0))
+                .text_xs()
+                .cursor_pointer()
+                .when(selected, |el| {
+                    el.bg(rgba(colors.primary.with_alpha(0.2))).text_color(rgb(colors.text_primary))
+                })
+                .when(!selected, |el| el.text_color(rgb(colors.text_secondary)).hover(|el| el.bg(rgb(colors.hover))))
+                .child(label)
+        };
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .border_b_1()
+            .border_color(rgb(colors.border))
+            .child(
+                div()
+                    .id("section-tests")
+                    .w_full()
+                    .h(dense(40.0))
+                    .px(dense(16.0))
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .cursor_pointer()
+                    .hover(|s| s.bg(rgba(colors.hover)))
+                    .when(self.is_section_highlighted("Tests", cx), |el| {
+                        el.border_1().border_color(rgb(colors.focus_ring))
+                    })
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.toggle_section("Tests", cx);
+                    }))
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .gap(dense(8.0))
+                            .child(
+                                svg_icon(arrow_icon, IconSize::XSmall)
+                                    .text_color(rgb(colors.text_secondary)),
+                            )
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .font_weight(FontWeight::MEDIUM)
+                                    .text_color(rgb(colors.text_primary))
+                                    .child(t("context.tests")),
+                            ),
+                    )
+                    .when_some(status, |el, (label, color)| {
+                        el.child(div().text_xs().text_color(rgb(color)).child(label))
+                    }),
+            )
+            .when(is_expanded, |el| {
+                el.child(
+                    div()
+                        .w_full()
+                        .px(dense(16.0))
+                        .pb(dense(12.0))
+                        .flex()
+                        .flex_col()
+                        .gap(dense(8.0))
+                        .child(
+                            div()
+                                .flex()
+                                .items_center()
+                                .gap(dense(8.0))
+                                .child(
+                                    div()
+                                        .flex_1()
+                                        .p(dense(6.0))
+                                        .rounded(px(6.0))
+                                        .border_1()
+                                        .border_color(rgb(colors.border))
+                                        .bg(rgb(colors.surface))
+                                        .text_sm()
+                                        .font_family("monospace")
+                                        .child(self.test_command_input.clone()),
+                                )
+                                .child(
+                                    div()
+                                        .id("save-test-command-btn")
+                                        .px(dense(12.0))
+                                        .py(dense(4.0))
+                                        .rounded(px(6.0))
+                                        .text_xs()
+                                        .when(has_changes, |el| {
+                                            el.bg(rgb(colors.primary))
+                                                .text_color(white())
+                                                .cursor_pointer()
+                                                .hover(|el| el.bg(rgb(colors.primary_hover)))
+                                                .on_click(cx.listener(move |this, _, cx| {
+                                                    this.save_test_config(run_after_turn, cx);
+                                                }))
+                                        })
+                                        .when(!has_changes, |el| {
+                                            el.bg(rgb(colors.surface))
+                                                .text_color(rgb(colors.text_secondary))
+                                        })
+                                        .child(t("common.save")),
+                                ),
+                        )
+                        .child(
+                            div()
+                                .flex()
+                                .items_center()
+                                .justify_between()
+                                .child(
+                                    chip("tests-after-turn-btn", t("tests.run_after_turn"), run_after_turn)
+                                        .on_click(cx.listener(move |this, _, cx| {
+                                            this.save_test_config(!run_after_turn, cx);
+                                        })),
+                                )
+                                .child(
+                                    div()
+                                        .id("run-tests-btn")
+                                        .px(dense(12.0))
+                                        .py(dense(4.0))
+                                        .rounded(px(6.0))
+                                        .text_xs()
+                                        .when(!testing && config.is_some(), |el| {
+                                            el.bg(rgb(colors.primary))
+                                                .text_color(white())
+                                                .cursor_pointer()
+                                                .hover(|el| el.bg(rgb(colors.primary_hover)))
+                                                .on_click(cx.listener(|this, _, cx| {
+                                                    let workspace = this.acp.manager.get_working_dir();
+                                                    this.acp.manager.start_test_run(workspace);
+                                                    cx.notify();
+                                                }))
+                                        })
+                                        .when(testing || config.is_none(), |el| {
+                                            el.bg(rgb(colors.surface))
+                                                .text_color(rgb(colors.text_secondary))
+                                        })
+                                        .child(t(if testing { "tests.running" } else { "tests.run" })),
+                                ),
+                        )
+                        .when_some(
+                            self.acp.manager.test_error.clone().or_else(|| {
+                                self.test_command_status.clone().and_then(|status| status.err())
+                            }),
+                            |el, e| el.child(div().text_xs().text_color(rgb(colors.error)).child(e)),
+                        )
+                        .when_some(run, |el, run| {
+                            let counts = match &run.summary {
+                                Some(summary) => t_args(
+                                    "tests.counts",
+                                    &[
+                                        ("passed", &summary.passed),
+                                        ("failed", &summary.failed),
+                                        ("skipped", &summary.skipped),
+                                    ],
+                                ),
+                                None => t_args("tests.exit_code", &[("code", &run.exit_code)]),
+                            };
+                            let failures =
+                                run.summary.as_ref().map(|summary| summary.failures.clone()).unwrap_or_default();
+                            let seconds = format!("{:.1}", run.duration_ms as f64 / 1000.0);
+                            el.child(
+                                div()
+                                    .text_xs()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .child(t_args("tests.last_run", &[("counts", &counts), ("seconds", &seconds)])),
+                            )
+                            .when(!failures.is_empty(), |el| {
+                                el.child(
+                                    div()
+                                        .flex()
+                                        .flex_col()
+                                        .gap(dense(2.0))
+                                        .children(failures.into_iter().map(|name| {
+                                            div()
+                                                .text_xs()
+                                                .font_family("monospace")
+                                                .text_color(rgb(colors.error))
+                                                .text_ellipsis()
+                                                .child(name)
+                                        })),
+                                )
+                            })
+                            .when(self.acp.active_session().is_some(), |el| {
+                                el.child(
+                                    div().flex().child(
+                                        chip("attach-test-results-btn", t("tests.attach"), attaching).on_click(
+                                            cx.listener(|this, _, cx| {
+                                                this.acp.toggle_attach_test_results();
+                                                cx.notify();
+                                            }),
+                                        ),
+                                    ),
+                                )
+                            })
+                        }),
+                )
+            })
+    }
+
+    /// Render the workspace's build and lint commands, its diagnostics file
+    /// and the problems they report
+    fn render_problems_section(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let is_expanded = self.expanded_sections.contains(&"Problems".to_string());
+        let arrow_icon = if is_expanded { IconName::ChevronDown } else { IconName::ChevronRight };
+        let config = self.acp.manager.check_config();
+        let run_after_turn = config.as_ref().is_some_and(|config| config.run_after_turn);
+        let saved = config.as_ref().map(|config| config.commands.join("\n")).unwrap_or_default();
+        let saved_file = config.as_ref().and_then(|config| config.diagnostics_file.clone()).unwrap_or_default();
+        let has_changes = self.check_commands_input.read(cx).content().trim() != saved
+            || self.diagnostics_file_input.read(cx).content().trim() != saved_file;
+        let can_run = config.as_ref().is_some_and(|config| !config.commands.is_empty());
+        let checking = self.acp.manager.is_checking();
+        let run = self.acp.manager.check_run();
+        let diagnostics_file = self.acp.manager.diagnostics_file();
+        let problems = self.acp.manager.problems();
+
+        let errors = problems.iter().filter(|problem| problem.severity == Severity::Error).count();
+        let warnings = problems.iter().filter(|problem| problem.severity == Severity::Warning).count();
+        let status = if checking {
+            Some((t("tests.running").to_string(), colors.text_secondary))
+        } else if errors + warnings > 0 {
+            let color = if errors > 0 { colors.error } else { colors.text_secondary };
+            Some((t_args("problems.counts", &[("errors", &errors), ("warnings", &warnings)]), color))
+        } else if run.is_some_and(|run| !run.passed()) {
+            Some((t("problems.command_failed").to_string(), colors.error))
+        } else if run.is_some() || diagnostics_file.is_some_and(|file| file.updated_at().is_some()) {
+            Some((t("problems.none").to_string(), colors.success))
+        } else {
+            None
+        };
+        let hidden = problems.len().saturating_sub(MAX_PROBLEMS_SHOWN);
+        let shown: Vec<_> = problems.into_iter().take(MAX_PROBLEMS_SHOWN).collect();
+        let failed_commands: Vec<(String, i32)> = run
+            .map(|run| {
+                run.results
+                    .iter()
+                    .filter(|result| result.exit_code != 0)
+                    .map(|result| (result.command.clone(), result.exit_code))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .border_b_1()
+            .border_color(rgb(colors.border))
+            .child(
+                div()
+                    .id("section-problems")
+                    .w_full()
+                    .h(dense(40.0))
+                    .px(dense(16.0))
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .cursor_pointer()
+                    .hover(|s| s.bg(rgba(colors.hover)))
+                    .when(self.is_section_highlighted("Problems", cx), |el| {
+                        el.border_1().border_color(rgb(colors.focus_ring))
+                    })
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.toggle_section("Problems", cx);
+                    }))
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .gap(dense(8.0))
+                            .child(
+                                svg_icon(arrow_icon, IconSize::XSmall)
+                                    .text_color(rgb(colors.text_secondary)),
+                            )
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .font_weight(FontWeight::MEDIUM)
+                                    .text_color(rgb(colors.text_primary))
+                                    .child(t("context.problems")),
+                            ),
+                    )
+                    .when_some(status, |el, (label, color)| {
+                        el.child(div().text_xs().text_color(rgb(color)).child(label))
+                    }),
+            )
+            .when(is_expanded, |el| {
+                el.child(
+                    div()
+                        .w_full()
+                        .px(dense(16.0))
+                        .pb(dense(12.0))
+                        .flex()
+                        .flex_col()
+                        .gap(dense(8.0))
+                        .child(
+                            div()
+                                .text_xs()
+                                .text_color(rgb(colors.text_secondary))
+                                .child(t("problems.commands_hint")),
+                        )
+                        .child(
+                            div()
+                                .w_full()
+                                .min_h(dense(40.0))
+                                .p(dense(6.0))
+                                .rounded(px(6.0))
+                                .border_1()
+                                .border_color(rgb(colors.border))
+                                .bg(rgb(colors.surface))
+                                .text_sm()
+                                .font_family("monospace")
+                                .child(self.check_commands_input.clone()),
+                        )
+                        .child(
+                            div()
+                                .text_xs()
+                                .text_color(rgb(colors.text_secondary))
+                                .child(t("problems.diagnostics_file_hint")),
+                        )
+                        .child(
+                            div()
+                                .w_full()
+                                .p(dense(6.0))
+                                .rounded(px(6.0))
+                                .border_1()
+                                .border_color(rgb(colors.border))
+                                .bg(rgb(colors.surface))
+                                .text_sm()
+                                .font_family("monospace")
+                                .child(self.diagnostics_file_input.clone()),
+                        )
+                        .child(
+                            div()
+                                .flex()
+                                .items_center()
+                                .gap(dense(6.0))
+                                .child(
+                                    div()
+                                        .id("problems-after-turn-btn")
+                                        .px(dense(8.0))
+                                        .py(dense(2.0))
+                                        .rounded(px(4.0))
+                                        .text_xs()
+                                        .cursor_pointer()
+                                        .when(run_after_turn, |el| {
+                                            el.bg(rgba(colors.primary.with_alpha(0.2)))
+                                                .text_color(rgb(colors.text_primary))
+                                        })
+                                        .when(!run_after_turn, |el| {
+                                            el.text_color(rgb(colors.text_secondary))
+                                                .hover(|el| el.bg(rgb(colors.hover)))
+                                        })
+                                        .on_click(cx.listener(move |this, _, cx| {
+                                            this.save_check_config(!run_after_turn, cx);
+                                        }))
+                                        .child(t("tests.run_after_turn")),
+                                )
+                                .child(div().flex_1())
+                                .child(
+                                    div()
+                                        .id("save-check-commands-btn")
+                                        .px(dense(12.0))
+                                        .py(dense(4.0))
+                                        .rounded(px(6.0))
+                                        .text_xs()
+                                        .when(has_changes, |el| {
+                                            el.bg(rgb(colors.primary))
+                                                .text_color(white())
+                                                .cursor_pointer()
+                                                .hover(|el| el.bg(rgb(colors.primary_hover)))
+                                                .on_click(cx.listener(move |this, _, cx| {
+                                                    this.save_check_config(run_after_turn, cx);
+                                                }))
+                                        })
+                                        .when(!has_changes, |el| {
+                                            el.bg(rgb(colors.surface))
+                                                .text_color(rgb(colors.text_secondary))
+                                        })
+                                        .child(t("common.save")),
+                                )
+                                .child(
+                                    div()
+                                        .id("run-checks-btn")
+                                        .px(dense(12.0))
+                                        .py(dense(4.0))
+                                        .rounded(px(6.0))
+                                        .text_xs()
+                                        .when(!checking && can_run, |el| {
+                                            el.bg(rgb(colors.primary))
+                                                .text_color(white())
+                                                .cursor_pointer()
+                                                .hover(|el| el.bg(rgb(colors.primary_hover)))
+                                                .on_click(cx.listener(|this, _, cx| {
+                                                    let workspace = this.acp.manager.get_working_dir();
+                                                    this.acp.manager.start_check_run(workspace);
+                                                    cx.notify();
+                                                }))
+                                        })
+                                        .when(checking || !can_run, |el| {
+                                            el.bg(rgb(colors.surface))
+                                                .text_color(rgb(colors.text_secondary))
+                                        })
+                                        .child(t(if checking { "tests.running" } else { "problems.run" })),
+                                ),
+                        )
+                        .when_some(
+                            self.acp
+                                .manager
+                                .check_error
+                                .clone()
+                                .or_else(|| self.check_commands_status.clone().and_then(|status| status.err()))
+                                .or_else(|| diagnostics_file.and_then(|file| file.error())),
+                            |el, e| el.child(div().text_xs().text_color(rgb(colors.error)).child(e)),
+                        )
+                        .children(failed_commands.into_iter().map(|(command, code)| {
+                            div()
+                                .text_xs()
+                                .text_color(rgb(colors.error))
+                                .overflow_hidden()
+                                .whitespace_nowrap()
+                                .text_ellipsis()
+                                .child(t_args("problems.exited", &[("command", &command), ("code", &code)]))
+                        }))
+                        .when(!shown.is_empty(), |el| {
+                            el.child(
+                                div()
+                                    .flex()
+                                    .flex_col()
+                                    .gap(dense(2.0))
+                                    .children(shown.into_iter().enumerate().map(|(i, problem)| {
+                                        let color = match problem.severity {
+                                            Severity::Error => colors.error,
+                                            _ => colors.text_secondary,
+                                        };
+                                        let location = format!("{}:{}", problem.path, problem.line);
+                                        let (path, line) = (problem.path.clone(), problem.line as usize);
+                                        div()
+                                            .id(SharedString::from(format!("problem-{}", i)))
+                                            .px(dense(4.0))
+                                            .py(dense(2.0))
+                                            .rounded(px(4.0))
+                                            .flex()
+                                            .gap(dense(6.0))
+                                            .text_xs()
+                                            .cursor_pointer()
+                                            .hover(|el| el.bg(rgb(colors.hover)))
+                                            .on_click(cx.listener(move |this, _, cx| {
+                                                this.acp.manager.open_file_preview_at(&path, Some(line));
+                                                cx.notify();
+                                            }))
+                                            .child(
+                                                div()
+                                                    .flex_shrink_0()
+                                                    .font_family("monospace")
+                                                    .text_color(rgb(color))
+                                                    .child(location),
+                                            )
+                                            .child(
+                                                div()
+                                                    .flex_1()
+                                                    .min_w_0()
+                                                    .overflow_hidden()
+                                                    .whitespace_nowrap()
+                                                    .text_ellipsis()
+                                                    .text_color(rgb(colors.text_primary))
+                                                    .child(problem.message),
+                                            )
+                                    })),
+                            )
+                            .when(hidden > 0, |el| {
+                                el.child(
+                                    div()
+                                        .text_xs()
+                                        .text_color(rgb(colors.text_secondary))
+                                        .child(t_args("problems.more", &[("count", &hidden)])),
+                                )
+                            })
+                            .child(
+                                div().flex().child(
+                                    div()
+                                        .id("send-problems-btn")
+                                        .px(dense(12.0))
+                                        .py(dense(4.0))
+                                        .rounded(px(6.0))
+                                        .text_xs()
+                                        .bg(rgb(colors.primary))
+                                        .text_color(white())
+                                        .cursor_pointer()
+                                        .hover(|el| el.bg(rgb(colors.primary_hover)))
+                                        .on_click(cx.listener(|this, _, cx| this.send_problems(cx)))
+                                        .child(t("problems.send")),
+                                ),
+                            )
+                        }),
+                )
+            })
+    }
+
+    /// Render the Progress section showing task/plan completion
+    fn render_progress_section(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let is_expanded = self.expanded_sections.contains(&"Progress".to_string());
+        let arrow_icon = if is_expanded { IconName::ChevronDown } else { IconName::ChevronRight };
+
+        // Get real plan data from ACP session
+        let plan_entries: Vec<PlanEntry> = self
+            .acp
+            .active_session()
+            .and_then(|s| s.current_task.as_ref())
+            .map(|t| t.plan.clone())
+            .unwrap_or_default();
+
+        let completed_count = plan_entries
+            .iter()
+            .filter(|e| matches!(e.status, PlanStatus::Completed))
+            .count();
+        let total_count = plan_entries.len();
+        let has_plan = !plan_entries.is_empty();
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .border_b_1()
+            .border_color(rgb(colors.border))
+            .child(
+                div()
+                    .id("section-progress")
+                    .w_full()
+                    .h(dense(40.0))
+                    .px(dense(16.0))
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .cursor_pointer()
+                    .hover(|s| s.bg(rgba(colors.hover)))
+                    .when(self.is_section_highlighted("Progress", cx), |el| {
+                        el.border_1().border_color(rgb(colors.focus_ring))
+                    })
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.toggle_section("Progress", cx);
+                    }))
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .gap(dense(8.0))
+                            .child(
+                                svg_icon(arrow_icon, IconSize::XSmall)
+                                    .text_color(rgb(colors.text_secondary)),
+                            )
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .font_weight(FontWeight::MEDIUM)
+                                    .text_color(rgb(colors.text_primary))
+                                    .child(t("context.progress")),
+                            ),
+                    )
+                    // Progress indicator
+                    .when(has_plan, |el| {
+                        el.child(
+                            div()
+                                .text_xs()
+                                .text_color(rgb(colors.text_secondary))
+                                .child(format!("{}/{}", completed_count, total_count)),
+                        )
+                    }),
+            )
+            .when(is_expanded, |el| {
+                el.child(
+                    div()
+                        .w_full()
+                        .px(dense(16.0))
+                        .py(dense(12.0))
+                        .flex()
+                        .flex_col()
+                        .gap(dense(8.0))
+                        // Show progress bar only if there's a plan
+                        .when(has_plan, |el| {
+                            let progress_pct = if total_count > 0 {
+                                (completed_count as f32 / total_count as f32) * 100.0
+                            } else {
+                                0.0
+                            };
+                            el.child(
+                                div()
+                                    .w_full()
+                                    .h(px(4.0))
+                                    .rounded(px(2.0))
+                                    .bg(rgb(colors.surface))
+                                    .child(
+                                        div()
+                                            .h_full()
+                                            .w(px(progress_pct * 2.48)) // 248px max width
+                                            .rounded(px(2.0))
+                                            .bg(rgb(colors.primary)),
+                                    ),
+                            )
+                        })
+                        // Plan items or empty state
+                        .when(has_plan, |el| {
+                            el.child(
+                                div()
+                                    .flex()
+                                    .flex_col()
+                                    .gap(dense(4.0))
+                                    .children(plan_entries.iter().enumerate().map(|(idx, entry)| {
+                                        self.render_plan_item(idx, entry, cx)
+                                    })),
+                            )
+                        })
+                        .when(!has_plan, |el| {
+                            el.child(
+                                div()
+                                    .py(dense(8.0))
+                                    .flex()
+                                    .items_center()
+                                    .justify_center()
+                                    .child(
+                                        div()
+                                            .text_sm()
+                                            .text_color(rgb(colors.text_secondary))
+                                            .child(t("context.no_plan")),
+                                    ),
+                            )
+                        }),
+                )
+            })
+    }
+
+    /// Render the dry-run review section listing proposed actions
+    fn render_proposed_actions_section(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let actions = self.acp.proposed_actions();
+        let selected_count = actions
+            .iter()
+            .filter(|a| !self.deselected_actions.contains(&a.id))
+            .count();
+
+        div()
+            .id("proposed-actions")
+            .w_full()
+            .max_h(px(360.0))
+            .overflow_y_scroll()
+            .flex()
+            .flex_col()
+            .border_b_1()
+            .border_color(rgb(colors.border))
+            .child(
+                div()
+                    .w_full()
+                    .h(dense(40.0))
+                    .px(dense(16.0))
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(
+                        div()
+                            .text_sm()
+                            .font_weight(FontWeight::MEDIUM)
+                            .text_color(rgb(colors.text_primary))
+                            .child(t("context.proposed_changes")),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(format!("{}/{}", selected_count, actions.len())),
+                    ),
+            )
+            .when(actions.is_empty(), |el| {
+                el.child(
+                    div()
+                        .px(dense(16.0))
+                        .pb(dense(12.0))
+                        .text_sm()
+                        .text_color(rgb(colors.text_secondary))
+                        .child(t("context.dry_run_hint")),
+                )
+            })
+            .children(actions.iter().map(|action| self.render_proposed_action(action, cx)))
+            .when(!actions.is_empty(), |el| {
+                el.child(
+                    div()
+                        .px(dense(16.0))
+                        .py(dense(10.0))
+                        .flex()
+                        .justify_end()
+                        .gap(dense(8.0))
+                        .child(
+                            div()
+                                .id("discard-proposed")
+                                .px(dense(12.0))
+                                .py(dense(6.0))
+                                .rounded(px(6.0))
+                                .bg(rgb(colors.surface))
+                                .text_xs()
+                                .text_color(rgb(colors.text_secondary))
+                                .cursor_pointer()
+                                .hover(|el| el.bg(rgb(colors.border)))
+                                .on_click(cx.listener(|this, _, cx| {
+                                    if let Some(id) = this.acp.active_session_id.clone() {
+                                        this.acp.manager.discard_proposed_actions(&id);
+                                    }
+                                    this.deselected_actions.clear();
+                                    cx.notify();
+                                }))
+                                .child(t("context.discard_all")),
+                        )
+                        .child(
+                            div()
+                                .id("apply-proposed")
+                                .px(dense(12.0))
+                                .py(dense(6.0))
+                                .rounded(px(6.0))
+                                .bg(rgb(colors.primary))
+                                .text_xs()
+                                .text_color(white())
+                                .cursor_pointer()
+                                .hover(|el| el.bg(rgb(colors.primary_hover)))
+                                .on_click(cx.listener(|this, _, cx| {
+                                    this.apply_selected_actions(cx);
+                                }))
+                                .child(t_args("context.apply", &[("count", &selected_count)])),
+                        ),
+                )
+            })
+    }
+
+    /// Render a single proposed action with its checkbox and preview
+    fn render_proposed_action(&self, action: &ProposedAction, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let action_id = action.id.clone();
+        let selected = !self.deselected_actions.contains(&action.id);
+
+        // Preview: diff lines for writes, the command line for executions
+        let preview: Vec<(DiffLineKind, String)> = match &action.action {
+            ProposedActionKind::WriteFile { diff, .. } => diff
+                .hunks
+                .iter()
+                .flat_map(|h| h.lines.iter())
+                .filter(|l| l.kind != DiffLineKind::Context)
+                .take(12)
+                .map(|l| (l.kind, l.content.clone()))
+                .collect(),
+            ProposedActionKind::Execute { cwd, .. } => cwd
+                .iter()
+                .map(|dir| (DiffLineKind::Context, t_args("common.in_dir", &[("dir", &dir)])))
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        div()
+            .id(SharedString::from(format!("proposed-{}", action.id)))
+            .w_full()
+            .px(dense(16.0))
+            .py(dense(6.0))
+            .flex()
+            .flex_col()
+            .gap(dense(4.0))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(dense(8.0))
+                    .cursor_pointer()
+                    .on_mouse_down(MouseButton::Left, cx.listener(move |this, _, cx| {
+                        if !this.deselected_actions.remove(&action_id) {
+                            this.deselected_actions.insert(action_id.clone());
+                        }
+                        cx.notify();
+                    }))
+                    .child(
+                        svg_icon(
+                            if selected { IconName::CircleCheck } else { IconName::Circle },
+                            IconSize::XSmall,
+                        )
+                        .text_color(if selected { rgb(colors.primary) } else { rgb(colors.text_secondary) }),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .min_w_0()
+                            .text_xs()
+                            .text_color(rgb(colors.text_primary))
+                            .text_ellipsis()
+                            .child(action.summary()),
+                    ),
+            )
+            .when(!preview.is_empty(), |el| {
+                el.child(
+                    div()
+                        .ml(px(20.0))
+                        .px(dense(8.0))
+                        .py(dense(4.0))
+                        .rounded(px(4.0))
+                        .bg(rgb(colors.code_bg))
+                        .font_family("monospace")
+                        .text_xs()
+                        .flex()
+                        .flex_col()
+                        .children(preview.into_iter().map(|(kind, text)| {
+                            let (prefix, color) = match kind {
+                                DiffLineKind::Add => ("+", colors.success),
+                                DiffLineKind::Remove => ("-", colors.error),
+                                DiffLineKind::Context => (" ", colors.code_text),
+                            };
+                            div()
+                                .text_color(rgb(color))
+                                .child(format!("{}{}", prefix, text))
+                        })),
+                )
+            })
+    }
+
+    fn apply_selected_actions(&mut self, cx: &mut ViewContext<Self>) {
+        let ids: Vec<String> = self
+            .acp
+            .proposed_actions()
+            .into_iter()
+            .map(|a| a.id)
+            .filter(|id| !self.deselected_actions.contains(id))
+            .collect();
+        self.acp.manager.apply_proposed_actions(&ids);
+        cx.notify();
+    }
+
+    /// Render a single plan item
+    /// A plan step: clicking its icon marks it done, then skipped, then hands it
+    /// back to the agent; clicking the text jumps to where work on it began
+    fn render_plan_item(&self, idx: usize, entry: &PlanEntry, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let status = entry.status;
+        let session = self.acp.active_session();
+        let overridden = session.is_some_and(|session| session.plan_status_overridden(&entry.content));
+        let started = session.and_then(|session| session.plan_started.get(&entry.content).copied());
+
+        let (status_icon, icon_color) = match status {
+            PlanStatus::Completed => (IconName::Check, colors.success),
+            PlanStatus::InProgress => (IconName::Circle, colors.primary),
+            PlanStatus::Pending => (IconName::Circle, colors.text_secondary),
+            PlanStatus::Skipped => (IconName::Close, colors.text_secondary),
+        };
+        let (priority, priority_color) = match entry.priority {
+            PlanPriority::High => (t("plan.priority.high"), colors.error),
+            PlanPriority::Medium => (t("plan.priority.medium"), colors.warning),
+            PlanPriority::Low => (t("plan.priority.low"), colors.text_secondary),
+        };
+        let next_status = match (overridden, status) {
+            (false, _) => Some(PlanStatus::Completed),
+            (true, PlanStatus::Completed) => Some(PlanStatus::Skipped),
+            (true, _) => None,
+        };
+        let content = entry.content.clone();
+        let step = entry.content.clone();
+
+        div()
+            .w_full()
+            .py(dense(4.0))
+            .flex()
+            .items_center()
+            .gap(dense(8.0))
+            .child(
+                div()
+                    .id(SharedString::from(format!("plan-status-{}", idx)))
+                    .flex_shrink_0()
+                    .cursor_pointer()
+                    .tooltip(move |cx| {
+                        let hint = match next_status {
+                            Some(PlanStatus::Completed) => t("plan.mark_done"),
+                            Some(_) => t("plan.mark_skipped"),
+                            None => t("plan.reset"),
+                        };
+                        Tooltip::text(hint, cx)
+                    })
+                    .on_click(cx.listener(move |this, _, cx| {
+                        this.acp.set_plan_status(&content, next_status);
+                        cx.notify();
+                    }))
+                    .child(svg_icon(status_icon, IconSize::XSmall).text_color(rgb(icon_color))),
+            )
+            .child(
+                div()
+                    .id(SharedString::from(format!("plan-entry-{}", idx)))
+                    .flex_1()
+                    .min_w_0()
+                    .text_xs()
+                    .text_color(match status {
+                        PlanStatus::InProgress => rgb(colors.text_primary),
+                        PlanStatus::Completed | PlanStatus::Pending | PlanStatus::Skipped => {
+                            rgb(colors.text_secondary)
+                        }
+                    })
+                    .when_some(started, |el, started| {
+                        el.cursor_pointer()
+                            .hover(|s| s.text_color(rgb(colors.primary)))
+                            .on_click(cx.listener(move |this, _, cx| {
+                                this.plan_scroll_target = Some(started);
+                                cx.notify();
+                            }))
+                    })
+                    .child(entry.content.clone()),
+            )
+            .child(
+                div()
+                    .flex_shrink_0()
+                    .px(px(4.0))
+                    .rounded(px(3.0))
+                    .text_xs()
+                    .bg(rgba(priority_color.with_alpha(0.15)))
+                    .text_color(rgb(priority_color))
+                    .child(priority),
+            )
+            .child(
+                div()
+                    .id(SharedString::from(format!("plan-spawn-{}", idx)))
+                    .flex_shrink_0()
+                    .cursor_pointer()
+                    .tooltip(|cx| Tooltip::text(t("plan.spawn_subthread"), cx))
+                    .on_click(cx.listener(move |this, _, cx| {
+                        this.spawn_subthread(step.clone(), cx);
+                    }))
+                    .child(svg_icon(IconName::Plus, IconSize::XSmall).text_color(rgb(colors.text_secondary))),
+            )
+    }
+
+    fn render_collapsible_section(
+        &self,
+        title: &str,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let is_expanded = self.expanded_sections.contains(&title.to_string());
+        let arrow_icon = if is_expanded { IconName::ChevronDown } else { IconName::ChevronRight };
+        let section_name = title.to_string();
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .border_b_1()
+            .border_color(rgb(colors.border))
+            .child(
+                div()
+                    .id(SharedString::from(format!("section-{}", title.to_lowercase())))
+                    .w_full()
+                    .h(dense(40.0))
+                    .px(dense(16.0))
+                    .flex()
+                    .items_center()
+                    .gap(dense(8.0))
+                    .cursor_pointer()
+                    .hover(|s| s.bg(rgba(colors.hover)))
+                    .when(self.is_section_highlighted(title, cx), |el| {
+                        el.border_1().border_color(rgb(colors.focus_ring))
+                    })
+                    .on_click(cx.listener(move |this, _, cx| {
+                        this.toggle_section(&section_name, cx);
+                    }))
+                    .child(
+                        svg_icon(arrow_icon, IconSize::XSmall)
+                            .text_color(rgb(colors.text_secondary)),
+                    )
+                    .child(
+                        div()
+                            .text_sm()
+                            .font_weight(FontWeight::MEDIUM)
+                            .text_color(rgb(colors.text_primary))
+                            .child(section_label(title)),
+                    ),
+            )
+            .when(is_expanded, |el| {
+                el.child(
+                    div()
+                        .w_full()
+                        .min_h(dense(80.0))
+                        .px(dense(16.0))
+                        .py(dense(12.0))
+                        .child(
+                            div()
+                                .text_sm()
+                                .text_color(rgb(colors.text_secondary))
+                                .child(self.render_section_content(title)),
+                        ),
+                )
+            })
+    }
+
+    fn render_section_content(&self, section: &str) -> String {
+        match section {
+            "Artifacts" => t("context.no_artifacts").to_string(),
+            "Context" => t("context.no_context").to_string(),
+            _ => "".to_string(),
+        }
+    }
+}
+
+// ============================================================================
+// Render Implementation
+// ============================================================================
+
+impl FocusableView for CocoWorkWindow {
+    fn focus_handle(&self, _cx: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for CocoWorkWindow {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        self.sync_preview_lines();
+        cx.set_rem_size(px(REM_SIZE * self.theme.density.text_factor()));
+        if self.show_debug_overlay {
+            self.frame_times.record(std::time::Instant::now());
+        }
+        let colors = &self.theme.colors;
+
+        div()
+            .id("cocowork-window")
+            .key_context("CocoWorkWindow")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::focus_next_region))
+            .on_action(cx.listener(Self::focus_previous_region))
+            .on_action(cx.listener(|this, _: &ZoomIn, cx| this.set_zoom(this.theme.scale + UI_SCALE_STEP, cx)))
+            .on_action(cx.listener(|this, _: &ZoomOut, cx| this.set_zoom(this.theme.scale - UI_SCALE_STEP, cx)))
+            .on_action(cx.listener(|this, _: &ResetZoom, cx| this.set_zoom(1.0, cx)))
+            .on_action(cx.listener(Self::toggle_sidebar))
+            .on_action(cx.listener(Self::toggle_context_panel))
+            .on_action(cx.listener(Self::toggle_terminal_panel))
+            .on_action(cx.listener(Self::toggle_debug_overlay))
+            .size_full()
+            .flex()
+            .flex_col()
+            .bg(rgb(colors.panel_bg))
+            .text_color(rgb(colors.text_primary))
+            .on_mouse_down(MouseButton::Left, cx.listener(|this, _, cx| {
+                this.close_menus(cx);
+            }))
+            .on_mouse_move(cx.listener(|this, event: &MouseMoveEvent, cx| {
+                this.resize_sidebar(event, cx);
+                this.resize_context_panel(event, cx);
+            }))
+            .on_mouse_up(MouseButton::Left, cx.listener(|this, event: &MouseUpEvent, cx| {
+                this.stop_resizing_sidebar(event, cx);
+                this.stop_resizing_context_panel(event, cx);
+            }))
+            .on_mouse_up_out(MouseButton::Left, cx.listener(|this, event: &MouseUpEvent, cx| {
+                this.stop_resizing_sidebar(event, cx);
+                this.stop_resizing_context_panel(event, cx);
+            }))
+            .on_key_down(cx.listener(|this, event: &KeyDownEvent, cx| {
+                if event.keystroke.key == "escape" {
+                    if this.acp.manager.file_preview.is_some() {
+                        this.acp.manager.close_file_preview();
+                        cx.notify();
+                    }
+                    this.close_menus(cx);
+                }
+            }))
+            // Top bar
+            .child(self.render_top_bar(cx))
+            // Main content (three panels)
+            .child(
+                div()
+                    .flex_1()
+                    .min_h_0()  // Critical: Allow shrinking in flex column for child scrolling to work
+                    .flex()
+                    .flex_row()
+                    .overflow_hidden()
+                    .when(self.sidebar_collapsed, |el| el.child(self.render_sidebar_rail(cx)))
+                    .when(!self.sidebar_collapsed, |el| {
+                        el.child(self.render_sidebar(cx)).child(self.render_sidebar_resizer(cx))
+                    })
+                    .child(self.render_main_panel(cx))
+                    .when(!self.context_panel_collapsed, |el| {
+                        el.child(self.render_context_panel_resizer(cx)).child(self.render_context_panel(cx))
+                    })
+                    .when(self.context_panel_collapsed, |el| el.child(self.render_context_panel_rail(cx)))
+            )
+            // Bottom bar
+            .child(self.render_bottom_bar(cx))
+            // New thread dialog (modal overlay)
+            .when(self.show_new_thread_dialog, |el| {
+                el.child(self.render_new_thread_dialog(cx))
+            })
+            // Batched file change review (modal overlay)
+            .when_some(self.acp.manager.ready_change_set(), |el, session_id| {
+                el.child(self.render_change_set_sheet(&session_id, cx))
+            })
+            // Isolated thread's changes (modal overlay)
+            .when_some(self.isolation_review.clone(), |el, review| {
+                el.child(self.render_isolation_review(review, cx))
+            })
+            // Commit of the agent's changes (modal overlay)
+            .when_some(self.commit_review.clone(), |el, review| {
+                el.child(self.render_commit_dialog(review, cx))
+            })
+            // Sensitive strings to hide before a thread export (modal overlay)
+            .when(self.export_review.is_some(), |el| el.child(self.render_export_review(cx)))
+            // Pull request wizard (modal overlay)
+            .when_some(self.pull_request_wizard.clone(), |el, target| {
+                el.child(self.render_pull_request_wizard(target, cx))
+            })
+            // Agent sign-in guide (modal overlay)
+            .when(self.acp.manager.auth_flow.is_some(), |el| {
+                el.child(self.render_auth_dialog(cx))
+            })
+            // Agent capability inspector (modal overlay)
+            .when(self.show_capabilities, |el| {
+                el.child(self.render_capabilities_dialog(cx))
+            })
+            // Batch run across workspaces (modal overlay)
+            .when(self.show_batch_dialog, |el| {
+                el.child(self.render_batch_dialog(cx))
+            })
+            // Agent comparison setup (modal overlay)
+            .when(self.show_compare_dialog, |el| {
+                el.child(self.render_compare_dialog(cx))
+            })
+            // Settings (modal overlay)
+            .when(self.show_settings_dialog, |el| {
+                el.child(self.render_settings_dialog(cx))
+            })
+            // Agent install/update progress (modal overlay)
+            .when(self.show_install_dialog, |el| {
+                el.child(self.render_install_dialog(cx))
+            })
+            // Read-only file preview (modal overlay)
+            .when(self.acp.manager.file_preview.is_some(), |el| {
+                el.child(self.render_file_preview(cx))
+            })
+            // Terminal command confirmation (modal overlay)
+            .when(self.acp.manager.current_confirmation().is_some(), |el| {
+                el.child(self.render_command_confirmation_dialog(cx))
+            })
+            // Tool call permission with the agent's options (modal overlay)
+            .when(self.acp.manager.current_permission_request().is_some(), |el| {
+                el.child(self.render_permission_request_dialog(cx))
+            })
+            .when(self.show_debug_overlay, |el| el.child(self.render_debug_overlay()))
+    }
+}
+
+impl CocoWorkWindow {
+    /// Frame rate, timeline size, backlogs and pool usage, drawn over everything
+    fn render_debug_overlay(&self) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let now = std::time::Instant::now();
+        let runtime = self.acp.manager.runtime_stats();
+        let cache = self.message_markdown_cache.stats();
+        let rows = [
+            (
+                t("debug.fps"),
+                format!("{} ({} ms max)", self.frame_times.fps(now), self.frame_times.slowest_frame().as_millis()),
+            ),
+            (t("debug.timeline"), format!("{} / {}", self.rendered_timeline_len, self.timeline_len())),
+            (t("debug.notifications"), runtime.notification_backlog.to_string()),
+            (t("debug.requests"), runtime.pending_requests.to_string()),
+            (t("debug.tasks"), format!("{} / {}", runtime.tokio_tasks, runtime.tokio_workers)),
+            (
+                t("debug.db_pool"),
+                format!("{} / {} ({} idle)", runtime.db_connections - runtime.db_idle, runtime.db_max, runtime.db_idle),
+            ),
+            (
+                t("debug.markdown_cache"),
+                format!("{}, {}", cache.entries, format_size(cache.bytes as u64)),
+            ),
+            (
+                t("debug.markdown_cache_use"),
+                format!("{} / {} / {}", cache.hits, cache.misses, cache.evictions),
+            ),
+        ];
+
+        div()
+            .absolute()
+            .top(px(48.0))
+            .right(px(12.0))
+            .w(px(280.0))
+            .p(px(10.0))
+            .rounded(px(6.0))
+            .bg(rgba(colors.surface_elevated.with_alpha(0.92)))
+            .border_1()
+            .border_color(rgb(colors.border))
+            .shadow_lg()
+            .flex()
+            .flex_col()
+            .gap(px(2.0))
+            .font_family("monospace")
+            .text_xs()
+            .child(
+                div()
+                    .pb(px(4.0))
+                    .font_weight(FontWeight::SEMIBOLD)
+                    .text_color(rgb(colors.text_secondary))
+                    .child(t("debug.title")),
+            )
+            .children(rows.into_iter().map(|(label, value)| {
+                div()
+                    .flex()
+                    .justify_between()
+                    .gap(px(8.0))
+                    .child(div().text_color(rgb(colors.text_secondary)).child(label))
+                    .child(div().text_color(rgb(colors.text_primary)).child(value))
+            }))
+    }
+}
+
+impl CocoWorkWindow {
+    fn render_env_profile_picker(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let manager = &self.acp.manager;
+        // (id, label) with `None` meaning no profile
+        let options: Vec<(Option<String>, String)> = std::iter::once((None, t("common.none").to_string()))
+            .chain(
+                manager
+                    .env_profiles
+                    .iter()
+                    .map(|p| (Some(p.id.clone()), p.name.clone())),
+            )
+            .collect();
+
+        div()
+            .px(px(20.0))
+            .py(px(10.0))
+            .border_b_1()
+            .border_color(rgb(colors.border_subtle))
+            .flex()
+            .items_center()
+            .gap(px(6.0))
+            .child(
+                div()
+                    .text_xs()
+                    .font_weight(FontWeight::SEMIBOLD)
+                    .text_color(rgb(colors.text_secondary))
+                    .mr(px(4.0))
+                    .child(t("settings.env.badge")),
+            )
+            .children(options.into_iter().map(|(profile_id, label)| {
+                let is_selected = manager.selected_env_profile == profile_id;
+                let chip_id = profile_id.clone().unwrap_or_else(|| "none".to_string());
+
+                div()
+                    .id(SharedString::from(format!("env-profile-{}", chip_id)))
+                    .px(px(8.0))
+                    .py(px(2.0))
+                    .rounded(px(4.0))
+                    .text_xs()
+                    .cursor_pointer()
+                    .when(is_selected, |el| {
+                        el.bg(rgba(colors.primary.with_alpha(0.15)))
+                            .text_color(rgb(colors.primary))
+                    })
+                    .when(!is_selected, |el| {
+                        el.text_color(rgb(colors.text_secondary))
+                            .hover(|el| el.bg(rgb(colors.hover)))
+                    })
+                    .on_click(cx.listener(move |this, _, cx| {
+                        this.acp.manager.select_env_profile(profile_id.clone());
+                        cx.notify();
+                    }))
+                    .child(label)
+            }))
+            .child(
+                div()
+                    .id("manage-env-profiles-btn")
+                    .ml_auto()
+                    .text_xs()
+                    .text_color(rgb(colors.text_secondary))
+                    .cursor_pointer()
+                    .hover(|el| el.text_color(rgb(colors.text_primary)))
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.show_new_thread_dialog = false;
+                        this.open_settings(cx);
+                    }))
+                    .child(t("common.manage")),
+            )
+    }
+
+    /// URL of a GitHub issue to start the thread from, and what was fetched from it
+    fn render_new_thread_issue(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let importing = self.acp.manager.is_importing_issue();
+        let status = match &self.acp.manager.issue {
+            _ if importing => Some((t("new_thread.fetching_issue").to_string(), colors.text_secondary)),
+            Some(Ok(issue)) => Some((
+                t_args("new_thread.issue_fetched", &[("title", &issue.title), ("count", &issue.comments.len())]),
+                colors.text_secondary,
+            )),
+            Some(Err(e)) => Some((e.clone(), colors.error)),
+            None => None,
+        };
+
+        div()
+            .px(px(20.0))
+            .py(px(8.0))
+            .border_b_1()
+            .border_color(rgb(colors.border_subtle))
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(6.0))
+                    .child(
+                        div()
+                            .w(px(110.0))
+                            .flex_none()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("new_thread.issue")),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .min_w_0()
+                            .h(px(28.0))
+                            .px(px(8.0))
+                            .flex()
+                            .items_center()
+                            .rounded(px(6.0))
+                            .border_1()
+                            .border_color(rgb(colors.border))
+                            .bg(rgb(colors.surface))
+                            .text_sm()
+                            .child(self.new_thread_issue_input.clone()),
+                    )
+                    .child(
+                        div()
+                            .id("fetch-issue-btn")
+                            .text_xs()
+                            .when(importing, |el| el.text_color(rgb(colors.text_disabled)))
+                            .when(!importing, |el| {
+                                el.text_color(rgb(colors.primary))
+                                    .cursor_pointer()
+                                    .on_click(cx.listener(|this, _, cx| this.import_issue(cx)))
+                            })
+                            .child(t("new_thread.fetch_issue")),
+                    ),
+            )
+            .when_some(status, |el, (message, tint)| {
+                el.child(div().pl(px(116.0)).text_xs().text_color(rgb(tint)).text_ellipsis().child(message))
+            })
+    }
+
+    fn render_new_thread_advanced(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let expanded = self.show_new_thread_advanced;
+        let label = |key: &str| {
+            div()
+                .w(px(110.0))
+                .flex_none()
+                .text_xs()
+                .text_color(rgb(colors.text_secondary))
+                .child(t(key))
+        };
+        let input_box = |input: &View<TextInput>| {
+            div()
+                .flex_1()
+                .min_w_0()
+                .h(px(28.0))
+                .px(px(8.0))
+                .flex()
+                .items_center()
+                .rounded(px(6.0))
+                .border_1()
+                .border_color(rgb(colors.border))
+                .bg(rgb(colors.surface))
+                .text_sm()
+                .child(input.clone())
+        };
+        let working_dir = self.new_thread_dir.clone().unwrap_or_else(|| self.acp.get_working_dir());
+
+        div()
+            .px(px(20.0))
+            .py(px(8.0))
+            .border_b_1()
+            .border_color(rgb(colors.border_subtle))
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .child(
+                div()
+                    .id("new-thread-advanced-toggle")
+                    .text_xs()
+                    .font_weight(FontWeight::SEMIBOLD)
+                    .text_color(rgb(colors.text_secondary))
+                    .cursor_pointer()
+                    .hover(|el| el.text_color(rgb(colors.text_primary)))
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.show_new_thread_advanced = !this.show_new_thread_advanced;
+                        cx.notify();
+                    }))
+                    .child(format!("{} {}", if expanded { "▾" } else { "▸" }, t("new_thread.advanced"))),
+            )
+            .when(expanded, |el| {
+                el.child(
+                    div()
+                        .flex()
+                        .items_center()
+                        .gap(px(6.0))
+                        .child(label("new_thread.mcp_servers"))
+                        .children(self.mcp_servers.iter().map(|server| {
+                            let name = server.name.clone();
+                            let is_selected = self.new_thread_mcp.contains(&server.name);
+
+                            div()
+                                .id(SharedString::from(format!("new-thread-mcp-{}", server.name)))
+                                .px(px(8.0))
+                                .py(px(2.0))
+                                .rounded(px(4.0))
+                                .text_xs()
+                                .cursor_pointer()
+                                .when(is_selected, |el| {
+                                    el.bg(rgba(colors.primary.with_alpha(0.15)))
+                                        .text_color(rgb(colors.primary))
+                                })
+                                .when(!is_selected, |el| {
+                                    el.text_color(rgb(colors.text_secondary))
+                                        .hover(|el| el.bg(rgb(colors.hover)))
+                                })
+                                .on_click(cx.listener(move |this, _, cx| {
+                                    if !this.new_thread_mcp.remove(&name) {
+                                        this.new_thread_mcp.insert(name.clone());
+                                    }
+                                    cx.notify();
+                                }))
+                                .child(server.name.clone())
+                        }))
+                        .when(self.mcp_servers.is_empty(), |el| {
+                            el.child(
+                                div()
+                                    .text_xs()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .child(t("mcp.none")),
+                            )
+                        }),
+                )
+                .child(
+                    div()
+                        .flex()
+                        .items_center()
+                        .gap(px(6.0))
+                        .child(label("new_thread.variables"))
+                        .child(input_box(&self.new_thread_env_input)),
+                )
+                .child(
+                    div()
+                        .flex()
+                        .items_center()
+                        .gap(px(6.0))
+                        .child(label("new_thread.mode"))
+                        .child(input_box(&self.new_thread_mode_input))
+                        .child(
+                            div()
+                                .flex_none()
+                                .text_xs()
+                                .text_color(rgb(colors.text_secondary))
+                                .child(t("new_thread.model")),
+                        )
+                        .child(input_box(&self.new_thread_model_input)),
+                )
+                .child(
+                    div()
+                        .flex()
+                        .items_center()
+                        .gap(px(6.0))
+                        .child(label("new_thread.working_dir"))
+                        .child(
+                            div()
+                                .flex_1()
+                                .min_w_0()
+                                .text_xs()
+                                .font_family("monospace")
+                                .text_color(if self.new_thread_dir.is_some() {
+                                    rgb(colors.text_primary)
+                                } else {
+                                    rgb(colors.text_secondary)
+                                })
+                                .overflow_hidden()
+                                .whitespace_nowrap()
+                                .text_ellipsis()
+                                .child(working_dir.display().to_string()),
+                        )
+                        .child(
+                            div()
+                                .id("new-thread-choose-dir")
+                                .text_xs()
+                                .text_color(rgb(colors.text_secondary))
+                                .cursor_pointer()
+                                .hover(|el| el.text_color(rgb(colors.text_primary)))
+                                .on_click(cx.listener(|this, _, cx| this.select_new_thread_dir(cx)))
+                                .child(t("new_thread.choose_dir")),
+                        )
+                        .when(self.new_thread_dir.is_some(), |el| {
+                            el.child(
+                                div()
+                                    .id("new-thread-reset-dir")
+                                    .text_xs()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .cursor_pointer()
+                                    .hover(|el| el.text_color(rgb(colors.text_primary)))
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.new_thread_dir = None;
+                                        cx.notify();
+                                    }))
+                                    .child(t("common.reset")),
+                            )
+                        }),
+                )
+                .child(
+                    div()
+                        .id("new-thread-isolate")
+                        .flex()
+                        .items_center()
+                        .gap(px(6.0))
+                        .cursor_pointer()
+                        .on_click(cx.listener(|this, _, cx| {
+                            this.new_thread_isolate = !this.new_thread_isolate;
+                            cx.notify();
+                        }))
+                        .child(
+                            svg_icon(
+                                if self.new_thread_isolate { IconName::CircleCheck } else { IconName::Circle },
+                                IconSize::XSmall,
+                            )
+                            .text_color(if self.new_thread_isolate {
+                                rgb(colors.primary)
+                            } else {
+                                rgb(colors.text_secondary)
+                            }),
+                        )
+                        .child(
+                            div()
+                                .text_xs()
+                                .text_color(rgb(colors.text_primary))
+                                .child(t("new_thread.isolate")),
+                        ),
+                )
+                .when_some(self.new_thread_error.clone(), |el, error| {
+                    el.child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(colors.error))
+                            .child(error),
+                    )
+                })
+            })
+    }
+
+    fn render_new_thread_dialog(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let agents = self.acp.available_agents();
+
+        // Modal overlay
+        div()
+            .absolute()
+            .inset_0()
+            .flex()
+            .items_center()
+            .justify_center()
+            .bg(rgba(colors.panel_bg.with_alpha(0.9)))
+            .on_mouse_down(MouseButton::Left, cx.listener(|this, _, cx| {
+                this.show_new_thread_dialog = false;
+                cx.notify();
+            }))
+            .child(
+                // Dialog box
+                div()
+                    .w(px(400.0))
+                    .max_h(px(500.0))
+                    .bg(rgb(colors.surface_elevated))
+                    .rounded(px(12.0))
+                    .border_1()
+                    .border_color(rgb(colors.border))
+                    .shadow_lg()
+                    .flex()
+                    .flex_col()
+                    .on_mouse_down(MouseButton::Left, |_, cx| {
+                        cx.stop_propagation();
+                    })
+                    // Header
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(16.0))
+                            .border_b_1()
+                            .border_color(rgb(colors.border))
+                            .flex()
+                            .items_center()
+                            .justify_between()
+                            .child(
+                                div()
+                                    .text_lg()
+                                    .font_weight(FontWeight::SEMIBOLD)
+                                    .text_color(rgb(colors.text_primary))
+                                    .child(if self.subtask_seed.is_some() {
+                                        t("new_thread.subtask_title")
+                                    } else {
+                                        t("new_thread.title")
+                                    }),
+                            )
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .child(t("new_thread.select_agent")),
+                            ),
+                    )
+                    // Env profile picker
+                    .child(self.render_env_profile_picker(cx))
+                    // Issue to start from
+                    .when(self.subtask_seed.is_none(), |el| el.child(self.render_new_thread_issue(cx)))
+                    // Session overrides
+                    .child(self.render_new_thread_advanced(cx))
+                    // Agent list
+                    .child(
+                        div()
+                            .id("agent-list")
+                            .flex_1()
+                            .overflow_scroll()
+                            .p(px(12.0))
+                            .flex()
+                            .flex_col()
+                            .gap(px(8.0))
+                            .children(agents.iter().map(|agent| {
+                                let agent_id = agent.id.clone();
+                                let agent_name = agent.name.clone();
+                                let agent_desc = agent.description.clone().unwrap_or_default();
+                                let is_selected = self.acp.manager.selected_agent_id.as_ref() == Some(&agent_id);
+                                let availability = self.acp.agent_availability(&agent_id);
+                                let not_installed = availability.map(|a| !a.available).unwrap_or(false);
+                                let install_hint = availability
+                                    .filter(|a| !a.available)
+                                    .and_then(|a| a.install_instructions.clone());
+                                let checking = availability.is_none() && self.acp.is_checking_availability();
+                                let can_install = availability
+                                    .map(|a| a.available && a.installable && !a.installed)
+                                    .unwrap_or(false);
+                                let install_agent_id = agent_id.clone();
+
+                                div()
+                                    .id(SharedString::from(format!("agent-{}", agent_id)))
+                                    .px(px(16.0))
+                                    .py(px(12.0))
+                                    .rounded(px(8.0))
+                                    .border_1()
+                                    .when(is_selected, |el| {
+                                        el.border_color(rgb(colors.primary))
+                                            .bg(rgba(colors.primary.with_alpha(0.1)))
+                                    })
+                                    .when(!is_selected, |el| {
+                                        el.border_color(rgb(colors.border))
+                                            .hover(|el| el.bg(rgb(colors.surface)))
+                                    })
+                                    .when(not_installed, |el| el.opacity(0.7))
+                                    .when(!not_installed, |el| {
+                                        el.cursor_pointer()
+                                            .on_click(cx.listener(move |this, _, cx| {
+                                                this.create_new_thread_with_agent(&agent_id, cx);
+                                            }))
+                                    })
+                                    .child(
+                                        div()
+                                            .flex()
+                                            .flex_col()
+                                            .gap(px(4.0))
+                                            .child(
+                                                div()
+                                                    .flex()
+                                                    .items_center()
+                                                    .gap(px(8.0))
+                                                    .child(
+                                                        div()
+                                                            .text_base()
+                                                            .font_weight(FontWeight::MEDIUM)
+                                                            .text_color(rgb(colors.text_primary))
+                                                            .child(agent_name),
+                                                    )
+                                                    .when(is_selected, |el| {
+                                                        el.child(
+                                                            div()
+                                                                .text_xs()
+                                                                .px(px(6.0))
+                                                                .py(px(2.0))
+                                                                .rounded(px(4.0))
+                                                                .bg(rgb(colors.primary))
+                                                                .text_color(rgb(ThemeRgba::rgb(0xFFFFFF))) // White text on primary
+                                                                .child(t("common.current")),
+                                                        )
+                                                    })
+                                                    .when(not_installed, |el| {
+                                                        el.child(
+                                                            div()
+                                                                .text_xs()
+                                                                .px(px(6.0))
+                                                                .py(px(2.0))
+                                                                .rounded(px(4.0))
+                                                                .bg(rgba(colors.warning.with_alpha(0.2)))
+                                                                .text_color(rgb(colors.warning))
+                                                                .child(t("installs.not_installed")),
+                                                        )
+                                                    })
+                                                    .when(checking, |el| {
+                                                        el.child(
+                                                            div()
+                                                                .text_xs()
+                                                                .text_color(rgb(colors.text_secondary))
+                                                                .child(t("common.checking")),
+                                                        )
+                                                    })
+                                                    .when(can_install, |el| {
+                                                        el.child(
+                                                            div()
+                                                                .id(SharedString::from(format!("install-{}", install_agent_id)))
+                                                                .ml_auto()
+                                                                .text_xs()
+                                                                .px(px(8.0))
+                                                                .py(px(2.0))
+                                                                .rounded(px(4.0))
+                                                                .border_1()
+                                                                .border_color(rgb(colors.border))
+                                                                .text_color(rgb(colors.text_primary))
+                                                                .cursor_pointer()
+                                                                .hover(|el| el.bg(rgb(colors.hover)))
+                                                                .on_click(cx.listener(move |this, _, cx| {
+                                                                    cx.stop_propagation();
+                                                                    this.acp.manager.install_agent(&install_agent_id, false);
+                                                                    this.show_install_dialog = true;
+                                                                    cx.notify();
+                                                                }))
+                                                                .child(t("common.install")),
+                                                        )
+                                                    }),
+                                            )
+                                            .when(!agent_desc.is_empty(), |el| {
+                                                el.child(
+                                                    div()
+                                                        .text_sm()
+                                                        .text_color(rgb(colors.text_secondary))
+                                                        .child(agent_desc),
+                                                )
+                                            })
+                                            .when_some(install_hint, |el, hint| {
+                                                el.child(
+                                                    div()
+                                                        .mt(px(4.0))
+                                                        .text_xs()
+                                                        .text_color(rgb(colors.text_secondary))
+                                                        .child(hint),
+                                                )
+                                            }),
+                                    )
+                            })),
+                    )
+                    // Footer
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(12.0))
+                            .border_t_1()
+                            .border_color(rgb(colors.border))
+                            .flex()
+                            .justify_between()
+                            .child(
+                                div()
+                                    .flex()
+                                    .gap(px(4.0))
+                                    .child(
+                                        div()
+                                            .id("recheck-agents-btn")
+                                            .px(px(16.0))
+                                            .py(px(8.0))
+                                            .rounded(px(6.0))
+                                            .text_sm()
+                                            .text_color(rgb(colors.text_secondary))
+                                            .cursor_pointer()
+                                            .hover(|el| el.bg(rgb(colors.surface)))
+                                            .on_click(cx.listener(|this, _, cx| {
+                                                this.acp.start_availability_check();
+                                                cx.notify();
+                                            }))
+                                            .child(if self.acp.is_checking_availability() {
+                                                t("common.checking")
+                                            } else {
+                                                t("installs.recheck")
+                                            }),
+                                    )
+                                    .child(
+                                        div()
+                                            .id("check-agent-updates-btn")
+                                            .px(px(16.0))
+                                            .py(px(8.0))
+                                            .rounded(px(6.0))
+                                            .text_sm()
+                                            .text_color(rgb(colors.text_secondary))
+                                            .cursor_pointer()
+                                            .hover(|el| el.bg(rgb(colors.surface)))
+                                            .on_click(cx.listener(|this, _, cx| {
+                                                this.acp.manager.check_for_agent_updates();
+                                                this.show_install_dialog = true;
+                                                cx.notify();
+                                            }))
+                                            .child(t("menu.check_updates")),
+                                    ),
+                            )
+                            .child(
+                                div()
+                                    .id("cancel-btn")
+                                    .px(px(16.0))
+                                    .py(px(8.0))
+                                    .rounded(px(6.0))
+                                    .bg(rgb(colors.surface))
+                                    .text_sm()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .cursor_pointer()
+                                    .hover(|el| el.bg(rgb(colors.border)))
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.show_new_thread_dialog = false;
+                                        cx.notify();
+                                    }))
+                                    .child(t("common.cancel")),
+                            ),
+                    ),
+            )
+    }
+}
+
+impl CocoWorkWindow {
+    fn open_settings(&mut self, cx: &mut ViewContext<Self>) {
+        self.acp.manager.load_stored_secrets();
+        self.acp.manager.load_env_profiles();
+        self.secret_error = None;
+        self.env_profile_error = None;
+        self.proxy_status = None;
+        self.webhook_error = None;
+        self.policy_error = None;
+        self.template_error = None;
+        self.acp.manager.database_status = None;
+        self.acp.manager.load_backups();
+        self.acp.manager.load_prompt_templates();
+
+        // Show the saved proxy in the inputs
+        let proxy = self.acp.manager.proxy.clone();
+        let values = [proxy.http_proxy, proxy.https_proxy, proxy.no_proxy];
+        for (input, value) in self.proxy_inputs.clone().iter().zip(values) {
+            input.update(cx, |input, cx| input.set_content(value.unwrap_or_default(), cx));
+        }
+        self.load_container_settings(cx);
+        self.load_terminal_limits(cx);
+        self.show_settings_dialog = true;
+        cx.notify();
+    }
+
+    fn save_secret(&mut self, key: SecretKey, cx: &mut ViewContext<Self>) {
+        let Some(input) = self
+            .secret_inputs
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, input)| input.clone())
+        else {
+            return;
+        };
+        let value = input.read(cx).content().to_string();
+        match self.acp.manager.save_secret(key, &value) {
+            Ok(()) => {
+                self.secret_error = None;
+                input.update(cx, |input, cx| input.clear(cx));
+            }
+            Err(e) => self.secret_error = Some(format!("{}: {}", key.label(), e)),
+        }
+        cx.notify();
+    }
+
+    fn clear_secret(&mut self, key: SecretKey, cx: &mut ViewContext<Self>) {
+        self.secret_error = self
+            .acp
+            .manager
+            .clear_secret(key)
+            .err()
+            .map(|e| format!("{}: {}", key.label(), e));
+        cx.notify();
+    }
+
+    fn add_env_profile(&mut self, cx: &mut ViewContext<Self>) {
+        let name = self.env_profile_name_input.read(cx).content().to_string();
+        let variables = self.env_profile_vars_input.read(cx).content().to_string();
+        match self.acp.manager.add_env_profile(&name, &variables) {
+            Ok(()) => {
+                self.env_profile_error = None;
+                self.env_profile_name_input.update(cx, |input, cx| input.clear(cx));
+                self.env_profile_vars_input.update(cx, |input, cx| input.clear(cx));
+            }
+            Err(e) => self.env_profile_error = Some(e),
+        }
+        cx.notify();
+    }
+
+    fn save_prompt_template(&mut self, cx: &mut ViewContext<Self>) {
+        let name = self.template_name_input.read(cx).content().to_string();
+        let body = self.template_body_input.read(cx).content().to_string();
+        match self.acp.manager.save_prompt_template(&name, &body) {
+            Ok(()) => {
+                self.template_error = None;
+                self.template_name_input.update(cx, |input, cx| input.clear(cx));
+                self.template_body_input.update(cx, |input, cx| input.clear(cx));
+            }
+            Err(e) => self.template_error = Some(e),
+        }
+        cx.notify();
+    }
+
+    fn save_proxy_settings(&mut self, cx: &mut ViewContext<Self>) {
+        let [http, https, no_proxy] = self
+            .proxy_inputs
+            .clone()
+            .map(|input| input.read(cx).content().to_string());
+        self.proxy_status = Some(self.acp.manager.save_proxy_settings(&http, &https, &no_proxy));
+        cx.notify();
+    }
+
+    /// Show the working directory's container settings in the inputs
+    fn load_container_settings(&mut self, cx: &mut ViewContext<Self>) {
+        let config = self.acp.manager.container_config();
+        let mounts = config.mounts.iter().map(|mount| mount.to_string()).collect::<Vec<_>>().join(", ");
+        let [image_input, mounts_input] = self.container_inputs.clone();
+        image_input.update(cx, |input, cx| input.set_content(config.image, cx));
+        mounts_input.update(cx, |input, cx| input.set_content(mounts, cx));
+        self.container_runtime = config.runtime;
+        self.container_network = config.network;
+        self.container_status = None;
+    }
+
+    /// Show the saved command limits in the inputs, blank for no limit
+    fn load_terminal_limits(&mut self, cx: &mut ViewContext<Self>) {
+        let limits = self.acp.manager.terminal_policy().limits;
+        let values = [
+            limits.cpu_time_secs,
+            limits.timeout_secs,
+            limits.max_output_bytes.map(|bytes| (bytes / 1024) as u64),
+            limits.max_processes.map(|count| count as u64),
+        ];
+        for (input, value) in self.limit_inputs.clone().iter().zip(values) {
+            let value = value.map(|value| value.to_string()).unwrap_or_default();
+            input.update(cx, |input, cx| input.set_content(value, cx));
+        }
+        self.limits_status = None;
+    }
+
+    fn save_terminal_limits(&mut self, cx: &mut ViewContext<Self>) {
+        let [cpu, timeout, output, processes] = self
+            .limit_inputs
+            .clone()
+            .map(|input| input.read(cx).content().to_string());
+        self.limits_status = Some(self.acp.manager.save_terminal_limits(&cpu, &timeout, &output, &processes));
+        cx.notify();
+    }
+
+    fn set_terminal_backend(&mut self, backend: TerminalBackend, cx: &mut ViewContext<Self>) {
+        self.container_status = self.acp.manager.set_terminal_backend(backend).err().map(Err);
+        cx.notify();
+    }
+
+    fn save_container_settings(&mut self, cx: &mut ViewContext<Self>) {
+        let [image, mounts] = self
+            .container_inputs
+            .clone()
+            .map(|input| input.read(cx).content().to_string());
+        self.container_status = Some(self.acp.manager.save_container_config(
+            self.container_runtime,
+            &image,
+            &mounts,
+            self.container_network,
+        ));
+        cx.notify();
+    }
+
+    fn policy_scope_label(&self, scope: &PolicyScope) -> String {
+        match scope {
+            PolicyScope::Global => t("settings.permissions.everywhere").to_string(),
+            PolicyScope::Agent(_) => self.acp.selected_agent_name(),
+            PolicyScope::Workspace(path) => path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| t("settings.permissions.this_workspace").to_string()),
+        }
+    }
+
+    /// Change one kind's approval in the scope being edited
+    fn set_policy_approval(&mut self, operation: FileOperation, approval: Approval, cx: &mut ViewContext<Self>) {
+        let Some(scope) = self.acp.manager.policy_scopes().get(self.policy_scope_idx).cloned() else {
+            return;
+        };
+        let mut policy = self.acp.manager.tool_policy_for(&scope).unwrap_or_default();
+        policy.set_approval(operation, approval);
+        self.policy_error = self.acp.manager.set_tool_policy(&scope, Some(policy)).err();
+        cx.notify();
+    }
+
+    /// Remove the policy stored for the scope being edited
+    fn reset_policy_scope(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(scope) = self.acp.manager.policy_scopes().get(self.policy_scope_idx).cloned() else {
+            return;
+        };
+        self.policy_error = self.acp.manager.set_tool_policy(&scope, None).err();
+        cx.notify();
+    }
+
+    fn render_tool_policy_section(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let scopes = self.acp.manager.policy_scopes();
+        let scope_idx = self.policy_scope_idx.min(scopes.len() - 1);
+        let stored = self.acp.manager.tool_policy_for(&scopes[scope_idx]);
+        let policy = stored.unwrap_or_default();
+        let kinds = [
+            (t("settings.permissions.reads"), FileOperation::Read),
+            (t("settings.permissions.writes"), FileOperation::Write),
+            (t("settings.permissions.deletes"), FileOperation::Delete),
+            (t("settings.permissions.terminal"), FileOperation::Execute),
+        ];
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .pt(px(12.0))
+            .border_t_1()
+            .border_color(rgb(colors.border_subtle))
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(2.0))
+                    .child(
+                        div()
+                            .text_xs()
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.permissions.title")),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.permissions.description")),
+                    ),
+            )
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(4.0))
+                    .children(scopes.iter().enumerate().map(|(i, scope)| {
+                        let selected = i == scope_idx;
+                        div()
+                            .id(SharedString::from(format!("policy-scope-{}", i)))
+                            .px(px(8.0))
+                            .py(px(2.0))
+                            .rounded(px(4.0))
+                            .text_xs()
+                            .cursor_pointer()
+                            .when(selected, |el| {
+                                el.bg(rgba(colors.primary.with_alpha(0.2)))
+                                    .text_color(rgb(colors.text_primary))
+                            })
+                            .when(!selected, |el| {
+                                el.text_color(rgb(colors.text_secondary))
+                                    .hover(|el| el.bg(rgb(colors.hover)))
+                            })
+                            .on_click(cx.listener(move |this, _, cx| {
+                                this.policy_scope_idx = i;
+                                this.policy_error = None;
+                                cx.notify();
+                            }))
+                            .child(self.policy_scope_label(scope))
+                    })),
+            )
+            .children(kinds.into_iter().map(|(label, operation)| {
+                let current = policy.approval(operation);
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .child(
+                        div()
+                            .w(px(100.0))
+                            .text_sm()
+                            .text_color(rgb(colors.text_primary))
+                            .child(label),
+                    )
+                    .children(
+                        [Approval::Default, Approval::AutoAccept, Approval::Confirm]
+                            .into_iter()
+                            .map(|approval| {
+                                let selected = approval == current;
+                                div()
+                                    .id(SharedString::from(format!("policy-{}-{:?}", label, approval)))
+                                    .px(px(8.0))
+                                    .py(px(2.0))
+                                    .rounded(px(4.0))
+                                    .text_xs()
+                                    .cursor_pointer()
+                                    .when(selected, |el| {
+                                        el.bg(rgba(colors.primary.with_alpha(0.2)))
+                                            .text_color(rgb(colors.text_primary))
+                                    })
+                                    .when(!selected, |el| {
+                                        el.text_color(rgb(colors.text_secondary))
+                                            .hover(|el| el.bg(rgb(colors.hover)))
+                                    })
+                                    .on_click(cx.listener(move |this, _, cx| {
+                                        this.set_policy_approval(operation, approval, cx);
+                                    }))
+                                    .child(approval_label(approval))
+                            }),
+                    )
+            }))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .gap(px(8.0))
+                    .child(match &self.policy_error {
+                        Some(e) => div().text_xs().text_color(rgb(colors.error)).child(e.clone()),
+                        None => div()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t_args(
+                                "settings.permissions.in_effect",
+                                &[("summary", &policy_summary(&self.acp.manager.tool_policy))],
+                            )),
+                    })
+                    .when(stored.is_some(), |el| {
+                        el.child(
+                            div()
+                                .id("reset-policy-btn")
+                                .px(px(12.0))
+                                .py(px(6.0))
+                                .rounded(px(6.0))
+                                .text_xs()
+                                .text_color(rgb(colors.text_secondary))
+                                .cursor_pointer()
+                                .hover(|el| el.bg(rgb(colors.hover)))
+                                .on_click(cx.listener(|this, _, cx| {
+                                    this.reset_policy_scope(cx);
+                                }))
+                                .child(t("common.reset")),
+                        )
+                    }),
+            )
+    }
+
+    /// A settings section with an On/Off choice saved through `set`
+    fn render_switch_section(
+        &self,
+        id: &'static str,
+        title: &'static str,
+        description: &'static str,
+        enabled: bool,
+        set: fn(&mut AcpManager, bool) -> Result<(), String>,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        let colors = &self.theme.colors;
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .pt(px(12.0))
+            .border_t_1()
+            .border_color(rgb(colors.border_subtle))
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(2.0))
+                    .child(
+                        div()
+                            .text_xs()
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .text_color(rgb(colors.text_secondary))
+                            .child(title),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(description),
+                    ),
+            )
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(4.0))
+                    .children([(t("common.on"), true), (t("common.off"), false)].into_iter().map(|(label, value)| {
+                        let selected = value == enabled;
+                        div()
+                            .id(SharedString::from(format!("{}-{}", id, value)))
+                            .px(px(8.0))
+                            .py(px(2.0))
+                            .rounded(px(4.0))
+                            .text_xs()
+                            .cursor_pointer()
+                            .when(selected, |el| {
+                                el.bg(rgba(colors.primary.with_alpha(0.2)))
+                                    .text_color(rgb(colors.text_primary))
+                            })
+                            .when(!selected, |el| {
+                                el.text_color(rgb(colors.text_secondary))
+                                    .hover(|el| el.bg(rgb(colors.hover)))
+                            })
+                            .on_click(cx.listener(move |this, _, cx| {
+                                if let Err(e) = set(&mut this.acp.manager, value) {
+                                    tracing::warn!("Failed to save {} setting: {}", id, e);
+                                }
+                                this.apply_input_settings(cx);
+                                cx.notify();
+                            }))
+                            .child(label)
+                    })),
+            )
+    }
+
+    fn render_language_section(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let current = i18n::language();
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .pt(px(12.0))
+            .border_t_1()
+            .border_color(rgb(colors.border_subtle))
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(2.0))
+                    .child(
+                        div()
+                            .text_xs()
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.language.title")),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.language.description")),
+                    ),
+            )
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(4.0))
+                    .children(Language::ALL.into_iter().map(|language| {
+                        let selected = language == current;
+                        div()
+                            .id(SharedString::from(format!("language-{}", language.code())))
+                            .px(px(8.0))
+                            .py(px(2.0))
+                            .rounded(px(4.0))
+                            .text_xs()
+                            .cursor_pointer()
+                            .when(selected, |el| {
+                                el.bg(rgba(colors.primary.with_alpha(0.2)))
+                                    .text_color(rgb(colors.text_primary))
+                            })
+                            .when(!selected, |el| {
+                                el.text_color(rgb(colors.text_secondary))
+                                    .hover(|el| el.bg(rgb(colors.hover)))
+                            })
+                            .on_click(cx.listener(move |this, _, cx| this.set_language(language, cx)))
+                            .child(language.native_name())
+                    })),
+            )
+    }
+
+    fn render_density_section(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let current = self.theme.density;
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .pt(px(12.0))
+            .border_t_1()
+            .border_color(rgb(colors.border_subtle))
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(2.0))
+                    .child(
+                        div()
+                            .text_xs()
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.density.title")),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.density.description")),
+                    ),
+            )
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(4.0))
+                    .children(Density::ALL.into_iter().map(|density| {
+                        let selected = density == current;
+                        let label = match density {
+                            Density::Comfortable => t("settings.density.comfortable"),
+                            Density::Compact => t("settings.density.compact"),
+                        };
+                        div()
+                            .id(SharedString::from(format!("density-{}", density.code())))
+                            .px(px(8.0))
+                            .py(px(2.0))
+                            .rounded(px(4.0))
+                            .text_xs()
+                            .cursor_pointer()
+                            .when(selected, |el| {
+                                el.bg(rgba(colors.primary.with_alpha(0.2)))
+                                    .text_color(rgb(colors.text_primary))
+                            })
+                            .when(!selected, |el| {
+                                el.text_color(rgb(colors.text_secondary))
+                                    .hover(|el| el.bg(rgb(colors.hover)))
+                            })
+                            .on_click(cx.listener(move |this, _, cx| this.set_density(density, cx)))
+                            .child(label)
+                    })),
+            )
+    }
+
+    fn render_idle_section(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let current = self.acp.manager.idle_timeout.map(|timeout| timeout.as_secs() / 60);
+        let choices: [(String, Option<u64>); 4] = [
+            (t("settings.idle.never").to_string(), None),
+            (t_args("settings.idle.minutes", &[("minutes", &10)]), Some(10)),
+            (t_args("settings.idle.minutes", &[("minutes", &30)]), Some(30)),
+            (t("settings.idle.hour").to_string(), Some(60)),
+        ];
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .pt(px(12.0))
+            .border_t_1()
+            .border_color(rgb(colors.border_subtle))
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(2.0))
+                    .child(
+                        div()
+                            .text_xs()
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.idle.title")),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.idle.description")),
+                    ),
+            )
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(4.0))
+                    .children(choices.into_iter().map(|(label, minutes)| {
+                        let selected = minutes == current;
+                        div()
+                            .id(SharedString::from(format!("idle-{}", minutes.unwrap_or(0))))
+                            .px(px(8.0))
+                            .py(px(2.0))
+                            .rounded(px(4.0))
+                            .text_xs()
+                            .cursor_pointer()
+                            .when(selected, |el| {
+                                el.bg(rgba(colors.primary.with_alpha(0.2)))
+                                    .text_color(rgb(colors.text_primary))
+                            })
+                            .when(!selected, |el| {
+                                el.text_color(rgb(colors.text_secondary))
+                                    .hover(|el| el.bg(rgb(colors.hover)))
+                            })
+                            .on_click(cx.listener(move |this, _, cx| {
+                                if let Err(e) = this.acp.manager.set_idle_timeout(minutes) {
+                                    tracing::warn!("Failed to save idle disconnect setting: {}", e);
+                                }
+                                cx.notify();
+                            }))
+                            .child(label)
+                    })),
+            )
+    }
+
+    fn render_auto_retry_section(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let current = self.acp.manager.auto_retry_limit;
+        let choices: [(String, Option<u32>); 4] = [
+            (t("settings.auto_retry.off").to_string(), None),
+            (t_args("settings.auto_retry.times", &[("count", &1)]), Some(1)),
+            (t_args("settings.auto_retry.times", &[("count", &3)]), Some(3)),
+            (t_args("settings.auto_retry.times", &[("count", &5)]), Some(5)),
+        ];
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .pt(px(12.0))
+            .border_t_1()
+            .border_color(rgb(colors.border_subtle))
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(2.0))
+                    .child(
+                        div()
+                            .text_xs()
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.auto_retry.title")),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.auto_retry.description")),
+                    ),
+            )
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(4.0))
+                    .children(choices.into_iter().map(|(label, limit)| {
+                        let selected = limit == current;
+                        div()
+                            .id(SharedString::from(format!("auto-retry-{}", limit.unwrap_or(0))))
+                            .px(px(8.0))
+                            .py(px(2.0))
+                            .rounded(px(4.0))
+                            .text_xs()
+                            .cursor_pointer()
+                            .when(selected, |el| {
+                                el.bg(rgba(colors.primary.with_alpha(0.2)))
+                                    .text_color(rgb(colors.text_primary))
+                            })
+                            .when(!selected, |el| {
+                                el.text_color(rgb(colors.text_secondary))
+                                    .hover(|el| el.bg(rgb(colors.hover)))
+                            })
+                            .on_click(cx.listener(move |this, _, cx| {
+                                if let Err(e) = this.acp.manager.set_auto_retry_limit(limit) {
+                                    tracing::warn!("Failed to save auto-retry setting: {}", e);
+                                }
+                                cx.notify();
+                            }))
+                            .child(label)
+                    })),
+            )
+    }
+
+    fn render_attachment_limit_section(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let current = self.acp.manager.attachment_limit.map(|limit| limit / 1024);
+        let choices: [(String, Option<u64>); 4] = [
+            (t_args("settings.attachments.kb", &[("kb", &32)]), Some(32)),
+            (t_args("settings.attachments.kb", &[("kb", &128)]), Some(128)),
+            (t_args("settings.attachments.kb", &[("kb", &512)]), Some(512)),
+            (t("settings.attachments.none").to_string(), None),
+        ];
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .pt(px(12.0))
+            .border_t_1()
+            .border_color(rgb(colors.border_subtle))
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(2.0))
+                    .child(
+                        div()
+                            .text_xs()
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.attachments.title")),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.attachments.description")),
+                    ),
+            )
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(4.0))
+                    .children(choices.into_iter().map(|(label, kb)| {
+                        let selected = kb == current;
+                        div()
+                            .id(SharedString::from(format!("attachment-limit-{}", kb.unwrap_or(0))))
+                            .px(px(8.0))
+                            .py(px(2.0))
+                            .rounded(px(4.0))
+                            .text_xs()
+                            .cursor_pointer()
+                            .when(selected, |el| {
+                                el.bg(rgba(colors.primary.with_alpha(0.2)))
+                                    .text_color(rgb(colors.text_primary))
+                            })
+                            .when(!selected, |el| {
+                                el.text_color(rgb(colors.text_secondary))
+                                    .hover(|el| el.bg(rgb(colors.hover)))
+                            })
+                            .on_click(cx.listener(move |this, _, cx| {
+                                if let Err(e) = this.acp.manager.set_attachment_limit(kb) {
+                                    tracing::warn!("Failed to save attachment limit: {}", e);
+                                }
+                                cx.notify();
+                            }))
+                            .child(label)
+                    })),
+            )
+    }
+
+    fn render_markdown_cache_section(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let current = self.message_markdown_cache.budget();
+        let choices = [
+            (t("settings.markdown_cache.small"), MarkdownCacheBudget::SMALL),
+            (t("settings.markdown_cache.default"), MarkdownCacheBudget::DEFAULT),
+            (t("settings.markdown_cache.large"), MarkdownCacheBudget::LARGE),
+        ];
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .pt(px(12.0))
+            .border_t_1()
+            .border_color(rgb(colors.border_subtle))
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(2.0))
+                    .child(
+                        div()
+                            .text_xs()
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.markdown_cache.title")),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.markdown_cache.description")),
+                    ),
+            )
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(4.0))
+                    .children(choices.into_iter().map(|(label, budget)| {
+                        let selected = budget == current;
+                        let detail = t_args(
+                            "settings.markdown_cache.budget",
+                            &[("entries", &budget.max_entries), ("mb", &(budget.max_bytes / (1024 * 1024)))],
+                        );
+                        div()
+                            .id(SharedString::from(format!("markdown-cache-{}", budget.max_entries)))
+                            .px(px(8.0))
+                            .py(px(2.0))
+                            .rounded(px(4.0))
+                            .text_xs()
+                            .cursor_pointer()
+                            .when(selected, |el| {
+                                el.bg(rgba(colors.primary.with_alpha(0.2)))
+                                    .text_color(rgb(colors.text_primary))
+                            })
+                            .when(!selected, |el| {
+                                el.text_color(rgb(colors.text_secondary))
+                                    .hover(|el| el.bg(rgb(colors.hover)))
+                            })
+                            .tooltip(move |cx| Tooltip::text(detail.clone(), cx))
+                            .on_click(cx.listener(move |this, _, cx| this.set_markdown_cache_budget(budget, cx)))
+                            .child(label)
+                    })),
+            )
+    }
+
+    fn render_proxy_section(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let labels = [t("settings.proxy.http"), t("settings.proxy.https"), t("settings.proxy.no_proxy")];
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .pt(px(12.0))
+            .border_t_1()
+            .border_color(rgb(colors.border_subtle))
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(2.0))
+                    .child(
+                        div()
+                            .text_xs()
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.proxy.title")),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.proxy.description")),
+                    ),
+            )
+            .children(labels.iter().zip(self.proxy_inputs.iter()).map(|(label, input)| {
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .child(
+                        div()
+                            .w(px(100.0))
+                            .text_sm()
+                            .text_color(rgb(colors.text_primary))
+                            .child(*label),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .h(px(30.0))
+                            .px(px(8.0))
+                            .flex()
+                            .items_center()
+                            .rounded(px(6.0))
+                            .border_1()
+                            .border_color(rgb(colors.border))
+                            .bg(rgb(colors.surface))
+                            .text_sm()
+                            .child(input.clone()),
+                    )
+            }))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_end()
+                    .gap(px(8.0))
+                    .when_some(self.proxy_status.clone(), |el, status| {
+                        el.child(match status {
+                            Ok(()) => div()
+                                .text_xs()
+                                .text_color(rgb(colors.success))
+                                .child(t("settings.proxy.saved")),
+                            Err(e) => div().text_xs().text_color(rgb(colors.error)).child(e),
+                        })
+                    })
+                    .child(
+                        div()
+                            .id("save-proxy-btn")
+                            .px(px(12.0))
+                            .py(px(6.0))
+                            .rounded(px(6.0))
+                            .bg(rgb(colors.primary))
+                            .text_xs()
+                            .text_color(white())
+                            .cursor_pointer()
+                            .hover(|el| el.bg(rgb(colors.primary_hover)))
+                            .on_click(cx.listener(|this, _, cx| {
+                                this.save_proxy_settings(cx);
+                            }))
+                            .child(t("common.save")),
+                    ),
+            )
+    }
+
+    /// Where agents' commands run, and the working directory's container settings
+    fn render_container_section(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let backend = self.acp.manager.terminal_policy().backend;
+        let labels = [t("settings.container.image"), t("settings.container.mounts")];
+        let chip = |id: SharedString, label: &'static str, selected: bool| {
+            div()
+                .id(id)
+                .px(px(8.0))
+                .py(px(2.0))
+                .rounded(px(4.0))
+                .text_xs()
+                .cursor_pointer()
+                .when(selected, |el| {
+                    el.bg(rgba(colors.primary.with_alpha(0.2)))
+                        .text_color(rgb(colors.text_primary))
+                })
+                .when(!selected, |el| {
+                    el.text_color(rgb(colors.text_secondary))
+                        .hover(|el| el.bg(rgb(colors.hover)))
+                })
+                .child(label)
+        };
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .pt(px(12.0))
+            .border_t_1()
+            .border_color(rgb(colors.border_subtle))
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(2.0))
+                    .child(
+                        div()
+                            .text_xs()
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.container.title")),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.container.description")),
+                    ),
+            )
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(4.0))
+                    .child(
+                        div()
+                            .w(px(100.0))
+                            .text_sm()
+                            .text_color(rgb(colors.text_primary))
+                            .child(t("settings.container.run_on")),
+                    )
+                    .children(
+                        [
+                            (TerminalBackend::Host, t("settings.container.host")),
+                            (TerminalBackend::Container, t("settings.container.container")),
+                        ]
+                        .into_iter()
+                        .map(|(option, label)| {
+                            chip(SharedString::from(format!("terminal-backend-{:?}", option)), label, option == backend)
+                                .on_click(cx.listener(move |this, _, cx| this.set_terminal_backend(option, cx)))
+                        }),
+                    ),
+            )
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(4.0))
+                    .child(
+                        div()
+                            .w(px(100.0))
+                            .text_sm()
+                            .text_color(rgb(colors.text_primary))
+                            .child(t("settings.container.runtime")),
+                    )
+                    .children([ContainerRuntime::Docker, ContainerRuntime::Podman].into_iter().map(|runtime| {
+                        chip(
+                            SharedString::from(format!("container-runtime-{}", runtime.program())),
+                            runtime.program(),
+                            runtime == self.container_runtime,
+                        )
+                        .on_click(cx.listener(move |this, _, cx| {
+                            this.container_runtime = runtime;
+                            cx.notify();
+                        }))
+                    }))
+                    .child(div().w(px(12.0)))
+                    .child(
+                        chip("container-network".into(), t("settings.container.network"), self.container_network)
+                            .on_click(cx.listener(|this, _, cx| {
+                                this.container_network = !this.container_network;
+                                cx.notify();
+                            })),
+                    ),
+            )
+            .children(labels.iter().zip(self.container_inputs.iter()).map(|(label, input)| {
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .child(
+                        div()
+                            .w(px(100.0))
+                            .text_sm()
+                            .text_color(rgb(colors.text_primary))
+                            .child(*label),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .h(px(30.0))
+                            .px(px(8.0))
+                            .flex()
+                            .items_center()
+                            .rounded(px(6.0))
+                            .border_1()
+                            .border_color(rgb(colors.border))
+                            .bg(rgb(colors.surface))
+                            .text_sm()
+                            .child(input.clone()),
+                    )
+            }))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_end()
+                    .gap(px(8.0))
+                    .when_some(self.container_status.clone(), |el, status| {
+                        el.child(match status {
+                            Ok(()) => div()
+                                .text_xs()
+                                .text_color(rgb(colors.success))
+                                .child(t("settings.container.saved")),
+                            Err(e) => div().text_xs().text_color(rgb(colors.error)).child(e),
+                        })
+                    })
+                    .child(
+                        div()
+                            .id("save-container-btn")
+                            .px(px(12.0))
+                            .py(px(6.0))
+                            .rounded(px(6.0))
+                            .bg(rgb(colors.primary))
+                            .text_xs()
+                            .text_color(white())
+                            .cursor_pointer()
+                            .hover(|el| el.bg(rgb(colors.primary_hover)))
+                            .on_click(cx.listener(|this, _, cx| {
+                                this.save_container_settings(cx);
+                            }))
+                            .child(t("common.save")),
+                    ),
+            )
+    }
+
+    fn render_limits_section(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let labels = [
+            t("settings.limits.cpu_time"),
+            t("settings.limits.timeout"),
+            t("settings.limits.output"),
+            t("settings.limits.processes"),
+        ];
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .pt(px(12.0))
+            .border_t_1()
+            .border_color(rgb(colors.border_subtle))
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(2.0))
+                    .child(
+                        div()
+                            .text_xs()
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.limits.title")),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.limits.description")),
+                    ),
+            )
+            .children(labels.iter().zip(self.limit_inputs.iter()).map(|(label, input)| {
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .child(
+                        div()
+                            .w(px(160.0))
+                            .text_sm()
+                            .text_color(rgb(colors.text_primary))
+                            .child(*label),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .h(px(30.0))
+                            .px(px(8.0))
+                            .flex()
+                            .items_center()
+                            .rounded(px(6.0))
+                            .border_1()
+                            .border_color(rgb(colors.border))
+                            .bg(rgb(colors.surface))
+                            .text_sm()
+                            .child(input.clone()),
+                    )
+            }))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_end()
+                    .gap(px(8.0))
+                    .when_some(self.limits_status.clone(), |el, status| {
+                        el.child(match status {
+                            Ok(()) => div()
+                                .text_xs()
+                                .text_color(rgb(colors.success))
+                                .child(t("settings.limits.saved")),
+                            Err(e) => div().text_xs().text_color(rgb(colors.error)).child(e),
+                        })
+                    })
+                    .child(
+                        div()
+                            .id("save-limits-btn")
+                            .px(px(12.0))
+                            .py(px(6.0))
+                            .rounded(px(6.0))
+                            .bg(rgb(colors.primary))
+                            .text_xs()
+                            .text_color(white())
+                            .cursor_pointer()
+                            .hover(|el| el.bg(rgb(colors.primary_hover)))
+                            .on_click(cx.listener(|this, _, cx| {
+                                this.save_terminal_limits(cx);
+                            }))
+                            .child(t("common.save")),
+                    ),
+            )
+    }
+
+    fn render_database_section(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let busy = self.acp.manager.is_database_busy();
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .pt(px(12.0))
+            .border_t_1()
+            .border_color(rgb(colors.border_subtle))
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(2.0))
+                    .child(
+                        div()
+                            .text_xs()
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.database.title")),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(self.acp.manager.database_path().display().to_string()),
+                    ),
+            )
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_end()
+                    .gap(px(8.0))
+                    .when(busy, |el| {
+                        el.child(
+                            div()
+                                .text_xs()
+                                .text_color(rgb(colors.text_secondary))
+                                .child(t("common.working")),
+                        )
+                    })
+                    .when_some(self.acp.manager.database_status.clone(), |el, status| {
+                        el.child(match status {
+                            Ok(message) => div().text_xs().text_color(rgb(colors.success)).child(message),
+                            Err(e) => div().text_xs().text_color(rgb(colors.error)).child(e),
+                        })
+                    })
+                    .children(
+                        [
+                            ("check-database-btn", t("settings.database.check"), DatabaseTask::IntegrityCheck),
+                            ("vacuum-database-btn", t("settings.database.vacuum"), DatabaseTask::Vacuum),
+                            ("backup-database-btn", t("settings.database.back_up_now"), DatabaseTask::Backup),
+                        ]
+                        .into_iter()
+                        .map(|(id, label, task)| {
+                            div()
+                                .id(id)
+                                .px(px(12.0))
+                                .py(px(6.0))
+                                .rounded(px(6.0))
+                                .border_1()
+                                .border_color(rgb(colors.border))
+                                .text_xs()
+                                .text_color(rgb(colors.text_primary))
+                                .when(!busy, |el| {
+                                    el.cursor_pointer()
+                                        .hover(|el| el.bg(rgba(colors.hover)))
+                                        .on_click(cx.listener(move |this, _, cx| {
+                                            this.acp.manager.start_database_task(task);
+                                            cx.notify();
+                                        }))
+                                })
+                                .child(label)
+                        }),
+                    ),
+            )
+            .child(self.render_encryption_setting(cx))
+            .child(self.render_backup_settings(cx))
+    }
+
+    fn render_encryption_setting(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let encrypted = self.acp.manager.is_storage_encrypted();
+        let busy = self.acp.manager.is_database_busy();
+
+        div()
+            .flex()
+            .items_center()
+            .gap(px(10.0))
+            .child(
+                div()
+                    .id("toggle-encryption")
+                    .flex_shrink_0()
+                    .w(px(36.0))
+                    .h(px(20.0))
+                    .rounded(px(10.0))
+                    .bg(if encrypted { rgb(colors.primary) } else { rgb(colors.border) })
+                    .flex()
+                    .items_center()
+                    .child(
+                        div()
+                            .w(px(16.0))
+                            .h(px(16.0))
+                            .rounded_full()
+                            .bg(white())
+                            .ml(if encrypted { px(18.0) } else { px(2.0) }),
+                    )
+                    .when(!busy, |el| {
+                        el.cursor_pointer().on_click(cx.listener(move |this, _, cx| {
+                            let task = if encrypted { DatabaseTask::Decrypt } else { DatabaseTask::Encrypt };
+                            this.acp.manager.start_database_task(task);
+                            cx.notify();
+                        }))
+                    }),
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(2.0))
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(rgb(colors.text_primary))
+                            .child(t("settings.database.encrypt")),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.database.encrypt_description")),
+                    ),
+            )
+    }
+
+    fn render_backup_settings(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let policy = self.acp.manager.backup_policy;
+        let chip = |id: String, label: String, selected: bool| {
+            div()
+                .id(SharedString::from(id))
+                .px(px(8.0))
+                .py(px(2.0))
+                .rounded(px(4.0))
+                .text_xs()
+                .text_color(rgb(colors.text_primary))
+                .cursor_pointer()
+                .when(selected, |el| el.bg(rgba(colors.primary.with_alpha(0.15))))
+                .when(!selected, |el| el.hover(|s| s.bg(rgba(colors.hover))))
+                .child(label)
+        };
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(6.0))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(4.0))
+                    .child(div().text_xs().text_color(rgb(colors.text_secondary)).mr(px(4.0)).child(t("settings.database.back_up")))
+                    .children([(t("common.off"), 0), (t("settings.database.daily"), 24), (t("settings.database.weekly"), 24 * 7)].into_iter().map(|(label, hours)| {
+                        chip(format!("backup-interval-{}", hours), label.to_string(), policy.interval_hours == hours)
+                            .on_click(cx.listener(move |this, _, cx| {
+                                let policy = BackupPolicy { interval_hours: hours, ..this.acp.manager.backup_policy };
+                                if let Err(e) = this.acp.manager.set_backup_policy(policy) {
+                                    this.acp.manager.database_status = Some(Err(e));
+                                }
+                                cx.notify();
+                            }))
+                    }))
+                    .child(div().text_xs().text_color(rgb(colors.text_secondary)).ml(px(12.0)).mr(px(4.0)).child(t("common.keep")))
+                    .children([3, 7, 14, 30].into_iter().map(|keep| {
+                        chip(format!("backup-keep-{}", keep), keep.to_string(), policy.keep == keep)
+                            .on_click(cx.listener(move |this, _, cx| {
+                                let policy = BackupPolicy { keep, ..this.acp.manager.backup_policy };
+                                if let Err(e) = this.acp.manager.set_backup_policy(policy) {
+                                    this.acp.manager.database_status = Some(Err(e));
+                                }
+                                cx.notify();
+                            }))
+                    })),
+            )
+            .when(self.acp.manager.backups.is_empty(), |el| {
+                el.child(
+                    div()
+                        .text_xs()
+                        .text_color(rgb(colors.text_secondary))
+                        .child(t("settings.database.no_backups")),
+                )
+            })
+            .children(self.acp.manager.backups.iter().take(5).map(|backup| {
+                let restore = backup.clone();
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .child(
+                        div()
+                            .flex_1()
+                            .text_xs()
+                            .text_color(rgb(colors.text_primary))
+                            .child(backup.created_at.with_timezone(&chrono::Local).format("%b %-d, %Y %H:%M").to_string()),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(format!("{:.1} MB", backup.size as f64 / 1_048_576.0)),
+                    )
+                    .child(
+                        div()
+                            .id(SharedString::from(format!("restore-{}", backup.path.display())))
+                            .text_xs()
+                            .text_color(rgb(colors.primary))
+                            .cursor_pointer()
+                            .on_click(cx.listener(move |this, _, cx| {
+                                this.acp.manager.stage_restore(&restore);
+                                cx.notify();
+                            }))
+                            .child(t("common.restore")),
+                    )
+            }))
+    }
+
+    fn add_webhook(&mut self, cx: &mut ViewContext<Self>) {
+        let [url, secret] = self.webhook_inputs.each_ref().map(|input| input.read(cx).content().to_string());
+        match self.acp.manager.add_webhook(&url, &secret, self.webhook_events.clone()) {
+            Ok(()) => {
+                self.webhook_error = None;
+                for input in &self.webhook_inputs {
+                    input.update(cx, |input, cx| input.clear(cx));
+                }
+            }
+            Err(e) => self.webhook_error = Some(e),
+        }
+        cx.notify();
+    }
+
+    fn add_redaction_pattern(&mut self, cx: &mut ViewContext<Self>) {
+        let pattern = self.redaction_pattern_input.read(cx).content().to_string();
+        match self.acp.manager.add_redaction_pattern(&pattern) {
+            Ok(()) => {
+                self.redaction_pattern_error = None;
+                self.redaction_pattern_input.update(cx, |input, cx| input.clear(cx));
+            }
+            Err(e) => self.redaction_pattern_error = Some(e),
+        }
+        cx.notify();
+    }
+
+    /// The user's own secret patterns, masked along with the built-in ones
+    fn render_redaction_patterns_section(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(colors.text_secondary))
+                    .child(t("settings.redaction.patterns_description")),
+            )
+            .children(self.acp.manager.redaction_patterns.iter().enumerate().map(|(idx, pattern)| {
+                let remove = pattern.clone();
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .px(px(10.0))
+                    .py(px(6.0))
+                    .rounded(px(6.0))
+                    .bg(rgb(colors.surface))
+                    .child(
+                        div()
+                            .flex_1()
+                            .min_w_0()
+                            .text_sm()
+                            .font_family("monospace")
+                            .text_color(rgb(colors.text_primary))
+                            .overflow_hidden()
+                            .whitespace_nowrap()
+                            .text_ellipsis()
+                            .child(pattern.clone()),
+                    )
+                    .child(
+                        div()
+                            .id(SharedString::from(format!("remove-redaction-pattern-{}", idx)))
+                            .text_xs()
+                            .text_color(rgb(colors.error))
+                            .cursor_pointer()
+                            .on_click(cx.listener(move |this, _, cx| {
+                                this.redaction_pattern_error = this.acp.manager.remove_redaction_pattern(&remove).err();
+                                cx.notify();
+                            }))
+                            .child(t("common.remove")),
+                    )
+            }))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(6.0))
+                    .child(
+                        div()
+                            .flex_1()
+                            .h(px(30.0))
+                            .px(px(8.0))
+                            .flex()
+                            .items_center()
+                            .rounded(px(6.0))
+                            .border_1()
+                            .border_color(rgb(colors.border))
+                            .bg(rgb(colors.surface))
+                            .text_sm()
+                            .font_family("monospace")
+                            .child(self.redaction_pattern_input.clone()),
+                    )
+                    .child(
+                        div()
+                            .id("add-redaction-pattern-btn")
+                            .px(px(12.0))
+                            .py(px(6.0))
+                            .rounded(px(6.0))
+                            .bg(rgb(colors.surface))
+                            .text_xs()
+                            .text_color(rgb(colors.text_primary))
+                            .cursor_pointer()
+                            .hover(|el| el.bg(rgb(colors.hover)))
+                            .on_click(cx.listener(|this, _, cx| this.add_redaction_pattern(cx)))
+                            .child(t("common.add")),
+                    ),
+            )
+            .when_some(self.redaction_pattern_error.clone(), |el, error| {
+                el.child(div().text_xs().text_color(rgb(colors.error)).child(error))
+            })
+    }
+
+    fn render_webhooks_section(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let has_url = !self.webhook_inputs[0].read(cx).content().trim().is_empty();
+        let can_add = has_url && !self.webhook_events.is_empty();
+        let event_label = |event: WebhookEvent| match event {
+            WebhookEvent::TurnComplete => t("settings.webhooks.event.turn_complete"),
+            WebhookEvent::PermissionRequest => t("settings.webhooks.event.permission_request"),
+            WebhookEvent::AgentCrash => t("settings.webhooks.event.agent_crash"),
+        };
+        let input_box = |input: &View<TextInput>| {
+            div()
+                .flex_1()
+                .h(px(30.0))
+                .px(px(8.0))
+                .flex()
+                .items_center()
+                .rounded(px(6.0))
+                .border_1()
+                .border_color(rgb(colors.border))
+                .bg(rgb(colors.surface))
+                .text_sm()
+                .child(input.clone())
+        };
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .pt(px(12.0))
+            .border_t_1()
+            .border_color(rgb(colors.border_subtle))
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(2.0))
+                    .child(
+                        div()
+                            .text_xs()
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.webhooks.title")),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.webhooks.description")),
+                    ),
+            )
+            .children(self.acp.manager.webhooks.iter().map(|hook| {
+                let remove_id = hook.id.clone();
+                let events = hook.events.iter().map(|event| event_label(*event)).collect::<Vec<_>>().join(", ");
+
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .px(px(10.0))
+                    .py(px(6.0))
+                    .rounded(px(6.0))
+                    .bg(rgb(colors.surface))
+                    .child(
+                        div()
+                            .flex_1()
+                            .min_w_0()
+                            .flex()
+                            .flex_col()
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .font_family("monospace")
+                                    .text_color(rgb(colors.text_primary))
+                                    .overflow_hidden()
+                                    .whitespace_nowrap()
+                                    .text_ellipsis()
+                                    .child(hook.url.clone()),
+                            )
+                            .child(div().text_xs().text_color(rgb(colors.text_secondary)).child(events)),
+                    )
+                    .when(hook.secret.is_some(), |el| {
+                        el.child(
+                            div()
+                                .px(px(6.0))
+                                .py(px(1.0))
+                                .rounded(px(4.0))
+                                .bg(rgba(colors.success.with_alpha(0.15)))
+                                .text_xs()
+                                .text_color(rgb(colors.success))
+                                .child(t("settings.webhooks.signed")),
+                        )
+                    })
+                    .child(
+                        div()
+                            .id(SharedString::from(format!("remove-webhook-{}", hook.id)))
+                            .text_xs()
+                            .text_color(rgb(colors.error))
+                            .cursor_pointer()
+                            .on_click(cx.listener(move |this, _, cx| {
+                                this.webhook_error = this.acp.manager.remove_webhook(&remove_id).err();
+                                cx.notify();
+                            }))
+                            .child(t("common.remove")),
+                    )
+            }))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(6.0))
+                    .child(input_box(&self.webhook_inputs[0]))
+                    .child(input_box(&self.webhook_inputs[1]).flex_none().w(px(140.0))),
+            )
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(6.0))
+                    .children(WebhookEvent::ALL.into_iter().map(|event| {
+                        let selected = self.webhook_events.contains(&event);
+                        div()
+                            .id(SharedString::from(format!("webhook-event-{}", event.as_str())))
+                            .px(px(8.0))
+                            .py(px(3.0))
+                            .rounded(px(4.0))
+                            .border_1()
+                            .text_xs()
+                            .cursor_pointer()
+                            .when(selected, |el| {
+                                el.border_color(rgb(colors.primary))
+                                    .bg(rgba(colors.primary.with_alpha(0.15)))
+                                    .text_color(rgb(colors.text_primary))
+                            })
+                            .when(!selected, |el| {
+                                el.border_color(rgb(colors.border))
+                                    .text_color(rgb(colors.text_secondary))
+                            })
+                            .on_click(cx.listener(move |this, _, cx| {
+                                if let Some(idx) = this.webhook_events.iter().position(|e| *e == event) {
+                                    this.webhook_events.remove(idx);
+                                } else {
+                                    this.webhook_events.push(event);
+                                }
+                                cx.notify();
+                            }))
+                            .child(event_label(event))
+                    }))
+                    .child(div().flex_1())
+                    .child(
+                        div()
+                            .id("add-webhook-btn")
+                            .px(px(12.0))
+                            .py(px(6.0))
+                            .rounded(px(6.0))
+                            .text_xs()
+                            .when(can_add, |el| {
+                                el.bg(rgb(colors.primary))
+                                    .text_color(white())
+                                    .cursor_pointer()
+                                    .hover(|el| el.bg(rgb(colors.primary_hover)))
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.add_webhook(cx);
+                                    }))
+                            })
+                            .when(!can_add, |el| {
+                                el.bg(rgb(colors.surface))
+                                    .text_color(rgb(colors.text_secondary))
+                            })
+                            .child(t("common.add")),
+                    ),
+            )
+            .when_some(self.webhook_error.clone(), |el, error| {
+                el.child(
+                    div()
+                        .text_xs()
+                        .text_color(rgb(colors.error))
+                        .child(error),
+                )
+            })
+    }
+
+    fn render_env_profiles_section(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let workspace = self.acp.manager.get_working_dir();
+        let has_name = !self.env_profile_name_input.read(cx).content().trim().is_empty();
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .pt(px(12.0))
+            .border_t_1()
+            .border_color(rgb(colors.border_subtle))
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(2.0))
+                    .child(
+                        div()
+                            .text_xs()
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.env.title")),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t_args("settings.env.description", &[("dir", &workspace.display())])),
+                    ),
+            )
+            .children(self.acp.manager.env_profiles.iter().map(|profile| {
+                let default_id = profile.id.clone();
+                let delete_id = profile.id.clone();
+
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .px(px(10.0))
+                    .py(px(6.0))
+                    .rounded(px(6.0))
+                    .bg(rgb(colors.surface))
+                    .child(
+                        div()
+                            .flex_1()
+                            .min_w_0()
+                            .flex()
+                            .flex_col()
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .text_color(rgb(colors.text_primary))
+                                    .child(profile.name.clone()),
+                            )
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .font_family("monospace")
+                                    .text_color(rgb(colors.text_secondary))
+                                    .overflow_hidden()
+                                    .whitespace_nowrap()
+                                    .text_ellipsis()
+                                    .child(profile.variables_text()),
+                            ),
+                    )
+                    .when(profile.is_default, |el| {
+                        el.child(
+                            div()
+                                .px(px(6.0))
+                                .py(px(1.0))
+                                .rounded(px(4.0))
+                                .bg(rgba(colors.success.with_alpha(0.15)))
+                                .text_xs()
+                                .text_color(rgb(colors.success))
+                                .child(t("common.default")),
+                        )
+                    })
+                    .when(!profile.is_default, |el| {
+                        el.child(
+                            div()
+                                .id(SharedString::from(format!("default-env-profile-{}", profile.id)))
+                                .text_xs()
+                                .text_color(rgb(colors.text_secondary))
+                                .cursor_pointer()
+                                .hover(|el| el.text_color(rgb(colors.text_primary)))
+                                .on_click(cx.listener(move |this, _, cx| {
+                                    this.env_profile_error =
+                                        this.acp.manager.set_default_env_profile(&default_id).err();
+                                    cx.notify();
+                                }))
+                                .child(t("installs.make_default")),
+                        )
+                    })
+                    .child(
+                        div()
+                            .id(SharedString::from(format!("delete-env-profile-{}", profile.id)))
+                            .text_xs()
+                            .text_color(rgb(colors.error))
+                            .cursor_pointer()
+                            .on_click(cx.listener(move |this, _, cx| {
+                                this.env_profile_error =
+                                    this.acp.manager.delete_env_profile(&delete_id).err();
+                                cx.notify();
+                            }))
+                            .child(t("common.remove")),
+                    )
+            }))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(6.0))
+                    .child(
+                        div()
+                            .w(px(120.0))
+                            .h(px(30.0))
+                            .px(px(8.0))
+                            .flex()
+                            .items_center()
+                            .rounded(px(6.0))
+                            .border_1()
+                            .border_color(rgb(colors.border))
+                            .bg(rgb(colors.surface))
+                            .text_sm()
+                            .child(self.env_profile_name_input.clone()),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .h(px(30.0))
+                            .px(px(8.0))
+                            .flex()
+                            .items_center()
+                            .rounded(px(6.0))
+                            .border_1()
+                            .border_color(rgb(colors.border))
+                            .bg(rgb(colors.surface))
+                            .text_sm()
+                            .child(self.env_profile_vars_input.clone()),
+                    )
+                    .child(
+                        div()
+                            .id("add-env-profile-btn")
+                            .px(px(12.0))
+                            .py(px(6.0))
+                            .rounded(px(6.0))
+                            .text_xs()
+                            .when(has_name, |el| {
+                                el.bg(rgb(colors.primary))
+                                    .text_color(white())
+                                    .cursor_pointer()
+                                    .hover(|el| el.bg(rgb(colors.primary_hover)))
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.add_env_profile(cx);
+                                    }))
+                            })
+                            .when(!has_name, |el| {
+                                el.bg(rgb(colors.surface))
+                                    .text_color(rgb(colors.text_secondary))
+                            })
+                            .child(t("common.add")),
+                    ),
+            )
+            .when_some(self.env_profile_error.clone(), |el, error| {
+                el.child(
+                    div()
+                        .text_xs()
+                        .text_color(rgb(colors.error))
+                        .child(error),
+                )
+            })
+    }
+
+    fn render_prompt_templates_section(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let has_name = !self.template_name_input.read(cx).content().trim().is_empty();
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .pt(px(12.0))
+            .border_t_1()
+            .border_color(rgb(colors.border_subtle))
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(2.0))
+                    .child(
+                        div()
+                            .text_xs()
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("settings.templates.title")),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(
+                                "Insert from the input bar or type the slash command. \
+                                 {file} is filled with attached files, {selection} with the clipboard.",
+                            ),
+                    ),
+            )
+            .children(self.acp.manager.prompt_templates.iter().map(|template| {
+                let edit_template = template.clone();
+                let delete_id = template.id.clone();
+
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .px(px(10.0))
+                    .py(px(6.0))
+                    .rounded(px(6.0))
+                    .bg(rgb(colors.surface))
+                    .child(
+                        div()
+                            .flex_1()
+                            .min_w_0()
+                            .flex()
+                            .flex_col()
+                            .child(
+                                div()
+                                    .flex()
+                                    .items_center()
+                                    .gap(px(6.0))
+                                    .child(
+                                        div()
+                                            .text_sm()
+                                            .text_color(rgb(colors.text_primary))
+                                            .child(template.name.clone()),
+                                    )
+                                    .child(
+                                        div()
+                                            .text_xs()
+                                            .font_family("monospace")
+                                            .text_color(rgb(colors.text_secondary))
+                                            .child(template.command()),
+                                    ),
+                            )
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .overflow_hidden()
+                                    .whitespace_nowrap()
+                                    .text_ellipsis()
+                                    .child(template.body.clone()),
+                            ),
+                    )
+                    .child(
+                        div()
+                            .id(SharedString::from(format!("edit-template-{}", template.id)))
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .cursor_pointer()
+                            .hover(|el| el.text_color(rgb(colors.text_primary)))
+                            .on_click(cx.listener(move |this, _, cx| {
+                                let name = edit_template.name.clone();
+                                let body = edit_template.body.clone();
+                                this.template_name_input.update(cx, |input, cx| input.set_content(name, cx));
+                                this.template_body_input.update(cx, |input, cx| input.set_content(body, cx));
+                                cx.notify();
+                            }))
+                            .child(t("common.edit")),
+                    )
+                    .child(
+                        div()
+                            .id(SharedString::from(format!("delete-template-{}", template.id)))
+                            .text_xs()
+                            .text_color(rgb(colors.error))
+                            .cursor_pointer()
+                            .on_click(cx.listener(move |this, _, cx| {
+                                this.template_error =
+                                    this.acp.manager.delete_prompt_template(&delete_id).err();
+                                cx.notify();
+                            }))
+                            .child(t("common.remove")),
+                    )
+            }))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(6.0))
+                    .child(
+                        div()
+                            .w(px(120.0))
+                            .h(px(30.0))
+                            .px(px(8.0))
+                            .flex()
+                            .items_center()
+                            .rounded(px(6.0))
+                            .border_1()
+                            .border_color(rgb(colors.border))
+                            .bg(rgb(colors.surface))
+                            .text_sm()
+                            .child(self.template_name_input.clone()),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .h(px(30.0))
+                            .px(px(8.0))
+                            .flex()
+                            .items_center()
+                            .rounded(px(6.0))
+                            .border_1()
+                            .border_color(rgb(colors.border))
+                            .bg(rgb(colors.surface))
+                            .text_sm()
+                            .child(self.template_body_input.clone()),
+                    )
+                    .child(
+                        div()
+                            .id("save-template-btn")
+                            .px(px(12.0))
+                            .py(px(6.0))
+                            .rounded(px(6.0))
+                            .text_xs()
+                            .when(has_name, |el| {
+                                el.bg(rgb(colors.primary))
+                                    .text_color(white())
+                                    .cursor_pointer()
+                                    .hover(|el| el.bg(rgb(colors.primary_hover)))
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.save_prompt_template(cx);
+                                    }))
+                            })
+                            .when(!has_name, |el| {
+                                el.bg(rgb(colors.surface))
+                                    .text_color(rgb(colors.text_secondary))
+                            })
+                            .child(t("common.save")),
+                    ),
+            )
+            .when_some(self.template_error.clone(), |el, error| {
+                el.child(
+                    div()
+                        .text_xs()
+                        .text_color(rgb(colors.error))
+                        .child(error),
+                )
+            })
+    }
+
+    fn render_settings_dialog(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+
+        // Modal overlay
+        div()
+            .absolute()
+            .inset_0()
+            .flex()
+            .items_center()
+            .justify_center()
+            .bg(rgba(colors.panel_bg.with_alpha(0.9)))
+            .on_mouse_down(MouseButton::Left, cx.listener(|this, _, cx| {
+                this.show_settings_dialog = false;
+                cx.notify();
+            }))
+            .child(
+                // Dialog box
+                div()
+                    .w(px(520.0))
+                    .max_h(px(600.0))
+                    .bg(rgb(colors.surface_elevated))
+                    .rounded(px(12.0))
+                    .border_1()
+                    .border_color(rgb(colors.border))
+                    .shadow_lg()
+                    .flex()
+                    .flex_col()
+                    .on_mouse_down(MouseButton::Left, |_, cx| {
+                        cx.stop_propagation();
+                    })
+                    // Header
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(16.0))
+                            .border_b_1()
+                            .border_color(rgb(colors.border))
+                            .child(
+                                div()
+                                    .text_lg()
+                                    .font_weight(FontWeight::SEMIBOLD)
+                                    .text_color(rgb(colors.text_primary))
+                                    .child(t("common.settings")),
+                            ),
+                    )
+                    // API keys
+                    .child(
+                        div()
+                            .id("settings-content")
+                            .flex_1()
+                            .overflow_y_scroll()
+                            .px(px(20.0))
+                            .py(px(16.0))
+                            .flex()
+                            .flex_col()
+                            .gap(px(12.0))
+                            .child(
+                                div()
+                                    .flex()
+                                    .flex_col()
+                                    .gap(px(2.0))
+                                    .child(
+                                        div()
+                                            .text_xs()
+                                            .font_weight(FontWeight::SEMIBOLD)
+                                            .text_color(rgb(colors.text_secondary))
+                                            .child(t("settings.api_keys.title")),
+                                    )
+                                    .child(
+                                        div()
+                                            .text_xs()
+                                            .text_color(rgb(colors.text_secondary))
+                                            .child(t("settings.api_keys.description")),
+                                    ),
+                            )
+                            .children(self.secret_inputs.iter().map(|(key, input)| {
+                                let key = *key;
+                                let stored = self.acp.manager.stored_secret_preview(key).map(|s| s.to_string());
+                                let has_input = !input.read(cx).content().trim().is_empty();
+
+                                div()
+                                    .flex()
+                                    .flex_col()
+                                    .gap(px(6.0))
+                                    .child(
+                                        div()
+                                            .flex()
+                                            .items_center()
+                                            .justify_between()
+                                            .child(
+                                                div()
+                                                    .text_sm()
+                                                    .text_color(rgb(colors.text_primary))
+                                                    .child(key.label()),
+                                            )
+                                            .child(
+                                                div()
+                                                    .text_xs()
+                                                    .font_family("monospace")
+                                                    .text_color(rgb(colors.text_secondary))
+                                                    .child(match &stored {
+                                                        Some(preview) => t_args("settings.api_keys.stored", &[("preview", &preview)]),
+                                                        None => t_args("settings.api_keys.not_stored", &[("var", &key.env_var())]),
+                                                    }),
+                                            ),
+                                    )
+                                    .child(
+                                        div()
+                                            .flex()
+                                            .items_center()
+                                            .gap(px(6.0))
+                                            .child(
+                                                div()
+                                                    .flex_1()
+                                                    .h(px(30.0))
+                                                    .px(px(8.0))
+                                                    .flex()
+                                                    .items_center()
+                                                    .rounded(px(6.0))
+                                                    .border_1()
+                                                    .border_color(rgb(colors.border))
+                                                    .bg(rgb(colors.surface))
+                                                    .text_sm()
+                                                    .child(input.clone()),
+                                            )
+                                            .child(
+                                                div()
+                                                    .id(SharedString::from(format!("save-secret-{}", key.env_var())))
+                                                    .px(px(12.0))
+                                                    .py(px(6.0))
+                                                    .rounded(px(6.0))
+                                                    .text_xs()
+                                                    .when(has_input, |el| {
+                                                        el.bg(rgb(colors.primary))
+                                                            .text_color(white())
+                                                            .cursor_pointer()
+                                                            .hover(|el| el.bg(rgb(colors.primary_hover)))
+                                                            .on_click(cx.listener(move |this, _, cx| {
+                                                                this.save_secret(key, cx);
+                                                            }))
+                                                    })
+                                                    .when(!has_input, |el| {
+                                                        el.bg(rgb(colors.surface))
+                                                            .text_color(rgb(colors.text_secondary))
+                                                    })
+                                                    .child(t("common.save")),
+                                            )
+                                            .when(stored.is_some(), |el| {
+                                                el.child(
+                                                    div()
+                                                        .id(SharedString::from(format!("clear-secret-{}", key.env_var())))
+                                                        .px(px(12.0))
+                                                        .py(px(6.0))
+                                                        .rounded(px(6.0))
+                                                        .text_xs()
+                                                        .text_color(rgb(colors.error))
+                                                        .cursor_pointer()
+                                                        .hover(|el| el.bg(rgb(colors.hover)))
+                                                        .on_click(cx.listener(move |this, _, cx| {
+                                                            this.clear_secret(key, cx);
+                                                        }))
+                                                        .child(t("common.remove")),
+                                                )
+                                            }),
+                                    )
+                            }))
+                            .when_some(self.secret_error.clone(), |el, error| {
+                                el.child(
+                                    div()
+                                        .text_xs()
+                                        .text_color(rgb(colors.error))
+                                        .child(error),
+                                )
+                            })
+                            .child(self.render_env_profiles_section(cx))
+                            .child(self.render_prompt_templates_section(cx))
+                            .child(self.render_tool_policy_section(cx))
+                            .child(self.render_container_section(cx))
+                            .child(self.render_limits_section(cx))
+                            .child(self.render_switch_section(
+                                "timestamps",
+                                t("settings.timestamps.title"),
+                                t("settings.timestamps.description"),
+                                self.acp.manager.show_timestamps,
+                                AcpManager::set_show_timestamps,
+                                cx,
+                            ))
+                            .child(self.render_switch_section(
+                                "follow-ups",
+                                t("settings.follow_ups.title"),
+                                t("settings.follow_ups.description"),
+                                self.acp.manager.follow_ups_enabled,
+                                AcpManager::set_follow_ups_enabled,
+                                cx,
+                            ))
+                            .child(self.render_switch_section(
+                                "vim-mode",
+                                t("settings.vim.title"),
+                                t("settings.vim.description"),
+                                self.acp.manager.vim_mode,
+                                AcpManager::set_vim_mode,
+                                cx,
+                            ))
+                            .child(self.render_switch_section(
+                                "clipboard-capture",
+                                t("settings.clipboard_capture.title"),
+                                t("settings.clipboard_capture.description"),
+                                self.acp.manager.clipboard_capture,
+                                AcpManager::set_clipboard_capture,
+                                cx,
+                            ))
+                            .child(self.render_switch_section(
+                                "show-thoughts",
+                                t("settings.thoughts.title"),
+                                t("settings.thoughts.description"),
+                                self.acp.manager.show_thoughts,
+                                AcpManager::set_show_thoughts,
+                                cx,
+                            ))
+                            .child(self.render_switch_section(
+                                "auto-collapse-thoughts",
+                                t("settings.thoughts.collapse_title"),
+                                t("settings.thoughts.collapse_description"),
+                                self.acp.manager.auto_collapse_thoughts,
+                                AcpManager::set_auto_collapse_thoughts,
+                                cx,
+                            ))
+                            .child(self.render_switch_section(
+                                "protocol-log",
+                                t("settings.protocol_log.title"),
+                                t("settings.protocol_log.description"),
+                                self.acp.manager.protocol_log,
+                                AcpManager::set_protocol_log,
+                                cx,
+                            ))
+                            .child(self.render_switch_section(
+                                "secret-redaction",
+                                t("settings.redaction.title"),
+                                t("settings.redaction.description"),
+                                self.acp.manager.secret_redaction,
+                                AcpManager::set_secret_redaction,
+                                cx,
+                            ))
+                            .child(self.render_redaction_patterns_section(cx))
+                            .child(self.render_switch_section(
+                                "metrics",
+                                t("settings.metrics.title"),
+                                t("settings.metrics.description"),
+                                self.acp.manager.metrics_enabled,
+                                AcpManager::set_metrics_enabled,
+                                cx,
+                            ))
+                            .child(self.render_switch_section(
+                                "share-on-lan",
+                                t("settings.share_lan.title"),
+                                t("settings.share_lan.description"),
+                                self.acp.manager.share_on_lan,
+                                AcpManager::set_share_on_lan,
+                                cx,
+                            ))
+                            .child(self.render_switch_section(
+                                "offline-mode",
+                                t("settings.offline.title"),
+                                t("settings.offline.description"),
+                                cocowork_core::is_offline(),
+                                AcpManager::set_offline,
+                                cx,
+                            ))
+                            .child(self.render_language_section(cx))
+                            .child(self.render_density_section(cx))
+                            .child(self.render_idle_section(cx))
+                            .child(self.render_attachment_limit_section(cx))
+                            .child(self.render_auto_retry_section(cx))
+                            .child(self.render_markdown_cache_section(cx))
+                            .child(self.render_proxy_section(cx))
+                            .child(self.render_webhooks_section(cx))
+                            .child(self.render_database_section(cx)),
+                    )
+                    // Footer
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(12.0))
+                            .border_t_1()
+                            .border_color(rgb(colors.border))
+                            .flex()
+                            .justify_end()
+                            .child(
+                                div()
+                                    .id("close-settings-btn")
+                                    .px(px(16.0))
+                                    .py(px(8.0))
+                                    .rounded(px(6.0))
+                                    .bg(rgb(colors.surface))
+                                    .text_sm()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .cursor_pointer()
+                                    .hover(|el| el.bg(rgb(colors.border)))
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.show_settings_dialog = false;
+                                        cx.notify();
+                                    }))
+                                    .child(t("common.done")),
+                            ),
+                    ),
+            )
+    }
+}
+
+impl CocoWorkWindow {
+    fn render_dashboard_button(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let active = self.show_dashboard;
+
+        div()
+            .id("dashboard-btn")
+            .flex_shrink_0()
+            .mx(px(8.0))
+            .my(px(8.0))
+            .h(px(28.0))
+            .px(px(8.0))
+            .flex()
+            .items_center()
+            .gap(px(8.0))
+            .rounded(px(4.0))
+            .cursor_pointer()
+            .when(active, |el| el.bg(rgba(colors.primary.with_alpha(0.15))))
+            .when(!active, |el| el.hover(|s| s.bg(rgba(colors.hover))))
+            .on_click(cx.listener(|this, _, cx| {
+                this.toggle_dashboard(cx);
+            }))
+            .child(
+                svg_icon(IconName::Chart, IconSize::Small)
+                    .text_color(rgb(colors.text_secondary)),
+            )
+            .child(
+                div()
+                    .text_sm()
+                    .text_color(rgb(colors.text_primary))
+                    .child(t("sidebar.dashboard")),
+            )
+    }
+
+    fn toggle_dashboard(&mut self, cx: &mut ViewContext<Self>) {
+        self.show_dashboard = !self.show_dashboard;
+        if self.show_dashboard {
+            self.show_search = false;
+            self.show_audit = false;
+            self.show_task_board = false;
+            self.acp.manager.load_usage_stats(self.dashboard_days);
+        }
+        cx.notify();
+    }
+
+    fn set_dashboard_days(&mut self, days: i64, cx: &mut ViewContext<Self>) {
+        self.dashboard_days = days;
+        self.acp.manager.load_usage_stats(days);
+        cx.notify();
+    }
+
+    /// Usage totals from storage with simple bar charts
+    fn render_dashboard(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let stats = self.acp.manager.usage_stats.clone().unwrap_or_default();
+        let agents = self.acp.available_agents();
+        let agent_name = |agent_id: &str| {
+            agents
+                .iter()
+                .find(|a| a.id == agent_id)
+                .map(|a| a.name.clone())
+                .unwrap_or_else(|| agent_id.to_string())
+        };
+        let hours = |secs: i64| {
+            if secs >= 3600 {
+                format!("{:.1}h", secs as f64 / 3600.0)
+            } else {
+                format!("{}m", secs / 60)
+            }
+        };
+
+        // One bar per day in range, including days without prompts
+        let today = chrono::Utc::now().date_naive();
+        let days = (0..self.dashboard_days)
+            .rev()
+            .map(|back| today - chrono::Duration::days(back))
+            .map(|day| {
+                let count = stats
+                    .prompts_per_day
+                    .iter()
+                    .find(|(d, _)| *d == day)
+                    .map(|(_, count)| *count)
+                    .unwrap_or(0);
+                (day, count)
+            })
+            .collect::<Vec<_>>();
+        let max_prompts = days.iter().map(|(_, c)| *c).max().unwrap_or(0).max(1);
+        let max_tool_calls = stats.tool_calls_by_kind.first().map(|(_, c)| *c).unwrap_or(0).max(1);
+        let max_agent_prompts = stats.agents.iter().map(|a| a.prompts).max().unwrap_or(0).max(1);
+
+        let tile = |label: &'static str, value: String| {
+            div()
+                .flex_1()
+                .px(px(12.0))
+                .py(px(10.0))
+                .rounded(px(8.0))
+                .bg(rgb(colors.surface))
+                .flex()
+                .flex_col()
+                .gap(px(2.0))
+                .child(
+                    div()
+                        .text_xs()
+                        .text_color(rgb(colors.text_secondary))
+                        .child(label),
+                )
+                .child(
+                    div()
+                        .text_lg()
+                        .font_weight(FontWeight::SEMIBOLD)
+                        .text_color(rgb(colors.text_primary))
+                        .child(value),
+                )
+        };
+        let section_title = |title: &'static str| {
+            div()
+                .text_xs()
+                .font_weight(FontWeight::SEMIBOLD)
+                .text_color(rgb(colors.text_secondary))
+                .child(title)
+        };
+        let bar_row = |label: String, value: String, fraction: f32| {
+            div()
+                .flex()
+                .items_center()
+                .gap(px(8.0))
+                .child(
+                    div()
+                        .w(px(120.0))
+                        .flex_shrink_0()
+                        .text_xs()
+                        .text_color(rgb(colors.text_primary))
+                        .overflow_hidden()
+                        .whitespace_nowrap()
+                        .text_ellipsis()
+                        .child(label),
+                )
+                .child(
+                    div()
+                        .flex_1()
+                        .h(px(10.0))
+                        .rounded(px(3.0))
+                        .bg(rgb(colors.surface))
+                        .child(
+                            div()
+                                .h_full()
+                                .rounded(px(3.0))
+                                .bg(rgb(colors.primary))
+                                .w(relative(fraction)),
+                        ),
+                )
+                .child(
+                    div()
+                        .w(px(96.0))
+                        .flex_shrink_0()
+                        .text_xs()
+                        .text_color(rgb(colors.text_secondary))
+                        .child(value),
+                )
+        };
+
+        div()
+            .flex_1()
+            .min_h_0()
+            .flex()
+            .flex_col()
+            // Header with the date range
+            .child(
+                div()
+                    .w_full()
+                    .h(px(40.0))
+                    .flex_shrink_0()
+                    .px(px(16.0))
+                    .flex()
+                    .items_center()
+                    .gap(px(4.0))
+                    .border_b_1()
+                    .border_color(rgb(colors.border))
+                    .child(
+                        div()
+                            .flex_1()
+                            .text_sm()
+                            .font_weight(FontWeight::MEDIUM)
+                            .text_color(rgb(colors.text_primary))
+                            .child(t("common.usage")),
+                    )
+                    .children([7, 30, 90].map(|days| {
+                        let selected = self.dashboard_days == days;
+                        div()
+                            .id(SharedString::from(format!("dashboard-days-{}", days)))
+                            .px(px(8.0))
+                            .py(px(2.0))
+                            .rounded(px(4.0))
+                            .text_xs()
+                            .cursor_pointer()
+                            .when(selected, |el| {
+                                el.bg(rgba(colors.primary.with_alpha(0.2)))
+                                    .text_color(rgb(colors.text_primary))
+                            })
+                            .when(!selected, |el| {
+                                el.text_color(rgb(colors.text_secondary))
+                                    .hover(|el| el.bg(rgba(colors.hover)))
+                            })
+                            .on_click(cx.listener(move |this, _, cx| {
+                                this.set_dashboard_days(days, cx);
+                            }))
+                            .child(t_args("dashboard.days", &[("days", &days)]))
+                    })),
+            )
+            .child(
+                div()
+                    .id("dashboard-content")
+                    .flex_1()
+                    .min_h_0()
+                    .overflow_y_scroll()
+                    .px(px(24.0))
+                    .py(px(16.0))
+                    .flex()
+                    .flex_col()
+                    .gap(px(20.0))
+                    // Totals
+                    .child(
+                        div()
+                            .flex()
+                            .gap(px(12.0))
+                            .child(tile(t("dashboard.prompts"), stats.total_prompts().to_string()))
+                            .child(tile(t("dashboard.tool_calls"), stats.total_tool_calls().to_string()))
+                            .child(tile(t("dashboard.files_modified"), stats.files_modified.to_string()))
+                            .child(tile(t("dashboard.session_time"), hours(stats.total_session_secs))),
+                    )
+                    // Prompts per day
+                    .child(
+                        div()
+                            .flex()
+                            .flex_col()
+                            .gap(px(8.0))
+                            .child(section_title(t("dashboard.prompts_per_day")))
+                            .child(
+                                div()
+                                    .h(px(120.0))
+                                    .flex()
+                                    .items_end()
+                                    .gap(px(2.0))
+                                    .children(days.iter().map(|(_, count)| {
+                                        div()
+                                            .flex_1()
+                                            .h(relative(*count as f32 / max_prompts as f32))
+                                            .min_h(px(1.0))
+                                            .rounded(px(2.0))
+                                            .bg(if *count > 0 {
+                                                rgb(colors.primary)
+                                            } else {
+                                                rgb(colors.border)
+                                            })
+                                    })),
+                            )
+                            .child(
+                                div()
+                                    .flex()
+                                    .justify_between()
+                                    .text_xs()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .children(
+                                        [days.first(), days.last()]
+                                            .into_iter()
+                                            .flatten()
+                                            .map(|(day, _)| day.format("%b %-d").to_string()),
+                                    ),
+                            ),
+                    )
+                    // Tool calls by kind
+                    .child(
+                        div()
+                            .flex()
+                            .flex_col()
+                            .gap(px(6.0))
+                            .child(section_title(t("dashboard.tool_calls_by_kind")))
+                            .when(stats.tool_calls_by_kind.is_empty(), |el| {
+                                el.child(
+                                    div()
+                                        .text_xs()
+                                        .text_color(rgb(colors.text_secondary))
+                                        .child(t("context.no_tool_calls")),
+                                )
+                            })
+                            .children(stats.tool_calls_by_kind.iter().map(|(kind, count)| {
+                                bar_row(
+                                    kind.clone(),
+                                    count.to_string(),
+                                    *count as f32 / max_tool_calls as f32,
+                                )
+                            })),
+                    )
+                    // Per-agent usage
+                    .child(
+                        div()
+                            .flex()
+                            .flex_col()
+                            .gap(px(6.0))
+                            .child(section_title(t("dashboard.agents")))
+                            .when(stats.agents.is_empty(), |el| {
+                                el.child(
+                                    div()
+                                        .text_xs()
+                                        .text_color(rgb(colors.text_secondary))
+                                        .child(t("sidebar.no_threads")),
+                                )
+                            })
+                            .children(stats.agents.iter().map(|agent| {
+                                bar_row(
+                                    agent_name(&agent.agent_id),
+                                    format!(
+                                        "{} prompts · {}",
+                                        agent.prompts,
+                                        hours(agent.session_secs)
+                                    ),
+                                    agent.prompts as f32 / max_agent_prompts as f32,
+                                )
+                            })),
+                    )
+                    .child(self.render_metrics_section(cx)),
+            )
+    }
+
+    /// Recorded metrics with reply-time histograms, or a note that they're off
+    fn render_metrics_section(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let manager = &self.acp.manager;
+        let summary = manager.metrics_summary.clone().unwrap_or_default();
+        let duration = |ms: f64| {
+            if ms >= 1000.0 {
+                format!("{:.1}s", ms / 1000.0)
+            } else {
+                format!("{:.0}ms", ms)
+            }
+        };
+        let sessions = summary.counter(metrics::SESSION_STARTED);
+        let errors = summary.counter(metrics::TURN_ERROR) + summary.counter(metrics::CONNECT_ERROR);
+        let histograms = [
+            (t("dashboard.first_reply"), metrics::FIRST_CHUNK_MS),
+            (t("dashboard.turn_time"), metrics::TURN_MS),
+        ];
+        let bucket_labels = [
+            format!("≤{}", duration(metrics::LATENCY_BUCKETS_MS[0])),
+            format!(">{}", duration(metrics::LATENCY_BUCKETS_MS[metrics::LATENCY_BUCKETS_MS.len() - 1])),
+        ];
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(
+                        div()
+                            .text_xs()
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("dashboard.metrics")),
+                    )
+                    .when(!summary.is_empty(), |el| {
+                        el.child(
+                            div()
+                                .id("clear-metrics")
+                                .px(px(8.0))
+                                .py(px(2.0))
+                                .rounded(px(4.0))
+                                .text_xs()
+                                .text_color(rgb(colors.text_secondary))
+                                .cursor_pointer()
+                                .hover(|el| el.bg(rgba(colors.hover)))
+                                .on_click(cx.listener(|this, _, cx| {
+                                    if let Err(e) = this.acp.manager.clear_metrics() {
+                                        tracing::warn!("Failed to clear metrics: {}", e);
+                                    }
+                                    cx.notify();
+                                }))
+                                .child(t("dashboard.clear_metrics")),
+                        )
+                    }),
+            )
+            .when(summary.is_empty(), |el| {
+                el.child(
+                    div()
+                        .text_xs()
+                        .text_color(rgb(colors.text_secondary))
+                        .child(if manager.metrics_enabled {
+                            t("dashboard.metrics_empty")
+                        } else {
+                            t("dashboard.metrics_off")
+                        }),
+                )
+            })
+            .when(!summary.is_empty(), |el| {
+                el.child(
+                    div()
+                        .text_xs()
+                        .text_color(rgb(colors.text_primary))
+                        .child(t_args("dashboard.metrics_counts", &[("sessions", &sessions), ("errors", &errors)])),
+                )
+            })
+            .children(histograms.into_iter().filter_map(|(label, name)| {
+                let histogram = summary.histogram(name)?;
+                let max = histogram.buckets.iter().copied().max().unwrap_or(0).max(1);
+                Some(
+                    div()
+                        .flex()
+                        .flex_col()
+                        .gap(px(4.0))
+                        .child(
+                            div()
+                                .text_xs()
+                                .text_color(rgb(colors.text_primary))
+                                .child(t_args(
+                                    "dashboard.latency",
+                                    &[
+                                        ("label", &label),
+                                        ("p50", &duration(histogram.p50_ms)),
+                                        ("p95", &duration(histogram.p95_ms)),
+                                    ],
+                                )),
+                        )
+                        .child(
+                            div()
+                                .h(px(48.0))
+                                .flex()
+                                .items_end()
+                                .gap(px(2.0))
+                                .children(histogram.buckets.iter().map(|count| {
+                                    div()
+                                        .flex_1()
+                                        .h(relative(*count as f32 / max as f32))
+                                        .min_h(px(1.0))
+                                        .rounded(px(2.0))
+                                        .bg(if *count > 0 {
+                                            rgb(colors.primary)
+                                        } else {
+                                            rgb(colors.border)
+                                        })
+                                })),
+                        )
+                        .child(
+                            div()
+                                .flex()
+                                .justify_between()
+                                .text_xs()
+                                .text_color(rgb(colors.text_secondary))
+                                .children(bucket_labels.clone()),
+                        ),
+                )
+            }))
+    }
+
+    fn open_batch_dialog(&mut self, cx: &mut ViewContext<Self>) {
+        self.batch_error = None;
+        if self.batch_workspaces.is_empty() {
+            self.batch_workspaces.push(self.acp.manager.get_working_dir());
+        }
+        self.show_batch_dialog = true;
+        cx.notify();
+    }
+
+    fn add_batch_workspaces(&mut self, cx: &mut ViewContext<Self>) {
+        cx.spawn(|view, mut cx| async move {
+            let folders = rfd::AsyncFileDialog::new()
+                .set_title(t("dialog.add_batch_workspaces"))
+                .pick_folders()
+                .await;
+
+            if let Some(folders) = folders {
+                let _ = view.update(&mut cx, |this, cx| {
+                    for folder in folders {
+                        let path = folder.path().to_path_buf();
+                        if !this.batch_workspaces.contains(&path) {
+                            this.batch_workspaces.push(path);
+                        }
+                    }
+                    cx.notify();
+                });
+            }
+        })
+        .detach();
+    }
+
+    fn start_batch(&mut self, cx: &mut ViewContext<Self>) {
+        let prompt = self.batch_prompt_input.read(cx).content().to_string();
+        match self
+            .acp
+            .manager
+            .start_batch(&prompt, self.batch_workspaces.clone(), self.batch_concurrency)
+        {
+            Ok(()) => {
+                self.batch_error = None;
+                self.batch_prompt_input.update(cx, |input, cx| input.clear(cx));
+            }
+            Err(e) => self.batch_error = Some(e),
+        }
+        cx.notify();
+    }
+
+    /// What the connected agent said it supports, and the modes and models of the open thread
+    fn render_capabilities_dialog(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let handshake = self.acp.manager.handshake.as_ref().filter(|_| self.acp.manager.is_connected());
+        let session = self.acp.active_session();
+
+        let section = |title: &'static str| {
+            div()
+                .pt(px(8.0))
+                .text_xs()
+                .font_weight(FontWeight::SEMIBOLD)
+                .text_color(rgb(colors.text_secondary))
+                .child(title)
+        };
+        let row = |label: String, value: String| {
+            div()
+                .flex()
+                .items_center()
+                .gap(px(12.0))
+                .text_sm()
+                .child(div().w(px(160.0)).flex_shrink_0().text_color(rgb(colors.text_secondary)).child(label))
+                .child(div().flex_1().min_w_0().text_color(rgb(colors.text_primary)).child(value))
+        };
+        let mark = |supported: bool| if supported { "✓".to_string() } else { "—".to_string() };
+
+        let body = match handshake {
+            None => div()
+                .text_sm()
+                .text_color(rgb(colors.text_secondary))
+                .child(t("capabilities.not_connected")),
+            Some(handshake) => {
+                let caps = &handshake.capabilities;
+                let agent = handshake
+                    .agent_info
+                    .as_ref()
+                    .map(|info| format!("{} {}", info.name, info.version))
+                    .unwrap_or_else(|| t("capabilities.not_reported").to_string());
+                let auth = handshake.auth_methods.iter().map(|method| method.name.as_str()).collect::<Vec<_>>();
+                let modes = session
+                    .map(|session| {
+                        session
+                            .available_modes
+                            .iter()
+                            .map(|mode| {
+                                let current = session.current_mode.as_ref() == Some(&mode.id);
+                                format!("{}{}", mode.id.as_str(), if current { " •" } else { "" })
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+                let models = session
+                    .map(|session| {
+                        session
+                            .available_models
+                            .iter()
+                            .map(|model| {
+                                let current = session.current_model.as_ref() == Some(&model.id);
+                                format!("{}{}", model.id.as_str(), if current { " •" } else { "" })
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+                let list = |items: Vec<String>| {
+                    if items.is_empty() {
+                        t("capabilities.none").to_string()
+                    } else {
+                        items.join(", ")
+                    }
+                };
+
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(6.0))
+                    .child(row(t("capabilities.agent").to_string(), agent))
+                    .child(row(t("capabilities.protocol").to_string(), handshake.protocol_version.to_string()))
+                    .child(row(
+                        t("capabilities.auth").to_string(),
+                        list(auth.into_iter().map(str::to_string).collect()),
+                    ))
+                    .child(section(t("capabilities.features")))
+                    .children(
+                        [
+                            ("loadSession", caps.load_session),
+                            ("embeddedContext", caps.embedded_context),
+                            ("mcp", caps.supports_mcp),
+                            ("modes", caps.supports_modes),
+                            ("plans", caps.supports_plans),
+                            ("thoughts", caps.supports_thoughts),
+                        ]
+                        .into_iter()
+                        .map(|(name, supported)| row(name.to_string(), mark(supported))),
+                    )
+                    .child(section(t("capabilities.session")))
+                    .child(row(t("capabilities.modes").to_string(), list(modes)))
+                    .child(row(t("capabilities.models").to_string(), list(models)))
+            }
+        };
+        let json = handshake.and_then(|handshake| serde_json::to_string_pretty(handshake).ok());
+
+        // Modal overlay
+        div()
+            .absolute()
+            .inset_0()
+            .flex()
+            .items_center()
+            .justify_center()
+            .bg(rgba(colors.panel_bg.with_alpha(0.9)))
+            .on_mouse_down(MouseButton::Left, cx.listener(|this, _, cx| {
+                this.show_capabilities = false;
+                cx.notify();
+            }))
+            .child(
+                // Dialog box
+                div()
+                    .w(px(520.0))
+                    .bg(rgb(colors.surface_elevated))
+                    .rounded(px(12.0))
+                    .border_1()
+                    .border_color(rgb(colors.border))
+                    .shadow_lg()
+                    .flex()
+                    .flex_col()
+                    .on_mouse_down(MouseButton::Left, |_, cx| {
+                        cx.stop_propagation();
+                    })
+                    // Header
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(16.0))
+                            .border_b_1()
+                            .border_color(rgb(colors.border))
+                            .flex()
+                            .flex_col()
+                            .gap(px(4.0))
+                            .child(
+                                div()
+                                    .text_lg()
+                                    .font_weight(FontWeight::SEMIBOLD)
+                                    .text_color(rgb(colors.text_primary))
+                                    .child(t("capabilities.title")),
+                            )
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .child(self.acp.selected_agent_name()),
+                            ),
+                    )
+                    .child(div().px(px(20.0)).py(px(16.0)).child(body))
+                    // Footer
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(12.0))
+                            .border_t_1()
+                            .border_color(rgb(colors.border))
+                            .flex()
+                            .justify_end()
+                            .gap(px(8.0))
+                            .when_some(json, |el, json| {
+                                el.child(
+                                    div()
+                                        .id("copy-capabilities-btn")
+                                        .px(px(16.0))
+                                        .py(px(8.0))
+                                        .rounded(px(6.0))
+                                        .bg(rgb(colors.surface))
+                                        .text_sm()
+                                        .text_color(rgb(colors.text_secondary))
+                                        .cursor_pointer()
+                                        .hover(|el| el.bg(rgb(colors.border)))
+                                        .on_click(cx.listener(move |_, _, cx| {
+                                            cx.write_to_clipboard(ClipboardItem::new_string(json.clone()));
+                                        }))
+                                        .child(t("capabilities.copy_json")),
+                                )
+                            })
+                            .child(
+                                div()
+                                    .id("close-capabilities-btn")
+                                    .px(px(16.0))
+                                    .py(px(8.0))
+                                    .rounded(px(6.0))
+                                    .bg(rgb(colors.surface))
+                                    .text_sm()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .cursor_pointer()
+                                    .hover(|el| el.bg(rgb(colors.border)))
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.show_capabilities = false;
+                                        cx.notify();
+                                    }))
+                                    .child(t("common.close")),
+                            ),
+                    ),
+            )
+    }
+
+    fn render_batch_dialog(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let report = self.acp.manager.batch_report.as_ref();
+        let running = report.is_some_and(|r| r.is_running());
+
+        // Modal overlay
+        div()
+            .absolute()
+            .inset_0()
+            .flex()
+            .items_center()
+            .justify_center()
+            .bg(rgba(colors.panel_bg.with_alpha(0.9)))
+            .on_mouse_down(MouseButton::Left, cx.listener(|this, _, cx| {
+                this.show_batch_dialog = false;
+                cx.notify();
+            }))
+            .child(
+                // Dialog box
+                div()
+                    .w(px(600.0))
+                    .max_h(px(640.0))
+                    .bg(rgb(colors.surface_elevated))
+                    .rounded(px(12.0))
+                    .border_1()
+                    .border_color(rgb(colors.border))
+                    .shadow_lg()
+                    .flex()
+                    .flex_col()
+                    .on_mouse_down(MouseButton::Left, |_, cx| {
+                        cx.stop_propagation();
+                    })
+                    // Header
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(16.0))
+                            .border_b_1()
+                            .border_color(rgb(colors.border))
+                            .flex()
+                            .flex_col()
+                            .gap(px(4.0))
+                            .child(
+                                div()
+                                    .text_lg()
+                                    .font_weight(FontWeight::SEMIBOLD)
+                                    .text_color(rgb(colors.text_primary))
+                                    .child(t("batch.title")),
+                            )
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .child(match report {
+                                        Some(report) => format!("{}: {}", report.agent_id, report.summary()),
+                                        None => t_args("batch.description", &[("agent", &self.acp.selected_agent_name())]),
+                                    }),
+                            ),
+                    )
+                    .child(
+                        div()
+                            .id("batch-content")
+                            .flex_1()
+                            .overflow_y_scroll()
+                            .px(px(20.0))
+                            .py(px(16.0))
+                            .flex()
+                            .flex_col()
+                            .gap(px(12.0))
+                            .map(|el| match report {
+                                Some(report) => el.child(self.render_batch_report(report)),
+                                None => el.child(self.render_batch_form(cx)),
+                            }),
+                    )
+                    // Footer
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(12.0))
+                            .border_t_1()
+                            .border_color(rgb(colors.border))
+                            .flex()
+                            .justify_end()
+                            .gap(px(8.0))
+                            .when(report.is_some() && !running, |el| {
+                                el.child(
+                                    div()
+                                        .id("new-batch-btn")
+                                        .px(px(16.0))
+                                        .py(px(8.0))
+                                        .rounded(px(6.0))
+                                        .bg(rgb(colors.surface))
+                                        .text_sm()
+                                        .text_color(rgb(colors.text_secondary))
+                                        .cursor_pointer()
+                                        .hover(|el| el.bg(rgb(colors.border)))
+                                        .on_click(cx.listener(|this, _, cx| {
+                                            this.acp.manager.clear_batch_report();
+                                            cx.notify();
+                                        }))
+                                        .child(t("batch.new")),
+                                )
+                            })
+                            .when(report.is_none(), |el| {
+                                el.child(
+                                    div()
+                                        .id("start-batch-btn")
+                                        .px(px(16.0))
+                                        .py(px(8.0))
+                                        .rounded(px(6.0))
+                                        .bg(rgb(colors.primary))
+                                        .text_sm()
+                                        .text_color(white())
+                                        .cursor_pointer()
+                                        .hover(|el| el.bg(rgb(colors.primary_hover)))
+                                        .on_click(cx.listener(|this, _, cx| {
+                                            this.start_batch(cx);
+                                        }))
+                                        .child(t("common.run")),
+                                )
+                            })
+                            .child(
+                                div()
+                                    .id("close-batch-btn")
+                                    .px(px(16.0))
+                                    .py(px(8.0))
+                                    .rounded(px(6.0))
+                                    .bg(rgb(colors.surface))
+                                    .text_sm()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .cursor_pointer()
+                                    .hover(|el| el.bg(rgb(colors.border)))
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.show_batch_dialog = false;
+                                        cx.notify();
+                                    }))
+                                    .child(if running { t("common.hide") } else { t("common.close") }),
+                            ),
+                    ),
+            )
+    }
+
+    fn render_batch_form(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(12.0))
+            .child(
+                div()
+                    .w_full()
+                    .min_h(px(60.0))
+                    .p(px(8.0))
+                    .rounded(px(6.0))
+                    .border_1()
+                    .border_color(rgb(colors.border))
+                    .bg(rgb(colors.surface))
+                    .text_sm()
+                    .child(self.batch_prompt_input.clone()),
+            )
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(
+                        div()
+                            .text_xs()
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t_args("batch.workspaces", &[("count", &self.batch_workspaces.len())])),
+                    )
+                    .child(
+                        div()
+                            .id("add-batch-workspaces-btn")
+                            .text_xs()
+                            .text_color(rgb(colors.primary))
+                            .cursor_pointer()
+                            .on_click(cx.listener(|this, _, cx| {
+                                this.add_batch_workspaces(cx);
+                            }))
+                            .child(t("batch.add_folders")),
+                    ),
+            )
+            .children(self.batch_workspaces.iter().enumerate().map(|(idx, workspace)| {
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .px(px(10.0))
+                    .py(px(6.0))
+                    .rounded(px(6.0))
+                    .bg(rgb(colors.surface))
+                    .child(
+                        svg_icon(IconName::Folder, IconSize::Small)
+                            .text_color(rgb(colors.text_secondary)),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .min_w_0()
+                            .text_sm()
+                            .text_color(rgb(colors.text_primary))
+                            .overflow_hidden()
+                            .whitespace_nowrap()
+                            .text_ellipsis()
+                            .child(workspace.display().to_string()),
+                    )
+                    .child(
+                        div()
+                            .id(SharedString::from(format!("remove-batch-workspace-{}", idx)))
+                            .text_xs()
+                            .text_color(rgb(colors.error))
+                            .cursor_pointer()
+                            .on_click(cx.listener(move |this, _, cx| {
+                                this.batch_workspaces.remove(idx);
+                                cx.notify();
+                            }))
+                            .child(t("common.remove")),
+                    )
+            }))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(6.0))
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t("batch.concurrency")),
+                    )
+                    .children([1, 2, 4].map(|n| {
+                        let selected = self.batch_concurrency == n;
+                        div()
+                            .id(SharedString::from(format!("batch-concurrency-{}", n)))
+                            .px(px(8.0))
+                            .py(px(2.0))
+                            .rounded(px(4.0))
+                            .text_xs()
+                            .cursor_pointer()
+                            .when(selected, |el| {
+                                el.bg(rgba(colors.primary.with_alpha(0.2)))
+                                    .text_color(rgb(colors.text_primary))
+                            })
+                            .when(!selected, |el| {
+                                el.text_color(rgb(colors.text_secondary))
+                                    .hover(|el| el.bg(rgba(colors.hover)))
+                            })
+                            .on_click(cx.listener(move |this, _, cx| {
+                                this.batch_concurrency = n;
+                                cx.notify();
+                            }))
+                            .child(if n == 1 { t("batch.in_order").to_string() } else { n.to_string() })
+                    })),
+            )
+            .when_some(self.batch_error.clone(), |el, error| {
+                el.child(
+                    div()
+                        .text_xs()
+                        .text_color(rgb(colors.error))
+                        .child(error),
+                )
+            })
+    }
+
+    fn render_batch_report(&self, report: &cocowork_core::BatchReport) -> impl IntoElement {
+        let colors = &self.theme.colors;
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(colors.text_secondary))
+                    .overflow_hidden()
+                    .whitespace_nowrap()
+                    .text_ellipsis()
+                    .child(t_args("common.prompt", &[("prompt", &report.prompt)])),
+            )
+            .children(report.items.iter().map(|item| {
+                let (status, status_color) = match &item.state {
+                    BatchItemState::Queued => (t("common.queued").to_string(), colors.text_secondary),
+                    BatchItemState::Running => (t("common.running").to_string(), colors.primary),
+                    BatchItemState::Finished { stop_reason } => (
+                        format!("{:?}", stop_reason),
+                        if item.succeeded() { colors.success } else { colors.warning },
+                    ),
+                    BatchItemState::Failed { .. } => (t("common.failed").to_string(), colors.error),
+                };
+                let details = match &item.state {
+                    BatchItemState::Failed { error } => error.clone(),
+                    _ => item.response.trim().lines().last().unwrap_or_default().to_string(),
+                };
+                let stats = item
+                    .duration()
+                    .map(|d| format!("{}s · {} tool calls", d.num_seconds(), item.tool_calls))
+                    .unwrap_or_else(|| t_args("common.tool_calls", &[("count", &item.tool_calls)]));
+
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(4.0))
+                    .px(px(10.0))
+                    .py(px(8.0))
+                    .rounded(px(6.0))
+                    .bg(rgb(colors.surface))
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .gap(px(8.0))
+                            .child(
+                                div()
+                                    .flex_1()
+                                    .min_w_0()
+                                    .text_sm()
+                                    .text_color(rgb(colors.text_primary))
+                                    .overflow_hidden()
+                                    .whitespace_nowrap()
+                                    .text_ellipsis()
+                                    .child(item.workspace.display().to_string()),
+                            )
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .child(stats),
+                            )
+                            .child(
+                                div()
+                                    .px(px(6.0))
+                                    .py(px(1.0))
+                                    .rounded(px(4.0))
+                                    .bg(rgba(status_color.with_alpha(0.15)))
+                                    .text_xs()
+                                    .text_color(rgb(status_color))
+                                    .child(status),
+                            ),
+                    )
+                    .when(!details.is_empty(), |el| {
+                        el.child(
+                            div()
+                                .text_xs()
+                                .text_color(rgb(colors.text_secondary))
+                                .overflow_hidden()
+                                .whitespace_nowrap()
+                                .text_ellipsis()
+                                .child(details),
+                        )
+                    })
+            }))
+    }
+
+    fn open_compare_dialog(&mut self, cx: &mut ViewContext<Self>) {
+        self.compare_error = None;
+        if self.compare_agents[0].is_none() {
+            self.compare_agents[0] = self.acp.manager.selected_agent_id.clone();
+        }
+        // Start from whatever is in the message box
+        let draft = self.message_input.read(cx).content().to_string();
+        if !draft.trim().is_empty() {
+            self.compare_prompt_input
+                .update(cx, |input, cx| input.set_content(draft, cx));
+        }
+        self.show_compare_dialog = true;
+        cx.notify();
+    }
+
+    fn start_comparison(&mut self, cx: &mut ViewContext<Self>) {
+        let prompt = self.compare_prompt_input.read(cx).content().to_string();
+        let agent_ids = self.compare_agents.iter().flatten().cloned().collect();
+        match self.acp.manager.start_comparison(&prompt, agent_ids) {
+            Ok(()) => {
+                self.compare_error = None;
+                self.show_compare_dialog = false;
+                self.compare_prompt_input.update(cx, |input, cx| input.clear(cx));
+            }
+            Err(e) => self.compare_error = Some(e),
+        }
+        cx.notify();
+    }
+
+    fn render_compare_dialog(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let agents = self.acp.available_agents();
+
+        // Modal overlay
+        div()
+            .absolute()
+            .inset_0()
+            .flex()
+            .items_center()
+            .justify_center()
+            .bg(rgba(colors.panel_bg.with_alpha(0.9)))
+            .on_mouse_down(MouseButton::Left, cx.listener(|this, _, cx| {
+                this.show_compare_dialog = false;
+                cx.notify();
+            }))
+            .child(
+                // Dialog box
+                div()
+                    .w(px(520.0))
+                    .bg(rgb(colors.surface_elevated))
+                    .rounded(px(12.0))
+                    .border_1()
+                    .border_color(rgb(colors.border))
+                    .shadow_lg()
+                    .flex()
+                    .flex_col()
+                    .on_mouse_down(MouseButton::Left, |_, cx| {
+                        cx.stop_propagation();
+                    })
+                    // Header
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(16.0))
+                            .border_b_1()
+                            .border_color(rgb(colors.border))
+                            .flex()
+                            .flex_col()
+                            .gap(px(4.0))
+                            .child(
+                                div()
+                                    .text_lg()
+                                    .font_weight(FontWeight::SEMIBOLD)
+                                    .text_color(rgb(colors.text_primary))
+                                    .child(t("compare.title")),
+                            )
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .child(t("compare.description")),
+                            ),
+                    )
+                    .child(
+                        div()
+                            .id("compare-content")
+                            .px(px(20.0))
+                            .py(px(16.0))
+                            .flex()
+                            .flex_col()
+                            .gap(px(12.0))
+                            .child(
+                                div()
+                                    .w_full()
+                                    .min_h(px(60.0))
+                                    .p(px(8.0))
+                                    .rounded(px(6.0))
+                                    .border_1()
+                                    .border_color(rgb(colors.border))
+                                    .bg(rgb(colors.surface))
+                                    .text_sm()
+                                    .child(self.compare_prompt_input.clone()),
+                            )
+                            .children(["A", "B"].into_iter().enumerate().map(|(side, label)| {
+                                div()
+                                    .flex()
+                                    .flex_wrap()
+                                    .items_center()
+                                    .gap(px(6.0))
+                                    .child(
+                                        div()
+                                            .w(px(56.0))
+                                            .text_xs()
+                                            .font_weight(FontWeight::SEMIBOLD)
+                                            .text_color(rgb(colors.text_secondary))
+                                            .child(t_args("compare.agent", &[("label", &label)])),
+                                    )
+                                    .children(agents.iter().map(|agent| {
+                                        let selected =
+                                            self.compare_agents[side].as_deref() == Some(agent.id.as_str());
+                                        let agent_id = agent.id.clone();
+                                        div()
+                                            .id(SharedString::from(format!("compare-agent-{}-{}", side, agent.id)))
+                                            .px(px(8.0))
+                                            .py(px(2.0))
+                                            .rounded(px(4.0))
+                                            .text_xs()
+                                            .cursor_pointer()
+                                            .when(selected, |el| {
+                                                el.bg(rgba(colors.primary.with_alpha(0.2)))
+                                                    .text_color(rgb(colors.text_primary))
+                                            })
+                                            .when(!selected, |el| {
+                                                el.text_color(rgb(colors.text_secondary))
+                                                    .hover(|el| el.bg(rgba(colors.hover)))
+                                            })
+                                            .on_click(cx.listener(move |this, _, cx| {
+                                                this.compare_agents[side] = Some(agent_id.clone());
+                                                cx.notify();
+                                            }))
+                                            .child(agent.name.clone())
+                                    }))
+                            }))
+                            .when_some(self.compare_error.clone(), |el, error| {
+                                el.child(
+                                    div()
+                                        .text_xs()
+                                        .text_color(rgb(colors.error))
+                                        .child(error),
+                                )
+                            }),
+                    )
+                    // Footer
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(12.0))
+                            .border_t_1()
+                            .border_color(rgb(colors.border))
+                            .flex()
+                            .justify_end()
+                            .gap(px(8.0))
+                            .child(
+                                div()
+                                    .id("cancel-compare-btn")
+                                    .px(px(16.0))
+                                    .py(px(8.0))
+                                    .rounded(px(6.0))
+                                    .bg(rgb(colors.surface))
+                                    .text_sm()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .cursor_pointer()
+                                    .hover(|el| el.bg(rgb(colors.border)))
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.show_compare_dialog = false;
+                                        cx.notify();
+                                    }))
+                                    .child(t("common.cancel")),
+                            )
+                            .child(
+                                div()
+                                    .id("start-compare-btn")
+                                    .px(px(16.0))
+                                    .py(px(8.0))
+                                    .rounded(px(6.0))
+                                    .bg(rgb(colors.primary))
+                                    .text_sm()
+                                    .text_color(white())
+                                    .cursor_pointer()
+                                    .hover(|el| el.bg(rgb(colors.primary_hover)))
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.start_comparison(cx);
+                                    }))
+                                    .child(t("common.run")),
+                            ),
+                    ),
+            )
+    }
+
+    /// Split view of a running or finished comparison, replacing the thread
+    fn render_comparison(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = self.theme.colors.clone();
+        let Some(comparison) = self.acp.manager.comparison.clone() else {
+            return div();
+        };
+        let agents = self.acp.available_agents();
+        let running = comparison.is_running();
+
+        let columns = comparison
+            .sides
+            .iter()
+            .enumerate()
+            .map(|(idx, side)| {
+                let name = agents
+                    .iter()
+                    .find(|a| a.id == side.agent_id)
+                    .map(|a| a.name.clone())
+                    .unwrap_or_else(|| side.agent_id.clone());
+                let (status, status_color) = match &side.state {
+                    BatchItemState::Queued => (t("common.connecting").to_string(), colors.text_secondary),
+                    BatchItemState::Running => (t("common.running").to_string(), colors.primary),
+                    BatchItemState::Finished { stop_reason } => (format!("{:?}", stop_reason), colors.success),
+                    BatchItemState::Failed { .. } => (t("common.failed").to_string(), colors.error),
+                };
+                let seconds = |d: chrono::Duration| format!("{:.1}s", d.num_milliseconds() as f64 / 1000.0);
+                let mut stats = Vec::new();
+                if let Some(first) = side.time_to_first_output() {
+                    stats.push(t_args("compare.first_output", &[("time", &seconds(first))]));
+                }
+                if let Some(total) = side.duration() {
+                    stats.push(t_args("compare.total", &[("time", &seconds(total))]));
+                }
+                stats.push(t_args("common.tool_calls", &[("count", &side.tool_calls)]));
+                stats.push(t_args("compare.tokens", &[("count", &side.estimated_output_tokens())]));
+                let body = match &side.state {
+                    BatchItemState::Failed { error } => self.render_markdown_view(
+                        &format!("compare-{}", idx),
+                        error,
+                        true,
+                        cx,
+                    ),
+                    _ => self.render_markdown_view(&format!("compare-{}", idx), &side.response, false, cx),
+                };
+
+                div()
+                    .flex_1()
+                    .min_w_0()
+                    .min_h_0()
+                    .flex()
+                    .flex_col()
+                    .when(idx > 0, |el| el.border_l_1().border_color(rgb(colors.border)))
+                    .child(
+                        div()
+                            .flex_shrink_0()
+                            .px(px(16.0))
+                            .py(px(8.0))
+                            .flex()
+                            .flex_col()
+                            .gap(px(2.0))
+                            .border_b_1()
+                            .border_color(rgb(colors.border_subtle))
+                            .child(
+                                div()
+                                    .flex()
+                                    .items_center()
+                                    .gap(px(8.0))
+                                    .child(
+                                        div()
+                                            .flex_1()
+                                            .min_w_0()
+                                            .text_sm()
+                                            .font_weight(FontWeight::MEDIUM)
+                                            .text_color(rgb(colors.text_primary))
+                                            .text_ellipsis()
+                                            .child(name),
+                                    )
+                                    .child(
+                                        div()
+                                            .px(px(6.0))
+                                            .py(px(1.0))
+                                            .rounded(px(4.0))
+                                            .bg(rgba(status_color.with_alpha(0.15)))
+                                            .text_xs()
+                                            .text_color(rgb(status_color))
+                                            .child(status),
+                                    ),
+                            )
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .child(stats.join(" · ")),
+                            ),
+                    )
+                    .child(
+                        div()
+                            .id(SharedString::from(format!("compare-response-{}", idx)))
+                            .flex_1()
+                            .min_h_0()
+                            .overflow_y_scroll()
+                            .px(px(16.0))
+                            .py(px(12.0))
+                            .child(body),
+                    )
+            })
+            .collect::<Vec<_>>();
+
+        div()
+            .flex_1()
+            .min_h_0()
+            .flex()
+            .flex_col()
+            .child(
+                div()
+                    .flex_shrink_0()
+                    .px(px(16.0))
+                    .py(px(8.0))
+                    .flex()
+                    .items_center()
+                    .gap(px(12.0))
+                    .border_b_1()
+                    .border_color(rgb(colors.border))
+                    .child(
+                        div()
+                            .flex_1()
+                            .min_w_0()
+                            .text_xs()
+                            .text_color(rgb(colors.text_secondary))
+                            .overflow_hidden()
+                            .whitespace_nowrap()
+                            .text_ellipsis()
+                            .child(t_args("common.prompt", &[("prompt", &comparison.prompt)])),
+                    )
+                    .when(!running, |el| {
+                        el.child(
+                            div()
+                                .id("close-comparison-btn")
+                                .text_xs()
+                                .text_color(rgb(colors.primary))
+                                .cursor_pointer()
+                                .on_click(cx.listener(|this, _, cx| {
+                                    this.acp.manager.clear_comparison();
+                                    cx.notify();
+                                }))
+                                .child(t("compare.close")),
+                        )
+                    }),
+            )
+            .child(
+                div()
+                    .flex_1()
+                    .min_h_0()
+                    .flex()
+                    .children(columns),
+            )
+    }
+
+    fn render_auth_dialog(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let Some(flow) = self.acp.manager.auth_flow.as_ref() else {
+            return div();
+        };
+        let signing_in = self.acp.has_pending_operation();
+
+        // Modal overlay
+        div()
+            .absolute()
+            .inset_0()
+            .flex()
+            .items_center()
+            .justify_center()
+            .bg(rgba(colors.panel_bg.with_alpha(0.9)))
+            .child(
+                // Dialog box
+                div()
+                    .w(px(480.0))
+                    .bg(rgb(colors.surface_elevated))
+                    .rounded(px(12.0))
+                    .border_1()
+                    .border_color(rgb(colors.border))
+                    .shadow_lg()
+                    .flex()
+                    .flex_col()
+                    .on_mouse_down(MouseButton::Left, |_, cx| {
+                        cx.stop_propagation();
+                    })
+                    // Header
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(16.0))
+                            .border_b_1()
+                            .border_color(rgb(colors.border))
+                            .flex()
+                            .flex_col()
+                            .gap(px(4.0))
+                            .child(
+                                div()
+                                    .text_lg()
+                                    .font_weight(FontWeight::SEMIBOLD)
+                                    .text_color(rgb(colors.text_primary))
+                                    .child(t_args("auth.title", &[("agent", &flow.agent_name)])),
+                            )
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .child(format!(
+                                        "{} needs you to sign in before it can start a session.",
+                                        flow.agent_name
+                                    )),
+                            ),
+                    )
+                    // Options
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(16.0))
+                            .flex()
+                            .flex_col()
+                            .gap(px(8.0))
+                            .child(
+                                div()
+                                    .px(px(10.0))
+                                    .py(px(6.0))
+                                    .rounded(px(6.0))
+                                    .bg(rgb(colors.code_bg))
+                                    .text_xs()
+                                    .font_family("monospace")
+                                    .text_color(rgb(colors.code_text))
+                                    .child(flow.message.clone()),
+                            )
+                            .children(flow.actions.iter().enumerate().map(|(index, action)| {
+                                div()
+                                    .id(SharedString::from(format!("auth-action-{}", index)))
+                                    .px(px(12.0))
+                                    .py(px(10.0))
+                                    .rounded(px(8.0))
+                                    .border_1()
+                                    .border_color(rgb(colors.border))
+                                    .flex()
+                                    .flex_col()
+                                    .gap(px(2.0))
+                                    .when(!signing_in, |el| {
+                                        el.cursor_pointer()
+                                            .hover(|el| el.bg(rgb(colors.hover)))
+                                            .on_click(cx.listener(move |this, _, cx| {
+                                                // Entering an API key happens in settings
+                                                if this.acp.manager.run_auth_action(index).is_some() {
+                                                    this.open_settings(cx);
+                                                }
+                                                cx.notify();
+                                            }))
+                                    })
+                                    .child(
+                                        div()
+                                            .text_sm()
+                                            .font_weight(FontWeight::MEDIUM)
+                                            .text_color(rgb(colors.text_primary))
+                                            .child(action.label()),
+                                    )
+                                    .when_some(action.description(), |el, description| {
+                                        el.child(
+                                            div()
+                                                .text_xs()
+                                                .text_color(rgb(colors.text_secondary))
+                                                .child(description),
+                                        )
+                                    })
+                            }))
+                            .when_some(self.acp.manager.auth_error.clone(), |el, error| {
+                                el.child(
+                                    div()
+                                        .text_xs()
+                                        .text_color(rgb(colors.error))
+                                        .child(error),
+                                )
+                            }),
+                    )
+                    // Footer
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(12.0))
+                            .border_t_1()
+                            .border_color(rgb(colors.border))
+                            .flex()
+                            .justify_end()
+                            .gap(px(8.0))
+                            .child(
+                                div()
+                                    .id("cancel-auth-btn")
+                                    .px(px(16.0))
+                                    .py(px(8.0))
+                                    .rounded(px(6.0))
+                                    .bg(rgb(colors.surface))
+                                    .text_sm()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .cursor_pointer()
+                                    .hover(|el| el.bg(rgb(colors.border)))
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.acp.manager.dismiss_auth_flow();
+                                        cx.notify();
+                                    }))
+                                    .child(t("common.cancel")),
+                            )
+                            .child(
+                                div()
+                                    .id("retry-auth-btn")
+                                    .px(px(16.0))
+                                    .py(px(8.0))
+                                    .rounded(px(6.0))
+                                    .text_sm()
+                                    .when(!signing_in, |el| {
+                                        el.bg(rgb(colors.primary))
+                                            .text_color(white())
+                                            .cursor_pointer()
+                                            .hover(|el| el.bg(rgb(colors.primary_hover)))
+                                            .on_click(cx.listener(|this, _, cx| {
+                                                this.acp.manager.retry_after_auth();
+                                                cx.notify();
+                                            }))
+                                            .child(t("auth.retry"))
+                                    })
+                                    .when(signing_in, |el| {
+                                        el.bg(rgb(colors.surface))
+                                            .text_color(rgb(colors.text_secondary))
+                                            .child(t("auth.signing_in"))
+                                    }),
+                            ),
+                    ),
+            )
+    }
+}
+
+impl CocoWorkWindow {
+    fn render_install_dialog(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let installs = &self.acp.manager.installs;
+        let running = self.acp.manager.installs_running();
+        // Agents with versioned installs: (id, name, versions, pinned)
+        let versioned: Vec<(String, String, Vec<String>, Option<String>)> = self
+            .acp
+            .available_agents()
+            .into_iter()
+            .filter_map(|agent| {
+                let versions = self.acp.manager.installed_versions(&agent.id);
+                if versions.is_empty() {
+                    return None;
+                }
+                let pinned = self.acp.manager.pinned_version(&agent.id);
+                Some((agent.id, agent.name, versions, pinned))
+            })
+            .collect();
+
+        // Modal overlay
+        div()
+            .absolute()
+            .inset_0()
+            .flex()
+            .items_center()
+            .justify_center()
+            .bg(rgba(colors.panel_bg.with_alpha(0.9)))
+            .child(
+                // Dialog box
+                div()
+                    .w(px(520.0))
+                    .max_h(px(560.0))
+                    .bg(rgb(colors.surface_elevated))
+                    .rounded(px(12.0))
+                    .border_1()
+                    .border_color(rgb(colors.border))
+                    .shadow_lg()
+                    .flex()
+                    .flex_col()
+                    .on_mouse_down(MouseButton::Left, |_, cx| {
+                        cx.stop_propagation();
+                    })
+                    // Header
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(16.0))
+                            .border_b_1()
+                            .border_color(rgb(colors.border))
+                            .child(
+                                div()
+                                    .text_lg()
+                                    .font_weight(FontWeight::SEMIBOLD)
+                                    .text_color(rgb(colors.text_primary))
+                                    .child(t("installs.title")),
+                            ),
+                    )
+                    // Install list
+                    .child(
+                        div()
+                            .id("install-list")
+                            .flex_1()
+                            .overflow_y_scroll()
+                            .px(px(20.0))
+                            .py(px(16.0))
+                            .flex()
+                            .flex_col()
+                            .gap(px(16.0))
+                            .when(installs.is_empty(), |el| {
+                                el.child(
+                                    div()
+                                        .text_sm()
+                                        .text_color(rgb(colors.text_secondary))
+                                        .child(t("installs.none")),
+                                )
+                            })
+                            .children(installs.iter().map(|install| {
+                                let (status_color, error) = match &install.state {
+                                    InstallState::Running => (colors.text_secondary, None),
+                                    InstallState::Succeeded { .. } => (colors.success, None),
+                                    InstallState::Failed { error } => (colors.error, Some(error.clone())),
+                                };
+                                let tail: Vec<String> = install
+                                    .output
+                                    .iter()
+                                    .rev()
+                                    .take(6)
+                                    .rev()
+                                    .cloned()
+                                    .collect();
+
+                                div()
+                                    .flex()
+                                    .flex_col()
+                                    .gap(px(6.0))
+                                    .child(
+                                        div()
+                                            .flex()
+                                            .items_center()
+                                            .justify_between()
+                                            .child(
+                                                div()
+                                                    .text_sm()
+                                                    .font_weight(FontWeight::MEDIUM)
+                                                    .text_color(rgb(colors.text_primary))
+                                                    .child(install.agent_id.clone()),
+                                            )
+                                            .child(
+                                                div()
+                                                    .text_xs()
+                                                    .text_color(rgb(status_color))
+                                                    .child(install.message.clone()),
+                                            ),
+                                    )
+                                    // Progress bar
+                                    .when(install.is_running(), |el| {
+                                        el.child(
+                                            div()
+                                                .w_full()
+                                                .h(px(4.0))
+                                                .rounded(px(2.0))
+                                                .bg(rgb(colors.border))
+                                                .child(
+                                                    div()
+                                                        .h_full()
+                                                        .rounded(px(2.0))
+                                                        .bg(rgb(colors.primary))
+                                                        .w(relative(install.percent.unwrap_or(0.05))),
+                                                ),
+                                        )
+                                    })
+                                    .when(!tail.is_empty(), |el| {
+                                        el.child(
+                                            div()
+                                                .px(px(10.0))
+                                                .py(px(6.0))
+                                                .rounded(px(6.0))
+                                                .bg(rgb(colors.code_bg))
+                                                .font_family("monospace")
+                                                .text_xs()
+                                                .text_color(rgb(colors.code_text))
+                                                .flex()
+                                                .flex_col()
+                                                .children(tail.into_iter().map(|line| div().child(line))),
+                                        )
+                                    })
+                                    .when_some(error, |el, error| {
+                                        el.child(
+                                            div()
+                                                .text_xs()
+                                                .text_color(rgb(colors.error))
+                                                .child(error),
+                                        )
+                                    })
+                            })),
+                    )
+                    // Installed versions, with pinning
+                    .when(!versioned.is_empty(), |el| {
+                        el.child(
+                            div()
+                                .px(px(20.0))
+                                .py(px(12.0))
+                                .border_t_1()
+                                .border_color(rgb(colors.border))
+                                .flex()
+                                .flex_col()
+                                .gap(px(8.0))
+                                .child(
+                                    div()
+                                        .text_xs()
+                                        .font_weight(FontWeight::SEMIBOLD)
+                                        .text_color(rgb(colors.text_secondary))
+                                        .child(t("installs.versions")),
+                                )
+                                .children(versioned.into_iter().map(|(agent_id, name, versions, pinned)| {
+                                    let latest_id = agent_id.clone();
+                                    div()
+                                        .flex()
+                                        .items_center()
+                                        .flex_wrap()
+                                        .gap(px(6.0))
+                                        .child(
+                                            div()
+                                                .w(px(100.0))
+                                                .text_sm()
+                                                .text_color(rgb(colors.text_primary))
+                                                .child(name),
+                                        )
+                                        .child(
+                                            div()
+                                                .id(SharedString::from(format!("unpin-{}", agent_id)))
+                                                .px(px(8.0))
+                                                .py(px(2.0))
+                                                .rounded(px(4.0))
+                                                .border_1()
+                                                .text_xs()
+                                                .cursor_pointer()
+                                                .when(pinned.is_none(), |el| {
+                                                    el.border_color(rgb(colors.primary))
+                                                        .text_color(rgb(colors.primary))
+                                                })
+                                                .when(pinned.is_some(), |el| {
+                                                    el.border_color(rgb(colors.border))
+                                                        .text_color(rgb(colors.text_secondary))
+                                                })
+                                                .on_click(cx.listener(move |this, _, cx| {
+                                                    this.acp.manager.pin_agent_version(&latest_id, None);
+                                                    cx.notify();
+                                                }))
+                                                .child(t("common.latest")),
+                                        )
+                                        .children(versions.into_iter().map(|tag| {
+                                            let is_pinned = pinned.as_deref() == Some(tag.as_str());
+                                            let pin_agent_id = agent_id.clone();
+                                            let pin_tag = tag.clone();
+                                            div()
+                                                .id(SharedString::from(format!("pin-{}-{}", agent_id, tag)))
+                                                .px(px(8.0))
+                                                .py(px(2.0))
+                                                .rounded(px(4.0))
+                                                .border_1()
+                                                .text_xs()
+                                                .cursor_pointer()
+                                                .when(is_pinned, |el| {
+                                                    el.border_color(rgb(colors.primary))
+                                                        .text_color(rgb(colors.primary))
+                                                })
+                                                .when(!is_pinned, |el| {
+                                                    el.border_color(rgb(colors.border))
+                                                        .text_color(rgb(colors.text_secondary))
+                                                        .hover(|el| el.bg(rgb(colors.hover)))
+                                                })
+                                                .on_click(cx.listener(move |this, _, cx| {
+                                                    this.acp.manager.pin_agent_version(&pin_agent_id, Some(pin_tag.clone()));
+                                                    cx.notify();
+                                                }))
+                                                .child(tag)
+                                        }))
+                                })),
+                        )
+                    })
+                    // Footer
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(12.0))
+                            .border_t_1()
+                            .border_color(rgb(colors.border))
+                            .flex()
+                            .justify_end()
+                            .child(
+                                div()
+                                    .id("close-install-dialog-btn")
+                                    .px(px(16.0))
+                                    .py(px(8.0))
+                                    .rounded(px(6.0))
+                                    .bg(rgb(colors.surface))
+                                    .text_sm()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .cursor_pointer()
+                                    .hover(|el| el.bg(rgb(colors.border)))
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        // Running installs keep going in the background
+                                        this.acp.manager.clear_finished_installs();
+                                        this.show_install_dialog = false;
+                                        cx.notify();
+                                    }))
+                                    .child(if running { t("common.hide") } else { t("common.close") }),
+                            ),
+                    ),
+            )
+    }
+}
+
+impl CocoWorkWindow {
+    fn render_change_set_sheet(&self, session_id: &str, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+
+        // Snapshot the set so the lock isn't held while building elements
+        let Some((title, accepted_count, rows)) =
+            self.acp.manager.change_sets.with_change_set(session_id, |set| {
+                let rows: Vec<(String, String, bool, bool, (usize, usize))> = set
+                    .changes()
+                    .iter()
+                    .map(|c| (c.id.clone(), c.path.clone(), c.created, c.accepted, c.stats()))
+                    .collect();
+                (set.title(), set.accepted_count(), rows)
+            })
+        else {
+            return div();
+        };
+        let total = rows.len();
+
+        // Modal overlay
+        div()
+            .absolute()
+            .inset_0()
+            .flex()
+            .items_center()
+            .justify_center()
+            .bg(rgba(colors.panel_bg.with_alpha(0.9)))
+            .child(
+                // Sheet
+                div()
+                    .w(px(560.0))
+                    .max_h(px(520.0))
+                    .bg(rgb(colors.surface_elevated))
+                    .rounded(px(12.0))
+                    .border_1()
+                    .border_color(rgb(colors.border))
+                    .shadow_lg()
+                    .flex()
+                    .flex_col()
+                    .on_mouse_down(MouseButton::Left, |_, cx| {
+                        cx.stop_propagation();
+                    })
+                    // Header
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(16.0))
+                            .border_b_1()
+                            .border_color(rgb(colors.border))
+                            .flex()
+                            .items_center()
+                            .justify_between()
+                            .child(
+                                div()
+                                    .text_lg()
+                                    .font_weight(FontWeight::SEMIBOLD)
+                                    .text_color(rgb(colors.text_primary))
+                                    .child(title),
+                            )
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .child(t_args("changes.selected", &[("accepted", &accepted_count), ("total", &total)])),
+                            ),
+                    )
+                    // File list
+                    .child(
+                        div()
+                            .id("change-set-list")
+                            .flex_1()
+                            .overflow_y_scroll()
+                            .p(px(12.0))
+                            .flex()
+                            .flex_col()
+                            .gap(px(4.0))
+                            .children(rows.into_iter().map(|(change_id, path, created, accepted, (added, removed))| {
+                                let sid = session_id.to_string();
+                                div()
+                                    .id(SharedString::from(format!("change-{}", change_id)))
+                                    .px(px(10.0))
+                                    .py(px(6.0))
+                                    .rounded(px(6.0))
+                                    .flex()
+                                    .items_center()
+                                    .gap(px(8.0))
+                                    .cursor_pointer()
+                                    .hover(|el| el.bg(rgba(colors.hover)))
+                                    .on_click(cx.listener(move |this, _, cx| {
+                                        this.acp.manager.toggle_change(&sid, &change_id);
+                                        cx.notify();
+                                    }))
+                                    .child(
+                                        svg_icon(
+                                            if accepted { IconName::CircleCheck } else { IconName::Circle },
+                                            IconSize::Small,
+                                        )
+                                        .text_color(if accepted { rgb(colors.primary) } else { rgb(colors.text_secondary) }),
+                                    )
+                                    .child(
+                                        div()
+                                            .flex_1()
+                                            .min_w_0()
+                                            .text_sm()
+                                            .text_color(rgb(colors.text_primary))
+                                            .text_ellipsis()
+                                            .child(path),
+                                    )
+                                    .when(created, |el| {
+                                        el.child(
+                                            div()
+                                                .text_xs()
+                                                .text_color(rgb(colors.text_secondary))
+                                                .child(t("common.new_badge")),
+                                        )
+                                    })
+                                    .child(
+                                        div()
+                                            .text_xs()
+                                            .text_color(rgb(colors.success))
+                                            .child(format!("+{}", added)),
+                                    )
+                                    .child(
+                                        div()
+                                            .text_xs()
+                                            .text_color(rgb(colors.error))
+                                            .child(format!("-{}", removed)),
+                                    )
+                            })),
+                    )
+                    // Footer
+                    .child({
+                        let sid_all = session_id.to_string();
+                        let sid_none = session_id.to_string();
+                        let sid_apply = session_id.to_string();
+                        div()
+                            .px(px(20.0))
+                            .py(px(12.0))
+                            .border_t_1()
+                            .border_color(rgb(colors.border))
+                            .flex()
+                            .items_center()
+                            .justify_between()
+                            .child(
+                                div()
+                                    .flex()
+                                    .gap(px(8.0))
+                                    .child(
+                                        div()
+                                            .id("accept-all-changes")
+                                            .text_xs()
+                                            .text_color(rgb(colors.text_secondary))
+                                            .cursor_pointer()
+                                            .hover(|el| el.text_color(rgb(colors.text_primary)))
+                                            .on_click(cx.listener(move |this, _, cx| {
+                                                this.acp.manager.set_all_changes_accepted(&sid_all, true);
+                                                cx.notify();
+                                            }))
+                                            .child(t("common.select_all")),
+                                    )
+                                    .child(
+                                        div()
+                                            .id("reject-all-changes")
+                                            .text_xs()
+                                            .text_color(rgb(colors.text_secondary))
+                                            .cursor_pointer()
+                                            .hover(|el| el.text_color(rgb(colors.text_primary)))
+                                            .on_click(cx.listener(move |this, _, cx| {
+                                                this.acp.manager.set_all_changes_accepted(&sid_none, false);
+                                                cx.notify();
+                                            }))
+                                            .child(t("common.select_none")),
+                                    ),
+                            )
+                            .child(
+                                div()
+                                    .id("apply-change-set")
+                                    .px(px(16.0))
+                                    .py(px(8.0))
+                                    .rounded(px(6.0))
+                                    .bg(rgb(colors.primary))
+                                    .text_sm()
+                                    .text_color(white())
+                                    .cursor_pointer()
+                                    .hover(|el| el.bg(rgb(colors.primary_hover)))
+                                    .on_click(cx.listener(move |this, _, cx| {
+                                        this.acp.manager.resolve_change_set(&sid_apply);
+                                        cx.notify();
+                                    }))
+                                    .child(if accepted_count == total {
+                                        t("changes.accept_all").to_string()
+                                    } else {
+                                        t_args("changes.accept", &[("accepted", &accepted_count), ("total", &total)])
+                                    }),
+                            )
+                    }),
+            )
+    }
+
+    /// Files the isolated thread changed, to merge back into the workspace or throw away
+    fn render_isolation_review(
+        &self,
+        review: Result<Vec<FileDiff>, String>,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let has_changes = review.as_ref().is_ok_and(|diffs| !diffs.is_empty());
+        let status = self.isolation_status.clone().map(|status| match status {
+            Ok(workspace) => (t_args("isolation.merged", &[("dir", &workspace.display())]), colors.success),
+            Err(e) => (e, colors.error),
+        });
+        let body = match review {
+            Err(e) => div().text_sm().text_color(rgb(colors.error)).child(e),
+            Ok(diffs) if diffs.is_empty() => div()
+                .text_sm()
+                .text_color(rgb(colors.text_secondary))
+                .child(t("isolation.no_changes")),
+            Ok(diffs) => self.render_diff_list(diffs),
+        };
+
+        // Modal overlay
+        div()
+            .absolute()
+            .inset_0()
+            .flex()
+            .items_center()
+            .justify_center()
+            .bg(rgba(colors.panel_bg.with_alpha(0.9)))
+            .on_mouse_down(MouseButton::Left, cx.listener(|this, _, cx| {
+                this.isolation_review = None;
+                cx.notify();
+            }))
+            .child(
+                // Sheet
+                div()
+                    .w(px(640.0))
+                    .max_h(px(560.0))
+                    .bg(rgb(colors.surface_elevated))
+                    .rounded(px(12.0))
+                    .border_1()
+                    .border_color(rgb(colors.border))
+                    .shadow_lg()
+                    .flex()
+                    .flex_col()
+                    .on_mouse_down(MouseButton::Left, |_, cx| {
+                        cx.stop_propagation();
+                    })
+                    // Header
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(16.0))
+                            .border_b_1()
+                            .border_color(rgb(colors.border))
+                            .text_lg()
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .text_color(rgb(colors.text_primary))
+                            .child(t("isolation.title")),
+                    )
+                    // Changed files
+                    .child(
+                        div()
+                            .id("isolation-diff-list")
+                            .flex_1()
+                            .overflow_y_scroll()
+                            .p(px(16.0))
+                            .child(body),
+                    )
+                    // Footer
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(12.0))
+                            .border_t_1()
+                            .border_color(rgb(colors.border))
+                            .flex()
+                            .items_center()
+                            .gap(px(12.0))
+                            .child(
+                                div()
+                                    .id("discard-isolation-btn")
+                                    .text_xs()
+                                    .text_color(rgb(colors.error))
+                                    .cursor_pointer()
+                                    .on_click(cx.listener(|this, _, cx| this.discard_isolation(cx)))
+                                    .child(t("isolation.discard")),
+                            )
+                            .child(
+                                div()
+                                    .flex_1()
+                                    .min_w_0()
+                                    .text_xs()
+                                    .text_ellipsis()
+                                    .when_some(status, |el, (message, tint)| {
+                                        el.text_color(rgb(tint)).child(message)
+                                    }),
+                            )
+                            .child(
+                                div()
+                                    .id("close-isolation-btn")
+                                    .px(px(16.0))
+                                    .py(px(8.0))
+                                    .rounded(px(6.0))
+                                    .bg(rgb(colors.surface))
+                                    .text_sm()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .cursor_pointer()
+                                    .hover(|el| el.bg(rgb(colors.border)))
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.isolation_review = None;
+                                        cx.notify();
+                                    }))
+                                    .child(t("common.close")),
+                            )
+                            .when(has_changes, |el| {
+                                el.child(
+                                    div()
+                                        .id("merge-back-btn")
+                                        .px(px(16.0))
+                                        .py(px(8.0))
+                                        .rounded(px(6.0))
+                                        .bg(rgb(colors.primary))
+                                        .text_sm()
+                                        .text_color(white())
+                                        .cursor_pointer()
+                                        .hover(|el| el.bg(rgb(colors.primary_hover)))
+                                        .on_click(cx.listener(|this, _, cx| this.merge_back_isolation(cx)))
+                                        .child(t("isolation.merge_back")),
+                                )
+                            }),
+                    ),
+            )
+    }
+
+    /// Changed files with their line counts and changed lines
+    fn render_diff_list(&self, diffs: Vec<FileDiff>) -> Div {
+        let colors = &self.theme.colors;
+        div().flex().flex_col().gap(px(10.0)).children(diffs.into_iter().map(|diff| {
+            let (added, removed) = cocowork_core::sandbox::diff::diff_stats(&diff);
+            let lines: Vec<_> = diff
+                .hunks
+                .iter()
+                .flat_map(|hunk| hunk.lines.iter())
+                .filter(|line| line.kind != DiffLineKind::Context)
+                .take(40)
+                .map(|line| (line.kind, line.content.clone()))
+                .collect();
+
+            div()
+                .flex()
+                .flex_col()
+                .gap(px(4.0))
+                .child(
+                    div()
+                        .flex()
+                        .items_center()
+                        .gap(px(8.0))
+                        .child(
+                            div()
+                                .flex_1()
+                                .min_w_0()
+                                .text_sm()
+                                .text_color(rgb(colors.text_primary))
+                                .text_ellipsis()
+                                .child(diff.path.clone()),
+                        )
+                        .child(div().text_xs().text_color(rgb(colors.success)).child(format!("+{}", added)))
+                        .child(div().text_xs().text_color(rgb(colors.error)).child(format!("-{}", removed))),
+                )
+                .child(
+                    div()
+                        .px(px(8.0))
+                        .py(px(4.0))
+                        .rounded(px(4.0))
+                        .bg(rgb(colors.code_bg))
+                        .font_family("monospace")
+                        .text_xs()
+                        .flex()
+                        .flex_col()
+                        .children(lines.into_iter().map(|(kind, text)| {
+                            let (prefix, color) = match kind {
+                                DiffLineKind::Add => ("+", colors.success),
+                                DiffLineKind::Remove => ("-", colors.error),
+                                DiffLineKind::Context => (" ", colors.code_text),
+                            };
+                            div().text_color(rgb(color)).child(format!("{}{}", prefix, text))
+                        })),
+                )
+        }))
+    }
+
+    /// What the agent changed since the thread's last commit, with a message to commit it under
+    fn render_commit_dialog(
+        &self,
+        review: Result<Vec<FileDiff>, String>,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let has_changes = review.as_ref().is_ok_and(|diffs| !diffs.is_empty());
+        let drafting = self.acp.is_loading();
+        let can_commit =
+            has_changes && !drafting && !self.commit_message_input.read(cx).content().trim().is_empty();
+        let status = match &self.commit_error {
+            Some(e) => Some((e.clone(), colors.error)),
+            None => drafting.then(|| (t("commit.drafting").to_string(), colors.text_secondary)),
+        };
+        let body = match review {
+            Err(e) => div().text_sm().text_color(rgb(colors.error)).child(e),
+            Ok(diffs) if diffs.is_empty() => div()
+                .text_sm()
+                .text_color(rgb(colors.text_secondary))
+                .child(t("commit.no_changes")),
+            Ok(diffs) => self.render_diff_list(diffs),
+        };
+
+        // Modal overlay
+        div()
+            .absolute()
+            .inset_0()
+            .flex()
+            .items_center()
+            .justify_center()
+            .bg(rgba(colors.panel_bg.with_alpha(0.9)))
+            .on_mouse_down(MouseButton::Left, cx.listener(|this, _, cx| {
+                this.commit_review = None;
+                cx.notify();
+            }))
+            .child(
+                // Sheet
+                div()
+                    .w(px(640.0))
+                    .max_h(px(640.0))
+                    .bg(rgb(colors.surface_elevated))
+                    .rounded(px(12.0))
+                    .border_1()
+                    .border_color(rgb(colors.border))
+                    .shadow_lg()
+                    .flex()
+                    .flex_col()
+                    .on_mouse_down(MouseButton::Left, |_, cx| {
+                        cx.stop_propagation();
+                    })
+                    // Header
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(16.0))
+                            .border_b_1()
+                            .border_color(rgb(colors.border))
+                            .text_lg()
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .text_color(rgb(colors.text_primary))
+                            .child(t("commit.title")),
+                    )
+                    // Changed files
+                    .child(
+                        div()
+                            .id("commit-diff-list")
+                            .flex_1()
+                            .overflow_y_scroll()
+                            .p(px(16.0))
+                            .child(body),
+                    )
+                    // Message
+                    .when(has_changes, |el| {
+                        el.child(
+                            div()
+                                .px(px(16.0))
+                                .pb(px(12.0))
+                                .flex()
+                                .flex_col()
+                                .gap(px(6.0))
+                                .child(
+                                    div()
+                                        .min_h(px(72.0))
+                                        .px(px(10.0))
+                                        .py(px(8.0))
+                                        .rounded(px(6.0))
+                                        .border_1()
+                                        .border_color(rgb(colors.border))
+                                        .bg(rgb(colors.surface))
+                                        .text_sm()
+                                        .child(self.commit_message_input.clone()),
+                                )
+                                .child(
+                                    div()
+                                        .id("draft-commit-message-btn")
+                                        .text_xs()
+                                        .when(drafting, |el| el.text_color(rgb(colors.text_disabled)))
+                                        .when(!drafting, |el| {
+                                            el.text_color(rgb(colors.primary))
+                                                .cursor_pointer()
+                                                .on_click(cx.listener(|this, _, cx| this.draft_commit_message(cx)))
+                                        })
+                                        .child(t("commit.draft")),
+                                ),
+                        )
+                    })
+                    // Footer
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(12.0))
+                            .border_t_1()
+                            .border_color(rgb(colors.border))
+                            .flex()
+                            .items_center()
+                            .gap(px(12.0))
+                            .child(
+                                div()
+                                    .flex_1()
+                                    .min_w_0()
+                                    .text_xs()
+                                    .text_ellipsis()
+                                    .when_some(status, |el, (message, tint)| {
+                                        el.text_color(rgb(tint)).child(message)
+                                    }),
+                            )
+                            .child(
+                                div()
+                                    .id("close-commit-btn")
+                                    .px(px(16.0))
+                                    .py(px(8.0))
+                                    .rounded(px(6.0))
+                                    .bg(rgb(colors.surface))
+                                    .text_sm()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .cursor_pointer()
+                                    .hover(|el| el.bg(rgb(colors.border)))
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.commit_review = None;
+                                        cx.notify();
+                                    }))
+                                    .child(t("common.cancel")),
+                            )
+                            .when(has_changes, |el| {
+                                el.child(
+                                    div()
+                                        .id("commit-btn")
+                                        .px(px(16.0))
+                                        .py(px(8.0))
+                                        .rounded(px(6.0))
+                                        .text_sm()
+                                        .when(can_commit, |el| {
+                                            el.bg(rgb(colors.primary))
+                                                .text_color(white())
+                                                .cursor_pointer()
+                                                .hover(|el| el.bg(rgb(colors.primary_hover)))
+                                                .on_click(cx.listener(|this, _, cx| this.commit_changes(cx)))
+                                        })
+                                        .when(!can_commit, |el| {
+                                            el.bg(rgb(colors.surface)).text_color(rgb(colors.text_disabled))
+                                        })
+                                        .child(t("commit.commit")),
+                                )
+                            }),
+                    ),
+            )
+    }
+
+    /// Sensitive strings found in the thread being exported, each hidden or
+    /// shown on its own or by kind, plus terms the user adds
+    fn render_export_review(&self, cx: &mut ViewContext<Self>) -> Div {
+        let colors = &self.theme.colors;
+        let Some((_, _, findings)) = &self.export_review else {
+            return div();
+        };
+        let redaction = &self.export_redaction;
+        let kind_style = |kind: SensitiveKind| match kind {
+            SensitiveKind::Secret => ("redact.kind.secret", "redact.rule.secret", colors.error),
+            SensitiveKind::Email => ("redact.kind.email", "redact.rule.email", colors.warning),
+            SensitiveKind::Path => ("redact.kind.path", "redact.rule.path", colors.primary),
+        };
+        let hidden = findings.iter().filter(|finding| redaction.hides(finding)).count();
+        let summary = t_args("redact.summary", &[("hidden", &hidden), ("count", &findings.len())]);
+
+        let rules = SensitiveKind::ALL.into_iter().map(|kind| {
+            let (_, rule, tint) = kind_style(kind);
+            let count = findings.iter().filter(|finding| finding.kind == kind).count();
+            let on = redaction.kinds.contains(&kind);
+            div()
+                .id(SharedString::from(format!("redact-rule-{:?}", kind)))
+                .px(px(10.0))
+                .py(px(4.0))
+                .rounded(px(12.0))
+                .border_1()
+                .text_xs()
+                .cursor_pointer()
+                .when(on, |el| el.border_color(rgb(tint)).bg(rgba(tint.with_alpha(0.15))).text_color(rgb(tint)))
+                .when(!on, |el| {
+                    el.border_color(rgb(colors.border)).text_color(rgb(colors.text_secondary))
+                })
+                .on_click(cx.listener(move |this, _, cx| {
+                    if let Some((_, _, findings)) = &this.export_review {
+                        this.export_redaction.toggle_kind(kind, findings);
+                    }
+                    cx.notify();
+                }))
+                .child(t_args(rule, &[("count", &count)]))
+        });
+        // Collected so the listeners' borrows of `cx` don't overlap
+        let rules: Vec<_> = rules.collect();
+
+        let rows = findings.iter().enumerate().map(|(idx, finding)| {
+            let (label, _, tint) = kind_style(finding.kind);
+            let hides = redaction.hides(finding);
+            div()
+                .id(SharedString::from(format!("redact-finding-{}", idx)))
+                .px(px(8.0))
+                .py(px(4.0))
+                .flex()
+                .items_center()
+                .gap(px(8.0))
+                .rounded(px(4.0))
+                .cursor_pointer()
+                .hover(|s| s.bg(rgba(colors.hover)))
+                .on_click(cx.listener(move |this, _, cx| {
+                    if let Some((_, _, findings)) = &this.export_review {
+                        this.export_redaction.toggle(&findings[idx], findings);
+                    }
+                    cx.notify();
+                }))
+                .child(
+                    div()
+                        .w(px(14.0))
+                        .h(px(14.0))
+                        .flex_none()
+                        .flex()
+                        .items_center()
+                        .justify_center()
+                        .rounded(px(3.0))
+                        .border_1()
+                        .text_xs()
+                        .when(hides, |el| {
+                            el.border_color(rgb(colors.primary)).bg(rgb(colors.primary)).text_color(white()).child("✓")
+                        })
+                        .when(!hides, |el| el.border_color(rgb(colors.border))),
+                )
+                .child(div().w(px(48.0)).flex_none().text_xs().text_color(rgb(tint)).child(t(label)))
+                .child(
+                    div()
+                        .flex_1()
+                        .min_w_0()
+                        .text_sm()
+                        .font_family("monospace")
+                        .text_ellipsis()
+                        .px(px(4.0))
+                        .rounded(px(3.0))
+                        .when(hides, |el| el.bg(rgba(tint.with_alpha(0.15))).text_color(rgb(colors.text_primary)))
+                        .when(!hides, |el| el.text_color(rgb(colors.text_secondary)))
+                        .child(finding.text.clone()),
+                )
+                .child(
+                    div()
+                        .flex_none()
+                        .text_xs()
+                        .text_color(rgb(colors.text_secondary))
+                        .child(if hides {
+                            finding.kind.placeholder().to_string()
+                        } else {
+                            format!("×{}", finding.count)
+                        }),
+                )
+        });
+        let rows: Vec<_> = rows.collect();
+
+        let terms = redaction.terms.iter().map(|term| {
+            let term = term.clone();
+            div()
+                .id(SharedString::from(format!("redact-term-{}", term)))
+                .px(px(8.0))
+                .py(px(2.0))
+                .rounded(px(12.0))
+                .bg(rgb(colors.surface))
+                .border_1()
+                .border_color(rgb(colors.border))
+                .text_xs()
+                .text_color(rgb(colors.text_primary))
+                .cursor_pointer()
+                .hover(|s| s.bg(rgba(colors.hover)))
+                .child(format!("{}  ×", term))
+                .on_click(cx.listener(move |this, _, cx| {
+                    this.export_redaction.terms.remove(&term);
+                    cx.notify();
+                }))
+        });
+
+        // Modal overlay
+        div()
+            .absolute()
+            .inset_0()
+            .flex()
+            .items_center()
+            .justify_center()
+            .bg(rgba(colors.panel_bg.with_alpha(0.9)))
+            .on_mouse_down(MouseButton::Left, cx.listener(|this, _, cx| {
+                this.export_review = None;
+                cx.notify();
+            }))
+            .child(
+                // Sheet
+                div()
+                    .w(px(640.0))
+                    .max_h(px(640.0))
+                    .bg(rgb(colors.surface_elevated))
+                    .rounded(px(12.0))
+                    .border_1()
+                    .border_color(rgb(colors.border))
+                    .shadow_lg()
+                    .flex()
+                    .flex_col()
+                    .on_mouse_down(MouseButton::Left, |_, cx| {
+                        cx.stop_propagation();
+                    })
+                    // Header
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(16.0))
+                            .border_b_1()
+                            .border_color(rgb(colors.border))
+                            .flex()
+                            .flex_col()
+                            .gap(px(4.0))
+                            .child(
+                                div()
+                                    .text_lg()
+                                    .font_weight(FontWeight::SEMIBOLD)
+                                    .text_color(rgb(colors.text_primary))
+                                    .child(t("redact.title")),
+                            )
+                            .child(div().text_xs().text_color(rgb(colors.text_secondary)).child(t("redact.hint"))),
+                    )
+                    // Rules by kind
+                    .child(div().px(px(16.0)).pt(px(12.0)).flex().flex_wrap().gap(px(6.0)).children(rules))
+                    // Found strings
+                    .child(
+                        div()
+                            .id("redact-findings")
+                            .flex_1()
+                            .overflow_y_scroll()
+                            .p(px(8.0))
+                            .when(findings.is_empty(), |el| {
+                                el.child(
+                                    div()
+                                        .p(px(8.0))
+                                        .text_sm()
+                                        .text_color(rgb(colors.text_secondary))
+                                        .child(t("redact.none_found")),
+                                )
+                            })
+                            .children(rows),
+                    )
+                    // Terms of the user's own
+                    .child(
+                        div()
+                            .px(px(16.0))
+                            .pb(px(12.0))
+                            .flex()
+                            .flex_col()
+                            .gap(px(6.0))
+                            .child(
+                                div()
+                                    .flex()
+                                    .items_center()
+                                    .gap(px(6.0))
+                                    .child(
+                                        div()
+                                            .flex_1()
+                                            .min_w_0()
+                                            .h(px(28.0))
+                                            .px(px(8.0))
+                                            .flex()
+                                            .items_center()
+                                            .rounded(px(6.0))
+                                            .border_1()
+                                            .border_color(rgb(colors.border))
+                                            .bg(rgb(colors.surface))
+                                            .text_sm()
+                                            .child(self.redact_term_input.clone()),
+                                    )
+                                    .child(
+                                        div()
+                                            .id("add-redact-term-btn")
+                                            .text_xs()
+                                            .text_color(rgb(colors.primary))
+                                            .cursor_pointer()
+                                            .on_click(cx.listener(|this, _, cx| this.add_redact_term(cx)))
+                                            .child(t("redact.add_term")),
+                                    ),
+                            )
+                            .when(!redaction.terms.is_empty(), |el| {
+                                el.child(div().flex().flex_wrap().gap(px(6.0)).children(terms))
+                            }),
+                    )
+                    // Footer
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(12.0))
+                            .border_t_1()
+                            .border_color(rgb(colors.border))
+                            .flex()
+                            .items_center()
+                            .gap(px(12.0))
+                            .child(
+                                div()
+                                    .flex_1()
+                                    .min_w_0()
+                                    .text_xs()
+                                    .text_ellipsis()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .child(summary),
+                            )
+                            .child(
+                                div()
+                                    .id("close-redact-btn")
+                                    .px(px(16.0))
+                                    .py(px(8.0))
+                                    .rounded(px(6.0))
+                                    .bg(rgb(colors.surface))
+                                    .text_sm()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .cursor_pointer()
+                                    .hover(|el| el.bg(rgb(colors.border)))
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.export_review = None;
+                                        cx.notify();
+                                    }))
+                                    .child(t("common.cancel")),
+                            )
+                            .child(
+                                div()
+                                    .id("copy-redacted-btn")
+                                    .px(px(16.0))
+                                    .py(px(8.0))
+                                    .rounded(px(6.0))
+                                    .bg(rgb(colors.surface))
+                                    .text_sm()
+                                    .text_color(rgb(colors.text_primary))
+                                    .cursor_pointer()
+                                    .hover(|el| el.bg(rgb(colors.border)))
+                                    .on_click(cx.listener(|this, _, cx| this.copy_redacted_export(cx)))
+                                    .child(t("redact.copy")),
+                            )
+                            .child(
+                                div()
+                                    .id("save-redacted-btn")
+                                    .px(px(16.0))
+                                    .py(px(8.0))
+                                    .rounded(px(6.0))
+                                    .bg(rgb(colors.primary))
+                                    .text_sm()
+                                    .text_color(white())
+                                    .cursor_pointer()
+                                    .hover(|el| el.bg(rgb(colors.primary_hover)))
+                                    .on_click(cx.listener(|this, _, cx| this.save_redacted_export(cx)))
+                                    .child(t("redact.export")),
+                            ),
+                    ),
+            )
+    }
+
+    /// Pull request wizard: a token for the forge if there's none yet, then
+    /// the title and description, then the link to the opened pull request
+    fn render_pull_request_wizard(
+        &self,
+        target: Result<PullRequestTarget, String>,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let manager = &self.acp.manager;
+        let opening = manager.is_opening_pull_request();
+        let drafting = self.acp.is_loading();
+        let opened = manager.pull_request.clone().and_then(Result::ok);
+        let needs_token = target.is_ok() && !self.pull_request_has_token && opened.is_none();
+        let editing = target.is_ok() && !needs_token && opened.is_none();
+        let can_open = editing
+            && !opening
+            && !drafting
+            && !self.pull_request_title_input.read(cx).content().trim().is_empty();
+        let failure = self
+            .pull_request_error
+            .clone()
+            .or_else(|| manager.pull_request.clone().and_then(Result::err));
+        let status = match failure {
+            Some(e) => Some((e, colors.error)),
+            None if opening => Some((t("pr.opening").to_string(), colors.text_secondary)),
+            None if drafting && editing => Some((t("pr.drafting").to_string(), colors.text_secondary)),
+            None => None,
+        };
+        let input_box = |input: View<TextInput>, min_height: f32| {
+            div()
+                .min_h(px(min_height))
+                .px(px(10.0))
+                .py(px(8.0))
+                .rounded(px(6.0))
+                .border_1()
+                .border_color(rgb(colors.border))
+                .bg(rgb(colors.surface))
+                .text_sm()
+                .child(input)
+        };
+
+        let body = match (&target, opened) {
+            (Err(e), _) => div()
+                .flex()
+                .flex_col()
+                .gap(px(8.0))
+                .child(div().text_sm().text_color(rgb(colors.error)).child(e.clone()))
+                .when_some(self.pull_request_unknown_host.clone(), |el, host| {
+                    el.child(
+                        div()
+                            .id("add-gitlab-host-btn")
+                            .text_xs()
+                            .text_color(rgb(colors.primary))
+                            .cursor_pointer()
+                            .on_click(cx.listener(move |this, _, cx| this.add_gitlab_host(&host, cx)))
+                            .child(t_args("pr.add_gitlab_host", &[("host", &host)])),
+                    )
+                }),
+            (Ok(_), Some(pull_request)) => {
+                let url = pull_request.url.clone();
+                let copy_url = pull_request.url.clone();
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(8.0))
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(rgb(colors.success))
+                            .child(t_args("pr.opened", &[("number", &pull_request.number)])),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .font_family("monospace")
+                            .text_color(rgb(colors.text_secondary))
+                            .child(pull_request.url.clone()),
+                    )
+                    .child(
+                        div()
+                            .flex()
+                            .gap(px(12.0))
+                            .child(
+                                div()
+                                    .id("open-pull-request-url-btn")
+                                    .text_xs()
+                                    .text_color(rgb(colors.primary))
+                                    .cursor_pointer()
+                                    .on_click(cx.listener(move |this, _, cx| {
+                                        if let Err(e) = cocowork_core::agent::open_url(&url) {
+                                            this.pull_request_error = Some(e.to_string());
+                                            cx.notify();
+                                        }
+                                    }))
+                                    .child(t("pr.view")),
+                            )
+                            .child(
+                                div()
+                                    .id("copy-pull-request-url-btn")
+                                    .text_xs()
+                                    .text_color(rgb(colors.primary))
+                                    .cursor_pointer()
+                                    .on_click(cx.listener(move |_, _, cx| {
+                                        cx.write_to_clipboard(ClipboardItem::new_string(copy_url.clone()));
+                                    }))
+                                    .child(t("banner.copy_link")),
+                            ),
+                    )
+            }
+            (Ok(target), None) if needs_token => {
+                let forge = target.remote.kind.label();
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(8.0))
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(rgb(colors.text_secondary))
+                            .child(t_args("pr.token_hint", &[("forge", &forge), ("host", &target.remote.host)])),
+                    )
+                    .child(input_box(self.pull_request_token_input.clone(), 0.0))
+            }
+            (Ok(target), None) => div()
+                .flex()
+                .flex_col()
+                .gap(px(8.0))
+                .child(
+                    div()
+                        .text_xs()
+                        .font_family("monospace")
+                        .text_color(rgb(colors.text_secondary))
+                        .child(t_args(
+                            "pr.target",
+                            &[("head", &target.head), ("base", &target.base), ("repo", &target.remote.path)],
+                        )),
+                )
+                .child(input_box(self.pull_request_title_input.clone(), 0.0))
+                .child(input_box(self.pull_request_body_input.clone(), 160.0))
+                .child(
+                    div()
+                        .id("draft-pull-request-btn")
+                        .text_xs()
+                        .when(drafting || opening, |el| el.text_color(rgb(colors.text_disabled)))
+                        .when(!drafting && !opening, |el| {
+                            el.text_color(rgb(colors.primary))
+                                .cursor_pointer()
+                                .on_click(cx.listener(|this, _, cx| this.draft_pull_request(cx)))
+                        })
+                        .child(t("commit.draft")),
+                ),
+        };
+        let action = if needs_token {
+            Some(("save-forge-token-btn", t("pr.save_token"), true))
+        } else if editing {
+            Some(("open-pull-request-btn", t("pr.open"), can_open))
+        } else {
+            None
+        };
+
+        // Modal overlay
+        div()
+            .absolute()
+            .inset_0()
+            .flex()
+            .items_center()
+            .justify_center()
+            .bg(rgba(colors.panel_bg.with_alpha(0.9)))
+            .on_mouse_down(MouseButton::Left, cx.listener(|this, _, cx| this.close_pull_request_wizard(cx)))
+            .child(
+                // Sheet
+                div()
+                    .w(px(560.0))
+                    .max_h(px(640.0))
+                    .bg(rgb(colors.surface_elevated))
+                    .rounded(px(12.0))
+                    .border_1()
+                    .border_color(rgb(colors.border))
+                    .shadow_lg()
+                    .flex()
+                    .flex_col()
+                    .on_mouse_down(MouseButton::Left, |_, cx| {
+                        cx.stop_propagation();
+                    })
+                    // Header
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(16.0))
+                            .border_b_1()
+                            .border_color(rgb(colors.border))
+                            .text_lg()
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .text_color(rgb(colors.text_primary))
+                            .child(t("pr.title")),
+                    )
+                    .child(div().id("pull-request-body").flex_1().overflow_y_scroll().p(px(16.0)).child(body))
+                    // Footer
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(12.0))
+                            .border_t_1()
+                            .border_color(rgb(colors.border))
+                            .flex()
+                            .items_center()
+                            .gap(px(12.0))
+                            .child(
+                                div()
+                                    .flex_1()
+                                    .min_w_0()
+                                    .text_xs()
+                                    .text_ellipsis()
+                                    .when_some(status, |el, (message, tint)| {
+                                        el.text_color(rgb(tint)).child(message)
+                                    }),
+                            )
+                            .child(
+                                div()
+                                    .id("close-pull-request-btn")
+                                    .px(px(16.0))
+                                    .py(px(8.0))
+                                    .rounded(px(6.0))
+                                    .bg(rgb(colors.surface))
+                                    .text_sm()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .cursor_pointer()
+                                    .hover(|el| el.bg(rgb(colors.border)))
+                                    .on_click(cx.listener(|this, _, cx| this.close_pull_request_wizard(cx)))
+                                    .child(if action.is_some() { t("common.cancel") } else { t("common.close") }),
+                            )
+                            .when_some(action, |el, (id, label, enabled)| {
+                                el.child(
+                                    div()
+                                        .id(id)
+                                        .px(px(16.0))
+                                        .py(px(8.0))
+                                        .rounded(px(6.0))
+                                        .text_sm()
+                                        .when(enabled, |el| {
+                                            el.bg(rgb(colors.primary))
+                                                .text_color(white())
+                                                .cursor_pointer()
+                                                .hover(|el| el.bg(rgb(colors.primary_hover)))
+                                                .on_click(cx.listener(move |this, _, cx| {
+                                                    if needs_token {
+                                                        this.save_forge_token(cx);
+                                                    } else {
+                                                        this.open_pull_request(cx);
+                                                    }
+                                                }))
+                                        })
+                                        .when(!enabled, |el| {
+                                            el.bg(rgb(colors.surface)).text_color(rgb(colors.text_disabled))
+                                        })
+                                        .child(label),
+                                )
+                            }),
+                    ),
+            )
+    }
+
+    fn resolve_command_confirmation(&mut self, approved: bool, cx: &mut ViewContext<Self>) {
+        self.acp.manager.resolve_confirmation(approved);
+        cx.notify();
+    }
+
+    /// A tool call the agent asks permission for, with the edit or command it
+    /// would make and the options the agent offers
+    fn render_permission_request_dialog(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let Some(request) = self.acp.manager.current_permission_request() else {
+            return div();
+        };
+        let queued = self.acp.manager.pending_permissions.len().saturating_sub(1);
+        let (icon, title) = match request.operation {
+            FileOperation::Execute => (IconName::Terminal, t("confirm.execute")),
+            FileOperation::Read | FileOperation::List => (IconName::File, t("confirm.read")),
+            FileOperation::Write | FileOperation::Move => (IconName::File, t("confirm.write")),
+            FileOperation::Delete => (IconName::File, t("confirm.delete")),
+        };
+        let code_block = || {
+            div()
+                .w_full()
+                .px(px(10.0))
+                .py(px(8.0))
+                .rounded(px(6.0))
+                .bg(rgb(colors.code_bg))
+                .font_family("monospace")
+                .text_color(rgb(colors.code_text))
+        };
+        let previews = request.previews.iter().map(|preview| match preview {
+            PermissionPreview::Diff(diff) => {
+                let (added, removed) = cocowork_core::sandbox::diff::diff_stats(diff);
+                let lines: Vec<_> = diff.hunks.iter().flat_map(|hunk| hunk.lines.iter()).take(80).collect();
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(4.0))
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .gap(px(8.0))
+                            .child(
+                                div()
+                                    .flex_1()
+                                    .min_w_0()
+                                    .text_sm()
+                                    .text_color(rgb(colors.text_primary))
+                                    .text_ellipsis()
+                                    .child(diff.path.clone()),
+                            )
+                            .child(div().text_xs().text_color(rgb(colors.success)).child(format!("+{}", added)))
+                            .child(div().text_xs().text_color(rgb(colors.error)).child(format!("-{}", removed))),
+                    )
+                    .child(code_block().text_xs().flex().flex_col().children(lines.into_iter().map(|line| {
+                        let (prefix, color) = match line.kind {
+                            DiffLineKind::Add => ("+", colors.success),
+                            DiffLineKind::Remove => ("-", colors.error),
+                            DiffLineKind::Context => (" ", colors.code_text),
+                        };
+                        div().text_color(rgb(color)).child(format!("{}{}", prefix, line.content))
+                    })))
+            }
+            PermissionPreview::Command(line) => div().child(code_block().text_sm().child(line.clone())),
+            PermissionPreview::Text(text) => {
+                div().text_sm().text_color(rgb(colors.text_secondary)).child(text.clone())
+            }
+        });
+
+        // Modal overlay
+        div()
+            .absolute()
+            .inset_0()
+            .flex()
+            .items_center()
+            .justify_center()
+            .bg(rgba(colors.panel_bg.with_alpha(0.9)))
+            .child(
+                // Dialog box
+                div()
+                    .w(px(560.0))
+                    .bg(rgb(colors.surface_elevated))
+                    .rounded(px(12.0))
+                    .border_1()
+                    .border_color(rgb(colors.border))
+                    .shadow_lg()
+                    .flex()
+                    .flex_col()
+                    .on_mouse_down(MouseButton::Left, |_, cx| {
+                        cx.stop_propagation();
+                    })
+                    // Header
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(16.0))
+                            .border_b_1()
+                            .border_color(rgb(colors.border))
+                            .flex()
+                            .items_center()
+                            .gap(px(8.0))
+                            .child(svg_icon(icon, IconSize::Small).text_color(rgb(colors.text_secondary)))
+                            .child(
+                                div()
+                                    .text_lg()
+                                    .font_weight(FontWeight::SEMIBOLD)
+                                    .text_color(rgb(colors.text_primary))
+                                    .child(title),
+                            ),
+                    )
+                    // What the tool call would do
+                    .child(
+                        div()
+                            .id("permission-previews")
+                            .max_h(px(360.0))
+                            .overflow_y_scroll()
+                            .px(px(20.0))
+                            .py(px(16.0))
+                            .flex()
+                            .flex_col()
+                            .gap(px(10.0))
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .child(request.title.clone()),
+                            )
+                            .children(previews)
+                            .when(queued > 0, |el| {
+                                el.child(
+                                    div()
+                                        .text_xs()
+                                        .text_color(rgb(colors.text_secondary))
+                                        .child(t_args("confirm.more_waiting", &[("count", &queued)])),
+                                )
+                            }),
+                    )
+                    // The agent's options
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(12.0))
+                            .border_t_1()
+                            .border_color(rgb(colors.border))
+                            .flex()
+                            .flex_wrap()
+                            .justify_end()
+                            .gap(px(8.0))
+                            .when(request.options.is_empty(), |el| {
+                                el.child(
+                                    div()
+                                        .id("dismiss-permission-btn")
+                                        .px(px(16.0))
+                                        .py(px(8.0))
+                                        .rounded(px(6.0))
+                                        .bg(rgb(colors.surface))
+                                        .text_sm()
+                                        .text_color(rgb(colors.text_secondary))
+                                        .cursor_pointer()
+                                        .hover(|el| el.bg(rgb(colors.border)))
+                                        .on_click(cx.listener(|this, _, cx| {
+                                            this.acp.manager.resolve_permission_request(None);
+                                            cx.notify();
+                                        }))
+                                        .child(t("common.deny")),
+                                )
+                            })
+                            .children(request.options.iter().enumerate().map(|(i, option)| {
+                                let primary = option.kind == PermissionOptionKind::AllowOnce;
+                                let option_id = option.option_id.clone();
+                                div()
+                                    .id(SharedString::from(format!("permission-option-{}", i)))
+                                    .px(px(16.0))
+                                    .py(px(8.0))
+                                    .rounded(px(6.0))
+                                    .text_sm()
+                                    .cursor_pointer()
+                                    .when(primary, |el| {
+                                        el.bg(rgb(colors.primary))
+                                            .text_color(white())
+                                            .hover(|el| el.bg(rgb(colors.primary_hover)))
+                                    })
+                                    .when(!primary, |el| {
+                                        el.bg(rgb(colors.surface))
+                                            .text_color(rgb(if option.kind.is_allow() {
+                                                colors.text_primary
+                                            } else {
+                                                colors.text_secondary
+                                            }))
+                                            .hover(|el| el.bg(rgb(colors.border)))
+                                    })
+                                    .on_click(cx.listener(move |this, _, cx| {
+                                        this.acp.manager.resolve_permission_request(Some(option_id.clone()));
+                                        cx.notify();
+                                    }))
+                                    .child(option.name.clone())
+                            })),
+                    ),
+            )
+    }
+
+    fn render_command_confirmation_dialog(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let Some(request) = self.acp.manager.current_confirmation() else {
+            return div();
+        };
+        let queued = self.acp.manager.pending_confirmations.len().saturating_sub(1);
+        let (icon, title) = match request.operation {
+            FileOperation::Execute => (IconName::Terminal, t("confirm.execute")),
+            FileOperation::Read | FileOperation::List => (IconName::File, t("confirm.read")),
+            FileOperation::Write | FileOperation::Move => (IconName::File, t("confirm.write")),
+            FileOperation::Delete => (IconName::File, t("confirm.delete")),
+        };
+
+        // Modal overlay
+        div()
+            .absolute()
+            .inset_0()
+            .flex()
+            .items_center()
+            .justify_center()
+            .bg(rgba(colors.panel_bg.with_alpha(0.9)))
+            .child(
+                // Dialog box
+                div()
+                    .w(px(480.0))
+                    .bg(rgb(colors.surface_elevated))
+                    .rounded(px(12.0))
+                    .border_1()
+                    .border_color(rgb(colors.border))
+                    .shadow_lg()
+                    .flex()
+                    .flex_col()
+                    .on_mouse_down(MouseButton::Left, |_, cx| {
+                        cx.stop_propagation();
+                    })
+                    // Header
                     .child(
-                        // Thinking header (clickable to collapse)
                         div()
-                            .id(SharedString::from(format!("thinking-header-{}", idx)))
+                            .px(px(20.0))
+                            .py(px(16.0))
+                            .border_b_1()
+                            .border_color(rgb(colors.border))
                             .flex()
                             .items_center()
                             .gap(px(8.0))
-                            .cursor_pointer()
-                            .on_click(cx.listener(move |this, _, cx| {
-                                this.toggle_thinking(idx, cx);
-                            }))
                             .child(
-                                // Lightbulb icon
+                                svg_icon(icon, IconSize::Small)
+                                    .text_color(rgb(colors.text_secondary)),
+                            )
+                            .child(
+                                div()
+                                    .text_lg()
+                                    .font_weight(FontWeight::SEMIBOLD)
+                                    .text_color(rgb(colors.text_primary))
+                                    .child(title),
+                            ),
+                    )
+                    // Command details
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(16.0))
+                            .flex()
+                            .flex_col()
+                            .gap(px(10.0))
+                            .child(
                                 div()
                                     .text_sm()
                                     .text_color(rgb(colors.text_secondary))
-                                    .child("💡"),
+                                    .child(request.reason.clone()),
+                            )
+                            .child(
+                                div()
+                                    .w_full()
+                                    .px(px(10.0))
+                                    .py(px(8.0))
+                                    .rounded(px(6.0))
+                                    .bg(rgb(colors.code_bg))
+                                    .font_family("monospace")
+                                    .text_sm()
+                                    .text_color(rgb(colors.code_text))
+                                    .child(request.command_line.clone()),
                             )
+                            .when_some(request.cwd.clone(), |el, cwd| {
+                                el.child(
+                                    div()
+                                        .text_xs()
+                                        .text_color(rgb(colors.text_secondary))
+                                        .child(t_args("common.in_dir", &[("dir", &cwd)])),
+                                )
+                            })
+                            .when(queued > 0, |el| {
+                                el.child(
+                                    div()
+                                        .text_xs()
+                                        .text_color(rgb(colors.text_secondary))
+                                        .child(t_args("confirm.more_waiting", &[("count", &queued)])),
+                                )
+                            }),
+                    )
+                    // Footer
+                    .child(
+                        div()
+                            .px(px(20.0))
+                            .py(px(12.0))
+                            .border_t_1()
+                            .border_color(rgb(colors.border))
+                            .flex()
+                            .justify_end()
+                            .gap(px(8.0))
                             .child(
                                 div()
+                                    .id("deny-command-btn")
+                                    .px(px(16.0))
+                                    .py(px(8.0))
+                                    .rounded(px(6.0))
+                                    .bg(rgb(colors.surface))
                                     .text_sm()
                                     .text_color(rgb(colors.text_secondary))
-                                    .child("Thinking"),
+                                    .cursor_pointer()
+                                    .hover(|el| el.bg(rgb(colors.border)))
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.resolve_command_confirmation(false, cx);
+                                    }))
+                                    .child(t("common.deny")),
                             )
                             .child(
-                                // Collapse indicator
                                 div()
-                                    .text_xs()
-                                    .text_color(rgb(colors.text_secondary))
-                                    .child(if is_collapsed { "▶" } else { "▼" }),
+                                    .id("allow-command-btn")
+                                    .px(px(16.0))
+                                    .py(px(8.0))
+                                    .rounded(px(6.0))
+                                    .bg(rgb(colors.primary))
+                                    .text_sm()
+                                    .text_color(white())
+                                    .cursor_pointer()
+                                    .hover(|el| el.bg(rgb(colors.primary_hover)))
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.resolve_command_confirmation(true, cx);
+                                    }))
+                                    .child(t("common.run")),
                             ),
-                    )
-                    // Thinking content with left border
-                    .when(!is_collapsed, move |el| {
-                        el.child(
-                            div()
-                                .w_full()
-                                .mt(px(8.0))
-                                .pl(px(12.0))
-                                .overflow_hidden()
-                                .border_l_2()
-                                .border_color(rgb(colors.border))
-                                .child(
-                                    div()
-                                        .w_full()
-                                        .overflow_x_hidden()
-                                        .text_sm()
-                                        .text_color(rgba(colors.text_secondary.with_alpha(0.9)))
-                                        .child(markdown),
-                                ),
-                        )
-                    })
-            }
-
-            // Agent response: Markdown (Zed renderer)
-            MessageBlock::Agent { content, .. } => {
-                let text = content
-                    .iter()
-                    .filter_map(|c| match c {
-                        ContentBlock::Text { text } => Some(text.clone()),
-                        _ => None,
-                    })
-                    .collect::<Vec<_>>()
-                    .join("");
-
-                div()
-                    .w_full()
-                    .flex_shrink_0()
-                    .overflow_hidden()
-                    .child(self.render_markdown_view(&format!("agent-{}", idx), &text, false, cx))
-            }
-
-            // System message: Muted style
-            MessageBlock::System { content, .. } => {
-                div()
-                    .w_full()
-                    .flex_shrink_0()
-                    .child(
-                        div()
-                            .text_xs()
-                            .text_color(rgb(colors.text_secondary))
-                            .child(content.clone()),
-                    )
-            }
-        }
-    }
-
-    fn render_markdown_view(
-        &mut self,
-        key: &str,
-        text: &str,
-        muted: bool,
-        cx: &mut ViewContext<Self>,
-    ) -> AnyElement {
-        let view = self.markdown_view(key, text, muted, cx);
-        div()
-            .w_full()
-            .min_w_0()
-            .overflow_x_hidden()
-            .child(view)
-            .into_any_element()
-    }
-
-    fn markdown_view(
-        &mut self,
-        key: &str,
-        text: &str,
-        muted: bool,
-        cx: &mut ViewContext<Self>,
-    ) -> View<Markdown> {
-        let cache_key = format!("{}:{}", key, if muted { "muted" } else { "normal" });
-        if let Some(view) = self.message_markdown_cache.get(&cache_key) {
-            let _ = view.update(cx, |markdown, cx| {
-                markdown.reset(text.to_string(), cx);
-            });
-            return view.clone();
-        }
-
-        let style = self.markdown_style(muted, cx);
-        let view = cx.new_view(|cx| Markdown::new(text.to_string(), style, None, cx, None));
-        self.message_markdown_cache.insert(cache_key, view.clone());
-        view
-    }
-
-    fn markdown_style(&self, muted: bool, cx: &mut ViewContext<Self>) -> MarkdownStyle {
-        let colors = &self.theme.colors;
-        let base_color = if muted {
-            rgba(colors.text_secondary.with_alpha(0.9))
-        } else {
-            rgb(colors.text_primary)
-        };
-        let code_bg = rgb(colors.code_bg);
-        let code_text = rgb(colors.code_text);
-        let link_color = rgb(colors.text_link);
-
-        let mut base_text_style = cx.text_style();
-        base_text_style.color = Hsla::from(base_color);
-        base_text_style.font_size = px(self.theme.typography.base_size).into();
-
-        MarkdownStyle {
-            base_text_style,
-            code_block: StyleRefinement {
-                background: Some(code_bg.into()),
-                padding: EdgesRefinement {
-                    top: Some(px(8.0).into()),
-                    left: Some(px(10.0).into()),
-                    right: Some(px(10.0).into()),
-                    bottom: Some(px(8.0).into()),
-                },
-                margin: EdgesRefinement {
-                    top: Some(Length::Definite(px(6.0).into())),
-                    left: Some(Length::Definite(px(0.0).into())),
-                    right: Some(Length::Definite(px(0.0).into())),
-                    bottom: Some(Length::Definite(px(6.0).into())),
-                },
-                border_color: Some(rgba(colors.border).into()),
-                border_widths: EdgesRefinement {
-                    top: Some(px(1.0).into()),
-                    left: Some(px(1.0).into()),
-                    right: Some(px(1.0).into()),
-                    bottom: Some(px(1.0).into()),
-                },
-                text: Some(TextStyleRefinement {
-                    font_family: Some("monospace".into()),
-                    color: Some(Hsla::from(code_text)),
-                    ..Default::default()
-                }),
-                ..Default::default()
-            },
-            inline_code: TextStyleRefinement {
-                font_family: Some("monospace".into()),
-                background_color: Some(Hsla::from(code_bg)),
-                color: Some(Hsla::from(code_text)),
-                ..Default::default()
-            },
-            block_quote: TextStyleRefinement {
-                color: Some(Hsla::from(rgba(colors.text_secondary))),
-                ..Default::default()
-            },
-            link: TextStyleRefinement {
-                color: Some(Hsla::from(link_color)),
-                underline: Some(UnderlineStyle {
-                    thickness: px(1.0),
-                    color: Some(Hsla::from(link_color)),
-                    wavy: false,
-                }),
-                ..Default::default()
-            },
-            rule_color: Hsla::from(rgba(colors.divider)),
-            block_quote_border_color: Hsla::from(rgba(colors.border)),
-            selection_background_color: Hsla::from(rgba(colors.selection)),
-            ..Default::default()
-        }
+                    ),
+            )
     }
+}
 
-    fn toggle_thinking(&mut self, idx: usize, cx: &mut ViewContext<Self>) {
-        if self.collapsed_thinking.contains(&idx) {
-            self.collapsed_thinking.remove(&idx);
-        } else {
-            self.collapsed_thinking.insert(idx);
+impl CocoWorkWindow {
+    fn render_search_button(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let active = self.show_search;
+
+        div()
+            .id("project-search-btn")
+            .flex_shrink_0()
+            .mx(px(8.0))
+            .mt(px(8.0))
+            .h(px(28.0))
+            .px(px(8.0))
+            .flex()
+            .items_center()
+            .gap(px(8.0))
+            .rounded(px(4.0))
+            .cursor_pointer()
+            .when(active, |el| el.bg(rgba(colors.primary.with_alpha(0.15))))
+            .when(!active, |el| el.hover(|s| s.bg(rgba(colors.hover))))
+            .on_click(cx.listener(|this, _, cx| {
+                this.toggle_project_search(cx);
+            }))
+            .child(
+                svg_icon(IconName::Search, IconSize::Small)
+                    .text_color(rgb(colors.text_secondary)),
+            )
+            .child(
+                div()
+                    .text_sm()
+                    .text_color(rgb(colors.text_primary))
+                    .child(t("sidebar.search_files")),
+            )
+    }
+
+    fn toggle_project_search(&mut self, cx: &mut ViewContext<Self>) {
+        self.show_search = !self.show_search;
+        if self.show_search {
+            self.show_dashboard = false;
+            self.show_audit = false;
+            self.show_task_board = false;
+            let focus = self.project_search_input.read(cx).focus_handle(cx);
+            cx.focus(&focus);
         }
         cx.notify();
     }
 
-    fn render_tool_call(&self, tool_call: &ToolCallState, _cx: &mut ViewContext<Self>) -> impl IntoElement {
-        let colors = &self.theme.colors;
-
-        // Status color
-        let status_color = match tool_call.status {
-            ToolCallStatus::Pending => rgb(colors.text_secondary),
-            ToolCallStatus::InProgress => rgb(colors.primary),
-            ToolCallStatus::Completed => rgb(ThemeRgba::rgb(0x4ADE80)),
-            ToolCallStatus::Failed => rgb(ThemeRgba::rgb(0xF87171)),
-            ToolCallStatus::Cancelled => rgb(colors.text_secondary),
-        };
+    fn run_project_search(&mut self, cx: &mut ViewContext<Self>) {
+        let pattern = self.project_search_input.read(cx).content().to_string();
+        let query = SearchQuery::new(pattern)
+            .with_regex(self.search_regex)
+            .with_case_sensitive(self.search_case_sensitive);
+        self.search_preview = None;
+        self.search_error = self.acp.manager.start_project_search(query).err();
+        cx.notify();
+    }
 
-        // Tool kind icon
-        let kind_icon = match tool_call.kind {
-            Some(ToolCallKind::Read) => IconName::File,
-            Some(ToolCallKind::Write) => IconName::Pencil,
-            Some(ToolCallKind::Edit) => IconName::Pencil,
-            Some(ToolCallKind::Delete) => IconName::Close,
-            Some(ToolCallKind::Execute) | Some(ToolCallKind::Bash) | Some(ToolCallKind::Terminal) => IconName::Terminal,
-            Some(ToolCallKind::Search) | Some(ToolCallKind::Grep) | Some(ToolCallKind::Glob) => IconName::Search,
-            Some(ToolCallKind::Fetch) => IconName::Web,
-            Some(ToolCallKind::Task) => IconName::CircleCheck,
-            Some(ToolCallKind::Plan) => IconName::CircleCheck,
-            Some(ToolCallKind::Think) => IconName::Chat,
-            _ => IconName::Settings,
-        };
+    /// Show the file around `found` in the preview pane
+    fn open_search_preview(&mut self, found: &TextMatch, cx: &mut ViewContext<Self>) {
+        let first_line = found.line.saturating_sub(SEARCH_PREVIEW_CONTEXT).max(1);
+        let lines = std::fs::read_to_string(self.acp.manager.get_working_dir().join(&found.path))
+            .map(|text| {
+                text.lines()
+                    .skip(first_line - 1)
+                    .take(SEARCH_PREVIEW_CONTEXT * 2 + 1)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .map_err(|e| e.to_string());
+        self.search_preview = Some(SearchPreview {
+            path: found.path.clone(),
+            line: found.line,
+            column: found.column,
+            length: found.length,
+            first_line,
+            lines,
+        });
+        cx.notify();
+    }
 
-        // Status icon based on status
-        let status_icon = match tool_call.status {
-            ToolCallStatus::Pending => IconName::Circle,
-            ToolCallStatus::InProgress => IconName::Circle,
-            ToolCallStatus::Completed => IconName::Check,
-            ToolCallStatus::Failed => IconName::Close,
-            ToolCallStatus::Cancelled => IconName::Close,
+    /// Attach every file with a match to the next prompt
+    fn attach_search_results(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(search) = &self.acp.manager.project_search else {
+            return;
         };
+        let working_dir = self.acp.manager.get_working_dir();
+        for path in search.files() {
+            let full_path = working_dir.join(path).display().to_string();
+            if !self.attached_files.contains(&full_path) {
+                self.attached_files.push(full_path);
+            }
+        }
+        self.show_search = false;
+        cx.notify();
+    }
 
-        let title = tool_call.title.as_deref().unwrap_or("Tool call");
+    /// A line of text with the match at `column` (1-based, in chars) highlighted
+    fn render_highlighted_line(&self, text: &str, column: usize, length: usize) -> Div {
+        let colors = &self.theme.colors;
+        let chars: Vec<char> = text.chars().collect();
+        let start = column.saturating_sub(1).min(chars.len());
+        let end = (start + length).min(chars.len());
+        let before: String = chars[..start].iter().collect();
+        let matched: String = chars[start..end].iter().collect();
+        let after: String = chars[end..].iter().collect();
 
         div()
-            .w_full()
-            .flex_shrink_0()
-            .px(px(12.0))
-            .py(px(6.0))
-            .rounded(px(6.0))
-            .bg(rgb(colors.surface))
-            .border_1()
-            .border_color(rgb(colors.border))
+            .flex()
+            .whitespace_nowrap()
+            .overflow_hidden()
+            .child(before)
             .child(
                 div()
-                    .flex()
-                    .items_center()
-                    .gap(px(8.0))
-                    // Status indicator (SVG icon)
-                    .child(
-                        svg_icon(status_icon, IconSize::XSmall)
-                            .text_color(status_color),
-                    )
-                    // Kind icon (SVG icon)
-                    .child(
-                        svg_icon(kind_icon, IconSize::Small)
-                            .text_color(rgb(colors.text_secondary)),
-                    )
-                    // Title
-                    .child(
-                        div()
-                            .flex_1()
-                            .text_sm()
-                            .text_color(rgb(colors.text_primary))
-                            .child(title.to_string()),
-                    )
-                    // Tool ID (dimmed)
-                    .child(
-                        div()
-                            .text_xs()
-                            .text_color(rgb(colors.text_secondary))
-                            .child(format!("#{}", &tool_call.id[..8.min(tool_call.id.len())])),
-                    ),
+                    .rounded(px(2.0))
+                    .bg(rgba(colors.warning.with_alpha(0.35)))
+                    .text_color(rgb(colors.text_primary))
+                    .child(matched),
             )
+            .child(after)
     }
 
-    fn render_input_bar(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+    /// Query bar, streamed matches grouped by file, and a preview of the selected match
+    fn render_project_search(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
         let colors = &self.theme.colors;
+        let search = self.acp.manager.project_search.as_ref();
+
+        let status = match (&self.search_error, search) {
+            (Some(error), _) => error.clone(),
+            (None, None) => t("search.idle").to_string(),
+            (None, Some(search)) if !search.done => t_args("search.searching", &[("count", &search.matches.len())]),
+            (None, Some(search)) => {
+                let mut status = t_args(
+                    "search.results",
+                    &[("count", &search.matches.len()), ("files", &search.files().len())],
+                );
+                if search.is_truncated() {
+                    status.push_str(t("search.truncated"));
+                }
+                status
+            }
+        };
+        let has_results = search.is_some_and(|s| !s.matches.is_empty());
+        let searching = search.is_some_and(|s| !s.done);
+
+        // Group the listed matches by file, keeping the order files were found in
+        let mut groups: Vec<(PathBuf, Vec<TextMatch>)> = Vec::new();
+        for found in search.iter().flat_map(|s| s.matches.iter()).take(MAX_SEARCH_ROWS) {
+            match groups.iter_mut().find(|(path, _)| *path == found.path) {
+                Some((_, matches)) => matches.push(found.clone()),
+                None => groups.push((found.path.clone(), vec![found.clone()])),
+            }
+        }
+
+        let toggle_chip = |id: &'static str, label: &'static str, on: bool| {
+            div()
+                .id(id)
+                .px(px(8.0))
+                .py(px(2.0))
+                .rounded(px(4.0))
+                .text_xs()
+                .font_family("monospace")
+                .cursor_pointer()
+                .when(on, |el| {
+                    el.bg(rgba(colors.primary.with_alpha(0.2)))
+                        .text_color(rgb(colors.text_primary))
+                })
+                .when(!on, |el| {
+                    el.text_color(rgb(colors.text_secondary))
+                        .hover(|el| el.bg(rgba(colors.hover)))
+                })
+                .child(label)
+        };
 
         div()
-            .id("input-bar")
-            .w_full()
-            .flex_shrink_0()  // Never shrink, keep natural height
-            .p(px(8.0))
+            .flex_1()
+            .min_h_0()
             .flex()
             .flex_col()
-            .gap(px(8.0))
-            .bg(rgb(colors.panel_bg))
-            .border_t_1()
-            .border_color(rgb(colors.border))
-            // Handle Enter key for sending
-            .on_key_down(cx.listener(|this, event: &KeyDownEvent, cx| {
-                if event.keystroke.key == "enter" && !event.keystroke.modifiers.shift {
-                    this.handle_send_message(cx);
-                }
-            }))
-            // Editor container (like Zed's message editor)
+            // Query bar
             .child(
                 div()
                     .w_full()
-                    .rounded(px(8.0))
-                    .bg(rgb(colors.surface))
-                    .border_1()
-                    .border_color(rgb(colors.border_subtle))
+                    .h(px(40.0))
+                    .flex_shrink_0()
+                    .px(px(16.0))
                     .flex()
-                    .flex_col()
-                    // Text input area - use the TextInput view
+                    .items_center()
+                    .gap(px(6.0))
+                    .border_b_1()
+                    .border_color(rgb(colors.border))
+                    .on_key_down(cx.listener(|this, event: &KeyDownEvent, cx| {
+                        if event.keystroke.key == "enter" {
+                            this.run_project_search(cx);
+                        }
+                    }))
                     .child(
                         div()
-                            .w_full()
-                            .min_h(px(80.0))
-                            .max_h(px(200.0))
-                            .p(px(12.0))
-                            .overflow_hidden()
-                            .child(self.message_input.clone()),
+                            .flex_1()
+                            .px(px(8.0))
+                            .py(px(4.0))
+                            .rounded(px(6.0))
+                            .bg(rgb(colors.surface))
+                            .border_1()
+                            .border_color(rgb(colors.border_subtle))
+                            .text_sm()
+                            .child(self.project_search_input.clone()),
+                    )
+                    .child(
+                        toggle_chip("search-regex-btn", ".*", self.search_regex).on_click(cx.listener(|this, _, cx| {
+                            this.search_regex = !this.search_regex;
+                            cx.notify();
+                        })),
+                    )
+                    .child(
+                        toggle_chip("search-case-btn", "Aa", self.search_case_sensitive).on_click(cx.listener(
+                            |this, _, cx| {
+                                this.search_case_sensitive = !this.search_case_sensitive;
+                                cx.notify();
+                            },
+                        )),
                     )
-                    // Bottom controls inside the editor box
                     .child(
                         div()
-                            .w_full()
-                            .px(px(8.0))
-                            .py(px(6.0))
-                            .flex()
-                            .items_center()
-                            .justify_between()
-                            .border_t_1()
-                            .border_color(rgb(colors.border_subtle))
-                            // Left: Context button
-                            .child(self.render_context_button(cx))
-                            // Right: Send button only (agent selection moved to new thread dialog)
-                            .child(
-                                div()
-                                    .flex()
-                                    .items_center()
-                                    .gap(px(6.0))
-                                    .child(self.render_send_button(cx)),
-                            ),
+                            .id("run-search-btn")
+                            .px(px(10.0))
+                            .py(px(4.0))
+                            .rounded(px(6.0))
+                            .bg(rgb(colors.primary))
+                            .text_xs()
+                            .text_color(white())
+                            .cursor_pointer()
+                            .hover(|el| el.bg(rgb(colors.primary_hover)))
+                            .on_click(cx.listener(|this, _, cx| {
+                                if this.acp.manager.project_search.as_ref().is_some_and(|s| !s.done) {
+                                    this.acp.manager.cancel_project_search();
+                                    cx.notify();
+                                } else {
+                                    this.run_project_search(cx);
+                                }
+                            }))
+                            .child(if searching { t("common.stop") } else { t("common.search") }),
                     ),
             )
-    }
-
-    fn render_context_button(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
-        let colors = &self.theme.colors;
-        let workspace_display = self.workspace_path.as_ref().map(|p| {
-            // Show only the last folder name
-            std::path::Path::new(p)
-                .file_name()
-                .map(|n| n.to_string_lossy().to_string())
-                .unwrap_or_else(|| p.clone())
-        });
-
-        div()
-            .flex()
-            .items_center()
-            .gap(px(4.0))
-            // Folder button (workspace selector)
+            // Status and actions
             .child(
                 div()
-                    .id("folder-btn")
-                    .h(px(26.0))
-                    .px(px(8.0))
+                    .w_full()
+                    .flex_shrink_0()
+                    .px(px(16.0))
+                    .py(px(6.0))
                     .flex()
                     .items_center()
-                    .gap(px(4.0))
-                    .rounded(px(4.0))
-                    .cursor_pointer()
-                    .hover(|s| s.bg(rgba(colors.hover)))
-                    .on_click(cx.listener(|this, _, cx| {
-                        this.select_workspace(cx);
-                    }))
+                    .gap(px(8.0))
+                    .border_b_1()
+                    .border_color(rgb(colors.border))
                     .child(
-                        svg_icon(IconName::Folder, IconSize::Small)
-                            .text_color(rgb(colors.text_secondary)),
+                        div()
+                            .flex_1()
+                            .text_xs()
+                            .text_color(if self.search_error.is_some() {
+                                rgb(colors.error)
+                            } else {
+                                rgb(colors.text_secondary)
+                            })
+                            .child(status),
                     )
-                    .when_some(workspace_display.clone(), |el, name| {
+                    .when(has_results, |el| {
                         el.child(
                             div()
+                                .id("attach-search-results-btn")
+                                .px(px(10.0))
+                                .py(px(4.0))
+                                .rounded(px(6.0))
+                                .bg(rgb(colors.surface))
                                 .text_xs()
-                                .text_color(rgb(colors.text_secondary))
-                                .max_w(px(120.0))
-                                .text_ellipsis()
-                                .child(name),
+                                .text_color(rgb(colors.text_primary))
+                                .cursor_pointer()
+                                .hover(|el| el.bg(rgb(colors.border)))
+                                .on_click(cx.listener(|this, _, cx| {
+                                    this.attach_search_results(cx);
+                                }))
+                                .child(t("search.add_to_context")),
                         )
                     }),
             )
-            // + button (add attachment)
             .child(
                 div()
-                    .id("add-btn")
-                    .h(px(26.0))
-                    .px(px(6.0))
+                    .flex_1()
+                    .min_h_0()
                     .flex()
-                    .items_center()
-                    .rounded(px(4.0))
-                    .cursor_pointer()
-                    .hover(|s| s.bg(rgba(colors.hover)))
-                    .on_click(cx.listener(|this, _, cx| {
-                        this.add_attachment(cx);
-                    }))
+                    // Matches grouped by file
                     .child(
-                        svg_icon(IconName::Plus, IconSize::Small)
-                            .text_color(rgb(colors.text_secondary)),
-                    ),
+                        div()
+                            .id("search-results")
+                            .flex_1()
+                            .min_w_0()
+                            .h_full()
+                            .overflow_y_scroll()
+                            .py(px(4.0))
+                            .children(groups.into_iter().enumerate().map(|(group_idx, (path, matches))| {
+                                div()
+                                    .flex()
+                                    .flex_col()
+                                    .child(
+                                        div()
+                                            .px(px(16.0))
+                                            .py(px(4.0))
+                                            .flex()
+                                            .items_center()
+                                            .gap(px(6.0))
+                                            .child(
+                                                svg_icon(IconName::File, IconSize::XSmall)
+                                                    .text_color(rgb(colors.text_secondary)),
+                                            )
+                                            .child(
+                                                div()
+                                                    .text_xs()
+                                                    .font_weight(FontWeight::MEDIUM)
+                                                    .text_color(rgb(colors.text_primary))
+                                                    .child(path.display().to_string()),
+                                            ),
+                                    )
+                                    .children(matches.into_iter().enumerate().map(|(i, found)| {
+                                        let selected = self.search_preview.as_ref().is_some_and(|p| {
+                                            p.path == found.path && p.line == found.line
+                                        });
+                                        let line = self.render_highlighted_line(
+                                            found.text.trim_end(),
+                                            found.column,
+                                            found.length,
+                                        );
+                                        div()
+                                            .id(SharedString::from(format!("search-match-{}-{}", group_idx, i)))
+                                            .pl(px(32.0))
+                                            .pr(px(16.0))
+                                            .py(px(2.0))
+                                            .flex()
+                                            .gap(px(8.0))
+                                            .text_xs()
+                                            .font_family("monospace")
+                                            .cursor_pointer()
+                                            .when(selected, |el| el.bg(rgba(colors.primary.with_alpha(0.15))))
+                                            .when(!selected, |el| el.hover(|s| s.bg(rgba(colors.hover))))
+                                            .child(
+                                                div()
+                                                    .w(px(40.0))
+                                                    .flex_shrink_0()
+                                                    .text_color(rgb(colors.text_secondary))
+                                                    .child(found.line.to_string()),
+                                            )
+                                            .child(line.flex_1().min_w_0().text_color(rgb(colors.text_secondary)))
+                                            .on_click(cx.listener(move |this, _, cx| {
+                                                this.open_search_preview(&found, cx);
+                                            }))
+                                    }))
+                            })),
+                    )
+                    // Preview of the selected match
+                    .when_some(self.search_preview.as_ref(), |el, preview| {
+                        el.child(self.render_search_preview(preview, cx))
+                    }),
             )
-            // Show attached files as chips
-            .children(self.attached_files.iter().map(|file| {
-                let file_name = file.clone();
-                let display_name = std::path::Path::new(file)
-                    .file_name()
-                    .map(|n| n.to_string_lossy().to_string())
-                    .unwrap_or_else(|| file.clone());
+    }
+
+    fn render_search_preview(&self, preview: &SearchPreview, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
 
+        div()
+            .id("search-preview")
+            .w(relative(0.5))
+            .h_full()
+            .flex_shrink_0()
+            .overflow_y_scroll()
+            .border_l_1()
+            .border_color(rgb(colors.border))
+            .bg(rgb(colors.surface))
+            .child({
+                let path = preview.path.clone();
+                let line = preview.line;
                 div()
-                    .id(SharedString::from(format!("attach-{}", file)))
-                    .h(px(22.0))
-                    .px(px(6.0))
+                    .px(px(12.0))
+                    .py(px(6.0))
                     .flex()
                     .items_center()
-                    .gap(px(4.0))
-                    .rounded(px(4.0))
-                    .bg(rgba(colors.primary.with_alpha(0.2)))
-                    .child(
-                        div()
-                            .text_xs()
-                            .text_color(rgb(colors.text_primary))
-                            .max_w(px(100.0))
-                            .text_ellipsis()
-                            .child(display_name),
-                    )
+                    .justify_between()
+                    .border_b_1()
+                    .border_color(rgb(colors.border))
+                    .text_xs()
+                    .text_color(rgb(colors.text_secondary))
+                    .child(format!("{}:{}", preview.path.display(), preview.line))
                     .child(
                         div()
-                            .id(SharedString::from(format!("remove-{}", file)))
-                            .text_xs()
-                            .text_color(rgb(colors.text_secondary))
+                            .id("open-search-file-btn")
                             .cursor_pointer()
-                            .hover(|s| s.text_color(rgb(colors.error)))
+                            .hover(|s| s.text_color(rgb(colors.primary)))
                             .on_click(cx.listener(move |this, _, cx| {
-                                this.remove_attachment(&file_name, cx);
+                                this.acp.manager.open_file_preview_at(&path, Some(line));
+                                cx.notify();
                             }))
-                            .child("×"),
+                            .child(t("search.open_file")),
                     )
-            }))
-    }
-
-    fn render_send_button(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
-        let colors = &self.theme.colors;
-        let has_text = !self.message_input.read(cx).content().is_empty();
-
-        div()
-            .id("send-button")
-            .h(px(26.0))
-            .w(px(26.0))
-            .flex()
-            .items_center()
-            .justify_center()
-            .rounded(px(4.0))
-            .when(has_text, |el| {
-                el.bg(rgb(colors.primary))
-                    .cursor_pointer()
-                    .hover(|s| s.bg(rgb(colors.primary_hover)))
             })
-            .when(!has_text, |el| {
-                el.bg(rgb(colors.surface))
-                    .cursor_default()
+            .map(|el| match &preview.lines {
+                Err(error) => el.child(
+                    div()
+                        .p(px(12.0))
+                        .text_xs()
+                        .text_color(rgb(colors.error))
+                        .child(t_args("search.read_failed", &[("error", &error)])),
+                ),
+                Ok(lines) => el.child(
+                    div()
+                        .py(px(4.0))
+                        .text_xs()
+                        .font_family("monospace")
+                        .children(lines.iter().enumerate().map(|(i, text)| {
+                            let number = preview.first_line + i;
+                            let is_match = number == preview.line;
+                            div()
+                                .px(px(12.0))
+                                .flex()
+                                .gap(px(8.0))
+                                .when(is_match, |el| el.bg(rgba(colors.warning.with_alpha(0.12))))
+                                .child(
+                                    div()
+                                        .w(px(40.0))
+                                        .flex_shrink_0()
+                                        .text_color(rgb(colors.text_secondary))
+                                        .child(number.to_string()),
+                                )
+                                .child(if is_match {
+                                    self.render_highlighted_line(text, preview.column, preview.length)
+                                        .text_color(rgb(colors.text_primary))
+                                } else {
+                                    div()
+                                        .whitespace_nowrap()
+                                        .overflow_hidden()
+                                        .text_color(rgb(colors.text_primary))
+                                        .child(text.clone())
+                                })
+                        })),
+                ),
             })
-            .on_click(cx.listener(|this, _, cx| {
-                this.handle_send_message(cx);
-            }))
-            .child(
-                svg_icon(IconName::ArrowUp, IconSize::Small)
-                    .text_color(if has_text { white() } else { rgb(colors.text_secondary) }),
-            )
     }
+}
 
-    // ========================================================================
-    // Context Panel
-    // ========================================================================
+impl CocoWorkWindow {
+    /// Highlight the previewed file once it has loaded
+    fn sync_preview_lines(&mut self) {
+        match &self.acp.manager.file_preview {
+            Some(FilePreviewState {
+                path,
+                content: Some(Ok(file)),
+                ..
+            }) => {
+                if self.preview_lines.is_none() {
+                    self.preview_lines = Some(highlight_lines(path, &file.content));
+                }
+            }
+            _ => self.preview_lines = None,
+        }
+    }
 
-    fn render_context_panel(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+    fn token_color(&self, kind: TokenKind) -> Rgba {
         let colors = &self.theme.colors;
-
-        div()
-            .id("context-panel")
-            .w(px(self.context_panel_width))
-            .h_full()
-            .flex_shrink_0()
-            .overflow_hidden()
-            .flex()
-            .flex_col()
-            .bg(rgb(colors.sidebar_bg))  // Same as left sidebar
-            .border_l_1()                 // Left border for separation
-            .border_color(rgb(colors.border))
-            .child(self.render_progress_section(cx))
-            .child(self.render_collapsible_section("Artifacts", cx))
-            .child(self.render_collapsible_section("Context", cx))
+        match kind {
+            TokenKind::Plain => rgb(colors.code_text),
+            TokenKind::Keyword => rgb(colors.primary_hover),
+            TokenKind::String => rgb(colors.success),
+            TokenKind::Comment => rgb(colors.text_secondary),
+            TokenKind::Number => rgb(colors.warning),
+        }
     }
 
-    /// Render the Progress section showing task/plan completion
-    fn render_progress_section(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+    /// Read-only view of a file with line numbers and syntax highlighting
+    fn render_file_preview(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
         let colors = &self.theme.colors;
-        let is_expanded = self.expanded_sections.contains(&"Progress".to_string());
-        let arrow_icon = if is_expanded { IconName::ChevronDown } else { IconName::ChevronRight };
-
-        // Get real plan data from ACP session
-        let plan_entries: Vec<PlanEntry> = self
-            .acp
-            .active_session()
-            .and_then(|s| s.current_task.as_ref())
-            .map(|t| t.plan.clone())
-            .unwrap_or_default();
-
-        let completed_count = plan_entries
-            .iter()
-            .filter(|e| matches!(e.status, PlanStatus::Completed))
-            .count();
-        let total_count = plan_entries.len();
-        let has_plan = !plan_entries.is_empty();
+        let Some(preview) = self.acp.manager.file_preview.as_ref() else {
+            return div();
+        };
+        let title = preview
+            .path
+            .strip_prefix(self.acp.manager.get_working_dir())
+            .unwrap_or(&preview.path)
+            .display()
+            .to_string();
+        let line_count = self.preview_lines.as_ref().map_or(0, Vec::len);
+        let number_width = px(line_count.max(1).to_string().len() as f32 * 8.0 + 16.0);
 
         div()
-            .w_full()
+            .absolute()
+            .inset_0()
             .flex()
-            .flex_col()
-            .border_b_1()
-            .border_color(rgb(colors.border))
+            .items_center()
+            .justify_center()
+            .bg(rgba(colors.panel_bg.with_alpha(0.9)))
             .child(
                 div()
-                    .id("section-progress")
-                    .w_full()
-                    .h(px(40.0))
-                    .px(px(16.0))
+                    .w(px(760.0))
+                    .h(px(560.0))
                     .flex()
-                    .items_center()
-                    .justify_between()
-                    .cursor_pointer()
-                    .hover(|s| s.bg(rgba(colors.hover)))
-                    .on_click(cx.listener(|this, _, cx| {
-                        this.toggle_section("Progress", cx);
-                    }))
+                    .flex_col()
+                    .bg(rgb(colors.surface_elevated))
+                    .rounded(px(12.0))
+                    .border_1()
+                    .border_color(rgb(colors.border))
+                    .shadow_lg()
+                    .overflow_hidden()
+                    // Header
                     .child(
                         div()
+                            .px(px(16.0))
+                            .py(px(10.0))
                             .flex()
                             .items_center()
                             .gap(px(8.0))
+                            .border_b_1()
+                            .border_color(rgb(colors.border))
                             .child(
-                                svg_icon(arrow_icon, IconSize::XSmall)
+                                svg_icon(IconName::File, IconSize::Small)
                                     .text_color(rgb(colors.text_secondary)),
                             )
                             .child(
                                 div()
+                                    .flex_1()
+                                    .min_w_0()
                                     .text_sm()
                                     .font_weight(FontWeight::MEDIUM)
                                     .text_color(rgb(colors.text_primary))
-                                    .child("Progress"),
-                            ),
-                    )
-                    // Progress indicator
-                    .when(has_plan, |el| {
-                        el.child(
-                            div()
-                                .text_xs()
-                                .text_color(rgb(colors.text_secondary))
-                                .child(format!("{}/{}", completed_count, total_count)),
-                        )
-                    }),
-            )
-            .when(is_expanded, |el| {
-                el.child(
-                    div()
-                        .w_full()
-                        .px(px(16.0))
-                        .py(px(12.0))
-                        .flex()
-                        .flex_col()
-                        .gap(px(8.0))
-                        // Show progress bar only if there's a plan
-                        .when(has_plan, |el| {
-                            let progress_pct = if total_count > 0 {
-                                (completed_count as f32 / total_count as f32) * 100.0
-                            } else {
-                                0.0
-                            };
-                            el.child(
-                                div()
-                                    .w_full()
-                                    .h(px(4.0))
-                                    .rounded(px(2.0))
-                                    .bg(rgb(colors.surface))
-                                    .child(
-                                        div()
-                                            .h_full()
-                                            .w(px(progress_pct * 2.48)) // 248px max width
-                                            .rounded(px(2.0))
-                                            .bg(rgb(colors.primary)),
-                                    ),
-                            )
-                        })
-                        // Plan items or empty state
-                        .when(has_plan, |el| {
-                            el.child(
-                                div()
-                                    .flex()
-                                    .flex_col()
-                                    .gap(px(4.0))
-                                    .children(plan_entries.iter().map(|entry| {
-                                        self.render_plan_item(&entry.content, &entry.status)
-                                    })),
+                                    .overflow_hidden()
+                                    .whitespace_nowrap()
+                                    .text_ellipsis()
+                                    .child(title),
                             )
-                        })
-                        .when(!has_plan, |el| {
-                            el.child(
+                            .when_some(preview.content.as_ref().and_then(|c| c.as_ref().ok()), |el, file| {
+                                el.child(
+                                    div()
+                                        .text_xs()
+                                        .text_color(rgb(colors.text_secondary))
+                                        .child(t_args("search.file_info", &[("lines", &line_count), ("bytes", &file.size)])),
+                                )
+                            })
+                            .child(
                                 div()
-                                    .py(px(8.0))
-                                    .flex()
-                                    .items_center()
-                                    .justify_center()
+                                    .id("close-file-preview-btn")
+                                    .p(px(4.0))
+                                    .rounded(px(4.0))
+                                    .cursor_pointer()
+                                    .hover(|s| s.bg(rgba(colors.hover)))
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        this.acp.manager.close_file_preview();
+                                        cx.notify();
+                                    }))
                                     .child(
-                                        div()
-                                            .text_sm()
-                                            .text_color(rgb(colors.text_secondary))
-                                            .child("No active plan"),
+                                        svg_icon(IconName::Close, IconSize::Small)
+                                            .text_color(rgb(colors.text_secondary)),
                                     ),
-                            )
-                        }),
-                )
-            })
+                            ),
+                    )
+                    // Body
+                    .child(
+                        div()
+                            .id("file-preview-body")
+                            .flex_1()
+                            .min_h_0()
+                            .overflow_y_scroll()
+                            .bg(rgb(colors.code_bg))
+                            .map(|el| match (&preview.content, &self.preview_lines) {
+                                (None, _) => el.child(
+                                    div()
+                                        .p(px(16.0))
+                                        .text_sm()
+                                        .text_color(rgb(colors.text_secondary))
+                                        .child(t("common.loading")),
+                                ),
+                                (Some(Err(error)), _) => el.child(
+                                    div()
+                                        .p(px(16.0))
+                                        .text_sm()
+                                        .text_color(rgb(colors.error))
+                                        .child(t_args("search.open_failed", &[("error", &error)])),
+                                ),
+                                (Some(Ok(_)), lines) => el
+                                    .py(px(8.0))
+                                    .text_xs()
+                                    .font_family("monospace")
+                                    .children(lines.iter().flatten().take(MAX_PREVIEW_LINES).enumerate().map(
+                                        |(i, tokens)| {
+                                            div()
+                                                .flex()
+                                                .when(preview.line == Some(i + 1), |el| {
+                                                    el.bg(rgba(colors.warning.with_alpha(0.12)))
+                                                })
+                                                .child(
+                                                    div()
+                                                        .w(number_width)
+                                                        .flex_shrink_0()
+                                                        .pr(px(12.0))
+                                                        .flex()
+                                                        .justify_end()
+                                                        .text_color(rgb(colors.text_secondary))
+                                                        .child((i + 1).to_string()),
+                                                )
+                                                .child(
+                                                    div().flex().whitespace_nowrap().children(tokens.iter().map(
+                                                        |token| {
+                                                            div()
+                                                                .text_color(self.token_color(token.kind))
+                                                                .child(token.text.clone())
+                                                        },
+                                                    )),
+                                                )
+                                        },
+                                    ))
+                                    .when(line_count > MAX_PREVIEW_LINES, |el| {
+                                        el.child(
+                                            div()
+                                                .px(px(16.0))
+                                                .py(px(8.0))
+                                                .text_color(rgb(colors.text_secondary))
+                                                .child(t_args(
+                                                    "search.preview_truncated",
+                                                    &[("shown", &MAX_PREVIEW_LINES), ("total", &line_count)],
+                                                )),
+                                        )
+                                    }),
+                            }),
+                    ),
+            )
+    }
+}
+
+impl CocoWorkWindow {
+    /// Whether the main panel shows a view other than the open thread
+    fn showing_other_view(&self) -> bool {
+        self.show_dashboard || self.show_search || self.show_audit || self.show_task_board
     }
 
-    /// Render a single plan item
-    fn render_plan_item(&self, title: &str, status: &PlanStatus) -> impl IntoElement {
+    fn render_task_board_button(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
         let colors = &self.theme.colors;
-
-        let (status_icon, icon_color) = match status {
-            PlanStatus::Completed => (IconName::Check, colors.success),
-            PlanStatus::InProgress => (IconName::Circle, colors.primary),
-            PlanStatus::Pending => (IconName::Circle, colors.text_secondary),
-            PlanStatus::Skipped => (IconName::Close, colors.text_secondary),
-        };
+        let active = self.show_task_board;
 
         div()
-            .w_full()
-            .py(px(4.0))
+            .id("task-board-btn")
+            .flex_shrink_0()
+            .mx(px(8.0))
+            .mb(px(8.0))
+            .h(px(28.0))
+            .px(px(8.0))
             .flex()
             .items_center()
             .gap(px(8.0))
+            .rounded(px(4.0))
+            .cursor_pointer()
+            .when(active, |el| el.bg(rgba(colors.primary.with_alpha(0.15))))
+            .when(!active, |el| el.hover(|s| s.bg(rgba(colors.hover))))
+            .on_click(cx.listener(|this, _, cx| {
+                this.toggle_task_board(cx);
+            }))
             .child(
-                svg_icon(status_icon, IconSize::XSmall)
-                    .text_color(rgb(icon_color)),
+                svg_icon(IconName::Agent, IconSize::Small)
+                    .text_color(rgb(colors.text_secondary)),
             )
             .child(
                 div()
-                    .flex_1()
-                    .text_xs()
-                    .text_color(match status {
-                        PlanStatus::Completed => rgb(colors.text_secondary),
-                        PlanStatus::InProgress => rgb(colors.text_primary),
-                        PlanStatus::Pending => rgb(colors.text_secondary),
-                        PlanStatus::Skipped => rgb(colors.text_secondary),
-                    })
-                    .child(title.to_string()),
+                    .text_sm()
+                    .text_color(rgb(colors.text_primary))
+                    .child(t("sidebar.task_board")),
             )
     }
 
-    fn render_collapsible_section(
-        &self,
-        title: &str,
-        cx: &mut ViewContext<Self>,
-    ) -> impl IntoElement {
+    fn toggle_task_board(&mut self, cx: &mut ViewContext<Self>) {
+        self.show_task_board = !self.show_task_board;
+        if self.show_task_board {
+            self.show_dashboard = false;
+            self.show_search = false;
+            self.show_audit = false;
+        }
+        cx.notify();
+    }
+
+    /// Open threads' tasks in columns by how far along they are
+    fn render_task_board(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
         let colors = &self.theme.colors;
-        let is_expanded = self.expanded_sections.contains(&title.to_string());
-        let arrow_icon = if is_expanded { IconName::ChevronDown } else { IconName::ChevronRight };
-        let section_name = title.to_string();
+        let cards = self.acp.task_board();
+        let agents = self.acp.available_agents();
+        let now = chrono::Utc::now();
 
         div()
-            .w_full()
+            .flex_1()
+            .min_h_0()
             .flex()
             .flex_col()
-            .border_b_1()
-            .border_color(rgb(colors.border))
             .child(
                 div()
-                    .id(SharedString::from(format!("section-{}", title.to_lowercase())))
                     .w_full()
                     .h(px(40.0))
+                    .flex_shrink_0()
                     .px(px(16.0))
                     .flex()
                     .items_center()
-                    .gap(px(8.0))
-                    .cursor_pointer()
-                    .hover(|s| s.bg(rgba(colors.hover)))
-                    .on_click(cx.listener(move |this, _, cx| {
-                        this.toggle_section(&section_name, cx);
-                    }))
-                    .child(
-                        svg_icon(arrow_icon, IconSize::XSmall)
-                            .text_color(rgb(colors.text_secondary)),
-                    )
+                    .border_b_1()
+                    .border_color(rgb(colors.border))
                     .child(
                         div()
                             .text_sm()
                             .font_weight(FontWeight::MEDIUM)
                             .text_color(rgb(colors.text_primary))
-                            .child(title.to_string()),
+                            .child(t("task_board.title")),
                     ),
             )
-            .when(is_expanded, |el| {
-                el.child(
-                    div()
-                        .w_full()
-                        .min_h(px(80.0))
-                        .px(px(16.0))
-                        .py(px(12.0))
-                        .child(
-                            div()
-                                .text_sm()
-                                .text_color(rgb(colors.text_secondary))
-                                .child(self.render_section_content(title)),
-                        ),
-                )
-            })
-    }
-
-    fn render_section_content(&self, section: &str) -> String {
-        match section {
-            "Artifacts" => "No artifacts yet".to_string(),
-            "Context" => "No context added".to_string(),
-            _ => "".to_string(),
-        }
-    }
-}
+            .child(
+                div()
+                    .flex_1()
+                    .min_h_0()
+                    .p(px(12.0))
+                    .flex()
+                    .gap(px(12.0))
+                    .children(TaskColumn::ALL.into_iter().map(|column| {
+                        let (label, accent) = match column {
+                            TaskColumn::Planning => (t("task_board.planning"), colors.text_secondary),
+                            TaskColumn::InProgress => (t("task_board.in_progress"), colors.primary),
+                            TaskColumn::Completed => (t("task_board.completed"), colors.success),
+                            TaskColumn::Failed => (t("task_board.failed"), colors.error),
+                        };
+                        let column_cards: Vec<&TaskCard> = cards.iter().filter(|card| card.column == column).collect();
 
-// ============================================================================
-// Render Implementation
-// ============================================================================
+                        div()
+                            .flex_1()
+                            .min_w_0()
+                            .h_full()
+                            .rounded(px(8.0))
+                            .bg(rgb(colors.surface))
+                            .flex()
+                            .flex_col()
+                            .child(
+                                div()
+                                    .px(px(12.0))
+                                    .py(px(8.0))
+                                    .flex()
+                                    .items_center()
+                                    .gap(px(6.0))
+                                    .child(div().size(px(8.0)).rounded_full().bg(rgb(accent)))
+                                    .child(
+                                        div()
+                                            .flex_1()
+                                            .text_xs()
+                                            .font_weight(FontWeight::SEMIBOLD)
+                                            .text_color(rgb(colors.text_secondary))
+                                            .child(label),
+                                    )
+                                    .child(
+                                        div()
+                                            .text_xs()
+                                            .text_color(rgb(colors.text_secondary))
+                                            .child(column_cards.len().to_string()),
+                                    ),
+                            )
+                            .child(
+                                div()
+                                    .id(SharedString::from(format!("task-column-{:?}", column)))
+                                    .flex_1()
+                                    .min_h_0()
+                                    .overflow_y_scroll()
+                                    .px(px(8.0))
+                                    .pb(px(8.0))
+                                    .flex()
+                                    .flex_col()
+                                    .gap(px(8.0))
+                                    .when(column_cards.is_empty(), |el| {
+                                        el.child(
+                                            div()
+                                                .py(px(12.0))
+                                                .text_xs()
+                                                .text_center()
+                                                .text_color(rgb(colors.text_secondary))
+                                                .child(t("task_board.empty")),
+                                        )
+                                    })
+                                    .children(column_cards.into_iter().map(|card| {
+                                        let title = self
+                                            .threads
+                                            .iter()
+                                            .find(|thread| thread.id == card.session_id)
+                                            .map(|thread| thread.name.clone())
+                                            .unwrap_or_else(|| card.session_id.clone());
+                                        let agent = agents
+                                            .iter()
+                                            .find(|agent| agent.id == card.agent_id)
+                                            .map(|agent| agent.name.clone())
+                                            .unwrap_or_else(|| card.agent_id.clone());
+                                        let folder = card
+                                            .working_dir
+                                            .file_name()
+                                            .map(|name| name.to_string_lossy().to_string())
+                                            .unwrap_or_else(|| card.working_dir.display().to_string());
+                                        let session_id = card.session_id.clone();
 
-impl FocusableView for CocoWorkWindow {
-    fn focus_handle(&self, _cx: &AppContext) -> FocusHandle {
-        self.focus_handle.clone()
+                                        div()
+                                            .id(SharedString::from(format!("task-card-{}", card.session_id)))
+                                            .p(px(10.0))
+                                            .rounded(px(6.0))
+                                            .bg(rgb(colors.panel_bg))
+                                            .border_1()
+                                            .border_color(rgb(colors.border_subtle))
+                                            .border_l_2()
+                                            .border_color(rgb(accent))
+                                            .cursor_pointer()
+                                            .hover(|s| s.bg(rgba(colors.hover)))
+                                            .on_click(cx.listener(move |this, _, cx| {
+                                                if let Some(idx) =
+                                                    this.threads.iter().position(|thread| thread.id == session_id)
+                                                {
+                                                    this.select_thread(idx, cx);
+                                                }
+                                            }))
+                                            .flex()
+                                            .flex_col()
+                                            .gap(px(4.0))
+                                            .child(
+                                                div()
+                                                    .text_sm()
+                                                    .text_color(rgb(colors.text_primary))
+                                                    .text_ellipsis()
+                                                    .child(title),
+                                            )
+                                            .child(
+                                                div()
+                                                    .flex()
+                                                    .items_center()
+                                                    .gap(px(4.0))
+                                                    .text_xs()
+                                                    .text_color(rgb(colors.text_secondary))
+                                                    .child(
+                                                        svg_icon(IconName::Folder, IconSize::XSmall)
+                                                            .text_color(rgb(colors.text_secondary)),
+                                                    )
+                                                    .child(div().min_w_0().text_ellipsis().child(folder))
+                                                    .child(format!("· {}", agent)),
+                                            )
+                                            .child(
+                                                div()
+                                                    .flex()
+                                                    .justify_between()
+                                                    .text_xs()
+                                                    .text_color(rgb(colors.text_secondary))
+                                                    .child(if card.plan_total > 0 {
+                                                        t_args(
+                                                            "task_board.steps",
+                                                            &[("done", &card.plan_done), ("total", &card.plan_total)],
+                                                        )
+                                                    } else {
+                                                        String::new()
+                                                    })
+                                                    .child(format_relative(card.updated_at, now)),
+                                            )
+                                    })),
+                            )
+                    })),
+            )
     }
-}
 
-impl Render for CocoWorkWindow {
-    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+    fn render_audit_button(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
         let colors = &self.theme.colors;
+        let active = self.show_audit;
 
         div()
-            .id("cocowork-window")
-            .key_context("CocoWorkWindow")
-            .track_focus(&self.focus_handle)
-            .size_full()
+            .id("audit-btn")
+            .flex_shrink_0()
+            .mx(px(8.0))
+            .mb(px(8.0))
+            .h(px(28.0))
+            .px(px(8.0))
             .flex()
-            .flex_col()
-            .bg(rgb(colors.panel_bg))
-            .text_color(rgb(colors.text_primary))
-            .on_mouse_down(MouseButton::Left, cx.listener(|this, _, cx| {
-                this.close_menus(cx);
-            }))
-            .on_mouse_move(cx.listener(|this, event: &MouseMoveEvent, cx| {
-                this.resize_sidebar(event, cx);
-                this.resize_context_panel(event, cx);
-            }))
-            .on_mouse_up(MouseButton::Left, cx.listener(|this, event: &MouseUpEvent, cx| {
-                this.stop_resizing_sidebar(event, cx);
-                this.stop_resizing_context_panel(event, cx);
-            }))
-            .on_mouse_up_out(MouseButton::Left, cx.listener(|this, event: &MouseUpEvent, cx| {
-                this.stop_resizing_sidebar(event, cx);
-                this.stop_resizing_context_panel(event, cx);
-            }))
-            .on_key_down(cx.listener(|this, event: &KeyDownEvent, cx| {
-                if event.keystroke.key == "escape" {
-                    this.close_menus(cx);
-                }
+            .items_center()
+            .gap(px(8.0))
+            .rounded(px(4.0))
+            .cursor_pointer()
+            .when(active, |el| el.bg(rgba(colors.primary.with_alpha(0.15))))
+            .when(!active, |el| el.hover(|s| s.bg(rgba(colors.hover))))
+            .on_click(cx.listener(|this, _, cx| {
+                this.toggle_audit_log(cx);
             }))
-            // Top bar
-            .child(self.render_top_bar(cx))
-            // Main content (three panels)
+            .child(
+                svg_icon(IconName::CircleCheck, IconSize::Small)
+                    .text_color(rgb(colors.text_secondary)),
+            )
             .child(
                 div()
-                    .flex_1()
-                    .min_h_0()  // Critical: Allow shrinking in flex column for child scrolling to work
-                    .flex()
-                    .flex_row()
-                    .overflow_hidden()
-                    .child(self.render_sidebar(cx))
-                    .child(self.render_sidebar_resizer(cx))
-                    .child(self.render_main_panel(cx))
-                    .child(self.render_context_panel_resizer(cx))
-                    .child(self.render_context_panel(cx))
+                    .text_sm()
+                    .text_color(rgb(colors.text_primary))
+                    .child(t("sidebar.audit_log")),
             )
-            // Bottom bar
-            .child(self.render_bottom_bar(cx))
-            // New thread dialog (modal overlay)
-            .when(self.show_new_thread_dialog, |el| {
-                el.child(self.render_new_thread_dialog(cx))
-            })
     }
-}
 
-impl CocoWorkWindow {
-    fn render_new_thread_dialog(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+    fn toggle_audit_log(&mut self, cx: &mut ViewContext<Self>) {
+        self.show_audit = !self.show_audit;
+        if self.show_audit {
+            self.show_dashboard = false;
+            self.show_search = false;
+            self.show_task_board = false;
+            self.audit_status = None;
+            self.reload_audit_log(cx);
+        }
+        cx.notify();
+    }
+
+    /// The audit filter with the current search text
+    fn current_audit_filter(&self, cx: &ViewContext<Self>) -> AuditFilter {
+        let search = self.audit_search_input.read(cx).content().trim().to_string();
+        AuditFilter {
+            search: (!search.is_empty()).then_some(search),
+            ..self.audit_filter.clone()
+        }
+    }
+
+    fn reload_audit_log(&mut self, cx: &mut ViewContext<Self>) {
+        let filter = AuditFilter {
+            limit: Some(MAX_AUDIT_ROWS),
+            ..self.current_audit_filter(cx)
+        };
+        match self.acp.manager.audit_entries(&filter) {
+            Ok(entries) => {
+                self.audit_entries = entries;
+                self.audit_total = self.acp.manager.count_audit_entries(&filter);
+            }
+            Err(e) => {
+                self.audit_entries.clear();
+                self.audit_total = 0;
+                self.audit_status = Some(Err(e));
+            }
+        }
+        cx.notify();
+    }
+
+    fn set_audit_filter(&mut self, filter: AuditFilter, cx: &mut ViewContext<Self>) {
+        self.audit_filter = filter;
+        self.audit_status = None;
+        self.reload_audit_log(cx);
+    }
+
+    /// Save every entry matching the filter as CSV or JSON
+    fn export_audit(&mut self, format: AuditExportFormat, cx: &mut ViewContext<Self>) {
+        let entries = match self.acp.manager.audit_entries(&self.current_audit_filter(cx)) {
+            Ok(entries) => entries,
+            Err(e) => {
+                self.audit_status = Some(Err(e));
+                cx.notify();
+                return;
+            }
+        };
+        let document = export_audit_log(&entries, format);
+        let file_name = format!("cocowork-audit.{}", format.extension());
+        let filter = match format {
+            AuditExportFormat::Csv => "CSV",
+            AuditExportFormat::Json => "JSON",
+        };
+
+        cx.spawn(|view, mut cx| async move {
+            let file = rfd::AsyncFileDialog::new()
+                .set_title(t("dialog.export_audit_log"))
+                .set_file_name(file_name)
+                .add_filter(filter, &[format.extension()])
+                .save_file()
+                .await;
+
+            if let Some(file) = file {
+                let path = file.path().to_path_buf();
+                let status = std::fs::write(&path, document)
+                    .map(|_| path)
+                    .map_err(|e| t_args("audit.export_failed", &[("error", &e)]));
+                let _ = view.update(&mut cx, |this, cx| {
+                    this.audit_status = Some(status);
+                    cx.notify();
+                });
+            }
+        })
+        .detach();
+    }
+
+    fn audit_outcome_color(&self, outcome: AuditOutcome) -> ThemeRgba {
         let colors = &self.theme.colors;
-        let agents = self.acp.available_agents();
+        match outcome {
+            AuditOutcome::Allowed | AuditOutcome::Recorded => colors.text_secondary,
+            AuditOutcome::Approved => colors.success,
+            AuditOutcome::AutoApproved => colors.warning,
+            AuditOutcome::Rejected | AuditOutcome::Denied | AuditOutcome::Failed | AuditOutcome::LimitExceeded => {
+                colors.error
+            }
+        }
+    }
+
+    /// Every agent operation and how it was decided, filterable and exportable
+    fn render_audit_log(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let colors = &self.theme.colors;
+        let filter = &self.audit_filter;
+        let active_session = self.acp.active_session_id.clone();
+        let this_thread = active_session.is_some() && filter.session_id == active_session;
+
+        let chip = |id: SharedString, label: String, on: bool| {
+            div()
+                .id(id)
+                .px(px(8.0))
+                .py(px(2.0))
+                .rounded(px(4.0))
+                .text_xs()
+                .cursor_pointer()
+                .when(on, |el| {
+                    el.bg(rgba(colors.primary.with_alpha(0.2)))
+                        .text_color(rgb(colors.text_primary))
+                })
+                .when(!on, |el| {
+                    el.text_color(rgb(colors.text_secondary))
+                        .hover(|el| el.bg(rgba(colors.hover)))
+                })
+                .child(label)
+        };
+
+        let status = match &self.audit_status {
+            Some(Ok(path)) => t_args("audit.exported", &[("path", &path.display())]),
+            Some(Err(e)) => e.clone(),
+            None if self.audit_total > self.audit_entries.len() => {
+                t_args("audit.showing_latest", &[("shown", &self.audit_entries.len()), ("total", &self.audit_total)])
+            }
+            None => t_args("audit.entries", &[("count", &self.audit_total)]),
+        };
+        let status_color = match &self.audit_status {
+            Some(Ok(_)) => colors.success,
+            Some(Err(_)) => colors.error,
+            None => colors.text_secondary,
+        };
 
-        // Modal overlay
         div()
-            .absolute()
-            .inset_0()
+            .flex_1()
+            .min_h_0()
             .flex()
-            .items_center()
-            .justify_center()
-            .bg(rgba(colors.panel_bg.with_alpha(0.9)))
-            .on_mouse_down(MouseButton::Left, cx.listener(|this, _, cx| {
-                this.show_new_thread_dialog = false;
-                cx.notify();
-            }))
+            .flex_col()
+            // Search bar
             .child(
-                // Dialog box
                 div()
-                    .w(px(400.0))
-                    .max_h(px(500.0))
-                    .bg(rgb(colors.surface_elevated))
-                    .rounded(px(12.0))
-                    .border_1()
+                    .w_full()
+                    .h(px(40.0))
+                    .flex_shrink_0()
+                    .px(px(16.0))
+                    .flex()
+                    .items_center()
+                    .gap(px(6.0))
+                    .border_b_1()
                     .border_color(rgb(colors.border))
-                    .shadow_lg()
+                    .on_key_down(cx.listener(|this, event: &KeyDownEvent, cx| {
+                        if event.keystroke.key == "enter" {
+                            this.audit_status = None;
+                            this.reload_audit_log(cx);
+                        }
+                    }))
+                    .child(
+                        div()
+                            .flex_1()
+                            .px(px(8.0))
+                            .py(px(4.0))
+                            .rounded(px(6.0))
+                            .bg(rgb(colors.surface))
+                            .border_1()
+                            .border_color(rgb(colors.border_subtle))
+                            .text_sm()
+                            .child(self.audit_search_input.clone()),
+                    )
+                    .when_some(active_session, |el, session_id| {
+                        el.child(
+                            chip("audit-this-thread".into(), t("audit.this_thread").to_string(), this_thread).on_click(
+                                cx.listener(move |this, _, cx| {
+                                    let filter = AuditFilter {
+                                        session_id: (!this_thread).then(|| session_id.clone()),
+                                        ..this.audit_filter.clone()
+                                    };
+                                    this.set_audit_filter(filter, cx);
+                                }),
+                            ),
+                        )
+                    })
+                    .child(
+                        div()
+                            .id("refresh-audit-btn")
+                            .px(px(10.0))
+                            .py(px(4.0))
+                            .rounded(px(6.0))
+                            .bg(rgb(colors.primary))
+                            .text_xs()
+                            .text_color(white())
+                            .cursor_pointer()
+                            .hover(|el| el.bg(rgb(colors.primary_hover)))
+                            .on_click(cx.listener(|this, _, cx| {
+                                this.audit_status = None;
+                                this.reload_audit_log(cx);
+                            }))
+                            .child(t("common.refresh")),
+                    ),
+            )
+            // Outcome and operation filters
+            .child(
+                div()
+                    .w_full()
+                    .flex_shrink_0()
+                    .px(px(16.0))
+                    .py(px(6.0))
                     .flex()
                     .flex_col()
-                    .on_mouse_down(MouseButton::Left, |_, cx| {
-                        cx.stop_propagation();
-                    })
-                    // Header
+                    .gap(px(4.0))
+                    .border_b_1()
+                    .border_color(rgb(colors.border))
                     .child(
                         div()
-                            .px(px(20.0))
-                            .py(px(16.0))
-                            .border_b_1()
-                            .border_color(rgb(colors.border))
                             .flex()
+                            .flex_wrap()
                             .items_center()
-                            .justify_between()
+                            .gap(px(4.0))
                             .child(
-                                div()
-                                    .text_lg()
-                                    .font_weight(FontWeight::SEMIBOLD)
-                                    .text_color(rgb(colors.text_primary))
-                                    .child("New Thread"),
+                                chip("audit-outcome-all".into(), t("audit.all_outcomes").to_string(), filter.outcome.is_none())
+                                    .on_click(cx.listener(|this, _, cx| {
+                                        let filter = AuditFilter {
+                                            outcome: None,
+                                            ..this.audit_filter.clone()
+                                        };
+                                        this.set_audit_filter(filter, cx);
+                                    })),
                             )
-                            .child(
-                                div()
-                                    .text_sm()
-                                    .text_color(rgb(colors.text_secondary))
-                                    .child("Select an agent"),
-                            ),
+                            .children(AuditOutcome::ALL.into_iter().map(|outcome| {
+                                chip(
+                                    SharedString::from(format!("audit-outcome-{}", outcome.as_str())),
+                                    audit_outcome_label(outcome).to_string(),
+                                    filter.outcome == Some(outcome),
+                                )
+                                .on_click(cx.listener(move |this, _, cx| {
+                                    let filter = AuditFilter {
+                                        outcome: Some(outcome),
+                                        ..this.audit_filter.clone()
+                                    };
+                                    this.set_audit_filter(filter, cx);
+                                }))
+                            })),
                     )
-                    // Agent list
                     .child(
                         div()
-                            .id("agent-list")
-                            .flex_1()
-                            .overflow_scroll()
-                            .p(px(12.0))
                             .flex()
-                            .flex_col()
-                            .gap(px(8.0))
-                            .children(agents.iter().map(|agent| {
-                                let agent_id = agent.id.clone();
-                                let agent_name = agent.name.clone();
-                                let agent_desc = agent.description.clone().unwrap_or_default();
-                                let is_selected = self.acp.manager.selected_agent_id.as_ref() == Some(&agent_id);
-
-                                div()
-                                    .id(SharedString::from(format!("agent-{}", agent_id)))
-                                    .px(px(16.0))
-                                    .py(px(12.0))
-                                    .rounded(px(8.0))
-                                    .border_1()
-                                    .when(is_selected, |el| {
-                                        el.border_color(rgb(colors.primary))
-                                            .bg(rgba(colors.primary.with_alpha(0.1)))
-                                    })
-                                    .when(!is_selected, |el| {
-                                        el.border_color(rgb(colors.border))
-                                            .hover(|el| el.bg(rgb(colors.surface)))
-                                    })
-                                    .cursor_pointer()
-                                    .on_click(cx.listener(move |this, _, cx| {
-                                        this.create_new_thread_with_agent(&agent_id, cx);
-                                    }))
-                                    .child(
-                                        div()
-                                            .flex()
-                                            .flex_col()
-                                            .gap(px(4.0))
-                                            .child(
-                                                div()
-                                                    .flex()
-                                                    .items_center()
-                                                    .gap(px(8.0))
-                                                    .child(
-                                                        div()
-                                                            .text_base()
-                                                            .font_weight(FontWeight::MEDIUM)
-                                                            .text_color(rgb(colors.text_primary))
-                                                            .child(agent_name),
-                                                    )
-                                                    .when(is_selected, |el| {
-                                                        el.child(
-                                                            div()
-                                                                .text_xs()
-                                                                .px(px(6.0))
-                                                                .py(px(2.0))
-                                                                .rounded(px(4.0))
-                                                                .bg(rgb(colors.primary))
-                                                                .text_color(rgb(ThemeRgba::rgb(0xFFFFFF))) // White text on primary
-                                                                .child("Current"),
-                                                        )
-                                                    }),
-                                            )
-                                            .when(!agent_desc.is_empty(), |el| {
-                                                el.child(
-                                                    div()
-                                                        .text_sm()
-                                                        .text_color(rgb(colors.text_secondary))
-                                                        .child(agent_desc),
-                                                )
-                                            }),
-                                    )
+                            .flex_wrap()
+                            .items_center()
+                            .gap(px(4.0))
+                            .child(
+                                chip(
+                                    "audit-operation-all".into(),
+                                    t("audit.all_operations").to_string(),
+                                    filter.operation.is_none(),
+                                )
+                                .on_click(cx.listener(|this, _, cx| {
+                                    let filter = AuditFilter {
+                                        operation: None,
+                                        ..this.audit_filter.clone()
+                                    };
+                                    this.set_audit_filter(filter, cx);
+                                })),
+                            )
+                            .children(AUDIT_OPERATIONS.into_iter().map(|operation| {
+                                chip(
+                                    SharedString::from(format!("audit-operation-{}", operation)),
+                                    operation.to_string(),
+                                    filter.operation.as_deref() == Some(operation),
+                                )
+                                .on_click(cx.listener(move |this, _, cx| {
+                                    let filter = AuditFilter {
+                                        operation: Some(operation.to_string()),
+                                        ..this.audit_filter.clone()
+                                    };
+                                    this.set_audit_filter(filter, cx);
+                                }))
                             })),
-                    )
-                    // Footer
+                    ),
+            )
+            // Status and export
+            .child(
+                div()
+                    .w_full()
+                    .flex_shrink_0()
+                    .px(px(16.0))
+                    .py(px(6.0))
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .border_b_1()
+                    .border_color(rgb(colors.border))
                     .child(
                         div()
-                            .px(px(20.0))
-                            .py(px(12.0))
-                            .border_t_1()
-                            .border_color(rgb(colors.border))
-                            .flex()
-                            .justify_end()
-                            .child(
+                            .flex_1()
+                            .min_w_0()
+                            .text_xs()
+                            .text_ellipsis()
+                            .text_color(rgb(status_color))
+                            .child(status),
+                    )
+                    .children(
+                        [("export-audit-csv", t("audit.export_csv"), AuditExportFormat::Csv), ("export-audit-json", t("audit.export_json"), AuditExportFormat::Json)]
+                            .into_iter()
+                            .map(|(id, label, format)| {
                                 div()
-                                    .id("cancel-btn")
-                                    .px(px(16.0))
-                                    .py(px(8.0))
+                                    .id(id)
+                                    .px(px(10.0))
+                                    .py(px(4.0))
                                     .rounded(px(6.0))
                                     .bg(rgb(colors.surface))
-                                    .text_sm()
-                                    .text_color(rgb(colors.text_secondary))
+                                    .text_xs()
+                                    .text_color(rgb(colors.text_primary))
                                     .cursor_pointer()
                                     .hover(|el| el.bg(rgb(colors.border)))
-                                    .on_click(cx.listener(|this, _, cx| {
-                                        this.show_new_thread_dialog = false;
-                                        cx.notify();
+                                    .on_click(cx.listener(move |this, _, cx| {
+                                        this.export_audit(format, cx);
                                     }))
-                                    .child("Cancel"),
-                            ),
+                                    .child(label)
+                            }),
                     ),
             )
+            // Entries, newest first
+            .child(
+                div()
+                    .id("audit-entries")
+                    .flex_1()
+                    .min_h_0()
+                    .overflow_y_scroll()
+                    .py(px(4.0))
+                    .when(self.audit_entries.is_empty(), |el| {
+                        el.child(
+                            div()
+                                .px(px(16.0))
+                                .py(px(12.0))
+                                .text_sm()
+                                .text_color(rgb(colors.text_secondary))
+                                .child(t("audit.none")),
+                        )
+                    })
+                    .children(self.audit_entries.iter().map(|entry| {
+                        let outcome_color = self.audit_outcome_color(entry.outcome);
+                        div()
+                            .px(px(16.0))
+                            .py(px(4.0))
+                            .flex()
+                            .items_center()
+                            .gap(px(10.0))
+                            .hover(|s| s.bg(rgba(colors.hover)))
+                            .child(
+                                div()
+                                    .w(px(110.0))
+                                    .flex_shrink_0()
+                                    .text_xs()
+                                    .text_color(rgb(colors.text_secondary))
+                                    .child(
+                                        entry
+                                            .created_at
+                                            .with_timezone(&chrono::Local)
+                                            .format("%b %-d %H:%M:%S")
+                                            .to_string(),
+                                    ),
+                            )
+                            .child(
+                                div()
+                                    .w(px(70.0))
+                                    .flex_shrink_0()
+                                    .text_xs()
+                                    .font_family("monospace")
+                                    .text_color(rgb(colors.text_primary))
+                                    .child(entry.operation.clone()),
+                            )
+                            .child(
+                                div()
+                                    .flex_1()
+                                    .min_w_0()
+                                    .flex()
+                                    .flex_col()
+                                    .child(
+                                        div()
+                                            .text_xs()
+                                            .font_family("monospace")
+                                            .text_color(rgb(colors.text_primary))
+                                            .whitespace_nowrap()
+                                            .overflow_hidden()
+                                            .text_ellipsis()
+                                            .child(entry.target.clone()),
+                                    )
+                                    .when_some(entry.detail.clone(), |el, detail| {
+                                        el.child(
+                                            div()
+                                                .text_xs()
+                                                .text_color(rgb(colors.text_secondary))
+                                                .whitespace_nowrap()
+                                                .overflow_hidden()
+                                                .text_ellipsis()
+                                                .child(detail),
+                                        )
+                                    }),
+                            )
+                            .child(
+                                div()
+                                    .flex_shrink_0()
+                                    .px(px(6.0))
+                                    .py(px(1.0))
+                                    .rounded(px(4.0))
+                                    .bg(rgba(outcome_color.with_alpha(0.15)))
+                                    .text_xs()
+                                    .text_color(rgb(outcome_color))
+                                    .child(audit_outcome_label(entry.outcome)),
+                            )
+                    })),
+            )
+    }
+}
+
+/// The file a tool call reads or writes, if it names one
+/// `bytes` as B, KB or MB
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        1024..=1_048_575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
     }
 }
 
+fn tool_call_path(tool_call: &ToolCallState) -> Option<String> {
+    let from_diff = tool_call.content.iter().find_map(|content| match content {
+        ToolCallContent::Diff { diff } => Some(diff.path.clone()),
+        _ => None,
+    });
+    from_diff.or_else(|| {
+        let input = tool_call.input.as_ref()?;
+        ["path", "file_path", "abs_path"]
+            .iter()
+            .find_map(|key| input.get(key)?.as_str().map(str::to_string))
+    })
+}
+
 // ============================================================================
 // Color Helpers
 // ============================================================================
 
-fn rgb(c: cocowork_ui::Rgba) -> Rgba {
+pub(super) fn rgb(c: cocowork_ui::Rgba) -> Rgba {
     Rgba {
         r: c.r,
         g: c.g,
@@ -1,5 +1,7 @@
 //! Main window implementation
 
 mod cocowork_window;
+mod quick_prompt;
 
-pub use cocowork_window::CocoWorkWindow;
+pub use cocowork_window::{initial_window_bounds, register_bindings, CocoWorkWindow};
+pub use quick_prompt::register_quick_prompt;
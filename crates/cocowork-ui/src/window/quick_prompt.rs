@@ -0,0 +1,197 @@
+//! Quick prompt popover
+//!
+//! A small floating window with one input that starts a new thread with the
+//! selected agent in the current workspace, without bringing the main window
+//! forward. It's opened from the app menu or with Cmd+Shift+Space. gpui has
+//! no tray icon or system-wide shortcut, so both only reach it while CocoWork
+//! is running and, for the shortcut, active.
+//!
+//! With clipboard capture turned on in the settings, Cmd+Alt+Space opens it
+//! with what's on the clipboard, e.g. a stack trace copied from a terminal,
+//! already quoted as a code block.
+
+use super::cocowork_window::{rgb, CocoWorkWindow, REM_SIZE};
+use cocowork_ui::{
+    components::{TextInput, TextInputEvent},
+    i18n::t,
+    theme::scaled,
+    tool_render::quote_block,
+    Theme,
+};
+use gpui::*;
+
+actions!(quick_prompt, [ShowQuickPrompt, CaptureClipboard, DismissQuickPrompt]);
+
+/// Size of the popover at 100%
+const QUICK_PROMPT_WIDTH: f32 = 560.0;
+const QUICK_PROMPT_HEIGHT: f32 = 132.0;
+/// Height with captured clipboard text, so a few lines of it show
+const CAPTURE_HEIGHT: f32 = 320.0;
+
+/// Let the app menu and Cmd+Shift+Space open a quick prompt that sends through `main`
+pub fn register_quick_prompt(main: WindowHandle<CocoWorkWindow>, cx: &mut AppContext) {
+    cx.bind_keys([
+        KeyBinding::new("cmd-shift-space", ShowQuickPrompt, None),
+        KeyBinding::new("cmd-alt-space", CaptureClipboard, None),
+        KeyBinding::new("escape", DismissQuickPrompt, Some("QuickPrompt")),
+    ]);
+    cx.set_menus(vec![Menu {
+        name: "CocoWork".into(),
+        items: vec![MenuItem::action(t("quick_prompt.menu"), ShowQuickPrompt)],
+    }]);
+    cx.on_action(move |_: &ShowQuickPrompt, cx| show_quick_prompt(main, None, cx));
+    cx.on_action(move |_: &CaptureClipboard, cx| capture_clipboard(main, cx));
+}
+
+/// Open the quick prompt with the clipboard's text as a code block, if
+/// clipboard capture is turned on
+fn capture_clipboard(main: WindowHandle<CocoWorkWindow>, cx: &mut AppContext) {
+    if !main.read(cx).is_ok_and(|window| window.clipboard_capture_enabled()) {
+        return;
+    }
+    let text = cx.read_from_clipboard().and_then(|item| item.text());
+    let Some(text) = text.filter(|text| !text.trim().is_empty()) else {
+        return;
+    };
+    show_quick_prompt(main, Some(format!("{}\n\n", quote_block(&text, None))), cx);
+}
+
+/// Bring up the quick prompt, reusing it if it's already open, with `text`
+/// in it when given
+fn show_quick_prompt(main: WindowHandle<CocoWorkWindow>, text: Option<String>, cx: &mut AppContext) {
+    let open = cx.windows().into_iter().find_map(|window| window.downcast::<QuickPrompt>());
+    if let Some(window) = open {
+        let _ = window.update(cx, |prompt, cx| {
+            cx.activate_window();
+            if let Some(text) = text {
+                prompt.input.update(cx, |input, cx| input.set_content(text, cx));
+            }
+            prompt.focus_input(cx);
+        });
+        return;
+    }
+
+    let Ok(theme) = main.read(cx).map(|window| window.theme().clone()) else {
+        return;
+    };
+    let height = if text.is_some() { CAPTURE_HEIGHT } else { QUICK_PROMPT_HEIGHT };
+    let size = size(px(scaled(QUICK_PROMPT_WIDTH)), px(scaled(height)));
+    let options = WindowOptions {
+        titlebar: None,
+        window_bounds: Some(WindowBounds::Windowed(Bounds::centered(None, size, cx))),
+        focus: true,
+        show: true,
+        kind: WindowKind::PopUp,
+        is_movable: false,
+        window_background: WindowBackgroundAppearance::Opaque,
+        ..Default::default()
+    };
+    if let Err(e) = cx.open_window(options, |cx| cx.new_view(|cx| QuickPrompt::new(main, theme, text, cx))) {
+        tracing::warn!("Failed to open quick prompt: {}", e);
+    }
+}
+
+/// The popover's view
+pub struct QuickPrompt {
+    /// The main window, which owns the agent connection; updated through its
+    /// handle so it redraws in its own window
+    main: WindowHandle<CocoWorkWindow>,
+    theme: Theme,
+    input: View<TextInput>,
+    /// Where the prompt goes, like "New thread with Claude Code in cocowork"
+    target: String,
+}
+
+impl QuickPrompt {
+    fn new(main: WindowHandle<CocoWorkWindow>, theme: Theme, text: Option<String>, cx: &mut ViewContext<Self>) -> Self {
+        let input = cx.new_view(|cx| {
+            let mut input = TextInput::new(cx);
+            input.set_multi_line(true);
+            input.set_placeholder(t("quick_prompt.placeholder"));
+            if let Some(text) = text {
+                input.set_content(text, cx);
+            }
+            input
+        });
+        cx.subscribe(&input, |this, _, event: &TextInputEvent, cx| match event {
+            TextInputEvent::Submit => this.send(cx),
+        })
+        .detach();
+        cx.observe(&input, |_, _, cx| cx.notify()).detach();
+
+        // Like a menu, the popover goes away once something else is clicked
+        cx.observe_window_activation(|_, cx| {
+            if !cx.is_window_active() {
+                cx.remove_window();
+            }
+        })
+        .detach();
+
+        let target = main.read(cx).map(|window| window.quick_prompt_target()).unwrap_or_default();
+        let this = Self { main, theme, input, target };
+        this.focus_input(cx);
+        this
+    }
+
+    fn focus_input(&self, cx: &mut ViewContext<Self>) {
+        let handle = self.input.read(cx).focus_handle(cx);
+        cx.focus(&handle);
+    }
+
+    fn send(&mut self, cx: &mut ViewContext<Self>) {
+        let text = self.input.read(cx).content().trim().to_string();
+        if text.is_empty() {
+            return;
+        }
+        let sent = self.main.update(cx, |window, cx| window.send_quick_prompt(text, cx));
+        if sent.is_ok() {
+            cx.remove_window();
+        }
+    }
+
+    fn dismiss(&mut self, _: &DismissQuickPrompt, cx: &mut ViewContext<Self>) {
+        cx.remove_window();
+    }
+}
+
+impl Render for QuickPrompt {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        cx.set_rem_size(px(scaled(REM_SIZE * self.theme.density.text_factor())));
+        let colors = &self.theme.colors;
+
+        div()
+            .key_context("QuickPrompt")
+            .on_action(cx.listener(Self::dismiss))
+            .size_full()
+            .flex()
+            .flex_col()
+            .gap(px(scaled(8.0)))
+            .p(px(scaled(12.0)))
+            .bg(rgb(colors.surface_elevated))
+            .border_1()
+            .border_color(rgb(colors.border))
+            .text_color(rgb(colors.text_primary))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(colors.text_secondary))
+                    .child(self.target.clone()),
+            )
+            .child(
+                div()
+                    .flex_1()
+                    .px(px(scaled(8.0)))
+                    .py(px(scaled(6.0)))
+                    .rounded(px(scaled(6.0)))
+                    .bg(rgb(colors.input_bg))
+                    .text_sm()
+                    .child(self.input.clone()),
+            )
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(colors.text_disabled))
+                    .child(t("quick_prompt.hint")),
+            )
+    }
+}